@@ -1,6 +1,6 @@
 use crate::auth::{get_token_from_req, AuthImpl, UserId};
 use actix_web::{dev::ServiceRequest, Error as ActixError};
-use reqwest::StatusCode;
+use reqwest::{header::ACCEPT, StatusCode};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
@@ -16,6 +16,45 @@ struct TokenRequestBody {
     access_token: String,
 }
 
+impl GitHubAuth {
+    /// Whether the user of the token can push to the GitHub repository at the URL
+    pub async fn can_push(&self, token: &str, url: &url::Url) -> Result<bool, reqwest::Error> {
+        if url.host_str() != Some("github.com") {
+            return Ok(false);
+        }
+
+        let Some((owner, repo)) = url.path_segments().and_then(|mut segments| {
+            let owner = segments.next().filter(|owner| !owner.is_empty())?;
+            let repo = segments.next().filter(|repo| !repo.is_empty())?;
+
+            Some((owner, repo.trim_end_matches(".git")))
+        }) else {
+            return Ok(false);
+        };
+
+        let response = self
+            .reqwest_client
+            .get(format!("https://api.github.com/repos/{owner}/{repo}"))
+            .bearer_auth(token)
+            .header(ACCEPT, "application/vnd.github+json")
+            .send()
+            .await?;
+
+        // private repositories the user can't see are reported as missing
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        let permissions = response
+            .error_for_status()?
+            .json::<RepositoryResponse>()
+            .await?
+            .permissions;
+
+        Ok(permissions.is_some_and(|permissions| permissions.push || permissions.admin))
+    }
+}
+
 impl AuthImpl for GitHubAuth {
     async fn for_write_request(&self, req: &ServiceRequest) -> Result<Option<UserId>, ActixError> {
         let token = match get_token_from_req(req) {
@@ -79,3 +118,17 @@ struct User {
 struct UserResponse {
     user: User,
 }
+
+#[derive(Debug, Deserialize)]
+struct Permissions {
+    #[serde(default)]
+    admin: bool,
+    #[serde(default)]
+    push: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryResponse {
+    #[serde(default)]
+    permissions: Option<Permissions>,
+}
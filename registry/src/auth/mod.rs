@@ -3,7 +3,7 @@ mod none;
 mod rw_token;
 mod token;
 
-use crate::{benv, make_reqwest, AppState};
+use crate::{make_reqwest, tenant::Tenant, AppState};
 use actix_governor::{KeyExtractor, SimpleKeyExtractionError};
 use actix_web::{
     body::MessageBody,
@@ -13,6 +13,7 @@ use actix_web::{
     middleware::Next,
     web, HttpMessage, HttpResponse,
 };
+use log::warn;
 use pesde::source::pesde::IndexConfig;
 use sha2::{Digest, Sha256};
 use std::fmt::Display;
@@ -56,7 +57,7 @@ pub trait AuthImpl: Display {
     }
 
     fn read_needs_auth(&self) -> bool {
-        benv!("READ_NEEDS_AUTH").is_ok()
+        false
     }
 }
 
@@ -124,7 +125,7 @@ pub async fn read_mw(
     req: ServiceRequest,
     next: Next<impl MessageBody + 'static>,
 ) -> Result<ServiceResponse<impl MessageBody>, ActixError> {
-    if app_state.auth.read_needs_auth() {
+    if app_state.read_needs_auth {
         let user_id = match app_state.auth.for_read_request(&req).await? {
             Some(user_id) => user_id,
             None => {
@@ -142,12 +143,12 @@ pub async fn read_mw(
     next.call(req).await.map(|res| res.map_into_left_body())
 }
 
-pub fn get_auth_from_env(config: IndexConfig) -> Auth {
-    if let Ok(token) = benv!("ACCESS_TOKEN") {
+pub fn get_auth_from_env(tenant: &Tenant, config: IndexConfig) -> Auth {
+    if let Ok(token) = tenant.var("ACCESS_TOKEN") {
         Auth::Token(token::TokenAuth {
             token: *Sha256::digest(token.as_bytes()).as_ref(),
         })
-    } else if let Ok(client_secret) = benv!("GITHUB_CLIENT_SECRET") {
+    } else if let Ok(client_secret) = tenant.var("GITHUB_CLIENT_SECRET") {
         Auth::GitHub(github::GitHubAuth {
             reqwest_client: make_reqwest(),
             client_id: config
@@ -155,8 +156,9 @@ pub fn get_auth_from_env(config: IndexConfig) -> Auth {
                 .expect("index isn't configured for GitHub"),
             client_secret,
         })
-    } else if let Ok((r, w)) =
-        benv!("READ_ACCESS_TOKEN").and_then(|r| benv!("WRITE_ACCESS_TOKEN").map(|w| (r, w)))
+    } else if let Ok((r, w)) = tenant
+        .var("READ_ACCESS_TOKEN")
+        .and_then(|r| tenant.var("WRITE_ACCESS_TOKEN").map(|w| (r, w)))
     {
         Auth::RwToken(rw_token::RwTokenAuth {
             read_token: *Sha256::digest(r.as_bytes()).as_ref(),
@@ -167,15 +169,54 @@ pub fn get_auth_from_env(config: IndexConfig) -> Auth {
     }
 }
 
-pub fn get_token_from_req(req: &ServiceRequest) -> Option<String> {
-    let token = match req
+/// How the `repository` of published packages is verified to belong to the publisher
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepositoryVerification {
+    /// Repositories aren't verified
+    Disabled,
+    /// Versions whose repository could be verified get a badge
+    Badge,
+    /// Versions whose repository can't be verified are rejected
+    Required,
+}
+
+impl Display for RepositoryVerification {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepositoryVerification::Disabled => write!(f, "disabled"),
+            RepositoryVerification::Badge => write!(f, "badge"),
+            RepositoryVerification::Required => write!(f, "required"),
+        }
+    }
+}
+
+pub fn get_repository_verification_from_env(
+    tenant: &Tenant,
+    auth: &Auth,
+) -> RepositoryVerification {
+    let verification = match tenant.var("REPOSITORY_VERIFICATION").as_deref() {
+        Ok("badge") => RepositoryVerification::Badge,
+        Ok("required") => RepositoryVerification::Required,
+        Ok(_) => panic!(
+            "Environment variable `REPOSITORY_VERIFICATION` must be either `badge` or `required`"
+        ),
+        Err(_) => RepositoryVerification::Disabled,
+    };
+
+    // only GitHub tokens can be used to check the publisher's access to the repository
+    if verification != RepositoryVerification::Disabled && !matches!(auth, Auth::GitHub(_)) {
+        warn!("repository verification requires GitHub authentication, disabling it");
+        return RepositoryVerification::Disabled;
+    }
+
+    verification
+}
+
+pub fn get_token_from_req<R: HttpMessage>(req: &R) -> Option<String> {
+    let token = req
         .headers()
         .get(AUTHORIZATION)
-        .and_then(|token| token.to_str().ok())
-    {
-        Some(token) => token,
-        None => return None,
-    };
+        .and_then(|token| token.to_str().ok())?;
 
     let token = if token.to_lowercase().starts_with("bearer ") {
         token[7..].to_string()
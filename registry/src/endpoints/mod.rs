@@ -1,4 +1,8 @@
+pub mod package_metrics;
 pub mod package_version;
 pub mod package_versions;
 pub mod publish_version;
+pub mod scope_info;
 pub mod search;
+pub mod user;
+pub mod yank_version;
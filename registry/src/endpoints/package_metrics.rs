@@ -0,0 +1,26 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use pesde::{names::PackageName, source::git_index::GitBasedSource};
+
+use crate::{error::Error, AppState};
+
+pub async fn get_package_metrics(
+    app_state: web::Data<AppState>,
+    path: web::Path<PackageName>,
+) -> Result<impl Responder, Error> {
+    let name = path.into_inner();
+
+    let (scope, name_part) = name.as_str();
+
+    {
+        let source = app_state.source.lock().unwrap();
+        if source
+            .read_file([scope, name_part], &app_state.project, None)?
+            .is_none()
+        {
+            return Ok(HttpResponse::NotFound().finish());
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(app_state.metrics.package(&name)))
+}
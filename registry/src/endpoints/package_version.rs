@@ -145,10 +145,15 @@ pub async fn get_package_version(
         return if readme {
             app_state.storage.get_readme(&name, v_id).await
         } else {
-            app_state.storage.get_package(&name, v_id).await
+            let response = app_state.storage.get_package(&name, v_id).await?;
+            app_state.metrics.record_download(&name, v_id);
+
+            Ok(response)
         };
     }
 
+    app_state.metrics.record_resolution(&name);
+
     let response = PackageResponse {
         name: name.to_string(),
         version: v_id.version().to_string(),
@@ -158,11 +163,15 @@ pub async fn get_package_version(
         license: entry.license.clone().unwrap_or_default(),
         authors: entry.authors.clone(),
         repository: entry.repository.clone().map(|url| url.to_string()),
+        repository_verified: entry.repository_verified,
+        keywords: entry.keywords.clone(),
+        categories: entry.categories.clone(),
     };
 
     let mut value = serde_json::to_value(response)?;
     value["docs"] = serde_json::to_value(entry.docs.clone())?;
     value["dependencies"] = serde_json::to_value(entry.dependencies.clone())?;
+    value["yanked"] = serde_json::to_value(entry.yanked)?;
 
     Ok(HttpResponse::Ok().json(value))
 }
@@ -24,6 +24,9 @@ pub async fn get_package_versions(
             None => return Ok(HttpResponse::NotFound().finish()),
         };
 
+    drop(source);
+    app_state.metrics.record_resolution(&name);
+
     let mut responses = BTreeMap::new();
 
     for (v_id, entry) in versions {
@@ -38,6 +41,9 @@ pub async fn get_package_versions(
                 license: entry.license.unwrap_or_default(),
                 authors: entry.authors.clone(),
                 repository: entry.repository.clone().map(|url| url.to_string()),
+                repository_verified: entry.repository_verified,
+                keywords: entry.keywords.clone(),
+                categories: entry.categories.clone(),
             });
 
         info.targets.insert(entry.target.into());
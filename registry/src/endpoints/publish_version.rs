@@ -1,9 +1,9 @@
 use actix_multipart::Multipart;
-use actix_web::{web, HttpResponse, Responder};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
 use convert_case::{Case, Casing};
 use flate2::read::GzDecoder;
 use futures::{future::join_all, join, StreamExt};
-use git2::{Remote, Repository, Signature};
+use relative_path::RelativePath;
 use serde::Deserialize;
 use sha2::{Digest, Sha256};
 use std::{
@@ -14,18 +14,22 @@ use std::{
 use tar::Archive;
 
 use crate::{
-    auth::UserId,
-    benv,
+    auth::{get_token_from_req, Auth, RepositoryVerification, UserId},
     error::{Error, ErrorResponse},
-    search::update_version,
+    git::commit_scope_files,
+    search::update_package,
     storage::StorageImpl,
     AppState,
 };
 use pesde::{
-    manifest::Manifest,
+    manifest::{target::TargetKind, DependencyType, Manifest},
+    publish::FileSelector,
     source::{
         git_index::GitBasedSource,
-        pesde::{DocEntry, DocEntryKind, IndexFile, IndexFileEntry, ScopeInfo, SCOPE_INFO_FILE},
+        pesde::{
+            signed_message, DocEntry, DocEntryKind, IndexFile, IndexFileEntry, ScopeInfo,
+            SCOPE_INFO_FILE,
+        },
         specifiers::DependencySpecifiers,
         version_id::VersionId,
         IGNORED_DIRS, IGNORED_FILES,
@@ -33,30 +37,32 @@ use pesde::{
     MANIFEST_FILE_NAME,
 };
 
-fn signature<'a>() -> Signature<'a> {
-    Signature::now(
-        &benv!(required "COMMITTER_GIT_NAME"),
-        &benv!(required "COMMITTER_GIT_EMAIL"),
-    )
-    .unwrap()
-}
+const ADDITIONAL_FORBIDDEN_FILES: &[&str] = &["default.project.json"];
 
-fn get_refspec(repo: &Repository, remote: &mut Remote) -> Result<String, git2::Error> {
-    let upstream_branch_buf = repo.branch_upstream_name(repo.head()?.name().unwrap())?;
-    let upstream_branch = upstream_branch_buf.as_str().unwrap();
+const MAX_TAGS: usize = 5;
+const MAX_TAG_LENGTH: usize = 24;
 
-    let refspec_buf = remote
-        .refspecs()
-        .find(|r| r.direction() == git2::Direction::Fetch && r.dst_matches(upstream_branch))
-        .unwrap()
-        .rtransform(upstream_branch)?;
-    let refspec = refspec_buf.as_str().unwrap();
+/// Returns why the keywords or categories of a package are invalid, if they are
+fn invalid_tags(kind: &str, tags: &BTreeSet<String>) -> Option<String> {
+    if tags.len() > MAX_TAGS {
+        return Some(format!("a package can have at most {MAX_TAGS} {kind}"));
+    }
 
-    Ok(refspec.to_string())
+    tags.iter()
+        .find(|tag| {
+            tag.is_empty()
+                || tag.len() > MAX_TAG_LENGTH
+                || !tag
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        })
+        .map(|tag| {
+            format!(
+                "invalid {kind} `{tag}`, they must be 1 to {MAX_TAG_LENGTH} lowercase letters, digits, or dashes"
+            )
+        })
 }
 
-const ADDITIONAL_FORBIDDEN_FILES: &[&str] = &["default.project.json"];
-
 #[derive(Debug, Deserialize, Default)]
 struct DocEntryInfo {
     #[serde(default)]
@@ -67,34 +73,36 @@ struct DocEntryInfo {
     collapsed: bool,
 }
 
-pub async fn publish_package(
-    app_state: web::Data<AppState>,
-    mut body: Multipart,
-    user_id: web::ReqData<UserId>,
-) -> Result<impl Responder, Error> {
-    let max_archive_size = {
-        let source = app_state.source.lock().unwrap();
-        source.refresh(&app_state.project).map_err(Box::new)?;
-        source.config(&app_state.project)?.max_archive_size
-    };
-
-    let bytes = body
-        .next()
-        .await
-        .ok_or(Error::InvalidArchive)?
-        .map_err(|_| Error::InvalidArchive)?
-        .bytes(max_archive_size)
-        .await
-        .map_err(|_| Error::InvalidArchive)?
-        .map_err(|_| Error::InvalidArchive)?;
+/// An archive of the request, unpacked
+struct Package {
+    bytes: Vec<u8>,
+    manifest: Manifest,
+    readme: Option<Vec<u8>>,
+    docs: BTreeSet<DocEntry>,
+    docs_pages: HashMap<String, Vec<u8>>,
+}
 
+/// Unpacks an archive, reading its manifest, README and docs. The inner error is why the archive
+/// was rejected
+fn unpack(bytes: Vec<u8>) -> Result<Result<Package, String>, Error> {
     let package_dir = tempfile::tempdir()?;
 
     {
         let mut decoder = GzDecoder::new(Cursor::new(&bytes));
         let mut archive = Archive::new(&mut decoder);
+        let default_excludes = FileSelector::new(&BTreeSet::new(), &BTreeSet::new())
+            .map_err(|_| Error::InvalidArchive)?;
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = entry.path()?.to_string_lossy().replace('\\', "/");
 
-        archive.unpack(package_dir.path())?;
+            if default_excludes.is_default_excluded(RelativePath::new(&path)) {
+                return Err(Error::InvalidArchive);
+            }
+
+            entry.unpack_in(package_dir.path())?;
+        }
     }
 
     let mut manifest = None::<Manifest>;
@@ -268,116 +276,62 @@ pub async fn publish_package(
         }
     }
 
-    let Some(manifest) = manifest else {
+    let Some(mut manifest) = manifest else {
         return Err(Error::InvalidArchive);
     };
 
-    {
-        let source = app_state.source.lock().unwrap();
-        source.refresh(&app_state.project).map_err(Box::new)?;
-        let config = source.config(&app_state.project)?;
-
-        let dependencies = manifest
-            .all_dependencies()
-            .map_err(|_| Error::InvalidArchive)?;
-
-        for (specifier, _) in dependencies.values() {
-            match specifier {
-                DependencySpecifiers::Pesde(specifier) => {
-                    if specifier
-                        .index
-                        .as_deref()
-                        .filter(|index| match gix::Url::try_from(*index) {
-                            Ok(_) if config.other_registries_allowed => true,
-                            Ok(url) => url == *source.repo_url(),
-                            Err(_) => false,
-                        })
-                        .is_none()
-                    {
-                        return Err(Error::InvalidArchive);
-                    }
-                }
-                DependencySpecifiers::Wally(specifier) => {
-                    if !config.wally_allowed {
-                        return Err(Error::InvalidArchive);
-                    }
-
-                    if specifier
-                        .index
-                        .as_ref()
-                        .filter(|index| index.parse::<url::Url>().is_ok())
-                        .is_none()
-                    {
-                        return Err(Error::InvalidArchive);
-                    }
-                }
-                DependencySpecifiers::Git(_) => {
-                    if !config.git_allowed {
-                        return Err(Error::InvalidArchive);
-                    }
-                }
-                DependencySpecifiers::Workspace(_) => {
-                    // workspace specifiers are to be transformed into Pesde specifiers by the sender
-                    return Err(Error::InvalidArchive);
-                }
-            }
-        }
+    if let Err(e) = manifest.target.validate_exports(package_dir.path()) {
+        return Ok(Err(e.to_string()));
+    }
 
-        let repo = source.repo_git2(&app_state.project)?;
+    Ok(Ok(Package {
+        bytes,
+        manifest,
+        readme,
+        docs,
+        docs_pages,
+    }))
+}
 
-        let (scope, name) = manifest.name.as_str();
-        let mut oids = vec![];
+/// Whether the user owns the scope, or `None` if the scope has no owners yet
+fn owns_scope(app_state: &AppState, scope: &str, user_id: &UserId) -> Result<Option<bool>, Error> {
+    let source = app_state.source.lock().unwrap();
 
+    Ok(
         match source.read_file([scope, SCOPE_INFO_FILE], &app_state.project, None)? {
-            Some(info) => {
-                let info: ScopeInfo = toml::de::from_str(&info)?;
-                if !info.owners.contains(&user_id.0) {
-                    return Ok(HttpResponse::Forbidden().finish());
-                }
-            }
-            None => {
-                let scope_info = toml::to_string(&ScopeInfo {
-                    owners: BTreeSet::from([user_id.0]),
-                })?;
-
-                let mut blob_writer = repo.blob_writer(None)?;
-                blob_writer.write_all(scope_info.as_bytes())?;
-                oids.push((SCOPE_INFO_FILE, blob_writer.commit()?));
-            }
-        };
-
-        let mut entries: IndexFile = toml::de::from_str(
-            &source
-                .read_file([scope, name], &app_state.project, None)?
-                .unwrap_or_default(),
-        )?;
-
-        let new_entry = IndexFileEntry {
-            target: manifest.target.clone(),
-            published_at: chrono::Utc::now(),
-            description: manifest.description.clone(),
-            license: manifest.license.clone(),
-            authors: manifest.authors.clone(),
-            repository: manifest.repository.clone(),
-            docs,
-
-            dependencies,
-        };
-
-        let this_version = entries
-            .keys()
-            .find(|v_id| *v_id.version() == manifest.version);
-        if let Some(this_version) = this_version {
-            let other_entry = entries.get(this_version).unwrap();
+            Some(info) => Some(
+                toml::de::from_str::<ScopeInfo>(&info)?
+                    .owners
+                    .contains(&user_id.0),
+            ),
+            None => None,
+        },
+    )
+}
 
+/// Adds the entries to the index file of their package, failing with the response to send if
+/// any of them can't be added
+fn add_entries(
+    entries: &mut IndexFile,
+    new_entries: &[(VersionId, IndexFileEntry)],
+) -> Result<(), HttpResponse> {
+    for (version_id, new_entry) in new_entries {
+        let other_entry = entries
+            .iter()
+            .find(|(v_id, _)| v_id.version() == version_id.version())
+            .map(|(_, entry)| entry);
+
+        if let Some(other_entry) = other_entry {
             // description cannot be different - which one to render in the "Recently published" list?
             // the others cannot be different because what to return from the versions endpoint?
             if other_entry.description != new_entry.description
                 || other_entry.license != new_entry.license
                 || other_entry.authors != new_entry.authors
                 || other_entry.repository != new_entry.repository
+                || other_entry.keywords != new_entry.keywords
+                || other_entry.categories != new_entry.categories
             {
-                return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                return Err(HttpResponse::BadRequest().json(ErrorResponse {
                     error: "same version with different description or license already exists"
                         .to_string(),
                 }));
@@ -385,99 +339,362 @@ pub async fn publish_package(
         }
 
         if entries
-            .insert(
-                VersionId::new(manifest.version.clone(), manifest.target.kind()),
-                new_entry.clone(),
-            )
+            .insert(version_id.clone(), new_entry.clone())
             .is_some()
         {
-            return Ok(HttpResponse::Conflict().finish());
+            return Err(HttpResponse::Conflict().finish());
         }
+    }
+
+    Ok(())
+}
 
-        let mut remote = repo.find_remote("origin")?;
-        let refspec = get_refspec(&repo, &mut remote)?;
+pub async fn publish_package(
+    app_state: web::Data<AppState>,
+    mut body: Multipart,
+    user_id: web::ReqData<UserId>,
+    req: HttpRequest,
+) -> Result<impl Responder, Error> {
+    let max_archive_size = {
+        let source = app_state.source.lock().unwrap();
+        source.refresh(&app_state.project).map_err(Box::new)?;
+        source.config(&app_state.project)?.max_archive_size
+    };
 
-        let reference = repo.find_reference(&refspec)?;
+    // every field is the archive of one target of the version
+    let mut packages = vec![];
 
-        {
-            let index_content = toml::to_string(&entries)?;
-            let mut blob_writer = repo.blob_writer(None)?;
-            blob_writer.write_all(index_content.as_bytes())?;
-            oids.push((name, blob_writer.commit()?));
+    while let Some(field) = body.next().await {
+        if packages.len() == TargetKind::VARIANTS.len() {
+            return Err(Error::InvalidArchive);
         }
 
-        let old_root_tree = reference.peel_to_tree()?;
-        let old_scope_tree = match old_root_tree.get_name(scope) {
-            Some(entry) => Some(repo.find_tree(entry.id())?),
-            None => None,
-        };
+        let bytes = field
+            .map_err(|_| Error::InvalidArchive)?
+            .bytes(max_archive_size)
+            .await
+            .map_err(|_| Error::InvalidArchive)?
+            .map_err(|_| Error::InvalidArchive)?;
 
-        let mut scope_tree = repo.treebuilder(old_scope_tree.as_ref())?;
-        for (file, oid) in oids {
-            scope_tree.insert(file, oid, 0o100644)?;
+        match unpack(bytes.to_vec())? {
+            Ok(package) => packages.push(package),
+            Err(error) => return Ok(HttpResponse::BadRequest().json(ErrorResponse { error })),
         }
+    }
 
-        let scope_tree_id = scope_tree.write()?;
-        let mut root_tree = repo.treebuilder(Some(&repo.find_tree(old_root_tree.id())?))?;
-        root_tree.insert(scope, scope_tree_id, 0o040000)?;
+    let Some(manifest) = packages.first().map(|package| package.manifest.clone()) else {
+        return Err(Error::InvalidArchive);
+    };
 
-        let tree_oid = root_tree.write()?;
+    let mut target_kinds = BTreeSet::new();
+    for package in &packages {
+        if package.manifest.name != manifest.name || package.manifest.version != manifest.version {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: "all archives must be of the same version of the same package".to_string(),
+            }));
+        }
 
-        repo.commit(
-            Some("HEAD"),
-            &signature(),
-            &signature(),
-            &format!(
-                "add {}@{} {}",
-                manifest.name, manifest.version, manifest.target
-            ),
-            &repo.find_tree(tree_oid)?,
-            &[&reference.peel_to_commit()?],
-        )?;
+        if !target_kinds.insert(package.manifest.target.kind()) {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                error: format!(
+                    "more than one archive targets {}",
+                    package.manifest.target.kind()
+                ),
+            }));
+        }
 
-        let mut push_options = git2::PushOptions::new();
-        let mut remote_callbacks = git2::RemoteCallbacks::new();
+        if let Some(error) = invalid_tags("keywords", &package.manifest.keywords)
+            .or_else(|| invalid_tags("categories", &package.manifest.categories))
+        {
+            return Ok(HttpResponse::BadRequest().json(ErrorResponse { error }));
+        }
+    }
+
+    let targets = packages
+        .iter()
+        .map(|package| package.manifest.target.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let repository_verified = match (
+        app_state.repository_verification,
+        &app_state.auth,
+        &manifest.repository,
+        get_token_from_req(&req),
+    ) {
+        (RepositoryVerification::Disabled, ..) => false,
+        (_, Auth::GitHub(github), Some(repository), Some(token)) => github
+            .can_push(&token, repository)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("failed to verify repository {repository}: {e}");
+                false
+            }),
+        _ => false,
+    };
 
-        let git_creds = app_state.project.auth_config().git_credentials().unwrap();
-        remote_callbacks.credentials(|_, _, _| {
-            git2::Cred::userpass_plaintext(&git_creds.username, &git_creds.password)
-        });
+    if !repository_verified && app_state.repository_verification == RepositoryVerification::Required
+    {
+        return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+            error: "repository must be a GitHub repository you can push to".to_string(),
+        }));
+    }
 
-        push_options.remote_callbacks(remote_callbacks);
+    // the archives are stored before the index is changed, so publishes of the same package must
+    // not interleave
+    let _publish_guard = app_state.publish_lock.lock().await;
 
-        remote.push(&[refspec], Some(&mut push_options))?;
+    let (scope, name) = manifest.name.as_str();
 
-        update_version(&app_state, &manifest.name, new_entry);
+    if owns_scope(&app_state, scope, &user_id)? == Some(false) {
+        return Ok(HttpResponse::Forbidden().finish());
     }
 
-    let version_id = VersionId::new(manifest.version.clone(), manifest.target.kind());
+    let mut new_entries = vec![];
 
-    let (a, b, c) = join!(
-        app_state
-            .storage
-            .store_package(&manifest.name, &version_id, bytes.to_vec()),
-        join_all(
-            docs_pages
-                .into_iter()
-                .map(|(hash, content)| app_state.storage.store_doc(hash, content)),
-        ),
-        async {
-            if let Some(readme) = readme {
+    {
+        let source = app_state.source.lock().unwrap();
+        source.refresh(&app_state.project).map_err(Box::new)?;
+        let config = source.config(&app_state.project)?;
+
+        for package in &packages {
+            let manifest = &package.manifest;
+
+            let dependencies = manifest
+                .all_dependencies()
+                .map_err(|_| Error::InvalidArchive)?;
+
+            for (specifier, ty) in dependencies.values() {
+                match specifier {
+                    DependencySpecifiers::Pesde(specifier) => {
+                        let Some(index_url) = specifier
+                            .index
+                            .as_deref()
+                            .and_then(|index| gix::Url::try_from(index).ok())
+                            .filter(|url| {
+                                config.other_registries_allowed || url == source.repo_url()
+                            })
+                        else {
+                            return Err(Error::InvalidArchive);
+                        };
+
+                        // dev dependencies aren't installed by consumers, and optional ones may be missing
+                        if index_url != *source.repo_url()
+                            || *ty == DependencyType::Dev
+                            || specifier.optional
+                        {
+                            continue;
+                        }
+
+                        let (scope, name) = specifier.name.as_str();
+                        let target = specifier.target.unwrap_or(manifest.target.kind());
+
+                        let available = source
+                            .read_file([scope, name], &app_state.project, None)?
+                            .map(|file| toml::de::from_str::<IndexFile>(&file))
+                            .transpose()?
+                            .is_some_and(|entries| {
+                                entries.iter().any(|(version_id, entry)| {
+                                    !entry.yanked
+                                        && *version_id.target() == target
+                                        && specifier.version.matches(version_id.version())
+                                })
+                            });
+
+                        if !available {
+                            return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                                error: format!(
+                                    "no version of {} matching {} is available for {target}, so the package couldn't be installed",
+                                    specifier.name, specifier.version
+                                ),
+                            }));
+                        }
+                    }
+                    DependencySpecifiers::Wally(specifier) => {
+                        if !config.wally_allowed {
+                            return Err(Error::InvalidArchive);
+                        }
+
+                        if specifier
+                            .index
+                            .as_ref()
+                            .filter(|index| index.parse::<url::Url>().is_ok())
+                            .is_none()
+                        {
+                            return Err(Error::InvalidArchive);
+                        }
+                    }
+                    DependencySpecifiers::Git(_) => {
+                        if !config.git_allowed {
+                            return Err(Error::InvalidArchive);
+                        }
+                    }
+                    DependencySpecifiers::Workspace(_) => {
+                        // workspace specifiers are to be transformed into Pesde specifiers by the sender
+                        return Err(Error::InvalidArchive);
+                    }
+                    DependencySpecifiers::Path(_) => {
+                        // path dependencies only exist on the machine of the sender
+                        return Err(Error::InvalidArchive);
+                    }
+                }
+            }
+
+            let version_id = VersionId::new(manifest.version.clone(), manifest.target.kind());
+            let checksum = format!("{:x}", Sha256::digest(&package.bytes));
+
+            new_entries.push((
+                version_id.clone(),
+                IndexFileEntry {
+                    target: manifest.target.clone(),
+                    published_at: chrono::Utc::now(),
+                    description: manifest.description.clone(),
+                    license: manifest.license.clone(),
+                    authors: manifest.authors.clone(),
+                    repository: manifest.repository.clone(),
+                    repository_verified,
+                    keywords: manifest.keywords.clone(),
+                    categories: manifest.categories.clone(),
+                    pesde_version: manifest.compatibility.pesde.clone(),
+                    tested_lune: manifest.compatibility.tested_lune.clone(),
+                    docs: package.docs.clone(),
+                    capabilities: manifest.capabilities.clone(),
+                    checksum: Some(checksum.clone()),
+                    signature: app_state.signing_key.as_ref().map(|key| {
+                        hex::encode(key.sign(
+                            signed_message(&manifest.name, &version_id, &checksum).as_bytes(),
+                        ))
+                    }),
+                    yanked: false,
+
+                    dependencies,
+                },
+            ));
+        }
+
+        let contents = source.read_file([scope, name], &app_state.project, None)?;
+
+        // names used to be case-sensitive, so the index may have a differently cased entry
+        if contents.is_none() {
+            if let Some(found) = source.find_ignoring_case(&[scope, name], &app_state.project)? {
+                return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!(
+                        "package {} conflicts with {} in the index, as package names are case-insensitive",
+                        manifest.name,
+                        found.join("/"),
+                    ),
+                }));
+            }
+        }
+
+        let mut entries: IndexFile = toml::de::from_str(&contents.unwrap_or_default())?;
+
+        if let Err(response) = add_entries(&mut entries, &new_entries) {
+            return Ok(response);
+        }
+    }
+
+    // if storing fails, the index doesn't reference any of the archives, so none of the targets
+    // are published
+    let (packages_stored, docs_stored) = join!(
+        join_all(packages.iter().map(|package| {
+            let version_id = VersionId::new(
+                package.manifest.version.clone(),
+                package.manifest.target.kind(),
+            );
+            let app_state = &app_state;
+            let manifest = &manifest;
+
+            async move {
                 app_state
                     .storage
-                    .store_readme(&manifest.name, &version_id, readme)
-                    .await
-            } else {
-                Ok(())
+                    .store_package(&manifest.name, &version_id, package.bytes.clone())
+                    .await?;
+
+                if let Some(readme) = &package.readme {
+                    app_state
+                        .storage
+                        .store_readme(&manifest.name, &version_id, readme.clone())
+                        .await?;
+                }
+
+                Ok::<_, Error>(())
             }
-        }
+        })),
+        join_all(
+            packages
+                .iter()
+                .flat_map(|package| &package.docs_pages)
+                .map(|(hash, content)| app_state.storage.store_doc(hash.clone(), content.clone())),
+        ),
     );
-    a?;
-    b.into_iter().collect::<Result<(), _>>()?;
-    c?;
+    packages_stored.into_iter().collect::<Result<(), _>>()?;
+    docs_stored.into_iter().collect::<Result<(), _>>()?;
+
+    {
+        let owned = owns_scope(&app_state, scope, &user_id)?;
+        if owned == Some(false) {
+            return Ok(HttpResponse::Forbidden().finish());
+        }
+
+        let source = app_state.source.lock().unwrap();
+        source.refresh(&app_state.project).map_err(Box::new)?;
+        let repo = source.repo_git2(&app_state.project)?;
+
+        let mut oids = vec![];
+
+        if owned.is_none() {
+            let scope_info = toml::to_string(&ScopeInfo {
+                owners: BTreeSet::from([user_id.0]),
+            })?;
+
+            let mut blob_writer = repo.blob_writer(None)?;
+            blob_writer.write_all(scope_info.as_bytes())?;
+            oids.push((SCOPE_INFO_FILE, blob_writer.commit()?));
+        }
+
+        // read again, as the index may have changed while the archives were stored
+        let contents = source.read_file([scope, name], &app_state.project, None)?;
+
+        // names used to be case-sensitive, so the index may have a differently cased entry
+        if contents.is_none() {
+            if let Some(found) = source.find_ignoring_case(&[scope, name], &app_state.project)? {
+                return Ok(HttpResponse::BadRequest().json(ErrorResponse {
+                    error: format!(
+                        "package {} conflicts with {} in the index, as package names are case-insensitive",
+                        manifest.name,
+                        found.join("/"),
+                    ),
+                }));
+            }
+        }
+
+        let mut entries: IndexFile = toml::de::from_str(&contents.unwrap_or_default())?;
+
+        if let Err(response) = add_entries(&mut entries, &new_entries) {
+            return Ok(response);
+        }
+
+        {
+            let index_content = toml::to_string(&entries)?;
+            let mut blob_writer = repo.blob_writer(None)?;
+            blob_writer.write_all(index_content.as_bytes())?;
+            oids.push((name, blob_writer.commit()?));
+        }
+
+        commit_scope_files(
+            &app_state,
+            &repo,
+            scope,
+            oids,
+            &format!("add {}@{} {targets}", manifest.name, manifest.version),
+        )?;
+
+        update_package(&app_state, &manifest.name, entries);
+    }
 
     Ok(HttpResponse::Ok().body(format!(
-        "published {}@{} {}",
-        manifest.name, manifest.version, manifest.target
+        "published {}@{} {targets}",
+        manifest.name, manifest.version
     )))
 }
@@ -0,0 +1,36 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use pesde::source::{
+    git_index::GitBasedSource,
+    pesde::{ScopeInfo, SCOPE_INFO_FILE},
+};
+
+use crate::{error::Error, package::PackageResponse, AppState};
+
+pub async fn get_scope_info(
+    app_state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<impl Responder, Error> {
+    let scope = path.into_inner();
+
+    let source = app_state.source.lock().unwrap();
+
+    let info: ScopeInfo =
+        match source.read_file([scope.as_str(), SCOPE_INFO_FILE], &app_state.project, None)? {
+            Some(info) => toml::de::from_str(&info)?,
+            None => return Ok(HttpResponse::NotFound().finish()),
+        };
+
+    let packages = source
+        .all_packages(&app_state.project)?
+        .into_iter()
+        .filter(|(name, _)| name.as_str().0 == scope)
+        .filter_map(|(name, versions)| PackageResponse::latest(&name, &versions))
+        .collect::<Vec<_>>();
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "scope": scope,
+        "owners": info.owners,
+        "packages": packages,
+    })))
+}
@@ -2,9 +2,15 @@ use std::collections::HashMap;
 
 use actix_web::{web, HttpResponse, Responder};
 use serde::Deserialize;
-use tantivy::{collector::Count, query::AllQuery, schema::Value, DateTime, Order};
+use tantivy::{
+    collector::{Count, DocSetCollector},
+    query::{AllQuery, BooleanQuery, Occur, Query, TermQuery},
+    schema::{IndexRecordOption, Value},
+    DateTime, Order, Term,
+};
 
 use pesde::{
+    manifest::target::TargetKind,
     names::PackageName,
     source::{git_index::GitBasedSource, pesde::IndexFile},
 };
@@ -17,8 +23,32 @@ pub struct Request {
     query: Option<String>,
     #[serde(default)]
     offset: Option<usize>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    target: Option<TargetKind>,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default)]
+    license: Option<String>,
+    #[serde(default)]
+    sort: Sort,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum Sort {
+    /// The most recently published packages first
+    #[default]
+    Recent,
+    /// The packages best matching the query first
+    Relevance,
+    /// The most downloaded packages first
+    Downloads,
 }
 
+const PAGE_SIZE: usize = 50;
+
 pub async fn search_packages(
     app_state: web::Data<AppState>,
     request: web::Query<Request>,
@@ -31,39 +61,153 @@ pub async fn search_packages(
     let scope = schema.get_field("scope").unwrap();
     let name = schema.get_field("name").unwrap();
     let description = schema.get_field("description").unwrap();
+    let keywords = schema.get_field("keywords").unwrap();
+    let categories = schema.get_field("categories").unwrap();
 
     let query = request.query.as_deref().unwrap_or_default().trim();
 
-    let query = if query.is_empty() {
+    let query: Box<dyn Query> = if query.is_empty() {
         Box::new(AllQuery)
     } else {
         let mut query_parser = tantivy::query::QueryParser::for_index(
             searcher.index(),
-            vec![scope, name, description],
+            vec![scope, name, description, keywords],
         );
         query_parser.set_field_boost(scope, 2.0);
         query_parser.set_field_boost(name, 3.5);
+        query_parser.set_field_boost(keywords, 2.5);
 
         query_parser.parse_query(query)?
     };
 
-    let (count, top_docs) = searcher
-        .search(
-            &query,
-            &(
-                Count,
-                tantivy::collector::TopDocs::with_limit(50)
-                    .and_offset(request.offset.unwrap_or_default())
-                    .order_by_fast_field::<DateTime>("published_at", Order::Desc),
-            ),
-        )
-        .unwrap();
+    let filters = [
+        (categories, request.category.clone()),
+        (
+            schema.get_field("targets").unwrap(),
+            request.target.map(|target| target.to_string()),
+        ),
+        (
+            schema.get_field("scope_exact").unwrap(),
+            request.scope.as_deref().map(str::to_lowercase),
+        ),
+        (
+            schema.get_field("license").unwrap(),
+            request.license.as_deref().map(str::to_lowercase),
+        ),
+    ]
+    .into_iter()
+    .filter_map(|(field, value)| {
+        value.map(|value| {
+            (
+                Occur::Must,
+                Box::new(TermQuery::new(
+                    Term::from_field_text(field, &value),
+                    IndexRecordOption::Basic,
+                )) as Box<dyn Query>,
+            )
+        })
+    })
+    .collect::<Vec<_>>();
+
+    let query: Box<dyn Query> = if filters.is_empty() {
+        query
+    } else {
+        Box::new(BooleanQuery::new(
+            std::iter::once((Occur::Must, query))
+                .chain(filters)
+                .collect(),
+        ))
+    };
+
+    let offset = request.offset.unwrap_or_default();
+
+    let (count, top_docs) = match request.sort {
+        Sort::Recent => {
+            let (count, top_docs) = searcher
+                .search(
+                    &query,
+                    &(
+                        Count,
+                        tantivy::collector::TopDocs::with_limit(PAGE_SIZE)
+                            .and_offset(offset)
+                            .order_by_fast_field::<DateTime>("published_at", Order::Desc),
+                    ),
+                )
+                .unwrap();
+
+            (
+                count,
+                top_docs
+                    .into_iter()
+                    .map(|(_, doc_address)| doc_address)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        Sort::Relevance => {
+            let (count, top_docs) = searcher
+                .search(
+                    &query,
+                    &(
+                        Count,
+                        tantivy::collector::TopDocs::with_limit(PAGE_SIZE).and_offset(offset),
+                    ),
+                )
+                .unwrap();
+
+            (
+                count,
+                top_docs
+                    .into_iter()
+                    .map(|(_, doc_address)| doc_address)
+                    .collect::<Vec<_>>(),
+            )
+        }
+        Sort::Downloads => {
+            let downloads = app_state.metrics.downloads();
+            let doc_addresses = searcher.search(&query, &DocSetCollector).unwrap();
+            let count = doc_addresses.len();
+
+            let mut doc_addresses = doc_addresses
+                .into_iter()
+                .map(|doc_address| {
+                    let doc = searcher.doc::<HashMap<_, _>>(doc_address).unwrap();
+                    let id = doc
+                        .get(&id)
+                        .unwrap()
+                        .as_str()
+                        .unwrap()
+                        .parse::<PackageName>()
+                        .unwrap();
+
+                    (
+                        downloads.get(&id).copied().unwrap_or_default(),
+                        id,
+                        doc_address,
+                    )
+                })
+                .collect::<Vec<_>>();
+            // ties are broken by name to keep pages stable
+            doc_addresses.sort_by(|(a_downloads, a_id, _), (b_downloads, b_id, _)| {
+                b_downloads.cmp(a_downloads).then_with(|| a_id.cmp(b_id))
+            });
+
+            (
+                count,
+                doc_addresses
+                    .into_iter()
+                    .skip(offset)
+                    .take(PAGE_SIZE)
+                    .map(|(_, _, doc_address)| doc_address)
+                    .collect(),
+            )
+        }
+    };
 
     let source = app_state.source.lock().unwrap();
 
     let top_docs = top_docs
         .into_iter()
-        .map(|(_, doc_address)| {
+        .map(|doc_address| {
             let doc = searcher.doc::<HashMap<_, _>>(doc_address).unwrap();
 
             let id = doc
@@ -83,29 +227,7 @@ pub async fn search_packages(
             )
             .unwrap();
 
-            let (latest_version, entry) = versions
-                .iter()
-                .max_by_key(|(v_id, _)| v_id.version())
-                .unwrap();
-
-            PackageResponse {
-                name: id.to_string(),
-                version: latest_version.version().to_string(),
-                targets: versions
-                    .iter()
-                    .filter(|(v_id, _)| v_id.version() == latest_version.version())
-                    .map(|(_, entry)| (&entry.target).into())
-                    .collect(),
-                description: entry.description.clone().unwrap_or_default(),
-                published_at: versions
-                    .values()
-                    .max_by_key(|entry| entry.published_at)
-                    .unwrap()
-                    .published_at,
-                license: entry.license.clone().unwrap_or_default(),
-                authors: entry.authors.clone(),
-                repository: entry.repository.clone().map(|url| url.to_string()),
-            }
+            PackageResponse::latest(&id, &versions).unwrap()
         })
         .collect::<Vec<_>>();
 
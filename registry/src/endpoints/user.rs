@@ -0,0 +1,9 @@
+use actix_web::{web, HttpResponse, Responder};
+
+use crate::auth::UserId;
+
+pub async fn get_user(user_id: web::ReqData<UserId>) -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({
+        "user_id": user_id.0,
+    }))
+}
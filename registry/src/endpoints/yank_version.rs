@@ -0,0 +1,101 @@
+use actix_web::{web, HttpResponse, Responder};
+use semver::Version;
+
+use crate::{auth::UserId, error::Error, git::commit_scope_files, AppState};
+use pesde::{
+    manifest::target::TargetKind,
+    names::PackageName,
+    source::{
+        git_index::GitBasedSource,
+        pesde::{IndexFile, ScopeInfo, SCOPE_INFO_FILE},
+        version_id::VersionId,
+    },
+};
+
+fn set_yanked(
+    app_state: &AppState,
+    name: &PackageName,
+    version_id: &VersionId,
+    user_id: UserId,
+    yanked: bool,
+) -> Result<HttpResponse, Error> {
+    let source = app_state.source.lock().unwrap();
+    source.refresh(&app_state.project).map_err(Box::new)?;
+
+    let (scope, name_part) = name.as_str();
+
+    match source.read_file([scope, SCOPE_INFO_FILE], &app_state.project, None)? {
+        Some(info) => {
+            let info: ScopeInfo = toml::de::from_str(&info)?;
+            if !info.owners.contains(&user_id.0) {
+                return Ok(HttpResponse::Forbidden().finish());
+            }
+        }
+        None => return Ok(HttpResponse::NotFound().finish()),
+    }
+
+    let mut entries: IndexFile =
+        match source.read_file([scope, name_part], &app_state.project, None)? {
+            Some(versions) => toml::de::from_str(&versions)?,
+            None => return Ok(HttpResponse::NotFound().finish()),
+        };
+
+    let Some(entry) = entries.get_mut(version_id) else {
+        return Ok(HttpResponse::NotFound().finish());
+    };
+
+    if entry.yanked == yanked {
+        return Ok(HttpResponse::Ok().finish());
+    }
+
+    entry.yanked = yanked;
+
+    let repo = source.repo_git2(&app_state.project)?;
+    let oid = repo.blob(toml::to_string(&entries)?.as_bytes())?;
+
+    commit_scope_files(
+        app_state,
+        &repo,
+        scope,
+        vec![(name_part, oid)],
+        &format!(
+            "{} {name}@{}",
+            if yanked { "yank" } else { "unyank" },
+            version_id.escaped()
+        ),
+    )?;
+
+    Ok(HttpResponse::Ok().finish())
+}
+
+pub async fn yank_package_version(
+    app_state: web::Data<AppState>,
+    path: web::Path<(PackageName, Version, TargetKind)>,
+    user_id: web::ReqData<UserId>,
+) -> Result<impl Responder, Error> {
+    let (name, version, target) = path.into_inner();
+
+    set_yanked(
+        &app_state,
+        &name,
+        &VersionId::new(version, target),
+        *user_id,
+        true,
+    )
+}
+
+pub async fn unyank_package_version(
+    app_state: web::Data<AppState>,
+    path: web::Path<(PackageName, Version, TargetKind)>,
+    user_id: web::ReqData<UserId>,
+) -> Result<impl Responder, Error> {
+    let (name, version, target) = path.into_inner();
+
+    set_yanked(
+        &app_state,
+        &name,
+        &VersionId::new(version, target),
+        *user_id,
+        false,
+    )
+}
@@ -1,5 +1,4 @@
 use actix_web::{body::BoxBody, HttpResponse, ResponseError};
-use log::error;
 use pesde::source::git_index::errors::{ReadFile, RefreshError};
 use serde::Serialize;
 use thiserror::Error;
@@ -38,6 +37,12 @@ pub enum Error {
 
     #[error("failed to serialize struct")]
     SerializeJson(#[from] serde_json::Error),
+
+    #[error("failed to read packages from index")]
+    AllPackages(#[from] pesde::source::pesde::errors::AllPackagesError),
+
+    #[error("failed to parse storage listing")]
+    StorageListing(#[source] Box<dyn std::error::Error + Send + Sync>),
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,209 @@
+use crate::{error::Error, storage::StorageImpl, tenant::Tenant, AppState};
+use actix_web::{
+    rt::time::{interval_at, Instant},
+    web,
+};
+use pesde::{
+    names::PackageName,
+    source::{
+        git_index::GitBasedSource,
+        pesde::{DocEntry, DocEntryKind},
+        version_id::VersionId,
+    },
+};
+use std::{collections::HashSet, time::Duration};
+
+/// The readmes and docs in storage which no index entry references
+#[derive(Debug, Default)]
+pub struct Garbage {
+    pub readmes: Vec<(PackageName, VersionId)>,
+    pub docs: Vec<String>,
+}
+
+fn collect_doc_hashes<'a, I: IntoIterator<Item = &'a DocEntry>>(
+    entries: I,
+    hashes: &mut HashSet<String>,
+) {
+    for entry in entries {
+        match &entry.kind {
+            DocEntryKind::Page { hash, .. } => {
+                hashes.insert(hash.clone());
+            }
+            DocEntryKind::Category { items, .. } => collect_doc_hashes(items, hashes),
+        }
+    }
+}
+
+/// Finds the readmes and docs in the tenant's storage which aren't referenced by any version in
+/// the index, such as those of versions which failed to be indexed, and deletes them unless
+/// `dry_run` is set. Those of yanked versions are kept, as they may be unyanked
+pub async fn collect_garbage(app_state: &AppState, dry_run: bool) -> Result<Garbage, Error> {
+    // publishing stores the readme and docs before indexing the version
+    let _publish_guard = app_state.publish_lock.lock().await;
+
+    let mut readmes = HashSet::new();
+    let mut docs = HashSet::new();
+
+    {
+        let source = app_state.source.lock().unwrap();
+        source.refresh(&app_state.project).map_err(Box::new)?;
+
+        for (name, file) in source.all_packages(&app_state.project)? {
+            for (version_id, entry) in file {
+                collect_doc_hashes(&entry.docs, &mut docs);
+                readmes.insert((name.clone(), version_id));
+            }
+        }
+    }
+
+    let garbage = Garbage {
+        readmes: app_state
+            .storage
+            .list_readmes()
+            .await?
+            .into_iter()
+            .filter(|readme| !readmes.contains(readme))
+            .collect(),
+        docs: app_state
+            .storage
+            .list_docs()
+            .await?
+            .into_iter()
+            .filter(|hash| !docs.contains(hash))
+            .collect(),
+    };
+
+    if dry_run {
+        return Ok(garbage);
+    }
+
+    for (name, version_id) in &garbage.readmes {
+        app_state.storage.delete_readme(name, version_id).await?;
+    }
+
+    for hash in &garbage.docs {
+        app_state.storage.delete_doc(hash).await?;
+    }
+
+    Ok(garbage)
+}
+
+/// Whether two storage locations overlap, meaning one's files could be mistaken for the other's
+fn overlaps(a: &str, b: &str) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// Spawns the task which collects the garbage in the tenant's storage every `GC_INTERVAL_HOURS`,
+/// if it is set. With `GC_DRY_RUN` set, the garbage is only reported. Collecting is refused if
+/// the storage overlaps with that of another tenant, in `others`, as its files would be deleted
+pub fn spawn_collector(tenant: &Tenant, app_state: web::Data<AppState>, others: &[String]) {
+    let Some(hours) = tenant.parse::<u64>("GC_INTERVAL_HOURS") else {
+        return;
+    };
+
+    let location = app_state.storage.location();
+    if others.iter().any(|other| overlaps(&location, other)) {
+        log::error!("[{tenant}] storage is shared with another tenant, not collecting garbage");
+        return;
+    }
+    let dry_run = tenant.var("GC_DRY_RUN").is_ok();
+    let tenant = tenant.clone();
+
+    let period = Duration::from_secs(hours * 60 * 60);
+
+    actix_web::rt::spawn(async move {
+        let mut interval = interval_at(Instant::now() + period, period);
+
+        loop {
+            interval.tick().await;
+
+            match collect_garbage(&app_state, dry_run).await {
+                Ok(garbage) => {
+                    for (name, version_id) in &garbage.readmes {
+                        log::info!("[{tenant}] orphaned readme: {name}@{version_id}");
+                    }
+                    for hash in &garbage.docs {
+                        log::info!("[{tenant}] orphaned doc: {hash}");
+                    }
+
+                    log::info!(
+                        "[{tenant}] {} {} orphaned readmes and {} orphaned docs",
+                        if dry_run { "found" } else { "deleted" },
+                        garbage.readmes.len(),
+                        garbage.docs.len()
+                    );
+                }
+                Err(e) => log::error!("[{tenant}] failed to collect garbage: {e:?}"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(label: &str, hash: &str) -> DocEntry {
+        DocEntry {
+            label: label.to_string(),
+            position: None,
+            kind: DocEntryKind::Page {
+                name: label.to_lowercase(),
+                hash: hash.to_string(),
+            },
+        }
+    }
+
+    fn category(label: &str, items: Vec<DocEntry>) -> DocEntry {
+        DocEntry {
+            label: label.to_string(),
+            position: None,
+            kind: DocEntryKind::Category {
+                items: items.into_iter().collect(),
+                collapsed: false,
+            },
+        }
+    }
+
+    #[test]
+    fn collects_nested_doc_hashes() {
+        let entries = [
+            category(
+                "Guide",
+                vec![
+                    page("Intro", "a"),
+                    category("Advanced", vec![page("Deep", "b")]),
+                ],
+            ),
+            page("Changelog", "c"),
+        ];
+
+        let mut hashes = HashSet::new();
+        collect_doc_hashes(&entries, &mut hashes);
+
+        assert_eq!(
+            hashes,
+            ["a", "b", "c"]
+                .map(ToString::to_string)
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn detects_overlapping_storage() {
+        assert!(overlaps(
+            "https://s3.acme.dev/bucket/",
+            "https://s3.acme.dev/bucket/"
+        ));
+        assert!(overlaps(
+            "https://s3.acme.dev/bucket/",
+            "https://s3.acme.dev/bucket/internal/"
+        ));
+        assert!(!overlaps(
+            "https://s3.acme.dev/bucket/public/",
+            "https://s3.acme.dev/bucket/internal/"
+        ));
+        assert!(!overlaps("/data/public/", "/data/public-2/"));
+    }
+}
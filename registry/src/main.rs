@@ -1,12 +1,19 @@
 use actix_cors::Cors;
 use actix_governor::{Governor, GovernorConfigBuilder};
 use actix_web::{
+    guard,
     middleware::{from_fn, Compress, Condition, Logger, NormalizePath, TrailingSlash},
     rt::System,
     web, App, HttpServer,
 };
-use log::info;
-use std::{env::current_dir, fs::create_dir_all, path::PathBuf, sync::Mutex};
+use log::{info, warn};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use std::{
+    env::current_dir,
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+    sync::{mpsc::Sender, Mutex},
+};
 
 use pesde::{
     source::{pesde::PesdePackageSource, traits::PackageSource},
@@ -14,17 +21,27 @@ use pesde::{
 };
 
 use crate::{
-    auth::{get_auth_from_env, Auth, UserIdExtractor},
-    search::make_search,
+    auth::{
+        get_auth_from_env, get_repository_verification_from_env, Auth, AuthImpl,
+        RepositoryVerification, UserIdExtractor,
+    },
+    gc::spawn_collector,
+    metrics::{spawn_flusher, Metrics},
+    search::{make_search, spawn_writer, SearchUpdate},
     storage::{get_storage_from_env, Storage},
+    tenant::{get_tenants_from_env, Route, Tenant},
 };
 
 mod auth;
 mod endpoints;
 mod error;
+mod gc;
+mod git;
+mod metrics;
 mod package;
 mod search;
 mod storage;
+mod tenant;
 
 pub fn make_reqwest() -> reqwest::Client {
     reqwest::ClientBuilder::new()
@@ -42,9 +59,18 @@ pub struct AppState {
     pub project: Project,
     pub storage: Storage,
     pub auth: Auth,
+    pub read_needs_auth: bool,
+    pub repository_verification: RepositoryVerification,
 
     pub search_reader: tantivy::IndexReader,
-    pub search_writer: Mutex<tantivy::IndexWriter>,
+    pub search_updates: Sender<SearchUpdate>,
+
+    pub metrics: Metrics,
+
+    pub signing_key: Option<Ed25519KeyPair>,
+
+    /// Held while publishing, which stores the archives before adding them to the index
+    pub publish_lock: futures::lock::Mutex<()>,
 }
 
 #[macro_export]
@@ -80,48 +106,126 @@ macro_rules! benv {
     };
 }
 
-async fn run(with_sentry: bool) -> std::io::Result<()> {
-    let address = benv!("ADDRESS" => "127.0.0.1");
-    let port: u16 = benv!(parse "PORT" => "8080");
-
-    let cwd = current_dir().unwrap();
-    let data_dir = cwd.join("data");
-    create_dir_all(&data_dir).unwrap();
+async fn make_app_state(tenant: &Tenant, cwd: &Path, data_dir: &Path) -> web::Data<AppState> {
+    let tenant_dir = match &tenant.name {
+        Some(name) => data_dir.join("tenants").join(name),
+        None => data_dir.to_path_buf(),
+    };
+    create_dir_all(&tenant_dir).unwrap();
 
     let project = Project::new(
-        &cwd,
+        cwd,
         None::<PathBuf>,
-        data_dir.join("project"),
-        &cwd,
+        tenant_dir.join("project"),
+        cwd,
         AuthConfig::new().with_git_credentials(Some(gix::sec::identity::Account {
-            username: benv!(required "GITHUB_USERNAME"),
-            password: benv!(required "GITHUB_PAT"),
+            username: tenant.required("GITHUB_USERNAME"),
+            password: tenant.required("GITHUB_PAT"),
         })),
     );
-    let source = PesdePackageSource::new(benv!(required "INDEX_REPO_URL").try_into().unwrap());
+    let source = PesdePackageSource::new(tenant.required("INDEX_REPO_URL").try_into().unwrap());
     source.refresh(&project).expect("failed to refresh source");
 
     let (search_reader, search_writer) = make_search(&project, &source);
+    let search_updates = spawn_writer(search_writer, search_reader.clone());
 
-    let app_data = web::Data::new(AppState {
-        storage: {
-            let storage = get_storage_from_env();
-            info!("storage: {storage}");
-            storage
-        },
-        auth: {
-            let auth =
-                get_auth_from_env(source.config(&project).expect("failed to get index config"));
-            info!("auth: {auth}");
-            auth
-        },
+    let storage = get_storage_from_env(tenant);
+    info!("[{tenant}] storage: {storage}");
+
+    let metrics = Metrics::load(&storage)
+        .await
+        .expect("failed to load metrics from storage");
+
+    let signing_key = tenant.var("SIGNING_KEY").ok().map(|key| {
+        let key = hex::decode(key).expect("`SIGNING_KEY` must be hex encoded");
+        let key = Ed25519KeyPair::from_pkcs8_maybe_unchecked(&key)
+            .expect("`SIGNING_KEY` must be an Ed25519 PKCS#8 document");
+
+        let public_key = hex::encode(key.public_key());
+        let config = source.config(&project).expect("failed to get index config");
+        if config.signing_key.as_deref() != Some(public_key.as_str()) {
+            warn!("[{tenant}] index config's `signing_key` should be set to {public_key} for clients to verify signatures");
+        }
+
+        key
+    });
+    info!(
+        "[{tenant}] signing: {}",
+        if signing_key.is_some() {
+            "enabled"
+        } else {
+            "disabled"
+        }
+    );
+
+    let auth = get_auth_from_env(
+        tenant,
+        source.config(&project).expect("failed to get index config"),
+    );
+    info!("[{tenant}] auth: {auth}");
+
+    let repository_verification = get_repository_verification_from_env(tenant, &auth);
+    info!("[{tenant}] repository verification: {repository_verification}");
+
+    web::Data::new(AppState {
+        storage,
+        read_needs_auth: auth.read_needs_auth() || tenant.var("READ_NEEDS_AUTH").is_ok(),
+        repository_verification,
+        auth,
         source: Mutex::new(source),
         project,
 
         search_reader,
-        search_writer: Mutex::new(search_writer),
-    });
+        search_updates,
 
+        metrics,
+
+        signing_key,
+        publish_lock: futures::lock::Mutex::new(()),
+    })
+}
+
+async fn run(with_sentry: bool) -> std::io::Result<()> {
+    let address = benv!("ADDRESS" => "127.0.0.1");
+    let port: u16 = benv!(parse "PORT" => "8080");
+
+    let cwd = current_dir().unwrap();
+    let data_dir = cwd.join("data");
+    create_dir_all(&data_dir).unwrap();
+
+    let mut tenants = vec![];
+    for tenant in get_tenants_from_env() {
+        let route = tenant.route();
+        info!("[{tenant}] serving {route}");
+
+        let app_state = make_app_state(&tenant, &cwd, &data_dir).await;
+        tenants.push((tenant, route, app_state));
+    }
+
+    let locations = tenants
+        .iter()
+        .map(|(_, _, app_state)| app_state.storage.location())
+        .collect::<Vec<_>>();
+    for (i, (tenant, _, app_state)) in tenants.iter().enumerate() {
+        let others = locations
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, location)| location.clone())
+            .collect::<Vec<_>>();
+
+        spawn_collector(tenant, app_state.clone(), &others);
+        spawn_flusher(tenant, app_state.clone());
+    }
+
+    let mut tenants = tenants
+        .into_iter()
+        .map(|(_, route, app_state)| (route, app_state))
+        .collect::<Vec<_>>();
+    // scopes are matched in order, so the catch-all scopes of hostnames must come last
+    tenants.sort_by_key(|(route, _)| !matches!(route, Route::PathPrefix(_)));
+
+    // rate limits are shared between tenants, as they share the machine
     let publish_governor_config = GovernorConfigBuilder::default()
         .key_extractor(UserIdExtractor)
         .burst_size(12)
@@ -133,47 +237,89 @@ async fn run(with_sentry: bool) -> std::io::Result<()> {
     info!("listening on {address}:{port}");
 
     HttpServer::new(move || {
-        App::new()
+        let mut app = App::new()
             .wrap(Condition::new(with_sentry, sentry_actix::Sentry::new()))
             .wrap(NormalizePath::new(TrailingSlash::Trim))
             .wrap(Cors::permissive())
             .wrap(Logger::default())
             .wrap(Compress::default())
-            .app_data(app_data.clone())
             .route(
                 "/",
                 web::get().to(|| async {
                     concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"))
                 }),
-            )
-            .service(
-                web::scope("/v0")
-                    .route(
-                        "/search",
-                        web::get()
-                            .to(endpoints::search::search_packages)
-                            .wrap(from_fn(auth::read_mw)),
-                    )
-                    .route(
-                        "/packages/{name}",
-                        web::get()
-                            .to(endpoints::package_versions::get_package_versions)
-                            .wrap(from_fn(auth::read_mw)),
-                    )
-                    .route(
-                        "/packages/{name}/{version}/{target}",
-                        web::get()
-                            .to(endpoints::package_version::get_package_version)
-                            .wrap(from_fn(auth::read_mw)),
-                    )
-                    .route(
-                        "/packages",
-                        web::post()
-                            .to(endpoints::publish_version::publish_package)
-                            .wrap(Governor::new(&publish_governor_config))
-                            .wrap(from_fn(auth::write_mw)),
-                    ),
-            )
+            );
+
+        for (route, app_data) in &tenants {
+            let scope = match route {
+                Route::Host(host) => web::scope("").guard(guard::Host(host)),
+                Route::PathPrefix(prefix) => web::scope(prefix),
+                Route::All => web::scope(""),
+            };
+
+            app = app.service(
+                scope.app_data(app_data.clone()).service(
+                    web::scope("/v0")
+                        .route(
+                            "/search",
+                            web::get()
+                                .to(endpoints::search::search_packages)
+                                .wrap(from_fn(auth::read_mw)),
+                        )
+                        .route(
+                            "/packages/{name}",
+                            web::get()
+                                .to(endpoints::package_versions::get_package_versions)
+                                .wrap(from_fn(auth::read_mw)),
+                        )
+                        .route(
+                            "/packages/{name}/metrics",
+                            web::get()
+                                .to(endpoints::package_metrics::get_package_metrics)
+                                .wrap(from_fn(auth::read_mw)),
+                        )
+                        .route(
+                            "/packages/{name}/{version}/{target}",
+                            web::get()
+                                .to(endpoints::package_version::get_package_version)
+                                .wrap(from_fn(auth::read_mw)),
+                        )
+                        .route(
+                            "/packages/{name}/{version}/{target}",
+                            web::delete()
+                                .to(endpoints::yank_version::yank_package_version)
+                                .wrap(from_fn(auth::write_mw)),
+                        )
+                        .route(
+                            "/packages/{name}/{version}/{target}/unyank",
+                            web::put()
+                                .to(endpoints::yank_version::unyank_package_version)
+                                .wrap(from_fn(auth::write_mw)),
+                        )
+                        .route(
+                            "/scopes/{scope}",
+                            web::get()
+                                .to(endpoints::scope_info::get_scope_info)
+                                .wrap(from_fn(auth::read_mw)),
+                        )
+                        .route(
+                            "/user",
+                            web::get()
+                                .to(endpoints::user::get_user)
+                                .wrap(from_fn(auth::write_mw)),
+                        )
+                        .route(
+                            "/packages",
+                            web::post()
+                                .to(endpoints::publish_version::publish_package)
+                                .wrap(Governor::new(&publish_governor_config))
+                                .wrap(from_fn(auth::write_mw)),
+                        ),
+                ),
+            );
+        }
+
+        app
     })
     .bind((address, port))?
     .run()
@@ -0,0 +1,281 @@
+use crate::{
+    error::Error,
+    storage::{Storage, StorageImpl},
+    AppState,
+};
+use actix_web::{
+    rt::time::{interval_at, Instant},
+    web,
+};
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use pesde::{names::PackageName, source::version_id::VersionId};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Mutex, time::Duration};
+
+/// How often changed counters are written to the storage
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// How many days of daily download counts are kept for each package
+const DAILY_HISTORY_DAYS: i64 = 90;
+
+/// The usage counters of a package
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct PackageMetrics {
+    /// Archive downloads of every version
+    #[serde(default)]
+    pub downloads: u64,
+    /// Requests resolving the package's versions without downloading an archive
+    #[serde(default)]
+    pub resolutions: u64,
+    /// Archive downloads of each version
+    #[serde(default)]
+    pub versions: BTreeMap<VersionId, u64>,
+    /// Archive downloads of each (UTC) day, for the last [`DAILY_HISTORY_DAYS`] days
+    #[serde(default)]
+    pub daily: BTreeMap<NaiveDate, u64>,
+}
+
+impl PackageMetrics {
+    /// Adds the other counters to these, dropping daily counts older than the history
+    fn merge(&mut self, other: &PackageMetrics) {
+        self.downloads += other.downloads;
+        self.resolutions += other.resolutions;
+
+        for (version, downloads) in &other.versions {
+            *self.versions.entry(version.clone()).or_default() += downloads;
+        }
+        for (day, downloads) in &other.daily {
+            *self.daily.entry(*day).or_default() += downloads;
+        }
+
+        let cutoff = Utc::now().date_naive() - ChronoDuration::days(DAILY_HISTORY_DAYS);
+        self.daily = self.daily.split_off(&cutoff);
+    }
+}
+
+type Counters = BTreeMap<PackageName, PackageMetrics>;
+
+fn merge(counters: &mut Counters, other: &Counters) {
+    for (name, metrics) in other {
+        counters.entry(name.clone()).or_default().merge(metrics);
+    }
+}
+
+/// Reads the counters from the storage, which are empty if they were never stored
+async fn read_counters(storage: &Storage) -> Result<Counters, Error> {
+    match storage.get_metrics().await? {
+        Some(contents) => Ok(serde_json::from_slice(&contents)?),
+        None => Ok(Counters::new()),
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The stored counters as of the last flush, plus the changes recorded since
+    totals: Counters,
+    /// The changes recorded since the last flush
+    pending: Counters,
+}
+
+/// Download and resolution counters of the packages in a tenant's index, persisted to the
+/// tenant's storage. Only the changes are written, so registries sharing the storage add up
+/// their counts instead of overwriting each other's
+#[derive(Debug)]
+pub struct Metrics {
+    state: Mutex<State>,
+}
+
+impl Metrics {
+    /// Loads the counters from the storage. Fails if the stored counters can't be read, so they
+    /// are never overwritten by the counts of this run alone
+    pub async fn load(storage: &Storage) -> Result<Self, Error> {
+        Ok(Self {
+            state: Mutex::new(State {
+                totals: read_counters(storage).await?,
+                pending: Counters::new(),
+            }),
+        })
+    }
+
+    fn record(&self, name: &PackageName, change: PackageMetrics) {
+        let state = &mut *self.state.lock().unwrap();
+
+        for counters in [&mut state.totals, &mut state.pending] {
+            counters.entry(name.clone()).or_default().merge(&change);
+        }
+    }
+
+    /// Records a download of the package's archive
+    pub fn record_download(&self, name: &PackageName, version: &VersionId) {
+        self.record(
+            name,
+            PackageMetrics {
+                downloads: 1,
+                versions: BTreeMap::from([(version.clone(), 1)]),
+                daily: BTreeMap::from([(Utc::now().date_naive(), 1)]),
+                ..Default::default()
+            },
+        );
+    }
+
+    /// Records a request resolving the package's versions
+    pub fn record_resolution(&self, name: &PackageName) {
+        self.record(
+            name,
+            PackageMetrics {
+                resolutions: 1,
+                ..Default::default()
+            },
+        );
+    }
+
+    /// The counters of the package, which are all zero if it has never been requested
+    pub fn package(&self, name: &PackageName) -> PackageMetrics {
+        self.state
+            .lock()
+            .unwrap()
+            .totals
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The total downloads of every package which has been downloaded
+    pub fn downloads(&self) -> BTreeMap<PackageName, u64> {
+        self.state
+            .lock()
+            .unwrap()
+            .totals
+            .iter()
+            .map(|(name, metrics)| (name.clone(), metrics.downloads))
+            .collect()
+    }
+
+    /// Adds the changes since the last flush to the stored counters, picking up the changes
+    /// written by other registries sharing the storage
+    async fn flush(&self, storage: &Storage) -> Result<(), Error> {
+        let pending = std::mem::take(&mut self.state.lock().unwrap().pending);
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let result = async {
+            // stored counters which fail to parse are left alone instead of being overwritten
+            let mut stored = read_counters(storage).await?;
+            merge(&mut stored, &pending);
+            storage.store_metrics(serde_json::to_vec(&stored)?).await?;
+
+            Ok::<_, Error>(stored)
+        }
+        .await;
+
+        let mut state = self.state.lock().unwrap();
+        match result {
+            Ok(mut stored) => {
+                // changes recorded while flushing are written by the next flush
+                merge(&mut stored, &state.pending);
+                state.totals = stored;
+
+                Ok(())
+            }
+            Err(e) => {
+                merge(&mut state.pending, &pending);
+
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Spawns the task which periodically writes the counters to the storage. Counters recorded since
+/// the last flush are lost if the registry stops
+pub fn spawn_flusher(tenant: &crate::tenant::Tenant, app_state: web::Data<AppState>) {
+    let tenant = tenant.clone();
+
+    actix_web::rt::spawn(async move {
+        let mut interval = interval_at(Instant::now() + FLUSH_INTERVAL, FLUSH_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = app_state.metrics.flush(&app_state.storage).await {
+                log::error!("[{tenant}] failed to write metrics: {e:?}");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::fs::FSStorage;
+
+    fn storage(dir: &std::path::Path) -> Storage {
+        Storage::FS(FSStorage {
+            root: dir.to_path_buf(),
+        })
+    }
+
+    fn name() -> PackageName {
+        "acme/package".parse().unwrap()
+    }
+
+    fn version() -> VersionId {
+        "1.0.0 luau".parse().unwrap()
+    }
+
+    #[test]
+    fn adds_up_counts_of_registries_sharing_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage(dir.path());
+
+        actix_web::rt::System::new().block_on(async {
+            let a = Metrics::load(&storage).await.unwrap();
+            let b = Metrics::load(&storage).await.unwrap();
+
+            a.record_download(&name(), &version());
+            b.record_download(&name(), &version());
+            b.record_resolution(&name());
+
+            a.flush(&storage).await.unwrap();
+            b.flush(&storage).await.unwrap();
+
+            let metrics = b.package(&name());
+            assert_eq!(metrics.downloads, 2);
+            assert_eq!(metrics.resolutions, 1);
+            assert_eq!(metrics.versions[&version()], 2);
+
+            let loaded = Metrics::load(&storage).await.unwrap().package(&name());
+            assert_eq!(loaded.downloads, 2);
+            assert_eq!(loaded.daily.values().sum::<u64>(), 2);
+        });
+    }
+
+    #[test]
+    fn never_overwrites_unparsable_counters() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = storage(dir.path());
+
+        actix_web::rt::System::new().block_on(async {
+            let metrics = Metrics::load(&storage).await.unwrap();
+            metrics.record_download(&name(), &version());
+
+            storage.store_metrics(b"{".to_vec()).await.unwrap();
+
+            assert!(Metrics::load(&storage).await.is_err());
+            assert!(metrics.flush(&storage).await.is_err());
+            assert_eq!(storage.get_metrics().await.unwrap().unwrap(), b"{");
+
+            // the changes are kept until they can be written
+            storage.store_metrics(b"{}".to_vec()).await.unwrap();
+            metrics.flush(&storage).await.unwrap();
+            assert_eq!(
+                Metrics::load(&storage)
+                    .await
+                    .unwrap()
+                    .package(&name())
+                    .downloads,
+                1
+            );
+        });
+    }
+}
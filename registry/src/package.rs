@@ -1,5 +1,9 @@
 use chrono::{DateTime, Utc};
-use pesde::manifest::target::{Target, TargetKind};
+use pesde::{
+    manifest::target::{Target, TargetKind},
+    names::PackageName,
+    source::pesde::IndexFile,
+};
 use serde::Serialize;
 use std::collections::BTreeSet;
 
@@ -21,7 +25,7 @@ impl From<&Target> for TargetInfo {
         TargetInfo {
             kind: target.kind(),
             lib: target.lib_path().is_some(),
-            bin: target.bin_path().is_some(),
+            bin: target.has_bins(),
         }
     }
 }
@@ -52,4 +56,39 @@ pub struct PackageResponse {
     pub authors: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repository: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub repository_verified: bool,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub keywords: BTreeSet<String>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub categories: BTreeSet<String>,
+}
+
+impl PackageResponse {
+    /// Describes the latest version of a package, or `None` if it has no versions
+    pub fn latest(name: &PackageName, versions: &IndexFile) -> Option<Self> {
+        let (latest_version, entry) = versions.iter().max_by_key(|(v_id, _)| v_id.version())?;
+
+        Some(PackageResponse {
+            name: name.to_string(),
+            version: latest_version.version().to_string(),
+            targets: versions
+                .iter()
+                .filter(|(v_id, _)| v_id.version() == latest_version.version())
+                .map(|(_, entry)| (&entry.target).into())
+                .collect(),
+            description: entry.description.clone().unwrap_or_default(),
+            published_at: versions
+                .values()
+                .map(|entry| entry.published_at)
+                .max()
+                .unwrap_or(entry.published_at),
+            license: entry.license.clone().unwrap_or_default(),
+            authors: entry.authors.clone(),
+            repository: entry.repository.clone().map(|url| url.to_string()),
+            repository_verified: entry.repository_verified,
+            keywords: entry.keywords.clone(),
+            categories: entry.categories.clone(),
+        })
+    }
 }
@@ -1,15 +1,73 @@
 use crate::AppState;
+use std::{
+    collections::BTreeMap,
+    sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender},
+    time::{Duration, Instant},
+};
+
 use pesde::{
     names::PackageName,
-    source::pesde::{IndexFileEntry, PesdePackageSource},
+    source::pesde::{IndexFile, PesdePackageSource},
     Project,
 };
 use tantivy::{
     doc,
-    schema::{IndexRecordOption, TextFieldIndexing, TextOptions, FAST, STORED, STRING},
-    DateTime, IndexReader, IndexWriter, Term,
+    schema::{IndexRecordOption, Schema, TextFieldIndexing, TextOptions, FAST, STORED, STRING},
+    DateTime, IndexReader, IndexWriter, TantivyDocument, Term,
 };
 
+/// How long the writer waits for more updates before committing a batch
+const COMMIT_DEBOUNCE: Duration = Duration::from_millis(500);
+/// The longest a batch may keep collecting updates, so a steady stream of publishes still commits
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(5);
+
+/// A package's index file, with which its search document is replaced
+pub type SearchUpdate = (PackageName, IndexFile);
+
+/// Creates the document of a package from its latest version, returning `None` if it has no versions
+fn package_document(
+    schema: &Schema,
+    name: &PackageName,
+    mut file: IndexFile,
+) -> Option<TantivyDocument> {
+    let (latest_id, entry) = file.pop_last()?;
+
+    let mut document = doc!(
+        schema.get_field("id").unwrap() => name.to_string(),
+        schema.get_field("scope").unwrap() => name.as_str().0,
+        schema.get_field("scope_exact").unwrap() => name.as_str().0,
+        schema.get_field("name").unwrap() => name.as_str().1,
+        schema.get_field("description").unwrap() => entry.description.unwrap_or_default(),
+        schema.get_field("published_at").unwrap() => DateTime::from_timestamp_secs(entry.published_at.timestamp()),
+    );
+
+    if let Some(license) = entry.license {
+        document.add_text(schema.get_field("license").unwrap(), license.to_lowercase());
+    }
+
+    let targets = schema.get_field("targets").unwrap();
+    document.add_text(targets, latest_id.target().to_string());
+    for (v_id, _) in file.range(..latest_id.clone()).rev() {
+        if v_id.version() != latest_id.version() {
+            break;
+        }
+
+        document.add_text(targets, v_id.target().to_string());
+    }
+
+    let keywords = schema.get_field("keywords").unwrap();
+    for keyword in entry.keywords {
+        document.add_text(keywords, keyword);
+    }
+
+    let categories = schema.get_field("categories").unwrap();
+    for category in entry.categories {
+        document.add_text(categories, category);
+    }
+
+    Some(document)
+}
+
 pub fn make_search(project: &Project, source: &PesdePackageSource) -> (IndexReader, IndexWriter) {
     let mut schema_builder = tantivy::schema::SchemaBuilder::new();
 
@@ -19,13 +77,19 @@ pub fn make_search(project: &Project, source: &PesdePackageSource) -> (IndexRead
             .set_index_option(IndexRecordOption::WithFreqsAndPositions),
     );
 
-    let id_field = schema_builder.add_text_field("id", STRING | STORED);
-    let scope = schema_builder.add_text_field("scope", field_options.clone());
-    let name = schema_builder.add_text_field("name", field_options.clone());
-    let description = schema_builder.add_text_field("description", field_options);
-    let published_at = schema_builder.add_date_field("published_at", FAST);
+    schema_builder.add_text_field("id", STRING | STORED);
+    schema_builder.add_text_field("scope", field_options.clone());
+    schema_builder.add_text_field("scope_exact", STRING);
+    schema_builder.add_text_field("name", field_options.clone());
+    schema_builder.add_text_field("description", field_options.clone());
+    schema_builder.add_text_field("keywords", field_options);
+    schema_builder.add_text_field("categories", STRING);
+    schema_builder.add_text_field("targets", STRING);
+    schema_builder.add_text_field("license", STRING);
+    schema_builder.add_date_field("published_at", FAST);
 
-    let search_index = tantivy::Index::create_in_ram(schema_builder.build());
+    let schema = schema_builder.build();
+    let search_index = tantivy::Index::create_in_ram(schema.clone());
     search_index.tokenizers().register(
         "ngram",
         tantivy::tokenizer::NgramTokenizer::all_ngrams(1, 12).unwrap(),
@@ -38,19 +102,13 @@ pub fn make_search(project: &Project, source: &PesdePackageSource) -> (IndexRead
         .unwrap();
     let mut search_writer = search_index.writer(50_000_000).unwrap();
 
-    for (pkg_name, mut file) in source.all_packages(project).unwrap() {
-        let Some((_, latest_entry)) = file.pop_last() else {
+    for (pkg_name, file) in source.all_packages(project).unwrap() {
+        let Some(document) = package_document(&schema, &pkg_name, file) else {
             log::warn!("no versions found for {pkg_name}");
             continue;
         };
 
-        search_writer.add_document(doc!(
-            id_field => pkg_name.to_string(),
-            scope => pkg_name.as_str().0,
-            name => pkg_name.as_str().1,
-            description => latest_entry.description.unwrap_or_default(),
-            published_at => DateTime::from_timestamp_secs(latest_entry.published_at.timestamp()),
-        )).unwrap();
+        search_writer.add_document(document).unwrap();
     }
 
     search_writer.commit().unwrap();
@@ -59,21 +117,91 @@ pub fn make_search(project: &Project, source: &PesdePackageSource) -> (IndexRead
     (search_reader, search_writer)
 }
 
-pub fn update_version(app_state: &AppState, name: &PackageName, entry: IndexFileEntry) {
-    let mut search_writer = app_state.search_writer.lock().unwrap();
+/// Receives the next batch of updates, keeping only the latest update per package.
+/// Returns `None` once every sender has been dropped and no updates are left
+fn next_batch(receiver: &Receiver<SearchUpdate>) -> Option<BTreeMap<PackageName, IndexFile>> {
+    let (name, file) = receiver.recv().ok()?;
+
+    let mut batch = BTreeMap::new();
+    batch.insert(name, file);
+
+    let deadline = Instant::now() + MAX_BATCH_DELAY;
+
+    loop {
+        let timeout = deadline
+            .saturating_duration_since(Instant::now())
+            .min(COMMIT_DEBOUNCE);
+
+        match receiver.recv_timeout(timeout) {
+            Ok((name, file)) => {
+                batch.insert(name, file);
+            }
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    Some(batch)
+}
+
+fn run_writer(
+    mut search_writer: IndexWriter,
+    search_reader: IndexReader,
+    receiver: Receiver<SearchUpdate>,
+) {
     let schema = search_writer.index().schema();
     let id_field = schema.get_field("id").unwrap();
 
-    search_writer.delete_term(Term::from_field_text(id_field, &name.to_string()));
+    while let Some(batch) = next_batch(&receiver) {
+        let count = batch.len();
 
-    search_writer.add_document(doc!(
-        id_field => name.to_string(),
-        schema.get_field("scope").unwrap() => name.as_str().0,
-        schema.get_field("name").unwrap() => name.as_str().1,
-        schema.get_field("description").unwrap() => entry.description.unwrap_or_default(),
-        schema.get_field("published_at").unwrap() => DateTime::from_timestamp_secs(entry.published_at.timestamp())
-    )).unwrap();
+        for (name, file) in batch {
+            search_writer.delete_term(Term::from_field_text(id_field, &name.to_string()));
 
-    search_writer.commit().unwrap();
-    app_state.search_reader.reload().unwrap();
+            let Some(document) = package_document(&schema, &name, file) else {
+                continue;
+            };
+
+            if let Err(e) = search_writer.add_document(document) {
+                log::error!("failed to index {name}: {e}");
+            }
+        }
+
+        if let Err(e) = search_writer.commit() {
+            log::error!("failed to commit search index: {e}");
+            if let Err(e) = search_writer.rollback() {
+                log::error!("failed to roll back search index: {e}");
+            }
+            continue;
+        }
+
+        // the committed changes will become visible on the next successful reload
+        if let Err(e) = search_reader.reload() {
+            log::error!("failed to reload search reader: {e}");
+            continue;
+        }
+
+        log::debug!("committed {count} search index update(s)");
+    }
+}
+
+/// Spawns the thread which owns the search writer, returning the channel to send updates to it
+pub fn spawn_writer(
+    search_writer: IndexWriter,
+    search_reader: IndexReader,
+) -> Sender<SearchUpdate> {
+    let (sender, receiver) = channel();
+
+    std::thread::Builder::new()
+        .name("search-writer".to_string())
+        .spawn(move || run_writer(search_writer, search_reader, receiver))
+        .expect("failed to spawn search writer thread");
+
+    sender
+}
+
+/// Reindexes the package from its index file
+pub fn update_package(app_state: &AppState, name: &PackageName, file: IndexFile) {
+    if app_state.search_updates.send((name.clone(), file)).is_err() {
+        log::error!("search writer has stopped, {name} will not be reindexed");
+    }
 }
@@ -1,10 +1,17 @@
-use crate::{error::Error, storage::StorageImpl};
+use crate::{
+    error::Error,
+    storage::{parse_readme_path, StorageImpl, METRICS_FILE},
+};
 use actix_web::{
     http::header::{CONTENT_ENCODING, CONTENT_TYPE},
     HttpResponse,
 };
 use pesde::{names::PackageName, source::version_id::VersionId};
-use std::{fmt::Display, fs::create_dir_all, path::PathBuf};
+use std::{
+    fmt::Display,
+    fs::create_dir_all,
+    path::{Path, PathBuf},
+};
 
 #[derive(Debug)]
 pub struct FSStorage {
@@ -117,6 +124,127 @@ impl StorageImpl for FSStorage {
             .append_header((CONTENT_ENCODING, "gzip"))
             .body(contents))
     }
+
+    async fn list_readmes(&self) -> Result<Vec<(PackageName, VersionId)>, Error> {
+        let mut readmes = vec![];
+
+        for scope in read_dirs(&self.root)? {
+            for name in read_dirs(&scope)? {
+                for version in read_dirs(&name)? {
+                    for target in read_dirs(&version)? {
+                        if !target.join("readme.gz").is_file() {
+                            continue;
+                        }
+
+                        let [scope, name, version, target] = [&scope, &name, &version, &target]
+                            .map(|path| {
+                                path.file_name()
+                                    .unwrap_or_default()
+                                    .to_string_lossy()
+                                    .to_string()
+                            });
+
+                        readmes.extend(parse_readme_path(&scope, &name, &version, &target));
+                    }
+                }
+            }
+        }
+
+        Ok(readmes)
+    }
+
+    async fn delete_readme(
+        &self,
+        package_name: &PackageName,
+        version: &VersionId,
+    ) -> Result<(), Error> {
+        let (scope, name) = package_name.as_str();
+
+        let path = self
+            .root
+            .join(scope)
+            .join(name)
+            .join(version.version().to_string())
+            .join(version.target().to_string());
+
+        match std::fs::remove_file(path.join("readme.gz")) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list_docs(&self) -> Result<Vec<String>, Error> {
+        let entries = match std::fs::read_dir(self.root.join("docs")) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut docs = vec![];
+
+        for entry in entries {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+
+            if let Some(hash) = entry.file_name().to_string_lossy().strip_suffix(".gz") {
+                docs.push(hash.to_string());
+            }
+        }
+
+        Ok(docs)
+    }
+
+    async fn delete_doc(&self, doc_hash: &str) -> Result<(), Error> {
+        let path = self.root.join("docs");
+
+        match std::fs::remove_file(path.join(format!("{doc_hash}.gz"))) {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn store_metrics(&self, contents: Vec<u8>) -> Result<(), Error> {
+        create_dir_all(&self.root)?;
+
+        // written to a temporary file first, so a crash never leaves the metrics half written
+        let path = self.root.join(METRICS_FILE);
+        let temp_path = path.with_extension("json.tmp");
+        std::fs::write(&temp_path, &contents)?;
+        std::fs::rename(temp_path, path)?;
+
+        Ok(())
+    }
+
+    async fn get_metrics(&self) -> Result<Option<Vec<u8>>, Error> {
+        match std::fs::read(self.root.join(METRICS_FILE)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// The directories within the directory, which are none if it doesn't exist
+fn read_dirs(path: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let entries = match std::fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+
+    let mut dirs = vec![];
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+
+    Ok(dirs)
 }
 
 impl Display for FSStorage {
@@ -1,15 +1,18 @@
-use crate::{benv, error::Error, make_reqwest};
+use crate::{error::Error, make_reqwest, tenant::Tenant};
 use actix_web::HttpResponse;
 use pesde::{names::PackageName, source::version_id::VersionId};
 use rusty_s3::{Bucket, Credentials, UrlStyle};
 use std::fmt::Display;
 
-mod fs;
+pub(crate) mod fs;
 mod s3;
 
+/// The name of the file the download metrics are stored in, at the root of the storage
+const METRICS_FILE: &str = "metrics.json";
+
 #[derive(Debug)]
 pub enum Storage {
-    S3(s3::S3Storage),
+    S3(Box<s3::S3Storage>),
     FS(fs::FSStorage),
 }
 
@@ -44,6 +47,22 @@ pub trait StorageImpl: Display {
         contents: Vec<u8>,
     ) -> Result<(), crate::error::Error>;
     async fn get_doc(&self, doc_hash: &str) -> Result<HttpResponse, crate::error::Error>;
+
+    /// The versions which have a readme stored
+    async fn list_readmes(&self) -> Result<Vec<(PackageName, VersionId)>, crate::error::Error>;
+    async fn delete_readme(
+        &self,
+        package_name: &PackageName,
+        version: &VersionId,
+    ) -> Result<(), crate::error::Error>;
+
+    /// The hashes of the stored doc pages
+    async fn list_docs(&self) -> Result<Vec<String>, crate::error::Error>;
+    async fn delete_doc(&self, doc_hash: &str) -> Result<(), crate::error::Error>;
+
+    async fn store_metrics(&self, contents: Vec<u8>) -> Result<(), crate::error::Error>;
+    /// The stored metrics, if they have been stored before
+    async fn get_metrics(&self) -> Result<Option<Vec<u8>>, crate::error::Error>;
 }
 
 impl StorageImpl for Storage {
@@ -106,6 +125,80 @@ impl StorageImpl for Storage {
             Storage::FS(fs) => fs.get_doc(doc_hash).await,
         }
     }
+
+    async fn list_readmes(&self) -> Result<Vec<(PackageName, VersionId)>, Error> {
+        match self {
+            Storage::S3(s3) => s3.list_readmes().await,
+            Storage::FS(fs) => fs.list_readmes().await,
+        }
+    }
+
+    async fn delete_readme(
+        &self,
+        package_name: &PackageName,
+        version: &VersionId,
+    ) -> Result<(), Error> {
+        match self {
+            Storage::S3(s3) => s3.delete_readme(package_name, version).await,
+            Storage::FS(fs) => fs.delete_readme(package_name, version).await,
+        }
+    }
+
+    async fn list_docs(&self) -> Result<Vec<String>, Error> {
+        match self {
+            Storage::S3(s3) => s3.list_docs().await,
+            Storage::FS(fs) => fs.list_docs().await,
+        }
+    }
+
+    async fn delete_doc(&self, doc_hash: &str) -> Result<(), Error> {
+        match self {
+            Storage::S3(s3) => s3.delete_doc(doc_hash).await,
+            Storage::FS(fs) => fs.delete_doc(doc_hash).await,
+        }
+    }
+
+    async fn store_metrics(&self, contents: Vec<u8>) -> Result<(), Error> {
+        match self {
+            Storage::S3(s3) => s3.store_metrics(contents).await,
+            Storage::FS(fs) => fs.store_metrics(contents).await,
+        }
+    }
+
+    async fn get_metrics(&self) -> Result<Option<Vec<u8>>, Error> {
+        match self {
+            Storage::S3(s3) => s3.get_metrics().await,
+            Storage::FS(fs) => fs.get_metrics().await,
+        }
+    }
+}
+
+/// Parses the package and version of a readme stored at `{scope}/{name}/{version}/{target}`
+fn parse_readme_path(
+    scope: &str,
+    name: &str,
+    version: &str,
+    target: &str,
+) -> Option<(PackageName, VersionId)> {
+    Some((
+        format!("{scope}/{name}").parse().ok()?,
+        VersionId::new(version.parse().ok()?, target.parse().ok()?),
+    ))
+}
+
+impl Storage {
+    /// Where the files of the storage are, which the locations of all of them start with
+    pub fn location(&self) -> String {
+        match self {
+            Storage::S3(s3) => format!("{}{}", s3.s3_bucket.base_url(), s3.prefix),
+            Storage::FS(fs) => format!(
+                "{}/",
+                std::path::absolute(&fs.root)
+                    .unwrap_or_else(|_| fs.root.clone())
+                    .display()
+            ),
+        }
+    }
 }
 
 impl Display for Storage {
@@ -117,25 +210,50 @@ impl Display for Storage {
     }
 }
 
-pub fn get_storage_from_env() -> Storage {
-    if let Ok(endpoint) = benv!(parse "S3_ENDPOINT") {
-        Storage::S3(s3::S3Storage {
+pub fn get_storage_from_env(tenant: &Tenant) -> Storage {
+    if let Some(endpoint) = tenant.parse("S3_ENDPOINT") {
+        Storage::S3(Box::new(s3::S3Storage {
             s3_bucket: Bucket::new(
                 endpoint,
                 UrlStyle::Path,
-                benv!(required "S3_BUCKET_NAME"),
-                benv!(required "S3_REGION"),
+                tenant.required("S3_BUCKET_NAME"),
+                tenant.required("S3_REGION"),
             )
             .unwrap(),
             s3_credentials: Credentials::new(
-                benv!(required "S3_ACCESS_KEY"),
-                benv!(required "S3_SECRET_KEY"),
+                tenant.required("S3_ACCESS_KEY"),
+                tenant.required("S3_SECRET_KEY"),
             ),
             reqwest_client: make_reqwest(),
+            prefix: tenant
+                .storage_prefix()
+                .map(|prefix| format!("{prefix}/"))
+                .unwrap_or_default(),
+        }))
+    } else if let Some(root) = tenant.parse::<std::path::PathBuf>("FS_STORAGE_ROOT") {
+        Storage::FS(fs::FSStorage {
+            root: match tenant.storage_prefix() {
+                Some(prefix) => root.join(prefix),
+                None => root,
+            },
         })
-    } else if let Ok(root) = benv!(parse "FS_STORAGE_ROOT") {
-        Storage::FS(fs::FSStorage { root })
     } else {
         panic!("no storage backend configured")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_readme_paths() {
+        let (name, version_id) = parse_readme_path("acme", "package", "1.2.3", "roblox").unwrap();
+        assert_eq!(name.to_string(), "acme/package");
+        assert_eq!(version_id.to_string(), "1.2.3 roblox");
+
+        assert!(parse_readme_path("acme", "package", "latest", "roblox").is_none());
+        assert!(parse_readme_path("acme", "package", "1.2.3", "unknown").is_none());
+        assert!(parse_readme_path("Acme!", "package", "1.2.3", "roblox").is_none());
+    }
+}
@@ -1,9 +1,15 @@
-use crate::{error::Error, storage::StorageImpl};
+use crate::{
+    error::Error,
+    storage::{parse_readme_path, StorageImpl, METRICS_FILE},
+};
 use actix_web::{http::header::LOCATION, HttpResponse};
 use pesde::{names::PackageName, source::version_id::VersionId};
-use reqwest::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{
+    header::{CONTENT_ENCODING, CONTENT_TYPE},
+    StatusCode,
+};
 use rusty_s3::{
-    actions::{GetObject, PutObject},
+    actions::{DeleteObject, GetObject, ListObjectsV2, PutObject},
     Bucket, Credentials, S3Action,
 };
 use std::{fmt::Display, time::Duration};
@@ -13,6 +19,8 @@ pub struct S3Storage {
     pub s3_bucket: Bucket,
     pub s3_credentials: Credentials,
     pub reqwest_client: reqwest::Client,
+    /// The prefix of every key, keeping tenants sharing a bucket apart
+    pub prefix: String,
 }
 
 pub const S3_SIGN_DURATION: Duration = Duration::from_secs(60 * 15);
@@ -27,11 +35,11 @@ impl StorageImpl for S3Storage {
         let object_url = PutObject::new(
             &self.s3_bucket,
             Some(&self.s3_credentials),
-            &format!(
+            &self.key(format!(
                 "{package_name}/{}/{}/pkg.tar.gz",
                 version.version(),
                 version.target()
-            ),
+            )),
         )
         .sign(S3_SIGN_DURATION);
 
@@ -55,11 +63,11 @@ impl StorageImpl for S3Storage {
         let object_url = GetObject::new(
             &self.s3_bucket,
             Some(&self.s3_credentials),
-            &format!(
+            &self.key(format!(
                 "{package_name}/{}/{}/pkg.tar.gz",
                 version.version(),
                 version.target()
-            ),
+            )),
         )
         .sign(S3_SIGN_DURATION);
 
@@ -77,11 +85,11 @@ impl StorageImpl for S3Storage {
         let object_url = PutObject::new(
             &self.s3_bucket,
             Some(&self.s3_credentials),
-            &format!(
+            &self.key(format!(
                 "{package_name}/{}/{}/readme.gz",
                 version.version(),
                 version.target()
-            ),
+            )),
         )
         .sign(S3_SIGN_DURATION);
 
@@ -105,11 +113,11 @@ impl StorageImpl for S3Storage {
         let object_url = GetObject::new(
             &self.s3_bucket,
             Some(&self.s3_credentials),
-            &format!(
+            &self.key(format!(
                 "{package_name}/{}/{}/readme.gz",
                 version.version(),
                 version.target()
-            ),
+            )),
         )
         .sign(S3_SIGN_DURATION);
 
@@ -122,7 +130,7 @@ impl StorageImpl for S3Storage {
         let object_url = PutObject::new(
             &self.s3_bucket,
             Some(&self.s3_credentials),
-            &format!("doc/{}.gz", doc_hash),
+            &self.key(format!("doc/{}.gz", doc_hash)),
         )
         .sign(S3_SIGN_DURATION);
 
@@ -142,7 +150,7 @@ impl StorageImpl for S3Storage {
         let object_url = GetObject::new(
             &self.s3_bucket,
             Some(&self.s3_credentials),
-            &format!("doc/{}.gz", doc_hash),
+            &self.key(format!("doc/{}.gz", doc_hash)),
         )
         .sign(S3_SIGN_DURATION);
 
@@ -150,6 +158,154 @@ impl StorageImpl for S3Storage {
             .append_header((LOCATION, object_url.as_str()))
             .finish())
     }
+
+    async fn list_readmes(&self) -> Result<Vec<(PackageName, VersionId)>, Error> {
+        Ok(self
+            .list_keys(None)
+            .await?
+            .into_iter()
+            .filter_map(|key| {
+                let parts = key.split('/').collect::<Vec<_>>();
+                match parts[..] {
+                    [scope, name, version, target, "readme.gz"] => {
+                        parse_readme_path(scope, name, version, target)
+                    }
+                    _ => None,
+                }
+            })
+            .collect())
+    }
+
+    async fn delete_readme(
+        &self,
+        package_name: &PackageName,
+        version: &VersionId,
+    ) -> Result<(), Error> {
+        self.delete_object(&self.key(format!(
+            "{package_name}/{}/{}/readme.gz",
+            version.version(),
+            version.target()
+        )))
+        .await
+    }
+
+    async fn list_docs(&self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .list_keys(Some("doc/"))
+            .await?
+            .into_iter()
+            .filter_map(|key| {
+                key.strip_prefix("doc/")
+                    .and_then(|key| key.strip_suffix(".gz"))
+                    // packages in a scope named `doc` have the same prefix
+                    .filter(|hash| !hash.contains('/'))
+                    .map(ToString::to_string)
+            })
+            .collect())
+    }
+
+    async fn delete_doc(&self, doc_hash: &str) -> Result<(), Error> {
+        self.delete_object(&self.key(format!("doc/{}.gz", doc_hash)))
+            .await
+    }
+
+    async fn store_metrics(&self, contents: Vec<u8>) -> Result<(), Error> {
+        let object_url = PutObject::new(
+            &self.s3_bucket,
+            Some(&self.s3_credentials),
+            &self.key(METRICS_FILE.to_string()),
+        )
+        .sign(S3_SIGN_DURATION);
+
+        self.reqwest_client
+            .put(object_url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(contents)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
+
+    async fn get_metrics(&self) -> Result<Option<Vec<u8>>, Error> {
+        let object_url = GetObject::new(
+            &self.s3_bucket,
+            Some(&self.s3_credentials),
+            &self.key(METRICS_FILE.to_string()),
+        )
+        .sign(S3_SIGN_DURATION);
+
+        let response = self.reqwest_client.get(object_url).send().await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        Ok(Some(response.error_for_status()?.bytes().await?.to_vec()))
+    }
+}
+
+impl S3Storage {
+    fn key(&self, key: String) -> String {
+        format!("{}{key}", self.prefix)
+    }
+
+    /// Lists the keys of the tenant's objects in the bucket with the prefix, following
+    /// continuations. The keys are returned without the tenant's prefix
+    async fn list_keys(&self, prefix: Option<&str>) -> Result<Vec<String>, Error> {
+        let mut keys = vec![];
+        let mut continuation_token = None::<String>;
+        let prefix = self.key(prefix.unwrap_or_default().to_string());
+
+        loop {
+            let mut action = ListObjectsV2::new(&self.s3_bucket, Some(&self.s3_credentials));
+            if !prefix.is_empty() {
+                action.with_prefix(prefix.as_str());
+            }
+            if let Some(token) = &continuation_token {
+                action.with_continuation_token(token.as_str());
+            }
+
+            let text = self
+                .reqwest_client
+                .get(action.sign(S3_SIGN_DURATION))
+                .send()
+                .await?
+                .error_for_status()?
+                .text()
+                .await?;
+            let response = ListObjectsV2::parse_response(&text)
+                .map_err(|e| Error::StorageListing(Box::new(e)))?;
+
+            // keys are URL encoded, as requested by the action
+            keys.extend(response.contents.into_iter().filter_map(|content| {
+                percent_encoding::percent_decode_str(&content.key)
+                    .decode_utf8_lossy()
+                    .strip_prefix(&self.prefix)
+                    .map(ToString::to_string)
+            }));
+
+            match response.next_continuation_token {
+                Some(token) => continuation_token = Some(token),
+                None => break,
+            }
+        }
+
+        Ok(keys)
+    }
+
+    async fn delete_object(&self, key: &str) -> Result<(), Error> {
+        let object_url = DeleteObject::new(&self.s3_bucket, Some(&self.s3_credentials), key)
+            .sign(S3_SIGN_DURATION);
+
+        self.reqwest_client
+            .delete(object_url)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(())
+    }
 }
 
 impl Display for S3Storage {
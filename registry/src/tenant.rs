@@ -0,0 +1,229 @@
+use crate::benv;
+use std::{collections::HashSet, env::VarError, fmt::Display, str::FromStr};
+
+/// An index served by the registry, with its own repository, storage and authentication
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    /// The name of the tenant, `None` for the single tenant of a registry without `TENANTS`
+    pub name: Option<String>,
+}
+
+/// How requests are routed to a tenant
+#[derive(Debug, Clone)]
+pub enum Route {
+    /// Requests to this hostname
+    Host(String),
+    /// Requests whose path starts with this prefix
+    PathPrefix(String),
+    /// Every request
+    All,
+}
+
+/// The variables which keep tenants apart, such as their index, credentials and storage. These
+/// are never shared, so a tenant missing one can't end up using another tenant's
+const ISOLATED_VARS: &[&str] = &[
+    "INDEX_REPO_URL",
+    "GITHUB_USERNAME",
+    "GITHUB_PAT",
+    "SIGNING_KEY",
+    "ACCESS_TOKEN",
+    "READ_ACCESS_TOKEN",
+    "WRITE_ACCESS_TOKEN",
+    "GITHUB_CLIENT_SECRET",
+    "S3_ENDPOINT",
+    "S3_BUCKET_NAME",
+    "S3_REGION",
+    "S3_ACCESS_KEY",
+    "S3_SECRET_KEY",
+    "FS_STORAGE_ROOT",
+];
+
+impl Tenant {
+    /// Reads the tenant's variable, which is the variable prefixed with the tenant's uppercased
+    /// name (e.g. `INTERNAL_S3_ENDPOINT`). Settings fall back to the unprefixed variable so they
+    /// can be shared between tenants, but the variables which keep tenants apart must be prefixed,
+    /// panicking if they are set unprefixed
+    pub fn var(&self, name: &str) -> Result<String, VarError> {
+        let var = benv!(self.key(name));
+        if self.name.is_none() {
+            return var;
+        }
+
+        if ISOLATED_VARS.contains(&name) {
+            if benv!(name).is_ok() {
+                panic!(
+                    "Environment variable `{name}` can't be shared between tenants, set `{}` instead",
+                    self.key(name)
+                );
+            }
+
+            return var;
+        }
+
+        var.or_else(|_| benv!(name))
+    }
+
+    /// Reads the tenant's variable, panicking if it isn't set
+    pub fn required(&self, name: &str) -> String {
+        self.var(name)
+            .unwrap_or_else(|_| panic!("Environment variable `{}` must be set", self.key(name)))
+    }
+
+    /// Reads and parses the tenant's variable, panicking if it isn't a valid value
+    pub fn parse<T: FromStr>(&self, name: &str) -> Option<T> {
+        self.var(name).ok().map(|value| {
+            value.parse().unwrap_or_else(|_| {
+                panic!(
+                    "Environment variable `{}` must be a valid value",
+                    self.key(name)
+                )
+            })
+        })
+    }
+
+    /// The prefix of the tenant's keys in storage, keeping them apart from other tenants' even if
+    /// they use the same bucket or root
+    pub fn storage_prefix(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn key(&self, name: &str) -> String {
+        match &self.name {
+            Some(tenant) => format!("{}_{name}", tenant.to_uppercase().replace('-', "_")),
+            None => name.to_string(),
+        }
+    }
+
+    /// Where requests to this tenant are routed, `{NAME}_HOST`, `{NAME}_PATH_PREFIX`, or
+    /// `/{name}` by default. These are never shared between tenants
+    pub fn route(&self) -> Route {
+        let Some(tenant) = &self.name else {
+            return Route::All;
+        };
+
+        if let Ok(host) = benv!(self.key("HOST")) {
+            Route::Host(host)
+        } else if let Ok(prefix) = benv!(self.key("PATH_PREFIX")) {
+            Route::PathPrefix(format!("/{}", prefix.trim_matches('/')))
+        } else {
+            Route::PathPrefix(format!("/{tenant}"))
+        }
+    }
+}
+
+impl Display for Tenant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "{name}"),
+            None => write!(f, "default"),
+        }
+    }
+}
+
+impl Display for Route {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Route::Host(host) => write!(f, "host {host}"),
+            Route::PathPrefix(prefix) => write!(f, "path {prefix}"),
+            Route::All => write!(f, "all requests"),
+        }
+    }
+}
+
+/// The tenants listed in `TENANTS`, separated by commas, or a single unnamed tenant reading the
+/// unprefixed variables
+pub fn get_tenants_from_env() -> Vec<Tenant> {
+    match benv!("TENANTS") {
+        Ok(tenants) => {
+            let tenants = tenants
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    if !name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                    {
+                        panic!(
+                            "tenant name `{name}` must only contain letters, digits, `-` and `_`"
+                        );
+                    }
+
+                    Tenant {
+                        name: Some(name.to_string()),
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            if tenants.is_empty() {
+                panic!("Environment variable `TENANTS` must list at least one tenant");
+            }
+
+            let unique = tenants
+                .iter()
+                .map(|tenant| tenant.key(""))
+                .collect::<HashSet<_>>();
+            if unique.len() != tenants.len() {
+                panic!("Environment variable `TENANTS` must not list a tenant twice");
+            }
+
+            tenants
+        }
+        Err(_) => vec![Tenant { name: None }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tenant(name: &str) -> Tenant {
+        Tenant {
+            name: Some(name.to_string()),
+        }
+    }
+
+    #[test]
+    fn settings_fall_back_to_unprefixed() {
+        let tenant = tenant("fallback");
+
+        std::env::set_var("TENANT_TEST_SETTING", "shared");
+        assert_eq!(tenant.var("TENANT_TEST_SETTING").unwrap(), "shared");
+
+        std::env::set_var("FALLBACK_TENANT_TEST_SETTING", "own");
+        assert_eq!(tenant.var("TENANT_TEST_SETTING").unwrap(), "own");
+    }
+
+    #[test]
+    fn isolated_vars_are_never_shared() {
+        let tenant = tenant("isolated-tenant");
+
+        std::env::set_var(
+            "ISOLATED_TENANT_INDEX_REPO_URL",
+            "https://example.com/index",
+        );
+        assert_eq!(
+            tenant.var("INDEX_REPO_URL").unwrap(),
+            "https://example.com/index"
+        );
+
+        std::env::set_var("S3_BUCKET_NAME", "shared");
+        assert!(std::panic::catch_unwind(|| tenant.var("S3_BUCKET_NAME")).is_err());
+        assert_eq!(
+            Tenant { name: None }.var("S3_BUCKET_NAME").unwrap(),
+            "shared"
+        );
+        std::env::remove_var("S3_BUCKET_NAME");
+
+        assert!(tenant.var("S3_BUCKET_NAME").is_err());
+    }
+
+    #[test]
+    fn keys_are_prefixed_with_uppercased_name() {
+        assert_eq!(tenant("my-index").key("S3_REGION"), "MY_INDEX_S3_REGION");
+        assert_eq!(Tenant { name: None }.key("S3_REGION"), "S3_REGION");
+
+        assert_eq!(tenant("my-index").storage_prefix(), Some("my-index"));
+        assert_eq!(Tenant { name: None }.storage_prefix(), None);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::{
+    lockfile::DependencyGraph,
+    names::PackageNames,
+    source::{git_index::GitBasedSource, version_id::VersionId},
+    util::hash,
+    Project,
+};
+use semver::VersionReq;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Formatter},
+    path::PathBuf,
+};
+
+/// The advisories of a package, keyed by their ID
+pub type AdvisoryFile = BTreeMap<String, Advisory>;
+
+/// A security advisory affecting some versions of a package
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Advisory {
+    /// A short summary of the vulnerability
+    pub title: String,
+    /// A longer description of the vulnerability and how to mitigate it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// How severe the vulnerability is
+    pub severity: Severity,
+    /// The affected versions
+    pub versions: VersionReq,
+    /// A URL with more information
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<url::Url>,
+}
+
+/// How severe a vulnerability is
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// A low severity vulnerability
+    Low,
+    /// A medium severity vulnerability
+    Medium,
+    /// A high severity vulnerability
+    High,
+    /// A critical severity vulnerability
+    Critical,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Low => write!(f, "low"),
+            Severity::Medium => write!(f, "medium"),
+            Severity::High => write!(f, "high"),
+            Severity::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+/// A package in a dependency graph affected by an advisory
+#[derive(Debug, Clone)]
+pub struct Finding {
+    /// The name of the affected package
+    pub name: PackageNames,
+    /// The version of the affected package
+    pub version_id: VersionId,
+    /// The ID of the advisory
+    pub id: String,
+    /// The advisory
+    pub advisory: Advisory,
+}
+
+/// A database of security advisories, stored in a Git repository like an index. The advisories
+/// of pesde packages are in `{scope}/{name}`, and those of Wally packages in
+/// `wally/{scope}/{name}`, each a TOML table of advisories keyed by their ID
+#[derive(Debug, Hash, PartialEq, Eq, Clone, PartialOrd, Ord)]
+pub struct AdvisoryDatabase {
+    repo_url: gix::Url,
+}
+
+impl GitBasedSource for AdvisoryDatabase {
+    fn path(&self, project: &Project) -> PathBuf {
+        project
+            .data_dir
+            .join("advisories")
+            .join(hash(self.repo_url.to_bstring()))
+    }
+
+    fn repo_url(&self) -> &gix::Url {
+        &self.repo_url
+    }
+}
+
+impl AdvisoryDatabase {
+    /// Creates a new advisory database
+    pub fn new(repo_url: gix::Url) -> Self {
+        Self { repo_url }
+    }
+
+    /// Reads the advisories of a package, which is empty if it has none
+    pub fn advisories(
+        &self,
+        name: &PackageNames,
+        project: &Project,
+    ) -> Result<AdvisoryFile, errors::AdvisoriesError> {
+        let (scope, name_part) = name.as_str();
+        let path = match name {
+            PackageNames::Pesde(_) => vec![scope, name_part],
+            #[cfg(feature = "wally-compat")]
+            PackageNames::Wally(_) => vec!["wally", scope, name_part],
+        };
+
+        let Some(string) = self
+            .read_file(path, project, None)
+            .map_err(|e| errors::AdvisoriesError::Read(name.to_string(), Box::new(e)))?
+        else {
+            return Ok(AdvisoryFile::new());
+        };
+
+        toml::from_str(&string).map_err(|e| errors::AdvisoriesError::Parse(name.to_string(), e))
+    }
+
+    /// Checks the packages of the dependency graph against the advisories, returning the ones
+    /// affecting them
+    pub fn check(
+        &self,
+        graph: &DependencyGraph,
+        project: &Project,
+    ) -> Result<Vec<Finding>, errors::AdvisoriesError> {
+        let mut findings = vec![];
+
+        for (name, versions) in graph {
+            let advisories = self.advisories(name, project)?;
+            if advisories.is_empty() {
+                continue;
+            }
+
+            for version_id in versions.keys() {
+                findings.extend(
+                    advisories
+                        .iter()
+                        .filter(|(_, advisory)| advisory.versions.matches(version_id.version()))
+                        .map(|(id, advisory)| Finding {
+                            name: name.clone(),
+                            version_id: version_id.clone(),
+                            id: id.clone(),
+                            advisory: advisory.clone(),
+                        }),
+                );
+            }
+        }
+
+        Ok(findings)
+    }
+}
+
+/// Errors that can occur when auditing packages
+pub mod errors {
+    use thiserror::Error;
+
+    /// Errors that can occur when reading the advisories of a package
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum AdvisoriesError {
+        /// Error reading the advisory file
+        #[error("error reading advisories of {0}")]
+        Read(
+            String,
+            #[source] Box<crate::source::git_index::errors::ReadFile>,
+        ),
+
+        /// Error parsing the advisory file
+        #[error("error parsing advisories of {0}")]
+        Parse(String, #[source] toml::de::Error),
+    }
+}
@@ -0,0 +1,27 @@
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// A token which can be used to cancel the operations of a project, such as resolving,
+/// downloading, and linking. Operations stop between packages, so the CAS is never left with
+/// partially written files. Clones share their state
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new, uncancelled `CancellationToken`
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancels the operations using this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the operations using this token were cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::cli::config::{read_config, write_config};
+use crate::cli::{
+    config::{read_config, write_config, CredentialStoreKind},
+    is_isolated,
+};
 use anyhow::Context;
 use gix::bstr::BStr;
 use keyring::Entry;
@@ -37,48 +40,197 @@ impl<'de> Deserialize<'de> for Tokens {
     }
 }
 
-pub fn get_tokens() -> anyhow::Result<Tokens> {
-    let config = read_config()?;
-    if !config.tokens.0.is_empty() {
-        return Ok(config.tokens);
+/// A place tokens can be stored in
+trait CredentialStore {
+    /// Returns all tokens in the store
+    fn all(&self) -> anyhow::Result<Tokens>;
+
+    /// Sets or removes the token for an index. Returns `false` if the store is unavailable
+    fn set(&self, index: &gix::Url, token: Option<&str>) -> anyhow::Result<bool>;
+}
+
+/// Stores tokens in plain text in the config file
+struct FileStore;
+
+impl CredentialStore for FileStore {
+    fn all(&self) -> anyhow::Result<Tokens> {
+        Ok(read_config()?.tokens)
+    }
+
+    fn set(&self, index: &gix::Url, token: Option<&str>) -> anyhow::Result<bool> {
+        let mut config = read_config()?;
+
+        let changed = match token {
+            Some(token) => {
+                config.tokens.0.insert(index.clone(), token.to_string()) != Some(token.to_string())
+            }
+            None => config.tokens.0.remove(index).is_some(),
+        };
+
+        if changed {
+            write_config(&config)?;
+        }
+
+        Ok(true)
+    }
+}
+
+/// Stores each index's token in its own entry of the system keyring
+struct KeyringStore;
+
+fn is_unavailable(e: &keyring::Error) -> bool {
+    matches!(
+        e,
+        keyring::Error::PlatformFailure(_) | keyring::Error::NoStorageAccess(_)
+    )
+}
+
+impl KeyringStore {
+    fn entry(index: &str) -> keyring::Result<Entry> {
+        Entry::new(env!("CARGO_PKG_NAME"), index)
     }
 
-    match Entry::new("tokens", env!("CARGO_PKG_NAME")) {
-        Ok(entry) => match entry.get_password() {
-            Ok(token) => return serde_json::from_str(&token).context("failed to parse tokens"),
-            Err(keyring::Error::PlatformFailure(_) | keyring::Error::NoEntry) => {}
+    /// Moves the tokens of older versions, which were stored together in a single entry, or in the config file
+    fn migrate(&self) -> anyhow::Result<()> {
+        let legacy = match Entry::new("tokens", env!("CARGO_PKG_NAME")) {
+            Ok(entry) => match entry.get_password() {
+                Ok(tokens) => Some((
+                    entry,
+                    serde_json::from_str::<Tokens>(&tokens).context("failed to parse tokens")?,
+                )),
+                Err(keyring::Error::NoEntry) => None,
+                Err(e) if is_unavailable(&e) => return Ok(()),
+                Err(e) => return Err(e.into()),
+            },
+            Err(e) if is_unavailable(&e) => return Ok(()),
             Err(e) => return Err(e.into()),
-        },
-        Err(keyring::Error::PlatformFailure(_)) => {}
-        Err(e) => return Err(e.into()),
+        };
+
+        if let Some((entry, tokens)) = legacy {
+            for (index, token) in &tokens.0 {
+                if !self.set(index, Some(token))? {
+                    return Ok(());
+                }
+            }
+
+            entry
+                .delete_credential()
+                .context("failed to delete legacy tokens entry")?;
+            log::debug!(
+                "migrated {} token(s) from the legacy keyring entry",
+                tokens.0.len()
+            );
+        }
+
+        let file_tokens = FileStore.all()?;
+        for (index, token) in &file_tokens.0 {
+            if !self.set(index, Some(token))? {
+                return Ok(());
+            }
+
+            FileStore.set(index, None)?;
+            log::debug!("migrated token for {index} from the config file to the keyring");
+        }
+
+        Ok(())
     }
+}
+
+impl CredentialStore for KeyringStore {
+    fn all(&self) -> anyhow::Result<Tokens> {
+        let mut tokens = BTreeMap::new();
+
+        for index in read_config()?.keyring_indices {
+            let url = gix::Url::from_bytes(BStr::new(&index))
+                .with_context(|| format!("invalid index url {index}"))?;
+
+            match Self::entry(&index).and_then(|entry| entry.get_password()) {
+                Ok(token) => {
+                    tokens.insert(url, token);
+                }
+                Err(keyring::Error::NoEntry) => {}
+                Err(e) if is_unavailable(&e) => {
+                    log::debug!("keyring unavailable, not reading token for {index}: {e}");
+                }
+                Err(e) => {
+                    return Err(e).with_context(|| format!("failed to get token for {index}"))
+                }
+            }
+        }
+
+        Ok(Tokens(tokens))
+    }
+
+    fn set(&self, index: &gix::Url, token: Option<&str>) -> anyhow::Result<bool> {
+        let key = index.to_bstring().to_string();
+
+        let result = Self::entry(&key).and_then(|entry| match token {
+            Some(token) => entry.set_password(token),
+            None => match entry.delete_credential() {
+                Err(keyring::Error::NoEntry) => Ok(()),
+                res => res,
+            },
+        });
+
+        match result {
+            Ok(()) => {}
+            Err(e) if is_unavailable(&e) => {
+                log::debug!("keyring unavailable: {e}");
+                return Ok(false);
+            }
+            Err(e) => return Err(e).with_context(|| format!("failed to set token for {index}")),
+        }
+
+        let mut config = read_config()?;
+        let changed = if token.is_some() {
+            config.keyring_indices.insert(key)
+        } else {
+            config.keyring_indices.remove(&key)
+        };
 
-    Ok(Tokens(BTreeMap::new()))
+        if changed {
+            write_config(&config)?;
+        }
+
+        Ok(true)
+    }
 }
 
-pub fn set_tokens(tokens: Tokens) -> anyhow::Result<()> {
-    let entry = Entry::new("tokens", env!("CARGO_PKG_NAME"))?;
-    let json = serde_json::to_string(&tokens).context("failed to serialize tokens")?;
+fn uses_keyring() -> anyhow::Result<bool> {
+    // the keyring is state outside of the isolated directory
+    Ok(!is_isolated() && read_config()?.credential_store == CredentialStoreKind::Keyring)
+}
 
-    match entry.set_password(&json) {
-        Ok(()) => return Ok(()),
-        Err(keyring::Error::PlatformFailure(_) | keyring::Error::NoEntry) => {}
-        Err(e) => return Err(e.into()),
+pub fn get_tokens() -> anyhow::Result<Tokens> {
+    if !uses_keyring()? {
+        return FileStore.all();
     }
 
-    let mut config = read_config()?;
-    config.tokens = tokens;
-    write_config(&config).map_err(Into::into)
+    KeyringStore.migrate()?;
+
+    // tokens are left in the config file if the keyring is unavailable
+    let mut tokens = FileStore.all()?;
+    tokens.0.extend(KeyringStore.all()?.0);
+
+    Ok(tokens)
 }
 
 pub fn set_token(repo: &gix::Url, token: Option<&str>) -> anyhow::Result<()> {
-    let mut tokens = get_tokens()?;
-    if let Some(token) = token {
-        tokens.0.insert(repo.clone(), token.to_string());
-    } else {
-        tokens.0.remove(repo);
+    if uses_keyring()? {
+        if KeyringStore.set(repo, token)? {
+            // make sure an older token doesn't linger in the config file
+            FileStore.set(repo, None)?;
+            return Ok(());
+        }
+
+        if token.is_some() {
+            log::warn!("system keyring is unavailable, storing token in the config file");
+        }
     }
-    set_tokens(tokens)
+
+    FileStore.set(repo, token)?;
+
+    Ok(())
 }
 
 #[derive(Debug, Deserialize)]
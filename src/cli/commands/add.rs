@@ -1,15 +1,21 @@
-use std::str::FromStr;
+use std::{fmt::Display, str::FromStr};
 
 use anyhow::Context;
 use clap::Args;
+use colored::Colorize;
 use semver::VersionReq;
 
-use crate::cli::{config::read_config, AnyPackageIdentifier, VersionedPackageName};
+use crate::cli::{
+    commands::search::search_index, config::read_config, messages::msg, AnyPackageIdentifier,
+    VersionedPackageName,
+};
 use pesde::{
-    manifest::target::TargetKind,
-    names::PackageNames,
+    manifest::{target::TargetKind, Manifest},
+    names::{PackageName, PackageNames},
+    registry_client::{SearchQuery, SearchResult},
     source::{
         git::{specifier::GitDependencySpecifier, GitPackageSource},
+        path::{specifier::PathDependencySpecifier, PathPackageSource},
         pesde::{specifier::PesdeDependencySpecifier, PesdePackageSource},
         specifiers::DependencySpecifiers,
         traits::PackageSource,
@@ -21,9 +27,9 @@ use pesde::{
 
 #[derive(Debug, Args)]
 pub struct AddCommand {
-    /// The package name to add
+    /// The package name to add, or a query to search the index for if it isn't one
     #[arg(index = 1)]
-    name: AnyPackageIdentifier<VersionReq>,
+    name: String,
 
     /// The index in which to search for the package
     #[arg(short, long)]
@@ -44,15 +50,126 @@ pub struct AddCommand {
     /// Whether to add the package as a dev dependency
     #[arg(short, long, conflicts_with = "peer")]
     dev: bool,
+
+    /// Whether the package may be missing, linking a stub in its place
+    #[arg(long)]
+    optional: bool,
+}
+
+/// A package found by searching the index, as shown in the picker
+struct Candidate {
+    result: SearchResult,
+    compatible: bool,
+}
+
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.result.name.bold(), self.result.version)?;
+
+        let targets = self
+            .result
+            .targets
+            .iter()
+            .map(|target| target.kind.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let targets = format!("[{targets}]");
+        if self.compatible {
+            write!(f, " {}", targets.green())?;
+        } else {
+            write!(f, " {}", targets.red())?;
+        }
+
+        if !self.result.description.is_empty() {
+            write!(f, " - {}", self.result.description.dimmed())?;
+        }
+
+        Ok(())
+    }
 }
 
 impl AddCommand {
-    pub fn run(self, project: Project) -> anyhow::Result<()> {
+    /// Searches the index for the query and lets the user pick one of the packages found
+    fn pick(
+        &self,
+        project: &Project,
+        manifest: &Manifest,
+        reqwest: &reqwest::blocking::Client,
+        parse_error: anyhow::Error,
+    ) -> anyhow::Result<Option<PackageName>> {
+        let index_url = match (
+            manifest
+                .indices
+                .get(self.index.as_deref().unwrap_or(DEFAULT_INDEX_NAME)),
+            &self.index,
+        ) {
+            (Some(index), _) => index.clone(),
+            (None, Some(index)) => anyhow::bail!("index {index} not found"),
+            (None, None) => read_config()?.default_index,
+        };
+
+        log::debug!("{} isn't a package name: {parse_error:?}", self.name);
+
+        let response = search_index(
+            project,
+            reqwest,
+            &index_url,
+            &SearchQuery::new(self.name.clone()),
+            None,
+        )
+        .with_context(|| format!("failed to search for {}", self.name))?;
+
+        if response.data.is_empty() {
+            return Err(parse_error.context(format!(
+                "{} isn't a package name, and no packages matching it were found",
+                self.name
+            )));
+        }
+
+        let target = self.target.unwrap_or(manifest.target.kind());
+        let candidates = response
+            .data
+            .into_iter()
+            .map(|result| Candidate {
+                compatible: result.targets.iter().any(|t| t.kind == target),
+                result,
+            })
+            .collect::<Vec<_>>();
+
+        let Some(candidate) = inquire::Select::new(
+            &format!("Which package matching {} should be added?", self.name),
+            candidates,
+        )
+        .prompt_skippable()?
+        else {
+            return Ok(None);
+        };
+
+        candidate
+            .result
+            .name
+            .parse()
+            .map(Some)
+            .context("index returned an invalid package name")
+    }
+
+    pub fn run(self, project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
         let manifest = project
             .deser_manifest()
             .context("failed to read manifest")?;
 
-        let (source, specifier) = match &self.name {
+        let name = match self.name.parse::<AnyPackageIdentifier<VersionReq>>() {
+            Ok(name) => name,
+            Err(e) => match self.pick(&project, &manifest, &reqwest, e)? {
+                Some(name) => AnyPackageIdentifier::PackageName(VersionedPackageName(
+                    PackageNames::Pesde(name),
+                    None,
+                )),
+                None => return Ok(()),
+            },
+        };
+
+        let (source, specifier) = match &name {
             AnyPackageIdentifier::PackageName(versioned) => match &versioned {
                 VersionedPackageName(PackageNames::Pesde(name), version) => {
                     let index = manifest
@@ -73,6 +190,7 @@ impl AddCommand {
                         version: version.clone().unwrap_or(VersionReq::STAR),
                         index: self.index,
                         target: self.target,
+                        optional: self.optional,
                     });
 
                     (source, specifier)
@@ -98,6 +216,7 @@ impl AddCommand {
                             name: name.clone(),
                             version: version.clone().unwrap_or(VersionReq::STAR),
                             index: self.index,
+                            optional: self.optional,
                         },
                     );
 
@@ -110,6 +229,7 @@ impl AddCommand {
                     repo: url.clone(),
                     rev: rev.to_string(),
                     path: None,
+                    optional: self.optional,
                 }),
             ),
             AnyPackageIdentifier::Workspace(VersionedPackageName(name, version)) => (
@@ -119,9 +239,17 @@ impl AddCommand {
                         name: name.clone(),
                         version: version.clone().unwrap_or_default(),
                         target: self.target,
+                        optional: self.optional,
                     },
                 ),
             ),
+            AnyPackageIdentifier::Path(path) => (
+                PackageSources::Path(PathPackageSource),
+                DependencySpecifiers::Path(PathDependencySpecifier {
+                    path: path.clone(),
+                    optional: self.optional,
+                }),
+            ),
         };
         source
             .refresh(&project)
@@ -152,16 +280,20 @@ impl AddCommand {
             "dependencies"
         };
 
-        let alias = self.alias.unwrap_or_else(|| match self.name.clone() {
+        let alias = self.alias.unwrap_or_else(|| match name.clone() {
             AnyPackageIdentifier::PackageName(versioned) => versioned.0.as_str().1.to_string(),
             AnyPackageIdentifier::Url((url, _)) => url
                 .path
                 .to_string()
                 .split('/')
-                .last()
+                .next_back()
                 .map(|s| s.to_string())
                 .unwrap_or(url.path.to_string()),
             AnyPackageIdentifier::Workspace(versioned) => versioned.0.as_str().1.to_string(),
+            AnyPackageIdentifier::Path(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or(path.to_string_lossy().to_string()),
         });
 
         let field = &mut manifest[dependency_key]
@@ -181,11 +313,14 @@ impl AddCommand {
                 }
 
                 println!(
-                    "added {}@{} {} to {}",
-                    spec.name,
-                    version_id.version(),
-                    version_id.target(),
-                    dependency_key
+                    "{}",
+                    msg!(
+                        "add.pesde",
+                        name = spec.name,
+                        version = version_id.version(),
+                        target = version_id.target(),
+                        key = dependency_key
+                    )
                 );
             }
             #[cfg(feature = "wally-compat")]
@@ -198,31 +333,64 @@ impl AddCommand {
                 }
 
                 println!(
-                    "added wally {}@{} to {}",
-                    spec.name,
-                    version_id.version(),
-                    dependency_key
+                    "{}",
+                    msg!(
+                        "add.wally",
+                        name = spec.name,
+                        version = version_id.version(),
+                        key = dependency_key
+                    )
                 );
             }
             DependencySpecifiers::Git(spec) => {
                 field["repo"] = toml_edit::value(spec.repo.to_bstring().to_string());
                 field["rev"] = toml_edit::value(spec.rev.clone());
 
-                println!("added git {}#{} to {}", spec.repo, spec.rev, dependency_key);
+                println!(
+                    "{}",
+                    msg!(
+                        "add.git",
+                        repo = spec.repo,
+                        rev = spec.rev,
+                        key = dependency_key
+                    )
+                );
             }
             DependencySpecifiers::Workspace(spec) => {
                 field["workspace"] = toml_edit::value(spec.name.clone().to_string());
-                if let AnyPackageIdentifier::Workspace(versioned) = self.name {
+                if let AnyPackageIdentifier::Workspace(versioned) = name {
                     if let Some(version) = versioned.1 {
                         field["version"] = toml_edit::value(version.to_string());
                     }
                 }
 
                 println!(
-                    "added workspace {}@{} to {}",
-                    spec.name, spec.version, dependency_key
+                    "{}",
+                    msg!(
+                        "add.workspace",
+                        name = spec.name,
+                        version = spec.version,
+                        key = dependency_key
+                    )
                 );
             }
+            DependencySpecifiers::Path(spec) => {
+                field["path"] = toml_edit::value(spec.path.to_string_lossy().to_string());
+
+                println!(
+                    "{}",
+                    msg!(
+                        "add.path",
+                        path = spec.path.display(),
+                        version = version_id.version(),
+                        key = dependency_key
+                    )
+                );
+            }
+        }
+
+        if self.optional {
+            field["optional"] = toml_edit::value(true);
         }
 
         project
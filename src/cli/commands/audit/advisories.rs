@@ -0,0 +1,63 @@
+use crate::cli::{find_advisories, messages::msg, print_advisories, up_to_date_lockfile};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    source::{
+        refs::PackageRefs,
+        traits::{PackageRef, PackageSource},
+    },
+    Project,
+};
+use std::collections::HashSet;
+
+#[derive(Debug, Args)]
+pub struct AdvisoriesCommand {}
+
+impl AdvisoriesCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let graph = if let Some(lockfile) = up_to_date_lockfile(&project)? {
+            lockfile.graph
+        } else {
+            anyhow::bail!("outdated lockfile, please run the install command first")
+        };
+
+        // the advisory databases are found in the configs of the indices
+        let mut refreshed_sources = HashSet::new();
+        for node in graph.values().flat_map(|versions| versions.values()) {
+            if !matches!(node.node.pkg_ref, PackageRefs::Pesde(_)) {
+                continue;
+            }
+
+            let source = node.node.pkg_ref.source();
+            if refreshed_sources.insert(source.clone()) {
+                source
+                    .refresh(&project)
+                    .context("failed to refresh source")?;
+            }
+        }
+
+        let graph = graph
+            .into_iter()
+            .map(|(name, versions)| {
+                (
+                    name,
+                    versions
+                        .into_iter()
+                        .map(|(version_id, node)| (version_id, node.node))
+                        .collect(),
+                )
+            })
+            .collect();
+
+        let mut findings = find_advisories(&project, &graph)?;
+        if findings.is_empty() {
+            println!("{}", msg!("audit.no_advisories").green());
+            return Ok(());
+        }
+
+        print_advisories(&mut findings);
+
+        anyhow::bail!("{}", msg!("audit.advisories", count = findings.len()));
+    }
+}
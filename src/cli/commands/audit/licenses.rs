@@ -0,0 +1,390 @@
+use crate::cli::{messages::msg, up_to_date_lockfile};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    manifest::migration::parse_manifest,
+    source::{
+        pesde::PesdePackageSource,
+        refs::PackageRefs,
+        traits::{PackageRef, PackageSource},
+    },
+    Project, MANIFEST_FILE_NAME, PACKAGES_CONTAINER_NAME,
+};
+use std::{
+    collections::{BTreeSet, HashSet},
+    path::Path,
+};
+
+#[derive(Debug, Args)]
+pub struct LicensesCommand {
+    /// Whether to also list the dependencies whose licenses are allowed
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// Reads the license from the manifest in the package's container folder, which is either a
+/// pesde or Wally manifest
+fn manifest_license(container_folder: &Path) -> anyhow::Result<Option<String>> {
+    match std::fs::read_to_string(container_folder.join(MANIFEST_FILE_NAME)) {
+        Ok(contents) => {
+            return parse_manifest(&contents)
+                .map(|manifest| manifest.license)
+                .context("failed to parse package manifest")
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e).context("failed to read package manifest"),
+    }
+
+    match std::fs::read_to_string(container_folder.join("wally.toml")) {
+        Ok(contents) => Ok(toml::from_str::<toml::Table>(&contents)
+            .context("failed to parse wally manifest")?
+            .get("package")
+            .and_then(|package| package.get("license"))
+            .and_then(|license| license.as_str())
+            .map(ToString::to_string)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).context("failed to read wally manifest"),
+    }
+}
+
+/// A parsed SPDX license expression
+#[derive(Debug, PartialEq, Eq)]
+enum LicenseExpression {
+    /// A license, optionally with an exception granting additional permissions
+    License {
+        id: String,
+        exception: Option<String>,
+    },
+    /// Both expressions apply
+    And(Box<LicenseExpression>, Box<LicenseExpression>),
+    /// Either expression may be chosen
+    Or(Box<LicenseExpression>, Box<LicenseExpression>),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Word(&'a str),
+}
+
+impl Token<'_> {
+    fn is_operator(&self, operator: &str) -> bool {
+        matches!(self, Token::Word(word) if word.eq_ignore_ascii_case(operator))
+    }
+}
+
+fn tokenize(expression: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut start = None;
+
+    for (i, c) in expression.char_indices() {
+        if c.is_whitespace() || c == '(' || c == ')' {
+            if let Some(start) = start.take() {
+                tokens.push(Token::Word(&expression[start..i]));
+            }
+
+            match c {
+                '(' => tokens.push(Token::Open),
+                ')' => tokens.push(Token::Close),
+                _ => {}
+            }
+        } else if start.is_none() {
+            start = Some(i);
+        }
+    }
+
+    if let Some(start) = start {
+        tokens.push(Token::Word(&expression[start..]));
+    }
+
+    tokens
+}
+
+/// Parses SPDX license expressions, where `WITH` binds tighter than `AND`, which binds tighter
+/// than `OR`. Operators are matched case-insensitively
+struct Parser<'a> {
+    tokens: std::iter::Peekable<std::vec::IntoIter<Token<'a>>>,
+}
+
+impl Parser<'_> {
+    fn parse(expression: &str) -> Option<LicenseExpression> {
+        let mut parser = Parser {
+            tokens: tokenize(expression).into_iter().peekable(),
+        };
+
+        let parsed = parser.or()?;
+        parser.tokens.next().is_none().then_some(parsed)
+    }
+
+    fn or(&mut self) -> Option<LicenseExpression> {
+        let mut expression = self.and()?;
+
+        while self
+            .tokens
+            .next_if(|token| token.is_operator("OR"))
+            .is_some()
+        {
+            expression = LicenseExpression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+
+        Some(expression)
+    }
+
+    fn and(&mut self) -> Option<LicenseExpression> {
+        let mut expression = self.license()?;
+
+        while self
+            .tokens
+            .next_if(|token| token.is_operator("AND"))
+            .is_some()
+        {
+            expression = LicenseExpression::And(Box::new(expression), Box::new(self.license()?));
+        }
+
+        Some(expression)
+    }
+
+    fn license(&mut self) -> Option<LicenseExpression> {
+        match self.tokens.next()? {
+            Token::Open => {
+                let expression = self.or()?;
+                (self.tokens.next()? == Token::Close).then_some(expression)
+            }
+            Token::Close => None,
+            token @ Token::Word(id) => {
+                if ["AND", "OR", "WITH"]
+                    .iter()
+                    .any(|operator| token.is_operator(operator))
+                {
+                    return None;
+                }
+
+                let exception = if self
+                    .tokens
+                    .next_if(|token| token.is_operator("WITH"))
+                    .is_some()
+                {
+                    match self.tokens.next()? {
+                        Token::Word(exception) => Some(exception.to_string()),
+                        _ => return None,
+                    }
+                } else {
+                    None
+                };
+
+                Some(LicenseExpression::License {
+                    id: id.to_string(),
+                    exception,
+                })
+            }
+        }
+    }
+}
+
+impl LicenseExpression {
+    fn is_allowed(&self, allowed: &BTreeSet<String>) -> bool {
+        let is_listed = |license: &str| {
+            allowed
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(license))
+        };
+
+        match self {
+            // exceptions only grant additional permissions, so an allowed license stays allowed
+            LicenseExpression::License { id, exception } => {
+                is_listed(id)
+                    || exception
+                        .as_ref()
+                        .is_some_and(|exception| is_listed(&format!("{id} WITH {exception}")))
+            }
+            LicenseExpression::And(a, b) => a.is_allowed(allowed) && b.is_allowed(allowed),
+            LicenseExpression::Or(a, b) => a.is_allowed(allowed) || b.is_allowed(allowed),
+        }
+    }
+}
+
+/// Whether the license is allowed. Licenses which aren't valid SPDX expressions must be listed
+/// as a whole
+fn is_allowed(license: &str, allowed: &BTreeSet<String>) -> bool {
+    match Parser::parse(license) {
+        Some(expression) => expression.is_allowed(allowed),
+        None => allowed
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(license.trim())),
+    }
+}
+
+impl LicensesCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let graph = if let Some(lockfile) = up_to_date_lockfile(&project)? {
+            lockfile.graph
+        } else {
+            anyhow::bail!("outdated lockfile, please run the install command first")
+        };
+
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+        let project_target = manifest.target.kind();
+
+        let mut refreshed_indices = HashSet::new();
+        let mut violations = 0usize;
+
+        for (name, versions) in &graph {
+            for (version_id, node) in versions {
+                let pkg_ref = &node.node.pkg_ref;
+
+                // workspace members are a part of the project itself
+                if matches!(pkg_ref, PackageRefs::Workspace(_)) {
+                    continue;
+                }
+
+                // the index has the license without needing the package to be installed
+                let index_license = match pkg_ref {
+                    PackageRefs::Pesde(pkg_ref) => {
+                        let source = PesdePackageSource::new(pkg_ref.index_url.clone());
+                        if refreshed_indices.insert(pkg_ref.index_url.clone()) {
+                            source
+                                .refresh(&project)
+                                .context("failed to refresh source")?;
+                        }
+
+                        source
+                            .index_file(&pkg_ref.name, &project)
+                            .context("failed to read index file")?
+                            .and_then(|mut file| file.remove(version_id))
+                            .map(|entry| entry.license)
+                    }
+                    _ => None,
+                };
+
+                let license = match index_license {
+                    Some(license) => license,
+                    None => {
+                        let container_folder = node.node.container_folder(
+                            &project
+                                .package_dir()
+                                .join(project_target.packages_folder(&pkg_ref.target_kind()))
+                                .join(PACKAGES_CONTAINER_NAME),
+                            name,
+                            version_id.version(),
+                        );
+
+                        manifest_license(&container_folder).with_context(|| {
+                            format!("failed to read license of {name}@{version_id}")
+                        })?
+                    }
+                };
+
+                let package = format!("{name}@{version_id}");
+                match license {
+                    None => {
+                        violations += 1;
+                        println!("{}", msg!("audit.missing_license", package = package).red());
+                    }
+                    Some(license)
+                        if !manifest.allowed_licenses.is_empty()
+                            && !is_allowed(&license, &manifest.allowed_licenses) =>
+                    {
+                        violations += 1;
+                        println!(
+                            "{}",
+                            msg!(
+                                "audit.disallowed_license",
+                                package = package,
+                                license = license
+                            )
+                            .red()
+                        );
+                    }
+                    Some(license) if self.verbose => {
+                        println!("{package}: {license}");
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if violations > 0 {
+            anyhow::bail!("{}", msg!("audit.violations", count = violations));
+        }
+
+        println!("{}", msg!("audit.passed").green());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(licenses: &[&str]) -> BTreeSet<String> {
+        licenses.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn allows_listed_licenses() {
+        assert!(is_allowed("MIT", &allowed(&["mit"])));
+        assert!(!is_allowed("MIT", &allowed(&["Apache-2.0"])));
+        assert!(is_allowed("MIT OR Apache-2.0", &allowed(&["Apache-2.0"])));
+        assert!(!is_allowed("MIT AND Apache-2.0", &allowed(&["Apache-2.0"])));
+    }
+
+    #[test]
+    fn respects_parentheses() {
+        let expression = "MIT AND (Apache-2.0 OR GPL-3.0)";
+
+        assert!(!is_allowed(expression, &allowed(&["GPL-3.0"])));
+        assert!(is_allowed(expression, &allowed(&["MIT", "GPL-3.0"])));
+        assert!(is_allowed(
+            "((MIT) OR (Apache-2.0 AND Zlib))",
+            &allowed(&["Apache-2.0", "Zlib"])
+        ));
+    }
+
+    #[test]
+    fn binds_and_tighter_than_or() {
+        let expression = "MIT OR Apache-2.0 AND GPL-3.0";
+
+        assert!(is_allowed(expression, &allowed(&["MIT"])));
+        assert!(!is_allowed(expression, &allowed(&["Apache-2.0"])));
+        assert!(is_allowed(expression, &allowed(&["Apache-2.0", "GPL-3.0"])));
+        assert!(is_allowed(
+            "mit or apache-2.0 and gpl-3.0",
+            &allowed(&["MIT"])
+        ));
+    }
+
+    #[test]
+    fn handles_exceptions() {
+        let expression = "GPL-2.0-only WITH Classpath-exception-2.0";
+
+        assert!(is_allowed(expression, &allowed(&["GPL-2.0-only"])));
+        assert!(is_allowed(
+            expression,
+            &allowed(&["GPL-2.0-only WITH Classpath-exception-2.0"])
+        ));
+        assert!(!is_allowed(
+            expression,
+            &allowed(&["Classpath-exception-2.0"])
+        ));
+        assert!(is_allowed(
+            "MIT OR GPL-2.0-only with Classpath-exception-2.0",
+            &allowed(&["GPL-2.0-only"])
+        ));
+    }
+
+    #[test]
+    fn matches_invalid_expressions_as_a_whole() {
+        assert!(is_allowed(
+            "Proprietary (internal)",
+            &allowed(&["Proprietary (internal)"])
+        ));
+        assert!(!is_allowed("MIT AND", &allowed(&["MIT"])));
+        assert!(!is_allowed("(MIT", &allowed(&["MIT"])));
+        assert!(!is_allowed("MIT WITH", &allowed(&["MIT"])));
+    }
+}
@@ -0,0 +1,25 @@
+use clap::Subcommand;
+use pesde::Project;
+
+mod advisories;
+mod licenses;
+
+#[derive(Debug, Subcommand)]
+pub enum AuditCommands {
+    /// Checks the licenses of the installed dependencies against the manifest's allowed licenses,
+    /// failing if any are missing or disallowed
+    Licenses(licenses::LicensesCommand),
+
+    /// Checks the installed dependencies against the security advisories of their indices,
+    /// failing if any are affected
+    Advisories(advisories::AdvisoriesCommand),
+}
+
+impl AuditCommands {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        match self {
+            AuditCommands::Licenses(licenses) => licenses.run(project),
+            AuditCommands::Advisories(advisories) => advisories.run(project),
+        }
+    }
+}
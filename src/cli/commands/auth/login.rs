@@ -1,16 +1,24 @@
 use anyhow::Context;
 use clap::Args;
 use colored::Colorize;
+use reqwest::StatusCode;
 use serde::Deserialize;
-use std::thread::spawn;
+use std::{io::IsTerminal, thread::spawn};
 use url::Url;
 
 use pesde::{
-    source::{pesde::PesdePackageSource, traits::PackageSource},
+    registry_client::RegistryClient,
+    source::{
+        pesde::{IndexConfig, PesdePackageSource},
+        traits::PackageSource,
+    },
     Project,
 };
 
-use crate::cli::auth::{get_token_login, set_token};
+use crate::cli::{
+    auth::{get_token_login, set_token},
+    messages::msg,
+};
 
 #[derive(Debug, Args)]
 pub struct LoginCommand {
@@ -32,9 +40,15 @@ struct DeviceCodeResponse {
 #[serde(rename_all = "snake_case", tag = "error")]
 enum AccessTokenError {
     AuthorizationPending,
-    SlowDown { interval: u64 },
+    SlowDown {
+        interval: u64,
+    },
     ExpiredToken,
     AccessDenied,
+    DeviceFlowDisabled,
+    IncorrectClientCredentials,
+    #[serde(other)]
+    Unknown,
 }
 
 #[derive(Debug, Deserialize)]
@@ -45,22 +59,20 @@ enum AccessTokenResponse {
     Error(AccessTokenError),
 }
 
+/// The page creating a classic personal access token with the minimal scope needed to log in
+const NEW_TOKEN_URL: &str =
+    "https://github.com/settings/tokens/new?scopes=read:user&description=pesde";
+
 impl LoginCommand {
     pub fn authenticate_device_flow(
         &self,
         index_url: &gix::Url,
-        project: &Project,
+        config: &IndexConfig,
         reqwest: &reqwest::blocking::Client,
     ) -> anyhow::Result<String> {
-        println!("logging in into {index_url}");
+        println!("{}", msg!("auth.logging_in", index = index_url));
 
-        let source = PesdePackageSource::new(index_url.clone());
-        source.refresh(project).context("failed to refresh index")?;
-
-        let config = source
-            .config(project)
-            .context("failed to read index config")?;
-        let Some(client_id) = config.github_oauth_client_id else {
+        let Some(client_id) = config.github_oauth_client_id.clone() else {
             anyhow::bail!("index not configured for Github oauth.");
         };
 
@@ -142,6 +154,15 @@ impl LoginCommand {
                     AccessTokenError::AccessDenied => {
                         anyhow::bail!("access denied, re-run the login command");
                     }
+                    AccessTokenError::DeviceFlowDisabled => {
+                        anyhow::bail!("device flow is disabled for the index's GitHub app");
+                    }
+                    AccessTokenError::IncorrectClientCredentials => {
+                        anyhow::bail!("the index's GitHub app client id is incorrect");
+                    }
+                    AccessTokenError::Unknown => {
+                        anyhow::bail!("GitHub rejected the device flow");
+                    }
                 },
             }
         }
@@ -149,30 +170,106 @@ impl LoginCommand {
         anyhow::bail!("code expired, please re-run the login command");
     }
 
+    /// Asks for a personal access token to use when the device flow can't be used
+    fn prompt_token(&self, error: anyhow::Error) -> anyhow::Result<String> {
+        if !std::io::stdin().is_terminal() {
+            return Err(error.context(format!(
+                "{}, {}",
+                msg!("auth.device_flow_failed"),
+                msg!("auth.device_flow_failed_hint")
+            )));
+        }
+
+        println!(
+            "{}: {error:#}\n{}",
+            msg!("auth.device_flow_failed").yellow().bold(),
+            msg!("auth.device_flow_failed_hint")
+        );
+
+        if !inquire::Confirm::new("paste a personal access token instead?")
+            .with_default(true)
+            .prompt()?
+        {
+            return Err(error);
+        }
+
+        println!(
+            "{}",
+            msg!("auth.token_instructions", url = NEW_TOKEN_URL.blue())
+        );
+
+        let token = inquire::Password::new("personal access token:")
+            .without_confirmation()
+            .prompt()?;
+        let token = token.trim();
+        if token.is_empty() {
+            anyhow::bail!("no token given");
+        }
+
+        Ok(token.to_string())
+    }
+
+    /// Checks that the registry accepts the token, so a rejected one isn't stored
+    fn verify_token(
+        &self,
+        index_url: &gix::Url,
+        config: &IndexConfig,
+        project: &Project,
+        reqwest: &reqwest::blocking::Client,
+        token: &str,
+    ) -> anyhow::Result<()> {
+        match RegistryClient::new(project, reqwest, index_url, config)
+            .with_token(Some(token.to_string()))
+            .user()
+        {
+            Ok(Some(_)) => Ok(()),
+            // registries predating the endpoint can't verify tokens
+            Ok(None) => {
+                log::warn!("the registry can't verify tokens, storing it unverified");
+                Ok(())
+            }
+            Err(e) if e.status() == Some(StatusCode::UNAUTHORIZED) => {
+                anyhow::bail!("{}", msg!("auth.token_rejected", index = index_url))
+            }
+            Err(e) => Err(e).context("failed to verify token with the registry"),
+        }
+    }
+
     pub fn run(
         self,
         index_url: gix::Url,
         project: Project,
         reqwest: reqwest::blocking::Client,
     ) -> anyhow::Result<()> {
-        let token_given = self.token.is_some();
-        let token = match self.token {
-            Some(token) => token,
-            None => self.authenticate_device_flow(&index_url, &project, &reqwest)?,
-        };
+        if let Some(token) = &self.token {
+            println!("{}", msg!("auth.token_set", index = index_url));
+            set_token(&index_url, Some(token))?;
+
+            return Ok(());
+        }
 
-        let token = if token_given {
-            println!("set token for {index_url}");
-            token
-        } else {
-            let token = format!("Bearer {token}");
-            println!(
-                "logged in as {} for {index_url}",
-                get_token_login(&reqwest, &token)?.bold()
-            );
-
-            token
+        let source = PesdePackageSource::new(index_url.clone());
+        source
+            .refresh(&project)
+            .context("failed to refresh index")?;
+        let config = source
+            .config(&project)
+            .context("failed to read index config")?;
+
+        let token = match self.authenticate_device_flow(&index_url, &config, &reqwest) {
+            Ok(token) => token,
+            Err(e) => self.prompt_token(e)?,
         };
+        let token = format!("Bearer {token}");
+
+        let login = get_token_login(&reqwest, &token)
+            .context("GitHub rejected the token, check that it is valid and authorized for your organization's SSO")?;
+        self.verify_token(&index_url, &config, &project, &reqwest, &token)?;
+
+        println!(
+            "{}",
+            msg!("auth.logged_in_as", user = login.bold(), index = index_url)
+        );
 
         set_token(&index_url, Some(&token))?;
 
@@ -1,4 +1,4 @@
-use crate::cli::auth::set_token;
+use crate::cli::{auth::set_token, messages::msg};
 use clap::Args;
 
 #[derive(Debug, Args)]
@@ -8,7 +8,7 @@ impl LogoutCommand {
     pub fn run(self, index_url: gix::Url) -> anyhow::Result<()> {
         set_token(&index_url, None)?;
 
-        println!("logged out of {index_url}");
+        println!("{}", msg!("auth.logged_out", index = index_url));
 
         Ok(())
     }
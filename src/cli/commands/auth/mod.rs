@@ -38,10 +38,7 @@ impl AuthSubcommand {
         };
 
         let index_url = match self.index.as_deref() {
-            Some(index) => match index.try_into() {
-                Ok(url) => Some(url),
-                Err(_) => None,
-            },
+            Some(index) => index.try_into().ok(),
             None => match manifest {
                 Some(_) => None,
                 None => Some(read_config()?.default_index),
@@ -1,4 +1,7 @@
-use crate::cli::auth::{get_token_login, get_tokens};
+use crate::cli::{
+    auth::{get_token_login, get_tokens},
+    messages::msg,
+};
 use clap::Args;
 use colored::Colorize;
 
@@ -15,14 +18,18 @@ impl WhoAmICommand {
         let token = match tokens.0.get(&index_url) {
             Some(token) => token,
             None => {
-                println!("not logged in into {index_url}");
+                println!("{}", msg!("auth.not_logged_in", index = index_url));
                 return Ok(());
             }
         };
 
         println!(
-            "logged in as {} into {index_url}",
-            get_token_login(&reqwest, token)?.bold()
+            "{}",
+            msg!(
+                "auth.logged_in_as",
+                user = get_token_login(&reqwest, token)?.bold(),
+                index = index_url
+            )
         );
 
         Ok(())
@@ -0,0 +1,13 @@
+use clap::Args;
+use pesde::Project;
+
+#[derive(Debug, Args)]
+pub struct DirCommand {}
+
+impl DirCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        println!("{}", project.cas_dir().display());
+
+        Ok(())
+    }
+}
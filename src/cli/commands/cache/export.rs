@@ -0,0 +1,114 @@
+use super::{CAS_FOLDER, DATA_FOLDER};
+use crate::cli::{messages::msg, up_to_date_lockfile};
+use anyhow::Context;
+use clap::Args;
+use pesde::{
+    source::{fs::PackageFS, traits::PackageRef},
+    Project,
+};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Args)]
+pub struct ExportCommand {
+    /// Where to write the cache archive to
+    #[arg(index = 1, default_value = "pesde-cache.tar.gz")]
+    output: PathBuf,
+}
+
+impl ExportCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let graph = if let Some(lockfile) = up_to_date_lockfile(&project)? {
+            lockfile.graph
+        } else {
+            anyhow::bail!("outdated lockfile, please run the install command first")
+        };
+
+        let mut sources = HashSet::new();
+        let mut files = BTreeSet::new();
+
+        for (name, versions) in &graph {
+            for (version_id, node) in versions {
+                let source = node.node.pkg_ref.source();
+
+                let Some(index_file) = source.cached_index_file(&node.node.pkg_ref, &project)
+                else {
+                    continue;
+                };
+
+                let fs = match std::fs::read_to_string(&index_file) {
+                    Ok(s) => toml::from_str::<PackageFS>(&s).with_context(|| {
+                        format!("failed to parse cached index file of {name}@{version_id}")
+                    })?,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        anyhow::bail!(
+                            "{name}@{version_id} is not cached, please run the install command first"
+                        )
+                    }
+                    Err(e) => {
+                        return Err(e).context(format!(
+                            "failed to read cached index file of {name}@{version_id}"
+                        ))
+                    }
+                };
+
+                files.extend(fs.cas_files(project.cas_dir()));
+                files.insert(index_file);
+                sources.insert(source);
+            }
+        }
+
+        let file = File::create(&self.output).context("failed to create cache archive")?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+        let mut archive = tar::Builder::new(encoder);
+
+        for path in &files {
+            let relative = path
+                .strip_prefix(project.cas_dir())
+                .context("cached file is outside of the cas directory")?;
+
+            archive
+                .append_path_with_name(path, Path::new(CAS_FOLDER).join(relative))
+                .with_context(|| format!("failed to add {} to cache archive", path.display()))?;
+        }
+
+        // the local copies of the sources are needed to refresh them without network access
+        for path in sources
+            .iter()
+            .filter_map(|source| source.local_path(&project))
+            .collect::<BTreeSet<_>>()
+        {
+            if !path.exists() {
+                continue;
+            }
+
+            let relative = path
+                .strip_prefix(project.data_dir())
+                .context("source is outside of the data directory")?;
+
+            archive
+                .append_dir_all(Path::new(DATA_FOLDER).join(relative), &path)
+                .with_context(|| format!("failed to add {} to cache archive", path.display()))?;
+        }
+
+        archive
+            .into_inner()
+            .context("failed to write cache archive")?
+            .finish()
+            .context("failed to finish cache archive")?;
+
+        println!(
+            "{}",
+            msg!(
+                "cache.exported",
+                count = files.len(),
+                path = self.output.display()
+            )
+        );
+
+        Ok(())
+    }
+}
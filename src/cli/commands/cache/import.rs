@@ -0,0 +1,175 @@
+use super::{CAS_FOLDER, DATA_FOLDER};
+use crate::cli::messages::msg;
+use anyhow::Context;
+use clap::Args;
+use pesde::Project;
+use std::{
+    fs::File,
+    io::Read,
+    path::{Component, PathBuf},
+};
+
+#[derive(Debug, Args)]
+pub struct ImportCommand {
+    /// The cache archive to import
+    #[arg(index = 1, default_value = "pesde-cache.tar.gz")]
+    input: PathBuf,
+}
+
+/// Unpacks the cache archive into the CAS and data directory of the project, returning how many
+/// CAS files were imported
+fn import_archive<R: Read>(archive: R, project: &Project) -> anyhow::Result<usize> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+
+    let mut imported = 0usize;
+
+    for entry in archive
+        .entries()
+        .context("failed to read cache archive entries")?
+    {
+        let mut entry = entry.context("failed to read cache archive entry")?;
+        let path = entry
+            .path()
+            .context("invalid path in cache archive")?
+            .into_owned();
+
+        let mut components = path.components();
+        let root = match components.next() {
+            Some(Component::Normal(root)) if root == CAS_FOLDER => project.cas_dir(),
+            Some(Component::Normal(root)) if root == DATA_FOLDER => project.data_dir(),
+            _ => anyhow::bail!("unexpected path {} in cache archive", path.display()),
+        };
+
+        if !components
+            .clone()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            anyhow::bail!("unexpected path {} in cache archive", path.display());
+        }
+
+        // links could point later entries outside of the cache, and exports never contain them
+        let entry_type = entry.header().entry_type();
+        if !entry_type.is_file() && !entry_type.is_dir() {
+            anyhow::bail!(
+                "unexpected entry type of {} in cache archive",
+                path.display()
+            );
+        }
+
+        let destination = root.join(components.as_path());
+
+        if entry_type.is_dir() {
+            std::fs::create_dir_all(&destination)
+                .with_context(|| format!("failed to create directory {}", destination.display()))?;
+            continue;
+        }
+
+        // CAS files are content addressed, and existing files are likely newer
+        if destination.exists() {
+            continue;
+        }
+
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create directory {}", parent.display()))?;
+        }
+
+        entry
+            .unpack(&destination)
+            .with_context(|| format!("failed to unpack {}", destination.display()))?;
+
+        if root == project.cas_dir() {
+            imported += 1;
+        }
+    }
+
+    Ok(imported)
+}
+
+impl ImportCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let file = File::open(&self.input).context("failed to open cache archive")?;
+        let imported = import_archive(file, &project)?;
+
+        println!("{}", msg!("cache.imported", count = imported));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pesde::AuthConfig;
+
+    fn archive(build: impl FnOnce(&mut tar::Builder<Vec<u8>>)) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        build(&mut builder);
+        let tar = builder.into_inner().unwrap();
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        std::io::Write::write_all(&mut encoder, &tar).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn append_file(builder: &mut tar::Builder<Vec<u8>>, path: &str, contents: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        builder.append_data(&mut header, path, contents).unwrap();
+    }
+
+    fn project(dir: &std::path::Path) -> Project {
+        Project::new(
+            dir.join("project"),
+            None::<PathBuf>,
+            dir.join("data"),
+            dir.join("cas"),
+            AuthConfig::default(),
+        )
+    }
+
+    #[test]
+    fn imports_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project(dir.path());
+
+        let archive = archive(|builder| {
+            append_file(builder, "cas/ab/cdef", b"contents");
+            append_file(builder, "data/index", b"index");
+        });
+
+        assert_eq!(import_archive(archive.as_slice(), &project).unwrap(), 1);
+        assert_eq!(
+            std::fs::read(project.cas_dir().join("ab").join("cdef")).unwrap(),
+            b"contents"
+        );
+        assert_eq!(
+            std::fs::read(project.data_dir().join("index")).unwrap(),
+            b"index"
+        );
+    }
+
+    #[test]
+    fn rejects_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project(dir.path());
+        let outside = dir.path().join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+
+        let archive = archive(|builder| {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder
+                .append_link(&mut header, "cas/ab", &outside)
+                .unwrap();
+
+            append_file(builder, "cas/ab/x", b"escaped");
+        });
+
+        assert!(import_archive(archive.as_slice(), &project).is_err());
+        assert!(!outside.join("x").exists());
+        assert!(!project.cas_dir().join("ab").exists());
+    }
+}
@@ -0,0 +1,38 @@
+use clap::Subcommand;
+use pesde::Project;
+
+mod dir;
+mod export;
+mod import;
+mod stats;
+
+/// The folder inside cache archives which holds files of the CAS
+const CAS_FOLDER: &str = "cas";
+/// The folder inside cache archives which holds files of the data directory
+const DATA_FOLDER: &str = "data";
+
+#[derive(Debug, Subcommand)]
+pub enum CacheCommands {
+    /// Exports the cached files needed to install the current lockfile into an archive
+    Export(export::ExportCommand),
+
+    /// Imports the cached files from an archive created by `cache export`
+    Import(import::ImportCommand),
+
+    /// Prints the location of the cache
+    Dir(dir::DirCommand),
+
+    /// Shows the size of the cache, how many packages it holds and the largest of them
+    Stats(stats::StatsCommand),
+}
+
+impl CacheCommands {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        match self {
+            CacheCommands::Export(export) => export.run(project),
+            CacheCommands::Import(import) => import.run(project),
+            CacheCommands::Dir(dir) => dir.run(project),
+            CacheCommands::Stats(stats) => stats.run(project),
+        }
+    }
+}
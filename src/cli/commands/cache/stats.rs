@@ -0,0 +1,146 @@
+use crate::cli::messages::msg;
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{names::PackageNames, source::fs::PackageFS, Project};
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+/// The folders of the CAS holding the file systems of cached packages, by source
+const SOURCES: &[(&str, &str)] = &[
+    ("pesde", "index"),
+    ("wally", "wally_index"),
+    ("git", "git_index"),
+];
+
+#[derive(Debug, Args)]
+pub struct StatsCommand {
+    /// How many of the largest packages to list
+    #[arg(short, long, default_value_t = 10)]
+    top: usize,
+}
+
+/// Every file within the directory, with its size
+fn files(dir: &Path) -> anyhow::Result<Vec<(PathBuf, u64)>> {
+    let mut files = vec![];
+    let mut stack = vec![dir.to_path_buf()];
+
+    while let Some(dir) = stack.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e).with_context(|| format!("failed to read {}", dir.display())),
+        };
+
+        for entry in entries {
+            let entry = entry.with_context(|| format!("failed to read {}", dir.display()))?;
+            let metadata = entry
+                .metadata()
+                .with_context(|| format!("failed to read {}", entry.path().display()))?;
+
+            if metadata.is_dir() {
+                stack.push(entry.path());
+            } else {
+                files.push((entry.path(), metadata.len()));
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+/// Describes a cached package from the path of its file system, relative to its source's folder
+fn package_label(source: &str, relative: &Path) -> String {
+    let parts = relative
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>();
+
+    match (source, parts.as_slice()) {
+        ("git", [_, tree_id]) => format!("git tree {tree_id}"),
+        (_, [name, version, rest @ ..]) => {
+            let name = PackageNames::from_escaped(name)
+                .map(|name| name.to_string())
+                .unwrap_or_else(|_| name.to_string());
+
+            match rest {
+                [target] => format!("{name}@{version} {target}"),
+                _ => format!("{name}@{version}"),
+            }
+        }
+        _ => relative.display().to_string(),
+    }
+}
+
+impl StatsCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let cas_dir = project.cas_dir();
+
+        let all_files = files(cas_dir)?;
+        let total_size = all_files.iter().map(|(_, size)| size).sum::<u64>();
+
+        println!(
+            "{}",
+            msg!(
+                "cache.stats.summary",
+                dir = cas_dir.display(),
+                size = indicatif::HumanBytes(total_size),
+                count = all_files.len()
+            )
+        );
+
+        let mut packages = vec![];
+        let mut counts = vec![];
+
+        for (source, folder) in SOURCES {
+            let folder = cas_dir.join(folder);
+            let index_files = files(&folder)?;
+            counts.push(format!("{} {source}", index_files.len()));
+
+            for (path, _) in index_files {
+                let fs = match std::fs::read_to_string(&path)
+                    .map_err(anyhow::Error::from)
+                    .and_then(|s| toml::from_str::<PackageFS>(&s).map_err(Into::into))
+                {
+                    Ok(fs) => fs,
+                    Err(e) => {
+                        log::warn!("failed to read cached package {}: {e}", path.display());
+                        continue;
+                    }
+                };
+
+                let size = fs
+                    .cas_files(cas_dir)
+                    .into_iter()
+                    .collect::<HashSet<_>>()
+                    .iter()
+                    .filter_map(|file| file.metadata().ok())
+                    .map(|metadata| metadata.len())
+                    .sum::<u64>();
+
+                let relative = path.strip_prefix(&folder).unwrap_or(&path);
+                packages.push((size, package_label(source, relative)));
+            }
+        }
+
+        println!(
+            "{}",
+            msg!("cache.stats.packages", counts = counts.join(", "))
+        );
+
+        if packages.is_empty() || self.top == 0 {
+            return Ok(());
+        }
+
+        packages.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        println!("\n{}", msg!("cache.stats.largest").bold());
+        for (size, label) in packages.into_iter().take(self.top) {
+            println!("{:>10} {label}", indicatif::HumanBytes(size).to_string());
+        }
+
+        Ok(())
+    }
+}
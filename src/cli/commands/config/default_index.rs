@@ -1,4 +1,7 @@
-use crate::cli::config::{read_config, write_config, CliConfig};
+use crate::cli::{
+    config::{read_config, write_config, CliConfig},
+    messages::msg,
+};
 use clap::Args;
 
 #[derive(Debug, Args)]
@@ -26,10 +29,13 @@ impl DefaultIndexCommand {
             Some(index) => {
                 config.default_index = index.clone();
                 write_config(&config)?;
-                println!("default index set to: {index}");
+                println!("{}", msg!("config.default_index.set", index = index));
             }
             None => {
-                println!("current default index: {}", config.default_index);
+                println!(
+                    "{}",
+                    msg!("config.default_index.current", index = config.default_index)
+                );
             }
         }
 
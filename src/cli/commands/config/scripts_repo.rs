@@ -1,6 +1,7 @@
 use crate::cli::{
     config::{read_config, write_config, CliConfig},
     home_dir,
+    messages::msg,
 };
 use anyhow::Context;
 use clap::Args;
@@ -34,10 +35,13 @@ impl ScriptsRepoCommand {
                 std::fs::remove_dir_all(home_dir()?.join("scripts"))
                     .context("failed to remove scripts directory")?;
 
-                println!("scripts repo set to: {repo}");
+                println!("{}", msg!("config.scripts_repo.set", repo = repo));
             }
             None => {
-                println!("current scripts repo: {}", config.scripts_repo);
+                println!(
+                    "{}",
+                    msg!("config.scripts_repo.current", repo = config.scripts_repo)
+                );
             }
         }
 
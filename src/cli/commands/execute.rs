@@ -1,18 +1,32 @@
-use crate::cli::{config::read_config, VersionedPackageName};
+use crate::cli::{
+    config::{read_config, write_config},
+    messages::msg,
+    VersionedPackageName,
+};
 use anyhow::Context;
 use clap::Args;
+use colored::Colorize;
 use pesde::{
     linking::generator::generate_bin_linking_module,
     manifest::target::TargetKind,
     names::PackageName,
     source::{
-        pesde::{specifier::PesdeDependencySpecifier, PesdePackageSource},
+        pesde::{
+            pkg_ref::PesdePackageRef, specifier::PesdeDependencySpecifier, PesdePackageSource,
+            ScopeInfo, SCOPE_INFO_FILE,
+        },
         traits::PackageSource,
+        version_id::VersionId,
     },
     Project,
 };
 use semver::VersionReq;
-use std::{env::current_dir, ffi::OsString, io::Write, process::Command};
+use std::{
+    env::current_dir,
+    ffi::OsString,
+    io::{IsTerminal, Write},
+    process::Command,
+};
 
 #[derive(Debug, Args)]
 pub struct ExecuteCommand {
@@ -24,11 +38,114 @@ pub struct ExecuteCommand {
     #[arg(short, long, value_parser = crate::cli::parse_gix_url)]
     index: Option<gix::Url>,
 
+    /// Whether to run the package without asking for confirmation, for non-interactive use such as CI
+    #[arg(short, long)]
+    yes: bool,
+
+    /// The named bin export to run, instead of the package's bin export
+    #[arg(short, long)]
+    bin: Option<String>,
+
     /// Arguments to pass to the script
     #[arg(index = 2, last = true)]
     args: Vec<OsString>,
 }
 
+const RUN: &str = "run";
+const TRUST_SCOPE: &str = "run and trust its scope";
+const CANCEL: &str = "cancel";
+
+/// Asks whether the package may be run, unless this version was confirmed before or its scope is
+/// trusted, remembering the decision in the config
+fn confirm_execution(
+    project: &Project,
+    source: &PesdePackageSource,
+    pkg_ref: &PesdePackageRef,
+) -> anyhow::Result<()> {
+    // scoped, as its `refresh` would be ambiguous with `PackageSource`'s in `run`
+    use pesde::source::git_index::GitBasedSource;
+
+    let mut config = read_config()?;
+    let index = source.repo_url().to_bstring().to_string();
+    let (scope, _) = pkg_ref.name.as_str();
+    let package = format!("{}@{}", pkg_ref.name, pkg_ref.version);
+
+    if config
+        .execute_permissions
+        .get(&index)
+        .is_some_and(|permissions| {
+            permissions.trusted_scopes.contains(scope) || permissions.confirmed.contains(&package)
+        })
+    {
+        return Ok(());
+    }
+
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!("{}", msg!("execute.unconfirmed", package = package));
+    }
+
+    let owners = match source
+        .read_file([scope, SCOPE_INFO_FILE], project, None)
+        .context("failed to read scope info")?
+    {
+        Some(info) => toml::from_str::<ScopeInfo>(&info)
+            .context("failed to parse scope info")?
+            .owners
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        None => "unknown".to_string(),
+    };
+    let entry = source
+        .index_file(&pkg_ref.name, project)
+        .context("failed to read index file")?
+        .and_then(|mut file| {
+            file.remove(&VersionId::new(
+                pkg_ref.version.clone(),
+                pkg_ref.target.kind(),
+            ))
+        });
+
+    println!("{}", msg!("execute.confirm", package = package).yellow());
+    println!("  {} {index}", "source:".dimmed());
+    println!("  {} {owners}", "publishers (user ids):".dimmed());
+    if let Some(entry) = entry {
+        println!(
+            "  {} {}",
+            "published:".dimmed(),
+            entry.published_at.format("%Y-%m-%d %H:%M UTC")
+        );
+        if !entry.authors.is_empty() {
+            println!("  {} {}", "authors:".dimmed(), entry.authors.join(", "));
+        }
+        if let Some(repository) = entry.repository {
+            print!("  {} {repository}", "repository:".dimmed());
+            if entry.repository_verified {
+                print!(" (verified)");
+            }
+            println!();
+        }
+    }
+
+    let choice = inquire::Select::new(&format!("run {package}?"), vec![RUN, TRUST_SCOPE, CANCEL])
+        .prompt()
+        .context("failed to prompt")?;
+
+    let permissions = config.execute_permissions.entry(index).or_default();
+    match choice {
+        RUN => {
+            permissions.confirmed.insert(package);
+        }
+        TRUST_SCOPE => {
+            permissions.trusted_scopes.insert(scope.to_string());
+        }
+        _ => anyhow::bail!("{}", msg!("execute.cancelled", package = package)),
+    }
+
+    write_config(&config).context("failed to write config")
+}
+
 impl ExecuteCommand {
     pub fn run(self, project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
         let index = self
@@ -47,6 +164,7 @@ impl ExecuteCommand {
                 version: version_req.clone(),
                 index: None,
                 target: None,
+                optional: false,
             };
 
             if let Some(res) = source
@@ -72,16 +190,26 @@ impl ExecuteCommand {
 
         log::info!("found package {}@{version}", pkg_ref.name);
 
+        if !self.yes {
+            confirm_execution(&project, &source, &pkg_ref)?;
+        }
+
         let (fs, target) = source
             .download(&pkg_ref, &project, &reqwest)
             .context("failed to download package")?;
-        let bin_path = target.bin_path().context("package has no binary export")?;
+        let bin_path = match &self.bin {
+            Some(bin) => target
+                .named_bins()
+                .and_then(|bins| bins.get(bin))
+                .with_context(|| format!("package has no binary export named {bin}"))?,
+            None => target.bin_path().context("package has no binary export")?,
+        };
 
         let tmp_dir = project.cas_dir().join(".tmp");
         std::fs::create_dir_all(&tmp_dir).context("failed to create temporary directory")?;
 
         let tempdir =
-            tempfile::tempdir_in(tmp_dir).context("failed to create temporary directory")?;
+            pesde::tempdirs::tempdir_in(tmp_dir).context("failed to create temporary directory")?;
 
         fs.write_to(tempdir.path(), project.cas_dir(), true)
             .context("failed to write package contents")?;
@@ -0,0 +1,114 @@
+use crate::cli::{commands::install::InstallCommand, messages::msg, VersionedPackageName};
+use anyhow::Context;
+use clap::Args;
+use indicatif::MultiProgress;
+use pesde::{
+    manifest::target::TargetKind,
+    names::PackageName,
+    source::{
+        pesde::{specifier::PesdeDependencySpecifier, PesdePackageSource},
+        traits::PackageSource,
+    },
+    Project, DEFAULT_INDEX_NAME,
+};
+use semver::VersionReq;
+use std::{str::FromStr, thread::JoinHandle};
+
+#[derive(Debug, Args)]
+pub struct AddCommand {
+    /// The package to install, optionally with a version requirement
+    #[arg(index = 1)]
+    package: VersionedPackageName<VersionReq, PackageName>,
+
+    /// The alias to install the package as, which is also the name of its binary
+    #[arg(short, long)]
+    alias: Option<String>,
+}
+
+impl AddCommand {
+    pub fn run(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+        update_task: &mut Option<JoinHandle<()>>,
+    ) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read globals manifest")?;
+        let index = manifest
+            .indices
+            .get(DEFAULT_INDEX_NAME)
+            .context("globals manifest has no default index")?
+            .clone();
+
+        let source = PesdePackageSource::new(index);
+        source
+            .refresh(&project)
+            .context("failed to refresh source")?;
+
+        let VersionedPackageName(name, version_req) = self.package;
+        let version_req = version_req.unwrap_or(VersionReq::STAR);
+
+        let mut resolved = None;
+        for target in [TargetKind::Lune, TargetKind::Luau] {
+            let specifier = PesdeDependencySpecifier {
+                name: name.clone(),
+                version: version_req.clone(),
+                index: None,
+                target: Some(target),
+                optional: false,
+            };
+
+            resolved = source
+                .resolve(&specifier, &project, target)
+                .context("failed to resolve package")?
+                .1
+                .pop_last();
+
+            if resolved.is_some() {
+                break;
+            }
+        }
+        let Some((version_id, pkg_ref)) = resolved else {
+            anyhow::bail!("no Lune or Luau package could be found for {name}@{version_req}");
+        };
+
+        if !pkg_ref.target.has_bins() {
+            anyhow::bail!("{name}@{} has no binary export", version_id.version());
+        }
+
+        let alias = self.alias.unwrap_or_else(|| name.as_str().1.to_string());
+
+        let mut document = toml_edit::DocumentMut::from_str(
+            &project
+                .read_manifest()
+                .context("failed to read globals manifest")?,
+        )
+        .context("failed to parse globals manifest")?;
+
+        let field = &mut document["dependencies"]
+            .or_insert(toml_edit::Item::Table(toml_edit::Table::new()))[&alias];
+        field["name"] = toml_edit::value(name.to_string());
+        field["version"] = toml_edit::value(format!("^{}", version_id.version()));
+        field["target"] = toml_edit::value(version_id.target().to_string());
+
+        project
+            .write_manifest(document.to_string())
+            .context("failed to write globals manifest")?;
+
+        InstallCommand::default().run(project, multi, reqwest, update_task)?;
+
+        println!(
+            "{}",
+            msg!(
+                "global.added",
+                name = name,
+                version = version_id.version(),
+                alias = alias
+            )
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+use crate::cli::messages::msg;
+use anyhow::Context;
+use clap::Args;
+use pesde::Project;
+
+#[derive(Debug, Args)]
+pub struct ListCommand {}
+
+impl ListCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read globals manifest")?;
+
+        if manifest.dependencies.is_empty() {
+            println!("{}", msg!("global.none"));
+            return Ok(());
+        }
+
+        let graph = project
+            .deser_lockfile()
+            .map(|lockfile| lockfile.graph)
+            .unwrap_or_default();
+
+        for (alias, specifier) in &manifest.dependencies {
+            let installed = graph.iter().find_map(|(name, versions)| {
+                versions.iter().find_map(|(version_id, node)| {
+                    node.node
+                        .direct
+                        .as_ref()
+                        .is_some_and(|(a, _)| a == alias)
+                        .then(|| format!("{name}@{}", version_id.version()))
+                })
+            });
+
+            match installed {
+                Some(installed) => println!("{alias}: {installed}"),
+                None => println!("{alias}: {specifier} (not installed)"),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,85 @@
+use crate::cli::{config::read_config, globals_dir};
+use anyhow::Context;
+use clap::Subcommand;
+use indicatif::MultiProgress;
+use pesde::{Project, MANIFEST_FILE_NAME};
+use std::{path::Path, thread::JoinHandle};
+
+mod add;
+mod list;
+mod remove;
+mod upgrade;
+
+#[derive(Debug, Subcommand)]
+pub enum GlobalCommands {
+    /// Installs a binary package globally, making its binary available everywhere
+    Add(add::AddCommand),
+
+    /// Removes a globally installed package
+    Remove(remove::RemoveCommand),
+
+    /// Lists the globally installed packages
+    List(list::ListCommand),
+
+    /// Upgrades globally installed packages to their latest versions
+    Upgrade(upgrade::UpgradeCommand),
+}
+
+impl GlobalCommands {
+    pub fn run(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+        update_task: &mut Option<JoinHandle<()>>,
+    ) -> anyhow::Result<()> {
+        let globals = globals_project(&project)?;
+
+        match self {
+            GlobalCommands::Add(add) => add.run(globals, multi, reqwest, update_task),
+            GlobalCommands::Remove(remove) => remove.run(globals, multi, reqwest, update_task),
+            GlobalCommands::List(list) => list.run(globals),
+            GlobalCommands::Upgrade(upgrade) => upgrade.run(globals, multi, reqwest, update_task),
+        }
+    }
+}
+
+/// Returns the project the globally installed packages are dependencies of, creating it if needed
+fn globals_project(project: &Project) -> anyhow::Result<Project> {
+    let dir = globals_dir()?;
+    let manifest_path = dir.join(MANIFEST_FILE_NAME);
+
+    if !manifest_path.exists() {
+        std::fs::create_dir_all(&dir).context("failed to create globals directory")?;
+
+        let manifest = format!(
+            r#"name = "pesde/globals"
+version = "0.0.0"
+private = true
+
+[target]
+environment = "lune"
+
+[indices]
+default = "{}"
+"#,
+            read_config()?.default_index.to_bstring()
+        );
+
+        std::fs::write(&manifest_path, manifest).context("failed to write globals manifest")?;
+    }
+
+    // the globals live in the home directory, so they use its CAS to be able to hard link
+    Ok(Project::new(
+        &dir,
+        None::<&Path>,
+        project.data_dir(),
+        project.data_dir().join("cas"),
+        project.auth_config().clone(),
+    )
+    .with_offline(project.offline())
+    .with_trust_policy(project.trust_policy())
+    .with_segmented_downloads(project.segmented_downloads())
+    .with_retry_policy(project.retry_policy())
+    .with_lenient_index(project.lenient_index()))
+}
@@ -0,0 +1,88 @@
+use crate::cli::{
+    bin_dir, commands::install::InstallCommand, messages::msg, shims::remove_bin_shims,
+};
+use anyhow::Context;
+use clap::Args;
+use indicatif::MultiProgress;
+use pesde::{source::specifiers::DependencySpecifiers, Project};
+use std::{str::FromStr, thread::JoinHandle};
+
+#[derive(Debug, Args)]
+pub struct RemoveCommand {
+    /// The alias or name of the package to remove
+    #[arg(index = 1)]
+    name: String,
+}
+
+impl RemoveCommand {
+    pub fn run(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+        update_task: &mut Option<JoinHandle<()>>,
+    ) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read globals manifest")?;
+
+        let alias = manifest
+            .dependencies
+            .iter()
+            .find(|(alias, spec)| {
+                **alias == self.name
+                    || matches!(spec, DependencySpecifiers::Pesde(spec) if spec.name.to_string() == self.name)
+            })
+            .map(|(alias, _)| alias.clone())
+            .with_context(|| format!("no global package named {} found", self.name))?;
+
+        let mut document = toml_edit::DocumentMut::from_str(
+            &project
+                .read_manifest()
+                .context("failed to read globals manifest")?,
+        )
+        .context("failed to parse globals manifest")?;
+
+        if let Some(dependencies) = document
+            .get_mut("dependencies")
+            .and_then(|item| item.as_table_like_mut())
+        {
+            dependencies.remove(&alias);
+        }
+
+        project
+            .write_manifest(document.to_string())
+            .context("failed to write globals manifest")?;
+
+        let bin_dir = bin_dir()?;
+        remove_bin_shims(&bin_dir, &alias)?;
+
+        // the named bin exports have shims of their own
+        if let Ok(lockfile) = project.deser_lockfile() {
+            for node in lockfile
+                .graph
+                .values()
+                .flat_map(|versions| versions.values())
+            {
+                if node.node.direct.as_ref().is_none_or(|(a, _)| *a != alias) {
+                    continue;
+                }
+
+                for name in node
+                    .target
+                    .named_bins()
+                    .into_iter()
+                    .flat_map(|bins| bins.keys())
+                {
+                    remove_bin_shims(&bin_dir, name)?;
+                }
+            }
+        }
+
+        InstallCommand::default().run(project, multi, reqwest, update_task)?;
+
+        println!("{}", msg!("global.removed", alias = alias));
+
+        Ok(())
+    }
+}
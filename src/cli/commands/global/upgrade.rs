@@ -0,0 +1,109 @@
+use crate::cli::{commands::install::InstallCommand, messages::msg};
+use anyhow::Context;
+use clap::Args;
+use indicatif::MultiProgress;
+use pesde::{
+    manifest::target::TargetKind,
+    source::{
+        pesde::{specifier::PesdeDependencySpecifier, PesdePackageSource},
+        specifiers::DependencySpecifiers,
+        traits::PackageSource,
+    },
+    Project, DEFAULT_INDEX_NAME,
+};
+use semver::VersionReq;
+use std::{str::FromStr, thread::JoinHandle};
+
+#[derive(Debug, Args)]
+pub struct UpgradeCommand {
+    /// The alias of the package to upgrade, or all packages if not specified
+    #[arg(index = 1)]
+    alias: Option<String>,
+}
+
+impl UpgradeCommand {
+    pub fn run(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+        update_task: &mut Option<JoinHandle<()>>,
+    ) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read globals manifest")?;
+
+        if let Some(alias) = &self.alias {
+            if !manifest.dependencies.contains_key(alias) {
+                anyhow::bail!("no global package named {alias} found");
+            }
+        }
+
+        let index = manifest
+            .indices
+            .get(DEFAULT_INDEX_NAME)
+            .context("globals manifest has no default index")?
+            .clone();
+        let source = PesdePackageSource::new(index);
+        source
+            .refresh(&project)
+            .context("failed to refresh source")?;
+
+        let mut document = toml_edit::DocumentMut::from_str(
+            &project
+                .read_manifest()
+                .context("failed to read globals manifest")?,
+        )
+        .context("failed to parse globals manifest")?;
+
+        for (alias, specifier) in &manifest.dependencies {
+            if self.alias.as_ref().is_some_and(|a| a != alias) {
+                continue;
+            }
+
+            let DependencySpecifiers::Pesde(specifier) = specifier else {
+                continue;
+            };
+
+            let target = specifier.target.unwrap_or(TargetKind::Lune);
+            let Some((version_id, _)) = source
+                .resolve(
+                    &PesdeDependencySpecifier {
+                        version: VersionReq::STAR,
+                        ..specifier.clone()
+                    },
+                    &project,
+                    target,
+                )
+                .context("failed to resolve package")?
+                .1
+                .pop_last()
+            else {
+                continue;
+            };
+
+            if specifier.version.to_string() == format!("^{}", version_id.version()) {
+                continue;
+            }
+
+            document["dependencies"][alias]["version"] =
+                toml_edit::value(format!("^{}", version_id.version()));
+
+            println!(
+                "{}",
+                msg!(
+                    "global.upgraded",
+                    alias = alias,
+                    from = specifier.version,
+                    to = version_id.version()
+                )
+            );
+        }
+
+        project
+            .write_manifest(document.to_string())
+            .context("failed to write globals manifest")?;
+
+        InstallCommand::default().run(project, multi, reqwest, update_task)
+    }
+}
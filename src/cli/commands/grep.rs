@@ -0,0 +1,146 @@
+use crate::cli::{messages::msg, up_to_date_lockfile};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    manifest::target::TargetKind, source::traits::PackageRef, Project, PACKAGES_CONTAINER_NAME,
+};
+use regex::RegexBuilder;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Args)]
+pub struct GrepCommand {
+    /// The regular expression to search for
+    #[arg(index = 1)]
+    pattern: String,
+
+    /// Also search the dependencies of direct dependencies
+    #[arg(long)]
+    deps: bool,
+
+    /// Whether to search case-insensitively
+    #[arg(short, long)]
+    ignore_case: bool,
+}
+
+const SOURCE_EXTENSIONS: &[&str] = &["luau", "lua"];
+
+fn source_files(dir: &Path, skipped: &BTreeSet<String>) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        if entry.file_type()?.is_dir() {
+            // linked dependencies of the package would be searched twice
+            if name.starts_with('.') || skipped.contains(&name) {
+                continue;
+            }
+
+            files.extend(source_files(&path, skipped)?);
+        } else if path
+            .extension()
+            .is_some_and(|ext| SOURCE_EXTENSIONS.iter().any(|e| ext == *e))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+impl GrepCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let regex = RegexBuilder::new(&self.pattern)
+            .case_insensitive(self.ignore_case)
+            .build()
+            .context("invalid pattern")?;
+
+        let graph = if let Some(lockfile) = up_to_date_lockfile(&project)? {
+            lockfile.graph
+        } else {
+            anyhow::bail!("outdated lockfile, please run the install command first")
+        };
+
+        let project_target = project
+            .deser_manifest()
+            .context("failed to read manifest")?
+            .target
+            .kind();
+
+        let packages_folders = TargetKind::VARIANTS
+            .iter()
+            .flat_map(|a| TargetKind::VARIANTS.iter().map(|b| a.packages_folder(b)))
+            .collect::<BTreeSet<_>>();
+
+        let mut matches = 0usize;
+
+        for (name, versions) in &graph {
+            for (version_id, node) in versions {
+                if !self.deps && node.node.direct.is_none() {
+                    continue;
+                }
+
+                let container_folder = node.node.container_folder(
+                    &project
+                        .package_dir()
+                        .join(project_target.packages_folder(&node.node.pkg_ref.target_kind()))
+                        .join(PACKAGES_CONTAINER_NAME),
+                    name,
+                    version_id.version(),
+                );
+
+                let files = match source_files(&container_folder, &packages_folders) {
+                    Ok(files) => files,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        log::warn!("{name}@{version_id} is not installed, skipping");
+                        continue;
+                    }
+                    Err(e) => {
+                        return Err(e)
+                            .context(format!("failed to read files of {name}@{version_id}"))
+                    }
+                };
+
+                for file in files {
+                    let contents = match std::fs::read_to_string(&file) {
+                        Ok(contents) => contents,
+                        Err(e) if e.kind() == std::io::ErrorKind::InvalidData => continue,
+                        Err(e) => {
+                            return Err(e).context(format!("failed to read {}", file.display()))
+                        }
+                    };
+
+                    let relative = file.strip_prefix(&container_folder).unwrap_or(&file);
+
+                    for (number, line) in contents.lines().enumerate() {
+                        if !regex.is_match(line) {
+                            continue;
+                        }
+
+                        matches += 1;
+
+                        println!(
+                            "{} {}:{}: {}",
+                            format!("{name}@{}", version_id.version()).cyan(),
+                            relative.display().to_string().magenta(),
+                            (number + 1).to_string().green(),
+                            line.trim()
+                        );
+                    }
+                }
+            }
+        }
+
+        if matches == 0 {
+            println!("{}", msg!("grep.none").red());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use anyhow::Context;
+use clap::Args;
+use relative_path::RelativePathBuf;
+use sha2::{Digest, Sha256};
+
+use pesde::{
+    manifest::migration::{convert_legacy_manifest, migrate, LEGACY_MANIFEST_FILE_NAME},
+    Project, MANIFEST_FILE_NAME,
+};
+
+#[derive(Debug, Args)]
+pub struct HashCommand {
+    /// Whether to print the inputs which were hashed
+    #[arg(short, long)]
+    list: bool,
+}
+
+/// The manifest in the directory, serialized so that formatting and comments don't affect it
+fn canonical_manifest(dir: &Path) -> anyhow::Result<String> {
+    let table = match std::fs::read_to_string(dir.join(MANIFEST_FILE_NAME)) {
+        Ok(contents) => {
+            let mut table = toml::from_str::<toml::Table>(&contents)
+                .with_context(|| format!("failed to parse manifest in {}", dir.display()))?;
+            migrate(&mut table);
+            table
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let contents = std::fs::read_to_string(dir.join(LEGACY_MANIFEST_FILE_NAME))
+                .with_context(|| format!("failed to read manifest in {}", dir.display()))?;
+            convert_legacy_manifest(&contents)
+                .with_context(|| format!("failed to parse legacy manifest in {}", dir.display()))?
+                .0
+        }
+        Err(e) => {
+            return Err(e).with_context(|| format!("failed to read manifest in {}", dir.display()))
+        }
+    };
+
+    toml::to_string(&table).context("failed to serialize manifest")
+}
+
+impl HashCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        // members are installed by their workspace, so it is what decides their packages
+        let project = match project.workspace_dir() {
+            Some(workspace_dir) => project.for_member(workspace_dir),
+            None => project,
+        };
+
+        let mut inputs = vec![
+            (
+                "pesde version".to_string(),
+                env!("CARGO_PKG_VERSION").to_string(),
+            ),
+            (
+                MANIFEST_FILE_NAME.to_string(),
+                canonical_manifest(project.package_dir())?,
+            ),
+            (
+                pesde::PINS_FILE_NAME.to_string(),
+                toml::to_string(&project.deser_pins().context("failed to read pins")?)
+                    .context("failed to serialize pins")?,
+            ),
+        ];
+
+        let mut members = project
+            .workspace_members(project.package_dir())
+            .context("failed to get workspace members")?
+            .into_keys()
+            .map(|path| {
+                let relative =
+                    RelativePathBuf::from_path(path.strip_prefix(project.package_dir()).unwrap())
+                        .unwrap();
+                (relative, path)
+            })
+            .collect::<Vec<_>>();
+        members.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (relative, path) in members {
+            inputs.push((
+                relative.join(MANIFEST_FILE_NAME).to_string(),
+                canonical_manifest(&path)?,
+            ));
+        }
+
+        let mut hasher = Sha256::new();
+        for (name, contents) in &inputs {
+            // lengths prefix every part, so moving bytes between inputs changes the hash
+            for part in [name, contents] {
+                hasher.update((part.len() as u64).to_le_bytes());
+                hasher.update(part.as_bytes());
+            }
+
+            if self.list {
+                eprintln!("{name}");
+            }
+        }
+
+        println!("{:x}", hasher.finalize());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use crate::cli::{
+    commands::index::{package_files, scopes},
+    messages::msg,
+};
+use anyhow::Context;
+use clap::Args;
+use pesde::source::pesde::{IndexFile, ScopeInfo, SCOPE_INFO_FILE};
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Args)]
+pub struct CompactCommand {
+    /// The directory of the index repository
+    #[arg(index = 1, default_value = ".")]
+    dir: PathBuf,
+}
+
+/// Rewrites the file in its normalized form, returning whether it changed
+fn normalize<T: Serialize + DeserializeOwned>(path: &Path) -> anyhow::Result<bool> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    let parsed: T = match toml::from_str(&contents) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            log::warn!(
+                "skipping {}, which failed to parse: {e}. run `pesde index validate` for details",
+                path.display()
+            );
+            return Ok(false);
+        }
+    };
+
+    let normalized = toml::to_string(&parsed)
+        .with_context(|| format!("failed to serialize {}", path.display()))?;
+
+    if normalized == contents {
+        return Ok(false);
+    }
+
+    std::fs::write(path, normalized)
+        .with_context(|| format!("failed to write {}", path.display()))?;
+
+    Ok(true)
+}
+
+impl CompactCommand {
+    pub fn run(self) -> anyhow::Result<()> {
+        let mut changed = 0;
+
+        for (_, scope_dir) in scopes(&self.dir)? {
+            let scope_info = scope_dir.join(SCOPE_INFO_FILE);
+            if scope_info.exists() && normalize::<ScopeInfo>(&scope_info)? {
+                changed += 1;
+            }
+
+            for (_, path) in package_files(&scope_dir)? {
+                if normalize::<IndexFile>(&path)? {
+                    log::debug!("normalized {}", path.display());
+                    changed += 1;
+                }
+            }
+        }
+
+        println!("{}", msg!("index.compacted", count = changed));
+
+        Ok(())
+    }
+}
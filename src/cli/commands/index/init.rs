@@ -0,0 +1,94 @@
+use crate::cli::messages::msg;
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use inquire::validator::Validation;
+use std::path::PathBuf;
+
+#[derive(Debug, Args)]
+pub struct InitCommand {
+    /// The directory of the index repository
+    #[arg(index = 1, default_value = ".")]
+    dir: PathBuf,
+}
+
+impl InitCommand {
+    pub fn run(self) -> anyhow::Result<()> {
+        let path = self.dir.join("config.toml");
+
+        if path.exists() {
+            println!("{}", msg!("index.already_initialized").red());
+            return Ok(());
+        }
+
+        let mut config = toml_edit::DocumentMut::new();
+
+        config["api"] = toml_edit::value(
+            inquire::Text::new("What is the URL of the registry API?")
+                .with_validator(|url: &str| {
+                    Ok(match url::Url::parse(url) {
+                        Ok(_) => Validation::Valid,
+                        Err(e) => Validation::Invalid(e.to_string().into()),
+                    })
+                })
+                .prompt()
+                .unwrap(),
+        );
+
+        let download = inquire::Text::new(
+            "What URL should packages be downloaded from? (leave empty to download from the API)",
+        )
+        .with_help_message(
+            "{API_URL}, {PACKAGE}, {PACKAGE_VERSION} and {PACKAGE_TARGET} are replaced",
+        )
+        .prompt()
+        .unwrap();
+
+        if !download.is_empty() {
+            config["download"] = toml_edit::value(download);
+        }
+
+        for (key, question) in [
+            (
+                "git_allowed",
+                "Should packages be allowed to depend on Git repositories?",
+            ),
+            (
+                "other_registries_allowed",
+                "Should packages be allowed to depend on packages from other registries?",
+            ),
+            (
+                "wally_allowed",
+                "Should packages be allowed to depend on Wally packages?",
+            ),
+        ] {
+            if inquire::Confirm::new(question)
+                .with_default(false)
+                .prompt()
+                .unwrap()
+            {
+                config[key] = toml_edit::value(true);
+            }
+        }
+
+        let github_oauth_client_id = inquire::Text::new(
+            "What is the client ID of the GitHub OAuth app used to log in? (leave empty for none)",
+        )
+        .prompt()
+        .unwrap();
+
+        if !github_oauth_client_id.is_empty() {
+            config["github_oauth_client_id"] = toml_edit::value(github_oauth_client_id);
+        }
+
+        std::fs::create_dir_all(&self.dir).context("failed to create index directory")?;
+        std::fs::write(&path, config.to_string()).context("failed to write index config")?;
+
+        println!(
+            "{}",
+            msg!("index.initialized", path = path.display()).green()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,81 @@
+use anyhow::Context;
+use clap::Subcommand;
+use pesde::source::pesde::SCOPE_INFO_FILE;
+use std::path::{Path, PathBuf};
+
+mod compact;
+mod init;
+mod validate;
+
+#[derive(Debug, Subcommand)]
+pub enum IndexCommands {
+    /// Checks every file of an index repository for problems
+    Validate(validate::ValidateCommand),
+
+    /// Rewrites the package files of an index repository in their normalized form
+    Compact(compact::CompactCommand),
+
+    /// Creates the config file of a new index repository
+    Init(init::InitCommand),
+}
+
+impl IndexCommands {
+    pub fn run(self) -> anyhow::Result<()> {
+        match self {
+            IndexCommands::Validate(validate) => validate.run(),
+            IndexCommands::Compact(compact) => compact.run(),
+            IndexCommands::Init(init) => init.run(),
+        }
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// The scope directories of an index repository, by name
+fn scopes(dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut scopes = vec![];
+
+    for entry in std::fs::read_dir(dir)
+        .with_context(|| format!("failed to read index directory {}", dir.display()))?
+    {
+        let path = entry
+            .context("failed to read index directory entry")?
+            .path();
+
+        if path.is_dir() && !is_hidden(&path) {
+            scopes.push((
+                path.file_name().unwrap().to_string_lossy().to_string(),
+                path,
+            ));
+        }
+    }
+
+    scopes.sort();
+
+    Ok(scopes)
+}
+
+/// The package files of a scope directory, by package name
+fn package_files(scope_dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut files = vec![];
+
+    for entry in std::fs::read_dir(scope_dir)
+        .with_context(|| format!("failed to read scope directory {}", scope_dir.display()))?
+    {
+        let path = entry
+            .context("failed to read scope directory entry")?
+            .path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+        if path.is_file() && !is_hidden(&path) && name != SCOPE_INFO_FILE {
+            files.push((name, path));
+        }
+    }
+
+    files.sort();
+
+    Ok(files)
+}
@@ -0,0 +1,232 @@
+use crate::cli::{
+    commands::index::{package_files, scopes},
+    messages::msg,
+};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    names::PackageName,
+    source::{
+        pesde::{IndexConfig, IndexFile, ScopeInfo, SCOPE_INFO_FILE},
+        specifiers::DependencySpecifiers,
+    },
+};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Args)]
+pub struct ValidateCommand {
+    /// The directory of the index repository
+    #[arg(index = 1, default_value = ".")]
+    dir: PathBuf,
+}
+
+#[derive(Debug, Default)]
+struct Issues {
+    errors: usize,
+    warnings: usize,
+}
+
+impl Issues {
+    fn error(&mut self, path: &Path, message: impl Display) {
+        self.errors += 1;
+        println!("{} {}: {message}", "error".red().bold(), path.display());
+    }
+
+    fn warning(&mut self, path: &Path, message: impl Display) {
+        self.warnings += 1;
+        println!(
+            "{} {}: {message}",
+            "warning".yellow().bold(),
+            path.display()
+        );
+    }
+}
+
+fn is_url(s: &str) -> bool {
+    url::Url::parse(s).is_ok()
+}
+
+fn validate_config(dir: &Path, issues: &mut Issues) -> anyhow::Result<()> {
+    let path = dir.join("config.toml");
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            issues.error(&path, "missing, create it with `pesde index init`");
+            return Ok(());
+        }
+        Err(e) => return Err(e).context("failed to read index config"),
+    };
+
+    let config: IndexConfig = match toml::from_str(&contents) {
+        Ok(config) => config,
+        Err(e) => {
+            issues.error(&path, format!("invalid config: {e}"));
+            return Ok(());
+        }
+    };
+
+    for url in config.download_urls() {
+        let example = url
+            .replace("{PACKAGE}", "scope%2Fname")
+            .replace("{PACKAGE_VERSION}", "1.0.0")
+            .replace("{PACKAGE_TARGET}", "luau");
+
+        if !is_url(&example) {
+            issues.error(&path, format!("invalid download URL {url}"));
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_package(scope: &str, name: &str, path: &Path, issues: &mut Issues) {
+    if let Err(e) = format!("{scope}/{name}").parse::<PackageName>() {
+        issues.error(path, format!("invalid package name: {e}"));
+    }
+
+    let file: IndexFile = match std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| toml::from_str(&contents).map_err(|e| e.to_string()))
+    {
+        Ok(file) => file,
+        Err(e) => {
+            issues.error(path, format!("invalid package file: {e}"));
+            return;
+        }
+    };
+
+    if file.is_empty() {
+        issues.warning(path, "package has no versions");
+    }
+
+    // versions only differing in build metadata can't be told apart by most tooling
+    let mut versions = BTreeMap::<_, Vec<_>>::new();
+
+    for (version_id, entry) in &file {
+        let version = version_id.version();
+        versions
+            .entry((
+                (
+                    version.major,
+                    version.minor,
+                    version.patch,
+                    version.pre.clone(),
+                ),
+                *version_id.target(),
+            ))
+            .or_default()
+            .push(version_id);
+
+        if entry.target.kind() != *version_id.target() {
+            issues.error(
+                path,
+                format!(
+                    "{version_id} has target {}, which doesn't match its key",
+                    entry.target.kind()
+                ),
+            );
+        }
+
+        for (alias, (specifier, _)) in &entry.dependencies {
+            match specifier {
+                DependencySpecifiers::Pesde(specifier) => {
+                    if !specifier.index.as_deref().is_some_and(is_url) {
+                        issues.error(
+                            path,
+                            format!("{version_id} dependency {alias} has an invalid index URL"),
+                        );
+                    }
+                }
+                #[cfg(feature = "wally-compat")]
+                DependencySpecifiers::Wally(specifier) => {
+                    if !specifier.index.as_deref().is_some_and(is_url) {
+                        issues.error(
+                            path,
+                            format!("{version_id} dependency {alias} has an invalid index URL"),
+                        );
+                    }
+                }
+                DependencySpecifiers::Git(_) => {}
+                DependencySpecifiers::Workspace(_) | DependencySpecifiers::Path(_) => {
+                    issues.error(
+                        path,
+                        format!("{version_id} dependency {alias} can't be resolved from an index"),
+                    );
+                }
+            }
+        }
+    }
+
+    for version_ids in versions
+        .values()
+        .filter(|version_ids| version_ids.len() > 1)
+    {
+        issues.error(
+            path,
+            format!(
+                "duplicate versions {}",
+                version_ids
+                    .iter()
+                    .map(|version_id| version_id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        );
+    }
+}
+
+impl ValidateCommand {
+    pub fn run(self) -> anyhow::Result<()> {
+        let mut issues = Issues::default();
+
+        validate_config(&self.dir, &mut issues)?;
+
+        for (scope, scope_dir) in scopes(&self.dir)? {
+            let packages = package_files(&scope_dir)?;
+
+            let scope_info = scope_dir.join(SCOPE_INFO_FILE);
+            match std::fs::read_to_string(&scope_info) {
+                Ok(contents) => match toml::from_str::<ScopeInfo>(&contents) {
+                    Ok(info) if info.owners.is_empty() => {
+                        issues.warning(&scope_info, "scope has no owners")
+                    }
+                    Ok(_) => {}
+                    Err(e) => issues.error(&scope_info, format!("invalid scope info: {e}")),
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    issues.error(&scope_dir, "orphaned scope, it has no scope info file")
+                }
+                Err(e) => return Err(e).context("failed to read scope info"),
+            }
+
+            if packages.is_empty() {
+                issues.warning(&scope_dir, "scope has no packages");
+            }
+
+            for (name, path) in packages {
+                validate_package(&scope, &name, &path, &mut issues);
+            }
+        }
+
+        println!(
+            "{}",
+            msg!(
+                "index.validated",
+                errors = issues.errors,
+                warnings = issues.warnings
+            )
+        );
+
+        if issues.errors > 0 {
+            anyhow::bail!("index has {} errors", issues.errors);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use crate::cli::{config::read_config, messages::msg, VersionedPackageName};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    names::PackageName,
+    registry_client::RegistryClient,
+    source::{
+        pesde::{IndexFileEntry, PesdePackageSource},
+        traits::PackageSource,
+    },
+    Project, DEFAULT_INDEX_NAME,
+};
+use semver::Version;
+use serde::Serialize;
+
+#[derive(Debug, Args)]
+pub struct InfoCommand {
+    /// The package to show, defaulting to its latest version
+    #[arg(index = 1)]
+    package: VersionedPackageName<Version, PackageName>,
+
+    /// The index URL the package is in, defaulting to the project's default index
+    #[arg(short, long, value_parser = crate::cli::parse_gix_url)]
+    index: Option<gix::Url>,
+
+    /// Whether to also show the package's readme, which is fetched from the registry
+    #[arg(short, long)]
+    readme: bool,
+
+    /// Whether to output the information as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct PackageInfo {
+    name: PackageName,
+    version: Version,
+    /// The entries of every target of the version
+    targets: Vec<IndexFileEntry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    readme: Option<String>,
+}
+
+impl InfoCommand {
+    pub fn run(self, project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
+        let index_url = match self.index {
+            Some(index) => index,
+            None => match project
+                .deser_manifest()
+                .ok()
+                .and_then(|manifest| manifest.indices.get(DEFAULT_INDEX_NAME).cloned())
+            {
+                Some(index) => index,
+                None => read_config()?.default_index,
+            },
+        };
+
+        let source = PesdePackageSource::new(index_url.clone());
+        source
+            .refresh(&project)
+            .context("failed to refresh source")?;
+
+        let VersionedPackageName(name, version) = self.package;
+
+        let file = source
+            .index_file(&name, &project)
+            .context("failed to read index file")?
+            .with_context(|| format!("package {name} not found"))?;
+
+        // without a version, the latest one is shown, preferring ones which aren't yanked
+        let Some(version) = version.or_else(|| {
+            file.iter()
+                .filter(|(_, entry)| !entry.yanked)
+                .map(|(version_id, _)| version_id)
+                .max()
+                .or_else(|| file.keys().max())
+                .map(|version_id| version_id.version().clone())
+        }) else {
+            anyhow::bail!("package {name} has no versions");
+        };
+
+        let entries = file
+            .into_iter()
+            .filter(|(version_id, _)| *version_id.version() == version)
+            .collect::<Vec<_>>();
+        let Some((first_version_id, _)) = entries.first() else {
+            anyhow::bail!("version {version} of {name} not found");
+        };
+
+        let readme = if self.readme {
+            let config = source
+                .config(&project)
+                .context("failed to read index config")?;
+
+            RegistryClient::new(&project, &reqwest, &index_url, &config)
+                .readme(&name, first_version_id)
+                .context("failed to get readme")?
+        } else {
+            None
+        };
+
+        let info = PackageInfo {
+            name,
+            version,
+            targets: entries.into_iter().map(|(_, entry)| entry).collect(),
+            readme,
+        };
+
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&info).context("failed to serialize info")?
+            );
+            return Ok(());
+        }
+
+        // the metadata is the same for every target, as they're published together
+        let entry = &info.targets[0];
+
+        print!("{}@{}", info.name.to_string().bold(), info.version);
+        if info.targets.iter().any(|entry| entry.yanked) {
+            print!(" {}", "(yanked)".red());
+        }
+        println!();
+
+        if let Some(description) = &entry.description {
+            println!("{description}");
+        }
+        println!();
+
+        let field = |label: &str, value: String| {
+            if !value.is_empty() {
+                println!("{} {value}", format!("{label}:").dimmed());
+            }
+        };
+
+        field("license", entry.license.clone().unwrap_or_default());
+        field("authors", entry.authors.join(", "));
+        field(
+            "repository",
+            entry
+                .repository
+                .as_ref()
+                .map(|url| {
+                    if entry.repository_verified {
+                        format!("{url} (verified)")
+                    } else {
+                        url.to_string()
+                    }
+                })
+                .unwrap_or_default(),
+        );
+        field(
+            "targets",
+            info.targets
+                .iter()
+                .map(|entry| entry.target.kind().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        field(
+            "keywords",
+            entry
+                .keywords
+                .iter()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        field(
+            "tested lune",
+            entry
+                .tested_lune
+                .iter()
+                .map(|version| version.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        );
+        field(
+            "published",
+            entry.published_at.format("%Y-%m-%d %H:%M UTC").to_string(),
+        );
+
+        if !entry.dependencies.is_empty() {
+            println!("\n{}", "dependencies:".dimmed());
+            for (alias, (specifier, ty)) in &entry.dependencies {
+                println!("  {} {specifier} ({ty:?})", alias.bold());
+            }
+        }
+
+        if self.readme {
+            match &info.readme {
+                Some(readme) => println!("\n{readme}"),
+                None => println!("\n{}", msg!("info.no_readme")),
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -9,7 +9,7 @@ use pesde::{
     errors::ManifestReadError, names::PackageName, scripts::ScriptName, Project, DEFAULT_INDEX_NAME,
 };
 
-use crate::cli::{config::read_config, HOME_DIR};
+use crate::cli::{config::read_config, messages::msg, HOME_DIR};
 
 #[derive(Debug, Args)]
 pub struct InitCommand {}
@@ -28,7 +28,7 @@ impl InitCommand {
     pub fn run(self, project: Project) -> anyhow::Result<()> {
         match project.read_manifest() {
             Ok(_) => {
-                println!("{}", "project already initialized".red());
+                println!("{}", msg!("init.already_initialized").red());
                 return Ok(());
             }
             Err(ManifestReadError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {}
@@ -170,7 +170,7 @@ impl InitCommand {
 
         project.write_manifest(manifest.to_string())?;
 
-        println!("{}", "initialized project".green());
+        println!("{}", msg!("init.done").green());
         Ok(())
     }
 }
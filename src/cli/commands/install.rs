@@ -1,21 +1,180 @@
 use crate::cli::{
-    bin_dir, download_graph, files::make_executable, run_on_workspace_members, up_to_date_lockfile,
+    bin_dir,
+    config::{read_config, write_config, ScriptPolicyKind},
+    download_graph, find_advisories,
+    messages::msg,
+    print_advisories, run_on_workspace_members,
+    shims::write_bin_shims,
+    up_to_date_lockfile,
 };
 use anyhow::Context;
 use clap::Args;
 use colored::{ColoredString, Colorize};
 use indicatif::MultiProgress;
 use pesde::{
-    lockfile::Lockfile,
-    manifest::{target::TargetKind, DependencyType},
-    Project, MANIFEST_FILE_NAME,
+    events::{Event, EventHandler},
+    linking::{generator::bin_linking_module_name, require_aliases, sourcemap::packages_sourcemap},
+    lockfile::{direct_dependency_subgraph, DependencyGraph, DownloadedGraph, Lockfile},
+    manifest::{target::TargetKind, DependencyType, Manifest, RequireAliases},
+    scripts::{graph_scripts, ScriptName, ScriptPolicy},
+    source::PackageSources,
+    Project, PACKAGES_CONTAINER_NAME,
 };
+use relative_path::RelativePathBuf;
 use std::{
-    collections::{BTreeSet, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
+    io::IsTerminal,
+    path::PathBuf,
     thread::JoinHandle,
 };
 
-#[derive(Debug, Args, Copy, Clone)]
+/// Writes the sourcemap of the installed Roblox packages to the file configured in the manifest
+fn write_sourcemap(project: &Project, manifest: &Manifest) -> anyhow::Result<()> {
+    let Some(path) = &manifest.sourcemap else {
+        return Ok(());
+    };
+
+    if manifest.target.build_files().is_none() {
+        log::warn!("sourcemaps are only written for Roblox projects");
+        return Ok(());
+    }
+
+    let sourcemap =
+        packages_sourcemap(project, manifest).context("failed to build packages sourcemap")?;
+
+    let path = path.to_path(project.package_dir());
+    std::fs::write(
+        &path,
+        serde_json::to_string(&sourcemap).context("failed to serialize sourcemap")?,
+    )
+    .with_context(|| format!("failed to write sourcemap to {}", path.display()))
+}
+
+/// Writes the require aliases of the dependencies to the files configured in the manifest
+fn write_require_aliases(
+    project: &Project,
+    manifest: &Manifest,
+    graph: &DownloadedGraph,
+) -> anyhow::Result<()> {
+    let RequireAliases { map, darklua } = &manifest.require_aliases;
+    if map.is_none() && darklua.is_none() {
+        return Ok(());
+    }
+
+    let aliases = require_aliases(manifest, graph)
+        .into_iter()
+        .map(|(alias, path)| (alias, format!("./{path}")))
+        .collect::<BTreeMap<_, _>>();
+
+    let write = |path: &RelativePathBuf, value: serde_json::Value| {
+        let path = path.to_path(project.package_dir());
+        std::fs::write(&path, format!("{value:#}\n"))
+            .with_context(|| format!("failed to write require aliases to {}", path.display()))
+    };
+
+    if let Some(path) = map {
+        write(path, serde_json::json!(aliases))?;
+    }
+
+    if let Some(path) = darklua {
+        let target = if manifest.target.build_files().is_some() {
+            serde_json::json!({
+                "name": "roblox",
+                "rojo_sourcemap": manifest
+                    .sourcemap
+                    .as_ref()
+                    .map_or_else(|| "./sourcemap.json".to_string(), |path| format!("./{path}")),
+            })
+        } else {
+            serde_json::json!({ "name": "path" })
+        };
+
+        write(
+            path,
+            serde_json::json!({
+                "rules": [{
+                    "rule": "convert_require",
+                    "current": {
+                        "name": "path",
+                        "sources": aliases
+                            .iter()
+                            .map(|(alias, path)| (format!("@{alias}"), path))
+                            .collect::<BTreeMap<_, _>>(),
+                    },
+                    "target": target,
+                }],
+            }),
+        )?;
+    }
+
+    Ok(())
+}
+
+const ALLOW_ONCE: &str = "allow";
+const ALLOW_ALWAYS: &str = "always allow";
+const DENY_ONCE: &str = "deny";
+const DENY_ALWAYS: &str = "always deny";
+
+/// Asks whether the manifest's scripts may be run for the packages of the graph without a decision
+/// yet, remembering the decisions which should be in the config
+fn confirm_scripts(
+    project: Project,
+    manifest: &Manifest,
+    graph: &DependencyGraph,
+) -> anyhow::Result<Project> {
+    let mut config = read_config()?;
+    if config.script_policy != ScriptPolicyKind::Prompt || !std::io::stdin().is_terminal() {
+        return Ok(project);
+    }
+
+    let pending = graph_scripts(manifest, graph)
+        .into_iter()
+        .filter(|(package, _)| !project.script_policy().is_decided(package))
+        .collect::<Vec<_>>();
+    if pending.is_empty() {
+        return Ok(project);
+    }
+
+    println!("{}", msg!("install.scripts").yellow());
+    for (package, scripts) in &pending {
+        println!(
+            "  {package}: {}",
+            scripts
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    let mut policy = project.script_policy().clone();
+    let mut remembered = false;
+
+    for (package, _) in pending {
+        let choice = inquire::Select::new(
+            &format!("run scripts for {package}?"),
+            vec![ALLOW_ONCE, ALLOW_ALWAYS, DENY_ONCE, DENY_ALWAYS],
+        )
+        .prompt()
+        .context("failed to prompt")?;
+
+        let allowed = choice == ALLOW_ONCE || choice == ALLOW_ALWAYS;
+        if choice == ALLOW_ALWAYS || choice == DENY_ALWAYS {
+            config.script_permissions.insert(package.clone(), allowed);
+            remembered = true;
+        }
+
+        policy = policy.with_decision(package, allowed);
+    }
+
+    if remembered {
+        write_config(&config).context("failed to write config")?;
+    }
+
+    Ok(project.with_script_policy(policy))
+}
+
+#[derive(Debug, Args, Clone)]
 pub struct InstallCommand {
     /// The amount of threads to use for downloading
     #[arg(short, long, default_value_t = 6, value_parser = clap::value_parser!(u64).range(1..=128))]
@@ -25,60 +184,54 @@ pub struct InstallCommand {
     #[arg(long)]
     locked: bool,
 
+    /// Whether to error on changes in the lockfile and not refresh indices, using their existing clones
+    #[arg(long)]
+    frozen: bool,
+
     /// Whether to not install dev dependencies
     #[arg(long)]
     prod: bool,
-}
 
-fn bin_link_file(alias: &str) -> String {
-    let mut all_combinations = BTreeSet::new();
+    /// Only install the direct dependency with this alias and its dependencies, without writing the lockfile
+    #[arg(long, value_name = "ALIAS")]
+    only: Option<String>,
+
+    /// Resolve the workspace and all of its members as one graph, sharing the versions of their common dependencies
+    #[arg(long, conflicts_with = "only")]
+    unified: bool,
 
-    for a in TargetKind::VARIANTS {
-        for b in TargetKind::VARIANTS {
-            all_combinations.insert((a, b));
+    /// The features of the manifest whose optional dependencies to install
+    #[arg(long = "with", value_name = "FEATURE")]
+    features: Vec<String>,
+
+    /// Whether to not run the manifest's lifecycle hook scripts, for untrusted projects
+    #[arg(long)]
+    ignore_hooks: bool,
+
+    /// Whether to not run the manifest's scripts for any package, such as the Roblox sync config generator
+    #[arg(long)]
+    no_scripts: bool,
+
+    /// Whether to list the files of downloaded packages which were skipped, as they are never installed
+    #[arg(long)]
+    list_skipped: bool,
+}
+
+impl Default for InstallCommand {
+    fn default() -> Self {
+        Self {
+            threads: 6,
+            locked: false,
+            frozen: false,
+            prod: false,
+            only: None,
+            unified: false,
+            features: vec![],
+            ignore_hooks: false,
+            no_scripts: false,
+            list_skipped: false,
         }
     }
-
-    let all_folders = all_combinations
-        .into_iter()
-        .map(|(a, b)| format!("{:?}", a.packages_folder(b)))
-        .collect::<BTreeSet<_>>()
-        .into_iter()
-        .collect::<Vec<_>>()
-        .join(", ");
-
-    #[cfg(not(unix))]
-    let prefix = String::new();
-    #[cfg(unix)]
-    let prefix = "#!/usr/bin/env -S lune run\n";
-    format!(
-        r#"{prefix}local process = require("@lune/process")
-local fs = require("@lune/fs")
-local stdio = require("@lune/stdio")
-
-local project_root = process.cwd
-local path_components = string.split(string.gsub(project_root, "\\", "/"), "/")
-
-for i = #path_components, 1, -1 do
-    local path = table.concat(path_components, "/", 1, i)
-    if fs.isFile(path .. "/{MANIFEST_FILE_NAME}") then
-        project_root = path
-        break
-    end
-end
-
-for _, packages_folder in {{ {all_folders} }} do
-    local path = `{{project_root}}/{{packages_folder}}/{alias}.bin.luau`
-    
-    if fs.isFile(path) then
-        require(path)
-        return
-    end
-end
-
-stdio.ewrite(stdio.color("red") .. "binary `{alias}` not found. are you in the right directory?" .. stdio.color("reset") .. "\n")
-    "#,
-    )
 }
 
 #[cfg(feature = "patches")]
@@ -91,21 +244,14 @@ fn job(n: u8) -> ColoredString {
 }
 
 impl InstallCommand {
-    pub fn run(
-        self,
-        project: Project,
-        multi: MultiProgress,
-        reqwest: reqwest::blocking::Client,
-        update_task: &mut Option<JoinHandle<()>>,
-    ) -> anyhow::Result<()> {
-        let mut refreshed_sources = HashSet::new();
-
-        let manifest = project
-            .deser_manifest()
-            .context("failed to read manifest")?;
-
-        let lockfile = if self.locked {
-            match up_to_date_lockfile(&project)? {
+    /// Returns the graph of the lockfile if it can be reused
+    fn old_graph(
+        &self,
+        project: &Project,
+        manifest: &Manifest,
+    ) -> anyhow::Result<Option<DependencyGraph>> {
+        let lockfile = if self.locked || self.frozen {
+            match up_to_date_lockfile(project)? {
                 None => {
                     anyhow::bail!(
                         "lockfile is out of sync, run `{} install` to update it",
@@ -136,25 +282,126 @@ impl InstallCommand {
             }
         };
 
+        Ok(lockfile.map(|lockfile| {
+            lockfile
+                .graph
+                .into_iter()
+                .map(|(name, versions)| {
+                    (
+                        name,
+                        versions
+                            .into_iter()
+                            .map(|(version, node)| (version, node.node))
+                            .collect(),
+                    )
+                })
+                .collect()
+        }))
+    }
+
+    pub fn run(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+        update_task: &mut Option<JoinHandle<()>>,
+    ) -> anyhow::Result<()> {
+        let project = project
+            .with_frozen(self.frozen)
+            .with_features(self.features.iter().cloned().collect())
+            .with_hooks(!self.ignore_hooks);
+        let project = if self.no_scripts {
+            project.with_script_policy(ScriptPolicy::new(false))
+        } else {
+            project
+        };
+        let project = if self.list_skipped {
+            project.with_event_handler(Some(EventHandler::new(|event| {
+                if let Event::FileSkipped { package, path } = event {
+                    log::info!(
+                        "{}",
+                        msg!("install.skipped", package = package, path = path)
+                    );
+                }
+            })))
+        } else {
+            project
+        };
+
+        self.run_with(
+            project,
+            multi,
+            reqwest,
+            update_task,
+            &mut HashSet::new(),
+            None,
+        )
+    }
+
+    fn run_with(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+        update_task: &mut Option<JoinHandle<()>>,
+        refreshed_sources: &mut HashSet<PackageSources>,
+        workspace_graphs: Option<&BTreeMap<PathBuf, DependencyGraph>>,
+    ) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+
+        let old_graph = self.old_graph(&project, &manifest)?;
+
         println!(
             "\n{}\n",
-            format!("[now installing {} {}]", manifest.name, manifest.target)
-                .bold()
-                .on_bright_black()
+            msg!(
+                "install.header",
+                name = manifest.name,
+                target = manifest.target
+            )
+            .bold()
+            .on_bright_black()
         );
 
-        println!("{} ❌ removing current package folders", job(1));
+        for shadowed in manifest
+            .shadowed_dependencies()
+            .context("failed to get dependencies")?
+        {
+            println!(
+                "{}",
+                msg!(
+                    "install.shadowed",
+                    alias = shadowed.alias,
+                    used = shadowed.used,
+                    ignored = shadowed.ignored
+                )
+                .yellow()
+            );
+        }
+
+        project
+            .run_lifecycle_hook(ScriptName::PreInstall)
+            .context("failed to run pre install hook")?;
+
+        println!("{} {}", job(1), msg!("install.removing_folders"));
 
         {
             let mut deleted_folders = HashSet::new();
 
+            // only the packages are removed, the linking modules next to them are kept so that
+            // unchanged ones aren't rewritten, and stale ones are removed when linking
             for target_kind in TargetKind::VARIANTS {
                 let folder = manifest.target.kind().packages_folder(target_kind);
 
                 if deleted_folders.insert(folder.to_string()) {
-                    log::debug!("deleting the {folder} folder");
+                    let container_folder = project
+                        .package_dir()
+                        .join(&folder)
+                        .join(PACKAGES_CONTAINER_NAME);
+                    log::debug!("deleting {}", container_folder.display());
 
-                    if let Some(e) = std::fs::remove_dir_all(project.package_dir().join(&folder))
+                    if let Some(e) = std::fs::remove_dir_all(&container_folder)
                         .err()
                         .filter(|e| e.kind() != std::io::ErrorKind::NotFound)
                     {
@@ -164,44 +411,122 @@ impl InstallCommand {
             }
         }
 
-        let old_graph = lockfile.map(|lockfile| {
-            lockfile
-                .graph
-                .into_iter()
-                .map(|(name, versions)| {
-                    (
-                        name,
+        println!("{} {}", job(2), msg!("install.building_graph"));
+
+        let mut computed_graphs = None;
+
+        let graph = match workspace_graphs.and_then(|graphs| graphs.get(project.package_dir())) {
+            Some(graph) => graph.clone(),
+            None if self.unified && project.workspace_dir().is_none() => {
+                let mut previous_graphs = BTreeMap::new();
+
+                if let Some(old_graph) = &old_graph {
+                    previous_graphs.insert(project.package_dir().to_path_buf(), old_graph.clone());
+                }
+
+                for dir in project
+                    .workspace_members(project.package_dir())
+                    .context("failed to get workspace members")?
+                    .into_keys()
+                {
+                    let member = project.for_member(&dir);
+                    let member_manifest = member
+                        .deser_manifest()
+                        .with_context(|| format!("failed to read manifest of {}", dir.display()))?;
+
+                    if let Some(old_graph) = self.old_graph(&member, &member_manifest)? {
+                        previous_graphs.insert(dir, old_graph);
+                    }
+                }
+
+                let graphs = computed_graphs.insert(
+                    project
+                        .workspace_dependency_graphs(&previous_graphs, refreshed_sources)
+                        .context("failed to build workspace dependency graph")?,
+                );
+
+                graphs
+                    .get(project.package_dir())
+                    .cloned()
+                    .unwrap_or_default()
+            }
+            None => project
+                .dependency_graph(old_graph.as_ref(), refreshed_sources)
+                .context("failed to build dependency graph")?,
+        };
+
+        let workspace_graphs = workspace_graphs.or(computed_graphs.as_ref());
+
+        if self.locked || self.frozen {
+            let packages = |graph: &DependencyGraph| {
+                graph
+                    .iter()
+                    .flat_map(|(name, versions)| {
                         versions
-                            .into_iter()
-                            .map(|(version, node)| (version, node.node))
-                            .collect(),
-                    )
-                })
-                .collect()
-        });
+                            .keys()
+                            .map(move |version_id| format!("{name}@{version_id}"))
+                    })
+                    .collect::<BTreeSet<_>>()
+            };
+
+            let locked = old_graph.as_ref().map(packages).unwrap_or_default();
+            let resolved = packages(&graph);
+
+            if locked != resolved {
+                anyhow::bail!(
+                    "lockfile would change ({}), run `{} install` to update it",
+                    resolved
+                        .symmetric_difference(&locked)
+                        .map(|package| if resolved.contains(package) {
+                            format!("+{package}")
+                        } else {
+                            format!("-{package}")
+                        })
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    env!("CARGO_BIN_NAME")
+                );
+            }
+        }
 
-        println!("{} 📦 building dependency graph", job(2));
+        let graph = match &self.only {
+            Some(alias) => direct_dependency_subgraph(&graph, alias)
+                .with_context(|| format!("no direct dependency with alias {alias} found"))?,
+            None => graph,
+        };
 
-        let graph = project
-            .dependency_graph(old_graph.as_ref(), &mut refreshed_sources)
-            .context("failed to build dependency graph")?;
+        // advisories shouldn't stop installing, so failing to check them is only a warning
+        match find_advisories(&project, &graph) {
+            Ok(findings) if findings.is_empty() => {}
+            Ok(mut findings) => {
+                println!("{}", msg!("install.advisories").yellow());
+                print_advisories(&mut findings);
+            }
+            Err(e) => log::warn!("failed to check security advisories: {e:?}"),
+        }
 
         if let Some(task) = update_task.take() {
             log::debug!("waiting for update task to finish");
             task.join().expect("failed to join update task");
         }
 
+        let project = if self.no_scripts {
+            project
+        } else {
+            confirm_scripts(project, &manifest, &graph)?
+        };
+
         let downloaded_graph = download_graph(
             &project,
-            &mut refreshed_sources,
+            refreshed_sources,
             &graph,
             &multi,
             &reqwest,
             self.threads as usize,
             self.prod,
             true,
-            format!("{} 📥 downloading dependencies", job(3)),
-            format!("{} 📥 downloaded dependencies", job(3)),
+            format!("{} {}", job(3), msg!("install.downloading")),
+            format!("{} {}", job(3), msg!("install.downloaded")),
         )?;
 
         let filtered_graph = if self.prod {
@@ -221,57 +546,84 @@ impl InstallCommand {
             downloaded_graph.clone()
         };
 
-        println!("{} 🗺️ linking dependencies", job(4));
+        println!("{} {}", job(4), msg!("install.linking"));
 
-        project
+        let summary = project
             .link_dependencies(&filtered_graph)
             .context("failed to link dependencies")?;
+        println!(
+            "{}",
+            msg!(
+                "install.linked",
+                refreshed = summary.refreshed,
+                reused = summary.reused,
+                removed = summary.removed
+            )
+            .dimmed()
+        );
+
+        write_require_aliases(&project, &manifest, &filtered_graph)?;
+        write_sourcemap(&project, &manifest)?;
+
+        project
+            .run_post_link_hooks(old_graph.as_ref(), &filtered_graph)
+            .context("failed to run post link commands")?;
+
+        project
+            .run_lifecycle_hook(ScriptName::PostInstall)
+            .context("failed to run post install hook")?;
 
         let bin_folder = bin_dir()?;
 
         for versions in filtered_graph.values() {
             for node in versions.values() {
-                if node.target.bin_path().is_none() {
-                    continue;
-                }
-
                 let Some((alias, _)) = &node.node.direct else {
                     continue;
                 };
 
-                if alias == env!("CARGO_BIN_NAME") {
-                    log::warn!("package {alias} has the same name as the CLI, skipping bin link");
-                    continue;
-                }
-
-                let bin_file = bin_folder.join(alias);
-                std::fs::write(&bin_file, bin_link_file(alias))
-                    .context("failed to write bin link file")?;
-
-                make_executable(&bin_file).context("failed to make bin link executable")?;
+                // the bin export is run by the alias, and the named ones by their name
+                let commands = node
+                    .target
+                    .bin_path()
+                    .map(|_| (alias.as_str(), None))
+                    .into_iter()
+                    .chain(node.target.named_bins().into_iter().flat_map(|bins| {
+                        bins.keys().map(|name| (name.as_str(), Some(name.as_str())))
+                    }));
+
+                for (command, bin_name) in commands {
+                    if command == env!("CARGO_BIN_NAME") {
+                        log::warn!(
+                            "bin {command} of {alias} has the same name as the CLI, skipping bin link"
+                        );
+                        continue;
+                    }
 
-                #[cfg(windows)]
-                {
-                    let bin_file = bin_file.with_extension(std::env::consts::EXE_EXTENSION);
-                    std::fs::copy(
-                        std::env::current_exe().context("failed to get current executable path")?,
-                        &bin_file,
-                    )
-                    .context("failed to copy bin link file")?;
+                    write_bin_shims(
+                        &bin_folder,
+                        command,
+                        &bin_linking_module_name(alias, bin_name),
+                    )?;
                 }
             }
         }
 
         #[cfg(feature = "patches")]
         {
-            println!("{} 🩹 applying patches", job(5));
+            println!("{} {}", job(5), msg!("install.patching"));
 
             project
                 .apply_patches(&filtered_graph)
                 .context("failed to apply patches")?;
         }
 
-        println!("{} 🧹 finishing up", job(JOBS));
+        println!("{} {}", job(JOBS), msg!("install.finishing"));
+
+        if let Some(alias) = &self.only {
+            println!("{}", msg!("install.only_done", alias = alias).yellow());
+
+            return Ok(());
+        }
 
         project
             .write_lockfile(Lockfile {
@@ -279,11 +631,20 @@ impl InstallCommand {
                 version: manifest.version,
                 target: manifest.target.kind(),
                 overrides: manifest.overrides,
+                pins: project.deser_pins().context("failed to read pins file")?,
+                features: project.features().clone(),
 
                 graph: downloaded_graph,
 
                 workspace: run_on_workspace_members(&project, |project| {
-                    self.run(project, multi.clone(), reqwest.clone(), &mut None)
+                    self.clone().run_with(
+                        project,
+                        multi.clone(),
+                        reqwest.clone(),
+                        &mut None,
+                        refreshed_sources,
+                        workspace_graphs,
+                    )
                 })?,
             })
             .context("failed to write lockfile")?;
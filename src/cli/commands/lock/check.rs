@@ -0,0 +1,69 @@
+use crate::cli::{messages::msg, run_on_workspace_members};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{lockfile::check_compatibility, Project};
+
+#[derive(Debug, Args, Copy, Clone)]
+pub struct CheckCommand {}
+
+impl CheckCommand {
+    /// Returns the number of mismatches found in the project's lockfile
+    fn check(project: &Project) -> anyhow::Result<usize> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+        let dir = project.package_dir().display();
+
+        let lockfile = match project.deser_lockfile() {
+            Ok(lockfile) => lockfile,
+            Err(pesde::errors::LockfileReadError::Io(e))
+                if e.kind() == std::io::ErrorKind::NotFound =>
+            {
+                println!("{}", msg!("lock.missing", dir = dir).red());
+                return Ok(1);
+            }
+            Err(e) => return Err(e).context("failed to read lockfile"),
+        };
+
+        let mut mismatches = check_compatibility(&manifest, &lockfile)
+            .context("failed to check lockfile compatibility")?
+            .into_iter()
+            .map(|mismatch| mismatch.to_string())
+            .collect::<Vec<_>>();
+
+        if project.deser_pins().context("failed to read pins file")? != lockfile.pins {
+            mismatches.push("pins changed".to_string());
+        }
+
+        if mismatches.is_empty() {
+            println!("{}", msg!("lock.up_to_date", dir = dir).green());
+        } else {
+            println!("{}", msg!("lock.out_of_date", dir = dir).red());
+
+            for mismatch in &mismatches {
+                println!("  - {mismatch}");
+            }
+        }
+
+        Ok(mismatches.len())
+    }
+
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let mut mismatches = Self::check(&project)?;
+
+        run_on_workspace_members(&project, |project| {
+            mismatches += Self::check(&project)?;
+            Ok(())
+        })?;
+
+        if mismatches > 0 {
+            anyhow::bail!(
+                "lockfile is out of date, run `{} install` to update it",
+                env!("CARGO_BIN_NAME")
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+use clap::Subcommand;
+use pesde::Project;
+
+mod check;
+
+#[derive(Debug, Subcommand)]
+pub enum LockCommands {
+    /// Checks whether the lockfile is up to date with the manifest, failing if it isn't
+    Check(check::CheckCommand),
+}
+
+impl LockCommands {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        match self {
+            LockCommands::Check(check) => check.run(project),
+        }
+    }
+}
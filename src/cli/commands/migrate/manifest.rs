@@ -0,0 +1,262 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use clap::Args;
+
+use crate::cli::messages::msg;
+use pesde::{
+    manifest::{
+        migration::{
+            convert_legacy_manifest, migrate, ManifestMigration, LEGACY_MANIFEST_FILE_NAME,
+        },
+        Manifest,
+    },
+    Project, MANIFEST_FILE_NAME,
+};
+use toml_edit::{Item, TableLike};
+
+#[derive(Debug, Args)]
+pub struct ManifestCommand {
+    /// Whether to only show the deprecated fields, without rewriting the manifest
+    #[arg(short, long)]
+    dry_run: bool,
+}
+
+/// Checks that the migrated manifest is valid
+fn validate(table: &toml::Table) -> anyhow::Result<()> {
+    toml::Value::Table(table.clone())
+        .try_into::<Manifest>()
+        .context("migrated manifest is invalid")?;
+
+    Ok(())
+}
+
+/// Applies the migrated values to the document key by key, descending into tables which exist in
+/// both, so only the migrated keys lose their formatting and comments
+fn apply_migrated(
+    document: &mut dyn TableLike,
+    inline: bool,
+    original: &toml::Table,
+    migrated: &toml::Table,
+    rendered: &dyn TableLike,
+) {
+    let removed = document
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !migrated.contains_key(key))
+        .collect::<Vec<_>>();
+    for key in removed {
+        document.remove(&key);
+    }
+
+    for (key, value) in migrated {
+        let original_value = original.get(key);
+        if original_value == Some(value) {
+            continue;
+        }
+
+        let Some(item) = rendered.get(key) else {
+            continue;
+        };
+
+        if let (Some(toml::Value::Table(original)), toml::Value::Table(migrated)) =
+            (original_value, value)
+        {
+            let is_value = document.get(key).is_some_and(Item::is_value);
+            if let (Some(table), Some(rendered)) = (
+                document.get_mut(key).and_then(Item::as_table_like_mut),
+                item.as_table_like(),
+            ) {
+                apply_migrated(table, inline || is_value, original, migrated, rendered);
+                continue;
+            }
+        }
+
+        let mut item = item.clone();
+        // inline tables can only hold values, and values stay values
+        if inline || document.get(key).is_some_and(Item::is_value) {
+            item = match item.into_value() {
+                Ok(value) => Item::Value(value),
+                Err(item) => item,
+            };
+        }
+        document.insert(key, item);
+    }
+}
+
+/// Migrates the manifest, only rewriting the keys which changed so the formatting and comments of
+/// the rest of the manifest are kept. Returns `None` if there is nothing to migrate
+fn migrate_manifest(contents: &str) -> anyhow::Result<Option<(String, Vec<ManifestMigration>)>> {
+    let original = toml::from_str::<toml::Table>(contents).context("failed to parse manifest")?;
+    let mut table = original.clone();
+    let migrations = migrate(&mut table);
+
+    if migrations.is_empty() {
+        return Ok(None);
+    }
+    validate(&table)?;
+
+    let mut document =
+        toml_edit::DocumentMut::from_str(contents).context("failed to parse manifest")?;
+    let rendered = toml_edit::DocumentMut::from_str(
+        &toml::to_string(&table).context("failed to serialize manifest")?,
+    )
+    .context("failed to parse migrated manifest")?;
+
+    apply_migrated(
+        document.as_table_mut(),
+        false,
+        &original,
+        &table,
+        rendered.as_table(),
+    );
+
+    Ok(Some((document.to_string(), migrations)))
+}
+
+impl ManifestCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let manifest_path = project.package_dir().join(MANIFEST_FILE_NAME);
+        let legacy_path = project.package_dir().join(LEGACY_MANIFEST_FILE_NAME);
+
+        let contents = match std::fs::read_to_string(&manifest_path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).context("failed to read manifest"),
+        };
+
+        let from_legacy = contents.is_none();
+        let (contents, migrations) = match contents {
+            Some(contents) => match migrate_manifest(&contents)? {
+                Some(migrated) => migrated,
+                None => {
+                    println!("{}", msg!("migrate.manifest.none"));
+                    return Ok(());
+                }
+            },
+            None => {
+                let contents = match std::fs::read_to_string(&legacy_path) {
+                    Ok(contents) => contents,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                        anyhow::bail!("no manifest found in {}", project.package_dir().display())
+                    }
+                    Err(e) => return Err(e).context("failed to read legacy manifest"),
+                };
+
+                let (table, migrations) = convert_legacy_manifest(&contents)
+                    .context("failed to parse legacy manifest")?;
+                validate(&table)?;
+
+                (
+                    toml::to_string(&table).context("failed to serialize manifest")?,
+                    migrations,
+                )
+            }
+        };
+
+        for migration in &migrations {
+            println!("{migration}");
+        }
+
+        if self.dry_run {
+            println!("\n{}", msg!("migrate.manifest.dry_run"));
+            return Ok(());
+        }
+
+        project
+            .write_manifest(contents)
+            .context("failed to write manifest")?;
+
+        if from_legacy {
+            std::fs::remove_file(&legacy_path).context("failed to remove legacy manifest")?;
+        }
+
+        println!(
+            "\n{}",
+            msg!("migrate.manifest.done", path = manifest_path.display())
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_formatting_of_unchanged_keys() {
+        let contents = r#"name = "acme/foo"
+version = "1.0.0"
+realm = "shared"
+
+# where the package runs
+[target]
+environment = "luau" # the runtime
+
+[exports]
+lib = "lib.luau"
+
+[dependencies]
+# the only dependency
+bar = { name = "acme/bar", version = "^1.0.0" } # pinned later
+"#;
+
+        let (migrated, migrations) = migrate_manifest(contents).unwrap().unwrap();
+        assert!(!migrations.is_empty());
+
+        assert_eq!(
+            migrated,
+            r#"name = "acme/foo"
+version = "1.0.0"
+
+# where the package runs
+[target]
+environment = "luau" # the runtime
+lib = "lib.luau"
+
+[dependencies]
+# the only dependency
+bar = { name = "acme/bar", version = "^1.0.0" } # pinned later
+"#
+        );
+    }
+
+    #[test]
+    fn migrates_inline_tables_in_place() {
+        let contents = r#"name = "acme/foo"
+version = "1.0.0"
+target = { environment = "luau" } # inline
+
+[exports]
+lib = "lib.luau"
+"#;
+
+        let (migrated, _) = migrate_manifest(contents).unwrap().unwrap();
+
+        // the table stays inline, keeping its comment
+        let line = migrated.lines().nth(2).unwrap();
+        assert!(line.starts_with("target = { environment = \"luau\""));
+        assert!(line.ends_with("} # inline"));
+        assert_eq!(
+            toml::from_str::<toml::Table>(&migrated).unwrap(),
+            toml::from_str::<toml::Table>(
+                r#"name = "acme/foo"
+version = "1.0.0"
+target = { environment = "luau", lib = "lib.luau" }
+"#
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn nothing_to_migrate() {
+        let contents = r#"name = "acme/foo"
+version = "1.0.0"
+target = { environment = "luau" }
+"#;
+
+        assert!(migrate_manifest(contents).unwrap().is_none());
+    }
+}
@@ -0,0 +1,18 @@
+use clap::Subcommand;
+use pesde::Project;
+
+mod manifest;
+
+#[derive(Debug, Subcommand)]
+pub enum MigrateCommands {
+    /// Rewrites the manifest, converting deprecated fields to the current layout
+    Manifest(manifest::ManifestCommand),
+}
+
+impl MigrateCommands {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        match self {
+            MigrateCommands::Manifest(manifest) => manifest.run(project),
+        }
+    }
+}
@@ -3,23 +3,45 @@ use pesde::Project;
 use std::thread::JoinHandle;
 
 mod add;
+mod audit;
 mod auth;
+mod cache;
 mod config;
 mod execute;
+mod global;
+mod grep;
+mod hash;
+mod index;
+mod info;
 mod init;
 mod install;
+mod lock;
+mod migrate;
 mod outdated;
 #[cfg(feature = "patches")]
 mod patch;
 #[cfg(feature = "patches")]
 mod patch_commit;
+mod pin;
 mod publish;
+mod remove;
+mod report;
+mod resolve;
 mod run;
+mod scope;
+mod search;
 #[cfg(feature = "version-management")]
 mod self_install;
 #[cfg(feature = "version-management")]
 mod self_upgrade;
+#[cfg(feature = "studio-sync")]
+mod studio_sync;
+mod unpin;
 mod update;
+mod upgrade;
+mod verify;
+mod verify_package;
+mod why;
 
 #[derive(Debug, clap::Subcommand)]
 pub enum Subcommand {
@@ -30,6 +52,14 @@ pub enum Subcommand {
     #[command(subcommand)]
     Config(config::ConfigCommands),
 
+    /// Cache-related commands
+    #[command(subcommand)]
+    Cache(cache::CacheCommands),
+
+    /// Commands for globally installed binary packages
+    #[command(subcommand)]
+    Global(global::GlobalCommands),
+
     /// Initializes a manifest file in the current directory
     Init(init::InitCommand),
 
@@ -39,6 +69,14 @@ pub enum Subcommand {
     /// Installs all dependencies for the project
     Install(install::InstallCommand),
 
+    /// Lockfile-related commands
+    #[command(subcommand)]
+    Lock(lock::LockCommands),
+
+    /// Commands for converting deprecated parts of the project
+    #[command(subcommand)]
+    Migrate(migrate::MigrateCommands),
+
     /// Publishes the project to the registry
     Publish(publish::PublishCommand),
 
@@ -61,15 +99,75 @@ pub enum Subcommand {
     /// Adds a dependency to the project
     Add(add::AddCommand),
 
+    /// Removes a dependency from the project
+    #[clap(visible_alias = "rm")]
+    Remove(remove::RemoveCommand),
+
+    /// Pins a package to an exact version, taking precedence over resolution. Run install to apply changes
+    Pin(pin::PinCommand),
+
+    /// Removes the pin of a package. Run install to apply changes
+    Unpin(unpin::UnpinCommand),
+
     /// Updates the project's lockfile. Run install to apply changes
     Update(update::UpdateCommand),
 
     /// Checks for outdated dependencies
     Outdated(outdated::OutdatedCommand),
 
+    /// Raises the version requirements of dependencies in the manifest to their latest versions. Run install to apply changes
+    Upgrade(upgrade::UpgradeCommand),
+
+    /// Searches for packages in an index
+    Search(search::SearchCommand),
+
+    /// Shows the metadata of a package in an index, and optionally its readme
+    Info(info::InfoCommand),
+
+    /// Serves the installed packages to the Rojo plugin in Roblox Studio, syncing changes live
+    #[cfg(feature = "studio-sync")]
+    StudioSync(studio_sync::StudioSyncCommand),
+
+    /// Commands for the scopes of an index
+    #[command(subcommand)]
+    Scope(scope::ScopeCommands),
+
+    /// Commands for operators maintaining the repository of an index
+    #[command(subcommand)]
+    Index(index::IndexCommands),
+
     /// Executes a binary package without needing to be run in a project directory
     #[clap(name = "x", visible_alias = "execute", visible_alias = "exec")]
     Execute(execute::ExecuteCommand),
+
+    /// Resolves the dependency graph without installing it, optionally comparing it with a snapshot
+    Resolve(resolve::ResolveCommand),
+
+    /// Resolves the dependencies again without writing anything, reporting how they drifted from the lockfile
+    Verify(verify::VerifyCommand),
+
+    /// Explains why a package is in the dependency graph
+    Why(why::WhyCommand),
+
+    /// Searches the source files of installed dependencies for a pattern
+    Grep(grep::GrepCommand),
+
+    /// Commands for auditing the installed dependencies
+    #[command(subcommand)]
+    Audit(audit::AuditCommands),
+
+    /// Prints a hash of everything the dependency graph is resolved from, for use as a CI cache key
+    Hash(hash::HashCommand),
+
+    /// Bundles information about the project into an archive for bug reports
+    Report(report::ReportCommand),
+
+    /// Downloads a published package into a temporary project and checks that it links and works
+    VerifyPackage(verify_package::VerifyPackageCommand),
+
+    /// Runs the background helper
+    #[clap(name = "__helper", hide = true)]
+    Helper,
 }
 
 impl Subcommand {
@@ -85,9 +183,13 @@ impl Subcommand {
         let res = match self {
             Subcommand::Auth(auth) => auth.run(project, reqwest),
             Subcommand::Config(config) => config.run(),
+            Subcommand::Cache(cache) => cache.run(project),
+            Subcommand::Global(global) => global.run(project, multi, reqwest, &mut update_task),
             Subcommand::Init(init) => init.run(project),
             Subcommand::Run(run) => run.run(project, &mut update_task),
             Subcommand::Install(install) => install.run(project, multi, reqwest, &mut update_task),
+            Subcommand::Lock(lock) => lock.run(project),
+            Subcommand::Migrate(migrate) => migrate.run(project),
             Subcommand::Publish(publish) => publish.run(project, reqwest),
             #[cfg(feature = "version-management")]
             Subcommand::SelfInstall(self_install) => self_install.run(),
@@ -97,10 +199,31 @@ impl Subcommand {
             Subcommand::PatchCommit(patch_commit) => patch_commit.run(project),
             #[cfg(feature = "version-management")]
             Subcommand::SelfUpgrade(self_upgrade) => self_upgrade.run(reqwest),
-            Subcommand::Add(add) => add.run(project),
+            Subcommand::Add(add) => add.run(project, reqwest),
+            Subcommand::Remove(remove) => remove.run(project, multi, reqwest, &mut update_task),
+            Subcommand::Pin(pin) => pin.run(project),
+            Subcommand::Unpin(unpin) => unpin.run(project),
             Subcommand::Update(update) => update.run(project, multi, reqwest, &mut update_task),
             Subcommand::Outdated(outdated) => outdated.run(project),
+            Subcommand::Upgrade(upgrade) => upgrade.run(project),
+            Subcommand::Search(search) => search.run(project, reqwest),
+            Subcommand::Info(info) => info.run(project, reqwest),
+            Subcommand::Scope(scope) => scope.run(project, reqwest),
+            Subcommand::Index(index) => index.run(),
+            #[cfg(feature = "studio-sync")]
+            Subcommand::StudioSync(studio_sync) => studio_sync.run(project),
             Subcommand::Execute(execute) => execute.run(project, reqwest),
+            Subcommand::Resolve(resolve) => resolve.run(project),
+            Subcommand::Verify(verify) => verify.run(project),
+            Subcommand::Why(why) => why.run(project),
+            Subcommand::Grep(grep) => grep.run(project),
+            Subcommand::Audit(audit) => audit.run(project),
+            Subcommand::Hash(hash) => hash.run(project),
+            Subcommand::Report(report) => report.run(project),
+            Subcommand::VerifyPackage(verify_package) => {
+                verify_package.run(project, multi, reqwest)
+            }
+            Subcommand::Helper => crate::cli::helper::run(project, reqwest),
         };
 
         if let Some(handle) = update_task.take() {
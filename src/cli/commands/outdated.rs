@@ -2,21 +2,64 @@ use std::collections::HashSet;
 
 use anyhow::Context;
 use clap::Args;
-use semver::VersionReq;
+use serde::Serialize;
 
 use pesde::{
+    names::PackageNames,
     source::{
-        specifiers::DependencySpecifiers,
+        git_index::GitBasedSource,
+        pesde::IndexFileEntry,
         traits::{PackageRef, PackageSource},
+        version_id::VersionId,
+        PackageSources,
     },
     Project,
 };
 
+use crate::cli::messages::msg;
+
 #[derive(Debug, Args)]
 pub struct OutdatedCommand {
-    /// Whether to check within version requirements
+    /// Whether to only show updates allowed by the version requirements
     #[arg(short, long)]
     strict: bool,
+
+    /// Whether to output the outdated packages as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct OutdatedEntry {
+    name: PackageNames,
+    alias: String,
+    current: VersionId,
+    /// The latest version allowed by the version requirement
+    compatible: VersionId,
+    /// The latest version overall, which may need a manifest change
+    latest: VersionId,
+    /// The URL of the index or repository the package is from
+    source: Option<String>,
+    /// The metadata of the latest version, only read for the JSON output
+    #[serde(flatten)]
+    metadata: Option<LatestMetadata>,
+}
+
+#[derive(Debug, Serialize)]
+struct LatestMetadata {
+    license: Option<String>,
+    repository: Option<url::Url>,
+    published_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<IndexFileEntry> for LatestMetadata {
+    fn from(entry: IndexFileEntry) -> Self {
+        LatestMetadata {
+            license: entry.license,
+            repository: entry.repository,
+            published_at: entry.published_at,
+        }
+    }
 }
 
 impl OutdatedCommand {
@@ -28,19 +71,17 @@ impl OutdatedCommand {
             .context("failed to read manifest")?;
 
         let mut refreshed_sources = HashSet::new();
+        let mut entries = vec![];
 
         for (name, versions) in graph {
             for (current_version_id, node) in versions {
-                let Some((alias, mut specifier)) = node.node.direct else {
+                let Some((alias, specifier)) = node.node.direct else {
                     continue;
                 };
 
-                if matches!(
-                    specifier,
-                    DependencySpecifiers::Git(_) | DependencySpecifiers::Workspace(_)
-                ) {
+                let Some(unconstrained) = specifier.unconstrained() else {
                     continue;
-                }
+                };
 
                 let source = node.node.pkg_ref.source();
 
@@ -48,34 +89,111 @@ impl OutdatedCommand {
                     source.refresh(&project)?;
                 }
 
-                if !self.strict {
-                    match specifier {
-                        DependencySpecifiers::Pesde(ref mut spec) => {
-                            spec.version = VersionReq::STAR;
-                        }
-                        #[cfg(feature = "wally-compat")]
-                        DependencySpecifiers::Wally(ref mut spec) => {
-                            spec.version = VersionReq::STAR;
-                        }
-                        DependencySpecifiers::Git(_) => {}
-                        DependencySpecifiers::Workspace(_) => {}
-                    };
-                }
+                let resolve = |specifier| {
+                    source
+                        .resolve(specifier, &project, manifest.target.kind())
+                        .context("failed to resolve package versions")?
+                        .1
+                        .pop_last()
+                        .map(|(v_id, _)| v_id)
+                        .context(format!("no versions of {specifier} found"))
+                };
 
-                let version_id = source
-                    .resolve(&specifier, &project, manifest.target.kind())
-                    .context("failed to resolve package versions")?
-                    .1
-                    .pop_last()
-                    .map(|(v_id, _)| v_id)
-                    .context(format!("no versions of {specifier} found"))?;
+                let compatible = resolve(&specifier)?;
+                let latest = if self.strict {
+                    compatible.clone()
+                } else {
+                    resolve(&unconstrained)?
+                };
 
-                if version_id != current_version_id {
-                    println!("{name} ({alias}) {current_version_id} -> {version_id}");
+                if compatible == current_version_id && latest == current_version_id {
+                    continue;
+                }
+
+                let source_url = match &source {
+                    PackageSources::Pesde(source) => Some(source.repo_url()),
+                    #[cfg(feature = "wally-compat")]
+                    PackageSources::Wally(source) => Some(source.repo_url()),
+                    PackageSources::Git(source) => Some(source.repo_url()),
+                    _ => None,
                 }
+                .map(|url| url.to_bstring().to_string());
+
+                // only the index has the metadata, so it's read just when it will be shown
+                let metadata = match (&source, &name) {
+                    (PackageSources::Pesde(source), PackageNames::Pesde(pkg_name)) if self.json => {
+                        source
+                            .index_file(pkg_name, &project)
+                            .context("failed to read index file")?
+                            .and_then(|mut file| file.remove(&latest))
+                            .map(LatestMetadata::from)
+                    }
+                    _ => None,
+                };
+
+                entries.push(OutdatedEntry {
+                    name: name.clone(),
+                    alias,
+                    current: current_version_id,
+                    compatible,
+                    latest,
+                    source: source_url,
+                    metadata,
+                });
+            }
+        }
+
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&entries).context("failed to serialize entries")?
+            );
+        } else {
+            for entry in &entries {
+                let (name, alias, current, compatible, latest) = (
+                    &entry.name,
+                    &entry.alias,
+                    &entry.current,
+                    &entry.compatible,
+                    &entry.latest,
+                );
+
+                let message = if compatible == current {
+                    msg!(
+                        "outdated.incompatible",
+                        name = name,
+                        alias = alias,
+                        current = current,
+                        latest = latest
+                    )
+                } else if latest == compatible {
+                    msg!(
+                        "outdated.compatible",
+                        name = name,
+                        alias = alias,
+                        current = current,
+                        compatible = compatible
+                    )
+                } else {
+                    msg!(
+                        "outdated.entry",
+                        name = name,
+                        alias = alias,
+                        current = current,
+                        compatible = compatible,
+                        latest = latest
+                    )
+                };
+
+                println!("{message}");
             }
         }
 
+        // outdated packages fail the command, so CI can gate on them
+        if !entries.is_empty() {
+            anyhow::bail!("{}", msg!("outdated.failed", count = entries.len()));
+        }
+
         Ok(())
     }
 }
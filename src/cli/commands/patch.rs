@@ -37,6 +37,10 @@ impl PatchCommand {
             anyhow::bail!("cannot patch a workspace package")
         }
 
+        if matches!(node.node.pkg_ref, PackageRefs::Path(_)) {
+            anyhow::bail!("cannot patch a path package, edit it directly instead")
+        }
+
         let source = node.node.pkg_ref.source();
 
         let directory = project
@@ -55,6 +59,30 @@ impl PatchCommand {
 
         setup_patches_repo(&directory)?;
 
+        if let Some(patch) = project
+            .patches()
+            .context("failed to read patches")?
+            .get(&name)
+            .and_then(|versions| versions.get(&version_id))
+        {
+            println!(
+                "{}: {name}@{version_id} is already patched by {}",
+                "note".blue(),
+                patch.file().display()
+            );
+        }
+
+        if project.workspace_dir().is_some() {
+            println!(
+                concat!(
+                    "{}: pass --workspace to `",
+                    env!("CARGO_BIN_NAME"),
+                    " patch-commit` to share the patch with every workspace member"
+                ),
+                "note".blue()
+            );
+        }
+
         println!(
             concat!(
                 "done! modify the files in the directory, then run `",
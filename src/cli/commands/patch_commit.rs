@@ -1,7 +1,10 @@
 use crate::cli::up_to_date_lockfile;
 use anyhow::Context;
 use clap::Args;
-use pesde::{names::PackageNames, patches::create_patch, source::version_id::VersionId, Project};
+use pesde::{
+    names::PackageNames, patches::create_patch, source::version_id::VersionId, Project,
+    MANIFEST_FILE_NAME,
+};
 use std::{path::PathBuf, str::FromStr};
 
 #[derive(Debug, Args)]
@@ -9,6 +12,10 @@ pub struct PatchCommitCommand {
     /// The directory containing the patch to commit
     #[arg(index = 1)]
     directory: PathBuf,
+
+    /// Whether to add the patch to the workspace root's manifest, so every member inherits it
+    #[arg(short, long)]
+    workspace: bool,
 }
 
 impl PatchCommitCommand {
@@ -47,15 +54,24 @@ impl PatchCommitCommand {
             .and_then(|versions| versions.get(&version_id))
             .context("package not found in graph")?;
 
+        let owner_dir = if self.workspace {
+            project
+                .workspace_dir()
+                .context("project is not a member of a workspace")?
+        } else {
+            project.package_dir()
+        };
+
         let mut manifest = toml_edit::DocumentMut::from_str(
-            &project.read_manifest().context("failed to read manifest")?,
+            &std::fs::read_to_string(owner_dir.join(MANIFEST_FILE_NAME))
+                .context("failed to read manifest")?,
         )
         .context("failed to parse manifest")?;
 
         let patch = create_patch(&self.directory).context("failed to create patch")?;
         std::fs::remove_dir_all(self.directory).context("failed to remove patch directory")?;
 
-        let patches_dir = project.package_dir().join("patches");
+        let patches_dir = owner_dir.join("patches");
         std::fs::create_dir_all(&patches_dir).context("failed to create patches directory")?;
 
         let patch_file_name = format!("{}-{}.patch", name.escaped(), version_id.escaped());
@@ -71,8 +87,7 @@ impl PatchCommitCommand {
             [&name.to_string()][&version_id.to_string()] =
             toml_edit::value(format!("patches/{patch_file_name}"));
 
-        project
-            .write_manifest(manifest.to_string())
+        std::fs::write(owner_dir.join(MANIFEST_FILE_NAME), manifest.to_string())
             .context("failed to write manifest")?;
 
         println!(concat!(
@@ -0,0 +1,34 @@
+use crate::cli::{messages::msg, VersionedPackageName};
+use anyhow::Context;
+use clap::Args;
+use pesde::{names::PackageNames, Project};
+use semver::Version;
+
+#[derive(Debug, Args)]
+pub struct PinCommand {
+    /// The package to pin, with the exact version to pin it to
+    #[arg(index = 1)]
+    package: VersionedPackageName<Version, PackageNames>,
+}
+
+impl PinCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let VersionedPackageName(name, version) = self.package;
+        let version =
+            version.context("a version to pin to is required, e.g. `scope/name@1.2.3`")?;
+
+        let mut pins = project.deser_pins().context("failed to read pins file")?;
+
+        if let Some(previous) = pins.insert(name.clone(), version.clone()) {
+            log::debug!("replacing pin of {name} to {previous}");
+        }
+
+        project
+            .write_pins(&pins)
+            .context("failed to write pins file")?;
+
+        println!("{}", msg!("pin.done", name = name, version = version));
+
+        Ok(())
+    }
+}
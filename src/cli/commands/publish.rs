@@ -1,435 +1,300 @@
 use anyhow::Context;
 use clap::Args;
 use colored::Colorize;
-use reqwest::{header::AUTHORIZATION, StatusCode};
-use semver::VersionReq;
-use std::{
-    io::{Seek, Write},
-    path::Component,
-};
-use tempfile::tempfile;
+use relative_path::RelativePathBuf;
+use std::path::{Path, PathBuf};
 
-use crate::cli::{run_on_workspace_members, up_to_date_lockfile};
+use crate::cli::{messages::msg, run_on_workspace_members, up_to_date_lockfile};
 use pesde::{
-    manifest::{target::Target, DependencyType},
-    scripts::ScriptName,
-    source::{
-        pesde::{specifier::PesdeDependencySpecifier, PesdePackageSource},
-        specifiers::DependencySpecifiers,
-        traits::PackageSource,
-        workspace::{
-            specifier::{VersionType, VersionTypeOrReq},
-            WorkspacePackageSource,
-        },
-        IGNORED_DIRS, IGNORED_FILES,
+    manifest::target::TargetKind,
+    publish::{
+        errors::PublishError, prepare_packages, publish_packages, FileSelector, PublishOptions,
+        PublishOutcome,
     },
-    Project, DEFAULT_INDEX_NAME, MANIFEST_FILE_NAME,
+    scripts::ScriptName,
+    Project, MANIFEST_FILE_NAME,
 };
 
-#[derive(Debug, Args, Copy, Clone)]
+#[derive(Debug, Args, Clone)]
 pub struct PublishCommand {
-    /// Whether to output a tarball instead of publishing
+    /// Whether to output a tarball instead of publishing, without contacting the registry
     #[arg(short, long)]
     dry_run: bool,
 
+    /// Where to write the tarball of a dry run, relative to the package's directory
+    #[arg(short, long, default_value = "package.tar.gz", requires = "dry_run")]
+    output: PathBuf,
+
     /// Agree to all prompts
     #[arg(short, long)]
     yes: bool,
+
+    /// Only list the files matched by the includes which won't be published, and why
+    #[arg(long, conflicts_with = "dry_run")]
+    list_stripped: bool,
+
+    /// Whether to not run the manifest's lifecycle hook scripts, for untrusted projects
+    #[arg(long)]
+    ignore_hooks: bool,
+}
+
+fn write_dry_run(
+    archive: &[u8],
+    files: &[(RelativePathBuf, u64)],
+    path: &Path,
+) -> anyhow::Result<()> {
+    for (file, size) in files {
+        println!("{:>10} {file}", indicatif::HumanBytes(*size).to_string());
+    }
+
+    std::fs::write(path, archive).context("failed to write tarball")?;
+
+    println!(
+        "\n{}",
+        msg!(
+            "publish.dry_run",
+            count = files.len(),
+            path = path.display(),
+            size = indicatif::HumanBytes(archive.len() as u64)
+        )
+        .green()
+        .bold()
+    );
+
+    Ok(())
+}
+
+/// Where to write the tarball of a target when a dry run packs several, for example
+/// `package.roblox.tar.gz` for `package.tar.gz`
+fn target_output(output: &Path, kind: TargetKind) -> PathBuf {
+    let file_name = output
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    match file_name.split_once('.') {
+        Some((stem, extension)) => output.with_file_name(format!("{stem}.{kind}.{extension}")),
+        None => output.with_file_name(format!("{file_name}.{kind}")),
+    }
 }
 
 impl PublishCommand {
-    fn run_impl(self, project: &Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
-        let mut manifest = project
+    fn run_impl(
+        &self,
+        project: &Project,
+        reqwest: reqwest::blocking::Client,
+    ) -> anyhow::Result<()> {
+        let manifest = project
             .deser_manifest()
             .context("failed to read manifest")?;
 
         println!(
             "\n{}\n",
-            format!("[now publishing {} {}]", manifest.name, manifest.target)
-                .bold()
-                .on_bright_black()
+            msg!(
+                "publish.header",
+                name = manifest.name,
+                target = manifest.target
+            )
+            .bold()
+            .on_bright_black()
         );
 
         if manifest.private {
-            println!("{}", "package is private, cannot publish".red().bold());
+            println!("{}", msg!("publish.private").red().bold());
 
             return Ok(());
         }
 
-        if manifest.target.lib_path().is_none() && manifest.target.bin_path().is_none() {
-            anyhow::bail!("no exports found in target");
-        }
+        project
+            .run_lifecycle_hook(ScriptName::PrePublish)
+            .context("failed to run pre publish hook")?;
 
-        if matches!(
-            manifest.target,
-            Target::Roblox { .. } | Target::RobloxServer { .. }
-        ) {
-            if manifest.target.build_files().is_none_or(|f| f.is_empty()) {
-                anyhow::bail!("no build files found in target");
-            }
+        let lockfile = if std::iter::once(&manifest.target)
+            .chain(&manifest.extra_targets)
+            .any(|target| target.build_files().is_some())
+        {
+            up_to_date_lockfile(project)?
+        } else {
+            None
+        };
 
-            match up_to_date_lockfile(project)? {
-                Some(lockfile) => {
-                    if lockfile
-                        .graph
-                        .values()
-                        .flatten()
-                        .filter_map(|(_, node)| node.node.direct.as_ref().map(|_| node))
-                        .any(|node| {
-                            node.target.build_files().is_none()
-                                && !matches!(node.node.ty, DependencyType::Dev)
-                        })
-                    {
-                        anyhow::bail!("roblox packages may not depend on non-roblox packages");
-                    }
-                }
-                None => {
-                    anyhow::bail!("outdated lockfile, please run the install command first")
-                }
+        let packages = prepare_packages(project, lockfile.as_ref())?;
+
+        let mut warnings = vec![];
+        for warning in packages.iter().flat_map(|package| &package.warnings) {
+            if !warnings.contains(&warning) {
+                println!("{}: {warning}", "warn".yellow().bold());
+                warnings.push(warning);
             }
         }
 
-        let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
-            vec![],
-            flate2::Compression::best(),
-        ));
+        if self.list_stripped {
+            for package in &packages {
+                let stripped =
+                    FileSelector::new(&package.manifest.includes, &package.manifest.excludes)
+                        .context("failed to create file selector")?
+                        .stripped(project.package_dir())
+                        .context("failed to list stripped files")?;
 
-        let mut display_includes: Vec<String> = vec![MANIFEST_FILE_NAME.to_string()];
-        let mut display_build_files: Vec<String> = vec![];
+                println!(
+                    "\n{}",
+                    msg!(
+                        "publish.stripped",
+                        target = package.manifest.target.kind(),
+                        count = stripped.len()
+                    )
+                    .bold()
+                );
+                for (path, reason) in stripped {
+                    println!("{path} {}", format!("({reason})").dimmed());
+                }
+            }
 
-        let (lib_path, bin_path, target_kind) = (
-            manifest.target.lib_path().cloned(),
-            manifest.target.bin_path().cloned(),
-            manifest.target.kind(),
-        );
+            return Ok(());
+        }
 
-        let mut roblox_target = match &mut manifest.target {
-            Target::Roblox { build_files, .. } => Some(build_files),
-            Target::RobloxServer { build_files, .. } => Some(build_files),
-            _ => None,
-        };
+        let manifest = &packages[0].manifest;
 
-        if manifest.includes.insert(MANIFEST_FILE_NAME.to_string()) {
+        {
+            let none = msg!("publish.confirm.none");
+
+            println!("\n{}", msg!("publish.confirm.header").bold());
+            println!("{}", msg!("publish.confirm.name", value = manifest.name));
             println!(
-                "{}: {MANIFEST_FILE_NAME} was not in includes, adding it",
-                "warn".yellow().bold()
+                "{}",
+                msg!("publish.confirm.version", value = manifest.version)
             );
-        }
-
-        if manifest.includes.remove(".git") {
             println!(
-                "{}: .git was in includes, removing it",
-                "warn".yellow().bold()
+                "{}",
+                msg!(
+                    "publish.confirm.description",
+                    value = manifest.description.as_deref().unwrap_or(&none)
+                )
             );
-        }
-
-        if !manifest.includes.iter().any(|f| {
-            matches!(
-                f.to_lowercase().as_str(),
-                "readme" | "readme.md" | "readme.txt"
-            )
-        }) {
             println!(
-                "{}: no README file in includes, consider adding one",
-                "warn".yellow().bold()
+                "{}",
+                msg!(
+                    "publish.confirm.license",
+                    value = manifest.license.as_deref().unwrap_or(&none)
+                )
             );
-        }
-
-        if !manifest.includes.iter().any(|f| f == "docs") {
             println!(
-                "{}: no docs directory in includes, consider adding one",
-                "warn".yellow().bold()
+                "{}",
+                msg!(
+                    "publish.confirm.authors",
+                    value = if manifest.authors.is_empty() {
+                        none.clone()
+                    } else {
+                        manifest.authors.join(", ")
+                    }
+                )
             );
-        }
-
-        if manifest.includes.remove("default.project.json") {
             println!(
-                "{}: default.project.json was in includes, this should be generated by the {} script upon dependants installation",
-                "warn".yellow().bold(),
-                ScriptName::RobloxSyncConfigGenerator
+                "{}",
+                msg!(
+                    "publish.confirm.repository",
+                    value = manifest
+                        .repository
+                        .as_ref()
+                        .map(|r| r.as_str())
+                        .unwrap_or(&none)
+                )
             );
-        }
-
-        for ignored_path in IGNORED_FILES.iter().chain(IGNORED_DIRS.iter()) {
-            if manifest.includes.remove(*ignored_path) {
+            if !manifest.keywords.is_empty() {
                 println!(
-                    r#"{}: {ignored_path} was in includes, removing it.
-{}: if this was a toolchain manager's manifest file, do not include it due to it possibly messing with user scripts
-{}: otherwise, the file was deemed unnecessary, if you don't understand why, please contact the maintainers"#,
-                    "warn".yellow().bold(),
-                    "info".blue().bold(),
-                    "info".blue().bold()
+                    "{}",
+                    msg!(
+                        "publish.confirm.keywords",
+                        value = manifest
+                            .keywords
+                            .iter()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
                 );
             }
-        }
-
-        for (name, path) in [("lib path", lib_path), ("bin path", bin_path)] {
-            let Some(export_path) = path else { continue };
-
-            let export_path = export_path.to_path(project.package_dir());
-            if !export_path.exists() {
-                anyhow::bail!("{name} points to non-existent file");
-            }
-
-            if !export_path.is_file() {
-                anyhow::bail!("{name} must point to a file");
-            }
-
-            let contents =
-                std::fs::read_to_string(&export_path).context(format!("failed to read {name}"))?;
-
-            if let Err(err) = full_moon::parse(&contents).map_err(|errs| {
-                errs.into_iter()
-                    .map(|err| err.to_string())
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            }) {
-                anyhow::bail!("{name} is not a valid Luau file: {err}");
-            }
-
-            let first_part = export_path
-                .strip_prefix(project.package_dir())
-                .context(format!("{name} not within project directory"))?
-                .components()
-                .next()
-                .context(format!("{name} must contain at least one part"))?;
-
-            let first_part = match first_part {
-                Component::Normal(part) => part,
-                _ => anyhow::bail!("{name} must be within project directory"),
-            };
-
-            let first_part_str = first_part.to_string_lossy();
-
-            if manifest.includes.insert(first_part_str.to_string()) {
+            if !manifest.categories.is_empty() {
                 println!(
-                    "{}: {name} was not in includes, adding {first_part_str}",
-                    "warn".yellow().bold()
+                    "{}",
+                    msg!(
+                        "publish.confirm.categories",
+                        value = manifest
+                            .categories
+                            .iter()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
                 );
             }
 
-            if roblox_target.as_mut().map_or(false, |build_files| {
-                build_files.insert(first_part_str.to_string())
-            }) {
+            for manifest in packages.iter().map(|package| &package.manifest) {
                 println!(
-                    "{}: {name} was not in build files, adding {first_part_str}",
-                    "warn".yellow().bold()
+                    "{}",
+                    msg!("publish.confirm.target", value = manifest.target)
+                );
+                println!(
+                    "\t{}",
+                    msg!(
+                        "publish.confirm.lib_path",
+                        value = manifest
+                            .target
+                            .lib_path()
+                            .map_or(none.clone(), |p| p.to_string())
+                    )
                 );
-            }
-        }
-
-        for included_name in &manifest.includes {
-            let included_path = project.package_dir().join(included_name);
-
-            if !included_path.exists() {
-                anyhow::bail!("included file {included_name} does not exist");
-            }
-
-            // it's already included, and guaranteed to be a file
-            if included_name.eq_ignore_ascii_case(MANIFEST_FILE_NAME) {
-                continue;
-            }
-
-            if included_path.is_file() {
-                display_includes.push(included_name.clone());
-
-                archive.append_file(
-                    included_name,
-                    &mut std::fs::File::open(&included_path)
-                        .context(format!("failed to read {included_name}"))?,
-                )?;
-            } else {
-                display_includes.push(format!("{included_name}/*"));
-
-                archive
-                    .append_dir_all(included_name, &included_path)
-                    .context(format!("failed to include directory {included_name}"))?;
-            }
-        }
 
-        if let Some(build_files) = &roblox_target {
-            for build_file in build_files.iter() {
-                if build_file.eq_ignore_ascii_case(MANIFEST_FILE_NAME) {
+                if let Some(build_files) = manifest.target.build_files() {
                     println!(
-                        "{}: {MANIFEST_FILE_NAME} is in build files, please remove it",
-                        "warn".yellow().bold()
+                        "\t{}",
+                        msg!(
+                            "publish.confirm.build_files",
+                            value = build_files
+                                .iter()
+                                .filter(|file| !file.eq_ignore_ascii_case(MANIFEST_FILE_NAME))
+                                .map(|file| if project.package_dir().join(file).is_file() {
+                                    file.clone()
+                                } else {
+                                    format!("{file}/*")
+                                })
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
                     );
-
-                    continue;
-                }
-
-                let build_file_path = project.package_dir().join(build_file);
-
-                if !build_file_path.exists() {
-                    anyhow::bail!("build file {build_file} does not exist");
-                }
-
-                if !manifest.includes.contains(build_file) {
-                    anyhow::bail!("build file {build_file} is not in includes, please add it");
-                }
-
-                if build_file_path.is_file() {
-                    display_build_files.push(build_file.clone());
                 } else {
-                    display_build_files.push(format!("{build_file}/*"));
-                }
-            }
-        }
-
-        #[cfg(feature = "wally-compat")]
-        let mut has_wally = false;
-        let mut has_git = false;
-
-        for specifier in manifest
-            .dependencies
-            .values_mut()
-            .chain(manifest.dev_dependencies.values_mut())
-            .chain(manifest.peer_dependencies.values_mut())
-        {
-            match specifier {
-                DependencySpecifiers::Pesde(specifier) => {
-                    let index_name = specifier
-                        .index
-                        .as_deref()
-                        .unwrap_or(DEFAULT_INDEX_NAME)
-                        .to_string();
-                    specifier.index = Some(
-                        manifest
-                            .indices
-                            .get(&index_name)
-                            .context(format!("index {index_name} not found in indices field"))?
-                            .to_string(),
-                    );
-                }
-                #[cfg(feature = "wally-compat")]
-                DependencySpecifiers::Wally(specifier) => {
-                    has_wally = true;
-
-                    let index_name = specifier
-                        .index
-                        .as_deref()
-                        .unwrap_or(DEFAULT_INDEX_NAME)
-                        .to_string();
-                    specifier.index = Some(
-                        manifest
-                            .wally_indices
-                            .get(&index_name)
-                            .context(format!(
-                                "index {index_name} not found in wally_indices field"
-                            ))?
-                            .to_string(),
-                    );
-                }
-                DependencySpecifiers::Git(_) => {
-                    has_git = true;
-                }
-                DependencySpecifiers::Workspace(spec) => {
-                    let pkg_ref = WorkspacePackageSource
-                        .resolve(spec, project, target_kind)
-                        .context("failed to resolve workspace package")?
-                        .1
-                        .pop_last()
-                        .context("no versions found for workspace package")?
-                        .1;
-
-                    let manifest = pkg_ref
-                        .path
-                        .to_path(
-                            project
-                                .workspace_dir()
-                                .context("failed to get workspace directory")?,
+                    println!(
+                        "\t{}",
+                        msg!(
+                            "publish.confirm.bin_path",
+                            value = manifest
+                                .target
+                                .bin_path()
+                                .map_or(none.clone(), |p| p.to_string())
                         )
-                        .join(MANIFEST_FILE_NAME);
-                    let manifest = std::fs::read_to_string(&manifest)
-                        .context("failed to read workspace package manifest")?;
-                    let manifest = toml::from_str::<pesde::manifest::Manifest>(&manifest)
-                        .context("failed to parse workspace package manifest")?;
-
-                    *specifier = DependencySpecifiers::Pesde(PesdeDependencySpecifier {
-                        name: spec.name.clone(),
-                        version: match spec.version.clone() {
-                            VersionTypeOrReq::VersionType(VersionType::Wildcard) => {
-                                VersionReq::STAR
-                            }
-                            VersionTypeOrReq::Req(r) => r,
-                            v => VersionReq::parse(&format!("{v}{}", manifest.version))
-                                .context(format!("failed to parse version for {v}"))?,
-                        },
-                        index: Some(
-                            manifest
-                                .indices
-                                .get(DEFAULT_INDEX_NAME)
-                                .context("missing default index in workspace package manifest")?
-                                .to_string(),
-                        ),
-                        target: Some(spec.target.unwrap_or(manifest.target.kind())),
-                    });
-                }
-            }
-        }
-
-        {
-            println!("\n{}", "please confirm the following information:".bold());
-            println!("name: {}", manifest.name);
-            println!("version: {}", manifest.version);
-            println!(
-                "description: {}",
-                manifest.description.as_deref().unwrap_or("(none)")
-            );
-            println!(
-                "license: {}",
-                manifest.license.as_deref().unwrap_or("(none)")
-            );
-            println!(
-                "authors: {}",
-                if manifest.authors.is_empty() {
-                    "(none)".to_string()
-                } else {
-                    manifest.authors.join(", ")
+                    );
                 }
-            );
-            println!(
-                "repository: {}",
-                manifest
-                    .repository
-                    .as_ref()
-                    .map(|r| r.as_str())
-                    .unwrap_or("(none)")
-            );
-
-            let roblox_target = roblox_target.is_some_and(|_| true);
 
-            println!("target: {}", manifest.target);
-            println!(
-                "\tlib path: {}",
-                manifest
-                    .target
-                    .lib_path()
-                    .map_or("(none)".to_string(), |p| p.to_string())
-            );
-
-            if roblox_target {
-                println!("\tbuild files: {}", display_build_files.join(", "));
-            } else {
                 println!(
-                    "\tbin path: {}",
-                    manifest
-                        .target
-                        .bin_path()
-                        .map_or("(none)".to_string(), |p| p.to_string())
+                    "\t{}",
+                    msg!(
+                        "publish.confirm.includes",
+                        value =
+                            std::iter::once(MANIFEST_FILE_NAME)
+                                .chain(manifest.includes.iter().map(String::as_str).filter(
+                                    |pattern| !pattern.eq_ignore_ascii_case(MANIFEST_FILE_NAME)
+                                ))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                    )
                 );
             }
 
-            println!(
-                "includes: {}",
-                display_includes.into_iter().collect::<Vec<_>>().join(", ")
-            );
-
             if !self.dry_run
                 && !self.yes
-                && !inquire::Confirm::new("is this information correct?").prompt()?
+                && !inquire::Confirm::new(&msg!("publish.confirm.prompt")).prompt()?
             {
-                println!("\n{}", "publish aborted".red().bold());
+                println!("\n{}", msg!("publish.aborted").red().bold());
 
                 return Ok(());
             }
@@ -437,114 +302,54 @@ impl PublishCommand {
             println!();
         }
 
-        let mut temp_manifest = tempfile().context("failed to create temp manifest file")?;
-        temp_manifest
-            .write_all(
-                toml::to_string(&manifest)
-                    .context("failed to serialize manifest")?
-                    .as_bytes(),
-            )
-            .context("failed to write temp manifest file")?;
-        temp_manifest
-            .rewind()
-            .context("failed to rewind temp manifest file")?;
-
-        archive.append_file(MANIFEST_FILE_NAME, &mut temp_manifest)?;
-
-        let archive = archive
-            .into_inner()
-            .context("failed to encode archive")?
-            .finish()
-            .context("failed to get archive bytes")?;
-
-        let index_url = manifest
-            .indices
-            .get(DEFAULT_INDEX_NAME)
-            .context("missing default index")?;
-        let source = PesdePackageSource::new(index_url.clone());
-        source
-            .refresh(project)
-            .context("failed to refresh source")?;
-        let config = source
-            .config(project)
-            .context("failed to get source config")?;
-
-        if archive.len() > config.max_archive_size {
-            anyhow::bail!(
-                "archive size exceeds maximum size of {} bytes by {} bytes",
-                config.max_archive_size,
-                archive.len() - config.max_archive_size
-            );
-        }
-
-        manifest.all_dependencies().context("dependency conflict")?;
-
-        if !config.git_allowed && has_git {
-            anyhow::bail!("git dependencies are not allowed on this index");
-        }
-
-        #[cfg(feature = "wally-compat")]
-        if !config.wally_allowed && has_wally {
-            anyhow::bail!("wally dependencies are not allowed on this index");
-        }
-
-        if self.dry_run {
-            std::fs::write("package.tar.gz", archive)?;
-
-            println!(
-                "{}",
-                "(dry run) package written to package.tar.gz".green().bold()
-            );
-
-            return Ok(());
-        }
+        let options = PublishOptions::new().with_dry_run(self.dry_run);
 
-        let mut request = reqwest
-            .post(format!("{}/v0/packages", config.api()))
-            .multipart(reqwest::blocking::multipart::Form::new().part(
-                "tarball",
-                reqwest::blocking::multipart::Part::bytes(archive).file_name("package.tar.gz"),
-            ));
+        match publish_packages(project, &packages, &reqwest, &options) {
+            Ok(PublishOutcome::DryRun(files)) => {
+                let output = project.package_dir().join(&self.output);
 
-        if let Some(token) = project.auth_config().tokens().get(index_url) {
-            log::debug!("using token for {index_url}");
-            request = request.header(AUTHORIZATION, token);
-        }
+                for (package, files) in packages.iter().zip(files) {
+                    let output = if packages.len() > 1 {
+                        target_output(&output, package.manifest.target.kind())
+                    } else {
+                        output.clone()
+                    };
 
-        let response = request.send().context("failed to send request")?;
+                    write_dry_run(&package.archive, &files, &output)?;
+                }
 
-        let status = response.status();
-        let text = response.text().context("failed to get response text")?;
-        match status {
-            StatusCode::CONFLICT => {
-                println!("{}", "package version already exists".red().bold());
+                Ok(())
             }
-            StatusCode::FORBIDDEN => {
-                println!(
-                    "{}",
-                    "unauthorized to publish under this scope".red().bold()
-                );
+            Ok(PublishOutcome::Published(text)) => {
+                println!("{text}");
+
+                project
+                    .run_lifecycle_hook(ScriptName::PostPublish)
+                    .context("failed to run post publish hook")
             }
-            StatusCode::BAD_REQUEST => {
-                println!("{}: {text}", "invalid package".red().bold());
+            Err(PublishError::VersionExists) => {
+                println!("{}", msg!("publish.version_exists").red().bold());
+                Ok(())
             }
-            code if !code.is_success() => {
-                anyhow::bail!("failed to publish package: {code} ({text})");
+            Err(PublishError::Unauthorized) => {
+                println!("{}", msg!("publish.unauthorized_scope").red().bold());
+                Ok(())
             }
-            _ => {
-                println!("{text}");
+            Err(PublishError::InvalidPackage(text)) => {
+                println!("{}: {text}", msg!("publish.invalid_package").red().bold());
+                Ok(())
             }
+            Err(e) => Err(e.into()),
         }
-
-        Ok(())
     }
 
     pub fn run(self, project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
+        let project = project.with_hooks(!self.ignore_hooks);
         let result = self.run_impl(&project, reqwest.clone());
         if project.workspace_dir().is_some() {
             return result;
         } else if let Err(result) = result {
-            println!("an error occurred publishing workspace root: {result}");
+            println!("{}", msg!("publish.workspace_root_error", error = result));
         }
 
         run_on_workspace_members(&project, |project| self.run_impl(&project, reqwest.clone()))
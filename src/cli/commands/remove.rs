@@ -0,0 +1,189 @@
+use std::{str::FromStr, thread::JoinHandle};
+
+use anyhow::Context;
+use clap::Args;
+use indicatif::MultiProgress;
+
+use crate::cli::{commands::install::InstallCommand, messages::msg};
+use pesde::{
+    linking::generator::{assets_linking_module_name, bin_linking_module_name},
+    lockfile::remove_direct_dependency,
+    source::{specifiers::DependencySpecifiers, traits::PackageRef},
+    Project, PACKAGES_CONTAINER_NAME,
+};
+
+#[derive(Debug, Args)]
+pub struct RemoveCommand {
+    /// The alias or name of the dependency to remove
+    #[arg(index = 1)]
+    name: String,
+
+    /// Whether to run an install afterwards to re-link the remaining dependencies
+    #[arg(short, long)]
+    install: bool,
+}
+
+fn specifier_matches(spec: &DependencySpecifiers, name: &str) -> bool {
+    match spec {
+        DependencySpecifiers::Pesde(spec) => spec.name.to_string() == name,
+        #[cfg(feature = "wally-compat")]
+        DependencySpecifiers::Wally(spec) => spec.name.to_string() == name,
+        DependencySpecifiers::Git(spec) => spec.repo.to_bstring() == name,
+        DependencySpecifiers::Workspace(spec) => spec.name.to_string() == name,
+        DependencySpecifiers::Path(spec) => spec.path.to_string_lossy() == name,
+    }
+}
+
+impl RemoveCommand {
+    pub fn run(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+        update_task: &mut Option<JoinHandle<()>>,
+    ) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+        let dependencies = manifest
+            .all_dependencies()
+            .context("failed to get dependencies")?;
+
+        let alias = if dependencies.contains_key(&self.name) {
+            self.name.clone()
+        } else {
+            let mut matching = dependencies
+                .iter()
+                .filter(|(_, (spec, _))| specifier_matches(spec, &self.name))
+                .map(|(alias, _)| alias.clone());
+
+            let alias = matching
+                .next()
+                .with_context(|| format!("no dependency named {} found", self.name))?;
+
+            if matching.next().is_some() {
+                anyhow::bail!(
+                    "multiple dependencies match {}, specify the alias instead",
+                    self.name
+                );
+            }
+
+            alias
+        };
+
+        let mut document = toml_edit::DocumentMut::from_str(
+            &project.read_manifest().context("failed to read manifest")?,
+        )
+        .context("failed to parse manifest")?;
+
+        let mut dependency_key = None;
+        for key in ["dependencies", "peer_dependencies", "dev_dependencies"] {
+            let Some(table) = document
+                .get_mut(key)
+                .and_then(|item| item.as_table_like_mut())
+            else {
+                continue;
+            };
+
+            if table.remove(&alias).is_some() {
+                dependency_key = Some(key);
+                break;
+            }
+        }
+        let dependency_key =
+            dependency_key.with_context(|| format!("dependency {alias} not found in manifest"))?;
+
+        project
+            .write_manifest(document.to_string())
+            .context("failed to write manifest")?;
+
+        println!(
+            "{}",
+            msg!("remove.done", alias = alias, key = dependency_key)
+        );
+
+        match project.deser_lockfile() {
+            Ok(mut lockfile) => {
+                let project_target = manifest.target.kind();
+
+                for versions in lockfile.graph.values() {
+                    for node in versions.values() {
+                        if node.node.direct.as_ref().is_none_or(|(a, _)| *a != alias) {
+                            continue;
+                        }
+
+                        let base_folder = project
+                            .package_dir()
+                            .join(project_target.packages_folder(&node.node.pkg_ref.target_kind()));
+
+                        let files = [
+                            format!("{alias}.luau"),
+                            bin_linking_module_name(&alias, None),
+                            assets_linking_module_name(&alias),
+                        ]
+                        .into_iter()
+                        .chain(
+                            node.target.named_bins().into_iter().flat_map(|bins| {
+                                bins.keys()
+                                    .map(|name| bin_linking_module_name(&alias, Some(name)))
+                            }),
+                        );
+
+                        for file in files {
+                            match std::fs::remove_file(base_folder.join(&file)) {
+                                Ok(_) => {}
+                                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                                Err(e) => {
+                                    return Err(e).context(format!("failed to remove {file}"))
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let removed = remove_direct_dependency(&mut lockfile.graph, &alias);
+
+                for (name, version_id, node) in &removed {
+                    let container_folder = node.node.container_folder(
+                        &project
+                            .package_dir()
+                            .join(project_target.packages_folder(&node.node.pkg_ref.target_kind()))
+                            .join(PACKAGES_CONTAINER_NAME),
+                        name,
+                        version_id.version(),
+                    );
+
+                    // the container folder is nested in a folder for the version
+                    let version_folder = container_folder.parent().unwrap();
+
+                    match std::fs::remove_dir_all(version_folder) {
+                        Ok(_) => {}
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(e) => {
+                            return Err(e)
+                                .context(format!("failed to remove {}", version_folder.display()))
+                        }
+                    }
+
+                    // remove the package's folder if no other versions are left, ignoring errors if it isn't empty
+                    let _ = std::fs::remove_dir(version_folder.parent().unwrap());
+                }
+
+                println!("{}", msg!("remove.pruned", count = removed.len()));
+
+                project
+                    .write_lockfile(lockfile)
+                    .context("failed to write lockfile")?;
+            }
+            Err(pesde::errors::LockfileReadError::Io(e))
+                if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).context("failed to read lockfile"),
+        }
+
+        if self.install {
+            InstallCommand::default().run(project, multi, reqwest, update_task)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,434 @@
+use crate::cli::{
+    auth::get_tokens,
+    config::{read_config, CliConfig},
+    messages::msg,
+};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    lockfile::Lockfile,
+    names::PackageNames,
+    source::{
+        git_index::GitBasedSource, pesde::PesdePackageSource, traits::PackageRef,
+        version_id::VersionId, PackageSources,
+    },
+    AuthConfig, Project, LOCKFILE_FILE_NAME, MANIFEST_FILE_NAME,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    ffi::OsString,
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+const REPORT_FILE_NAME: &str = "report.toml";
+const TRACE_FILE_NAME: &str = "trace.log";
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Args)]
+pub struct ReportCommand {
+    /// Where to write the report archive to
+    #[arg(short, long, default_value = "pesde-report.tar.gz")]
+    output: PathBuf,
+
+    /// Re-runs resolution against the index states captured in the given report archive
+    #[arg(long, value_name = "ARCHIVE", conflicts_with_all = ["output", "command"])]
+    replay: Option<PathBuf>,
+
+    /// The failing command to capture a trace of, e.g. `pesde report -- install`
+    #[arg(last = true)]
+    command: Vec<OsString>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum IndexKind {
+    Pesde,
+    #[cfg(feature = "wally-compat")]
+    Wally,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CapturedIndex {
+    kind: IndexKind,
+    url: String,
+    commit: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CapturedConfig {
+    default_index: String,
+    scripts_repo: String,
+}
+
+impl From<&CliConfig> for CapturedConfig {
+    fn from(config: &CliConfig) -> Self {
+        CapturedConfig {
+            default_index: config.default_index.to_bstring().to_string(),
+            scripts_repo: config.scripts_repo.to_bstring().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Report {
+    pesde_version: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    os: String,
+    arch: String,
+    config: CapturedConfig,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    indices: Vec<CapturedIndex>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    command: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    exit_code: Option<i32>,
+}
+
+fn index_source(kind: IndexKind, url: gix::Url) -> PackageSources {
+    match kind {
+        IndexKind::Pesde => PackageSources::Pesde(PesdePackageSource::new(url)),
+        #[cfg(feature = "wally-compat")]
+        IndexKind::Wally => {
+            PackageSources::Wally(pesde::source::wally::WallyPackageSource::new(url))
+        }
+    }
+}
+
+fn used_indices(project: &Project) -> anyhow::Result<BTreeSet<(IndexKind, gix::Url)>> {
+    let mut indices = BTreeSet::new();
+
+    if let Ok(manifest) = project.deser_manifest() {
+        indices.extend(
+            manifest
+                .indices
+                .into_values()
+                .map(|url| (IndexKind::Pesde, url)),
+        );
+        #[cfg(feature = "wally-compat")]
+        indices.extend(
+            manifest
+                .wally_indices
+                .into_values()
+                .map(|url| (IndexKind::Wally, url)),
+        );
+    }
+
+    if let Ok(lockfile) = project.deser_lockfile() {
+        for node in lockfile.graph.values().flat_map(BTreeMap::values) {
+            match node.node.pkg_ref.source() {
+                PackageSources::Pesde(source) => {
+                    indices.insert((IndexKind::Pesde, source.repo_url().clone()));
+                }
+                #[cfg(feature = "wally-compat")]
+                PackageSources::Wally(source) => {
+                    indices.insert((IndexKind::Wally, source.repo_url().clone()));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(indices)
+}
+
+fn current_commit<S: GitBasedSource>(source: &S, project: &Project) -> anyhow::Result<String> {
+    let repo = gix::open(source.path(project)).context("failed to open index repository")?;
+    let local_ref = source
+        .local_ref_name(&repo)
+        .context("failed to get index reference")?;
+
+    Ok(repo
+        .find_reference(&local_ref)
+        .context("failed to find index reference")?
+        .into_fully_peeled_id()
+        .context("failed to peel index reference")?
+        .to_string())
+}
+
+fn pin_to_commit<S: GitBasedSource>(
+    source: &S,
+    project: &Project,
+    commit: &str,
+) -> anyhow::Result<()> {
+    let repo = gix::open(source.path(project)).context("failed to open index repository")?;
+    let local_ref = source
+        .local_ref_name(&repo)
+        .context("failed to get index reference")?;
+    let id = gix::ObjectId::from_hex(commit.as_bytes()).context("invalid commit id")?;
+
+    repo.reference(
+        local_ref.as_str(),
+        id,
+        gix::refs::transaction::PreviousValue::Any,
+        "pesde report replay",
+    )
+    .with_context(|| format!("failed to pin index to commit {commit}"))?;
+
+    Ok(())
+}
+
+fn redact(mut text: String, secrets: &[String]) -> String {
+    for secret in secrets {
+        let secret = secret.strip_prefix("Bearer ").unwrap_or(secret);
+        if !secret.is_empty() {
+            text = text.replace(secret, REDACTED);
+        }
+    }
+
+    text
+}
+
+fn append_file<W: std::io::Write>(
+    archive: &mut tar::Builder<W>,
+    name: &str,
+    contents: &[u8],
+) -> anyhow::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(chrono::Utc::now().timestamp() as u64);
+    header.set_cksum();
+
+    archive
+        .append_data(&mut header, name, contents)
+        .with_context(|| format!("failed to add {name} to report archive"))
+}
+
+impl ReportCommand {
+    fn capture(self, project: Project) -> anyhow::Result<()> {
+        let config = read_config()?;
+        let secrets = get_tokens()?.0.into_values().collect::<Vec<_>>();
+
+        let mut indices = vec![];
+        for (kind, url) in used_indices(&project)? {
+            let source = index_source(kind, url.clone());
+            let commit = match &source {
+                PackageSources::Pesde(source) => current_commit(source, &project),
+                #[cfg(feature = "wally-compat")]
+                PackageSources::Wally(source) => current_commit(source, &project),
+                _ => unreachable!(),
+            };
+
+            match commit {
+                Ok(commit) => indices.push(CapturedIndex {
+                    kind,
+                    url: url.to_bstring().to_string(),
+                    commit,
+                }),
+                Err(e) => log::warn!("failed to capture state of index {url}: {e:?}"),
+            }
+        }
+
+        let (trace, exit_code) = if self.command.is_empty() {
+            (None, None)
+        } else {
+            let output = std::process::Command::new(
+                std::env::current_exe().context("failed to get current executable path")?,
+            )
+            .args(&self.command)
+            .env("RUST_LOG", "trace")
+            .current_dir(project.package_dir())
+            .output()
+            .context("failed to run command")?;
+
+            let trace = format!(
+                "--- stdout ---\n{}\n--- stderr ---\n{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+
+            (Some(redact(trace, &secrets)), output.status.code())
+        };
+
+        let report = Report {
+            pesde_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: chrono::Utc::now(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            config: (&config).into(),
+            indices,
+            command: self
+                .command
+                .iter()
+                .map(|arg| redact(arg.to_string_lossy().to_string(), &secrets))
+                .collect(),
+            exit_code,
+        };
+
+        let file = File::create(&self.output).context("failed to create report archive")?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::best());
+        let mut archive = tar::Builder::new(encoder);
+
+        append_file(
+            &mut archive,
+            REPORT_FILE_NAME,
+            toml::to_string(&report)
+                .context("failed to serialize report")?
+                .as_bytes(),
+        )?;
+
+        for file_name in [MANIFEST_FILE_NAME, LOCKFILE_FILE_NAME] {
+            match std::fs::read_to_string(project.package_dir().join(file_name)) {
+                Ok(contents) => append_file(
+                    &mut archive,
+                    file_name,
+                    redact(contents, &secrets).as_bytes(),
+                )?,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).context(format!("failed to read {file_name}")),
+            }
+        }
+
+        if let Some(trace) = trace {
+            append_file(&mut archive, TRACE_FILE_NAME, trace.as_bytes())?;
+        }
+
+        archive
+            .into_inner()
+            .context("failed to write report archive")?
+            .finish()
+            .context("failed to finish report archive")?;
+
+        println!(
+            "{} {}",
+            msg!(
+                "report.written",
+                path = self.output.display().to_string().bold()
+            ),
+            msg!("report.sensitive").yellow()
+        );
+
+        Ok(())
+    }
+
+    fn replay(archive_path: &Path) -> anyhow::Result<()> {
+        let dir = pesde::tempdirs::tempdir().context("failed to create temporary directory")?;
+
+        {
+            let file = File::open(archive_path).context("failed to open report archive")?;
+            let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            archive
+                .unpack(dir.path())
+                .context("failed to unpack report archive")?;
+        }
+
+        let report: Report = toml::from_str(
+            &std::fs::read_to_string(dir.path().join(REPORT_FILE_NAME))
+                .context("failed to read report")?,
+        )
+        .context("failed to parse report")?;
+
+        println!(
+            "{}",
+            msg!(
+                "report.replaying",
+                version = report.pesde_version,
+                os = report.os,
+                arch = report.arch,
+                created_at = report.created_at
+            )
+        );
+        if !report.command.is_empty() {
+            println!(
+                "{}",
+                msg!(
+                    "report.command",
+                    command = report.command.join(" "),
+                    code = report
+                        .exit_code
+                        .map_or_else(|| "unknown".to_string(), |code| code.to_string())
+                )
+            );
+        }
+
+        let project = Project::new(
+            dir.path(),
+            None::<PathBuf>,
+            dir.path().join("data"),
+            dir.path().join("cas"),
+            AuthConfig::new().with_tokens(get_tokens()?.0),
+        );
+
+        let mut refreshed_sources = HashSet::new();
+
+        for index in &report.indices {
+            let url = gix::Url::try_from(index.url.as_str())
+                .with_context(|| format!("invalid index url {}", index.url))?;
+            let source = index_source(index.kind, url);
+
+            match &source {
+                PackageSources::Pesde(source) => {
+                    GitBasedSource::refresh(source, &project)
+                        .with_context(|| format!("failed to refresh index {}", index.url))?;
+                    pin_to_commit(source, &project, &index.commit)?;
+                }
+                #[cfg(feature = "wally-compat")]
+                PackageSources::Wally(source) => {
+                    GitBasedSource::refresh(source, &project)
+                        .with_context(|| format!("failed to refresh index {}", index.url))?;
+                    pin_to_commit(source, &project, &index.commit)?;
+                }
+                _ => unreachable!(),
+            }
+
+            log::debug!("pinned index {} to {}", index.url, index.commit);
+            refreshed_sources.insert(source);
+        }
+
+        let graph = project
+            .dependency_graph(None, &mut refreshed_sources)
+            .context("failed to replay resolution")?;
+
+        let resolved = graph
+            .iter()
+            .flat_map(|(name, versions)| versions.keys().map(move |v_id| (name, v_id)))
+            .collect::<BTreeSet<(&PackageNames, &VersionId)>>();
+
+        println!("{}", msg!("report.resolved").green().bold());
+
+        let lockfile = match std::fs::read_to_string(dir.path().join(LOCKFILE_FILE_NAME)) {
+            Ok(lockfile) => toml::from_str::<Lockfile>(&lockfile)
+                .context("failed to parse captured lockfile")?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                for (name, version_id) in resolved {
+                    println!("{name}@{version_id}");
+                }
+
+                return Ok(());
+            }
+            Err(e) => return Err(e).context("failed to read captured lockfile"),
+        };
+
+        let locked = lockfile
+            .graph
+            .iter()
+            .flat_map(|(name, versions)| versions.keys().map(move |v_id| (name, v_id)))
+            .collect::<BTreeSet<_>>();
+
+        let mut differs = false;
+        for (name, version_id) in resolved.difference(&locked) {
+            differs = true;
+            println!("{} {name}@{version_id}", "+".green().bold());
+        }
+        for (name, version_id) in locked.difference(&resolved) {
+            differs = true;
+            println!("{} {name}@{version_id}", "-".red().bold());
+        }
+
+        if !differs {
+            println!("{}", msg!("report.matches_lockfile"));
+        }
+
+        Ok(())
+    }
+
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        match &self.replay {
+            Some(archive) => Self::replay(archive),
+            None => self.capture(project),
+        }
+    }
+}
@@ -0,0 +1,164 @@
+use std::{collections::HashSet, path::PathBuf};
+
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+
+use crate::cli::messages::msg;
+use pesde::{
+    lockfile::{graph_snapshot, DependencyGraph},
+    Project,
+};
+
+#[derive(Debug, Args)]
+pub struct ResolveCommand {
+    /// The snapshot file to compare the resolved graph with, relative to the package's directory.
+    /// It is written if it doesn't exist yet
+    #[arg(short, long)]
+    snapshot: Option<PathBuf>,
+
+    /// Whether to overwrite the snapshot with the resolved graph instead of comparing them
+    #[arg(short, long, requires = "snapshot")]
+    update: bool,
+
+    /// Whether to resolve every package again, ignoring the versions in the lockfile
+    #[arg(short, long)]
+    fresh: bool,
+
+    /// The features of the manifest whose optional dependencies to resolve
+    #[arg(long = "with", value_name = "FEATURE")]
+    features: Vec<String>,
+}
+
+/// The lines removed from and added to `old` to get `new`, in order
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(char, &'a str)> {
+    // lengths of the longest common subsequences of the suffixes of both
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = vec![];
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(('-', old[i]));
+            i += 1;
+        } else {
+            diff.push(('+', new[j]));
+            j += 1;
+        }
+    }
+
+    diff
+}
+
+impl ResolveCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let project = project.with_features(self.features.into_iter().collect());
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+
+        let previous_graph = if self.fresh {
+            None
+        } else {
+            match project.deser_lockfile() {
+                Ok(lockfile)
+                    if lockfile.overrides == manifest.overrides
+                        && lockfile.target == manifest.target.kind() =>
+                {
+                    Some(lockfile.graph)
+                }
+                Ok(_) => None,
+                Err(pesde::errors::LockfileReadError::Io(e))
+                    if e.kind() == std::io::ErrorKind::NotFound =>
+                {
+                    None
+                }
+                Err(e) => return Err(e).context("failed to read lockfile"),
+            }
+        }
+        .map(|graph| {
+            graph
+                .into_iter()
+                .map(|(name, versions)| {
+                    (
+                        name,
+                        versions
+                            .into_iter()
+                            .map(|(version, node)| (version, node.node))
+                            .collect(),
+                    )
+                })
+                .collect::<DependencyGraph>()
+        });
+
+        let graph = project
+            .dependency_graph(previous_graph.as_ref(), &mut HashSet::new())
+            .context("failed to build dependency graph")?;
+        let snapshot = graph_snapshot(&graph);
+
+        let Some(path) = self.snapshot else {
+            print!("{snapshot}");
+            return Ok(());
+        };
+        let path = project.package_dir().join(path);
+
+        let existing = match std::fs::read_to_string(&path) {
+            Ok(existing) if !self.update => existing,
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(e).context("failed to read snapshot");
+            }
+            _ => {
+                std::fs::write(&path, &snapshot).context("failed to write snapshot")?;
+                println!(
+                    "{}",
+                    msg!("resolve.snapshot_written", path = path.display())
+                );
+
+                return Ok(());
+            }
+        };
+
+        // normalize line endings, which Git may have changed on checkout
+        let existing = existing.replace("\r\n", "\n");
+        if existing == snapshot {
+            println!(
+                "{}",
+                msg!("resolve.snapshot_matches", path = path.display())
+            );
+            return Ok(());
+        }
+
+        let old = existing.lines().collect::<Vec<_>>();
+        let new = snapshot.lines().collect::<Vec<_>>();
+        for (sign, line) in diff_lines(&old, &new) {
+            let line = format!("{sign} {line}");
+            if sign == '+' {
+                println!("{}", line.green());
+            } else {
+                println!("{}", line.red());
+            }
+        }
+
+        anyhow::bail!(
+            "{}",
+            msg!(
+                "resolve.snapshot_changed",
+                path = path.display(),
+                bin = env!("CARGO_BIN_NAME")
+            )
+        );
+    }
+}
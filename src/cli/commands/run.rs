@@ -1,26 +1,159 @@
-use crate::cli::up_to_date_lockfile;
+use crate::cli::{messages::msg, up_to_date_lockfile};
 use anyhow::Context;
 use clap::Args;
+use colored::Colorize;
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
 use pesde::{
     linking::generator::generate_bin_linking_module,
+    manifest::target::TargetKind,
     names::{PackageName, PackageNames},
     source::traits::PackageRef,
     Project, PACKAGES_CONTAINER_NAME,
 };
 use relative_path::RelativePathBuf;
 use std::{
-    env::current_dir, ffi::OsString, io::Write, path::PathBuf, process::Command, thread::JoinHandle,
+    env::current_dir,
+    ffi::OsString,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    sync::mpsc,
+    thread::JoinHandle,
+    time::Duration,
 };
 
 #[derive(Debug, Args)]
 pub struct RunCommand {
-    /// The package name, script name, or path to a script to run
+    /// The package name, script name, or path to a script to run. A named bin export of a
+    /// package is run with `scope/name:bin`
     #[arg(index = 1)]
     package_or_script: Option<String>,
 
     /// Arguments to pass to the script
     #[arg(index = 2, last = true)]
     args: Vec<OsString>,
+
+    /// Run the script in every workspace member which has it
+    #[arg(short, long)]
+    workspace: bool,
+
+    /// Only run in members affected by the changed paths read from stdin, one per line,
+    /// relative to the workspace root
+    #[arg(long, requires = "workspace")]
+    affected: bool,
+
+    /// Re-run the script whenever files in the project change
+    #[arg(long, conflicts_with = "workspace")]
+    watch: bool,
+}
+
+/// How long to wait for more changes before re-running the script in watch mode
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+
+fn lune_command(
+    package_dir: &Path,
+    script: &Path,
+    args: &[OsString],
+    cwd: &Path,
+) -> (Command, tempfile::NamedTempFile) {
+    let mut caller = tempfile::NamedTempFile::new().expect("failed to create tempfile");
+    caller
+        .write_all(
+            generate_bin_linking_module(package_dir, &format!("{:?}", script.to_string_lossy()))
+                .as_bytes(),
+        )
+        .expect("failed to write to tempfile");
+
+    let mut command = Command::new("lune");
+    command
+        .arg("run")
+        .arg(caller.path())
+        .arg("--")
+        .args(args)
+        .current_dir(cwd);
+
+    (command, caller)
+}
+
+fn run_with_lune(package_dir: &Path, script: &Path, args: &[OsString], cwd: &Path) -> ExitStatus {
+    let (mut command, caller) = lune_command(package_dir, script, args, cwd);
+    let status = command.status().expect("failed to run script");
+
+    drop(caller);
+
+    status
+}
+
+/// Whether a change to the path should re-run the script, which isn't the case for installed packages
+fn is_watched(package_dir: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(package_dir) else {
+        return false;
+    };
+
+    !relative.components().any(|component| {
+        let component = component.as_os_str();
+
+        component == PACKAGES_CONTAINER_NAME
+            || component == ".git"
+            || TargetKind::VARIANTS
+                .iter()
+                .any(|kind| component == kind.packages_folder(kind).as_str())
+    })
+}
+
+fn watch_with_lune(
+    package_dir: &Path,
+    script: &Path,
+    args: &[OsString],
+    cwd: &Path,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer =
+        new_debouncer(WATCH_DEBOUNCE, tx).context("failed to create file watcher")?;
+    debouncer
+        .watcher()
+        .watch(package_dir, RecursiveMode::Recursive)
+        .context("failed to watch project directory")?;
+
+    loop {
+        let (mut command, caller) = lune_command(package_dir, script, args, cwd);
+        let mut child = command.spawn().context("failed to run script")?;
+        let mut exited = false;
+
+        loop {
+            match rx.recv_timeout(Duration::from_millis(100)) {
+                Ok(Ok(events)) => {
+                    if events
+                        .iter()
+                        .any(|event| is_watched(package_dir, &event.path))
+                    {
+                        break;
+                    }
+                }
+                Ok(Err(e)) => log::warn!("file watcher error: {e}"),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    anyhow::bail!("file watcher stopped unexpectedly")
+                }
+            }
+
+            if !exited {
+                if let Some(status) = child.try_wait().context("failed to wait for script")? {
+                    exited = true;
+                    println!("{}", msg!("run.watch.exited", status = status).dimmed());
+                }
+            }
+        }
+
+        if !exited {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        drop(caller);
+
+        println!("{}", msg!("run.watch.rerunning").dimmed());
+    }
 }
 
 impl RunCommand {
@@ -29,48 +162,51 @@ impl RunCommand {
         project: Project,
         update_task: &mut Option<JoinHandle<()>>,
     ) -> anyhow::Result<()> {
-        let mut run = |path: PathBuf| {
+        let mut run = |path: PathBuf| -> anyhow::Result<()> {
             if let Some(handle) = update_task.take() {
                 handle.join().expect("failed to join update task");
             }
 
-            let mut caller = tempfile::NamedTempFile::new().expect("failed to create tempfile");
-            caller
-                .write_all(
-                    generate_bin_linking_module(
-                        project.package_dir(),
-                        &format!("{:?}", path.to_string_lossy()),
-                    )
-                    .as_bytes(),
-                )
-                .expect("failed to write to tempfile");
-
-            let status = Command::new("lune")
-                .arg("run")
-                .arg(caller.path())
-                .arg("--")
-                .args(&self.args)
-                .current_dir(current_dir().expect("failed to get current directory"))
-                .status()
-                .expect("failed to run script");
-
-            drop(caller);
+            let cwd = current_dir().expect("failed to get current directory");
+
+            if self.watch {
+                return watch_with_lune(project.package_dir(), &path, &self.args, &cwd);
+            }
+
+            let status = run_with_lune(project.package_dir(), &path, &self.args, &cwd);
 
             std::process::exit(status.code().unwrap_or(1))
         };
 
+        if self.workspace {
+            if let Some(handle) = update_task.take() {
+                handle.join().expect("failed to join update task");
+            }
+
+            let script = self
+                .package_or_script
+                .context("a script name is required with --workspace")?;
+
+            return run_on_workspace(&project, &script, &self.args, self.affected);
+        }
+
         let package_or_script = match self.package_or_script {
             Some(package_or_script) => package_or_script,
             None => {
                 if let Some(script_path) = project.deser_manifest()?.target.bin_path() {
-                    run(script_path.to_path(project.package_dir()));
+                    return run(script_path.to_path(project.package_dir()));
                 }
 
                 anyhow::bail!("no package or script specified")
             }
         };
 
-        if let Ok(pkg_name) = package_or_script.parse::<PackageName>() {
+        let (package, bin_name) = match package_or_script.split_once(':') {
+            Some((package, bin_name)) => (package, Some(bin_name)),
+            None => (package_or_script.as_str(), None),
+        };
+
+        if let Ok(pkg_name) = package.parse::<PackageName>() {
             let graph = if let Some(lockfile) = up_to_date_lockfile(&project)? {
                 lockfile.graph
             } else {
@@ -84,8 +220,13 @@ impl RunCommand {
                     continue;
                 }
 
-                let Some(bin_path) = node.target.bin_path() else {
-                    anyhow::bail!("package has no bin path");
+                let bin_path = match bin_name {
+                    Some(bin_name) => node
+                        .target
+                        .named_bins()
+                        .and_then(|bins| bins.get(bin_name))
+                        .with_context(|| format!("package has no bin named {bin_name}"))?,
+                    None => node.target.bin_path().context("package has no bin path")?,
                 };
 
                 let base_folder = project
@@ -102,13 +243,13 @@ impl RunCommand {
                     version_id.version(),
                 );
 
-                run(bin_path.to_path(&container_folder))
+                return run(bin_path.to_path(&container_folder));
             }
         }
 
         if let Ok(manifest) = project.deser_manifest() {
             if let Some(script_path) = manifest.scripts.get(&package_or_script) {
-                run(script_path.to_path(project.package_dir()))
+                return run(script_path.to_path(project.package_dir()));
             }
         };
 
@@ -119,8 +260,74 @@ impl RunCommand {
             anyhow::bail!("path does not exist: {}", path.display());
         }
 
-        run(path);
+        run(path)
+    }
+}
+
+fn run_on_workspace(
+    project: &Project,
+    script: &str,
+    args: &[OsString],
+    affected: bool,
+) -> anyhow::Result<()> {
+    let graph = project
+        .workspace_graph()
+        .context("failed to build workspace graph")?;
+
+    let affected = if affected {
+        let changed_paths = std::io::stdin()
+            .lock()
+            .lines()
+            .collect::<Result<Vec<_>, _>>()
+            .context("failed to read changed paths from stdin")?;
+
+        Some(
+            graph.affected_by(
+                changed_paths
+                    .iter()
+                    .map(|path| path.trim())
+                    .filter(|path| !path.is_empty()),
+            ),
+        )
+    } else {
+        None
+    };
+
+    let mut failed = vec![];
+
+    for member in graph.sorted() {
+        if affected
+            .as_ref()
+            .is_some_and(|affected| !affected.contains(member))
+        {
+            continue;
+        }
 
-        Ok(())
+        let manifest = &graph.members[member];
+        let Some(script_path) = manifest.scripts.get(script) else {
+            continue;
+        };
+
+        println!(
+            "{}",
+            msg!(
+                "run.workspace.header",
+                script = script,
+                name = manifest.name,
+                target = manifest.target
+            )
+            .bold()
+        );
+
+        let status = run_with_lune(member, &script_path.to_path(member), args, member);
+        if !status.success() {
+            failed.push(manifest.name.to_string());
+        }
     }
+
+    if !failed.is_empty() {
+        anyhow::bail!("script {script} failed in {}", failed.join(", "));
+    }
+
+    Ok(())
 }
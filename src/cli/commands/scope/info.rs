@@ -0,0 +1,73 @@
+use crate::cli::{config::read_config, messages::msg};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{registry_client::RegistryClient, Project, DEFAULT_INDEX_NAME};
+
+#[derive(Debug, Args)]
+pub struct InfoCommand {
+    /// The scope to show
+    #[arg(index = 1)]
+    scope: String,
+
+    /// The index URL the scope is in, defaulting to the project's default index
+    #[arg(short, long, value_parser = crate::cli::parse_gix_url)]
+    index: Option<gix::Url>,
+}
+
+impl InfoCommand {
+    pub fn run(self, project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
+        let index_url = match self.index {
+            Some(index) => index,
+            None => match project
+                .deser_manifest()
+                .ok()
+                .and_then(|manifest| manifest.indices.get(DEFAULT_INDEX_NAME).cloned())
+            {
+                Some(index) => index,
+                None => read_config()?.default_index,
+            },
+        };
+
+        let Some(response) = RegistryClient::from_index(&project, &reqwest, &index_url)
+            .context("failed to create registry client")?
+            .scope(&self.scope)
+            .context("failed to get scope info")?
+        else {
+            anyhow::bail!("scope {} does not exist", self.scope);
+        };
+
+        println!("{}", self.scope.bold());
+        println!(
+            "{}",
+            msg!(
+                "scope.owners",
+                owners = response
+                    .owners
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        );
+
+        if response.packages.is_empty() {
+            println!("{}", msg!("scope.no_packages"));
+            return Ok(());
+        }
+
+        println!();
+
+        for package in &response.packages {
+            print!("{}@{}", package.name.bold(), package.version);
+
+            if package.description.is_empty() {
+                println!();
+            } else {
+                println!(" - {}", package.description.dimmed());
+            }
+        }
+
+        Ok(())
+    }
+}
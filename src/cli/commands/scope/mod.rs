@@ -0,0 +1,18 @@
+use clap::Subcommand;
+use pesde::Project;
+
+mod info;
+
+#[derive(Debug, Subcommand)]
+pub enum ScopeCommands {
+    /// Shows the owners and packages of a scope
+    Info(info::InfoCommand),
+}
+
+impl ScopeCommands {
+    pub fn run(self, project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
+        match self {
+            ScopeCommands::Info(info) => info.run(project, reqwest),
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use crate::cli::{config::read_config, messages::msg};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    manifest::target::TargetKind,
+    registry_client::{self, RegistryClient, SearchQuery, SearchResponse},
+    Project, DEFAULT_INDEX_NAME,
+};
+
+#[derive(Debug, Args)]
+pub struct SearchCommand {
+    /// The text to search for
+    #[arg(index = 1)]
+    query: Option<String>,
+
+    /// Only show packages in this category
+    #[arg(short, long)]
+    category: Option<String>,
+
+    /// Only show packages whose latest version supports this target
+    #[arg(short, long)]
+    target: Option<TargetKind>,
+
+    /// Only show packages in this scope
+    #[arg(short, long)]
+    scope: Option<String>,
+
+    /// Only show packages with this license
+    #[arg(short, long)]
+    license: Option<String>,
+
+    /// How to order the packages
+    #[arg(long, value_enum, default_value_t = SearchSort::Recent)]
+    sort: SearchSort,
+
+    /// The maximum number of packages to show, fetching more pages if needed
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Whether to output the packages as JSON
+    #[arg(long)]
+    json: bool,
+
+    /// The index URL to search in, defaulting to the project's default index
+    #[arg(short, long, value_parser = crate::cli::parse_gix_url)]
+    index: Option<gix::Url>,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum SearchSort {
+    /// The most recently published packages first
+    Recent,
+    /// The packages best matching the query first
+    Relevance,
+    /// The most downloaded packages first
+    Downloads,
+}
+
+impl From<SearchSort> for registry_client::SearchSort {
+    fn from(sort: SearchSort) -> Self {
+        match sort {
+            SearchSort::Recent => registry_client::SearchSort::Recent,
+            SearchSort::Relevance => registry_client::SearchSort::Relevance,
+            SearchSort::Downloads => registry_client::SearchSort::Downloads,
+        }
+    }
+}
+
+/// Searches the packages of an index through its API, fetching more pages until `limit` packages
+/// are found, or just the first page without a limit
+pub fn search_index(
+    project: &Project,
+    reqwest: &reqwest::blocking::Client,
+    index_url: &gix::Url,
+    query: &SearchQuery,
+    limit: Option<usize>,
+) -> anyhow::Result<SearchResponse> {
+    RegistryClient::from_index(project, reqwest, index_url)
+        .context("failed to create registry client")?
+        .search_all(query, limit)
+        .context("failed to search packages")
+}
+
+impl SearchCommand {
+    pub fn run(self, project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
+        let index_url = match self.index {
+            Some(index) => index,
+            None => match project
+                .deser_manifest()
+                .ok()
+                .and_then(|manifest| manifest.indices.get(DEFAULT_INDEX_NAME).cloned())
+            {
+                Some(index) => index,
+                None => read_config()?.default_index,
+            },
+        };
+
+        let query = SearchQuery {
+            query: self.query.unwrap_or_default(),
+            category: self.category,
+            target: self.target,
+            scope: self.scope,
+            license: self.license,
+            sort: Some(self.sort.into()),
+        };
+
+        let response = search_index(&project, &reqwest, &index_url, &query, self.limit)?;
+
+        if self.json {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&response.data)
+                    .context("failed to serialize packages")?
+            );
+            return Ok(());
+        }
+
+        if response.data.is_empty() {
+            println!("{}", msg!("search.none"));
+            return Ok(());
+        }
+
+        let rows = response
+            .data
+            .iter()
+            .map(|result| {
+                [
+                    result.name.clone(),
+                    result.version.clone(),
+                    result
+                        .targets
+                        .iter()
+                        .map(|target| target.kind.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ]
+            })
+            .collect::<Vec<_>>();
+        let header = ["NAME", "VERSION", "TARGETS"].map(str::to_string);
+
+        let widths = std::iter::once(&header)
+            .chain(&rows)
+            .fold([0; 3], |mut widths, row| {
+                for (width, cell) in widths.iter_mut().zip(row) {
+                    *width = (*width).max(cell.chars().count());
+                }
+                widths
+            });
+
+        let [name_width, version_width, targets_width] = widths;
+
+        println!(
+            "{}",
+            format!(
+                "{:<name_width$}  {:<version_width$}  {:<targets_width$}  DESCRIPTION",
+                header[0], header[1], header[2],
+            )
+            .dimmed()
+        );
+
+        for ([name, version, targets], result) in rows.iter().zip(&response.data) {
+            println!(
+                "{}  {version:<version_width$}  {targets:<targets_width$}  {}",
+                format!("{name:<name_width$}").bold(),
+                result.description,
+            );
+        }
+
+        if response.count > response.data.len() {
+            println!(
+                "\n{}",
+                msg!(
+                    "search.more",
+                    shown = response.data.len(),
+                    count = response.count
+                )
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -1,4 +1,8 @@
-use crate::cli::{config::read_config, version::get_or_download_version};
+use crate::cli::{
+    config::{read_config, VersionSource},
+    version::{check_for_updates, get_or_download_version},
+};
+use anyhow::Context;
 use clap::Args;
 
 #[derive(Debug, Args)]
@@ -6,9 +10,18 @@ pub struct SelfUpgradeCommand {}
 
 impl SelfUpgradeCommand {
     pub fn run(self, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
-        let config = read_config()?;
+        if read_config()?.version_source == VersionSource::Disabled {
+            anyhow::bail!("version management is disabled, cannot upgrade");
+        }
 
-        get_or_download_version(&reqwest, &config.last_checked_updates.unwrap().1)?;
+        check_for_updates(&reqwest)?;
+
+        let version = read_config()?
+            .last_checked_updates
+            .context("no version to upgrade to was found")?
+            .1;
+
+        get_or_download_version(&reqwest, &version, false)?;
         // a call to `update_bin_exe` or other similar function *should* be here, in case new versions
         // have fixes to bugs in executing other versions, but that would cause
         // the current file to be overwritten by itself, so this needs more thought
@@ -0,0 +1,802 @@
+use crate::cli::messages::msg;
+use anyhow::Context;
+use clap::Args;
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use pesde::{
+    manifest::target::{RobloxPlaceKind, TargetKind},
+    Project,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+    sync::{mpsc, Arc, Condvar, Mutex, MutexGuard, PoisonError},
+    time::Duration,
+};
+
+/// The port the Rojo plugin connects to by default
+const DEFAULT_PORT: u16 = 34872;
+/// The version of the Rojo serve protocol implemented, used by Rojo 7.0 to 7.4
+const PROTOCOL_VERSION: u32 = 4;
+/// The Rojo version reported to the plugin, which it checks for compatibility
+const SERVER_VERSION: &str = "7.4.0";
+/// How long subscriptions are held open waiting for changes
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(25);
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+/// How long a connection may take to send its request or accept its response
+const SOCKET_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many messages are kept for subscribers catching up
+const MAX_MESSAGES: usize = 64;
+/// The id Rojo uses for the absence of an instance
+const NONE_ID: &str = "00000000000000000000000000000000";
+
+#[derive(Debug, Args)]
+pub struct StudioSyncCommand {
+    /// The port to listen on for the Rojo plugin
+    #[arg(short, long, default_value_t = DEFAULT_PORT)]
+    port: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Metadata {
+    ignore_unknown_instances: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Instance {
+    id: String,
+    parent: String,
+    name: String,
+    class_name: String,
+    properties: BTreeMap<String, Value>,
+    children: Vec<String>,
+    metadata: Metadata,
+}
+
+type Tree = BTreeMap<String, Instance>;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Update {
+    id: String,
+    changed_name: Option<String>,
+    changed_class_name: Option<String>,
+    changed_properties: BTreeMap<String, Value>,
+    changed_metadata: Option<Metadata>,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Message {
+    removed: Vec<String>,
+    added: BTreeMap<String, Instance>,
+    updated: Vec<Update>,
+}
+
+impl Message {
+    fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.added.is_empty() && self.updated.is_empty()
+    }
+}
+
+struct Session {
+    id: String,
+    project_name: String,
+    root_id: String,
+    tree: Tree,
+    /// The cursor of the first message kept
+    first_cursor: usize,
+    messages: Vec<Message>,
+}
+
+impl Session {
+    /// The cursor of the next message
+    fn cursor(&self) -> usize {
+        self.first_cursor + self.messages.len()
+    }
+
+    /// The messages from the cursor on, which is `None` if some of them were already dropped
+    fn messages_since(&self, cursor: usize) -> Option<&[Message]> {
+        let start = cursor.checked_sub(self.first_cursor)?;
+        Some(self.messages.get(start..).unwrap_or_default())
+    }
+
+    /// Adds a message, dropping the oldest ones beyond the cap
+    fn push(&mut self, message: Message) {
+        self.messages.push(message);
+
+        let excess = self.messages.len().saturating_sub(MAX_MESSAGES);
+        self.messages.drain(..excess);
+        self.first_cursor += excess;
+    }
+}
+
+type SharedSession = Arc<(Mutex<Session>, Condvar)>;
+
+/// Locks the session, which stays consistent even if a thread panicked while holding it
+fn lock(session: &Mutex<Session>) -> MutexGuard<'_, Session> {
+    session.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Where the packages folder is placed in the data model
+struct Placement {
+    service: String,
+    /// The folders leading to the packages folder, the last being the packages folder itself
+    folders: Vec<String>,
+}
+
+/// Parses a place such as `game.ReplicatedStorage.Packages` or
+/// `game:GetService("ReplicatedStorage").Packages`
+fn parse_place(place: &str) -> Option<Placement> {
+    let rest = place.trim().strip_prefix("game")?;
+
+    let (service, rest) = if let Some(rest) = rest.strip_prefix(":GetService(") {
+        let (service, rest) = rest.split_once(')')?;
+        (service.trim_matches(['"', '\'']).to_string(), rest)
+    } else {
+        let rest = rest.strip_prefix('.')?;
+        let (service, rest) = rest.split_once('.').unwrap_or((rest, ""));
+        (service.to_string(), rest)
+    };
+
+    let folders = rest
+        .split('.')
+        .filter(|part| !part.is_empty())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    if service.is_empty() || folders.is_empty() {
+        return None;
+    }
+
+    Some(Placement { service, folders })
+}
+
+fn instance_id(path: &str) -> String {
+    format!("{:x}", Sha256::digest(path.as_bytes()))[..32].to_string()
+}
+
+/// The class name and instance name of a script file, or `None` if the file isn't a script
+fn classify_script(file_name: &str) -> Option<(&'static str, &str)> {
+    let stem = file_name
+        .strip_suffix(".luau")
+        .or_else(|| file_name.strip_suffix(".lua"))?;
+
+    Some(if let Some(stem) = stem.strip_suffix(".server") {
+        ("Script", stem)
+    } else if let Some(stem) = stem.strip_suffix(".client") {
+        ("LocalScript", stem)
+    } else {
+        ("ModuleScript", stem)
+    })
+}
+
+fn source_property(path: &Path) -> anyhow::Result<BTreeMap<String, Value>> {
+    let source = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+
+    Ok(BTreeMap::from([(
+        "Source".to_string(),
+        json!({ "String": source }),
+    )]))
+}
+
+fn insert(tree: &mut Tree, instance: Instance) -> String {
+    let id = instance.id.clone();
+
+    if let Some(parent) = tree.get_mut(&instance.parent) {
+        parent.children.push(id.clone());
+    }
+    tree.insert(id.clone(), instance);
+
+    id
+}
+
+/// Adds a directory and its contents, mapped the way Rojo maps them, to the tree
+fn add_dir(
+    tree: &mut Tree,
+    parent: &str,
+    dir: &Path,
+    key: &str,
+    name: String,
+    ignore_unknown_instances: bool,
+) -> anyhow::Result<()> {
+    let mut entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("failed to read {}", dir.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => vec![],
+        Err(e) => return Err(e).with_context(|| format!("failed to read {}", dir.display())),
+    };
+    entries.sort_by_key(|entry| entry.file_name());
+
+    let init = entries.iter().find_map(|entry| {
+        let file_name = entry.file_name();
+        let (class_name, stem) = classify_script(file_name.to_str()?)?;
+        (stem == "init").then(|| (class_name, entry.path()))
+    });
+
+    let (class_name, properties) = match &init {
+        Some((class_name, path)) => (class_name.to_string(), source_property(path)?),
+        None => ("Folder".to_string(), BTreeMap::new()),
+    };
+
+    let id = insert(
+        tree,
+        Instance {
+            id: instance_id(key),
+            parent: parent.to_string(),
+            name,
+            class_name,
+            properties,
+            children: vec![],
+            metadata: Metadata {
+                ignore_unknown_instances,
+            },
+        },
+    );
+
+    for entry in entries {
+        let path = entry.path();
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let key = format!("{key}/{file_name}");
+
+        if path.is_dir() {
+            add_dir(tree, &id, &path, &key, file_name, false)?;
+            continue;
+        }
+
+        let Some((class_name, stem)) = classify_script(&file_name) else {
+            continue;
+        };
+
+        if init.as_ref().is_some_and(|(_, init)| *init == path) {
+            continue;
+        }
+
+        insert(
+            tree,
+            Instance {
+                id: instance_id(&key),
+                parent: id.clone(),
+                name: stem.to_string(),
+                class_name: class_name.to_string(),
+                properties: source_property(&path)?,
+                children: vec![],
+                metadata: Metadata {
+                    ignore_unknown_instances: false,
+                },
+            },
+        );
+    }
+
+    Ok(())
+}
+
+/// Builds the tree of the data model, containing only the packages folder and its ancestors
+fn build_tree(
+    project_name: &str,
+    placement: &Placement,
+    packages_dir: &Path,
+) -> anyhow::Result<(String, Tree)> {
+    let mut tree = Tree::new();
+
+    let root_id = insert(
+        &mut tree,
+        Instance {
+            id: instance_id(""),
+            parent: NONE_ID.to_string(),
+            name: project_name.to_string(),
+            class_name: "DataModel".to_string(),
+            properties: BTreeMap::new(),
+            children: vec![],
+            metadata: Metadata {
+                ignore_unknown_instances: true,
+            },
+        },
+    );
+
+    let mut key = format!("game/{}", placement.service);
+    let mut parent = insert(
+        &mut tree,
+        Instance {
+            id: instance_id(&key),
+            parent: root_id.clone(),
+            name: placement.service.clone(),
+            class_name: placement.service.clone(),
+            properties: BTreeMap::new(),
+            children: vec![],
+            metadata: Metadata {
+                ignore_unknown_instances: true,
+            },
+        },
+    );
+
+    let (packages_folder, folders) = placement.folders.split_last().unwrap();
+
+    for folder in folders {
+        key = format!("{key}/{folder}");
+        parent = insert(
+            &mut tree,
+            Instance {
+                id: instance_id(&key),
+                parent,
+                name: folder.clone(),
+                class_name: "Folder".to_string(),
+                properties: BTreeMap::new(),
+                children: vec![],
+                metadata: Metadata {
+                    ignore_unknown_instances: true,
+                },
+            },
+        );
+    }
+
+    add_dir(
+        &mut tree,
+        &parent,
+        packages_dir,
+        &format!("{key}/{packages_folder}"),
+        packages_folder.clone(),
+        false,
+    )?;
+
+    Ok((root_id, tree))
+}
+
+/// Computes the message turning the old tree into the new tree
+fn diff(old: &Tree, new: &Tree) -> Message {
+    let mut message = Message::default();
+
+    for (id, instance) in old {
+        // removing an instance removes its descendants too
+        if !new.contains_key(id) && new.contains_key(&instance.parent) {
+            message.removed.push(id.clone());
+        }
+    }
+
+    for (id, instance) in new {
+        let Some(old_instance) = old.get(id) else {
+            message.added.insert(id.clone(), instance.clone());
+            continue;
+        };
+
+        let changed_properties = instance
+            .properties
+            .iter()
+            .filter(|(name, value)| old_instance.properties.get(*name) != Some(value))
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .chain(
+                old_instance
+                    .properties
+                    .keys()
+                    .filter(|name| !instance.properties.contains_key(*name))
+                    .map(|name| (name.clone(), Value::Null)),
+            )
+            .collect::<BTreeMap<_, _>>();
+
+        let update = Update {
+            id: id.clone(),
+            changed_name: (instance.name != old_instance.name).then(|| instance.name.clone()),
+            changed_class_name: (instance.class_name != old_instance.class_name)
+                .then(|| instance.class_name.clone()),
+            changed_properties,
+            changed_metadata: (instance.metadata != old_instance.metadata)
+                .then(|| instance.metadata.clone()),
+        };
+
+        if update.changed_name.is_some()
+            || update.changed_class_name.is_some()
+            || !update.changed_properties.is_empty()
+            || update.changed_metadata.is_some()
+        {
+            message.updated.push(update);
+        }
+    }
+
+    message
+}
+
+fn collect_descendants(tree: &Tree, id: &str, instances: &mut BTreeMap<String, Instance>) {
+    let Some(instance) = tree.get(id) else {
+        return;
+    };
+
+    for child in &instance.children {
+        collect_descendants(tree, child, instances);
+    }
+
+    instances.insert(id.to_string(), instance.clone());
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &Value) -> std::io::Result<()> {
+    let body = body.to_string();
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )?;
+    stream.flush()
+}
+
+fn handle_connection(mut stream: TcpStream, session: &SharedSession) -> std::io::Result<()> {
+    // idle connections would otherwise hold their thread forever
+    stream.set_read_timeout(Some(SOCKET_TIMEOUT))?;
+    stream.set_write_timeout(Some(SOCKET_TIMEOUT))?;
+
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+
+    // the plugin only sends bodies with requests which aren't supported
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return respond(
+            &mut stream,
+            "400 Bad Request",
+            &json!({ "kind": "BadRequest" }),
+        );
+    };
+    log::debug!("studio-sync: {method} {path}");
+
+    let (session_lock, condvar) = &**session;
+
+    let body = match (method, path.trim_end_matches('/')) {
+        ("GET", "/api/rojo") => {
+            let session = lock(session_lock);
+
+            json!({
+                "sessionId": session.id,
+                "serverVersion": SERVER_VERSION,
+                "protocolVersion": PROTOCOL_VERSION,
+                "projectName": session.project_name,
+                "expectedPlaceIds": null,
+                "unexpectedPlaceIds": null,
+                "rootInstanceId": session.root_id,
+                "gameId": null,
+                "placeId": null,
+            })
+        }
+        ("GET", path) if path.starts_with("/api/read/") => {
+            let session = lock(session_lock);
+
+            let mut instances = BTreeMap::new();
+            for id in path["/api/read/".len()..].split(',') {
+                collect_descendants(&session.tree, id, &mut instances);
+            }
+
+            json!({
+                "sessionId": session.id,
+                "messageCursor": session.cursor(),
+                "instances": instances,
+            })
+        }
+        ("GET", path) if path.starts_with("/api/subscribe/") => {
+            let Ok(cursor) = path["/api/subscribe/".len()..].parse::<usize>() else {
+                return respond(
+                    &mut stream,
+                    "400 Bad Request",
+                    &json!({ "kind": "BadRequest" }),
+                );
+            };
+
+            let (session, _) = condvar
+                .wait_timeout_while(lock(session_lock), SUBSCRIBE_TIMEOUT, |session| {
+                    session.cursor() <= cursor
+                })
+                .unwrap_or_else(PoisonError::into_inner);
+
+            // the plugin has to reconnect to read the tree again
+            let Some(messages) = session.messages_since(cursor) else {
+                return respond(
+                    &mut stream,
+                    "410 Gone",
+                    &json!({
+                        "kind": "BadRequest",
+                        "details": "message cursor is too old, reconnect to sync again",
+                    }),
+                );
+            };
+
+            json!({
+                "sessionId": session.id,
+                "messageCursor": session.cursor(),
+                "messages": messages,
+            })
+        }
+        _ => {
+            return respond(&mut stream, "404 Not Found", &json!({ "kind": "NotFound" }));
+        }
+    };
+
+    respond(&mut stream, "200 OK", &body)
+}
+
+impl StudioSyncCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+
+        let target = manifest.target.kind();
+        let Ok(place_kind): Result<RobloxPlaceKind, _> = (&target).try_into() else {
+            anyhow::bail!("studio-sync is only available for roblox projects");
+        };
+
+        let packages_folder = target.packages_folder(&target);
+        let placement = match manifest.place.get(&place_kind) {
+            Some(place) => parse_place(place)
+                .with_context(|| format!("place `{place}` for {place_kind} isn't supported"))?,
+            None => Placement {
+                service: match target {
+                    TargetKind::RobloxServer => "ServerScriptService",
+                    _ => "ReplicatedStorage",
+                }
+                .to_string(),
+                folders: vec![packages_folder.clone()],
+            },
+        };
+
+        let packages_dir = project.package_dir().join(&packages_folder);
+        let project_name = manifest.name.to_string();
+        let (root_id, tree) = build_tree(&project_name, &placement, &packages_dir)?;
+
+        let session: SharedSession = Arc::new((
+            Mutex::new(Session {
+                id: instance_id(&format!(
+                    "{}-{}",
+                    std::process::id(),
+                    chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+                )),
+                project_name: project_name.clone(),
+                root_id,
+                tree,
+                first_cursor: 0,
+                messages: vec![],
+            }),
+            Condvar::new(),
+        ));
+
+        let listener = TcpListener::bind(("127.0.0.1", self.port))
+            .with_context(|| format!("failed to listen on port {}", self.port))?;
+
+        {
+            let session = session.clone();
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let stream = match stream {
+                        Ok(stream) => stream,
+                        Err(e) => {
+                            log::warn!("failed to accept connection: {e}");
+                            continue;
+                        }
+                    };
+
+                    let session = session.clone();
+                    std::thread::spawn(move || {
+                        if let Err(e) = handle_connection(stream, &session) {
+                            log::debug!("studio-sync connection failed: {e}");
+                        }
+                    });
+                }
+            });
+        }
+
+        println!(
+            "{}",
+            msg!(
+                "studio_sync.listening",
+                folder = packages_folder,
+                port = self.port
+            )
+        );
+
+        watch(
+            &project_name,
+            &placement,
+            project.package_dir(),
+            &packages_dir,
+            &session,
+        )
+    }
+}
+
+fn watch(
+    project_name: &str,
+    placement: &Placement,
+    package_dir: &Path,
+    packages_dir: &Path,
+    session: &SharedSession,
+) -> anyhow::Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer =
+        new_debouncer(WATCH_DEBOUNCE, tx).context("failed to create file watcher")?;
+    // the packages folder may not exist yet, or be recreated by installs
+    debouncer
+        .watcher()
+        .watch(package_dir, RecursiveMode::Recursive)
+        .context("failed to watch project directory")?;
+
+    for events in rx {
+        let events = match events {
+            Ok(events) => events,
+            Err(e) => {
+                log::warn!("file watcher error: {e}");
+                continue;
+            }
+        };
+
+        if !events
+            .iter()
+            .any(|event| event.path.starts_with(packages_dir))
+        {
+            continue;
+        }
+
+        let (_, tree) = match build_tree(project_name, placement, packages_dir) {
+            Ok(tree) => tree,
+            // files may be mid-write during installs, the next event will rebuild the tree
+            Err(e) => {
+                log::debug!("failed to rebuild tree: {e:?}");
+                continue;
+            }
+        };
+
+        let (session_lock, condvar) = &**session;
+        let mut session = lock(session_lock);
+
+        let message = diff(&session.tree, &tree);
+        if message.is_empty() {
+            continue;
+        }
+
+        log::debug!(
+            "syncing {} removed, {} added, {} updated instances",
+            message.removed.len(),
+            message.added.len(),
+            message.updated.len()
+        );
+
+        session.tree = tree;
+        session.push(message);
+        condvar.notify_all();
+    }
+
+    anyhow::bail!("file watcher stopped unexpectedly")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_places() {
+        for place in [
+            "game.ReplicatedStorage.Shared.Packages",
+            "game:GetService(\"ReplicatedStorage\").Shared.Packages",
+            " game:GetService('ReplicatedStorage').Shared.Packages ",
+        ] {
+            let placement = parse_place(place).unwrap();
+            assert_eq!(placement.service, "ReplicatedStorage");
+            assert_eq!(placement.folders, ["Shared", "Packages"]);
+        }
+
+        for place in [
+            "workspace.Packages",
+            "game.ReplicatedStorage",
+            "game:GetService(\"ReplicatedStorage\")",
+            "game..Packages",
+            "game:GetService(\"ReplicatedStorage\"",
+        ] {
+            assert!(parse_place(place).is_none(), "{place}");
+        }
+    }
+
+    fn instance(id: &str, parent: &str, source: Option<&str>) -> (String, Instance) {
+        (
+            id.to_string(),
+            Instance {
+                id: id.to_string(),
+                parent: parent.to_string(),
+                name: id.to_string(),
+                class_name: if source.is_some() {
+                    "ModuleScript"
+                } else {
+                    "Folder"
+                }
+                .to_string(),
+                properties: source
+                    .map(|source| ("Source".to_string(), json!({ "String": source })))
+                    .into_iter()
+                    .collect(),
+                children: vec![],
+                metadata: Metadata {
+                    ignore_unknown_instances: false,
+                },
+            },
+        )
+    }
+
+    #[test]
+    fn diffs_trees() {
+        let old = Tree::from([
+            instance("root", NONE_ID, None),
+            instance("kept", "root", Some("return 1")),
+            instance("removed", "root", None),
+            instance("removed_child", "removed", Some("return 2")),
+        ]);
+        let new = Tree::from([
+            instance("root", NONE_ID, None),
+            instance("kept", "root", Some("return 3")),
+            instance("added", "root", None),
+        ]);
+
+        let message = diff(&old, &new);
+
+        // descendants of removed instances are removed with them
+        assert_eq!(message.removed, ["removed"]);
+        assert_eq!(message.added.keys().collect::<Vec<_>>(), ["added"]);
+        assert_eq!(message.updated.len(), 1);
+        assert_eq!(message.updated[0].id, "kept");
+        assert_eq!(
+            message.updated[0].changed_properties,
+            BTreeMap::from([("Source".to_string(), json!({ "String": "return 3" }))])
+        );
+
+        assert!(diff(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn diff_clears_removed_properties() {
+        let old = Tree::from([instance("script", NONE_ID, Some("return 1"))]);
+        let new = Tree::from([instance("script", NONE_ID, None)]);
+
+        let message = diff(&old, &new);
+        let update = &message.updated[0];
+        assert_eq!(update.changed_class_name.as_deref(), Some("Folder"));
+        assert_eq!(
+            update.changed_properties,
+            BTreeMap::from([("Source".to_string(), Value::Null)])
+        );
+    }
+
+    #[test]
+    fn trims_messages_by_cursor() {
+        let mut session = Session {
+            id: String::new(),
+            project_name: String::new(),
+            root_id: String::new(),
+            tree: Tree::new(),
+            first_cursor: 0,
+            messages: vec![],
+        };
+
+        for _ in 0..MAX_MESSAGES + 10 {
+            session.push(Message::default());
+        }
+
+        assert_eq!(session.messages.len(), MAX_MESSAGES);
+        assert_eq!(session.cursor(), MAX_MESSAGES + 10);
+        assert!(session.messages_since(9).is_none());
+        assert_eq!(session.messages_since(10).unwrap().len(), MAX_MESSAGES);
+        assert_eq!(session.messages_since(session.cursor()).unwrap().len(), 0);
+        assert_eq!(
+            session.messages_since(session.cursor() + 1).unwrap().len(),
+            0
+        );
+    }
+}
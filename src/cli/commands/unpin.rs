@@ -0,0 +1,29 @@
+use crate::cli::messages::msg;
+use anyhow::Context;
+use clap::Args;
+use pesde::{names::PackageNames, Project};
+
+#[derive(Debug, Args)]
+pub struct UnpinCommand {
+    /// The package to unpin
+    #[arg(index = 1)]
+    name: PackageNames,
+}
+
+impl UnpinCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let mut pins = project.deser_pins().context("failed to read pins file")?;
+
+        if pins.remove(&self.name).is_none() {
+            anyhow::bail!("{} is not pinned", self.name);
+        }
+
+        project
+            .write_pins(&pins)
+            .context("failed to write pins file")?;
+
+        println!("{}", msg!("unpin.done", name = self.name));
+
+        Ok(())
+    }
+}
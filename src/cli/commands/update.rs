@@ -1,19 +1,244 @@
-use crate::cli::{download_graph, run_on_workspace_members};
+use crate::cli::{download_graph, messages::msg, run_on_workspace_members};
 use anyhow::Context;
 use clap::Args;
 use colored::Colorize;
 use indicatif::MultiProgress;
-use pesde::{lockfile::Lockfile, Project};
+#[cfg(feature = "patches")]
+use pesde::{
+    lockfile::DownloadedGraph,
+    patches::{stale_patches, ReappliedPatch, StalePatch},
+    MANIFEST_FILE_NAME,
+};
+use pesde::{
+    lockfile::{DependencyGraph, Lockfile},
+    source::PackageSources,
+    Project,
+};
 use std::{collections::HashSet, thread::JoinHandle};
+#[cfg(feature = "patches")]
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    path::PathBuf,
+    str::FromStr,
+};
 
-#[derive(Debug, Args, Copy, Clone)]
+/// Carries the patches of packages which were updated over to their new versions, reporting the
+/// ones which conflict. Patches are updated in the manifest declaring them, which is the workspace
+/// root's for inherited patches
+#[cfg(feature = "patches")]
+fn reapply_patches(
+    project: &Project,
+    old_graph: &DownloadedGraph,
+    graph: &DownloadedGraph,
+    reqwest: &reqwest::blocking::Client,
+) -> anyhow::Result<()> {
+    let stale = stale_patches(&project.patches().context("failed to read patches")?, graph);
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    let mut documents = BTreeMap::<PathBuf, toml_edit::DocumentMut>::new();
+
+    for stale in stale {
+        let StalePatch {
+            name,
+            patched,
+            resolved,
+            patch,
+        } = &stale;
+        let patch_path = &patch.path;
+
+        let (Some(patched_node), Some(resolved_node)) = (
+            old_graph
+                .get(name)
+                .and_then(|versions| versions.get(patched)),
+            graph.get(name).and_then(|versions| versions.get(resolved)),
+        ) else {
+            continue;
+        };
+
+        let document = match documents.entry(patch.owner_dir.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let manifest = std::fs::read_to_string(patch.owner_dir.join(MANIFEST_FILE_NAME))
+                    .context("failed to read manifest")?;
+                entry.insert(
+                    toml_edit::DocumentMut::from_str(&manifest)
+                        .context("failed to parse manifest")?,
+                )
+            }
+        };
+        let patches = &mut document["patches"][&name.to_string()];
+
+        match project
+            .reapply_patch(
+                &stale,
+                &patched_node.node.pkg_ref,
+                &resolved_node.node.pkg_ref,
+                reqwest,
+            )
+            .with_context(|| format!("failed to carry patch for {name}@{patched} over"))?
+        {
+            ReappliedPatch::Clean(contents) if contents.is_empty() => {
+                if let Some(table) = patches.as_table_like_mut() {
+                    table.remove(&patched.to_string());
+                }
+
+                println!(
+                    "{}",
+                    msg!(
+                        "update.patch_upstreamed",
+                        name = name,
+                        patched = patched,
+                        resolved = resolved,
+                        path = patch_path
+                    )
+                    .green()
+                );
+            }
+            ReappliedPatch::Clean(contents) => {
+                let patch_file_name = format!("{}-{}.patch", name.escaped(), resolved.escaped());
+                let patches_dir = patch.owner_dir.join("patches");
+                std::fs::create_dir_all(&patches_dir)
+                    .context("failed to create patches directory")?;
+
+                let new_path = patches_dir.join(&patch_file_name);
+                if new_path.exists() {
+                    anyhow::bail!("patch file already exists: {}", new_path.display());
+                }
+
+                std::fs::write(&new_path, contents).context("failed to write patch file")?;
+
+                if let Some(table) = patches.as_table_like_mut() {
+                    table.remove(&patched.to_string());
+                }
+                patches[&resolved.to_string()] =
+                    toml_edit::value(format!("patches/{patch_file_name}"));
+
+                println!(
+                    "{}",
+                    msg!(
+                        "update.patch_reapplied",
+                        name = name,
+                        patched = patched,
+                        resolved = resolved,
+                        path = patch_path
+                    )
+                    .green()
+                );
+            }
+            ReappliedPatch::Conflicts(files) => {
+                println!(
+                    "{}",
+                    msg!(
+                        "update.patch_conflicts",
+                        name = name,
+                        patched = patched,
+                        resolved = resolved,
+                        path = patch_path,
+                        files = files
+                            .iter()
+                            .map(|file| file.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        bin = env!("CARGO_BIN_NAME")
+                    )
+                    .red()
+                );
+            }
+        }
+    }
+
+    for (owner_dir, document) in documents {
+        std::fs::write(owner_dir.join(MANIFEST_FILE_NAME), document.to_string())
+            .context("failed to write manifest")?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Args, Clone)]
 pub struct UpdateCommand {
+    /// The dependencies to update, by name or alias. All dependencies are updated if none are given
+    #[arg(index = 1)]
+    packages: Vec<String>,
+
+    /// Also update the dependencies of the given dependencies
+    #[arg(short, long, requires = "packages")]
+    recursive: bool,
+
     /// The amount of threads to use for downloading
     #[arg(short, long, default_value_t = 6, value_parser = clap::value_parser!(u64).range(1..=128))]
     threads: u64,
 }
 
 impl UpdateCommand {
+    fn targeted_graph(
+        &self,
+        project: &Project,
+        refreshed_sources: &mut HashSet<PackageSources>,
+    ) -> anyhow::Result<DependencyGraph> {
+        let previous_graph = match project.deser_lockfile() {
+            Ok(lockfile) => lockfile
+                .graph
+                .into_iter()
+                .map(|(name, versions)| {
+                    (
+                        name,
+                        versions
+                            .into_iter()
+                            .map(|(version, node)| (version, node.node))
+                            .collect(),
+                    )
+                })
+                .collect::<DependencyGraph>(),
+            Err(pesde::errors::LockfileReadError::Io(e))
+                if e.kind() == std::io::ErrorKind::NotFound =>
+            {
+                log::debug!("no lockfile found, updating all dependencies");
+                return project
+                    .dependency_graph(None, refreshed_sources)
+                    .context("failed to build dependency graph");
+            }
+            Err(e) => return Err(e).context("failed to read lockfile"),
+        };
+
+        let mut packages = HashSet::new();
+
+        for package in &self.packages {
+            let matching = previous_graph
+                .iter()
+                .filter(|(name, versions)| {
+                    name.to_string() == *package
+                        || versions.values().any(|node| {
+                            node.direct
+                                .as_ref()
+                                .is_some_and(|(alias, _)| alias == package)
+                        })
+                })
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+
+            if matching.is_empty() {
+                log::warn!(
+                    "{package} is not a dependency of {}",
+                    project.package_dir().display()
+                );
+            }
+
+            packages.extend(matching);
+        }
+
+        project
+            .update_dependency_graph(
+                &previous_graph,
+                &packages,
+                self.recursive,
+                refreshed_sources,
+            )
+            .context("failed to build dependency graph")
+    }
+
     pub fn run(
         self,
         project: Project,
@@ -23,47 +248,75 @@ impl UpdateCommand {
     ) -> anyhow::Result<()> {
         let mut refreshed_sources = HashSet::new();
 
+        // keep the features the dependencies were installed with
+        let features = project
+            .deser_lockfile()
+            .map(|lockfile| lockfile.features)
+            .unwrap_or_default();
+        let project = project.with_features(features);
+
         let manifest = project
             .deser_manifest()
             .context("failed to read manifest")?;
 
         println!(
             "\n{}\n",
-            format!("[now updating {} {}]", manifest.name, manifest.target)
-                .bold()
-                .on_bright_black()
+            msg!(
+                "update.header",
+                name = manifest.name,
+                target = manifest.target
+            )
+            .bold()
+            .on_bright_black()
         );
 
-        let graph = project
-            .dependency_graph(None, &mut refreshed_sources)
-            .context("failed to build dependency graph")?;
+        #[cfg(feature = "patches")]
+        let old_graph = project.deser_lockfile().ok().map(|lockfile| lockfile.graph);
+
+        let graph = if self.packages.is_empty() {
+            project
+                .dependency_graph(None, &mut refreshed_sources)
+                .context("failed to build dependency graph")?
+        } else {
+            self.targeted_graph(&project, &mut refreshed_sources)?
+        };
 
         if let Some(handle) = update_task.take() {
             handle.join().expect("failed to join update task");
         }
 
+        let downloaded_graph = download_graph(
+            &project,
+            &mut refreshed_sources,
+            &graph,
+            &multi,
+            &reqwest,
+            self.threads as usize,
+            false,
+            false,
+            msg!("update.downloading"),
+            msg!("update.downloaded"),
+        )?;
+
+        #[cfg(feature = "patches")]
+        if let Some(old_graph) = &old_graph {
+            reapply_patches(&project, old_graph, &downloaded_graph, &reqwest)?;
+        }
+
         project
             .write_lockfile(Lockfile {
                 name: manifest.name,
                 version: manifest.version,
                 target: manifest.target.kind(),
                 overrides: manifest.overrides,
+                pins: project.deser_pins().context("failed to read pins file")?,
+                features: project.features().clone(),
 
-                graph: download_graph(
-                    &project,
-                    &mut refreshed_sources,
-                    &graph,
-                    &multi,
-                    &reqwest,
-                    self.threads as usize,
-                    false,
-                    false,
-                    "📥 downloading dependencies".to_string(),
-                    "📥 downloaded dependencies".to_string(),
-                )?,
+                graph: downloaded_graph,
 
                 workspace: run_on_workspace_members(&project, |project| {
-                    self.run(project, multi.clone(), reqwest.clone(), &mut None)
+                    self.clone()
+                        .run(project, multi.clone(), reqwest.clone(), &mut None)
                 })?,
             })
             .context("failed to write lockfile")?;
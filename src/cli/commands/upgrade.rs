@@ -0,0 +1,216 @@
+use std::{collections::HashSet, str::FromStr};
+
+use anyhow::Context;
+use clap::Args;
+use semver::{Op, Version, VersionReq};
+
+use crate::cli::messages::msg;
+use pesde::{
+    manifest::Manifest,
+    source::{
+        pesde::PesdePackageSource, specifiers::DependencySpecifiers, traits::PackageSource,
+        PackageSources,
+    },
+    Project, DEFAULT_INDEX_NAME,
+};
+
+#[derive(Debug, Args)]
+pub struct UpgradeCommand {
+    /// The aliases or names of the dependencies to upgrade, defaulting to all of them
+    #[arg(index = 1)]
+    packages: Vec<String>,
+
+    /// Whether to only show the changes, without writing them to the manifest
+    #[arg(short, long)]
+    dry_run: bool,
+}
+
+/// The source a dependency of the project's manifest is resolved from, if it has versions
+fn source_of(
+    manifest: &Manifest,
+    specifier: &DependencySpecifiers,
+) -> anyhow::Result<Option<PackageSources>> {
+    Ok(match specifier {
+        DependencySpecifiers::Pesde(specifier) => {
+            let index_name = specifier.index.as_deref().unwrap_or(DEFAULT_INDEX_NAME);
+            let index_url = manifest
+                .indices
+                .get(index_name)
+                .with_context(|| format!("index {index_name} not found in indices field"))?;
+
+            Some(PackageSources::Pesde(PesdePackageSource::new(
+                index_url.clone(),
+            )))
+        }
+        #[cfg(feature = "wally-compat")]
+        DependencySpecifiers::Wally(specifier) => {
+            let index_name = specifier.index.as_deref().unwrap_or(DEFAULT_INDEX_NAME);
+            let index_url = manifest
+                .wally_indices
+                .get(index_name)
+                .with_context(|| format!("index {index_name} not found in wally_indices field"))?;
+
+            Some(PackageSources::Wally(
+                pesde::source::wally::WallyPackageSource::new(index_url.clone()),
+            ))
+        }
+        _ => None,
+    })
+}
+
+/// The requirement allowing the version and those compatible with it, keeping the operator of
+/// the current requirement if it is a single exact or tilde comparator. `None` if the current
+/// requirement doesn't need raising
+fn upgraded_req(current: &VersionReq, version: &Version) -> Option<String> {
+    let op = match current.comparators.as_slice() {
+        [comparator] => {
+            let lowest = Version {
+                major: comparator.major,
+                minor: comparator.minor.unwrap_or(0),
+                patch: comparator.patch.unwrap_or(0),
+                pre: comparator.pre.clone(),
+                build: Default::default(),
+            };
+
+            if lowest >= *version {
+                return None;
+            }
+
+            match comparator.op {
+                Op::Exact => "=",
+                Op::Tilde => "~",
+                _ => "^",
+            }
+        }
+        _ if current.matches(version) => return None,
+        _ => "^",
+    };
+
+    Some(format!("{op}{version}"))
+}
+
+impl UpgradeCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+        let mut document = toml_edit::DocumentMut::from_str(
+            &project.read_manifest().context("failed to read manifest")?,
+        )
+        .context("failed to parse manifest")?;
+
+        let mut found = HashSet::new();
+        let mut refreshed_sources = HashSet::new();
+        let mut upgraded = 0;
+
+        for (key, dependencies) in [
+            ("dependencies", &manifest.dependencies),
+            ("peer_dependencies", &manifest.peer_dependencies),
+            ("dev_dependencies", &manifest.dev_dependencies),
+        ] {
+            for (alias, specifier) in dependencies {
+                let (name, current) = match specifier {
+                    DependencySpecifiers::Pesde(specifier) => {
+                        (specifier.name.to_string(), &specifier.version)
+                    }
+                    #[cfg(feature = "wally-compat")]
+                    DependencySpecifiers::Wally(specifier) => {
+                        (specifier.name.to_string(), &specifier.version)
+                    }
+                    _ => continue,
+                };
+
+                if !self.packages.is_empty() {
+                    let matching = self
+                        .packages
+                        .iter()
+                        .filter(|package| *package == alias || **package == name)
+                        .collect::<Vec<_>>();
+
+                    if matching.is_empty() {
+                        continue;
+                    }
+
+                    found.extend(matching);
+                }
+
+                let (Some(unconstrained), Some(source)) =
+                    (specifier.unconstrained(), source_of(&manifest, specifier)?)
+                else {
+                    continue;
+                };
+
+                if refreshed_sources.insert(source.clone()) {
+                    source
+                        .refresh(&project)
+                        .context("failed to refresh package source")?;
+                }
+
+                let Some((version_id, _)) = source
+                    .resolve(&unconstrained, &project, manifest.target.kind())
+                    .with_context(|| format!("failed to resolve {alias}"))?
+                    .1
+                    .pop_last()
+                else {
+                    log::warn!("no versions of {unconstrained} found");
+                    continue;
+                };
+
+                let Some(req) = upgraded_req(current, version_id.version()) else {
+                    continue;
+                };
+
+                upgraded += 1;
+                println!(
+                    "{}",
+                    msg!(
+                        "upgrade.entry",
+                        alias = alias,
+                        name = name,
+                        from = current,
+                        to = req,
+                        key = key
+                    )
+                );
+
+                let Some(version) = document[key][alias]
+                    .as_table_like_mut()
+                    .and_then(|table| table.get_mut("version"))
+                    .and_then(|version| version.as_value_mut())
+                else {
+                    anyhow::bail!("{key}.{alias} has no version field");
+                };
+
+                let decor = version.decor().clone();
+                *version = req.into();
+                *version.decor_mut() = decor;
+            }
+        }
+
+        if let Some(package) = self
+            .packages
+            .iter()
+            .find(|package| !found.contains(package))
+        {
+            anyhow::bail!("{package} is not a dependency with a version requirement");
+        }
+
+        if upgraded == 0 {
+            println!("{}", msg!("upgrade.none"));
+            return Ok(());
+        }
+
+        if self.dry_run {
+            println!("\n{}", msg!("upgrade.dry_run"));
+            return Ok(());
+        }
+
+        project
+            .write_manifest(document.to_string())
+            .context("failed to write manifest")?;
+
+        println!("\n{}", msg!("upgrade.done"));
+
+        Ok(())
+    }
+}
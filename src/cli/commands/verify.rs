@@ -0,0 +1,153 @@
+use crate::cli::messages::msg;
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    lockfile::check_compatibility,
+    source::{
+        pesde::PesdePackageSource,
+        refs::PackageRefs,
+        traits::{PackageRef, PackageSource},
+    },
+    Project,
+};
+use std::collections::{BTreeSet, HashSet};
+
+#[derive(Debug, Args)]
+pub struct VerifyCommand {
+    /// Whether to also fail if resolving again would choose different versions
+    #[arg(long)]
+    strict: bool,
+}
+
+impl VerifyCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        let manifest = project
+            .deser_manifest()
+            .context("failed to read manifest")?;
+        let lockfile = match project.deser_lockfile() {
+            Ok(lockfile) => lockfile,
+            Err(pesde::errors::LockfileReadError::Io(e))
+                if e.kind() == std::io::ErrorKind::NotFound =>
+            {
+                anyhow::bail!("no lockfile found, please run the install command first")
+            }
+            Err(e) => return Err(e).context("failed to read lockfile"),
+        };
+        let project = project.with_features(lockfile.features.clone());
+
+        let mut problems = 0usize;
+        let mut drifted = 0usize;
+
+        for mismatch in
+            check_compatibility(&manifest, &lockfile).context("failed to check lockfile")?
+        {
+            problems += 1;
+            println!(
+                "{}",
+                msg!("drift.lockfile", mismatch = mismatch.to_string()).red()
+            );
+        }
+
+        let mut refreshed_sources = HashSet::new();
+
+        for (name, versions) in &lockfile.graph {
+            for (version_id, node) in versions {
+                let PackageRefs::Pesde(pkg_ref) = &node.node.pkg_ref else {
+                    continue;
+                };
+
+                let source = node.node.pkg_ref.source();
+                if refreshed_sources.insert(source.clone()) {
+                    source
+                        .refresh(&project)
+                        .context("failed to refresh source")?;
+                }
+
+                let package = format!("{name}@{version_id}");
+                let Some(entry) = PesdePackageSource::new(pkg_ref.index_url.clone())
+                    .index_file(&pkg_ref.name, &project)
+                    .context("failed to read index file")?
+                    .and_then(|mut file| file.remove(version_id))
+                else {
+                    problems += 1;
+                    println!("{}", msg!("drift.missing", package = package).red());
+                    continue;
+                };
+
+                if entry.yanked {
+                    println!("{}", msg!("drift.yanked", package = package).yellow());
+                }
+
+                if pkg_ref.checksum.is_some() && pkg_ref.checksum != entry.checksum {
+                    problems += 1;
+                    println!("{}", msg!("drift.checksum", package = package).red());
+                }
+            }
+        }
+
+        // resolving from scratch shows which versions would be chosen today
+        let graph = project
+            .dependency_graph(None, &mut refreshed_sources)
+            .context("failed to build dependency graph")?;
+
+        let names = lockfile
+            .graph
+            .keys()
+            .chain(graph.keys())
+            .collect::<BTreeSet<_>>();
+
+        for name in names {
+            let locked = lockfile
+                .graph
+                .get(name)
+                .map(|versions| versions.keys().collect::<BTreeSet<_>>())
+                .unwrap_or_default();
+            let resolved = graph
+                .get(name)
+                .map(|versions| versions.keys().collect::<BTreeSet<_>>())
+                .unwrap_or_default();
+
+            if locked == resolved {
+                continue;
+            }
+
+            let versions = |versions: BTreeSet<_>| {
+                if versions.is_empty() {
+                    return msg!("drift.nothing");
+                }
+
+                versions
+                    .into_iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+
+            drifted += 1;
+            println!(
+                "{}",
+                msg!(
+                    "drift.versions",
+                    name = name,
+                    locked = versions(locked),
+                    resolved = versions(resolved)
+                )
+                .yellow()
+            );
+        }
+
+        if problems > 0 || (self.strict && drifted > 0) {
+            anyhow::bail!(
+                "{}",
+                msg!("drift.failed", problems = problems, drifted = drifted)
+            );
+        }
+
+        if drifted == 0 {
+            println!("{}", msg!("drift.reproducible").green());
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+use crate::cli::{config::read_config, download_graph, messages::msg, VersionedPackageName};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use indicatif::MultiProgress;
+use pesde::{
+    linking::generator::get_file_types,
+    lockfile::DownloadedDependencyGraphNode,
+    manifest::{migration::parse_manifest, target::TargetKind},
+    names::{PackageName, PackageNames},
+    source::{
+        pesde::{errors::ResolveError, specifier::PesdeDependencySpecifier, PesdePackageSource},
+        traits::PackageSource,
+        version_id::VersionId,
+    },
+    Project, MANIFEST_FILE_NAME, PACKAGES_CONTAINER_NAME,
+};
+use semver::{Version, VersionReq};
+use std::{collections::HashSet, path::Path, process::Command};
+
+/// The name of the script run to verify a package, if the package has it
+const VERIFY_SCRIPT_NAME: &str = "verify";
+
+#[derive(Debug, Args)]
+pub struct VerifyPackageCommand {
+    /// The package to verify, defaulting to its latest version
+    #[arg(index = 1)]
+    package: VersionedPackageName<Version, PackageName>,
+
+    /// The index URL to use for the package
+    #[arg(short, long, value_parser = crate::cli::parse_gix_url)]
+    index: Option<gix::Url>,
+
+    /// Whether to skip running the package's `verify` script
+    #[arg(long)]
+    no_script: bool,
+
+    /// The amount of threads to use for downloading
+    #[arg(short, long, default_value_t = 6, value_parser = clap::value_parser!(u64).range(1..=128))]
+    threads: u64,
+}
+
+/// The manifest of the project a package is verified in
+fn verify_manifest(
+    name: &PackageName,
+    version_id: &VersionId,
+    index: &gix::Url,
+) -> anyhow::Result<String> {
+    let mut manifest = toml::Table::new();
+    manifest.insert("name".into(), "pesde/verify".into());
+    manifest.insert("version".into(), "0.0.0".into());
+    manifest.insert("private".into(), true.into());
+
+    let mut target = toml::Table::new();
+    target.insert("environment".into(), version_id.target().to_string().into());
+    manifest.insert("target".into(), target.into());
+
+    // Roblox packages with dependencies for the other realm need to know where they are
+    if matches!(
+        version_id.target(),
+        TargetKind::Roblox | TargetKind::RobloxServer
+    ) {
+        let mut place = toml::Table::new();
+        place.insert("shared".into(), "game.ReplicatedStorage.Packages".into());
+        place.insert("server".into(), "game.ServerScriptService.Packages".into());
+        manifest.insert("place".into(), place.into());
+    }
+
+    let mut indices = toml::Table::new();
+    indices.insert(
+        pesde::DEFAULT_INDEX_NAME.into(),
+        index.to_bstring().to_string().into(),
+    );
+    manifest.insert("indices".into(), indices.into());
+
+    let mut dependency = toml::Table::new();
+    dependency.insert("name".into(), name.to_string().into());
+    dependency.insert(
+        "version".into(),
+        format!("={}", version_id.version()).into(),
+    );
+    dependency.insert("target".into(), version_id.target().to_string().into());
+
+    let mut dependencies = toml::Table::new();
+    dependencies.insert("package".into(), dependency.into());
+    manifest.insert("dependencies".into(), dependencies.into());
+
+    toml::to_string(&manifest).context("failed to serialize verification manifest")
+}
+
+/// Checks that the files the package's target refers to exist and its library can be parsed,
+/// returning the problems found
+fn check_files(container_folder: &Path, node: &DownloadedDependencyGraphNode) -> Vec<String> {
+    let mut problems = vec![];
+
+    if let Some(lib_path) = node.target.lib_path() {
+        match std::fs::read_to_string(lib_path.to_path(container_folder)) {
+            Ok(contents) => match get_file_types(&contents) {
+                Ok(types) => log::debug!("lib file {lib_path} exports {} types", types.len()),
+                Err(errors) => problems.push(msg!(
+                    "verify.problem.lib_unparsable",
+                    path = lib_path,
+                    errors = errors
+                        .iter()
+                        .map(|e| e.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                problems.push(msg!("verify.problem.lib_missing", path = lib_path));
+            }
+            Err(e) => problems.push(msg!(
+                "verify.problem.lib_unreadable",
+                path = lib_path,
+                error = e
+            )),
+        }
+    }
+
+    for bin_path in node.target.bin_path().into_iter().chain(
+        node.target
+            .named_bins()
+            .into_iter()
+            .flat_map(|bins| bins.values()),
+    ) {
+        if !bin_path.to_path(container_folder).is_file() {
+            problems.push(msg!("verify.problem.bin_missing", path = bin_path));
+        }
+    }
+
+    for build_file in node.target.build_files().into_iter().flatten() {
+        if !container_folder.join(build_file).exists() {
+            problems.push(msg!("verify.problem.build_missing", path = build_file));
+        }
+    }
+
+    problems
+}
+
+/// Runs the package's verify script in the project, if it has one
+fn run_verify_script(
+    project: &Project,
+    container_folder: &Path,
+    problems: &mut Vec<String>,
+) -> anyhow::Result<()> {
+    let manifest = match std::fs::read_to_string(container_folder.join(MANIFEST_FILE_NAME)) {
+        Ok(manifest) => manifest,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            problems.push(msg!(
+                "verify.problem.manifest_missing",
+                file = MANIFEST_FILE_NAME
+            ));
+            return Ok(());
+        }
+        Err(e) => return Err(e).context("failed to read package manifest"),
+    };
+
+    let manifest = match parse_manifest(&manifest) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            problems.push(msg!(
+                "verify.problem.manifest_invalid",
+                file = MANIFEST_FILE_NAME,
+                error = e
+            ));
+            return Ok(());
+        }
+    };
+
+    let Some(script) = manifest.scripts.get(VERIFY_SCRIPT_NAME) else {
+        return Ok(());
+    };
+
+    println!("{}", msg!("verify.script", script = script));
+
+    let status = Command::new("lune")
+        .arg("run")
+        .arg(script.to_path(container_folder))
+        .arg("--")
+        .arg(container_folder)
+        .current_dir(project.package_dir())
+        .env("PESDE_ROOT", project.package_dir())
+        .status()
+        .context("failed to run verify script")?;
+
+    if !status.success() {
+        problems.push(match status.code() {
+            Some(code) => msg!(
+                "verify.problem.script_failed",
+                script = VERIFY_SCRIPT_NAME,
+                code = code
+            ),
+            None => msg!("verify.problem.script_killed", script = VERIFY_SCRIPT_NAME),
+        });
+    }
+
+    Ok(())
+}
+
+impl VerifyPackageCommand {
+    pub fn run(
+        self,
+        project: Project,
+        multi: MultiProgress,
+        reqwest: reqwest::blocking::Client,
+    ) -> anyhow::Result<()> {
+        let index = match self.index {
+            Some(index) => index,
+            None => read_config()?.default_index,
+        };
+
+        let source = PesdePackageSource::new(index.clone());
+        source
+            .refresh(&project)
+            .context("failed to refresh source")?;
+
+        let VersionedPackageName(name, version) = self.package;
+        let version_req = version.as_ref().map_or(VersionReq::STAR, |version| {
+            VersionReq::parse(&format!("={version}")).unwrap()
+        });
+
+        let mut version_ids = vec![];
+        for target in TargetKind::VARIANTS {
+            let specifier = PesdeDependencySpecifier {
+                name: name.clone(),
+                version: version_req.clone(),
+                index: None,
+                target: Some(*target),
+                optional: false,
+            };
+
+            match source.resolve(&specifier, &project, *target) {
+                Ok((_, versions)) => version_ids.extend(versions.into_keys()),
+                // the malformed versions are only a problem if no target has a valid one
+                Err(ResolveError::NoValidVersions(..)) => {
+                    log::debug!("no valid versions of {name} for {target}");
+                }
+                Err(e) => return Err(e).context("failed to resolve package"),
+            }
+        }
+
+        // without a version, every target of the latest version is verified
+        let Some(version) = version.or_else(|| {
+            version_ids
+                .iter()
+                .map(|version_id| version_id.version().clone())
+                .max()
+        }) else {
+            anyhow::bail!("no version of {name} matching {version_req} found");
+        };
+        version_ids.retain(|version_id| *version_id.version() == version);
+
+        let tmp_dir = project.cas_dir().join(".tmp");
+        std::fs::create_dir_all(&tmp_dir).context("failed to create temporary directory")?;
+
+        let mut refreshed_sources = HashSet::new();
+        let mut failed = 0;
+
+        for version_id in version_ids {
+            println!(
+                "\n{}\n",
+                msg!(
+                    "verify.header",
+                    package = format!("{name}@{}", version_id.version()),
+                    target = version_id.target()
+                )
+                .bold()
+            );
+
+            let tempdir = pesde::tempdirs::tempdir_in(&tmp_dir)
+                .context("failed to create temporary directory")?;
+            std::fs::write(
+                tempdir.path().join(MANIFEST_FILE_NAME),
+                verify_manifest(&name, &version_id, &index)?,
+            )
+            .context("failed to write verification manifest")?;
+
+            let verify_project = Project::new(
+                tempdir.path(),
+                None::<&Path>,
+                project.data_dir(),
+                project.cas_dir(),
+                project.auth_config().clone(),
+            )
+            .with_source_replacements(project.source_replacements().clone())
+            .with_offline(project.offline())
+            .with_trust_policy(project.trust_policy())
+            .with_retry_policy(project.retry_policy())
+            .with_lenient_index(project.lenient_index())
+            .with_script_policy(project.script_policy().clone())
+            .with_network_limiter(project.network_limiter().clone());
+
+            let graph = verify_project
+                .dependency_graph(None, &mut refreshed_sources)
+                .context("failed to build dependency graph")?;
+
+            let downloaded_graph = download_graph(
+                &verify_project,
+                &mut refreshed_sources,
+                &graph,
+                &multi,
+                &reqwest,
+                self.threads as usize,
+                false,
+                true,
+                msg!("verify.downloading"),
+                msg!("verify.downloaded"),
+            )?;
+
+            let package_name = PackageNames::Pesde(name.clone());
+            let node = downloaded_graph
+                .get(&package_name)
+                .and_then(|versions| versions.get(&version_id))
+                .context("package not found in downloaded graph")?;
+
+            let container_folder = node.node.container_folder(
+                &verify_project
+                    .package_dir()
+                    .join(version_id.target().packages_folder(version_id.target()))
+                    .join(PACKAGES_CONTAINER_NAME),
+                &package_name,
+                version_id.version(),
+            );
+
+            let mut problems = check_files(&container_folder, node);
+
+            if let Err(e) = verify_project.link_dependencies(&downloaded_graph) {
+                problems.push(msg!(
+                    "verify.problem.linking_failed",
+                    error = format!("{:#}", anyhow::Error::from(e))
+                ));
+            } else if !self.no_script {
+                run_verify_script(&verify_project, &container_folder, &mut problems)?;
+            }
+
+            if problems.is_empty() {
+                println!(
+                    "{}",
+                    msg!(
+                        "verify.passed",
+                        package = format!("{name}@{}", version_id.version()),
+                        target = version_id.target()
+                    )
+                    .green()
+                );
+            } else {
+                failed += 1;
+                for problem in problems {
+                    println!("{}", format!("  - {problem}").red());
+                }
+            }
+        }
+
+        if failed > 0 {
+            anyhow::bail!("{}", msg!("verify.failed", count = failed));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use crate::cli::{messages::msg, VersionedPackageName};
+use anyhow::Context;
+use clap::Args;
+use colored::Colorize;
+use pesde::{
+    lockfile::{dependency_paths, dependents},
+    Project,
+};
+
+#[derive(Debug, Args)]
+pub struct WhyCommand {
+    /// The package to explain the presence of, optionally with a version
+    #[arg(index = 1)]
+    package: VersionedPackageName,
+}
+
+impl WhyCommand {
+    pub fn run(self, project: Project) -> anyhow::Result<()> {
+        // the lockfile records everything needed, so it doesn't have to be up to date
+        let graph = project
+            .deser_lockfile()
+            .context("failed to read lockfile, please run the install command first")?
+            .graph;
+
+        let VersionedPackageName(name, version_id) = self.package;
+
+        let versions = graph
+            .get(&name)
+            .context("package not found in graph")?
+            .keys()
+            .filter(|v| {
+                version_id
+                    .as_ref()
+                    .is_none_or(|version_id| *v == version_id)
+            })
+            .collect::<Vec<_>>();
+
+        if versions.is_empty() {
+            anyhow::bail!("version not found in graph");
+        }
+
+        let dependents = dependents(&graph);
+
+        for version_id in versions {
+            println!("{}", format!("{name}@{version_id}").bold());
+
+            if let Some(provenance) = &graph[&name][version_id].node.provenance {
+                let resolved = match &provenance.index_commit {
+                    Some(commit) => format!(
+                        "resolved at {} from index commit {commit}",
+                        provenance.resolved_at
+                    ),
+                    None => format!("resolved at {}", provenance.resolved_at),
+                };
+
+                println!("  {}", resolved.dimmed());
+            }
+
+            let paths = dependency_paths(&graph, &dependents, &name, version_id);
+
+            if paths.is_empty() {
+                println!("  {}", msg!("why.unreachable").red());
+                continue;
+            }
+
+            for path in paths {
+                let path = path
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, version_id, alias))| {
+                        let package = format!("{name}@{}", version_id.version());
+
+                        if i == 0 {
+                            format!("{} ({})", alias.cyan(), package.dimmed())
+                        } else {
+                            package.dimmed().to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+
+                println!("  {path}");
+            }
+        }
+
+        Ok(())
+    }
+}
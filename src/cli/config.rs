@@ -1,6 +1,18 @@
-use crate::cli::{auth::Tokens, home_dir};
+use crate::cli::{
+    auth::Tokens,
+    home_dir,
+    messages::{Locale, OutputStyle},
+};
 use anyhow::Context;
+use pesde::source::{pesde::TrustPolicy, retry::RetryPolicy};
 use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    path::PathBuf,
+    str::FromStr,
+    time::Duration,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CliConfig {
@@ -15,10 +27,262 @@ pub struct CliConfig {
     )]
     pub scripts_repo: gix::Url,
 
+    /// Tokens stored in plain text, either because of the credential store, or because the keyring was unavailable
     pub tokens: Tokens,
+    /// Where to store tokens
+    #[serde(default, skip_serializing_if = "CredentialStoreKind::is_keyring")]
+    pub credential_store: CredentialStoreKind,
+    /// The indices which have a token stored in the keyring
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub keyring_indices: BTreeSet<String>,
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub last_checked_updates: Option<(chrono::DateTime<chrono::Utc>, semver::Version)>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub locale: Option<Locale>,
+    #[serde(default)]
+    pub output_style: OutputStyle,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub messages: BTreeMap<String, String>,
+
+    #[serde(default, skip_serializing_if = "VersionSource::is_github")]
+    pub version_source: VersionSource,
+
+    /// TLS client certificates to use for the API and downloads of indices, keyed by index URL.
+    /// Git operations on the index repositories don't use them
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub client_certificates: BTreeMap<String, ClientCertificate>,
+
+    /// Whether to check for updates, refresh the scripts repository and prefetch indices
+    /// in a background process instead of at the start of every command
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub background_helper: bool,
+
+    /// How checksums and signatures of packages downloaded from pesde registries are treated
+    #[serde(default)]
+    pub trust_policy: TrustPolicy,
+
+    /// Whether to fetch large archives in segments from multiple mirrors of an index in parallel
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub segmented_downloads: bool,
+
+    /// How failed package downloads are retried
+    #[serde(default, skip_serializing_if = "DownloadRetries::is_default")]
+    pub download_retries: DownloadRetries,
+
+    /// Whether malformed versions in index files fail resolution instead of being skipped
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict_index: bool,
+
+    /// Mirrors to fetch indices and Git repositories from instead of their URL, keyed by the URL
+    /// they replace. Lockfiles keep the replaced URL
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub source_replacement: BTreeMap<String, String>,
+
+    /// Limits on the network usage of package downloads and Git fetches
+    #[serde(default, skip_serializing_if = "NetworkLimits::is_default")]
+    pub network_limits: NetworkLimits,
+
+    /// Whether the project's scripts are run for packages without a decision in `script_permissions`
+    #[serde(default, skip_serializing_if = "ScriptPolicyKind::is_prompt")]
+    pub script_policy: ScriptPolicyKind,
+    /// Whether the project's scripts may be run for a package, by its name or Git repository URL
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub script_permissions: BTreeMap<String, bool>,
+
+    /// Which packages `pesde x` runs without asking for confirmation, keyed by index URL
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub execute_permissions: BTreeMap<String, ExecutePermissions>,
+}
+
+/// The packages of an index which `pesde x` runs without asking for confirmation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExecutePermissions {
+    /// The scopes whose packages are trusted
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub trusted_scopes: BTreeSet<String>,
+    /// The versions of packages which have been confirmed before, as `name@version`
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub confirmed: BTreeSet<String>,
+}
+
+/// Whether the project's scripts, such as the Roblox sync config generator, are run for packages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptPolicyKind {
+    /// Ask when installing interactively, running them otherwise
+    #[default]
+    Prompt,
+    /// Run them
+    Allow,
+    /// Never run them
+    Deny,
+}
+
+impl ScriptPolicyKind {
+    fn is_prompt(&self) -> bool {
+        matches!(self, ScriptPolicyKind::Prompt)
+    }
+}
+
+/// Limits on the network usage of package downloads and Git fetches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkLimits {
+    /// The highest rate packages are downloaded at in total
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_download_rate: Option<ByteRate>,
+    /// How many connections may be open to a host at once
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_host_connections: Option<NonZeroUsize>,
+}
+
+impl NetworkLimits {
+    fn is_default(&self) -> bool {
+        *self == NetworkLimits::default()
+    }
+}
+
+/// A number of bytes per second, written with an optional `K`, `M` or `G` suffix
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ByteRate(pub u64);
+
+impl FromStr for ByteRate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s
+            .strip_suffix("/s")
+            .unwrap_or(s)
+            .trim_end_matches(['B', 'b']);
+
+        let (number, multiplier) = match s.char_indices().last() {
+            Some((i, 'k' | 'K')) => (&s[..i], 1024),
+            Some((i, 'm' | 'M')) => (&s[..i], 1024 * 1024),
+            Some((i, 'g' | 'G')) => (&s[..i], 1024 * 1024 * 1024),
+            _ => (s, 1),
+        };
+
+        let number = number
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|number| number.is_finite() && *number > 0.0)
+            .with_context(|| format!("invalid rate `{s}`, expected for example `500K` or `2M`"))?;
+
+        Ok(ByteRate((number * multiplier as f64) as u64))
+    }
+}
+
+impl TryFrom<String> for ByteRate {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ByteRate> for String {
+    fn from(rate: ByteRate) -> Self {
+        rate.0.to_string()
+    }
+}
+
+/// How failed package downloads are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadRetries {
+    /// The maximum number of attempts, including the first one
+    pub attempts: u32,
+    /// How long to wait before the first retry in milliseconds, doubled for every following one
+    pub backoff_ms: u64,
+    /// The longest to wait between two attempts in milliseconds
+    pub max_backoff_ms: u64,
+}
+
+impl Default for DownloadRetries {
+    fn default() -> Self {
+        RetryPolicy::default().into()
+    }
+}
+
+impl DownloadRetries {
+    fn is_default(&self) -> bool {
+        *self == DownloadRetries::default()
+    }
+}
+
+impl From<RetryPolicy> for DownloadRetries {
+    fn from(policy: RetryPolicy) -> Self {
+        DownloadRetries {
+            attempts: policy.attempts,
+            backoff_ms: policy.backoff.as_millis() as u64,
+            max_backoff_ms: policy.max_backoff.as_millis() as u64,
+        }
+    }
+}
+
+impl From<DownloadRetries> for RetryPolicy {
+    fn from(retries: DownloadRetries) -> Self {
+        RetryPolicy {
+            attempts: retries.attempts.max(1),
+            backoff: Duration::from_millis(retries.backoff_ms),
+            max_backoff: Duration::from_millis(retries.max_backoff_ms),
+        }
+    }
+}
+
+/// Where tokens are stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialStoreKind {
+    /// The system keyring, falling back to the config file if it is unavailable
+    #[default]
+    Keyring,
+    /// The config file
+    File,
+}
+
+impl CredentialStoreKind {
+    fn is_keyring(&self) -> bool {
+        matches!(self, CredentialStoreKind::Keyring)
+    }
+}
+
+/// A TLS client certificate and its private key, both PEM encoded
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientCertificate {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// Where pesde looks for new versions of itself
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum VersionSource {
+    /// The releases of the GitHub repository pesde is developed in
+    #[default]
+    #[serde(rename = "github")]
+    GitHub,
+    /// An internal artifact server
+    Artifacts {
+        /// URL pattern of the release archives. `{version}`, `{os}` and `{arch}` are replaced
+        download_url: String,
+        /// URL which responds with the latest version as plain text. Update checks are skipped if unset
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        latest_url: Option<url::Url>,
+    },
+    /// Never check for or download versions, for air-gapped environments
+    Disabled,
+}
+
+impl VersionSource {
+    fn is_github(&self) -> bool {
+        matches!(self, VersionSource::GitHub)
+    }
 }
 
 impl Default for CliConfig {
@@ -32,8 +296,36 @@ impl Default for CliConfig {
                 .unwrap(),
 
             tokens: Tokens(Default::default()),
+            credential_store: CredentialStoreKind::default(),
+            keyring_indices: BTreeSet::new(),
 
             last_checked_updates: None,
+
+            locale: None,
+            output_style: OutputStyle::default(),
+            messages: BTreeMap::new(),
+
+            version_source: VersionSource::default(),
+
+            client_certificates: BTreeMap::new(),
+
+            background_helper: false,
+
+            trust_policy: TrustPolicy::default(),
+
+            segmented_downloads: false,
+
+            download_retries: DownloadRetries::default(),
+
+            strict_index: false,
+
+            source_replacement: BTreeMap::new(),
+            network_limits: NetworkLimits::default(),
+
+            script_policy: ScriptPolicyKind::default(),
+            script_permissions: BTreeMap::new(),
+
+            execute_permissions: BTreeMap::new(),
         }
     }
 }
@@ -59,3 +351,49 @@ pub fn write_config(config: &CliConfig) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_byte_rates() {
+        for (rate, bytes) in [
+            ("1024", 1024),
+            ("500K", 500 * 1024),
+            ("2M", 2 * 1024 * 1024),
+            ("1.5m", 1024 * 1024 * 3 / 2),
+            ("1G", 1024 * 1024 * 1024),
+            ("2MB/s", 2 * 1024 * 1024),
+            (" 10 kb ", 10 * 1024),
+        ] {
+            assert_eq!(rate.parse::<ByteRate>().unwrap(), ByteRate(bytes), "{rate}");
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_byte_rates() {
+        for rate in ["", "0", "-1M", "fast", "1T", "NaN", "inf"] {
+            assert!(rate.parse::<ByteRate>().is_err(), "{rate}");
+        }
+    }
+
+    #[test]
+    fn network_limits_round_trip() {
+        let limits =
+            toml::from_str::<NetworkLimits>("max_download_rate = \"2M\"\nmax_host_connections = 4")
+                .unwrap();
+        assert_eq!(limits.max_download_rate, Some(ByteRate(2 * 1024 * 1024)));
+        assert_eq!(limits.max_host_connections, NonZeroUsize::new(4));
+
+        assert_eq!(
+            toml::from_str::<NetworkLimits>(&toml::to_string(&limits).unwrap()).unwrap(),
+            limits
+        );
+    }
+
+    #[test]
+    fn rejects_zero_host_connections() {
+        assert!(toml::from_str::<NetworkLimits>("max_host_connections = 0").is_err());
+    }
+}
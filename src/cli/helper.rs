@@ -0,0 +1,236 @@
+use crate::cli::{config::read_config, home_dir, is_isolated, repos::update_repo_dependencies};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use pesde::{
+    source::{pesde::PesdePackageSource, traits::PackageSource, PackageSources},
+    Project,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs::OpenOptions,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    time::Duration,
+};
+
+/// The name of the hidden command the background helper is run with
+pub const HELPER_COMMAND: &str = "__helper";
+
+const MARKERS_FILE_NAME: &str = "helper.toml";
+const LOCK_FILE_NAME: &str = "helper.lock";
+const COMMAND_LOCK_FILE_NAME: &str = "commands.lock";
+
+/// How long the work of the helper is considered fresh
+const REFRESH_INTERVAL: chrono::Duration = chrono::Duration::minutes(30);
+/// After how long a lock is considered abandoned, for example by a killed helper
+const STALE_LOCK: Duration = Duration::from_secs(10 * 60);
+
+/// When the background helper last finished each part of its work
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Markers {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scripts_refreshed: Option<DateTime<Utc>>,
+    /// Keyed by the directory of the project whose indices were prefetched
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    indices_prefetched: BTreeMap<PathBuf, DateTime<Utc>>,
+}
+
+fn is_fresh(time: Option<&DateTime<Utc>>) -> bool {
+    time.is_some_and(|time| Utc::now() - *time < REFRESH_INTERVAL)
+}
+
+fn read_markers() -> anyhow::Result<Markers> {
+    let path = home_dir()?.join(MARKERS_FILE_NAME);
+
+    match std::fs::read_to_string(&path) {
+        Ok(s) => toml::from_str(&s).context("failed to parse helper markers"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Markers::default()),
+        Err(e) => Err(e).context("failed to read helper markers"),
+    }
+}
+
+fn write_markers(markers: &Markers) -> anyhow::Result<()> {
+    let path = home_dir()?.join(MARKERS_FILE_NAME);
+    let s = toml::to_string(markers).context("failed to serialize helper markers")?;
+    std::fs::write(path, s).context("failed to write helper markers")
+}
+
+fn lock_path() -> anyhow::Result<PathBuf> {
+    Ok(home_dir()?.join(LOCK_FILE_NAME))
+}
+
+fn is_locked(path: &PathBuf) -> bool {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|elapsed| elapsed < STALE_LOCK)
+}
+
+struct Lock(PathBuf);
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn acquire_lock() -> anyhow::Result<Option<Lock>> {
+    let path = lock_path()?;
+
+    if is_locked(&path) {
+        return Ok(None);
+    }
+
+    // the lock is abandoned if it exists at this point
+    let _ = std::fs::remove_file(&path);
+
+    match OpenOptions::new().write(true).create_new(true).open(&path) {
+        Ok(mut file) => {
+            write!(file, "{}", std::process::id()).context("failed to write helper lock")?;
+            Ok(Some(Lock(path)))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Ok(None),
+        Err(e) => Err(e).context("failed to create helper lock"),
+    }
+}
+
+fn open_command_lock() -> anyhow::Result<std::fs::File> {
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(home_dir()?.join(COMMAND_LOCK_FILE_NAME))
+        .context("failed to open command lock")
+}
+
+/// Waits for the background helper to finish refreshing, then locks it out of refreshing the
+/// repositories and indices the command may read until the returned file is closed. Commands
+/// share the lock with each other
+pub fn lock_for_command() -> anyhow::Result<std::fs::File> {
+    let file = open_command_lock()?;
+    file.lock_shared()
+        .context("failed to acquire command lock")?;
+
+    Ok(file)
+}
+
+/// Whether the background helper should do the long-running work of this invocation
+pub fn enabled(project: &Project) -> anyhow::Result<bool> {
+    // commands need the scripts, so the first clone must happen in the foreground
+    Ok(read_config()?.background_helper
+        && !is_isolated()
+        && !project.offline()
+        && home_dir()?.join("scripts").exists())
+}
+
+/// Spawns the background helper if its work has gone stale and it isn't already running
+pub fn spawn_if_stale(project: &Project) -> anyhow::Result<()> {
+    let markers = read_markers()?;
+
+    if is_fresh(markers.scripts_refreshed.as_ref())
+        && is_fresh(markers.indices_prefetched.get(project.package_dir()))
+    {
+        return Ok(());
+    }
+
+    if is_locked(&lock_path()?) {
+        log::debug!("background helper is already running");
+        return Ok(());
+    }
+
+    let mut command = Command::new(std::env::current_exe().context("failed to get current exe")?);
+    command
+        .arg(HELPER_COMMAND)
+        .current_dir(project.package_dir())
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+
+        const DETACHED_PROCESS: u32 = 0x00000008;
+        command.creation_flags(DETACHED_PROCESS);
+    }
+
+    command
+        .spawn()
+        .context("failed to spawn background helper")?;
+
+    log::debug!("spawned background helper");
+
+    Ok(())
+}
+
+/// Does the work of the background helper, then exits
+pub fn run(project: Project, reqwest: reqwest::blocking::Client) -> anyhow::Result<()> {
+    let Some(_lock) = acquire_lock()? else {
+        return Ok(());
+    };
+
+    #[cfg(not(feature = "version-management"))]
+    let _ = reqwest;
+
+    #[cfg(feature = "version-management")]
+    if let Err(e) = crate::cli::version::refresh_latest_version(&reqwest) {
+        log::warn!("failed to check for updates: {e:?}");
+    }
+
+    // refreshing while a command reads the repositories or indices could leave it reading a
+    // half-fetched one, so this waits until no command is running
+    let command_lock = open_command_lock()?;
+    command_lock
+        .lock()
+        .context("failed to acquire command lock")?;
+
+    let scripts_refreshed = match update_repo_dependencies(&project) {
+        Ok(()) => Some(Utc::now()),
+        Err(e) => {
+            log::warn!("failed to update repository dependencies: {e:?}");
+            None
+        }
+    };
+
+    // prefetching makes the refreshes of commands only need to fetch the latest changes
+    let indices_prefetched = project.deser_manifest().ok().map(|manifest| {
+        let sources = manifest
+            .indices
+            .into_values()
+            .map(|url| PackageSources::Pesde(PesdePackageSource::new(url)));
+        #[cfg(feature = "wally-compat")]
+        let sources =
+            sources.chain(manifest.wally_indices.into_values().map(|url| {
+                PackageSources::Wally(pesde::source::wally::WallyPackageSource::new(url))
+            }));
+
+        for source in sources {
+            if let Err(e) = source.refresh(&project) {
+                log::warn!("failed to prefetch index: {e:?}");
+            }
+        }
+
+        Utc::now()
+    });
+
+    drop(command_lock);
+
+    // another helper may have run for a different project in the meantime
+    let mut markers = read_markers()?;
+    if let Some(time) = scripts_refreshed {
+        markers.scripts_refreshed = Some(time);
+    }
+    if let Some(time) = indices_prefetched {
+        markers
+            .indices_prefetched
+            .insert(project.package_dir().to_path_buf(), time);
+    }
+    markers
+        .indices_prefetched
+        .retain(|_, time| is_fresh(Some(time)));
+
+    write_markers(&markers)
+}
@@ -0,0 +1,763 @@
+use crate::cli::config::CliConfig;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Display, sync::OnceLock};
+
+/// The locales the CLI's messages are available in
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+}
+
+/// How much detail the CLI's messages should contain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputStyle {
+    /// Full sentences, suited for interactive use
+    #[default]
+    Verbose,
+    /// Short phrasing, suited for embedding in other tools
+    Terse,
+}
+
+impl OutputStyle {
+    pub fn is_verbose(&self) -> bool {
+        matches!(self, OutputStyle::Verbose)
+    }
+}
+
+// (key, verbose, terse)
+type Entries = &'static [(&'static str, &'static str, &'static str)];
+
+const EN: Entries = &[
+    (
+        "install.header",
+        "[now installing {name} {target}]",
+        "[install {name} {target}]",
+    ),
+    (
+        "install.removing_folders",
+        "❌ removing current package folders",
+        "clean",
+    ),
+    (
+        "install.building_graph",
+        "📦 building dependency graph",
+        "resolve",
+    ),
+    (
+        "install.downloading",
+        "📥 downloading dependencies",
+        "download",
+    ),
+    (
+        "install.downloaded",
+        "📥 downloaded dependencies",
+        "downloaded",
+    ),
+    (
+        "download.mirror",
+        "downloaded {packages} from {mirror}",
+        "{mirror}: {packages}",
+    ),
+    (
+        "download.network",
+        "downloaded {size} in {downloads} packages and fetched {fetches} repositories, throttled for {throttled}, waited {waited} for connections",
+        "{size} {downloads}/{fetches} throttled {throttled} waited {waited}",
+    ),
+    ("install.linking", "🗺️ linking dependencies", "link"),
+    (
+        "install.linked",
+        "wrote {refreshed} linking modules, kept {reused} unchanged ones and removed {removed} stale ones",
+        "{refreshed} written, {reused} kept, {removed} removed",
+    ),
+    ("install.patching", "🩹 applying patches", "patch"),
+    ("install.finishing", "🧹 finishing up", "finish"),
+    (
+        "install.scripts",
+        "installing runs the manifest's scripts for these packages, which haven't been allowed or denied yet:",
+        "scripts to run:",
+    ),
+    (
+        "install.shadowed",
+        "{alias} is both a {used} and {ignored} dependency, the {used} one is used",
+        "{alias}: {used} over {ignored}",
+    ),
+    (
+        "install.only_done",
+        "installed only {alias} and its dependencies, the lockfile was left untouched",
+        "only {alias}, lockfile untouched",
+    ),
+    (
+        "update.header",
+        "[now updating {name} {target}]",
+        "[update {name} {target}]",
+    ),
+    (
+        "update.downloading",
+        "📥 downloading dependencies",
+        "download",
+    ),
+    (
+        "update.downloaded",
+        "📥 downloaded dependencies",
+        "downloaded",
+    ),
+    (
+        "update.patch_reapplied",
+        "🩹 carried the patch for {name}@{patched} over to {resolved}, {path} is no longer used",
+        "{name}@{patched} -> {resolved}: patch reapplied",
+    ),
+    (
+        "update.patch_upstreamed",
+        "🩹 {name}@{resolved} already contains the patch for {name}@{patched}, {path} is no longer used",
+        "{name}@{patched} -> {resolved}: patch upstreamed",
+    ),
+    (
+        "update.patch_conflicts",
+        "🩹 the patch for {name}@{patched} conflicts with {name}@{resolved} in {files}\nrecreate it with `{bin} patch {name}@{resolved}`, using {path} as a reference",
+        "{name}@{patched} -> {resolved}: patch conflicts in {files}",
+    ),
+    (
+        "run.workspace.header",
+        "[{script} in {name} {target}]",
+        "[{script} {name} {target}]",
+    ),
+    (
+        "run.watch.exited",
+        "[script exited with {status}, waiting for changes]",
+        "[exited {status}]",
+    ),
+    (
+        "run.watch.rerunning",
+        "[files changed, re-running script]",
+        "[rerun]",
+    ),
+    (
+        "add.pesde",
+        "added {name}@{version} {target} to {key}",
+        "+ {name}@{version} {target} ({key})",
+    ),
+    (
+        "add.wally",
+        "added wally {name}@{version} to {key}",
+        "+ wally {name}@{version} ({key})",
+    ),
+    (
+        "add.git",
+        "added git {repo}#{rev} to {key}",
+        "+ git {repo}#{rev} ({key})",
+    ),
+    (
+        "add.workspace",
+        "added workspace {name}@{version} to {key}",
+        "+ workspace {name}@{version} ({key})",
+    ),
+    (
+        "add.path",
+        "added path {path} ({version}) to {key}",
+        "+ path {path} ({version}) ({key})",
+    ),
+    (
+        "remove.done",
+        "removed {alias} from {key}",
+        "- {alias} ({key})",
+    ),
+    (
+        "remove.pruned",
+        "pruned {count} package(s) which are no longer needed",
+        "pruned {count}",
+    ),
+    (
+        "cache.exported",
+        "exported {count} cached file(s) to {path}",
+        "exported {count} -> {path}",
+    ),
+    (
+        "cache.imported",
+        "imported {count} cached file(s)",
+        "imported {count}",
+    ),
+    (
+        "cache.stats.summary",
+        "the cache at {dir} takes up {size} in {count} file(s)",
+        "{dir}: {size}, {count} file(s)",
+    ),
+    (
+        "cache.stats.packages",
+        "cached packages: {counts}",
+        "packages: {counts}",
+    ),
+    (
+        "cache.stats.largest",
+        "largest packages:",
+        "largest:",
+    ),
+    (
+        "global.added",
+        "installed {name}@{version} globally as {alias}",
+        "+ {alias} ({name}@{version})",
+    ),
+    (
+        "global.removed",
+        "removed global package {alias}",
+        "- {alias}",
+    ),
+    (
+        "global.upgraded",
+        "upgrading {alias} from {from} to {to}",
+        "{alias} {from} -> {to}",
+    ),
+    ("search.none", "no packages found", "none"),
+    ("grep.none", "no matches found", "none"),
+    (
+        "search.more",
+        "showing {shown} of {count} packages",
+        "{shown}/{count}",
+    ),
+    ("info.no_readme", "this package has no readme", "no readme"),
+    ("global.none", "no packages are installed globally", "none"),
+    (
+        "scope.owners",
+        "owned by user ids {owners}",
+        "owners: {owners}",
+    ),
+    ("scope.no_packages", "this scope has no packages", "none"),
+    (
+        "index.validated",
+        "validated index with {errors} errors and {warnings} warnings",
+        "{errors} errors, {warnings} warnings",
+    ),
+    (
+        "index.compacted",
+        "normalized {count} index files",
+        "normalized {count}",
+    ),
+    (
+        "index.already_initialized",
+        "index already initialized",
+        "already initialized",
+    ),
+    (
+        "index.initialized",
+        "wrote index config to {path}, commit it to the index repository to use it",
+        "wrote {path}",
+    ),
+    (
+        "studio_sync.listening",
+        "syncing {folder} to Roblox Studio, connect with the Rojo plugin on port {port}",
+        "listening on {port}",
+    ),
+    (
+        "pin.done",
+        "pinned {name} to {version}, run install to apply",
+        "pinned {name}@{version}",
+    ),
+    (
+        "unpin.done",
+        "unpinned {name}, run install to apply",
+        "unpinned {name}",
+    ),
+    (
+        "outdated.entry",
+        "{name} ({alias}) {current} -> {compatible}, {latest} needs a manifest change",
+        "{name} {current} -> {compatible} ({latest})",
+    ),
+    (
+        "outdated.compatible",
+        "{name} ({alias}) {current} -> {compatible}",
+        "{name} {current} -> {compatible}",
+    ),
+    (
+        "outdated.incompatible",
+        "{name} ({alias}) {current} -> {latest}, needs a manifest change",
+        "{name} {current} -> ({latest})",
+    ),
+    (
+        "outdated.failed",
+        "{count} dependencies are outdated",
+        "{count} outdated",
+    ),
+    (
+        "upgrade.entry",
+        "{alias} ({name}) {from} -> {to} in {key}",
+        "{alias} {from} -> {to}",
+    ),
+    (
+        "upgrade.none",
+        "all version requirements are up to date",
+        "up to date",
+    ),
+    (
+        "upgrade.dry_run",
+        "(dry run) the manifest was not changed",
+        "dry run",
+    ),
+    (
+        "upgrade.done",
+        "upgraded the manifest's version requirements, run install to apply",
+        "upgraded",
+    ),
+    (
+        "resolve.snapshot_written",
+        "wrote the snapshot of the resolved graph to {path}",
+        "wrote {path}",
+    ),
+    (
+        "resolve.snapshot_matches",
+        "the resolved graph matches the snapshot in {path}",
+        "matches {path}",
+    ),
+    (
+        "resolve.snapshot_changed",
+        "the resolved graph differs from the snapshot in {path}, run `{bin} resolve --snapshot {path} --update` to accept the changes",
+        "differs from {path}",
+    ),
+    (
+        "report.written",
+        "wrote report to {path}.",
+        "wrote {path}",
+    ),
+    (
+        "report.sensitive",
+        "please check it for sensitive information before sharing it",
+        "check for sensitive information before sharing",
+    ),
+    (
+        "report.replaying",
+        "replaying report from pesde {version} on {os}-{arch} created at {created_at}",
+        "replay {version} {os}-{arch} {created_at}",
+    ),
+    (
+        "report.command",
+        "captured command: `{command}` (exit code: {code})",
+        "`{command}` ({code})",
+    ),
+    ("report.resolved", "resolution succeeded", "resolved"),
+    (
+        "report.matches_lockfile",
+        "resolved graph matches the captured lockfile",
+        "matches lockfile",
+    ),
+    (
+        "why.unreachable",
+        "not reachable from any direct dependency",
+        "unreachable",
+    ),
+    (
+        "drift.lockfile",
+        "the lockfile is out of date with the manifest: {mismatch}",
+        "outdated: {mismatch}",
+    ),
+    (
+        "drift.missing",
+        "{package} is locked but missing from its index",
+        "missing: {package}",
+    ),
+    (
+        "drift.yanked",
+        "{package} is locked but has been yanked",
+        "yanked: {package}",
+    ),
+    (
+        "drift.checksum",
+        "the checksum of {package} in the lockfile differs from the one in its index",
+        "checksum mismatch: {package}",
+    ),
+    (
+        "drift.versions",
+        "{name} is locked at {locked}, but would now resolve to {resolved}",
+        "{name}: {locked} -> {resolved}",
+    ),
+    ("drift.nothing", "nothing", "none"),
+    (
+        "drift.failed",
+        "found {problems} problem(s) and {drifted} drifted package(s)",
+        "{problems} problem(s), {drifted} drifted",
+    ),
+    (
+        "drift.reproducible",
+        "resolving the manifest again reproduces the lockfile",
+        "reproducible",
+    ),
+    (
+        "manifest.legacy",
+        "the manifest uses deprecated fields, run `{bin} migrate manifest` to update it",
+        "deprecated manifest",
+    ),
+    (
+        "migrate.manifest.none",
+        "the manifest has no deprecated fields",
+        "up to date",
+    ),
+    (
+        "migrate.manifest.dry_run",
+        "(dry run) the manifest was not changed",
+        "dry run",
+    ),
+    (
+        "migrate.manifest.done",
+        "migrated the manifest to {path}",
+        "migrated",
+    ),
+    (
+        "init.already_initialized",
+        "project already initialized",
+        "already initialized",
+    ),
+    ("init.done", "initialized project", "initialized"),
+    (
+        "auth.logging_in",
+        "logging in into {index}",
+        "login {index}",
+    ),
+    (
+        "auth.token_set",
+        "set token for {index}",
+        "token set {index}",
+    ),
+    (
+        "auth.logged_in_as",
+        "logged in as {user} into {index}",
+        "{user} @ {index}",
+    ),
+    (
+        "auth.device_flow_failed",
+        "couldn't log in with the device flow",
+        "device flow failed",
+    ),
+    (
+        "auth.device_flow_failed_hint",
+        "this is often caused by organizations enforcing SSO or restricting OAuth apps, a personal access token can be used instead with `--token`",
+        "use a personal access token with `--token`",
+    ),
+    (
+        "auth.token_instructions",
+        "create a classic personal access token at {url}, with only the read:user scope\nif your organization enforces SSO, authorize the token for it after creating it",
+        "create a token with the read:user scope at {url}",
+    ),
+    (
+        "auth.token_rejected",
+        "the registry of {index} rejected the token, it may not have access to the index",
+        "token rejected by {index}",
+    ),
+    (
+        "auth.logged_out",
+        "logged out of {index}",
+        "logged out {index}",
+    ),
+    (
+        "auth.not_logged_in",
+        "not logged in into {index}",
+        "not logged in {index}",
+    ),
+    (
+        "config.default_index.set",
+        "default index set to: {index}",
+        "{index}",
+    ),
+    (
+        "config.default_index.current",
+        "current default index: {index}",
+        "{index}",
+    ),
+    (
+        "config.scripts_repo.set",
+        "scripts repo set to: {repo}",
+        "{repo}",
+    ),
+    (
+        "config.scripts_repo.current",
+        "current scripts repo: {repo}",
+        "{repo}",
+    ),
+    (
+        "lock.missing",
+        "{dir} has no lockfile",
+        "{dir}: no lockfile",
+    ),
+    (
+        "lock.up_to_date",
+        "lockfile of {dir} is up to date",
+        "{dir}: up to date",
+    ),
+    (
+        "lock.out_of_date",
+        "lockfile of {dir} is out of date:",
+        "{dir}: out of date",
+    ),
+    (
+        "execute.confirm",
+        "{package} hasn't been run before, check that you trust it:",
+        "run {package}?",
+    ),
+    (
+        "execute.unconfirmed",
+        "{package} hasn't been run before, pass --yes to run it without confirmation",
+        "{package} unconfirmed, pass --yes",
+    ),
+    ("execute.cancelled", "cancelled running {package}", "cancelled"),
+    (
+        "verify.header",
+        "[now verifying {package} {target}]",
+        "[verify {package} {target}]",
+    ),
+    (
+        "verify.downloading",
+        "📥 downloading package",
+        "download",
+    ),
+    ("verify.downloaded", "📥 downloaded package", "downloaded"),
+    (
+        "verify.script",
+        "🧪 running verify script {script}",
+        "script {script}",
+    ),
+    (
+        "verify.problem.lib_unparsable",
+        "lib file {path} could not be parsed for its types: {errors}",
+        "{path}: unparsable ({errors})",
+    ),
+    (
+        "verify.problem.lib_missing",
+        "lib file {path} does not exist",
+        "{path}: missing",
+    ),
+    (
+        "verify.problem.lib_unreadable",
+        "lib file {path} could not be read: {error}",
+        "{path}: {error}",
+    ),
+    (
+        "verify.problem.bin_missing",
+        "bin file {path} does not exist",
+        "{path}: missing",
+    ),
+    (
+        "verify.problem.build_missing",
+        "build file {path} does not exist",
+        "{path}: missing",
+    ),
+    (
+        "verify.problem.manifest_missing",
+        "{file} is missing",
+        "{file}: missing",
+    ),
+    (
+        "verify.problem.manifest_invalid",
+        "{file} is invalid: {error}",
+        "{file}: {error}",
+    ),
+    (
+        "verify.problem.script_failed",
+        "{script} script failed with code {code}",
+        "{script}: code {code}",
+    ),
+    (
+        "verify.problem.script_killed",
+        "{script} script failed with no exit code",
+        "{script}: no exit code",
+    ),
+    (
+        "verify.problem.linking_failed",
+        "linking failed: {error}",
+        "linking: {error}",
+    ),
+    (
+        "verify.passed",
+        "{package} {target} passed verification",
+        "{package} {target}: ok",
+    ),
+    (
+        "verify.failed",
+        "verification failed for {count} targets",
+        "{count} targets failed",
+    ),
+    (
+        "audit.missing_license",
+        "{package} doesn't declare a license",
+        "{package}: no license",
+    ),
+    (
+        "audit.disallowed_license",
+        "{package} is licensed under {license}, which isn't allowed",
+        "{package}: {license}",
+    ),
+    (
+        "audit.violations",
+        "{count} dependencies have missing or disallowed licenses",
+        "{count} violations",
+    ),
+    (
+        "audit.advisories",
+        "{count} security advisories affect the dependencies",
+        "{count} advisories",
+    ),
+    (
+        "audit.no_advisories",
+        "no security advisories affect the dependencies",
+        "ok",
+    ),
+    (
+        "install.skipped",
+        "skipped {path} of {package}, as it is never installed",
+        "skipped {package}: {path}",
+    ),
+    (
+        "install.advisories",
+        "security advisories affect these dependencies:",
+        "advisories:",
+    ),
+    (
+        "audit.passed",
+        "all dependencies have allowed licenses",
+        "ok",
+    ),
+    (
+        "publish.header",
+        "[now publishing {name} {target}]",
+        "[publish {name} {target}]",
+    ),
+    (
+        "publish.private",
+        "package is private, cannot publish",
+        "private package",
+    ),
+    ("publish.aborted", "publish aborted", "aborted"),
+    (
+        "publish.dry_run",
+        "(dry run) package with {count} files written to {path} ({size})",
+        "dry run: {count} files -> {path} ({size})",
+    ),
+    (
+        "publish.confirm.header",
+        "please confirm the following information:",
+        "confirm:",
+    ),
+    ("publish.confirm.none", "(none)", "-"),
+    ("publish.confirm.name", "name: {value}", "name: {value}"),
+    ("publish.confirm.version", "version: {value}", "version: {value}"),
+    (
+        "publish.confirm.description",
+        "description: {value}",
+        "description: {value}",
+    ),
+    ("publish.confirm.license", "license: {value}", "license: {value}"),
+    ("publish.confirm.authors", "authors: {value}", "authors: {value}"),
+    (
+        "publish.confirm.repository",
+        "repository: {value}",
+        "repository: {value}",
+    ),
+    ("publish.confirm.keywords", "keywords: {value}", "keywords: {value}"),
+    (
+        "publish.confirm.categories",
+        "categories: {value}",
+        "categories: {value}",
+    ),
+    ("publish.confirm.target", "target: {value}", "target: {value}"),
+    ("publish.confirm.lib_path", "lib path: {value}", "lib: {value}"),
+    (
+        "publish.confirm.build_files",
+        "build files: {value}",
+        "build: {value}",
+    ),
+    ("publish.confirm.bin_path", "bin path: {value}", "bin: {value}"),
+    ("publish.confirm.includes", "includes: {value}", "includes: {value}"),
+    (
+        "publish.confirm.prompt",
+        "is this information correct?",
+        "correct?",
+    ),
+    (
+        "publish.version_exists",
+        "package version already exists",
+        "version exists",
+    ),
+    (
+        "publish.unauthorized_scope",
+        "unauthorized to publish under this scope",
+        "unauthorized scope",
+    ),
+    ("publish.invalid_package", "invalid package", "invalid"),
+    (
+        "publish.stripped",
+        "files of the {target} package which won't be published ({count}):",
+        "stripped from {target} ({count}):",
+    ),
+    (
+        "publish.workspace_root_error",
+        "an error occurred publishing workspace root: {error}",
+        "workspace root: {error}",
+    ),
+];
+
+struct Catalog {
+    locale: Locale,
+    style: OutputStyle,
+    overrides: BTreeMap<String, String>,
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+fn entries(locale: Locale) -> Entries {
+    match locale {
+        Locale::En => EN,
+    }
+}
+
+/// Sets up the message catalog. The locale passed in (usually from `--locale`) takes
+/// precedence over the one in the config
+pub fn init(locale: Option<Locale>, config: &CliConfig) {
+    let _ = CATALOG.set(Catalog {
+        locale: locale.or(config.locale).unwrap_or_default(),
+        style: config.output_style,
+        overrides: config.messages.clone(),
+    });
+}
+
+/// Looks up a message by its key and fills in its `{placeholder}`s
+pub fn message(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let catalog = CATALOG.get();
+    let locale = catalog.map(|c| c.locale).unwrap_or_default();
+    let style = catalog.map(|c| c.style).unwrap_or_default();
+
+    let template = catalog
+        .and_then(|c| c.overrides.get(key))
+        .map(String::as_str)
+        .or_else(|| {
+            entries(locale)
+                .iter()
+                .chain(entries(Locale::default()))
+                .find(|(k, _, _)| *k == key)
+                .map(|(_, verbose, terse)| if style.is_verbose() { *verbose } else { *terse })
+        });
+
+    let Some(template) = template else {
+        log::debug!("no message found for key {key}");
+        return key.to_string();
+    };
+
+    let mut output = template.to_string();
+    for (name, value) in args {
+        output = output.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+
+    output
+}
+
+/// Formats a message from the catalog, e.g. `msg!("auth.logged_out", index = index_url)`
+macro_rules! msg {
+    ($key:literal $(, $name:ident = $value:expr)* $(,)?) => {
+        $crate::cli::messages::message(
+            $key,
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),*],
+        )
+    };
+}
+
+pub(crate) use msg;
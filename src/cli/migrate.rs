@@ -0,0 +1,106 @@
+use crate::cli::{
+    config::{read_config, write_config},
+    home_dir,
+};
+use anyhow::Context;
+use std::path::{Path, PathBuf};
+
+const LAYOUT_FILE_NAME: &str = "layout";
+const BACKUPS_FOLDER: &str = "backups";
+/// Files which are backed up before migrating. The CAS and data directory aren't, as they're
+/// large and can be recreated by installing again
+const BACKED_UP_FILES: &[&str] = &["config.toml"];
+
+/// The version of the layout of the home directory this version of pesde uses
+pub const LAYOUT_VERSION: u32 = 1;
+
+type Migration = fn(&Path) -> anyhow::Result<()>;
+
+/// The migration at index `n` upgrades the home directory from layout version `n` to `n + 1`
+const MIGRATIONS: &[(&str, Migration)] = &[(
+    "rewrite the config file in the current format",
+    rewrite_config,
+)];
+
+const _: () = assert!(MIGRATIONS.len() as u32 == LAYOUT_VERSION);
+
+fn rewrite_config(home: &Path) -> anyhow::Result<()> {
+    if !home.join("config.toml").exists() {
+        return Ok(());
+    }
+
+    write_config(&read_config()?)
+}
+
+fn write_layout_version(home: &Path, version: u32) -> anyhow::Result<()> {
+    std::fs::write(home.join(LAYOUT_FILE_NAME), version.to_string())
+        .context("failed to write layout version")
+}
+
+fn backup(home: &Path, version: u32) -> anyhow::Result<PathBuf> {
+    let backup_dir = home.join(BACKUPS_FOLDER).join(format!(
+        "layout-{version}-{}",
+        chrono::Utc::now().timestamp()
+    ));
+    std::fs::create_dir_all(&backup_dir).context("failed to create backup directory")?;
+
+    for file in BACKED_UP_FILES {
+        match std::fs::copy(home.join(file), backup_dir.join(file)) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e).with_context(|| format!("failed to back up {file}")),
+        }
+    }
+
+    Ok(backup_dir)
+}
+
+/// Upgrades the home directory to the layout of this version of pesde
+pub fn migrate_home() -> anyhow::Result<()> {
+    let home = home_dir()?;
+
+    let version = match std::fs::read_to_string(home.join(LAYOUT_FILE_NAME)) {
+        Ok(s) => s.trim().parse::<u32>().context("invalid layout version")?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            let is_fresh = !home.join("config.toml").exists() && !home.join("data").exists();
+
+            if is_fresh {
+                std::fs::create_dir_all(&home).context("failed to create home directory")?;
+                return write_layout_version(&home, LAYOUT_VERSION);
+            }
+
+            // the home directory was created before layout versions existed
+            0
+        }
+        Err(e) => return Err(e).context("failed to read layout version"),
+    };
+
+    if version >= LAYOUT_VERSION {
+        if version > LAYOUT_VERSION {
+            log::debug!(
+                "home directory uses layout version {version}, newer than the supported {LAYOUT_VERSION}"
+            );
+        }
+
+        return Ok(());
+    }
+
+    let backup_dir = backup(&home, version)?;
+
+    for (from, (description, migration)) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        let to = from as u32 + 1;
+
+        log::info!("migrating home directory to layout version {to}: {description}");
+
+        migration(&home).with_context(|| {
+            format!(
+                "failed to migrate home directory to layout version {to}, a backup is available at {}",
+                backup_dir.display()
+            )
+        })?;
+
+        write_layout_version(&home, to)?;
+    }
+
+    Ok(())
+}
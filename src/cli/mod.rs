@@ -1,19 +1,29 @@
+use crate::cli::messages::msg;
 use anyhow::Context;
+use colored::Colorize;
 use indicatif::MultiProgress;
 use pesde::{
-    lockfile::{DependencyGraph, DownloadedGraph, Lockfile},
+    audit::{AdvisoryDatabase, Finding},
+    lockfile::{check_compatibility, DependencyGraph, DownloadedGraph, Lockfile},
     manifest::target::TargetKind,
     names::{PackageName, PackageNames},
-    source::{version_id::VersionId, workspace::specifier::VersionTypeOrReq, PackageSources},
+    source::{
+        git_index::GitBasedSource, pesde::PesdePackageSource, progress::DownloadProgress,
+        refs::PackageRefs, version_id::VersionId, workspace::specifier::VersionTypeOrReq,
+        PackageSources,
+    },
     Project,
 };
 use relative_path::RelativePathBuf;
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashSet},
     fs::create_dir_all,
     path::PathBuf,
     str::FromStr,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
     time::Duration,
 };
 
@@ -21,13 +31,34 @@ pub mod auth;
 pub mod commands;
 pub mod config;
 pub mod files;
+pub mod helper;
+pub mod messages;
+pub mod migrate;
 pub mod repos;
+pub mod shims;
 #[cfg(feature = "version-management")]
 pub mod version;
 
 pub const HOME_DIR: &str = concat!(".", env!("CARGO_PKG_NAME"));
 
+static ISOLATED_HOME_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Keeps all state of pesde in the given directory instead of the user's home directory
+pub fn isolate(dir: PathBuf) {
+    ISOLATED_HOME_DIR
+        .set(dir)
+        .expect("home directory already isolated");
+}
+
+pub fn is_isolated() -> bool {
+    ISOLATED_HOME_DIR.get().is_some()
+}
+
 pub fn home_dir() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = ISOLATED_HOME_DIR.get() {
+        return Ok(dir.clone());
+    }
+
     Ok(dirs::home_dir()
         .context("failed to get home directory")?
         .join(HOME_DIR))
@@ -39,6 +70,11 @@ pub fn bin_dir() -> anyhow::Result<PathBuf> {
     Ok(bin_dir)
 }
 
+/// The directory of the project which globally installed packages are dependencies of
+pub fn globals_dir() -> anyhow::Result<PathBuf> {
+    Ok(home_dir()?.join("globals"))
+}
+
 pub fn up_to_date_lockfile(project: &Project) -> anyhow::Result<Option<Lockfile>> {
     let manifest = project.deser_manifest()?;
     let lockfile = match project.deser_lockfile() {
@@ -51,46 +87,93 @@ pub fn up_to_date_lockfile(project: &Project) -> anyhow::Result<Option<Lockfile>
         Err(e) => return Err(e.into()),
     };
 
-    if manifest.overrides != lockfile.overrides {
-        log::debug!("overrides are different");
+    if project.deser_pins()? != lockfile.pins {
+        log::debug!("pins are different");
         return Ok(None);
     }
 
-    if manifest.target.kind() != lockfile.target {
-        log::debug!("target kind is different");
-        return Ok(None);
+    let mismatches = check_compatibility(&manifest, &lockfile)
+        .context("failed to check lockfile compatibility")?;
+
+    for mismatch in &mismatches {
+        log::debug!("lockfile is out of date: {mismatch}");
     }
 
-    if manifest.name != lockfile.name || manifest.version != lockfile.version {
-        log::debug!("name or version is different");
-        return Ok(None);
+    Ok(mismatches.is_empty().then_some(lockfile))
+}
+
+/// Checks the packages of the graph against the advisory databases of the indices they're from,
+/// whose repositories must have been refreshed
+pub fn find_advisories(project: &Project, graph: &DependencyGraph) -> anyhow::Result<Vec<Finding>> {
+    let mut index_urls = HashSet::new();
+    let mut databases = BTreeSet::new();
+
+    for node in graph.values().flat_map(|versions| versions.values()) {
+        let PackageRefs::Pesde(pkg_ref) = &node.pkg_ref else {
+            continue;
+        };
+        if !index_urls.insert(pkg_ref.index_url.clone()) {
+            continue;
+        }
+
+        let config = PesdePackageSource::new(pkg_ref.index_url.clone())
+            .config(project)
+            .context("failed to read index config")?;
+        if let Some(advisories) = config.advisories {
+            databases.insert(AdvisoryDatabase::new(advisories));
+        }
     }
 
-    let specs = lockfile
-        .graph
-        .iter()
-        .flat_map(|(_, versions)| versions)
-        .filter_map(|(_, node)| {
-            node.node
-                .direct
-                .as_ref()
-                .map(|(_, spec)| (spec, node.node.ty))
-        })
-        .collect::<HashSet<_>>();
-
-    let same_dependencies = manifest
-        .all_dependencies()
-        .context("failed to get all dependencies")?
-        .iter()
-        .all(|(_, (spec, ty))| specs.contains(&(spec, *ty)));
-
-    log::debug!("dependencies are the same: {same_dependencies}");
-
-    Ok(if same_dependencies {
-        Some(lockfile)
-    } else {
-        None
-    })
+    let mut findings = vec![];
+    let mut seen = HashSet::new();
+
+    for database in databases {
+        database.refresh(project).with_context(|| {
+            format!("failed to refresh advisories from {}", database.repo_url())
+        })?;
+
+        // databases may share advisories, which should only be reported once
+        findings.extend(
+            database
+                .check(graph, project)
+                .context("failed to check advisories")?
+                .into_iter()
+                .filter(|finding| {
+                    seen.insert((
+                        finding.name.clone(),
+                        finding.version_id.clone(),
+                        finding.id.clone(),
+                    ))
+                }),
+        );
+    }
+
+    Ok(findings)
+}
+
+/// Prints the findings of an advisory check, the most severe first
+pub fn print_advisories(findings: &mut [Finding]) {
+    findings.sort_by_key(|finding| std::cmp::Reverse(finding.advisory.severity));
+
+    for finding in findings {
+        let severity = finding.advisory.severity.to_string();
+        println!(
+            "{} {} {}@{}: {}",
+            match finding.advisory.severity {
+                pesde::audit::Severity::Low | pesde::audit::Severity::Medium => severity.yellow(),
+                _ => severity.red(),
+            }
+            .bold(),
+            finding.id.dimmed(),
+            finding.name,
+            finding.version_id,
+            finding.advisory.title
+        );
+
+        if let Some(url) = &finding.advisory.url {
+            println!("  {url}");
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -150,6 +233,7 @@ enum AnyPackageIdentifier<V: FromStr = VersionId, N: FromStr = PackageNames> {
     PackageName(VersionedPackageName<V, N>),
     Url((gix::Url, String)),
     Workspace(VersionedPackageName<VersionTypeOrReq, PackageName>),
+    Path(PathBuf),
 }
 
 impl<V: FromStr<Err = E>, E: Into<anyhow::Error>, N: FromStr<Err = F>, F: Into<anyhow::Error>>
@@ -168,6 +252,8 @@ impl<V: FromStr<Err = E>, E: Into<anyhow::Error>, N: FromStr<Err = F>, F: Into<a
             )))
         } else if let Some(rest) = s.strip_prefix("workspace:") {
             Ok(AnyPackageIdentifier::Workspace(rest.parse()?))
+        } else if let Some(rest) = s.strip_prefix("path:") {
+            Ok(AnyPackageIdentifier::Path(rest.into()))
         } else if s.contains(':') {
             let (url, rev) = s.split_once('#').context("missing revision")?;
 
@@ -200,48 +286,124 @@ pub fn download_graph(
 ) -> anyhow::Result<DownloadedGraph> {
     let bar = multi.add(
         indicatif::ProgressBar::new(graph.values().map(|versions| versions.len() as u64).sum())
-            .with_style(
-                indicatif::ProgressStyle::default_bar()
-                    .template("{msg} {bar:40.208/166} {pos}/{len} {percent}% {elapsed_precise}")?,
-            )
+            .with_style(indicatif::ProgressStyle::default_bar().template(
+                "{msg} {bar:40.208/166} {pos}/{len} {percent}% {elapsed_precise} {prefix}",
+            )?)
             .with_message(progress_msg),
     );
     bar.enable_steady_tick(Duration::from_millis(100));
 
+    let downloaded_bytes = Arc::new(AtomicU64::new(0));
+    let mirrors = Arc::new(Mutex::new(BTreeMap::<String, String>::new()));
+    let project = project
+        .clone()
+        .with_download_progress(Some(DownloadProgress::new({
+            let bar = bar.clone();
+            let mirrors = mirrors.clone();
+            move |update| {
+                let downloaded = downloaded_bytes.fetch_add(update.received, Ordering::Relaxed)
+                    + update.received;
+                bar.set_prefix(indicatif::HumanBytes(downloaded).to_string());
+
+                if let Some(mirror) = update.mirror {
+                    mirrors
+                        .lock()
+                        .unwrap()
+                        .entry(update.package.to_string())
+                        .or_insert_with(|| mirror.to_string());
+                }
+            }
+        })));
+
     let (rx, downloaded_graph) = project
         .download_graph(graph, refreshed_sources, reqwest, threads, prod, write)
         .context("failed to download dependencies")?;
 
+    let mut requires_network = vec![];
+    let mut failures = vec![];
+
     while let Ok(result) = rx.recv() {
         bar.inc(1);
 
         match result {
             Ok(()) => {}
-            Err(e) => return Err(e.into()),
+            Err(e) => match e.offline_package() {
+                Some(package) => requires_network.push(package.to_string()),
+                None => failures.push(anyhow::Error::from(e)),
+            },
         }
     }
 
+    if failures.len() == 1 {
+        return Err(failures.remove(0));
+    } else if !failures.is_empty() {
+        anyhow::bail!(
+            "failed to download {} packages:\n{}",
+            failures.len(),
+            failures
+                .iter()
+                .map(|e| format!("  - {e:#}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
+    if !requires_network.is_empty() {
+        anyhow::bail!(
+            "the following packages require network access:\n{}",
+            requires_network
+                .iter()
+                .map(|package| format!("  - {package}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+
     bar.finish_with_message(finish_msg);
 
+    if project.network_limiter().is_limited() {
+        let metrics = project.network_limiter().metrics();
+
+        println!(
+            "{}",
+            msg!(
+                "download.network",
+                size = indicatif::HumanBytes(metrics.downloaded),
+                downloads = metrics.downloads,
+                fetches = metrics.git_fetches,
+                throttled = indicatif::HumanDuration(metrics.throttled),
+                waited = indicatif::HumanDuration(metrics.connection_wait)
+            )
+            .dimmed()
+        );
+    }
+
+    let mut packages_by_mirror = BTreeMap::<String, Vec<String>>::new();
+    for (package, mirror) in std::mem::take(&mut *mirrors.lock().unwrap()) {
+        packages_by_mirror.entry(mirror).or_default().push(package);
+    }
+
+    for (mirror, packages) in packages_by_mirror {
+        println!(
+            "{}",
+            msg!(
+                "download.mirror",
+                packages = packages.join(", "),
+                mirror = mirror
+            )
+            .dimmed()
+        );
+    }
+
     Ok(Arc::into_inner(downloaded_graph)
         .unwrap()
         .into_inner()
         .unwrap())
 }
 
-pub fn shift_project_dir(project: &Project, pkg_dir: PathBuf) -> Project {
-    Project::new(
-        pkg_dir,
-        Some(project.package_dir()),
-        project.data_dir(),
-        project.cas_dir(),
-        project.auth_config().clone(),
-    )
-}
-
 pub fn run_on_workspace_members(
     project: &Project,
-    f: impl Fn(Project) -> anyhow::Result<()>,
+    mut f: impl FnMut(Project) -> anyhow::Result<()>,
 ) -> anyhow::Result<BTreeMap<PackageName, BTreeMap<TargetKind, RelativePathBuf>>> {
     Ok(match project.workspace_dir() {
         Some(_) => {
@@ -262,11 +424,8 @@ pub fn run_on_workspace_members(
                 )
             })
             .map(|(name, target, path)| {
-                f(shift_project_dir(
-                    project,
-                    path.to_path(project.package_dir()),
-                ))
-                .map(|_| (name, target, path))
+                f(project.for_member(path.to_path(project.package_dir())))
+                    .map(|_| (name, target, path))
             })
             .collect::<Result<Vec<_>, _>>()
             .context("failed to install workspace member's dependencies")?
@@ -14,6 +14,15 @@ fn update_repo<P: AsRef<Path>>(
     project: &Project,
 ) -> anyhow::Result<()> {
     let path = path.as_ref();
+
+    if project.offline() {
+        if !path.exists() {
+            log::warn!("{name} repository has not been cloned yet, and offline mode is enabled");
+        }
+
+        return Ok(());
+    }
+
     if path.exists() {
         let repo = gix::open(path).context(format!("failed to open {name} repository"))?;
 
@@ -0,0 +1,318 @@
+use crate::cli::{globals_dir, home_dir};
+use anyhow::Context;
+use pesde::{manifest::target::TargetKind, MANIFEST_FILE_NAME};
+use semver::Version;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+/// The engine used to run binary packages
+pub const ENGINE: &str = "lune";
+
+/// The executable of the newest version of the engine in the managed engines directory, laid out
+/// as `{engine}/{version}/{engine}`
+fn managed_engine(engines_dir: &Path) -> Option<PathBuf> {
+    let executable = format!("{ENGINE}{}", std::env::consts::EXE_SUFFIX);
+
+    std::fs::read_dir(engines_dir.join(ENGINE))
+        .ok()?
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let version = entry.file_name().to_str()?.parse::<Version>().ok()?;
+            let path = entry.path().join(&executable);
+
+            path.is_file().then_some((version, path))
+        })
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, path)| path)
+}
+
+/// The executable of the engine on `PATH`
+fn path_engine() -> Option<PathBuf> {
+    let executable = format!("{ENGINE}{}", std::env::consts::EXE_SUFFIX);
+
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .map(|dir| dir.join(&executable))
+        .find(|path| path.is_file())
+}
+
+/// The engine shims run binary packages with, preferring the ones managed by pesde over the one
+/// on `PATH`. Shims point at it by path, so they keep working if `PATH` changes
+pub fn engine_path() -> anyhow::Result<PathBuf> {
+    let engines_dir = home_dir()?.join("engines");
+
+    Ok(managed_engine(&engines_dir)
+        .or_else(path_engine)
+        .unwrap_or_else(|| PathBuf::from(ENGINE)))
+}
+
+fn bin_script(command: &str, module: &str, globals_dir: &Path) -> String {
+    let mut all_combinations = BTreeSet::new();
+
+    for a in TargetKind::VARIANTS {
+        for b in TargetKind::VARIANTS {
+            all_combinations.insert((a, b));
+        }
+    }
+
+    let all_folders = all_combinations
+        .into_iter()
+        .map(|(a, b)| format!("{:?}", a.packages_folder(b)))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        r#"local process = require("@lune/process")
+local fs = require("@lune/fs")
+local stdio = require("@lune/stdio")
+
+local project_root = process.cwd
+local path_components = string.split(string.gsub(project_root, "\\", "/"), "/")
+
+for i = #path_components, 1, -1 do
+    local path = table.concat(path_components, "/", 1, i)
+    if fs.isFile(path .. "/{MANIFEST_FILE_NAME}") then
+        project_root = path
+        break
+    end
+end
+
+for _, root in {{ project_root, {globals_dir:?} }} do
+    for _, packages_folder in {{ {all_folders} }} do
+        local path = `{{root}}/{{packages_folder}}/{module}`
+
+        if fs.isFile(path) then
+            require(path)
+            return
+        end
+    end
+end
+
+stdio.ewrite(stdio.color("red") .. "binary `{command}` not found. are you in the right directory?" .. stdio.color("reset") .. "\n")
+    "#,
+    )
+}
+
+#[cfg(unix)]
+fn sh_shim(command: &str, engine: &Path) -> String {
+    // single quotes keep the shell from expanding anything in the path
+    let engine = engine.display().to_string().replace('\'', r"'\''");
+
+    format!(
+        r#"#!/bin/sh
+exec '{engine}' run "$(dirname "$0")/{command}.luau" -- "$@"
+"#
+    )
+}
+
+#[cfg(windows)]
+fn cmd_shim(command: &str, engine: &Path) -> String {
+    format!(
+        "@echo off\r\n\"{}\" run \"%~dp0{command}.luau\" -- %*\r\n",
+        engine.display()
+    )
+}
+
+#[cfg(windows)]
+fn ps1_shim(command: &str, engine: &Path) -> String {
+    let engine = engine.display().to_string().replace('\'', "''");
+
+    format!(
+        r#"& '{engine}' run "$PSScriptRoot\{command}.luau" -- @args
+exit $LASTEXITCODE
+"#
+    )
+}
+
+/// Writes the script and platform-appropriate shims for the command into `bin_dir`, running the
+/// binary linking module with the file name `module`
+pub fn write_bin_shims(bin_dir: &Path, command: &str, module: &str) -> anyhow::Result<()> {
+    write_shims(bin_dir, command, module, &globals_dir()?, &engine_path()?)
+}
+
+fn write_shims(
+    bin_dir: &Path,
+    command: &str,
+    module: &str,
+    globals_dir: &Path,
+    engine: &Path,
+) -> anyhow::Result<()> {
+    std::fs::write(
+        bin_dir.join(format!("{command}.luau")),
+        bin_script(command, module, globals_dir),
+    )
+    .context("failed to write bin script")?;
+
+    #[cfg(unix)]
+    {
+        use crate::cli::files::make_executable;
+
+        let shim = bin_dir.join(command);
+        std::fs::write(&shim, sh_shim(command, engine)).context("failed to write sh shim")?;
+        make_executable(&shim).context("failed to make sh shim executable")?;
+    }
+
+    #[cfg(windows)]
+    {
+        std::fs::write(
+            bin_dir.join(format!("{command}.cmd")),
+            cmd_shim(command, engine),
+        )
+        .context("failed to write cmd shim")?;
+        std::fs::write(
+            bin_dir.join(format!("{command}.ps1")),
+            ps1_shim(command, engine),
+        )
+        .context("failed to write PowerShell shim")?;
+
+        // older versions copied the pesde executable as the shim, which would take precedence
+        for legacy in [
+            bin_dir.join(command),
+            bin_dir.join(format!("{command}{}", std::env::consts::EXE_SUFFIX)),
+        ] {
+            match std::fs::remove_file(&legacy) {
+                Ok(_) => log::debug!("removed legacy shim {}", legacy.display()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e).context("failed to remove legacy shim"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes the script and shims of a command from `bin_dir`
+pub fn remove_bin_shims(bin_dir: &Path, command: &str) -> anyhow::Result<()> {
+    let mut files = vec![bin_dir.join(format!("{command}.luau"))];
+
+    #[cfg(unix)]
+    files.push(bin_dir.join(command));
+
+    #[cfg(windows)]
+    files.extend([
+        bin_dir.join(format!("{command}.cmd")),
+        bin_dir.join(format!("{command}.ps1")),
+    ]);
+
+    for file in files {
+        match std::fs::remove_file(&file) {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("failed to remove {}", file.display()))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bin_script_searches_project_and_globals() {
+        let script = bin_script("tool", "tool.bin.luau", Path::new("/home/user/globals"));
+
+        assert!(script.contains(&format!("\"/{MANIFEST_FILE_NAME}\"")));
+        assert!(script.contains("{ project_root, \"/home/user/globals\" }"));
+        assert!(script.contains("/tool.bin.luau`"));
+        assert!(script.contains("binary `tool` not found"));
+
+        for kind in TargetKind::VARIANTS {
+            assert!(script.contains(&format!("{:?}", kind.packages_folder(kind))));
+        }
+    }
+
+    #[test]
+    fn prefers_newest_managed_engine() {
+        let engines_dir = tempfile::tempdir().unwrap();
+        assert_eq!(managed_engine(engines_dir.path()), None);
+
+        let executable = format!("{ENGINE}{}", std::env::consts::EXE_SUFFIX);
+        for version in ["0.8.9", "0.10.0", "not-a-version"] {
+            let dir = engines_dir.path().join(ENGINE).join(version);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(&executable), "").unwrap();
+        }
+        // versions without an executable are skipped
+        std::fs::create_dir_all(engines_dir.path().join(ENGINE).join("0.11.0")).unwrap();
+
+        assert_eq!(
+            managed_engine(engines_dir.path()),
+            Some(
+                engines_dir
+                    .path()
+                    .join(ENGINE)
+                    .join("0.10.0")
+                    .join(&executable)
+            )
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sh_shim_quotes_engine() {
+        assert_eq!(
+            sh_shim("tool", Path::new("/opt/it's/lune")),
+            "#!/bin/sh\nexec '/opt/it'\\''s/lune' run \"$(dirname \"$0\")/tool.luau\" -- \"$@\"\n"
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_shims_point_at_engine() {
+        let engine = Path::new(r"C:\engines\lune\0.8.9\lune.exe");
+
+        assert_eq!(
+            cmd_shim("tool", engine),
+            "@echo off\r\n\"C:\\engines\\lune\\0.8.9\\lune.exe\" run \"%~dp0tool.luau\" -- %*\r\n"
+        );
+        assert!(ps1_shim("tool", engine)
+            .starts_with(r#"& 'C:\engines\lune\0.8.9\lune.exe' run "$PSScriptRoot\tool.luau""#));
+    }
+
+    #[test]
+    fn writes_and_removes_shims() {
+        let bin_dir = tempfile::tempdir().unwrap();
+        let engine = Path::new("/opt/lune");
+
+        write_shims(
+            bin_dir.path(),
+            "tool",
+            "tool.bin.luau",
+            Path::new("/globals"),
+            engine,
+        )
+        .unwrap();
+        assert!(bin_dir.path().join("tool.luau").is_file());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let shim = bin_dir.path().join("tool");
+            assert_eq!(
+                std::fs::read_to_string(&shim).unwrap(),
+                sh_shim("tool", engine)
+            );
+            assert_ne!(
+                std::fs::metadata(&shim).unwrap().permissions().mode() & 0o111,
+                0
+            );
+        }
+
+        #[cfg(windows)]
+        {
+            assert!(bin_dir.path().join("tool.cmd").is_file());
+            assert!(bin_dir.path().join("tool.ps1").is_file());
+        }
+
+        remove_bin_shims(bin_dir.path(), "tool").unwrap();
+        assert_eq!(std::fs::read_dir(bin_dir.path()).unwrap().count(), 0);
+    }
+}
@@ -7,7 +7,7 @@ use std::{fs::create_dir_all, io::Read, path::PathBuf};
 
 use crate::cli::{
     bin_dir,
-    config::{read_config, write_config, CliConfig},
+    config::{read_config, write_config, CliConfig, VersionSource},
     files::make_executable,
     home_dir,
 };
@@ -38,69 +38,128 @@ fn get_repo() -> (String, String) {
 
 const CHECK_INTERVAL: chrono::Duration = chrono::Duration::hours(6);
 
-pub fn check_for_updates(reqwest: &reqwest::blocking::Client) -> anyhow::Result<()> {
-    let (owner, repo) = get_repo();
+fn fetch_latest_version(
+    reqwest: &reqwest::blocking::Client,
+    source: &VersionSource,
+) -> anyhow::Result<Option<Version>> {
+    match source {
+        VersionSource::GitHub => {
+            let (owner, repo) = get_repo();
+
+            let releases = reqwest
+                .get(format!(
+                    "https://api.github.com/repos/{owner}/{repo}/releases",
+                ))
+                .send()
+                .context("failed to send request to GitHub API")?
+                .error_for_status()
+                .context("failed to get GitHub API response")?
+                .json::<Vec<Release>>()
+                .context("failed to parse GitHub API response")?;
+
+            releases
+                .into_iter()
+                .filter_map(|release| Version::parse(release.tag_name.trim_start_matches('v')).ok())
+                .max()
+                .context("failed to find latest version")
+                .map(Some)
+        }
+        VersionSource::Artifacts {
+            latest_url: Some(latest_url),
+            ..
+        } => {
+            let version = reqwest
+                .get(latest_url.clone())
+                .send()
+                .context("failed to send request to artifact server")?
+                .error_for_status()
+                .context("failed to get artifact server response")?
+                .text()
+                .context("failed to read artifact server response")?;
+
+            Version::parse(version.trim().trim_start_matches('v'))
+                .context("artifact server returned an invalid version")
+                .map(Some)
+        }
+        VersionSource::Artifacts {
+            latest_url: None, ..
+        }
+        | VersionSource::Disabled => Ok(None),
+    }
+}
 
+/// Returns the latest version, fetching it if the cached one is older than the check interval
+pub fn refresh_latest_version(
+    reqwest: &reqwest::blocking::Client,
+) -> anyhow::Result<Option<Version>> {
     let config = read_config()?;
 
-    let version = if let Some((_, version)) = config
+    if let Some((_, version)) = config
         .last_checked_updates
+        .clone()
         .filter(|(time, _)| chrono::Utc::now() - *time < CHECK_INTERVAL)
     {
-        version
-    } else {
-        let releases = reqwest
-            .get(format!(
-                "https://api.github.com/repos/{owner}/{repo}/releases",
-            ))
-            .send()
-            .context("failed to send request to GitHub API")?
-            .error_for_status()
-            .context("failed to get GitHub API response")?
-            .json::<Vec<Release>>()
-            .context("failed to parse GitHub API response")?;
-
-        let version = releases
-            .into_iter()
-            .map(|release| Version::parse(release.tag_name.trim_start_matches('v')).unwrap())
-            .max()
-            .context("failed to find latest version")?;
-
-        write_config(&CliConfig {
-            last_checked_updates: Some((chrono::Utc::now(), version.clone())),
-            ..config
-        })?;
-
-        version
+        return Ok(Some(version));
+    }
+
+    let Some(version) = fetch_latest_version(reqwest, &config.version_source)? else {
+        log::debug!("update checks are disabled for the configured version source");
+        return Ok(None);
     };
 
-    if version > current_version() {
-        let name = env!("CARGO_PKG_NAME");
+    write_config(&CliConfig {
+        last_checked_updates: Some((chrono::Utc::now(), version.clone())),
+        ..config
+    })?;
+
+    Ok(Some(version))
+}
+
+fn notify_of_update(version: &Version) {
+    if *version <= current_version() {
+        return;
+    }
 
-        let unformatted_message = format!("a new version of {name} is available: {version}");
+    let name = env!("CARGO_PKG_NAME");
 
-        let message = format!(
-            "a new version of {} is available: {}",
-            name.cyan(),
-            version.to_string().yellow().bold()
-        );
+    let unformatted_message = format!("a new version of {name} is available: {version}");
 
-        let stars = "-"
-            .repeat(unformatted_message.len() + 4)
-            .bright_magenta()
-            .bold();
-        let column = "|".bright_magenta().bold();
+    let message = format!(
+        "a new version of {} is available: {}",
+        name.cyan(),
+        version.to_string().yellow().bold()
+    );
 
-        println!("\n{stars}\n{column} {message} {column}\n{stars}\n");
+    let stars = "-"
+        .repeat(unformatted_message.len() + 4)
+        .bright_magenta()
+        .bold();
+    let column = "|".bright_magenta().bold();
+
+    println!("\n{stars}\n{column} {message} {column}\n{stars}\n");
+}
+
+pub fn check_for_updates(reqwest: &reqwest::blocking::Client) -> anyhow::Result<()> {
+    if let Some(version) = refresh_latest_version(reqwest)? {
+        notify_of_update(&version);
     }
 
     Ok(())
 }
 
-pub fn download_github_release(
+/// Notifies of a new version using only the last version the background helper fetched
+pub fn check_for_cached_updates() -> anyhow::Result<()> {
+    if let Some((_, version)) = read_config()?.last_checked_updates {
+        notify_of_update(&version);
+    }
+
+    Ok(())
+}
+
+fn github_asset_url(
     reqwest: &reqwest::blocking::Client,
     version: &Version,
-) -> anyhow::Result<Vec<u8>> {
+) -> anyhow::Result<url::Url> {
     let (owner, repo) = get_repo();
 
     let release = reqwest
@@ -126,8 +185,35 @@ pub fn download_github_release(
         })
         .context("failed to find asset for current platform")?;
 
+    Ok(asset.url)
+}
+
+fn artifact_url(pattern: &str, version: &Version) -> anyhow::Result<url::Url> {
+    pattern
+        .replace("{version}", &version.to_string())
+        .replace("{os}", std::env::consts::OS)
+        .replace("{arch}", std::env::consts::ARCH)
+        .parse()
+        .context("failed to parse artifact url")
+}
+
+pub fn download_release(
+    reqwest: &reqwest::blocking::Client,
+    source: &VersionSource,
+    version: &Version,
+) -> anyhow::Result<Vec<u8>> {
+    let url = match source {
+        VersionSource::GitHub => github_asset_url(reqwest, version)?,
+        VersionSource::Artifacts { download_url, .. } => artifact_url(download_url, version)?,
+        VersionSource::Disabled => {
+            anyhow::bail!(
+                "version {version} cannot be downloaded as version management is disabled"
+            )
+        }
+    };
+
     let bytes = reqwest
-        .get(asset.url)
+        .get(url)
         .header(ACCEPT, "application/octet-stream")
         .send()
         .context("failed to send request to download asset")?
@@ -139,22 +225,25 @@ pub fn download_github_release(
     let mut decoder = flate2::read::GzDecoder::new(bytes.as_ref());
     let mut archive = tar::Archive::new(&mut decoder);
 
-    let entry = archive
+    let mut entry = archive
         .entries()
         .context("failed to read archive entries")?
         .next()
         .context("archive has no entry")?
         .context("failed to get first archive entry")?;
 
+    let mut bytes = vec![];
     entry
-        .bytes()
-        .collect::<Result<Vec<u8>, std::io::Error>>()
-        .context("failed to read archive entry bytes")
+        .read_to_end(&mut bytes)
+        .context("failed to read archive entry bytes")?;
+
+    Ok(bytes)
 }
 
 pub fn get_or_download_version(
     reqwest: &reqwest::blocking::Client,
     version: &Version,
+    offline: bool,
 ) -> anyhow::Result<Option<PathBuf>> {
     let path = home_dir()?.join("versions");
     create_dir_all(&path).context("failed to create versions directory")?;
@@ -175,7 +264,20 @@ pub fn get_or_download_version(
         std::fs::copy(std::env::current_exe()?, &path)
             .context("failed to copy current executable to version directory")?;
     } else {
-        let bytes = download_github_release(reqwest, version)?;
+        let source = read_config()?.version_source;
+        if offline || source == VersionSource::Disabled {
+            anyhow::bail!(
+                "version {version} is not installed and cannot be downloaded {}. place the binary at {} to use it",
+                if offline {
+                    "in offline mode"
+                } else {
+                    "as version management is disabled"
+                },
+                path.display()
+            );
+        }
+
+        let bytes = download_release(reqwest, &source, version)?;
         std::fs::write(&path, bytes).context("failed to write downloaded version file")?;
     }
 
@@ -196,7 +298,7 @@ pub fn max_installed_version() -> anyhow::Result<Version> {
         .context("failed to read versions directory")?
         .collect::<Result<Vec<_>, _>>()?
         .into_iter()
-        .map(|entry| {
+        .filter_map(|entry| {
             #[cfg(not(windows))]
             let name = entry
                 .path()
@@ -212,7 +314,7 @@ pub fn max_installed_version() -> anyhow::Result<Version> {
                 .to_string_lossy()
                 .to_string();
 
-            Version::parse(&name).unwrap()
+            Version::parse(&name).ok()
         })
         .max()
         .filter(|v| v >= &current_version())
@@ -1,18 +1,57 @@
 use crate::{
+    events::Event,
     lockfile::{DependencyGraph, DownloadedDependencyGraphNode, DownloadedGraph},
-    manifest::DependencyType,
+    manifest::{target::Target, DependencyType},
+    names::PackageNames,
     source::{
+        fs::PackageFS,
+        progress::DownloadProgress,
         traits::{PackageRef, PackageSource},
+        version_id::VersionId,
         PackageSources,
     },
     Project, PACKAGES_CONTAINER_NAME,
 };
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fs::create_dir_all,
-    sync::{mpsc::Receiver, Arc, Mutex},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex, OnceLock,
+    },
 };
 
+type DownloadKey = (PackageNames, VersionId, PackageSources);
+type DownloadCell = Arc<OnceLock<Option<(PackageFS, Target)>>>;
+
+/// The packages downloaded during a run, shared between clones of a project so each package is
+/// only fetched once, even when several graphs containing it are downloaded. A download which is
+/// in flight is waited for instead of being started again
+#[derive(Debug, Default, Clone)]
+pub struct DownloadCache(Arc<Mutex<HashMap<DownloadKey, DownloadCell>>>);
+
+impl DownloadCache {
+    /// Removes all cached downloads, forcing packages to be downloaded again
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+
+    fn cell(
+        &self,
+        name: &PackageNames,
+        version_id: &VersionId,
+        source: &PackageSources,
+    ) -> DownloadCell {
+        self.0
+            .lock()
+            .unwrap()
+            .entry((name.clone(), version_id.clone(), source.clone()))
+            .or_default()
+            .clone()
+    }
+}
+
 type MultithreadedGraph = Arc<Mutex<DownloadedGraph>>;
 
 type MultithreadDownloadJob = (
@@ -39,10 +78,23 @@ impl Project {
 
         for (name, versions) in graph {
             for (version_id, node) in versions {
+                // every package still reports a result, so receivers can count them
+                if self.cancelled() {
+                    tx.send(Err(errors::DownloadGraphError::Cancelled)).unwrap();
+                    continue;
+                }
+
                 let source = node.pkg_ref.source();
 
                 if refreshed_sources.insert(source.clone()) {
-                    source.refresh(self).map_err(Box::new)?;
+                    source.refresh(self).map_err(|e| {
+                        errors::DownloadGraphError::Package(
+                            name.clone(),
+                            version_id.clone(),
+                            Box::new(source.clone()),
+                            Box::new(Box::new(e).into()),
+                        )
+                    })?;
                 }
 
                 let container_folder = node.container_folder(
@@ -74,25 +126,82 @@ impl Project {
                 threadpool.execute(move || {
                     let project = project.clone();
 
+                    if project.cancelled() {
+                        tx.send(Err(errors::DownloadGraphError::Cancelled)).unwrap();
+                        return;
+                    }
+
+                    let fail = |e: errors::DownloadGraphError| {
+                        tx.send(Err(errors::DownloadGraphError::Package(
+                            name.clone(),
+                            version_id.clone(),
+                            Box::new(source.clone()),
+                            Box::new(e),
+                        )))
+                        .unwrap();
+                    };
+
                     log::debug!("downloading {name}@{version_id}");
+                    project.emit(|| Event::DownloadStarted {
+                        name: name.clone(),
+                        version_id: version_id.clone(),
+                    });
+
+                    // count the bytes received for this package, keeping the existing callback
+                    let received = Arc::new(AtomicU64::new(0));
+                    let project = match project.event_handler() {
+                        Some(_) => Arc::new(project.as_ref().clone().with_download_progress(Some(
+                            DownloadProgress::new({
+                                let received = received.clone();
+                                let progress = project.download_progress().cloned();
+                                move |update| {
+                                    received.fetch_add(update.received, Ordering::Relaxed);
+                                    if let Some(progress) = &progress {
+                                        progress.report(update);
+                                    }
+                                }
+                            }),
+                        ))),
+                        None => project,
+                    };
 
-                    let (fs, target) = match source.download(&node.pkg_ref, &project, &reqwest) {
-                        Ok(target) => target,
-                        Err(e) => {
-                            tx.send(Err(Box::new(e).into())).unwrap();
+                    let cell = project.download_cache().cell(&name, &version_id, &source);
+                    let mut error = None;
+                    let downloaded = cell.get_or_init(|| {
+                        match source.download(&node.pkg_ref, &project, &reqwest) {
+                            Ok(downloaded) => Some(downloaded),
+                            Err(e) => {
+                                error = Some(e);
+                                None
+                            }
+                        }
+                    });
+
+                    let (fs, target) = match (downloaded, error) {
+                        (Some(downloaded), _) => {
+                            log::debug!("downloaded {name}@{version_id}");
+                            downloaded.clone()
+                        }
+                        (None, Some(e)) => {
+                            fail(Box::new(e).into());
                             return;
                         }
+                        // another download of the package failed, so try again to get the error
+                        (None, None) => match source.download(&node.pkg_ref, &project, &reqwest) {
+                            Ok(downloaded) => downloaded,
+                            Err(e) => {
+                                fail(Box::new(e).into());
+                                return;
+                            }
+                        },
                     };
 
-                    log::debug!("downloaded {name}@{version_id}");
-
                     if write {
                         if !prod || node.ty != DependencyType::Dev {
                             match fs.write_to(container_folder, project.cas_dir(), true) {
                                 Ok(_) => {}
                                 Err(e) => {
-                                    tx.send(Err(errors::DownloadGraphError::WriteFailed(e)))
-                                        .unwrap();
+                                    fail(errors::DownloadGraphError::WriteFailed(e));
                                     return;
                                 }
                             };
@@ -101,11 +210,21 @@ impl Project {
                         }
                     }
 
-                    let mut downloaded_graph = downloaded_graph.lock().unwrap();
+                    project.emit(|| Event::PackageDownloaded {
+                        name: name.clone(),
+                        version_id: version_id.clone(),
+                        bytes: received.load(Ordering::Relaxed),
+                    });
+
                     downloaded_graph
+                        .lock()
+                        .unwrap()
                         .entry(name)
                         .or_default()
                         .insert(version_id, DownloadedDependencyGraphNode { node, target });
+                    // the graph must be released before the last result is sent, as receivers
+                    // take it out of its `Arc` once every job is done
+                    drop(downloaded_graph);
 
                     tx.send(Ok(())).unwrap();
                 });
@@ -118,6 +237,10 @@ impl Project {
 
 /// Errors that can occur when downloading a graph
 pub mod errors {
+    use crate::{
+        names::PackageNames,
+        source::{version_id::VersionId, PackageSources},
+    };
     use thiserror::Error;
 
     /// Errors that can occur when downloading a graph
@@ -143,5 +266,29 @@ pub mod errors {
         /// Error writing package contents
         #[error("failed to write package contents")]
         WriteFailed(std::io::Error),
+
+        /// The operation was cancelled
+        #[error("downloading was cancelled")]
+        Cancelled,
+
+        /// An error occurred downloading a specific package from the given source
+        #[error("failed to download {0}@{1} from {2}")]
+        Package(
+            PackageNames,
+            VersionId,
+            Box<PackageSources>,
+            #[source] Box<DownloadGraphError>,
+        ),
+    }
+
+    impl DownloadGraphError {
+        /// The package which could not be downloaded because it requires network access in offline mode, if that is the cause
+        pub fn offline_package(&self) -> Option<&str> {
+            match self {
+                DownloadGraphError::DownloadFailed(e) => e.offline_package(),
+                DownloadGraphError::Package(_, _, _, e) => e.offline_package(),
+                _ => None,
+            }
+        }
     }
 }
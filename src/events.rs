@@ -0,0 +1,94 @@
+use crate::{names::PackageNames, source::version_id::VersionId};
+use relative_path::RelativePathBuf;
+use std::{
+    fmt::{Debug, Formatter},
+    path::PathBuf,
+    sync::Arc,
+};
+
+/// An event emitted while resolving, downloading, patching, or linking a project's dependencies
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum Event {
+    /// Resolving the dependency graph of the package in the directory started
+    ResolveStarted {
+        /// The directory of the package
+        package_dir: PathBuf,
+    },
+    /// A package was resolved from its source, instead of being kept from the previous graph
+    PackageResolved {
+        /// The name of the package
+        name: PackageNames,
+        /// The version of the package
+        version_id: VersionId,
+    },
+    /// Resolving the dependency graph of the package in the directory finished
+    ResolveFinished {
+        /// The directory of the package
+        package_dir: PathBuf,
+        /// The number of packages in the dependency graph
+        packages: usize,
+    },
+    /// Downloading a package started
+    DownloadStarted {
+        /// The name of the package
+        name: PackageNames,
+        /// The version of the package
+        version_id: VersionId,
+    },
+    /// A file of a package being downloaded was skipped, as it is never installed. Only emitted
+    /// when the package isn't already stored locally
+    FileSkipped {
+        /// The package, described by its name and version, or its repository for Git packages
+        package: String,
+        /// The path of the file or directory within the package
+        path: RelativePathBuf,
+    },
+    /// A package was downloaded
+    PackageDownloaded {
+        /// The name of the package
+        name: PackageNames,
+        /// The version of the package
+        version_id: VersionId,
+        /// The number of bytes received for the package, which is 0 if it was already downloaded
+        /// during this run or is stored locally
+        bytes: u64,
+    },
+    /// A patch was applied to a package
+    PatchApplied {
+        /// The name of the package
+        name: PackageNames,
+        /// The version of the package
+        version_id: VersionId,
+    },
+    /// The dependencies of a package were linked
+    Linked {
+        /// The name of the package
+        name: PackageNames,
+        /// The version of the package
+        version_id: VersionId,
+    },
+}
+
+/// A callback receiving the events of a project, for example to render progress. It may be called
+/// from multiple threads
+#[derive(Clone)]
+pub struct EventHandler(Arc<dyn Fn(&Event) + Send + Sync>);
+
+impl EventHandler {
+    /// Create a new `EventHandler` from a callback
+    pub fn new<F: Fn(&Event) + Send + Sync + 'static>(callback: F) -> Self {
+        EventHandler(Arc::new(callback))
+    }
+
+    /// Calls the callback with the event
+    pub fn emit(&self, event: &Event) {
+        (self.0)(event)
+    }
+}
+
+impl Debug for EventHandler {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EventHandler")
+    }
+}
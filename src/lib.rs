@@ -3,15 +3,41 @@
 //! pesde has its own registry, however it can also use Wally, and Git repositories as package sources.
 //! It has been designed with multiple targets in mind, namely Roblox, Lune, and Luau.
 
-use crate::{lockfile::Lockfile, manifest::Manifest};
+use crate::{
+    cancellation::CancellationToken,
+    download::DownloadCache,
+    events::{Event, EventHandler},
+    lockfile::{Lockfile, Pins},
+    manifest::{
+        migration::{
+            parse_legacy_manifest, parse_manifest, parse_manifest_with_migrations,
+            ManifestMigration, LEGACY_MANIFEST_FILE_NAME,
+        },
+        Manifest,
+    },
+    scripts::ScriptPolicy,
+    source::{
+        network::NetworkLimiter,
+        pesde::{mirrors::MirrorLatencies, TrustPolicy},
+        progress::DownloadProgress,
+        retry::RetryPolicy,
+    },
+    workspace::{glob_base, mtime, WorkspaceMembersCache},
+};
 use gix::sec::identity::Account;
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     path::{Path, PathBuf},
 };
 
+/// Checking packages against security advisories
+pub mod audit;
+/// Cancelling operations
+pub mod cancellation;
 /// Downloading packages
 pub mod download;
+/// Events emitted while installing
+pub mod events;
 /// Linking packages
 pub mod linking;
 /// Lockfile
@@ -23,6 +49,11 @@ pub mod names;
 /// Patching packages
 #[cfg(feature = "patches")]
 pub mod patches;
+/// Packing and publishing packages
+pub mod publish;
+/// A typed client for the registry API
+#[cfg(feature = "registry-client")]
+pub mod registry_client;
 /// Resolving packages
 pub mod resolver;
 /// Running scripts
@@ -30,11 +61,18 @@ pub mod scripts;
 /// Package sources
 pub mod source;
 pub(crate) mod util;
+pub use util::tempdirs;
+/// Workspace graph
+pub mod workspace;
 
 /// The name of the manifest file
 pub const MANIFEST_FILE_NAME: &str = "pesde.toml";
 /// The name of the lockfile
 pub const LOCKFILE_FILE_NAME: &str = "pesde.lock";
+/// The comment written at the top of the lockfile
+const LOCKFILE_HEADER: &str = "# This file is automatically generated by pesde.\n# It is not intended for manual editing.\n\n";
+/// The name of the pins file
+pub const PINS_FILE_NAME: &str = "pesde.pins.toml";
 /// The name of the default index
 pub const DEFAULT_INDEX_NAME: &str = "default";
 /// The name of the packages container
@@ -46,6 +84,7 @@ pub(crate) const LINK_LIB_NO_FILE_FOUND: &str = "____pesde_no_export_file_found"
 pub struct AuthConfig {
     tokens: HashMap<gix::Url, String>,
     git_credentials: Option<Account>,
+    index_clients: HashMap<gix::Url, reqwest::blocking::Client>,
 }
 
 impl AuthConfig {
@@ -72,11 +111,25 @@ impl AuthConfig {
         self
     }
 
+    /// Set the HTTP clients to use for specific indices, for example ones with a TLS client certificate
+    pub fn with_index_clients<I: IntoIterator<Item = (gix::Url, reqwest::blocking::Client)>>(
+        mut self,
+        index_clients: I,
+    ) -> Self {
+        self.index_clients = index_clients.into_iter().collect();
+        self
+    }
+
     /// Get the tokens
     pub fn tokens(&self) -> &HashMap<gix::Url, String> {
         &self.tokens
     }
 
+    /// Get the HTTP client to use for the given index, if it has a specific one
+    pub fn index_client(&self, index_url: &gix::Url) -> Option<&reqwest::blocking::Client> {
+        self.index_clients.get(index_url)
+    }
+
     /// Get the git credentials
     pub fn git_credentials(&self) -> Option<&Account> {
         self.git_credentials.as_ref()
@@ -91,6 +144,23 @@ pub struct Project {
     data_dir: PathBuf,
     auth_config: AuthConfig,
     cas_dir: PathBuf,
+    source_replacements: HashMap<gix::Url, gix::Url>,
+    offline: bool,
+    frozen: bool,
+    trust_policy: TrustPolicy,
+    download_progress: Option<DownloadProgress>,
+    event_handler: Option<EventHandler>,
+    cancellation: CancellationToken,
+    mirror_latencies: MirrorLatencies,
+    segmented_downloads: bool,
+    retry_policy: RetryPolicy,
+    lenient_index: bool,
+    features: BTreeSet<String>,
+    hooks: bool,
+    script_policy: ScriptPolicy,
+    network_limiter: NetworkLimiter,
+    workspace_members_cache: WorkspaceMembersCache,
+    download_cache: DownloadCache,
 }
 
 impl Project {
@@ -108,9 +178,145 @@ impl Project {
             data_dir: data_dir.as_ref().to_path_buf(),
             auth_config,
             cas_dir: cas_dir.as_ref().to_path_buf(),
+            source_replacements: HashMap::new(),
+            offline: false,
+            frozen: false,
+            trust_policy: TrustPolicy::default(),
+            download_progress: None,
+            event_handler: None,
+            cancellation: CancellationToken::default(),
+            mirror_latencies: MirrorLatencies::default(),
+            segmented_downloads: false,
+            retry_policy: RetryPolicy::default(),
+            lenient_index: false,
+            features: BTreeSet::new(),
+            hooks: true,
+            script_policy: ScriptPolicy::default(),
+            network_limiter: NetworkLimiter::default(),
+            workspace_members_cache: WorkspaceMembersCache::default(),
+            download_cache: DownloadCache::default(),
         }
     }
 
+    /// Create the project of a member of this project's workspace, in the given directory. The
+    /// member shares this project's settings, caches and cancellation token, but not its download
+    /// progress callback or enabled features
+    pub fn for_member<P: AsRef<Path>>(&self, package_dir: P) -> Self {
+        Project {
+            package_dir: package_dir.as_ref().to_path_buf(),
+            workspace_dir: Some(self.package_dir.clone()),
+            download_progress: None,
+            features: BTreeSet::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Set the mirrors to fetch Git repositories, such as indices, from instead of their URL, keyed
+    /// by the URL they replace. Packages keep referring to the replaced URL, so lockfiles can
+    /// still be shared with machines without the replacements
+    pub fn with_source_replacements<I: IntoIterator<Item = (gix::Url, gix::Url)>>(
+        mut self,
+        source_replacements: I,
+    ) -> Self {
+        self.source_replacements = source_replacements.into_iter().collect();
+        self
+    }
+
+    /// Set whether the project should only use the already cloned indices and the CAS, never accessing the network
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Set whether the indices should never be refreshed, only using their existing clones
+    pub fn with_frozen(mut self, frozen: bool) -> Self {
+        self.frozen = frozen;
+        self
+    }
+
+    /// Set how checksums and signatures of downloaded packages are treated
+    pub fn with_trust_policy(mut self, trust_policy: TrustPolicy) -> Self {
+        self.trust_policy = trust_policy;
+        self
+    }
+
+    /// Set the callback reporting the progress of package downloads
+    pub fn with_download_progress(mut self, download_progress: Option<DownloadProgress>) -> Self {
+        self.download_progress = download_progress;
+        self
+    }
+
+    /// Set the callback receiving the events emitted while installing
+    pub fn with_event_handler(mut self, event_handler: Option<EventHandler>) -> Self {
+        self.event_handler = event_handler;
+        self
+    }
+
+    /// Set the token which cancels the project's operations
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = cancellation;
+        self
+    }
+
+    /// Set whether large archives should be fetched in segments from multiple mirrors in parallel,
+    /// if their index lists mirrors
+    pub fn with_segmented_downloads(mut self, segmented_downloads: bool) -> Self {
+        self.segmented_downloads = segmented_downloads;
+        self
+    }
+
+    /// Set how failed package downloads are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Set whether malformed versions in index files are skipped instead of failing resolution
+    pub fn with_lenient_index(mut self, lenient_index: bool) -> Self {
+        self.lenient_index = lenient_index;
+        self
+    }
+
+    /// Set the features of the manifest whose optional dependencies are installed
+    pub fn with_features(mut self, features: BTreeSet<String>) -> Self {
+        self.features = features;
+        self
+    }
+
+    /// Set whether the manifest's lifecycle hook scripts are run, which should be disabled for
+    /// untrusted projects
+    pub fn with_hooks(mut self, hooks: bool) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// Set which packages the project's scripts may be run for
+    pub fn with_script_policy(mut self, script_policy: ScriptPolicy) -> Self {
+        self.script_policy = script_policy;
+        self
+    }
+
+    /// Set the limits on the network usage of downloads and fetches
+    pub fn with_network_limiter(mut self, network_limiter: NetworkLimiter) -> Self {
+        self.network_limiter = network_limiter;
+        self
+    }
+
+    /// Set the cache of workspace members, to share it with other projects
+    pub fn with_workspace_members_cache(
+        mut self,
+        workspace_members_cache: WorkspaceMembersCache,
+    ) -> Self {
+        self.workspace_members_cache = workspace_members_cache;
+        self
+    }
+
+    /// Set the cache of packages downloaded during this run, to share it with other projects
+    pub fn with_download_cache(mut self, download_cache: DownloadCache) -> Self {
+        self.download_cache = download_cache;
+        self
+    }
+
     /// The directory of the package
     pub fn package_dir(&self) -> &Path {
         &self.package_dir
@@ -136,16 +342,135 @@ impl Project {
         &self.cas_dir
     }
 
+    /// The mirrors Git repositories are fetched from instead of their URL, keyed by the URL they replace
+    pub fn source_replacements(&self) -> &HashMap<gix::Url, gix::Url> {
+        &self.source_replacements
+    }
+
+    /// The mirror the Git repository with the URL is fetched from, if it is replaced
+    pub fn source_replacement(&self, url: &gix::Url) -> Option<&gix::Url> {
+        self.source_replacements.get(url)
+    }
+
+    /// Whether the project is in offline mode
+    pub fn offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Whether the indices are never refreshed
+    pub fn frozen(&self) -> bool {
+        self.frozen
+    }
+
+    /// How checksums and signatures of downloaded packages are treated
+    pub fn trust_policy(&self) -> TrustPolicy {
+        self.trust_policy
+    }
+
+    /// The callback reporting the progress of package downloads
+    pub fn download_progress(&self) -> Option<&DownloadProgress> {
+        self.download_progress.as_ref()
+    }
+
+    /// The callback receiving the events emitted while installing
+    pub fn event_handler(&self) -> Option<&EventHandler> {
+        self.event_handler.as_ref()
+    }
+
+    /// Emits the event to the event handler, only creating it if there is one
+    pub(crate) fn emit(&self, event: impl FnOnce() -> Event) {
+        if let Some(event_handler) = &self.event_handler {
+            event_handler.emit(&event());
+        }
+    }
+
+    /// The token which cancels the project's operations
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancellation
+    }
+
+    /// Whether the project's operations were cancelled
+    pub(crate) fn cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// The latencies of the download mirrors used by this project
+    pub(crate) fn mirror_latencies(&self) -> &MirrorLatencies {
+        &self.mirror_latencies
+    }
+
+    /// Whether large archives are fetched in segments from multiple mirrors in parallel
+    pub fn segmented_downloads(&self) -> bool {
+        self.segmented_downloads
+    }
+
+    /// How failed package downloads are retried
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Whether malformed versions in index files are skipped
+    pub fn lenient_index(&self) -> bool {
+        self.lenient_index
+    }
+
+    /// The features of the manifest whose optional dependencies are installed
+    pub fn features(&self) -> &BTreeSet<String> {
+        &self.features
+    }
+
+    /// Whether the manifest's lifecycle hook scripts are run
+    pub fn hooks(&self) -> bool {
+        self.hooks
+    }
+
+    /// Which packages the project's scripts may be run for
+    pub fn script_policy(&self) -> &ScriptPolicy {
+        &self.script_policy
+    }
+
+    /// The limits on the network usage of downloads and fetches, and their counters
+    pub fn network_limiter(&self) -> &NetworkLimiter {
+        &self.network_limiter
+    }
+
+    /// The cache of workspace members
+    pub fn workspace_members_cache(&self) -> &WorkspaceMembersCache {
+        &self.workspace_members_cache
+    }
+
+    /// The cache of packages downloaded during this run
+    pub fn download_cache(&self) -> &DownloadCache {
+        &self.download_cache
+    }
+
     /// Read the manifest file
     pub fn read_manifest(&self) -> Result<String, errors::ManifestReadError> {
         let string = std::fs::read_to_string(self.package_dir.join(MANIFEST_FILE_NAME))?;
         Ok(string)
     }
 
-    /// Deserialize the manifest file
+    /// Deserialize the manifest file, converting legacy layouts to the current one
     pub fn deser_manifest(&self) -> Result<Manifest, errors::ManifestReadError> {
-        let string = std::fs::read_to_string(self.package_dir.join(MANIFEST_FILE_NAME))?;
-        Ok(toml::from_str(&string)?)
+        self.deser_manifest_with_migrations()
+            .map(|(manifest, _)| manifest)
+    }
+
+    /// Deserialize the manifest file, converting legacy layouts to the current one and returning
+    /// what was converted. The legacy YAML manifest is used if there is no manifest file
+    pub fn deser_manifest_with_migrations(
+        &self,
+    ) -> Result<(Manifest, Vec<ManifestMigration>), errors::ManifestReadError> {
+        match std::fs::read_to_string(self.package_dir.join(MANIFEST_FILE_NAME)) {
+            Ok(string) => Ok(parse_manifest_with_migrations(&string)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                match std::fs::read_to_string(self.package_dir.join(LEGACY_MANIFEST_FILE_NAME)) {
+                    Ok(string) => Ok(parse_legacy_manifest(&string)?),
+                    Err(_) => Err(e.into()),
+                }
+            }
+            Err(e) => Err(e.into()),
+        }
     }
 
     /// Write the manifest file
@@ -161,20 +486,54 @@ impl Project {
 
     /// Write the lockfile
     pub fn write_lockfile(&self, lockfile: Lockfile) -> Result<(), errors::LockfileWriteError> {
-        let string = toml::to_string(&lockfile)?;
+        let string = format!("{LOCKFILE_HEADER}{}", toml::to_string(&lockfile)?);
         std::fs::write(self.package_dir.join(LOCKFILE_FILE_NAME), string)?;
         Ok(())
     }
 
-    /// Get the workspace members
+    /// Deserialize the pins file, returning no pins if it doesn't exist
+    pub fn deser_pins(&self) -> Result<Pins, errors::PinsReadError> {
+        match std::fs::read_to_string(self.package_dir.join(PINS_FILE_NAME)) {
+            Ok(string) => Ok(toml::from_str(&string)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Pins::new()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the pins file, removing it if there are no pins
+    pub fn write_pins(&self, pins: &Pins) -> Result<(), errors::PinsWriteError> {
+        let path = self.package_dir.join(PINS_FILE_NAME);
+
+        if pins.is_empty() {
+            return match std::fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+                _ => Ok(()),
+            };
+        }
+
+        let string = toml::to_string(pins)?;
+        std::fs::write(path, string)?;
+        Ok(())
+    }
+
+    /// Get the workspace members, reusing the cached ones if nothing they were found from changed
     pub fn workspace_members<P: AsRef<Path>>(
         &self,
         dir: P,
     ) -> Result<HashMap<PathBuf, Manifest>, errors::WorkspaceMembersError> {
         let dir = dir.as_ref().to_path_buf();
-        let manifest = std::fs::read_to_string(dir.join(MANIFEST_FILE_NAME))
+
+        if let Some(members) = self.workspace_members_cache.get(&dir) {
+            return Ok(members);
+        }
+
+        // modification times are read before the files, so changes made in between invalidate the cache
+        let manifest_path = dir.join(MANIFEST_FILE_NAME);
+        let mut mtimes = vec![(manifest_path.clone(), mtime(&manifest_path))];
+
+        let manifest = std::fs::read_to_string(&manifest_path)
             .map_err(|e| errors::WorkspaceMembersError::ManifestMissing(dir.to_path_buf(), e))?;
-        let manifest = toml::from_str::<Manifest>(&manifest).map_err(|e| {
+        let manifest = parse_manifest(&manifest).map_err(|e| {
             errors::WorkspaceMembersError::ManifestDeser(dir.to_path_buf(), Box::new(e))
         })?;
 
@@ -182,23 +541,36 @@ impl Project {
             .workspace_members
             .into_iter()
             .map(|glob| dir.join(glob))
+            .inspect(|path| {
+                let base = glob_base(path);
+                let modified = mtime(&base);
+                mtimes.push((base, modified));
+            })
             .map(|path| glob::glob(&path.as_os_str().to_string_lossy()))
             .collect::<Result<Vec<_>, _>>()?
             .into_iter()
             .flat_map(|paths| paths.into_iter())
             .collect::<Result<Vec<_>, _>>()?;
 
-        members
+        let members = members
             .into_iter()
             .map(|path| {
-                let manifest = std::fs::read_to_string(path.join(MANIFEST_FILE_NAME))
+                let manifest_path = path.join(MANIFEST_FILE_NAME);
+                mtimes.push((manifest_path.clone(), mtime(&manifest_path)));
+
+                let manifest = std::fs::read_to_string(&manifest_path)
                     .map_err(|e| errors::WorkspaceMembersError::ManifestMissing(path.clone(), e))?;
-                let manifest = toml::from_str::<Manifest>(&manifest).map_err(|e| {
+                let manifest = parse_manifest(&manifest).map_err(|e| {
                     errors::WorkspaceMembersError::ManifestDeser(path.clone(), Box::new(e))
                 })?;
                 Ok((path, manifest))
             })
-            .collect::<Result<_, _>>()
+            .collect::<Result<HashMap<_, _>, errors::WorkspaceMembersError>>()?;
+
+        self.workspace_members_cache
+            .insert(dir, mtimes, members.clone());
+
+        Ok(members)
     }
 }
 
@@ -233,6 +605,32 @@ pub mod errors {
         Serde(#[from] toml::de::Error),
     }
 
+    /// Errors that can occur when reading the pins file
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum PinsReadError {
+        /// An IO error occurred
+        #[error("io error reading pins file")]
+        Io(#[from] std::io::Error),
+
+        /// An error occurred while deserializing the pins file
+        #[error("error deserializing pins file")]
+        Serde(#[from] toml::de::Error),
+    }
+
+    /// Errors that can occur when writing the pins file
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum PinsWriteError {
+        /// An IO error occurred
+        #[error("io error writing pins file")]
+        Io(#[from] std::io::Error),
+
+        /// An error occurred while serializing the pins file
+        #[error("error serializing pins file")]
+        Serde(#[from] toml::ser::Error),
+    }
+
     /// Errors that can occur when writing the lockfile
     #[derive(Debug, Error)]
     #[non_exhaustive]
@@ -1,8 +1,11 @@
-use std::path::{Component, Path};
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path},
+};
 
 use crate::manifest::{target::TargetKind, Manifest};
 use full_moon::{ast::luau::ExportedTypeDeclaration, visitors::Visitor};
-use relative_path::RelativePathBuf;
+use relative_path::{RelativePath, RelativePathBuf};
 
 struct TypeVisitor {
     types: Vec<String>,
@@ -66,6 +69,16 @@ pub fn generate_lib_linking_module<I: IntoIterator<Item = S>, S: AsRef<str>>(
     output
 }
 
+/// Generate a linking module for a library without exported types, typing it as `any`
+pub fn generate_lib_stub_module(path: &str) -> String {
+    format!("local module = require({path})\nreturn (module :: any)")
+}
+
+/// Generate a stub module for an optional dependency which isn't installed
+pub fn generate_optional_stub_module(alias: &str) -> String {
+    format!("-- {alias} is an optional dependency which isn't installed\nreturn nil\n")
+}
+
 fn luau_style_path(path: &Path) -> String {
     let path = path
         .components()
@@ -156,6 +169,79 @@ return require({require_path})"#,
     )
 }
 
+/// The file name of the linking module of a binary of the dependency with the alias, which is
+/// the bin export if `name` is `None`, or the named bin export otherwise
+pub fn bin_linking_module_name(alias: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) => format!("{alias}.{name}.bin.luau"),
+        None => format!("{alias}.bin.luau"),
+    }
+}
+
+/// The file name of the assets linking module of the dependency with the alias
+pub fn assets_linking_module_name(alias: &str) -> String {
+    format!("{alias}.assets.luau")
+}
+
+/// Generate a linking module for the assets of a package. For Roblox targets it returns the
+/// instance the package is synced into, and otherwise a function returning the absolute path of
+/// an asset
+pub fn generate_assets_linking_module(
+    target: &TargetKind,
+    base_dir: &Path,
+    container_dir: &Path,
+) -> String {
+    if target.has_build_files() {
+        let path = pathdiff::diff_paths(container_dir, base_dir)
+            .unwrap()
+            .components()
+            .filter_map(|component| match component {
+                Component::ParentDir => Some(".Parent".to_string()),
+                Component::Normal(part) => Some(format!("[{:?}]", part.to_string_lossy())),
+                _ => None,
+            })
+            .collect::<String>();
+
+        return format!("return script.Parent{path}\n");
+    }
+
+    format!(
+        r#"local root = {:?}
+return function(path: string): string
+    return root .. "/" .. path
+end
+"#,
+        container_dir.to_string_lossy()
+    )
+}
+
+/// Generate a Rojo project file for a package, syncing its build files into an instance named
+/// after it. A single build file is the instance itself, and multiple ones are its children
+pub fn generate_roblox_sync_config(name: &str, build_files: &BTreeSet<String>) -> String {
+    let tree = match build_files.iter().collect::<Vec<_>>().as_slice() {
+        [build_file] => serde_json::json!({ "$path": build_file }),
+        build_files => {
+            let mut tree = serde_json::Map::new();
+            tree.insert("$className".to_string(), "Folder".into());
+
+            for build_file in build_files {
+                let build_file = RelativePath::new(build_file.as_str());
+                tree.insert(
+                    build_file
+                        .file_stem()
+                        .unwrap_or(build_file.as_str())
+                        .to_string(),
+                    serde_json::json!({ "$path": build_file }),
+                );
+            }
+
+            tree.into()
+        }
+    };
+
+    format!("{:#}\n", serde_json::json!({ "name": name, "tree": tree }))
+}
+
 /// Get the require path for a binary
 pub fn get_bin_require_path(
     base_dir: &Path,
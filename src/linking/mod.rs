@@ -1,20 +1,58 @@
 use crate::{
+    events::Event,
     linking::generator::get_file_types,
     lockfile::DownloadedGraph,
+    manifest::{migration::parse_manifest, target::TargetKind, Exports, Manifest},
     names::PackageNames,
-    scripts::{execute_script, ScriptName},
-    source::{fs::store_in_cas, traits::PackageRef, version_id::VersionId},
-    Project, LINK_LIB_NO_FILE_FOUND, PACKAGES_CONTAINER_NAME,
+    scripts::{execute_script, script_subject, ScriptName},
+    source::{
+        fs::store_in_cas, specifiers::DependencySpecifiers, traits::PackageRef,
+        version_id::VersionId,
+    },
+    util::hash,
+    Project, LINK_LIB_NO_FILE_FOUND, MANIFEST_FILE_NAME, PACKAGES_CONTAINER_NAME,
 };
+use relative_path::{RelativePath, RelativePathBuf};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeMap,
+    collections::{btree_map::Entry, BTreeMap, HashSet},
     ffi::OsStr,
     fs::create_dir_all,
+    io::Write,
     path::{Path, PathBuf},
 };
 
 /// Generates linking modules for a project
 pub mod generator;
+/// Builds sourcemaps of the installed packages
+pub mod sourcemap;
+
+/// The paths of the linking modules of the project's direct dependencies, relative to the project
+/// and keyed by alias, for tools which resolve requires by path
+pub fn require_aliases(
+    manifest: &Manifest,
+    graph: &DownloadedGraph,
+) -> BTreeMap<String, RelativePathBuf> {
+    graph
+        .values()
+        .flat_map(|versions| versions.values())
+        .filter(|node| node.target.lib_path().is_some())
+        .filter_map(|node| {
+            let (alias, _) = node.node.direct.as_ref()?;
+
+            Some((
+                alias.clone(),
+                RelativePathBuf::from(
+                    manifest
+                        .target
+                        .kind()
+                        .packages_folder(&node.node.pkg_ref.target_kind()),
+                )
+                .join(format!("{alias}.luau")),
+            ))
+        })
+        .collect()
+}
 
 fn create_and_canonicalize<P: AsRef<Path>>(path: P) -> std::io::Result<PathBuf> {
     let p = path.as_ref();
@@ -22,21 +60,291 @@ fn create_and_canonicalize<P: AsRef<Path>>(path: P) -> std::io::Result<PathBuf>
     p.canonicalize()
 }
 
-fn write_cas(destination: PathBuf, cas_dir: &Path, contents: &str) -> std::io::Result<()> {
-    let cas_path = store_in_cas(cas_dir, contents.as_bytes())?.1;
+/// Whether the file at the path exists and has the contents with the hash
+fn has_contents(path: &Path, contents_hash: &str) -> std::io::Result<bool> {
+    match std::fs::read(path) {
+        Ok(contents) => Ok(hash(contents) == contents_hash),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Hard links the CAS file to the destination, replacing the file there if there is one
+fn replace_with_cas(cas_path: &Path, destination: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(destination) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
 
     std::fs::hard_link(cas_path, destination)
 }
 
+/// The target of the package a specifier points to, as far as it can be known without resolving it
+fn specifier_target(specifier: &DependencySpecifiers, fallback: TargetKind) -> TargetKind {
+    match specifier {
+        DependencySpecifiers::Pesde(specifier) => specifier.target.unwrap_or(fallback),
+        #[cfg(feature = "wally-compat")]
+        DependencySpecifiers::Wally(_) => TargetKind::Roblox,
+        DependencySpecifiers::Git(_) => fallback,
+        DependencySpecifiers::Workspace(specifier) => specifier.target.unwrap_or(fallback),
+        DependencySpecifiers::Path(_) => fallback,
+    }
+}
+
+/// The folder in the data directory the linking state of each project is stored in
+const LINKING_STATE_FOLDER: &str = "linking";
+
+/// The file the Roblox sync config generator script is expected to create in a package's folder
+pub(crate) const ROBLOX_SYNC_CONFIG_FILE: &str = "default.project.json";
+
+/// A linking module written by a previous link
+#[derive(Debug, Serialize, Deserialize)]
+struct LinkedModule {
+    /// The hash of everything the module was generated from
+    fingerprint: String,
+    /// The CAS hash of the generated module
+    hash: String,
+}
+
+/// The linking modules of a project, keyed by their path
+type LinkingState = BTreeMap<String, LinkedModule>;
+
+/// The library file of a package
+struct Lib {
+    path: PathBuf,
+    contents: String,
+    hash: String,
+}
+
+/// The folder in the CAS the types exported by libraries are cached in, keyed by the library's hash
+const TYPES_CACHE_FOLDER: &str = "types";
+/// Must be bumped whenever the output of [`get_file_types`] changes, invalidating the cached types
+const TYPES_CACHE_VERSION: u32 = 1;
+
+/// The types exported by a library
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedTypes {
+    types: Vec<String>,
+}
+
+/// Gets the types exported by a library, only parsing it if they aren't cached yet
+fn file_types(cas_dir: &Path, lib: &Lib) -> Result<Vec<String>, errors::LinkingError> {
+    let (prefix, rest) = lib.hash.split_at(2);
+    let cache_path = cas_dir
+        .join(TYPES_CACHE_FOLDER)
+        .join(format!("v{TYPES_CACHE_VERSION}"))
+        .join(prefix)
+        .join(rest);
+
+    match std::fs::read_to_string(&cache_path) {
+        Ok(s) => match toml::from_str::<CachedTypes>(&s) {
+            Ok(cached) => return Ok(cached.types),
+            Err(e) => log::warn!(
+                "failed to parse cached types of {}, parsing it again: {e}",
+                lib.path.display()
+            ),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => log::warn!(
+            "failed to read cached types of {}, parsing it again: {e}",
+            lib.path.display()
+        ),
+    }
+
+    let types = get_file_types(&lib.contents)
+        .map_err(|e| errors::LinkingError::FullMoon(lib.path.display().to_string(), e))?;
+
+    log::debug!("{} has {} exported types", lib.path.display(), types.len());
+
+    // the cache only saves parsing the library again, so failing to write it isn't fatal
+    if let Err(e) = write_cached_types(&cache_path, &types) {
+        log::warn!("failed to cache types of {}: {e}", lib.path.display());
+    }
+
+    Ok(types)
+}
+
+/// Writes the cached types through a temporary file, so concurrent links never read a partially
+/// written cache
+fn write_cached_types(cache_path: &Path, types: &[String]) -> std::io::Result<()> {
+    let contents = toml::to_string(&CachedTypes {
+        types: types.to_vec(),
+    })
+    .map_err(std::io::Error::other)?;
+
+    let parent = cache_path.parent().unwrap();
+    create_dir_all(parent)?;
+
+    let mut file = tempfile::NamedTempFile::new_in(parent)?;
+    file.write_all(contents.as_bytes())?;
+    file.persist(cache_path)?;
+
+    Ok(())
+}
+
+/// Writes linking modules, leaving the modules of the previous link whose inputs haven't changed
+/// untouched instead of parsing the library and generating them again. Unchanged modules keep
+/// their modification time, so tools watching the packages folders aren't triggered
+struct ModuleLinker<'a> {
+    cas_dir: &'a Path,
+    previous: LinkingState,
+    current: LinkingState,
+    types: BTreeMap<PathBuf, Vec<String>>,
+    type_stubs: bool,
+    summary: LinkingSummary,
+}
+
+impl ModuleLinker<'_> {
+    /// Reuses the module at the destination if it was generated from the same inputs and wasn't
+    /// modified since, hard linking it from the CAS again if it is missing
+    fn reuse(
+        &mut self,
+        destination: &Path,
+        fingerprint: &str,
+    ) -> Result<bool, errors::LinkingError> {
+        let key = destination.to_string_lossy().to_string();
+
+        let Some(module) = self
+            .previous
+            .remove(&key)
+            .filter(|module| module.fingerprint == fingerprint)
+        else {
+            return Ok(false);
+        };
+
+        if has_contents(destination, &module.hash)? {
+            self.current.insert(key, module);
+            self.summary.reused += 1;
+            return Ok(true);
+        }
+
+        let (prefix, rest) = module.hash.split_at(2);
+        match replace_with_cas(&self.cas_dir.join(prefix).join(rest), destination) {
+            Ok(()) => {
+                self.current.insert(key, module);
+                self.summary.refreshed += 1;
+                Ok(true)
+            }
+            // the module was removed from the CAS, so it has to be generated again
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Writes the module to the destination, unless it already has its contents
+    fn write(
+        &mut self,
+        destination: &Path,
+        fingerprint: String,
+        contents: &str,
+    ) -> Result<(), errors::LinkingError> {
+        let (hash, cas_path) = store_in_cas(self.cas_dir, contents.as_bytes())?;
+
+        if has_contents(destination, &hash)? {
+            self.summary.reused += 1;
+        } else {
+            replace_with_cas(&cas_path, destination)?;
+            self.summary.refreshed += 1;
+        }
+
+        self.current.insert(
+            destination.to_string_lossy().to_string(),
+            LinkedModule { fingerprint, hash },
+        );
+
+        Ok(())
+    }
+
+    /// Writes a module whose contents are already known
+    fn write_generated(
+        &mut self,
+        destination: PathBuf,
+        contents: &str,
+    ) -> Result<(), errors::LinkingError> {
+        self.write(&destination, hash(contents), contents)
+    }
+
+    /// Writes the library linking module of a package
+    fn link(
+        &mut self,
+        destination: PathBuf,
+        require_path: &str,
+        lib: Option<&Lib>,
+    ) -> Result<(), errors::LinkingError> {
+        let fingerprint = hash(format!(
+            "{}\n{}\n{require_path}\n{}",
+            env!("CARGO_PKG_VERSION"),
+            lib.map_or("", |lib| lib.hash.as_str()),
+            self.type_stubs,
+        ));
+        if self.reuse(&destination, &fingerprint)? {
+            return Ok(());
+        }
+
+        let types = match lib {
+            Some(lib) => match self.types.entry(lib.path.clone()) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(file_types(self.cas_dir, lib)?),
+            },
+            None => &vec![],
+        };
+
+        let contents = if types.is_empty() && self.type_stubs {
+            generator::generate_lib_stub_module(require_path)
+        } else {
+            generator::generate_lib_linking_module(require_path, types)
+        };
+
+        self.write(&destination, fingerprint, &contents)
+    }
+
+    /// Removes the modules of the previous link which weren't linked again
+    fn remove_stale(&mut self) -> Result<(), errors::LinkingError> {
+        for path in std::mem::take(&mut self.previous).into_keys() {
+            if self.current.contains_key(&path) {
+                continue;
+            }
+
+            match std::fs::remove_file(&path) {
+                Ok(()) => self.summary.removed += 1,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// How many linking modules a link wrote, left untouched, and removed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LinkingSummary {
+    /// Modules which were written because they were missing or their contents changed
+    pub refreshed: usize,
+    /// Modules which already had the right contents, and were left untouched
+    pub reused: usize,
+    /// Modules of the previous link which are no longer needed
+    pub removed: usize,
+}
+
 impl Project {
-    /// Links the dependencies of the project
-    pub fn link_dependencies(&self, graph: &DownloadedGraph) -> Result<(), errors::LinkingError> {
+    /// Links the dependencies of the project. Linking again without changes leaves the linking
+    /// modules untouched
+    pub fn link_dependencies(
+        &self,
+        graph: &DownloadedGraph,
+    ) -> Result<LinkingSummary, errors::LinkingError> {
         let manifest = self.deser_manifest()?;
 
-        let mut package_types = BTreeMap::<&PackageNames, BTreeMap<&VersionId, Vec<String>>>::new();
+        let mut libs = BTreeMap::<&PackageNames, BTreeMap<&VersionId, Option<Lib>>>::new();
 
         for (name, versions) in graph {
             for (version_id, node) in versions {
+                if self.cancelled() {
+                    return Err(errors::LinkingError::Cancelled);
+                }
+
                 let Some(lib_file) = node.target.lib_path() else {
                     continue;
                 };
@@ -55,7 +363,7 @@ impl Project {
                     version_id.version(),
                 );
 
-                let types = if lib_file.as_str() != LINK_LIB_NO_FILE_FOUND {
+                let lib = if lib_file.as_str() != LINK_LIB_NO_FILE_FOUND {
                     let lib_file = lib_file.to_path(&container_folder);
 
                     let contents = match std::fs::read_to_string(&lib_file) {
@@ -68,27 +376,16 @@ impl Project {
                         Err(e) => return Err(e.into()),
                     };
 
-                    let types = match get_file_types(&contents) {
-                        Ok(types) => types,
-                        Err(e) => {
-                            return Err(errors::LinkingError::FullMoon(
-                                lib_file.display().to_string(),
-                                e,
-                            ))
-                        }
-                    };
-
-                    log::debug!("{name}@{version_id} has {} exported types", types.len());
-
-                    types
+                    Some(Lib {
+                        hash: hash(&contents),
+                        path: lib_file,
+                        contents,
+                    })
                 } else {
-                    vec![]
+                    None
                 };
 
-                package_types
-                    .entry(name)
-                    .or_default()
-                    .insert(version_id, types);
+                libs.entry(name).or_default().insert(version_id, lib);
 
                 if let Some(build_files) = Some(&node.target)
                     .filter(|_| !node.node.pkg_ref.like_wally())
@@ -97,13 +394,37 @@ impl Project {
                     let script_name = ScriptName::RobloxSyncConfigGenerator.to_string();
 
                     let Some(script_path) = manifest.scripts.get(&script_name) else {
-                        log::warn!("not having a `{script_name}` script in the manifest might cause issues with Roblox linking");
+                        log::debug!("no `{script_name}` script in the manifest, generating the sync config of {name}@{version_id}");
+
+                        let config_path = container_folder.join(ROBLOX_SYNC_CONFIG_FILE);
+                        // the package's files may be hard links into the CAS, which mustn't be
+                        // written through
+                        match std::fs::remove_file(&config_path) {
+                            Ok(()) => {}
+                            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                            Err(e) => return Err(e.into()),
+                        }
+                        std::fs::write(
+                            &config_path,
+                            generator::generate_roblox_sync_config(name.as_str().1, build_files),
+                        )?;
+
+                        validate_roblox_sync_config(&container_folder).map_err(|e| {
+                            errors::LinkingError::InvalidRobloxSyncConfig(
+                                format!("{name}@{version_id}"),
+                                e,
+                            )
+                        })?;
+
                         continue;
                     };
 
+                    let subject = script_subject(name, &node.node.pkg_ref);
+
                     execute_script(
                         ScriptName::RobloxSyncConfigGenerator,
                         &script_path.to_path(self.package_dir()),
+                        &subject,
                         std::iter::once(container_folder.as_os_str())
                             .chain(build_files.iter().map(OsStr::new)),
                         self,
@@ -115,12 +436,43 @@ impl Project {
                             e,
                         )
                     })?;
+
+                    // a broken config would otherwise only surface once a syncing tool reads it
+                    if self.script_policy().allows(&subject) {
+                        validate_roblox_sync_config(&container_folder).map_err(|e| {
+                            errors::LinkingError::InvalidRobloxSyncConfig(
+                                format!("{name}@{version_id}"),
+                                e,
+                            )
+                        })?;
+                    }
                 }
             }
         }
 
+        let state_path = self.linking_state_path();
+        let mut linker = ModuleLinker {
+            cas_dir: self.cas_dir(),
+            previous: match std::fs::read_to_string(&state_path) {
+                Ok(s) => toml::from_str(&s).unwrap_or_else(|e| {
+                    log::warn!("failed to parse linking state, relinking everything: {e}");
+                    LinkingState::new()
+                }),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => LinkingState::new(),
+                Err(e) => return Err(e.into()),
+            },
+            current: LinkingState::new(),
+            types: BTreeMap::new(),
+            type_stubs: manifest.type_stubs,
+            summary: LinkingSummary::default(),
+        };
+
         for (name, versions) in graph {
             for (version_id, node) in versions {
+                if self.cancelled() {
+                    return Err(errors::LinkingError::Cancelled);
+                }
+
                 let (node_container_folder, node_packages_folder) = {
                     let base_folder = create_and_canonicalize(
                         self.package_dir().join(
@@ -139,37 +491,40 @@ impl Project {
                     );
 
                     if let Some((alias, _)) = &node.node.direct.as_ref() {
-                        if let Some((lib_file, types)) =
-                            node.target.lib_path().and_then(|lib_file| {
-                                package_types
-                                    .get(name)
-                                    .and_then(|v| v.get(version_id))
-                                    .map(|types| (lib_file, types))
-                            })
-                        {
-                            write_cas(
+                        if let Some((lib_file, lib)) = node.target.lib_path().and_then(|lib_file| {
+                            libs.get(name)
+                                .and_then(|v| v.get(version_id))
+                                .map(|lib| (lib_file, lib))
+                        }) {
+                            linker.link(
                                 base_folder.join(format!("{alias}.luau")),
-                                self.cas_dir(),
-                                &generator::generate_lib_linking_module(
-                                    &generator::get_lib_require_path(
-                                        &node.target.kind(),
-                                        &base_folder,
-                                        lib_file,
-                                        &container_folder,
-                                        node.node.pkg_ref.use_new_structure(),
-                                        &base_folder,
-                                        container_folder.strip_prefix(&base_folder).unwrap(),
-                                        &manifest,
-                                    )?,
-                                    types,
-                                ),
+                                &generator::get_lib_require_path(
+                                    &node.target.kind(),
+                                    &base_folder,
+                                    lib_file,
+                                    &container_folder,
+                                    node.node.pkg_ref.use_new_structure(),
+                                    &base_folder,
+                                    container_folder.strip_prefix(&base_folder).unwrap(),
+                                    &manifest,
+                                )?,
+                                lib.as_ref(),
                             )?;
                         };
 
-                        if let Some(bin_file) = node.target.bin_path() {
-                            write_cas(
-                                base_folder.join(format!("{alias}.bin.luau")),
-                                self.cas_dir(),
+                        for (bin_name, bin_file) in node
+                            .target
+                            .bin_path()
+                            .map(|bin_file| (None, bin_file))
+                            .into_iter()
+                            .chain(node.target.named_bins().into_iter().flat_map(|bins| {
+                                bins.iter()
+                                    .map(|(bin_name, bin_file)| (Some(bin_name.as_str()), bin_file))
+                            }))
+                        {
+                            linker.write_generated(
+                                base_folder
+                                    .join(generator::bin_linking_module_name(alias, bin_name)),
                                 &generator::generate_bin_linking_module(
                                     &container_folder,
                                     &generator::get_bin_require_path(
@@ -180,6 +535,19 @@ impl Project {
                                 ),
                             )?;
                         }
+
+                        if !node.node.pkg_ref.like_wally()
+                            && !package_exports(&container_folder)?.assets.is_empty()
+                        {
+                            linker.write_generated(
+                                base_folder.join(generator::assets_linking_module_name(alias)),
+                                &generator::generate_assets_linking_module(
+                                    &node.target.kind(),
+                                    &base_folder,
+                                    &container_folder,
+                                ),
+                            )?;
+                        }
                     }
 
                     (container_folder, base_folder)
@@ -223,32 +591,296 @@ impl Project {
                             .join(node.node.base_folder(dependency_node.target.kind())),
                     )?;
 
-                    write_cas(
+                    linker.link(
                         linker_folder.join(format!("{dependency_alias}.luau")),
-                        self.cas_dir(),
-                        &generator::generate_lib_linking_module(
-                            &generator::get_lib_require_path(
-                                &dependency_node.target.kind(),
-                                &linker_folder,
-                                lib_file,
-                                &container_folder,
-                                dependency_node.node.pkg_ref.use_new_structure(),
-                                &node_packages_folder,
-                                container_folder.strip_prefix(&base_folder).unwrap(),
-                                &manifest,
-                            )?,
-                            package_types
-                                .get(dependency_name)
-                                .and_then(|v| v.get(dependency_version_id))
-                                .unwrap(),
+                        &generator::get_lib_require_path(
+                            &dependency_node.target.kind(),
+                            &linker_folder,
+                            lib_file,
+                            &container_folder,
+                            dependency_node.node.pkg_ref.use_new_structure(),
+                            &node_packages_folder,
+                            container_folder.strip_prefix(&base_folder).unwrap(),
+                            &manifest,
+                        )?,
+                        libs.get(dependency_name)
+                            .and_then(|v| v.get(dependency_version_id))
+                            .unwrap()
+                            .as_ref(),
+                    )?;
+                }
+
+                let linked_aliases = node
+                    .node
+                    .dependencies
+                    .values()
+                    .map(|(_, alias)| alias)
+                    .collect::<HashSet<_>>();
+
+                for (alias, (specifier, _)) in node.node.pkg_ref.dependencies() {
+                    if !specifier.is_optional() || linked_aliases.contains(alias) {
+                        continue;
+                    }
+
+                    let linker_folder = create_and_canonicalize(
+                        node_container_folder.join(
+                            node.node
+                                .base_folder(specifier_target(specifier, node.target.kind())),
                         ),
                     )?;
+
+                    linker.write_generated(
+                        linker_folder.join(format!("{alias}.luau")),
+                        &generator::generate_optional_stub_module(alias),
+                    )?;
                 }
+
+                self.emit(|| Event::Linked {
+                    name: name.clone(),
+                    version_id: version_id.clone(),
+                });
             }
         }
 
+        let installed_aliases = graph
+            .values()
+            .flat_map(|versions| versions.values())
+            .filter_map(|node| node.node.direct.as_ref().map(|(alias, _)| alias))
+            .collect::<HashSet<_>>();
+
+        for (alias, (specifier, _)) in manifest.all_dependencies()? {
+            if !specifier.is_optional() || installed_aliases.contains(&alias) {
+                continue;
+            }
+
+            log::debug!("linking stub for optional dependency {alias}");
+
+            let base_folder = create_and_canonicalize(
+                self.package_dir().join(
+                    manifest
+                        .target
+                        .kind()
+                        .packages_folder(&specifier_target(&specifier, manifest.target.kind())),
+                ),
+            )?;
+
+            linker.write_generated(
+                base_folder.join(format!("{alias}.luau")),
+                &generator::generate_optional_stub_module(&alias),
+            )?;
+        }
+
+        linker.remove_stale()?;
+
+        log::debug!(
+            "refreshed {} and reused {} linking modules, removed {} stale ones",
+            linker.summary.refreshed,
+            linker.summary.reused,
+            linker.summary.removed,
+        );
+
+        if let Some(parent) = state_path.parent() {
+            create_dir_all(parent)?;
+        }
+        std::fs::write(&state_path, toml::to_string(&linker.current)?)?;
+
+        if let Some(path) = &manifest.rojo_project {
+            if manifest.target.build_files().is_some() {
+                self.write_rojo_project(&manifest, graph, path)?;
+            } else {
+                log::warn!("rojo projects are only written for Roblox projects");
+            }
+        }
+
+        Ok(linker.summary)
+    }
+
+    /// Maps the packages folders of the dependencies into the game in the Rojo project at the
+    /// path, creating it if it doesn't exist. Shared packages are synced into
+    /// `ReplicatedStorage.Packages`, and server ones into `ServerScriptService.ServerPackages`
+    fn write_rojo_project(
+        &self,
+        manifest: &Manifest,
+        graph: &DownloadedGraph,
+        path: &RelativePath,
+    ) -> Result<(), errors::LinkingError> {
+        let project_path = path.to_path(self.package_dir());
+
+        let contents = match std::fs::read_to_string(&project_path) {
+            Ok(contents) => Some(contents),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        // keys keep their order, as serde_json is built with `preserve_order`
+        let previous = contents
+            .as_deref()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .transpose()
+            .map_err(|e| {
+                errors::LinkingError::RojoProject(project_path.display().to_string(), e)
+            })?;
+
+        let mut project = previous.clone().unwrap_or_else(|| {
+            serde_json::json!({
+                "name": manifest.name.as_str().1,
+                "tree": { "$className": "DataModel" },
+            })
+        });
+
+        let Some(tree) = project
+            .get_mut("tree")
+            .and_then(|tree| tree.as_object_mut())
+        else {
+            return Err(errors::LinkingError::InvalidRojoProject(
+                project_path.display().to_string(),
+            ));
+        };
+
+        let used_kinds = graph
+            .values()
+            .flat_map(|versions| versions.values())
+            .map(|node| node.node.pkg_ref.target_kind())
+            .collect::<HashSet<_>>();
+        let project_dir = path.parent().unwrap_or(RelativePath::new(""));
+
+        for (kind, service, folder) in [
+            (TargetKind::Roblox, "ReplicatedStorage", "Packages"),
+            (
+                TargetKind::RobloxServer,
+                "ServerScriptService",
+                "ServerPackages",
+            ),
+        ] {
+            if !used_kinds.contains(&kind) {
+                if let Some(service) = tree
+                    .get_mut(service)
+                    .and_then(|service| service.as_object_mut())
+                {
+                    service.remove(folder);
+                }
+
+                continue;
+            }
+
+            let packages_folder =
+                project_dir.relative(manifest.target.kind().packages_folder(&kind));
+
+            let Some(service) = tree
+                .entry(service)
+                .or_insert_with(|| serde_json::json!({ "$className": service }))
+                .as_object_mut()
+            else {
+                return Err(errors::LinkingError::InvalidRojoProject(
+                    project_path.display().to_string(),
+                ));
+            };
+
+            service.insert(
+                folder.to_string(),
+                serde_json::json!({ "$path": packages_folder }),
+            );
+        }
+
+        // rewriting an unchanged project would needlessly restart syncing tools watching it
+        if previous.as_ref() != Some(&project) {
+            if let Some(parent) = project_path.parent() {
+                create_dir_all(parent)?;
+            }
+            std::fs::write(&project_path, to_json_like(&project, contents.as_deref()))?;
+        }
+
         Ok(())
     }
+
+    /// The path of the file storing which linking modules were written by the last link
+    fn linking_state_path(&self) -> PathBuf {
+        self.data_dir().join(LINKING_STATE_FOLDER).join(format!(
+            "{}.toml",
+            hash(self.package_dir().to_string_lossy().as_bytes())
+        ))
+    }
+}
+
+/// The indentation of a JSON document, which is that of its first indented line
+fn json_indent(contents: &str) -> Option<&str> {
+    contents.lines().skip(1).find_map(|line| {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        (!indent.is_empty()).then_some(indent)
+    })
+}
+
+/// Serializes the value with the indentation of the previous contents of its file, or two spaces
+fn to_json_like(value: &serde_json::Value, previous: Option<&str>) -> Vec<u8> {
+    let indent = previous.and_then(json_indent).unwrap_or("  ");
+    let mut serializer = serde_json::Serializer::with_formatter(
+        vec![],
+        serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes()),
+    );
+    // serializing a value into memory can't fail
+    value.serialize(&mut serializer).unwrap();
+
+    let mut contents = serializer.into_inner();
+    contents.push(b'\n');
+    contents
+}
+
+/// Reads the exports from the manifest in a package's container folder, which are empty if it has
+/// no manifest
+fn package_exports(container_folder: &Path) -> Result<Exports, errors::LinkingError> {
+    let contents = match std::fs::read_to_string(container_folder.join(MANIFEST_FILE_NAME)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Exports::default()),
+        Err(e) => return Err(e.into()),
+    };
+
+    parse_manifest(&contents)
+        .map(|manifest| manifest.exports)
+        .map_err(|e| {
+            errors::LinkingError::PackageManifest(container_folder.display().to_string(), e)
+        })
+}
+
+/// Checks that the Roblox sync config generated for a package exists, is valid JSON, and that the
+/// paths it references exist in the package's folder
+fn validate_roblox_sync_config(
+    container_folder: &Path,
+) -> Result<(), errors::RobloxSyncConfigError> {
+    let path = container_folder.join(ROBLOX_SYNC_CONFIG_FILE);
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(errors::RobloxSyncConfigError::NotGenerated(
+                path.display().to_string(),
+            ));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let config: serde_json::Value = serde_json::from_str(&contents)?;
+
+    let mut values = vec![&config];
+    while let Some(value) = values.pop() {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    match value.as_str() {
+                        Some(path) if key == "$path" => {
+                            if !container_folder.join(path).exists() {
+                                return Err(errors::RobloxSyncConfigError::MissingPath(
+                                    path.to_string(),
+                                ));
+                            }
+                        }
+                        _ => values.push(value),
+                    }
+                }
+            }
+            serde_json::Value::Array(array) => values.extend(array),
+            _ => {}
+        }
+    }
+
+    Ok(())
 }
 
 /// Errors that can occur while linking dependencies
@@ -283,8 +915,127 @@ pub mod errors {
         #[error("error generating roblox sync config for {0}")]
         GenerateRobloxSyncConfig(String, #[source] std::io::Error),
 
+        /// An error occurred while parsing the manifest of a package
+        #[error("error parsing manifest of package at {0}")]
+        PackageManifest(String, #[source] toml::de::Error),
+
+        /// The generated Roblox sync config is invalid
+        #[error("invalid roblox sync config generated for {0}")]
+        InvalidRobloxSyncConfig(String, #[source] RobloxSyncConfigError),
+
+        /// The Rojo project the packages are mapped in is not valid JSON
+        #[error("error parsing rojo project at {0}")]
+        RojoProject(String, #[source] serde_json::Error),
+
+        /// The Rojo project the packages are mapped in doesn't have a tree of instances
+        #[error("rojo project at {0} has an invalid tree")]
+        InvalidRojoProject(String),
+
         /// An error occurred while getting the require path for a library
         #[error("error getting require path for library")]
         GetLibRequirePath(#[from] super::generator::errors::GetLibRequirePath),
+
+        /// An error occurred while getting the dependencies of the project
+        #[error("error getting project dependencies")]
+        AllDependencies(#[from] crate::manifest::errors::AllDependenciesError),
+
+        /// An error occurred while serializing the linking state
+        #[error("error serializing linking state")]
+        SerializeState(#[from] toml::ser::Error),
+
+        /// The operation was cancelled
+        #[error("linking was cancelled")]
+        Cancelled,
+    }
+
+    /// Errors that can occur while validating a generated Roblox sync config
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum RobloxSyncConfigError {
+        /// The config was not generated
+        #[error("{0} was not generated")]
+        NotGenerated(String),
+
+        /// An error occurred while reading the config
+        #[error("error reading config")]
+        Io(#[from] std::io::Error),
+
+        /// The config is not valid JSON
+        #[error("config is not valid JSON")]
+        Json(#[from] serde_json::Error),
+
+        /// The config references a path which doesn't exist
+        #[error("config references {0}, which does not exist")]
+        MissingPath(String),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lib() -> Lib {
+        let contents = "export type Foo = number\nreturn {}\n".to_string();
+
+        Lib {
+            path: PathBuf::from("lib.luau"),
+            hash: crate::util::hash(&contents),
+            contents,
+        }
+    }
+
+    #[test]
+    fn caches_types() {
+        let cas_dir = tempfile::tempdir().unwrap();
+        let lib = lib();
+        let types = get_file_types(&lib.contents).unwrap();
+
+        assert_eq!(file_types(cas_dir.path(), &lib).unwrap(), types);
+
+        // the cached types are used instead of parsing the library again
+        let unparsable = Lib {
+            contents: "export type".to_string(),
+            ..lib
+        };
+        assert_eq!(file_types(cas_dir.path(), &unparsable).unwrap(), types);
+    }
+
+    #[test]
+    fn caching_types_is_best_effort() {
+        let cas_dir = tempfile::tempdir().unwrap();
+        // the cache folder can't be created where a file is
+        std::fs::write(cas_dir.path().join(TYPES_CACHE_FOLDER), "").unwrap();
+
+        let lib = lib();
+        assert_eq!(
+            file_types(cas_dir.path(), &lib).unwrap(),
+            get_file_types(&lib.contents).unwrap()
+        );
+    }
+
+    #[test]
+    fn detects_json_indent() {
+        assert_eq!(json_indent("{\n\t\"name\": \"game\"\n}"), Some("\t"));
+        assert_eq!(json_indent("{\n    \"name\": \"game\"\n}"), Some("    "));
+        assert_eq!(json_indent("{\"name\": \"game\"}"), None);
+    }
+
+    #[test]
+    fn keeps_key_order_and_indent() {
+        let previous =
+            "{\n\t\"tree\": {\n\t\t\"$className\": \"DataModel\"\n\t},\n\t\"name\": \"game\"\n}\n";
+
+        let mut project = serde_json::from_str::<serde_json::Value>(previous).unwrap();
+        assert_eq!(
+            String::from_utf8(to_json_like(&project, Some(previous))).unwrap(),
+            previous
+        );
+
+        project["tree"]["ReplicatedStorage"] =
+            serde_json::json!({ "$className": "ReplicatedStorage" });
+        assert_eq!(
+            String::from_utf8(to_json_like(&project, Some(previous))).unwrap(),
+            "{\n\t\"tree\": {\n\t\t\"$className\": \"DataModel\",\n\t\t\"ReplicatedStorage\": {\n\t\t\t\"$className\": \"ReplicatedStorage\"\n\t\t}\n\t},\n\t\"name\": \"game\"\n}\n"
+        );
     }
 }
@@ -0,0 +1,242 @@
+use crate::{
+    linking::ROBLOX_SYNC_CONFIG_FILE,
+    manifest::{target::TargetKind, Manifest},
+    Project,
+};
+use relative_path::{PathExt, RelativePathBuf};
+use serde::Serialize;
+use std::path::Path;
+
+/// A node of a Rojo-compatible sourcemap, describing an instance and the files it comes from
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcemapNode {
+    /// The name of the instance
+    pub name: String,
+    /// The class of the instance
+    pub class_name: String,
+    /// The files the instance comes from, relative to the project
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub file_paths: Vec<RelativePathBuf>,
+    /// The children of the instance
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SourcemapNode>,
+}
+
+impl SourcemapNode {
+    fn folder(name: String) -> Self {
+        Self {
+            name,
+            class_name: "Folder".to_string(),
+            file_paths: vec![],
+            children: vec![],
+        }
+    }
+}
+
+/// The name and class of the instance a script file becomes, following Rojo's conventions
+fn script_instance(file_name: &str) -> Option<(&str, &str)> {
+    let stem = file_name
+        .strip_suffix(".luau")
+        .or_else(|| file_name.strip_suffix(".lua"))?;
+
+    Some(if let Some(name) = stem.strip_suffix(".server") {
+        (name, "Script")
+    } else if let Some(name) = stem.strip_suffix(".client") {
+        (name, "LocalScript")
+    } else {
+        (stem, "ModuleScript")
+    })
+}
+
+struct SourcemapBuilder<'a> {
+    root: &'a Path,
+}
+
+impl SourcemapBuilder<'_> {
+    fn relative(&self, path: &Path) -> Result<RelativePathBuf, errors::SourcemapError> {
+        path.relative_to(self.root)
+            .map_err(|_| errors::SourcemapError::OutsideProject(path.display().to_string()))
+    }
+
+    /// The node of a file or directory, which is `None` if Rojo wouldn't turn it into an instance
+    fn path_node(
+        &self,
+        path: &Path,
+        name: Option<String>,
+    ) -> Result<Option<SourcemapNode>, errors::SourcemapError> {
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        if path.is_file() {
+            let Some((stem, class_name)) = script_instance(&file_name) else {
+                return Ok(None);
+            };
+
+            return Ok(Some(SourcemapNode {
+                name: name.unwrap_or_else(|| stem.to_string()),
+                class_name: class_name.to_string(),
+                file_paths: vec![self.relative(path)?],
+                children: vec![],
+            }));
+        }
+
+        if !path.is_dir() {
+            return Ok(None);
+        }
+
+        let name = name.unwrap_or(file_name);
+
+        let project_file = path.join(ROBLOX_SYNC_CONFIG_FILE);
+        if project_file.is_file() {
+            return self.project_node(&project_file, name).map(Some);
+        }
+
+        let mut node = SourcemapNode::folder(name);
+        let mut entries = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for entry in entries {
+            let entry_name = entry
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+
+            // an init script turns its directory into the script itself
+            if entry.is_file() {
+                if let Some(("init", class_name)) = script_instance(&entry_name) {
+                    node.class_name = class_name.to_string();
+                    node.file_paths.push(self.relative(&entry)?);
+                    continue;
+                }
+            }
+
+            node.children.extend(self.path_node(&entry, None)?);
+        }
+
+        Ok(Some(node))
+    }
+
+    /// The node of a Rojo project file, named after the directory containing it
+    fn project_node(
+        &self,
+        project_file: &Path,
+        name: String,
+    ) -> Result<SourcemapNode, errors::SourcemapError> {
+        let contents = std::fs::read_to_string(project_file)?;
+        let project: serde_json::Value = serde_json::from_str(&contents)
+            .map_err(|e| errors::SourcemapError::Project(project_file.display().to_string(), e))?;
+
+        let base_dir = project_file.parent().unwrap();
+        let mut node = match project.get("tree") {
+            Some(serde_json::Value::Object(tree)) => self.tree_node(base_dir, name, tree)?,
+            _ => SourcemapNode::folder(name),
+        };
+        node.file_paths.insert(0, self.relative(project_file)?);
+
+        Ok(node)
+    }
+
+    /// The node of an instance in a Rojo project file's tree
+    fn tree_node(
+        &self,
+        base_dir: &Path,
+        name: String,
+        tree: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<SourcemapNode, errors::SourcemapError> {
+        let path_node = match tree.get("$path").and_then(|path| path.as_str()) {
+            Some(path) => self.path_node(&base_dir.join(path), Some(name.clone()))?,
+            None => None,
+        };
+
+        let mut node = path_node.unwrap_or_else(|| SourcemapNode::folder(name));
+
+        if let Some(class_name) = tree.get("$className").and_then(|class| class.as_str()) {
+            node.class_name = class_name.to_string();
+        }
+
+        for (child_name, child) in tree {
+            if child_name.starts_with('$') {
+                continue;
+            }
+
+            let serde_json::Value::Object(child) = child else {
+                continue;
+            };
+
+            node.children
+                .push(self.tree_node(base_dir, child_name.clone(), child)?);
+        }
+
+        Ok(node)
+    }
+}
+
+/// Builds a sourcemap of the Roblox packages folders of the project, so tools like luau-lsp can
+/// resolve the types of dependencies without running a sourcemap generator. Packages are mapped
+/// by the Rojo project files generated for them while linking
+pub fn packages_sourcemap(
+    project: &Project,
+    manifest: &Manifest,
+) -> Result<SourcemapNode, errors::SourcemapError> {
+    let builder = SourcemapBuilder {
+        root: project.package_dir(),
+    };
+
+    let mut root = SourcemapNode::folder(manifest.name.as_str().1.to_string());
+
+    for kind in TargetKind::VARIANTS
+        .iter()
+        .filter(|kind| kind.has_build_files())
+    {
+        let folder = manifest.target.kind().packages_folder(kind);
+        root.children
+            .extend(builder.path_node(&project.package_dir().join(&folder), Some(folder.clone()))?);
+    }
+
+    Ok(root)
+}
+
+/// Finds the file a package's Rojo project file maps its root to, which is its lib file
+pub fn project_lib_path(
+    package_dir: &Path,
+) -> Result<Option<RelativePathBuf>, errors::SourcemapError> {
+    let project_file = package_dir.join(ROBLOX_SYNC_CONFIG_FILE);
+    if !project_file.is_file() {
+        return Ok(None);
+    }
+
+    let builder = SourcemapBuilder { root: package_dir };
+    let node = builder.project_node(&project_file, String::new())?;
+
+    Ok(node.file_paths.into_iter().find(|path| {
+        path.extension()
+            .is_some_and(|ext| ext == "lua" || ext == "luau")
+    }))
+}
+
+/// Errors that can occur while building a sourcemap
+pub mod errors {
+    use thiserror::Error;
+
+    /// Errors that can occur while building a sourcemap
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum SourcemapError {
+        /// An error occurred while interacting with the filesystem
+        #[error("error interacting with filesystem")]
+        Io(#[from] std::io::Error),
+
+        /// A Rojo project file is not valid JSON
+        #[error("error parsing project file at {0}")]
+        Project(String, #[source] serde_json::Error),
+
+        /// A project file references a path outside of the project
+        #[error("{0} is outside of the project")]
+        OutsideProject(String),
+    }
+}
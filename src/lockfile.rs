@@ -1,8 +1,9 @@
 use crate::{
     manifest::{
+        errors::AllDependenciesError,
         overrides::OverrideKey,
         target::{Target, TargetKind},
-        DependencyType,
+        DependencyType, Manifest,
     },
     names::{PackageName, PackageNames},
     source::{
@@ -14,7 +15,8 @@ use relative_path::RelativePathBuf;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::{btree_map::Entry, BTreeMap},
+    collections::{btree_map::Entry, BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
     path::{Path, PathBuf},
 };
 
@@ -34,6 +36,22 @@ pub struct DependencyGraphNode {
     pub ty: DependencyType,
     /// The package reference
     pub pkg_ref: PackageRefs,
+    /// The aliases of the direct dependencies which introduced this package into the graph
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub introduced_by: BTreeSet<String>,
+    /// Where and when this package was resolved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provenance: Option<Provenance>,
+}
+
+/// Where and when a package was resolved
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    /// The commit of the index the package was resolved from, if it comes from an index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index_commit: Option<String>,
+    /// When the package was resolved
+    pub resolved_at: chrono::DateTime<chrono::Utc>,
 }
 
 impl DependencyGraphNode {
@@ -101,6 +119,81 @@ pub(crate) fn insert_node(
     }
 }
 
+/// Returns the part of the graph made up of the direct dependency with the given alias
+/// and all of its transitive dependencies, or `None` if there is no such direct dependency
+pub fn direct_dependency_subgraph(graph: &DependencyGraph, alias: &str) -> Option<DependencyGraph> {
+    let (name, version_id) = graph.iter().find_map(|(name, versions)| {
+        versions
+            .iter()
+            .find(|(_, node)| node.direct.as_ref().is_some_and(|(a, _)| a == alias))
+            .map(|(version_id, _)| (name, version_id))
+    })?;
+
+    let mut subgraph = DependencyGraph::new();
+    let mut queue = vec![(name, version_id)];
+
+    while let Some((name, version_id)) = queue.pop() {
+        let Some(node) = graph
+            .get(name)
+            .and_then(|versions| versions.get(version_id))
+        else {
+            log::warn!("{name}@{version_id} is referenced but not in the graph");
+            continue;
+        };
+
+        if subgraph
+            .entry(name.clone())
+            .or_default()
+            .insert(version_id.clone(), node.clone())
+            .is_some()
+        {
+            continue;
+        }
+
+        queue.extend(
+            node.dependencies
+                .iter()
+                .map(|(name, (version_id, _))| (name, version_id)),
+        );
+    }
+
+    Some(subgraph)
+}
+
+/// Records in every node of the graph which direct dependencies introduced it
+pub(crate) fn record_introducers(graph: &mut DependencyGraph) {
+    let aliases = graph
+        .values()
+        .flat_map(|versions| versions.values())
+        .filter_map(|node| node.direct.as_ref().map(|(alias, _)| alias.clone()))
+        .collect::<Vec<_>>();
+
+    let mut introducers = BTreeMap::<(PackageNames, VersionId), BTreeSet<String>>::new();
+
+    for alias in aliases {
+        let Some(subgraph) = direct_dependency_subgraph(graph, &alias) else {
+            continue;
+        };
+
+        for (name, versions) in subgraph {
+            for version_id in versions.into_keys() {
+                introducers
+                    .entry((name.clone(), version_id))
+                    .or_default()
+                    .insert(alias.clone());
+            }
+        }
+    }
+
+    for (name, versions) in graph.iter_mut() {
+        for (version_id, node) in versions.iter_mut() {
+            node.introduced_by = introducers
+                .remove(&(name.clone(), version_id.clone()))
+                .unwrap_or_default();
+        }
+    }
+}
+
 /// A downloaded dependency graph node, i.e. a `DependencyGraphNode` with a `Target`
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct DownloadedDependencyGraphNode {
@@ -114,6 +207,201 @@ pub struct DownloadedDependencyGraphNode {
 /// A graph of `DownloadedDependencyGraphNode`s
 pub type DownloadedGraph = Graph<DownloadedDependencyGraphNode>;
 
+/// Removes the direct dependency with the given alias from the graph along with every package
+/// which is no longer reachable from the remaining direct dependencies, returning the removed packages
+pub fn remove_direct_dependency(
+    graph: &mut DownloadedGraph,
+    alias: &str,
+) -> Vec<(PackageNames, VersionId, DownloadedDependencyGraphNode)> {
+    for node in graph
+        .values_mut()
+        .flat_map(|versions| versions.values_mut())
+    {
+        if node.node.direct.as_ref().is_some_and(|(a, _)| a == alias) {
+            node.node.direct = None;
+        }
+    }
+
+    let mut reachable = BTreeSet::new();
+    let mut queue = graph
+        .iter()
+        .flat_map(|(name, versions)| {
+            versions
+                .iter()
+                .filter(|(_, node)| node.node.direct.is_some())
+                .map(move |(version_id, _)| (name.clone(), version_id.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    while let Some((name, version_id)) = queue.pop() {
+        if !reachable.insert((name.clone(), version_id.clone())) {
+            continue;
+        }
+
+        if let Some(node) = graph
+            .get(&name)
+            .and_then(|versions| versions.get(&version_id))
+        {
+            queue.extend(
+                node.node
+                    .dependencies
+                    .iter()
+                    .map(|(name, (version_id, _))| (name.clone(), version_id.clone())),
+            );
+        }
+    }
+
+    let mut removed = vec![];
+
+    graph.retain(|name, versions| {
+        let (kept, unreachable) = std::mem::take(versions)
+            .into_iter()
+            .partition::<BTreeMap<_, _>, _>(|(version_id, _)| {
+                reachable.contains(&(name.clone(), version_id.clone()))
+            });
+
+        removed.extend(
+            unreachable
+                .into_iter()
+                .map(|(version_id, node)| (name.clone(), version_id, node)),
+        );
+        *versions = kept;
+
+        !versions.is_empty()
+    });
+
+    removed
+}
+
+/// The reverse edges of a graph, mapping each package to the packages which depend on it and the alias they use for it
+pub type Dependents =
+    BTreeMap<(PackageNames, VersionId), BTreeSet<(PackageNames, VersionId, String)>>;
+
+/// Builds the reverse edges of the graph
+pub fn dependents(graph: &DownloadedGraph) -> Dependents {
+    let mut dependents = Dependents::new();
+
+    for (name, versions) in graph {
+        for (version_id, node) in versions {
+            for (dependency_name, (dependency_version_id, alias)) in &node.node.dependencies {
+                dependents
+                    .entry((dependency_name.clone(), dependency_version_id.clone()))
+                    .or_default()
+                    .insert((name.clone(), version_id.clone(), alias.clone()));
+            }
+        }
+    }
+
+    dependents
+}
+
+/// A step of a path through the graph, made up of the package and the alias it was depended on with
+pub type PathStep = (PackageNames, VersionId, String);
+
+/// Returns every path from a direct dependency to the given package. Each path starts at a direct
+/// dependency, with its alias in the project, and ends at the given package
+pub fn dependency_paths(
+    graph: &DownloadedGraph,
+    dependents: &Dependents,
+    name: &PackageNames,
+    version_id: &VersionId,
+) -> Vec<Vec<PathStep>> {
+    fn walk(
+        graph: &DownloadedGraph,
+        dependents: &Dependents,
+        current: &mut Vec<PathStep>,
+        paths: &mut Vec<Vec<PathStep>>,
+    ) {
+        let (name, version_id, _) = current.last().unwrap().clone();
+
+        if let Some((alias, _)) = graph
+            .get(&name)
+            .and_then(|versions| versions.get(&version_id))
+            .and_then(|node| node.node.direct.as_ref())
+        {
+            let mut path = current.clone();
+            path.last_mut().unwrap().2 = alias.clone();
+            path.reverse();
+            paths.push(path);
+        }
+
+        let Some(parents) = dependents.get(&(name, version_id)) else {
+            return;
+        };
+
+        for (parent_name, parent_version_id, alias) in parents {
+            // guard against cycles
+            if current
+                .iter()
+                .any(|(n, v, _)| n == parent_name && v == parent_version_id)
+            {
+                continue;
+            }
+
+            current.last_mut().unwrap().2 = alias.clone();
+            current.push((
+                parent_name.clone(),
+                parent_version_id.clone(),
+                String::new(),
+            ));
+            walk(graph, dependents, current, paths);
+            current.pop();
+        }
+    }
+
+    let mut paths = vec![];
+    walk(
+        graph,
+        dependents,
+        &mut vec![(name.clone(), version_id.clone(), String::new())],
+        &mut paths,
+    );
+
+    paths
+}
+
+/// Describes the graph in a stable, line-based text format, leaving out everything which changes
+/// between resolutions of the same versions (such as provenance), so it can be compared over time
+pub fn graph_snapshot(graph: &DependencyGraph) -> String {
+    let mut snapshot = String::new();
+
+    for (name, versions) in graph {
+        for (version_id, node) in versions {
+            snapshot.push_str(&format!("{name}@{version_id}"));
+
+            let mut notes = vec![];
+            match node.ty {
+                DependencyType::Standard => {}
+                DependencyType::Peer => notes.push("peer".to_string()),
+                DependencyType::Dev => notes.push("dev".to_string()),
+            }
+            if let Some((alias, _)) = &node.direct {
+                notes.push(format!("direct as {alias}"));
+            }
+            if !notes.is_empty() {
+                snapshot.push_str(&format!(" ({})", notes.join(", ")));
+            }
+            snapshot.push('\n');
+
+            let mut dependencies = node
+                .dependencies
+                .iter()
+                .map(|(dep_name, (dep_version_id, alias))| (alias, dep_name, dep_version_id))
+                .collect::<Vec<_>>();
+            dependencies.sort();
+
+            for (alias, dep_name, dep_version_id) in dependencies {
+                snapshot.push_str(&format!("  {alias}: {dep_name}@{dep_version_id}\n"));
+            }
+        }
+    }
+
+    snapshot
+}
+
+/// Exact versions packages are pinned to, taking precedence over the versions resolution would pick
+pub type Pins = BTreeMap<PackageNames, Version>;
+
 /// A lockfile
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Lockfile {
@@ -126,6 +414,12 @@ pub struct Lockfile {
     /// The overrides of the package
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub overrides: BTreeMap<OverrideKey, DependencySpecifiers>,
+    /// The pins of the package
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pins: Pins,
+    /// The features the dependencies were installed with
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub features: BTreeSet<String>,
 
     /// The workspace members
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
@@ -135,3 +429,274 @@ pub struct Lockfile {
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub graph: DownloadedGraph,
 }
+
+/// A way in which a lockfile is out of date with the manifest it was created from
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LockfileMismatch {
+    /// The name of the package changed
+    Name {
+        /// The name in the manifest
+        manifest: PackageName,
+        /// The name in the lockfile
+        lockfile: PackageName,
+    },
+    /// The version of the package changed
+    Version {
+        /// The version in the manifest
+        manifest: Version,
+        /// The version in the lockfile
+        lockfile: Version,
+    },
+    /// The target of the package changed
+    Target {
+        /// The target in the manifest
+        manifest: TargetKind,
+        /// The target in the lockfile
+        lockfile: TargetKind,
+    },
+    /// An override was added, removed or changed
+    Override(OverrideKey),
+    /// A dependency of the manifest isn't in the lockfile
+    MissingDependency {
+        /// The alias of the dependency
+        alias: String,
+        /// The specifier of the dependency
+        specifier: DependencySpecifiers,
+    },
+    /// The specifier or type of a dependency changed
+    ChangedDependency {
+        /// The alias of the dependency
+        alias: String,
+        /// The specifier in the manifest
+        manifest: DependencySpecifiers,
+        /// The specifier in the lockfile
+        lockfile: DependencySpecifiers,
+    },
+    /// A dependency in the lockfile was removed from the manifest
+    RemovedDependency(String),
+}
+
+impl Display for LockfileMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockfileMismatch::Name { manifest, lockfile } => {
+                write!(f, "name changed from {lockfile} to {manifest}")
+            }
+            LockfileMismatch::Version { manifest, lockfile } => {
+                write!(f, "version changed from {lockfile} to {manifest}")
+            }
+            LockfileMismatch::Target { manifest, lockfile } => {
+                write!(f, "target changed from {lockfile} to {manifest}")
+            }
+            LockfileMismatch::Override(key) => write!(f, "override {key} changed"),
+            LockfileMismatch::MissingDependency { alias, specifier } => {
+                write!(f, "dependency {alias} ({specifier}) is missing")
+            }
+            LockfileMismatch::ChangedDependency {
+                alias,
+                manifest,
+                lockfile,
+            } if manifest.to_string() == lockfile.to_string() => {
+                write!(f, "dependency {alias} changed its type or options")
+            }
+            LockfileMismatch::ChangedDependency {
+                alias,
+                manifest,
+                lockfile,
+            } => write!(
+                f,
+                "dependency {alias} changed from {lockfile} to {manifest}"
+            ),
+            LockfileMismatch::RemovedDependency(alias) => {
+                write!(f, "dependency {alias} was removed")
+            }
+        }
+    }
+}
+
+/// Compares the lockfile with the manifest it should have been created from, returning every way
+/// in which it is out of date. An empty list means the lockfile can be installed as is. Pins
+/// aren't part of the manifest, so they must be compared separately
+pub fn check_compatibility(
+    manifest: &Manifest,
+    lockfile: &Lockfile,
+) -> Result<Vec<LockfileMismatch>, AllDependenciesError> {
+    let mut mismatches = vec![];
+
+    if manifest.name != lockfile.name {
+        mismatches.push(LockfileMismatch::Name {
+            manifest: manifest.name.clone(),
+            lockfile: lockfile.name.clone(),
+        });
+    }
+
+    if manifest.version != lockfile.version {
+        mismatches.push(LockfileMismatch::Version {
+            manifest: manifest.version.clone(),
+            lockfile: lockfile.version.clone(),
+        });
+    }
+
+    if manifest.target.kind() != lockfile.target {
+        mismatches.push(LockfileMismatch::Target {
+            manifest: manifest.target.kind(),
+            lockfile: lockfile.target,
+        });
+    }
+
+    mismatches.extend(
+        manifest
+            .overrides
+            .keys()
+            .chain(lockfile.overrides.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|key| manifest.overrides.get(*key) != lockfile.overrides.get(*key))
+            .map(|key| LockfileMismatch::Override(key.clone())),
+    );
+
+    let locked = lockfile
+        .graph
+        .values()
+        .flat_map(|versions| versions.values())
+        .filter_map(|node| {
+            node.node
+                .direct
+                .as_ref()
+                .map(|(alias, specifier)| (alias, (specifier, node.node.ty)))
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let dependencies = manifest.all_dependencies()?;
+
+    for (alias, (specifier, ty)) in &dependencies {
+        match locked.get(alias) {
+            // optional dependencies without a matching version aren't in the graph
+            None if specifier.is_optional() => {}
+            None => mismatches.push(LockfileMismatch::MissingDependency {
+                alias: alias.clone(),
+                specifier: specifier.clone(),
+            }),
+            Some((locked_specifier, locked_ty))
+                if *locked_specifier != specifier || locked_ty != ty =>
+            {
+                mismatches.push(LockfileMismatch::ChangedDependency {
+                    alias: alias.clone(),
+                    manifest: specifier.clone(),
+                    lockfile: (*locked_specifier).clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+
+    mismatches.extend(
+        locked
+            .keys()
+            .filter(|alias| !dependencies.contains_key(**alias))
+            .map(|alias| LockfileMismatch::RemovedDependency((*alias).clone())),
+    );
+
+    Ok(mismatches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"name = "acme/foo"
+version = "1.0.0"
+target = { environment = "luau" }
+
+[dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+"#;
+
+    fn node(alias: &str, version_req: &str, ty: &str) -> String {
+        format!(
+            r#"[graph."acme/{alias}"."1.0.0 luau"]
+direct = ["{alias}", {{ name = "acme/{alias}", version = "{version_req}" }}]
+ty = "{ty}"
+target = {{ environment = "luau" }}
+pkg_ref = {{ ref_ty = "pesde", name = "acme/{alias}", version = "1.0.0", index_url = "https://github.com/pesde-pkg/index", target = {{ environment = "luau" }} }}
+"#
+        )
+    }
+
+    fn lockfile(nodes: &[String]) -> Lockfile {
+        toml::from_str(&format!(
+            r#"name = "acme/foo"
+version = "1.0.0"
+target = "luau"
+
+{}"#,
+            nodes.concat()
+        ))
+        .unwrap()
+    }
+
+    fn mismatches(manifest: &str, lockfile: &Lockfile) -> Vec<String> {
+        check_compatibility(&toml::from_str(manifest).unwrap(), lockfile)
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn compatible_lockfile() {
+        let lockfile = lockfile(&[node("bar", "^1.0.0", "standard")]);
+        assert!(mismatches(MANIFEST, &lockfile).is_empty());
+    }
+
+    #[test]
+    fn reports_package_changes() {
+        let lockfile = lockfile(&[node("bar", "^1.0.0", "standard")]);
+        let manifest = MANIFEST
+            .replace("acme/foo", "acme/baz")
+            .replace(r#"version = "1.0.0""#, r#"version = "1.1.0""#)
+            .replace(r#""luau""#, r#""lune""#);
+
+        assert_eq!(
+            mismatches(&manifest, &lockfile),
+            [
+                "name changed from acme/foo to acme/baz",
+                "version changed from 1.0.0 to 1.1.0",
+                "target changed from luau to lune",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_dependency_changes() {
+        let lockfile = lockfile(&[
+            node("bar", "^0.9.0", "standard"),
+            node("qux", "^1.0.0", "standard"),
+        ]);
+        let manifest = format!(
+            r#"{MANIFEST}baz = {{ name = "acme/baz", version = "^1.0.0" }}
+opt = {{ name = "acme/opt", version = "^1.0.0", optional = true }}
+"#
+        );
+
+        assert_eq!(
+            mismatches(&manifest, &lockfile),
+            [
+                "dependency bar changed from acme/bar@^0.9.0 to acme/bar@^1.0.0",
+                "dependency baz (acme/baz@^1.0.0) is missing",
+                "dependency qux was removed",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_dependency_type_changes() {
+        let lockfile = lockfile(&[node("bar", "^1.0.0", "dev")]);
+
+        assert_eq!(
+            mismatches(MANIFEST, &lockfile),
+            ["dependency bar changed its type or options"]
+        );
+    }
+}
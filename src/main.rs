@@ -1,24 +1,43 @@
 #[cfg(feature = "version-management")]
 use crate::cli::version::{
-    check_for_updates, current_version, get_or_download_version, max_installed_version,
+    check_for_cached_updates, check_for_updates, current_version, get_or_download_version,
+    max_installed_version,
+};
+use crate::cli::{
+    auth::get_tokens,
+    config::{read_config, ByteRate, ScriptPolicyKind},
+    helper, home_dir,
+    repos::update_repo_dependencies,
+    HOME_DIR,
 };
-use crate::cli::{auth::get_tokens, home_dir, repos::update_repo_dependencies, HOME_DIR};
 use anyhow::Context;
 use clap::Parser;
 use colored::Colorize;
 use indicatif::MultiProgress;
 use indicatif_log_bridge::LogWrapper;
-use pesde::{AuthConfig, Project, MANIFEST_FILE_NAME};
+use pesde::{
+    manifest::migration::{parse_manifest, LEGACY_MANIFEST_FILE_NAME},
+    scripts::ScriptPolicy,
+    source::network::NetworkLimiter,
+    AuthConfig, Project, MANIFEST_FILE_NAME,
+};
 use std::{
     collections::HashSet,
+    ffi::OsString,
     fs::create_dir_all,
+    num::NonZeroUsize,
     path::{Path, PathBuf},
     thread::spawn,
+    time::Duration,
 };
 
 mod cli;
 pub mod util;
 
+/// How old entries of the temporary directories must be to be removed when starting, as they may
+/// belong to another running process
+const STALE_TEMP_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
 #[derive(Parser, Debug)]
 #[clap(
     version,
@@ -30,6 +49,35 @@ struct Cli {
     #[arg(short = 'v', short_alias = 'V', long, action = clap::builder::ArgAction::Version)]
     version: (),
 
+    /// The locale to print messages in
+    #[arg(long, global = true, value_enum)]
+    locale: Option<cli::messages::Locale>,
+
+    /// Only use the already cloned indices and cached packages, never accessing the network
+    // this is read before the arguments are parsed, it is only here for clap to accept it
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// The directory to store indices and cached packages in. Can also be set with the PESDE_DATA_DIR environment variable
+    // this is read before the arguments are parsed, it is only here for clap to accept it
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+
+    /// Keep all state (config, indices, cached packages, versions) in the project directory instead of the home directory
+    // this is read before the arguments are parsed, it is only here for clap to accept it
+    #[arg(long, global = true)]
+    isolated: bool,
+
+    /// The highest rate to download packages at in total, for example `500K` or `2M` bytes per second
+    // this is read before the arguments are parsed, it is only here for clap to accept it
+    #[arg(long, global = true)]
+    max_download_rate: Option<String>,
+
+    /// How many connections may be open to a host at once when downloading packages and fetching repositories
+    // this is read before the arguments are parsed, it is only here for clap to accept it
+    #[arg(long, global = true)]
+    max_host_connections: Option<NonZeroUsize>,
+
     #[command(subcommand)]
     subcommand: cli::commands::Subcommand,
 }
@@ -67,56 +115,40 @@ fn get_root(path: &std::path::Path) -> PathBuf {
     current.to_path_buf()
 }
 
-fn run() -> anyhow::Result<()> {
-    let cwd = std::env::current_dir().expect("failed to get current working directory");
+/// Reads the value of a global argument before the arguments are parsed
+fn global_arg_value(name: &str) -> Option<OsString> {
+    let mut args = std::env::args_os().skip(1).take_while(|arg| arg != "--");
+    let prefix = format!("{name}=");
 
-    #[cfg(windows)]
-    'scripts: {
-        let exe = std::env::current_exe().expect("failed to get current executable path");
-        if exe.parent().is_some_and(|parent| {
-            parent.file_name().is_some_and(|parent| parent != "bin")
-                || parent
-                    .parent()
-                    .and_then(|parent| parent.file_name())
-                    .is_some_and(|parent| parent != HOME_DIR)
-        }) {
-            break 'scripts;
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
         }
 
-        let exe_name = exe.file_name().unwrap().to_string_lossy();
-        let exe_name = exe_name
-            .strip_suffix(std::env::consts::EXE_SUFFIX)
-            .unwrap_or(&exe_name);
-
-        if exe_name == env!("CARGO_BIN_NAME") {
-            break 'scripts;
+        if let Some(value) = arg.to_str().and_then(|arg| arg.strip_prefix(&prefix)) {
+            return Some(value.into());
         }
-
-        // the bin script will search for the project root itself, so we do that to ensure
-        // consistency across platforms, since the script is executed using a shebang
-        // on unix systems
-        let status = std::process::Command::new("lune")
-            .arg("run")
-            .arg(exe.with_extension(""))
-            .arg("--")
-            .args(std::env::args_os().skip(1))
-            .current_dir(cwd)
-            .status()
-            .expect("failed to run lune");
-
-        std::process::exit(status.code().unwrap());
     }
 
+    None
+}
+
+fn run() -> anyhow::Result<()> {
+    let cwd = std::env::current_dir().expect("failed to get current working directory");
+
     let (project_root_dir, project_workspace_dir) = 'finder: {
         let mut current_path = Some(cwd.clone());
         let mut project_root = None::<PathBuf>;
         let mut workspace_dir = None::<PathBuf>;
 
         fn get_workspace_members(path: &Path) -> anyhow::Result<HashSet<PathBuf>> {
-            let manifest = std::fs::read_to_string(path.join(MANIFEST_FILE_NAME))
-                .context("failed to read manifest")?;
-            let manifest: pesde::manifest::Manifest =
-                toml::from_str(&manifest).context("failed to parse manifest")?;
+            let manifest = match std::fs::read_to_string(path.join(MANIFEST_FILE_NAME)) {
+                Ok(manifest) => manifest,
+                // legacy manifests can't have workspace members
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+                Err(e) => return Err(e).context("failed to read manifest"),
+            };
+            let manifest = parse_manifest(&manifest).context("failed to parse manifest")?;
 
             if manifest.workspace_members.is_empty() {
                 return Ok(HashSet::new());
@@ -138,7 +170,9 @@ fn run() -> anyhow::Result<()> {
         while let Some(path) = current_path {
             current_path = path.parent().map(|p| p.to_path_buf());
 
-            if !path.join(MANIFEST_FILE_NAME).exists() {
+            if !path.join(MANIFEST_FILE_NAME).exists()
+                && !path.join(LEGACY_MANIFEST_FILE_NAME).exists()
+            {
                 continue;
             }
 
@@ -156,7 +190,7 @@ fn run() -> anyhow::Result<()> {
                 (None, None) => {
                     if get_workspace_members(&path)?.contains(&cwd) {
                         // initializing a new member of a workspace
-                        break 'finder (cwd, Some(path));
+                        break 'finder (cwd.clone(), Some(path));
                     } else {
                         project_root = Some(path);
                     }
@@ -182,7 +216,23 @@ fn run() -> anyhow::Result<()> {
         multi
     };
 
-    let data_dir = home_dir()?.join("data");
+    let isolated = std::env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == "--isolated");
+    if isolated {
+        cli::isolate(project_root_dir.join(HOME_DIR));
+    }
+
+    cli::migrate::migrate_home().context("failed to migrate home directory")?;
+
+    let data_dir = match global_arg_value("--data-dir")
+        .or_else(|| std::env::var_os("PESDE_DATA_DIR"))
+        .filter(|dir| !dir.is_empty())
+    {
+        Some(dir) => cwd.join(dir),
+        None => home_dir()?.join("data"),
+    };
     create_dir_all(&data_dir).expect("failed to create data directory");
 
     let home_cas_dir = data_dir.join("cas");
@@ -196,15 +246,32 @@ fn run() -> anyhow::Result<()> {
         project_root.join(HOME_DIR).join("cas")
     };
 
-    let project = Project::new(
-        project_root_dir,
-        project_workspace_dir,
-        data_dir,
-        cas_dir,
-        AuthConfig::new().with_tokens(get_tokens()?.0),
-    );
+    pesde::tempdirs::set_root(data_dir.join("tmp"))
+        .context("failed to create temporary directory")?;
+    for dir in [data_dir.join("tmp"), cas_dir.join(".tmp")] {
+        match pesde::tempdirs::sweep(&dir, STALE_TEMP_AGE) {
+            Ok(0) => {}
+            Ok(removed) => log::debug!("removed {removed} stale entries from {}", dir.display()),
+            Err(e) => log::warn!("failed to sweep {}: {e}", dir.display()),
+        }
+    }
+    // destructors don't run when interrupted, so temporary directories have to be removed here
+    if let Err(e) = ctrlc::set_handler(|| {
+        pesde::tempdirs::cleanup();
+        std::process::exit(130);
+    }) {
+        log::warn!("failed to set interrupt handler: {e}");
+    }
+
+    let offline = std::env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == "--offline");
+    let is_helper = std::env::args_os()
+        .nth(1)
+        .is_some_and(|arg| arg == helper::HELPER_COMMAND);
 
-    let reqwest = {
+    let build_client = |identity: Option<reqwest::Identity>| -> anyhow::Result<_> {
         let mut headers = reqwest::header::HeaderMap::new();
 
         headers.insert(
@@ -214,15 +281,110 @@ fn run() -> anyhow::Result<()> {
                 .context("failed to create accept header")?,
         );
 
-        reqwest::blocking::Client::builder()
+        let mut builder = reqwest::blocking::Client::builder()
             .user_agent(concat!(
                 env!("CARGO_PKG_NAME"),
                 "/",
                 env!("CARGO_PKG_VERSION")
             ))
-            .default_headers(headers)
-            .build()?
+            .default_headers(headers);
+
+        if let Some(identity) = identity {
+            builder = builder.identity(identity);
+        }
+
+        Ok(builder.build()?)
+    };
+
+    let reqwest = build_client(None)?;
+
+    let index_clients = read_config()?
+        .client_certificates
+        .into_iter()
+        .map(|(index, certificate)| {
+            let index_url = gix::Url::try_from(index.as_str())
+                .with_context(|| format!("invalid index url {index}"))?;
+
+            let mut pem = std::fs::read(&certificate.cert).with_context(|| {
+                format!(
+                    "failed to read client certificate {}",
+                    certificate.cert.display()
+                )
+            })?;
+            pem.push(b'\n');
+            pem.extend(std::fs::read(&certificate.key).with_context(|| {
+                format!(
+                    "failed to read client certificate key {}",
+                    certificate.key.display()
+                )
+            })?);
+
+            let identity = reqwest::Identity::from_pem(&pem)
+                .with_context(|| format!("invalid client certificate for {index}"))?;
+
+            log::debug!("using client certificate for {index}, git operations will not use it");
+
+            Ok((index_url, build_client(Some(identity))?))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let config = read_config()?;
+
+    let source_replacements = config
+        .source_replacement
+        .iter()
+        .map(|(url, mirror)| {
+            Ok((
+                gix::Url::try_from(url.as_str())
+                    .with_context(|| format!("invalid replaced url {url}"))?,
+                gix::Url::try_from(mirror.as_str())
+                    .with_context(|| format!("invalid replacement url {mirror}"))?,
+            ))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let max_download_rate = match global_arg_value("--max-download-rate") {
+        Some(rate) => Some(
+            rate.to_string_lossy()
+                .parse::<ByteRate>()
+                .context("invalid --max-download-rate")?,
+        ),
+        None => config.network_limits.max_download_rate,
     };
+    let max_host_connections = match global_arg_value("--max-host-connections") {
+        Some(max) => Some(
+            max.to_string_lossy()
+                .parse::<NonZeroUsize>()
+                .context("invalid --max-host-connections")?,
+        ),
+        None => config.network_limits.max_host_connections,
+    };
+
+    let project = Project::new(
+        project_root_dir,
+        project_workspace_dir,
+        data_dir,
+        cas_dir,
+        AuthConfig::new()
+            .with_tokens(get_tokens()?.0)
+            .with_index_clients(index_clients),
+    )
+    .with_source_replacements(source_replacements)
+    .with_offline(offline)
+    .with_trust_policy(config.trust_policy)
+    .with_segmented_downloads(config.segmented_downloads)
+    .with_retry_policy(config.download_retries.into())
+    .with_lenient_index(!config.strict_index)
+    .with_network_limiter(NetworkLimiter::new(
+        max_download_rate.map(|rate| rate.0),
+        max_host_connections,
+    ))
+    .with_script_policy(config.script_permissions.iter().fold(
+        ScriptPolicy::new(config.script_policy != ScriptPolicyKind::Deny),
+        |policy, (package, allowed)| policy.with_decision(package.clone(), *allowed),
+    ));
+
+    let use_helper = !is_helper && helper::enabled(&project)?;
 
     #[cfg(feature = "version-management")]
     {
@@ -232,33 +394,54 @@ fn run() -> anyhow::Result<()> {
             .and_then(|manifest| manifest.pesde_version);
 
         // store the current version in case it needs to be used later
-        get_or_download_version(&reqwest, &current_version())?;
+        get_or_download_version(&reqwest, &current_version(), offline)?;
 
         let exe_path = if let Some(version) = target_version {
-            Some(get_or_download_version(&reqwest, &version)?)
+            Some(get_or_download_version(&reqwest, &version, offline)?)
         } else {
             None
         };
         let exe_path = if let Some(exe_path) = exe_path {
             exe_path
         } else {
-            get_or_download_version(&reqwest, &max_installed_version()?)?
+            get_or_download_version(&reqwest, &max_installed_version()?, offline)?
         };
 
         if let Some(exe_path) = exe_path {
-            let status = std::process::Command::new(exe_path)
+            let status = std::process::Command::new(&exe_path)
                 .args(std::env::args_os().skip(1))
                 .status()
-                .expect("failed to run new version");
+                .with_context(|| format!("failed to run version at {}", exe_path.display()))?;
 
-            std::process::exit(status.code().unwrap());
+            std::process::exit(status.code().unwrap_or(1));
         }
 
-        display_err(check_for_updates(&reqwest), " while checking for updates");
+        if use_helper {
+            display_err(check_for_cached_updates(), " while checking for updates");
+        } else if !offline && !is_helper {
+            display_err(check_for_updates(&reqwest), " while checking for updates");
+        }
+    }
+
+    let _command_lock = if use_helper {
+        Some(helper::lock_for_command()?)
+    } else {
+        None
+    };
+
+    if use_helper {
+        display_err(
+            helper::spawn_if_stale(&project),
+            " while spawning the background helper",
+        );
     }
 
     let project_2 = project.clone();
     let update_task = spawn(move || {
+        if use_helper || is_helper {
+            return;
+        }
+
         display_err(
             update_repo_dependencies(&project_2),
             " while updating repository dependencies",
@@ -274,6 +457,23 @@ fn run() -> anyhow::Result<()> {
         }
     };
 
+    cli::messages::init(cli.locale, &read_config()?);
+
+    if !matches!(cli.subcommand, cli::commands::Subcommand::Migrate(_)) {
+        if let Ok((_, migrations)) = project.deser_manifest_with_migrations() {
+            for migration in &migrations {
+                log::warn!("{migration}");
+            }
+
+            if !migrations.is_empty() {
+                log::warn!(
+                    "{}",
+                    cli::messages::msg!("manifest.legacy", bin = env!("CARGO_BIN_NAME"))
+                );
+            }
+        }
+    }
+
     cli.subcommand.run(project, multi, reqwest, update_task)
 }
 
@@ -0,0 +1,72 @@
+use crate::manifest::target::TargetKind;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A feature of a runtime which packages can require
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// The `@lune/fs` library
+    LuneFs,
+    /// The `@lune/net` library
+    LuneNet,
+    /// The `@lune/process` library
+    LuneProcess,
+    /// The `@lune/serde` library
+    LuneSerde,
+    /// The `@lune/roblox` library
+    LuneRoblox,
+    /// The `@lune/datetime` library
+    LuneDateTime,
+    /// The `@lune/regex` library
+    LuneRegex,
+}
+
+impl Capability {
+    /// All capabilities
+    pub const VARIANTS: &'static [Capability] = &[
+        Capability::LuneFs,
+        Capability::LuneNet,
+        Capability::LuneProcess,
+        Capability::LuneSerde,
+        Capability::LuneRoblox,
+        Capability::LuneDateTime,
+        Capability::LuneRegex,
+    ];
+
+    /// The target which provides this capability, and the first version of its runtime to do so
+    pub fn requirement(&self) -> (TargetKind, Version) {
+        let (target, major, minor) = match self {
+            Capability::LuneFs | Capability::LuneNet | Capability::LuneProcess => {
+                (TargetKind::Lune, 0, 5)
+            }
+            Capability::LuneSerde | Capability::LuneRoblox => (TargetKind::Lune, 0, 6),
+            Capability::LuneDateTime | Capability::LuneRegex => (TargetKind::Lune, 0, 8),
+        };
+
+        (target, Version::new(major, minor, 0))
+    }
+
+    /// Whether this capability is available on the given target, running on the given version of its runtime.
+    /// If the version is unknown, the capability is assumed to be available if the target matches
+    pub fn is_available(&self, target: TargetKind, engine: Option<&Version>) -> bool {
+        let (required_target, required_version) = self.requirement();
+
+        required_target == target && engine.is_none_or(|engine| *engine >= required_version)
+    }
+}
+
+impl Display for Capability {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Capability::LuneFs => write!(f, "lune_fs"),
+            Capability::LuneNet => write!(f, "lune_net"),
+            Capability::LuneProcess => write!(f, "lune_process"),
+            Capability::LuneSerde => write!(f, "lune_serde"),
+            Capability::LuneRoblox => write!(f, "lune_roblox"),
+            Capability::LuneDateTime => write!(f, "lune_date_time"),
+            Capability::LuneRegex => write!(f, "lune_regex"),
+        }
+    }
+}
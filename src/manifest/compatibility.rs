@@ -0,0 +1,59 @@
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// The versions of tooling a package is compatible with
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Compatibility {
+    /// The versions of pesde which can install the package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pesde: Option<VersionReq>,
+    /// The versions of Lune the package has been tested with
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tested_lune: BTreeSet<Version>,
+}
+
+impl Compatibility {
+    /// Whether no compatibility ranges are declared
+    pub fn is_empty(&self) -> bool {
+        self.pesde.is_none() && self.tested_lune.is_empty()
+    }
+}
+
+/// The version of pesde this library is, without its prerelease. Ranges never match
+/// prereleases, so `0.5.0-rc.6` is treated as `0.5.0` for `^0.5` to match it
+pub fn current_pesde_version() -> Version {
+    release_version(Version::parse(env!("CARGO_PKG_VERSION")).unwrap())
+}
+
+fn release_version(mut version: Version) -> Version {
+    version.pre = semver::Prerelease::EMPTY;
+    version.build = semver::BuildMetadata::EMPTY;
+    version
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prereleases_match_ranges_of_their_release() {
+        let version = release_version("0.5.0-rc.6+build.1".parse().unwrap());
+        assert_eq!(version, Version::new(0, 5, 0));
+
+        for req in ["^0.5", ">=0.4", "0.5.0"] {
+            assert!(
+                req.parse::<VersionReq>().unwrap().matches(&version),
+                "{req}"
+            );
+        }
+        assert!(!"<0.5.0".parse::<VersionReq>().unwrap().matches(&version));
+    }
+
+    #[test]
+    fn current_version_has_no_prerelease() {
+        let version = current_pesde_version();
+        assert!(version.pre.is_empty());
+        assert!(version.build.is_empty());
+    }
+}
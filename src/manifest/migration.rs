@@ -0,0 +1,311 @@
+use crate::manifest::Manifest;
+use std::fmt::Display;
+use toml::{Table, Value};
+
+/// The name of the manifest file used before pesde switched to TOML
+pub const LEGACY_MANIFEST_FILE_NAME: &str = "pesde.yaml";
+
+/// The dependency fields of a manifest
+const DEPENDENCY_FIELDS: &[&str] = &["dependencies", "peer_dependencies", "dev_dependencies"];
+
+/// Fields which are no longer used and are removed when migrating
+const REMOVED_FIELDS: &[&str] = &["realm", "sourcemap_generator"];
+
+/// A legacy part of a manifest which was converted to the current layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ManifestMigration {
+    /// The manifest is in the legacy YAML format
+    Yaml,
+    /// `exports` was moved into `target`
+    Exports,
+    /// `path_style` was converted into the Roblox target and its place
+    PathStyle,
+    /// The field's list of dependencies was converted into a table keyed by alias
+    DependencyList(String),
+    /// The dependency with this alias was in the `development` realm, so it was moved into
+    /// `dev_dependencies`
+    DevelopmentRealm(String),
+    /// The Wally dependency with this alias had an `index_url`, which was moved into
+    /// `wally_indices`
+    WallyIndexUrl(String),
+    /// The field is no longer used, so it was removed
+    Removed(String),
+}
+
+impl Display for ManifestMigration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ManifestMigration::Yaml => write!(
+                f,
+                "{LEGACY_MANIFEST_FILE_NAME} is deprecated, the manifest is now {}",
+                crate::MANIFEST_FILE_NAME
+            ),
+            ManifestMigration::Exports => {
+                write!(
+                    f,
+                    "`exports` is deprecated, set `lib` and `bin` in `target`"
+                )
+            }
+            ManifestMigration::PathStyle => write!(
+                f,
+                "`path_style` is deprecated, use the roblox environment in `target` and `place`"
+            ),
+            ManifestMigration::DependencyList(field) => write!(
+                f,
+                "lists of dependencies are deprecated, `{field}` should be a table keyed by alias"
+            ),
+            ManifestMigration::DevelopmentRealm(alias) => write!(
+                f,
+                "the development realm is deprecated, {alias} should be in `dev_dependencies`"
+            ),
+            ManifestMigration::WallyIndexUrl(alias) => write!(
+                f,
+                "`index_url` is deprecated, the index of {alias} should be in `wally_indices`"
+            ),
+            ManifestMigration::Removed(field) => {
+                write!(f, "`{field}` is no longer used and should be removed")
+            }
+        }
+    }
+}
+
+/// The table at the key, inserting an empty one if it's missing or not a table
+fn table_entry<'a>(table: &'a mut Table, key: &str) -> &'a mut Table {
+    let value = table
+        .entry(key)
+        .or_insert_with(|| Value::Table(Table::new()));
+
+    if !value.is_table() {
+        *value = Value::Table(Table::new());
+    }
+
+    value.as_table_mut().unwrap()
+}
+
+/// The alias a listed dependency is given, the last part of its name or repository
+fn dependency_alias(dependency: &Table) -> Option<String> {
+    let name = dependency
+        .get("name")
+        .or_else(|| dependency.get("wally"))
+        .or_else(|| dependency.get("repo"))?
+        .as_str()?;
+
+    let alias = name
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()?
+        .trim_end_matches(".git")
+        .to_string();
+
+    (!alias.is_empty()).then_some(alias)
+}
+
+/// Converts the legacy layouts in the manifest to the current one, returning what was converted
+pub fn migrate(manifest: &mut Table) -> Vec<ManifestMigration> {
+    let mut migrations = vec![];
+
+    if let Some(Value::Table(exports)) = manifest.remove("exports") {
+        let target = table_entry(manifest, "target");
+        for (key, value) in exports {
+            target.entry(key).or_insert(value);
+        }
+
+        migrations.push(ManifestMigration::Exports);
+    }
+
+    if let Some(path_style) = manifest.remove("path_style") {
+        if let Some(roblox) = path_style.get("roblox") {
+            table_entry(manifest, "target")
+                .entry("environment")
+                .or_insert_with(|| Value::String("roblox".to_string()));
+
+            if let Some(Value::Table(place)) = roblox.get("place") {
+                let manifest_place = table_entry(manifest, "place");
+                for (key, value) in place {
+                    manifest_place.entry(key).or_insert(value.clone());
+                }
+            }
+        }
+
+        migrations.push(ManifestMigration::PathStyle);
+    }
+
+    if !migrations.is_empty() {
+        // legacy manifests had no environment unless they were for Roblox
+        table_entry(manifest, "target")
+            .entry("environment")
+            .or_insert_with(|| Value::String("luau".to_string()));
+    }
+
+    for field in DEPENDENCY_FIELDS {
+        let Some(Value::Array(list)) = manifest.get(*field) else {
+            continue;
+        };
+        let list = list.clone();
+        manifest.insert(field.to_string(), Value::Table(Table::new()));
+
+        for dependency in list {
+            let Value::Table(mut dependency) = dependency else {
+                continue;
+            };
+
+            let Some(base_alias) = dependency_alias(&dependency) else {
+                continue;
+            };
+
+            let mut field = *field;
+            if dependency
+                .remove("realm")
+                .and_then(|realm| realm.as_str().map(str::to_string))
+                == Some("development".to_string())
+                && field == "dependencies"
+            {
+                field = "dev_dependencies";
+                migrations.push(ManifestMigration::DevelopmentRealm(base_alias.clone()));
+            }
+
+            if let Some(wally) = dependency.get_mut("wally") {
+                if let Some(name) = wally.as_str().and_then(|s| s.strip_prefix("wally#")) {
+                    *wally = Value::String(name.to_string());
+                }
+            }
+
+            if let Some(Value::String(index_url)) = dependency.remove("index_url") {
+                let wally_indices = table_entry(manifest, "wally_indices");
+                let index = match wally_indices
+                    .iter()
+                    .find(|(_, url)| url.as_str() == Some(&index_url))
+                {
+                    Some((name, _)) => name.clone(),
+                    None => {
+                        let name = (1..)
+                            .map(|n| match n {
+                                1 => crate::DEFAULT_INDEX_NAME.to_string(),
+                                n => format!("wally_{n}"),
+                            })
+                            .find(|name| !wally_indices.contains_key(name))
+                            .unwrap();
+                        wally_indices.insert(name.clone(), Value::String(index_url));
+                        name
+                    }
+                };
+
+                if index != crate::DEFAULT_INDEX_NAME {
+                    dependency.insert("index".to_string(), Value::String(index));
+                }
+                migrations.push(ManifestMigration::WallyIndexUrl(base_alias.clone()));
+            }
+
+            let dependencies = table_entry(manifest, field);
+            let alias = (1..)
+                .map(|n| match n {
+                    1 => base_alias.clone(),
+                    n => format!("{base_alias}_{n}"),
+                })
+                .find(|alias| !dependencies.contains_key(alias))
+                .unwrap();
+            dependencies.insert(alias, Value::Table(dependency));
+        }
+
+        migrations.push(ManifestMigration::DependencyList(field.to_string()));
+    }
+
+    for field in REMOVED_FIELDS {
+        if manifest.remove(*field).is_some() {
+            migrations.push(ManifestMigration::Removed(field.to_string()));
+        }
+    }
+
+    migrations
+}
+
+/// Parses a manifest, converting legacy layouts to the current one and returning what was
+/// converted
+pub fn parse_manifest_with_migrations(
+    contents: &str,
+) -> Result<(Manifest, Vec<ManifestMigration>), toml::de::Error> {
+    let mut table = toml::from_str::<Table>(contents)?;
+    let migrations = migrate(&mut table);
+
+    Ok((Value::Table(table).try_into()?, migrations))
+}
+
+/// Parses a manifest, converting legacy layouts to the current one
+pub fn parse_manifest(contents: &str) -> Result<Manifest, toml::de::Error> {
+    parse_manifest_with_migrations(contents).map(|(manifest, _)| manifest)
+}
+
+/// Converts a YAML value to a TOML one. Tagged values become tables with the tag as their only
+/// key, and nulls are dropped as TOML has no equivalent
+fn yaml_to_toml(value: serde_yaml::Value) -> Option<Value> {
+    use serde_yaml::Value as Yaml;
+
+    Some(match value {
+        Yaml::Null => return None,
+        Yaml::Bool(b) => Value::Boolean(b),
+        Yaml::Number(n) => match n.as_i64() {
+            Some(n) => Value::Integer(n),
+            None => Value::Float(n.as_f64()?),
+        },
+        Yaml::String(s) => Value::String(s),
+        Yaml::Sequence(values) => {
+            Value::Array(values.into_iter().filter_map(yaml_to_toml).collect())
+        }
+        Yaml::Mapping(mapping) => Value::Table(
+            mapping
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    let key = match key {
+                        Yaml::String(s) => s,
+                        Yaml::Number(n) => n.to_string(),
+                        Yaml::Bool(b) => b.to_string(),
+                        _ => return None,
+                    };
+
+                    Some((key, yaml_to_toml(value)?))
+                })
+                .collect(),
+        ),
+        Yaml::Tagged(tagged) => {
+            let tag = tagged.tag.to_string();
+            let value = yaml_to_toml(tagged.value).unwrap_or(Value::Table(Table::new()));
+
+            Value::Table(Table::from_iter([(
+                tag.trim_start_matches('!').to_string(),
+                value,
+            )]))
+        }
+    })
+}
+
+/// Converts a legacy YAML manifest to the current layout, returning it as a TOML table along with
+/// what was converted
+pub fn convert_legacy_manifest(
+    contents: &str,
+) -> Result<(Table, Vec<ManifestMigration>), toml::de::Error> {
+    let yaml = serde_yaml::from_str::<serde_yaml::Value>(contents)
+        .map_err(<toml::de::Error as serde::de::Error>::custom)?;
+
+    let Some(Value::Table(mut table)) = yaml_to_toml(yaml) else {
+        return Err(serde::de::Error::custom(
+            "legacy manifest must be a mapping",
+        ));
+    };
+
+    let migrations = std::iter::once(ManifestMigration::Yaml)
+        .chain(migrate(&mut table))
+        .collect();
+
+    Ok((table, migrations))
+}
+
+/// Parses a legacy YAML manifest, converting it to the current layout and returning what was
+/// converted
+pub fn parse_legacy_manifest(
+    contents: &str,
+) -> Result<(Manifest, Vec<ManifestMigration>), toml::de::Error> {
+    let (table, migrations) = convert_legacy_manifest(contents)?;
+
+    Ok((Value::Table(table).try_into()?, migrations))
+}
@@ -5,11 +5,21 @@ use semver::Version;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    manifest::{overrides::OverrideKey, target::Target},
+    manifest::{
+        overrides::OverrideKey,
+        target::{Target, TargetKind},
+    },
     names::PackageName,
+    scripts::ScriptName,
     source::specifiers::DependencySpecifiers,
 };
 
+/// Capabilities
+pub mod capabilities;
+/// Compatibility
+pub mod compatibility;
+/// Migrations of legacy manifests
+pub mod migration;
 /// Overrides
 pub mod overrides;
 /// Targets
@@ -34,14 +44,56 @@ pub struct Manifest {
     /// The repository of the package
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub repository: Option<url::Url>,
+    /// The keywords of the package, used when searching for packages
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub keywords: BTreeSet<String>,
+    /// The categories of the package, used when browsing packages
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub categories: BTreeSet<String>,
     /// The target of the package
     pub target: Target,
+    /// Other targets the package is published for alongside `target`, each as its own version
+    #[serde(default, skip_serializing)]
+    pub extra_targets: Vec<Target>,
     /// Whether the package is private
     #[serde(default)]
     pub private: bool,
+    /// The runtime features the package requires
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub capabilities: BTreeSet<capabilities::Capability>,
+    /// The versions of tooling the package is compatible with, checked when publishing
+    #[serde(
+        default,
+        skip_serializing_if = "compatibility::Compatibility::is_empty"
+    )]
+    pub compatibility: compatibility::Compatibility,
+    /// The version of the target's runtime the project uses, for example Lune's version
+    #[serde(default, skip_serializing)]
+    pub engine: Option<Version>,
     /// The scripts of the package
     #[serde(default, skip_serializing)]
     pub scripts: BTreeMap<String, RelativePathBuf>,
+    /// How many seconds each lifecycle hook script may run for before it is stopped
+    #[serde(default, skip_serializing)]
+    pub hook_timeouts: BTreeMap<ScriptName, u64>,
+    /// Commands to run after the dependencies have been linked, for example to regenerate sourcemaps
+    #[serde(default, skip_serializing)]
+    pub post_link: Vec<String>,
+    /// Whether to type the linking modules of libraries without exported types, such as Wally
+    /// packages, as `any`, so requiring them doesn't cause errors in strict mode
+    #[serde(default, skip_serializing)]
+    pub type_stubs: bool,
+    /// Where to write the require aliases of the dependencies when installing, for bundling tools
+    #[serde(default, skip_serializing)]
+    pub require_aliases: RequireAliases,
+    /// Where to write a Rojo-compatible sourcemap of the installed Roblox packages when
+    /// installing, for tools like luau-lsp
+    #[serde(default, skip_serializing)]
+    pub sourcemap: Option<RelativePathBuf>,
+    /// A Rojo project to map the Roblox packages folders into the game in when linking, which is
+    /// created if it doesn't exist
+    #[serde(default, skip_serializing)]
+    pub rojo_project: Option<RelativePathBuf>,
     /// The indices to use for the package
     #[serde(
         default,
@@ -58,12 +110,27 @@ pub struct Manifest {
         deserialize_with = "crate::util::deserialize_gix_url_map"
     )]
     pub wally_indices: BTreeMap<String, gix::Url>,
+    /// The name of an index in `indices` which mirrors Wally packages. Wally dependencies are
+    /// resolved from it instead when it has the version Wally would resolve to
+    #[cfg(feature = "wally-compat")]
+    #[serde(default, skip_serializing)]
+    pub wally_mirror: Option<String>,
     /// The overrides this package has
     #[serde(default, skip_serializing)]
     pub overrides: BTreeMap<OverrideKey, DependencySpecifiers>,
-    /// The files to include in the package
+    /// The patterns of files to include in the package
     #[serde(default)]
     pub includes: BTreeSet<String>,
+    /// The patterns of files to include in the package only when publishing it for a target, in
+    /// addition to `includes`
+    #[serde(default, skip_serializing)]
+    pub target_includes: BTreeMap<TargetKind, BTreeSet<String>>,
+    /// The patterns of files to exclude from the package, even if they are included
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub excludes: BTreeSet<String>,
+    /// The files the package exports besides its code
+    #[serde(default, skip_serializing_if = "Exports::is_empty")]
+    pub exports: Exports,
     /// The patches to apply to packages
     #[cfg(feature = "patches")]
     #[serde(default, skip_serializing)]
@@ -80,6 +147,17 @@ pub struct Manifest {
     /// The Roblox place of this project
     #[serde(default, skip_serializing)]
     pub place: BTreeMap<target::RobloxPlaceKind, String>,
+    /// Groups of optional dependencies, by name, which are only installed when enabled
+    #[serde(default, skip_serializing)]
+    pub features: BTreeMap<String, BTreeSet<String>>,
+    /// The licenses dependencies may have, checked by `pesde audit licenses`. Any license is
+    /// allowed if empty
+    #[serde(default, skip_serializing)]
+    pub allowed_licenses: BTreeSet<String>,
+    /// Whether using an alias for more than one type of dependency is always an error, instead of
+    /// the type with the highest precedence (standard, then peer, then dev) being used
+    #[serde(default, skip_serializing)]
+    pub strict_aliases: bool,
 
     /// The standard dependencies of the package
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
@@ -92,6 +170,33 @@ pub struct Manifest {
     pub dev_dependencies: BTreeMap<String, DependencySpecifiers>,
 }
 
+/// The files a package exports besides its code
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Exports {
+    /// The patterns of asset files, such as data or sounds, which are published with the package
+    /// and located at runtime through the assets linking module of its dependants
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub assets: BTreeSet<String>,
+}
+
+impl Exports {
+    /// Whether the package exports nothing besides its code
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+}
+
+/// Files listing the require aliases of a project's dependencies
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct RequireAliases {
+    /// A JSON file mapping each alias to the path of its linking module
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<RelativePathBuf>,
+    /// A DarkLua config containing a `convert_require` rule with the aliases as sources
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub darklua: Option<RelativePathBuf>,
+}
+
 /// A dependency type
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -104,29 +209,131 @@ pub enum DependencyType {
     Dev,
 }
 
+impl std::fmt::Display for DependencyType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DependencyType::Standard => write!(f, "standard"),
+            DependencyType::Peer => write!(f, "peer"),
+            DependencyType::Dev => write!(f, "dev"),
+        }
+    }
+}
+
+/// A dependency whose alias is also used by a dependency type with a higher precedence, which is
+/// used instead of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShadowedDependency {
+    /// The alias of the dependency
+    pub alias: String,
+    /// The type of the dependency which is used
+    pub used: DependencyType,
+    /// The type of the dependency which is ignored
+    pub ignored: DependencyType,
+}
+
+type AllDependencies = BTreeMap<String, (DependencySpecifiers, DependencyType)>;
+
 impl Manifest {
-    /// Get all dependencies from the manifest
+    /// Get all dependencies from the manifest, where an alias used by more than one dependency type
+    /// is the one of the type with the highest precedence
     pub fn all_dependencies(
         &self,
     ) -> Result<
         BTreeMap<String, (DependencySpecifiers, DependencyType)>,
         errors::AllDependenciesError,
     > {
-        let mut all_deps = BTreeMap::new();
+        self.resolve_aliases().map(|(all_deps, _)| all_deps)
+    }
+
+    /// Get the dependencies which are ignored because their alias is also used by a dependency
+    /// type with a higher precedence
+    pub fn shadowed_dependencies(
+        &self,
+    ) -> Result<Vec<ShadowedDependency>, errors::AllDependenciesError> {
+        self.resolve_aliases().map(|(_, shadowed)| shadowed)
+    }
+
+    fn resolve_aliases(
+        &self,
+    ) -> Result<(AllDependencies, Vec<ShadowedDependency>), errors::AllDependenciesError> {
+        let mut all_deps = AllDependencies::new();
+        let mut shadowed = vec![];
 
+        // the order of these is the precedence of the types
         for (deps, ty) in [
             (&self.dependencies, DependencyType::Standard),
             (&self.peer_dependencies, DependencyType::Peer),
             (&self.dev_dependencies, DependencyType::Dev),
         ] {
             for (alias, spec) in deps {
-                if all_deps.insert(alias.clone(), (spec.clone(), ty)).is_some() {
+                let Some((used_spec, used)) = all_deps.get(alias) else {
+                    all_deps.insert(alias.clone(), (spec.clone(), ty));
+                    continue;
+                };
+
+                // dev dependencies aren't installed in production, and the same specifier resolves
+                // to the same package, so only a standard and peer dependency which differ are
+                // ambiguous
+                if self.strict_aliases || (ty != DependencyType::Dev && used_spec != spec) {
                     return Err(errors::AllDependenciesError::AliasConflict(alias.clone()));
                 }
+
+                shadowed.push(ShadowedDependency {
+                    alias: alias.clone(),
+                    used: *used,
+                    ignored: ty,
+                });
             }
         }
 
-        Ok(all_deps)
+        Ok((all_deps, shadowed))
+    }
+
+    /// Get the aliases of the dependencies which are only installed with features, none of which
+    /// are among the enabled ones
+    pub fn disabled_dependencies(
+        &self,
+        enabled: &BTreeSet<String>,
+    ) -> Result<BTreeSet<String>, errors::FeaturesError> {
+        if let Some(feature) = enabled.iter().find(|f| !self.features.contains_key(*f)) {
+            return Err(errors::FeaturesError::UnknownFeature(feature.clone()));
+        }
+
+        let all_deps = self.all_dependencies()?;
+        let mut disabled = BTreeSet::new();
+
+        for (feature, aliases) in &self.features {
+            for alias in aliases {
+                match all_deps.get(alias) {
+                    None => {
+                        return Err(errors::FeaturesError::UnknownDependency {
+                            feature: feature.clone(),
+                            alias: alias.clone(),
+                        })
+                    }
+                    Some((specifier, _)) if !specifier.is_optional() => {
+                        return Err(errors::FeaturesError::NotOptional {
+                            feature: feature.clone(),
+                            alias: alias.clone(),
+                        })
+                    }
+                    Some(_) => {}
+                }
+
+                if !enabled.contains(feature) {
+                    disabled.insert(alias.clone());
+                }
+            }
+        }
+
+        // dependencies in several features are installed if any of them is enabled
+        for feature in enabled {
+            for alias in &self.features[feature] {
+                disabled.remove(alias);
+            }
+        }
+
+        Ok(disabled)
     }
 }
 
@@ -142,4 +349,147 @@ pub mod errors {
         #[error("another specifier is already using the alias {0}")]
         AliasConflict(String),
     }
+
+    /// Errors that can occur when trying to get the dependencies of a manifest's features
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum FeaturesError {
+        /// An error occurred while getting all dependencies from the manifest
+        #[error("error getting all dependencies")]
+        AllDependencies(#[from] AllDependenciesError),
+
+        /// An enabled feature isn't in the manifest
+        #[error("feature {0} not found in manifest")]
+        UnknownFeature(String),
+
+        /// A feature lists an alias which isn't a dependency
+        #[error("feature {feature} lists {alias}, which isn't a dependency")]
+        UnknownDependency {
+            /// The feature
+            feature: String,
+            /// The alias
+            alias: String,
+        },
+
+        /// A feature lists a dependency which isn't optional
+        #[error("feature {feature} lists {alias}, which isn't an optional dependency")]
+        NotOptional {
+            /// The feature
+            feature: String,
+            /// The alias
+            alias: String,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manifest(dependencies: &str) -> Manifest {
+        toml::from_str(&format!(
+            r#"name = "acme/foo"
+version = "1.0.0"
+target = {{ environment = "luau" }}
+
+{dependencies}"#
+        ))
+        .unwrap()
+    }
+
+    fn resolved(manifest: &Manifest) -> Vec<(String, String, DependencyType)> {
+        manifest
+            .all_dependencies()
+            .unwrap()
+            .into_iter()
+            .map(|(alias, (spec, ty))| (alias, spec.to_string(), ty))
+            .collect()
+    }
+
+    #[test]
+    fn standard_dependencies_shadow_dev_dependencies() {
+        let manifest = manifest(
+            r#"[dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+
+[dev_dependencies]
+bar = { name = "acme/bar", version = "^2.0.0" }
+"#,
+        );
+
+        assert_eq!(
+            resolved(&manifest),
+            [(
+                "bar".to_string(),
+                "acme/bar@^1.0.0".to_string(),
+                DependencyType::Standard
+            )]
+        );
+        assert_eq!(
+            manifest.shadowed_dependencies().unwrap(),
+            [ShadowedDependency {
+                alias: "bar".to_string(),
+                used: DependencyType::Standard,
+                ignored: DependencyType::Dev,
+            }]
+        );
+    }
+
+    #[test]
+    fn identical_peer_dependencies_are_shadowed() {
+        let manifest = manifest(
+            r#"[peer_dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+
+[dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+"#,
+        );
+
+        assert_eq!(
+            resolved(&manifest),
+            [(
+                "bar".to_string(),
+                "acme/bar@^1.0.0".to_string(),
+                DependencyType::Standard
+            )]
+        );
+        assert_eq!(manifest.shadowed_dependencies().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn differing_peer_dependencies_conflict() {
+        let manifest = manifest(
+            r#"[dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+
+[peer_dependencies]
+bar = { name = "acme/bar", version = "^2.0.0" }
+"#,
+        );
+
+        assert!(matches!(
+            manifest.all_dependencies(),
+            Err(errors::AllDependenciesError::AliasConflict(alias)) if alias == "bar"
+        ));
+    }
+
+    #[test]
+    fn strict_aliases_conflict() {
+        let manifest = manifest(
+            r#"strict_aliases = true
+
+[dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+
+[dev_dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+"#,
+        );
+
+        assert!(matches!(
+            manifest.shadowed_dependencies(),
+            Err(errors::AllDependenciesError::AliasConflict(alias)) if alias == "bar"
+        ));
+    }
 }
@@ -1,9 +1,10 @@
-use relative_path::RelativePathBuf;
+use relative_path::{Component, RelativePath, RelativePathBuf};
 use serde::{Deserialize, Serialize};
 use serde_with::{DeserializeFromStr, SerializeDisplay};
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{Display, Formatter},
+    path::Path,
     str::FromStr,
 };
 
@@ -56,6 +57,11 @@ impl TargetKind {
         TargetKind::Luau,
     ];
 
+    /// Whether targets of this kind have build files
+    pub fn has_build_files(&self) -> bool {
+        matches!(self, TargetKind::Roblox | TargetKind::RobloxServer)
+    }
+
     /// The folder to store packages in for this target
     /// self is the project's target, dependency is the target of the dependency
     pub fn packages_folder(&self, dependency: &Self) -> String {
@@ -100,6 +106,9 @@ pub enum Target {
         /// The path to the bin export file
         #[serde(default)]
         bin: Option<RelativePathBuf>,
+        /// The paths to additional bin export files, keyed by their name
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        bins: BTreeMap<String, RelativePathBuf>,
     },
     /// A Luau target
     Luau {
@@ -109,6 +118,9 @@ pub enum Target {
         /// The path to the bin export file
         #[serde(default)]
         bin: Option<RelativePathBuf>,
+        /// The paths to additional bin export files, keyed by their name
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        bins: BTreeMap<String, RelativePathBuf>,
     },
 }
 
@@ -143,6 +155,21 @@ impl Target {
         }
     }
 
+    /// Returns the named bin export files, keyed by their name
+    pub fn named_bins(&self) -> Option<&BTreeMap<String, RelativePathBuf>> {
+        match self {
+            Target::Roblox { .. } => None,
+            Target::RobloxServer { .. } => None,
+            Target::Lune { bins, .. } => Some(bins),
+            Target::Luau { bins, .. } => Some(bins),
+        }
+    }
+
+    /// Whether the target has any bin export, named or not
+    pub fn has_bins(&self) -> bool {
+        self.bin_path().is_some() || self.named_bins().is_some_and(|bins| !bins.is_empty())
+    }
+
     /// Returns the Roblox build files
     pub fn build_files(&self) -> Option<&BTreeSet<String>> {
         match self {
@@ -151,6 +178,94 @@ impl Target {
             _ => None,
         }
     }
+
+    /// Normalizes the export paths and build files of the target, checking that they point at
+    /// existing files within the package directory
+    pub fn validate_exports(&mut self, package_dir: &Path) -> Result<(), errors::ExportError> {
+        fn normalize(
+            name: &'static str,
+            path: &RelativePath,
+        ) -> Result<RelativePathBuf, errors::ExportError> {
+            let normalized = path.normalize();
+
+            match normalized.components().next() {
+                Some(Component::Normal(_)) => Ok(normalized),
+                _ => Err(errors::ExportError::OutsidePackage(name, path.to_string())),
+            }
+        }
+
+        fn normalize_export(
+            name: &'static str,
+            path: &mut Option<RelativePathBuf>,
+            package_dir: &Path,
+        ) -> Result<(), errors::ExportError> {
+            let Some(path) = path else {
+                return Ok(());
+            };
+
+            let normalized = normalize(name, path)?;
+
+            if !normalized.to_path(package_dir).is_file() {
+                return Err(errors::ExportError::NotAFile(name, path.to_string()));
+            }
+
+            *path = normalized;
+
+            Ok(())
+        }
+
+        match self {
+            Target::Roblox { lib, build_files } | Target::RobloxServer { lib, build_files } => {
+                normalize_export("lib path", lib, package_dir)?;
+
+                *build_files = build_files
+                    .iter()
+                    .map(|build_file| {
+                        let normalized = normalize("build file", RelativePath::new(build_file))?;
+
+                        if !normalized.to_path(package_dir).exists() {
+                            return Err(errors::ExportError::BuildFileNotFound(
+                                build_file.to_string(),
+                            ));
+                        }
+
+                        Ok(normalized.into_string())
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                // Roblox packages only contain their build files once synced
+                if let Some(lib) = lib {
+                    if !build_files
+                        .iter()
+                        .any(|build_file| lib.starts_with(build_file))
+                    {
+                        return Err(errors::ExportError::NotInBuildFiles(lib.to_string()));
+                    }
+                }
+            }
+            Target::Lune { lib, bin, bins } | Target::Luau { lib, bin, bins } => {
+                normalize_export("lib path", lib, package_dir)?;
+                normalize_export("bin path", bin, package_dir)?;
+
+                for (name, path) in bins {
+                    // the name is used for the linking module and shims
+                    if name.is_empty()
+                        || !name
+                            .chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        return Err(errors::ExportError::InvalidBinName(name.clone()));
+                    }
+
+                    let mut export = Some(path.clone());
+                    normalize_export("bin path", &mut export, package_dir)?;
+                    *path = export.unwrap();
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl Display for Target {
@@ -204,4 +319,30 @@ pub mod errors {
         #[error("unknown target kind {0}")]
         Unknown(String),
     }
+
+    /// Errors that can occur when validating the exports of a target
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum ExportError {
+        /// An export path or build file points outside the package
+        #[error("{0} {1} points outside the package")]
+        OutsidePackage(&'static str, String),
+
+        /// An export path doesn't point at a file
+        #[error("{0} {1} doesn't point at a file")]
+        NotAFile(&'static str, String),
+
+        /// A build file doesn't exist
+        #[error("build file {0} does not exist")]
+        BuildFileNotFound(String),
+
+        /// The name of a named bin export isn't made of alphanumeric characters, underscores and
+        /// hyphens
+        #[error("bin name {0} must only contain alphanumeric characters, underscores and hyphens")]
+        InvalidBinName(String),
+
+        /// The lib path of a Roblox target isn't within any of its build files
+        #[error("lib path {0} is not within any build file")]
+        NotInBuildFiles(String),
+    }
 }
@@ -20,7 +20,7 @@ impl Display for ErrorReason {
     }
 }
 
-/// A pesde package name
+/// A pesde package name. Names are case-insensitive, so they are stored in lowercase
 #[derive(
     Debug, DeserializeFromStr, SerializeDisplay, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
 )]
@@ -52,7 +52,7 @@ impl FromStr for PackageName {
             }
         }
 
-        Ok(Self(scope.to_string(), name.to_string()))
+        Ok(Self(scope.to_ascii_lowercase(), name.to_ascii_lowercase()))
     }
 }
 
@@ -151,7 +151,7 @@ pub mod wally {
 
     use crate::names::{errors, ErrorReason};
 
-    /// A Wally package name
+    /// A Wally package name. Names are case-insensitive, so they are stored in lowercase
     #[derive(
         Debug, DeserializeFromStr, SerializeDisplay, Clone, PartialEq, Eq, Hash, PartialOrd, Ord,
     )]
@@ -177,7 +177,7 @@ pub mod wally {
                 }
             }
 
-            Ok(Self(scope.to_string(), name.to_string()))
+            Ok(Self(scope.to_ascii_lowercase(), name.to_ascii_lowercase()))
         }
     }
 
@@ -213,7 +213,7 @@ pub mod errors {
         #[error("package name `{0}` is not in the format `scope/name`")]
         InvalidFormat(String),
 
-        /// The package name is outside the allowed characters: a-z, 0-9, and _
+        /// The package name is outside the allowed characters: a-z, 0-9, and _, ignoring case
         #[error("package {0} `{1}` contains characters outside a-z, 0-9, and _")]
         InvalidCharacters(ErrorReason, String),
 
@@ -238,7 +238,7 @@ pub mod errors {
         #[error("wally package name `{0}` is not in the format `scope/name`")]
         InvalidFormat(String),
 
-        /// The package name is outside the allowed characters: a-z, 0-9, and -
+        /// The package name is outside the allowed characters: a-z, 0-9, and -, ignoring case
         #[error("wally package {0} `{1}` contains characters outside a-z, 0-9, and -")]
         InvalidCharacters(ErrorReason, String),
 
@@ -1,10 +1,46 @@
 use crate::{
-    lockfile::DownloadedGraph, source::traits::PackageRef, Project, MANIFEST_FILE_NAME,
-    PACKAGES_CONTAINER_NAME,
+    events::Event,
+    lockfile::{DownloadedGraph, Graph},
+    manifest::migration::parse_manifest_with_migrations,
+    names::PackageNames,
+    source::{
+        refs::PackageRefs,
+        traits::{PackageRef, PackageSource},
+        version_id::VersionId,
+    },
+    Project, MANIFEST_FILE_NAME, PACKAGES_CONTAINER_NAME,
+};
+use git2::{
+    ApplyLocation, ApplyOptions, Diff, DiffFormat, DiffLineType, IndexAddOption, Repository,
+    Signature, Tree,
 };
-use git2::{ApplyLocation, ApplyOptions, Diff, DiffFormat, DiffLineType, Repository, Signature};
 use relative_path::RelativePathBuf;
-use std::{fs::read, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs::read,
+    path::{Path, PathBuf},
+};
+
+/// A patch which applies to a project
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectPatch {
+    /// The directory of the project whose manifest declares the patch, which is the workspace
+    /// root for inherited patches
+    pub owner_dir: PathBuf,
+    /// The path to the patch file, relative to `owner_dir`
+    pub path: RelativePathBuf,
+}
+
+impl ProjectPatch {
+    /// The path to the patch file
+    pub fn file(&self) -> PathBuf {
+        self.path.to_path(&self.owner_dir)
+    }
+}
+
+/// The patches which apply to a project, by package and version. Workspace members inherit the
+/// patches of the workspace root, and their own patches for the same versions override them
+pub type ProjectPatches = BTreeMap<PackageNames, BTreeMap<VersionId, ProjectPatch>>;
 
 /// Set up a git repository for patches
 pub fn setup_patches_repo<P: AsRef<Path>>(dir: P) -> Result<Repository, git2::Error> {
@@ -35,20 +71,8 @@ pub fn setup_patches_repo<P: AsRef<Path>>(dir: P) -> Result<Repository, git2::Er
     Ok(repo)
 }
 
-/// Create a patch from the current state of the repository
-pub fn create_patch<P: AsRef<Path>>(dir: P) -> Result<Vec<u8>, git2::Error> {
+fn diff_to_patch(diff: &Diff) -> Result<Vec<u8>, git2::Error> {
     let mut patches = vec![];
-    let repo = Repository::open(dir.as_ref())?;
-
-    let original = repo.head()?.peel_to_tree()?;
-
-    // reset the manifest file to the original state
-    let mut checkout_builder = git2::build::CheckoutBuilder::new();
-    checkout_builder.force();
-    checkout_builder.path(MANIFEST_FILE_NAME);
-    repo.checkout_tree(original.as_object(), Some(&mut checkout_builder))?;
-
-    let diff = repo.diff_tree_to_workdir(Some(&original), None)?;
 
     diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
         if matches!(
@@ -69,14 +93,202 @@ pub fn create_patch<P: AsRef<Path>>(dir: P) -> Result<Vec<u8>, git2::Error> {
     Ok(patches)
 }
 
+/// Create a patch from the current state of the repository
+pub fn create_patch<P: AsRef<Path>>(dir: P) -> Result<Vec<u8>, git2::Error> {
+    let repo = Repository::open(dir.as_ref())?;
+
+    let original = repo.head()?.peel_to_tree()?;
+
+    // reset the manifest file to the original state
+    let mut checkout_builder = git2::build::CheckoutBuilder::new();
+    checkout_builder.force();
+    checkout_builder.path(MANIFEST_FILE_NAME);
+    repo.checkout_tree(original.as_object(), Some(&mut checkout_builder))?;
+
+    let diff = repo.diff_tree_to_workdir(Some(&original), None)?;
+
+    diff_to_patch(&diff)
+}
+
+/// Attaches the directory of the manifest declaring them to the patches
+fn with_owner(
+    owner_dir: &Path,
+    patches: BTreeMap<PackageNames, BTreeMap<VersionId, RelativePathBuf>>,
+) -> ProjectPatches {
+    patches
+        .into_iter()
+        .map(|(name, versions)| {
+            (
+                name,
+                versions
+                    .into_iter()
+                    .map(|(version_id, path)| {
+                        (
+                            version_id,
+                            ProjectPatch {
+                                owner_dir: owner_dir.to_path_buf(),
+                                path,
+                            },
+                        )
+                    })
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
+/// A patch for a version of a package which isn't in the graph, while another version of it is
+#[derive(Debug, Clone)]
+pub struct StalePatch {
+    /// The name of the patched package
+    pub name: PackageNames,
+    /// The version the patch was made for
+    pub patched: VersionId,
+    /// The version the package resolved to
+    pub resolved: VersionId,
+    /// The patch
+    pub patch: ProjectPatch,
+}
+
+/// Finds the patches whose package resolved to a different version. Packages which already have a
+/// patch for the resolved version are skipped
+pub fn stale_patches<Node>(patches: &ProjectPatches, graph: &Graph<Node>) -> Vec<StalePatch> {
+    let mut stale = vec![];
+
+    for (name, versions) in patches {
+        let Some(resolved_versions) = graph.get(name) else {
+            continue;
+        };
+
+        for (version_id, patch) in versions {
+            if resolved_versions.contains_key(version_id) {
+                continue;
+            }
+
+            let Some(resolved) = resolved_versions
+                .keys()
+                .filter(|resolved| resolved.target() == version_id.target())
+                .max_by(|a, b| a.version().cmp(b.version()))
+            else {
+                continue;
+            };
+
+            if versions.contains_key(resolved) {
+                continue;
+            }
+
+            stale.push(StalePatch {
+                name: name.clone(),
+                patched: version_id.clone(),
+                resolved: resolved.clone(),
+                patch: patch.clone(),
+            });
+        }
+    }
+
+    stale
+}
+
+/// The outcome of carrying a patch over to another version of its package
+#[derive(Debug, Clone)]
+pub enum ReappliedPatch {
+    /// The patch merged cleanly, resulting in this patch for the new version. It is empty if the
+    /// new version already contains the patch's changes
+    Clean(Vec<u8>),
+    /// The changes of the patch conflict with the changes of the new version in these files
+    Conflicts(Vec<RelativePathBuf>),
+}
+
+fn tree_of<'a>(repo: &'a Repository, dir: &Path) -> Result<Tree<'a>, git2::Error> {
+    repo.set_workdir(dir, false)?;
+
+    let mut index = repo.index()?;
+    index.clear()?;
+    index.add_all(["*"], IndexAddOption::FORCE, None)?;
+    let oid = index.write_tree()?;
+
+    repo.find_tree(oid)
+}
+
+/// Carries a patch made for the package in `base` over to the package in `updated` with a
+/// three-way merge
+pub fn reapply_patch<P: AsRef<Path>, Q: AsRef<Path>>(
+    patch: &[u8],
+    base: P,
+    updated: Q,
+) -> Result<ReappliedPatch, errors::ReapplyPatchError> {
+    let git_dir = crate::util::tempdirs::tempdir()?;
+    let repo = Repository::init_bare(git_dir.path())?;
+
+    let base_tree = tree_of(&repo, base.as_ref())?;
+    let updated_tree = tree_of(&repo, updated.as_ref())?;
+
+    let patched_oid = repo
+        .apply_to_tree(&base_tree, &Diff::from_buffer(patch)?, None)?
+        .write_tree_to(&repo)?;
+    let patched_tree = repo.find_tree(patched_oid)?;
+
+    let mut merged = repo.merge_trees(&base_tree, &updated_tree, &patched_tree, None)?;
+
+    if merged.has_conflicts() {
+        let mut files = vec![];
+
+        for conflict in merged.conflicts()? {
+            let conflict = conflict?;
+            let Some(entry) = conflict.our.or(conflict.their).or(conflict.ancestor) else {
+                continue;
+            };
+
+            files.push(RelativePathBuf::from(
+                String::from_utf8_lossy(&entry.path).into_owned(),
+            ));
+        }
+
+        return Ok(ReappliedPatch::Conflicts(files));
+    }
+
+    let merged_tree = repo.find_tree(merged.write_tree_to(&repo)?)?;
+    let diff = repo.diff_tree_to_tree(Some(&updated_tree), Some(&merged_tree), None)?;
+
+    Ok(ReappliedPatch::Clean(diff_to_patch(&diff)?))
+}
+
 impl Project {
+    /// The patches which apply to the project, see [ProjectPatches]
+    pub fn patches(&self) -> Result<ProjectPatches, crate::errors::ManifestReadError> {
+        let mut patches = ProjectPatches::new();
+
+        if let Some(workspace_dir) = self.workspace_dir() {
+            let root_manifest =
+                match std::fs::read_to_string(workspace_dir.join(MANIFEST_FILE_NAME)) {
+                    Ok(string) => Some(parse_manifest_with_migrations(&string)?.0),
+                    // the root may still use the legacy manifest, which can't declare patches
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+                    Err(e) => return Err(e.into()),
+                };
+
+            if let Some(root_manifest) = root_manifest {
+                patches.extend(with_owner(workspace_dir, root_manifest.patches));
+            }
+        }
+
+        for (name, versions) in with_owner(self.package_dir(), self.deser_manifest()?.patches) {
+            patches.entry(name).or_default().extend(versions);
+        }
+
+        Ok(patches)
+    }
+
     /// Apply patches to the project's dependencies
     pub fn apply_patches(&self, graph: &DownloadedGraph) -> Result<(), errors::ApplyPatchesError> {
         let manifest = self.deser_manifest()?;
+        let patches = self.patches()?;
+        let stale = stale_patches(&patches, graph);
 
-        for (name, versions) in manifest.patches {
-            for (version_id, patch_path) in versions {
-                let patch_path = patch_path.to_path(self.package_dir());
+        for (name, versions) in patches {
+            for (version_id, patch) in versions {
+                let patch_path = patch.file();
+                let inherited = patch.owner_dir != self.package_dir();
                 let patch = Diff::from_buffer(&read(&patch_path).map_err(|e| {
                     errors::ApplyPatchesError::PatchReadError(patch_path.clone(), e)
                 })?)?;
@@ -85,9 +297,22 @@ impl Project {
                     .get(&name)
                     .and_then(|versions| versions.get(&version_id))
                 else {
-                    log::warn!(
-                        "patch for {name}@{version_id} not applied because it is not in the graph"
-                    );
+                    match stale.iter().find(|stale| {
+                        stale.name == name && stale.patched == version_id
+                    }) {
+                        Some(stale) => log::warn!(
+                            "patch for {name}@{version_id} not applied because {name} resolved to {}, run `{} update {name}` to carry it over",
+                            stale.resolved,
+                            env!("CARGO_PKG_NAME")
+                        ),
+                        // members don't need to depend on every package the workspace patches
+                        None if inherited => log::debug!(
+                            "inherited patch for {name}@{version_id} not applied because it is not in the graph"
+                        ),
+                        None => log::warn!(
+                            "patch for {name}@{version_id} not applied because it is not in the graph"
+                        ),
+                    }
                     continue;
                 };
 
@@ -147,11 +372,44 @@ impl Project {
                 std::fs::remove_dir_all(container_folder.join(".git")).map_err(|e| {
                     errors::ApplyPatchesError::GitDirectoryRemovalError(container_folder, e)
                 })?;
+
+                self.emit(|| Event::PatchApplied {
+                    name: name.clone(),
+                    version_id: version_id.clone(),
+                });
             }
         }
 
         Ok(())
     }
+
+    /// Downloads the package the patch was made for and the package it resolved to, then carries
+    /// the patch over to the latter
+    pub fn reapply_patch(
+        &self,
+        stale: &StalePatch,
+        patched_ref: &PackageRefs,
+        resolved_ref: &PackageRefs,
+        reqwest: &reqwest::blocking::Client,
+    ) -> Result<ReappliedPatch, errors::ReapplyPatchError> {
+        let patch_path = stale.patch.file();
+        let patch = read(&patch_path)
+            .map_err(|e| errors::ReapplyPatchError::PatchReadError(patch_path, e))?;
+
+        let base = crate::util::tempdirs::tempdir()?;
+        let updated = crate::util::tempdirs::tempdir()?;
+
+        for (pkg_ref, dir) in [(patched_ref, &base), (resolved_ref, &updated)] {
+            pkg_ref
+                .source()
+                .download(pkg_ref, self, reqwest)
+                .map_err(Box::new)?
+                .0
+                .write_to(dir.path(), self.cas_dir(), false)?;
+        }
+
+        reapply_patch(&patch, base.path(), updated.path())
+    }
 }
 
 /// Errors that can occur when using patches
@@ -180,4 +438,114 @@ pub mod errors {
         #[error("error removing .git directory")]
         GitDirectoryRemovalError(PathBuf, #[source] std::io::Error),
     }
+
+    /// Errors that can occur when carrying a patch over to another version
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum ReapplyPatchError {
+        /// Error interacting with git
+        #[error("error interacting with git")]
+        GitError(#[from] git2::Error),
+
+        /// Error reading the patch file
+        #[error("error reading patch file at {0}")]
+        PatchReadError(PathBuf, #[source] std::io::Error),
+
+        /// Error downloading a version of the package
+        #[error("error downloading package")]
+        DownloadFailed(#[from] Box<crate::source::errors::DownloadError>),
+
+        /// An IO error occurred
+        #[error("IO error")]
+        Io(#[from] std::io::Error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATCH: &str = "diff --git a/lib.luau b/lib.luau
+--- a/lib.luau
++++ b/lib.luau
+@@ -1,3 +1,3 @@
+-local a = 1
++local a = 2
+ local b = 1
+ local c = 1
+diff --git a/ignored.luau b/ignored.luau
+--- a/ignored.luau
++++ b/ignored.luau
+@@ -1 +1 @@
+-return 1
++return 2
+";
+
+    fn package(lib: &str) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.luau"), lib).unwrap();
+        // files ignored by the package's own .gitignore are still part of it
+        std::fs::write(dir.path().join(".gitignore"), "ignored.luau\n").unwrap();
+        std::fs::write(dir.path().join("ignored.luau"), "return 1\n").unwrap();
+        dir
+    }
+
+    fn lib(lines: [&str; 5]) -> String {
+        lines.map(|line| format!("{line}\n")).concat()
+    }
+
+    #[test]
+    fn reapplies_patch_to_new_version() {
+        let base = package(&lib([
+            "local a = 1",
+            "local b = 1",
+            "local c = 1",
+            "local d = 1",
+            "local e = 1",
+        ]));
+        let updated = package(&lib([
+            "local a = 1",
+            "local b = 1",
+            "local c = 1",
+            "local d = 1",
+            "local e = 2",
+        ]));
+
+        let ReappliedPatch::Clean(patch) =
+            reapply_patch(PATCH.as_bytes(), base.path(), updated.path()).unwrap()
+        else {
+            panic!("patch should reapply cleanly");
+        };
+        let patch = String::from_utf8(patch).unwrap();
+
+        assert!(patch.contains("-local a = 1\n+local a = 2\n"));
+        assert!(patch.contains("-return 1\n+return 2\n"));
+        assert!(!patch.contains("local e = 2"));
+    }
+
+    #[test]
+    fn reports_conflicting_files() {
+        let base = package(&lib([
+            "local a = 1",
+            "local b = 1",
+            "local c = 1",
+            "local d = 1",
+            "local e = 1",
+        ]));
+        let updated = package(&lib([
+            "local a = 3",
+            "local b = 1",
+            "local c = 1",
+            "local d = 1",
+            "local e = 1",
+        ]));
+
+        let ReappliedPatch::Conflicts(files) =
+            reapply_patch(PATCH.as_bytes(), base.path(), updated.path()).unwrap()
+        else {
+            panic!("patch should conflict");
+        };
+
+        assert_eq!(files, [RelativePathBuf::from("lib.luau")]);
+    }
 }
@@ -0,0 +1,1303 @@
+#[cfg(feature = "registry-client")]
+use crate::registry_client::{errors::RegistryError, RegistryClient};
+use crate::{
+    lockfile::Lockfile,
+    manifest::{
+        compatibility::current_pesde_version,
+        migration::parse_manifest,
+        target::{Target, TargetKind},
+        DependencyType, Manifest,
+    },
+    scripts::ScriptName,
+    source::{
+        git_index::GitBasedSource,
+        pesde::{specifier::PesdeDependencySpecifier, IndexConfig, IndexFile, PesdePackageSource},
+        specifiers::DependencySpecifiers,
+        traits::PackageSource,
+        workspace::{
+            specifier::{VersionType, VersionTypeOrReq},
+            WorkspacePackageSource,
+        },
+        IGNORED_DIRS, IGNORED_FILES,
+    },
+    Project, DEFAULT_INDEX_NAME, MANIFEST_FILE_NAME,
+};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use relative_path::{RelativePath, RelativePathBuf};
+#[cfg(feature = "registry-client")]
+use reqwest::StatusCode;
+use semver::VersionReq;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    path::{Component, Path},
+};
+
+/// Paths which are never published, either because they break pesde's functionality or are
+/// specific to the machine of the publisher
+pub fn default_excludes() -> Vec<String> {
+    IGNORED_FILES
+        .iter()
+        .chain(IGNORED_DIRS)
+        .map(|path| path.to_string())
+        // junk created by the OS and nested repositories, wherever they are
+        .chain(["**/.DS_Store".to_string(), "**/.git".to_string()])
+        .chain(
+            TargetKind::VARIANTS
+                .iter()
+                .map(|kind| TargetKind::Luau.packages_folder(kind)),
+        )
+        .collect()
+}
+
+fn has_glob_syntax(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Builds a set matching the patterns and everything within directories they match
+fn build_set<'a, I: IntoIterator<Item = &'a str>>(
+    patterns: I,
+) -> Result<GlobSet, errors::FileSelectorError> {
+    let mut builder = GlobSetBuilder::new();
+
+    for pattern in patterns {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+
+        for pattern in [pattern.to_string(), format!("{pattern}/**")] {
+            builder.add(
+                GlobBuilder::new(&pattern)
+                    .literal_separator(true)
+                    .build()
+                    .map_err(|e| errors::FileSelectorError::InvalidPattern(pattern.clone(), e))?,
+            );
+        }
+    }
+
+    builder.build().map_err(errors::FileSelectorError::Build)
+}
+
+/// Selects the files of a package to publish from include and exclude patterns, respecting
+/// `.gitignore` files
+#[derive(Debug, Clone)]
+pub struct FileSelector {
+    includes: GlobSet,
+    /// Includes without glob syntax, which are published even if ignored by Git
+    literal_includes: Vec<String>,
+    excludes: GlobSet,
+    default_excludes: GlobSet,
+}
+
+impl FileSelector {
+    /// Create a new `FileSelector` from the include and exclude patterns of a manifest. Patterns
+    /// matching a directory select everything within it
+    pub fn new(
+        includes: &BTreeSet<String>,
+        excludes: &BTreeSet<String>,
+    ) -> Result<Self, errors::FileSelectorError> {
+        Ok(FileSelector {
+            includes: build_set(includes.iter().map(String::as_str))?,
+            literal_includes: includes
+                .iter()
+                .filter(|pattern| !has_glob_syntax(pattern))
+                .map(|pattern| {
+                    pattern
+                        .trim_start_matches("./")
+                        .trim_end_matches('/')
+                        .to_string()
+                })
+                .collect(),
+            excludes: build_set(excludes.iter().map(String::as_str))?,
+            default_excludes: build_set(default_excludes().iter().map(String::as_str))?,
+        })
+    }
+
+    /// Whether the path is never published
+    pub fn is_default_excluded(&self, path: &RelativePath) -> bool {
+        self.default_excludes.is_match(path.as_str())
+    }
+
+    /// Whether the path is selected by the patterns, not taking `.gitignore` files into account
+    pub fn is_selected(&self, path: &RelativePath) -> bool {
+        self.includes.is_match(path.as_str())
+            && !self.excludes.is_match(path.as_str())
+            && !self.is_default_excluded(path)
+    }
+
+    /// The files within the directory to publish, relative to it
+    pub fn select(&self, dir: &Path) -> Result<BTreeSet<RelativePathBuf>, errors::SelectError> {
+        let mut files = BTreeSet::new();
+
+        let mut walk = |root: &Path, git_ignore: bool| -> Result<(), errors::SelectError> {
+            for entry in ignore::WalkBuilder::new(root)
+                .standard_filters(false)
+                .git_ignore(git_ignore)
+                .git_exclude(git_ignore)
+                .parents(git_ignore)
+                .require_git(false)
+                .filter_entry(|entry| entry.file_name() != ".git")
+                .build()
+            {
+                let entry = entry?;
+
+                if !entry.file_type().is_some_and(|ty| ty.is_file()) {
+                    continue;
+                }
+
+                let Some(path) = entry
+                    .path()
+                    .strip_prefix(dir)
+                    .ok()
+                    .and_then(|path| RelativePathBuf::from_path(path).ok())
+                else {
+                    continue;
+                };
+
+                if self.is_selected(&path) {
+                    files.insert(path);
+                }
+            }
+
+            Ok(())
+        };
+
+        walk(dir, true)?;
+
+        // these were named explicitly, so they're published even if Git ignores them
+        for literal in &self.literal_includes {
+            let path = RelativePath::new(literal).to_path(dir);
+
+            if path.exists() {
+                walk(&path, false)?;
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// The files within the directory matched by the includes which aren't published, relative
+    /// to it, with why they aren't
+    pub fn stripped(
+        &self,
+        dir: &Path,
+    ) -> Result<BTreeMap<RelativePathBuf, StripReason>, errors::SelectError> {
+        let selected = self.select(dir)?;
+        let mut stripped = BTreeMap::new();
+
+        for entry in ignore::WalkBuilder::new(dir)
+            .standard_filters(false)
+            .filter_entry(|entry| entry.file_name() != ".git")
+            .build()
+        {
+            let entry = entry?;
+
+            if !entry.file_type().is_some_and(|ty| ty.is_file()) {
+                continue;
+            }
+
+            let Some(path) = entry
+                .path()
+                .strip_prefix(dir)
+                .ok()
+                .and_then(|path| RelativePathBuf::from_path(path).ok())
+            else {
+                continue;
+            };
+
+            if !self.includes.is_match(path.as_str()) || selected.contains(&path) {
+                continue;
+            }
+
+            let reason = if self.is_default_excluded(&path) {
+                StripReason::NeverPublished
+            } else if self.excludes.is_match(path.as_str()) {
+                StripReason::Excluded
+            } else {
+                StripReason::GitIgnored
+            };
+
+            stripped.insert(path, reason);
+        }
+
+        Ok(stripped)
+    }
+}
+
+/// Why a file matched by the includes isn't published
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StripReason {
+    /// The file is never published, such as toolchain manager manifests
+    NeverPublished,
+    /// The file is matched by the excludes
+    Excluded,
+    /// The file is ignored by Git, and wasn't included explicitly
+    GitIgnored,
+}
+
+impl Display for StripReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StripReason::NeverPublished => write!(f, "never published"),
+            StripReason::Excluded => write!(f, "excluded"),
+            StripReason::GitIgnored => write!(f, "ignored by git"),
+        }
+    }
+}
+
+/// A problem with a package which doesn't prevent it from being published, some of which were
+/// fixed in the published manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PublishWarning {
+    /// The manifest wasn't in the includes, so it was added
+    ManifestNotIncluded,
+    /// `.git` was in the includes, so it was removed
+    GitIncluded,
+    /// No README file is in the includes
+    NoReadme,
+    /// No docs directory is in the includes
+    NoDocs,
+    /// `default.project.json` was in the includes, so it was removed
+    SyncConfigIncluded,
+    /// A path which is never published was in the includes, so it was removed
+    IgnoredPathIncluded(String),
+    /// An export wasn't included, so the first part of its path was added to the includes
+    ExportNotIncluded {
+        /// The name of the export
+        export: &'static str,
+        /// The path added to the includes
+        added: String,
+    },
+    /// An export wasn't in the build files, so the first part of its path was added to them
+    ExportNotInBuildFiles {
+        /// The name of the export
+        export: &'static str,
+        /// The path added to the build files
+        added: String,
+    },
+    /// The manifest is in the build files
+    ManifestInBuildFiles,
+}
+
+impl Display for PublishWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublishWarning::ManifestNotIncluded => {
+                write!(f, "{MANIFEST_FILE_NAME} was not in includes, adding it")
+            }
+            PublishWarning::GitIncluded => write!(f, ".git was in includes, removing it"),
+            PublishWarning::NoReadme => {
+                write!(f, "no README file in includes, consider adding one")
+            }
+            PublishWarning::NoDocs => {
+                write!(f, "no docs directory in includes, consider adding one")
+            }
+            PublishWarning::SyncConfigIncluded => write!(
+                f,
+                "default.project.json was in includes, this should be generated by the {} script upon dependants installation",
+                ScriptName::RobloxSyncConfigGenerator
+            ),
+            PublishWarning::IgnoredPathIncluded(path) => write!(
+                f,
+                "{path} was in includes, removing it. if this was a toolchain manager's manifest file, do not include it due to it possibly messing with user scripts. otherwise, the file was deemed unnecessary, if you don't understand why, please contact the maintainers"
+            ),
+            PublishWarning::ExportNotIncluded { export, added } => {
+                write!(f, "{export} was not in includes, adding {added}")
+            }
+            PublishWarning::ExportNotInBuildFiles { export, added } => {
+                write!(f, "{export} was not in build files, adding {added}")
+            }
+            PublishWarning::ManifestInBuildFiles => {
+                write!(f, "{MANIFEST_FILE_NAME} is in build files, please remove it")
+            }
+        }
+    }
+}
+
+/// A package checked and packed for publishing
+#[derive(Debug, Clone)]
+pub struct PreparedPackage {
+    /// The manifest as it is published, with the fixes described by the warnings applied,
+    /// workspace dependencies converted to pesde ones and index names replaced by their URLs
+    pub manifest: Manifest,
+    /// The files of the package, relative to its directory, apart from the manifest
+    pub files: BTreeSet<RelativePathBuf>,
+    /// The problems found with the package
+    pub warnings: Vec<PublishWarning>,
+    /// The gzipped tarball of the package
+    pub archive: Vec<u8>,
+}
+
+/// Options for publishing a package
+#[derive(Debug, Clone)]
+pub struct PublishOptions {
+    index: String,
+    dry_run: bool,
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        PublishOptions {
+            index: DEFAULT_INDEX_NAME.to_string(),
+            dry_run: false,
+        }
+    }
+}
+
+impl PublishOptions {
+    /// Create new default publish options
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the name of the index in the manifest to publish to
+    pub fn with_index<S: Into<String>>(mut self, index: S) -> Self {
+        self.index = index.into();
+        self
+    }
+
+    /// Sets whether to only check the archive the way the registry would, without contacting it
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// The name of the index to publish to
+    pub fn index(&self) -> &str {
+        &self.index
+    }
+
+    /// Whether this is a dry run
+    pub fn dry_run(&self) -> bool {
+        self.dry_run
+    }
+}
+
+/// The result of publishing a package
+#[derive(Debug, Clone)]
+pub enum PublishOutcome {
+    /// The package was published, with the registry's response
+    Published(String),
+    /// The package wasn't uploaded because of a dry run, with the files of each package's
+    /// archive and their sizes, in the order of the packages
+    DryRun(Vec<Vec<(RelativePathBuf, u64)>>),
+}
+
+/// Lists the files in the archive with their sizes, checking it the same way the registry would
+pub fn inspect_archive(
+    archive: &[u8],
+) -> Result<Vec<(RelativePathBuf, u64)>, errors::InspectArchiveError> {
+    let dir = crate::util::tempdirs::tempdir()?;
+    let mut files = vec![];
+    let default_excludes = FileSelector::new(&BTreeSet::new(), &BTreeSet::new())?;
+
+    {
+        let mut decoder = flate2::read::GzDecoder::new(archive);
+        let mut archive = tar::Archive::new(&mut decoder);
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let path = RelativePathBuf::from(entry.path()?.to_string_lossy().replace('\\', "/"));
+
+            if default_excludes.is_default_excluded(&path) {
+                return Err(errors::InspectArchiveError::Excluded(path));
+            }
+
+            if entry.header().entry_type().is_file() {
+                files.push((path.clone(), entry.size()));
+            }
+
+            entry.unpack_in(dir.path())?;
+        }
+    }
+
+    let manifest = std::fs::read_to_string(dir.path().join(MANIFEST_FILE_NAME))
+        .map_err(errors::InspectArchiveError::MissingManifest)?;
+    let mut manifest = toml::from_str::<Manifest>(&manifest)?;
+
+    manifest.target.validate_exports(dir.path())?;
+
+    Ok(files)
+}
+
+/// Checks the package in the project's directory and packs it for publishing. Roblox packages
+/// need the project's lockfile, if it is up to date, to check their dependencies
+pub fn prepare_package(
+    project: &Project,
+    lockfile: Option<&Lockfile>,
+) -> Result<PreparedPackage, errors::PrepareError> {
+    let mut manifest = project.deser_manifest()?;
+    check_target_includes(&manifest)?;
+    manifest.extra_targets.clear();
+
+    prepare(project, lockfile, manifest)
+}
+
+/// Checks the package in the project's directory and packs it for publishing once for its target
+/// and once for each of its extra targets, in that order. Roblox packages need the project's
+/// lockfile, if it is up to date, to check their dependencies
+pub fn prepare_packages(
+    project: &Project,
+    lockfile: Option<&Lockfile>,
+) -> Result<Vec<PreparedPackage>, errors::PrepareError> {
+    let mut manifest = project.deser_manifest()?;
+    check_target_includes(&manifest)?;
+    let targets = std::iter::once(manifest.target.clone())
+        .chain(std::mem::take(&mut manifest.extra_targets))
+        .collect::<Vec<_>>();
+
+    let mut kinds = BTreeSet::new();
+    if let Some(target) = targets.iter().find(|target| !kinds.insert(target.kind())) {
+        return Err(errors::PrepareError::DuplicateTarget(target.kind()));
+    }
+
+    targets
+        .into_iter()
+        .map(|target| {
+            prepare(
+                project,
+                lockfile,
+                Manifest {
+                    target,
+                    ..manifest.clone()
+                },
+            )
+        })
+        .collect()
+}
+
+/// Checks that the manifest only has target-specific includes for its targets
+fn check_target_includes(manifest: &Manifest) -> Result<(), errors::PrepareError> {
+    match manifest.target_includes.keys().find(|kind| {
+        !std::iter::once(&manifest.target)
+            .chain(&manifest.extra_targets)
+            .any(|target| target.kind() == **kind)
+    }) {
+        Some(kind) => Err(errors::PrepareError::UnknownTargetIncludes(*kind)),
+        None => Ok(()),
+    }
+}
+
+fn prepare(
+    project: &Project,
+    lockfile: Option<&Lockfile>,
+    mut manifest: Manifest,
+) -> Result<PreparedPackage, errors::PrepareError> {
+    let mut warnings = vec![];
+
+    if let Some(includes) = manifest.target_includes.remove(&manifest.target.kind()) {
+        manifest.includes.extend(includes);
+    }
+
+    if manifest.private {
+        return Err(errors::PrepareError::Private);
+    }
+
+    if manifest.target.lib_path().is_none() && !manifest.target.has_bins() {
+        return Err(errors::PrepareError::NoExports);
+    }
+
+    if matches!(
+        manifest.target,
+        Target::Roblox { .. } | Target::RobloxServer { .. }
+    ) {
+        if manifest.target.build_files().is_none_or(|f| f.is_empty()) {
+            return Err(errors::PrepareError::NoBuildFiles);
+        }
+
+        let lockfile = lockfile.ok_or(errors::PrepareError::OutdatedLockfile)?;
+
+        // the lockfile only says which targets the dependencies resolved to for its own target,
+        // the registry checks the others
+        if lockfile.target == manifest.target.kind()
+            && lockfile
+                .graph
+                .values()
+                .flatten()
+                .filter_map(|(_, node)| node.node.direct.as_ref().map(|_| node))
+                .any(|node| {
+                    node.target.build_files().is_none()
+                        && !matches!(node.node.ty, DependencyType::Dev)
+                })
+        {
+            return Err(errors::PrepareError::NonRobloxDependency);
+        }
+    }
+
+    let (lib_path, bin_path, named_bins, target_kind) = (
+        manifest.target.lib_path().cloned(),
+        manifest.target.bin_path().cloned(),
+        manifest.target.named_bins().cloned().unwrap_or_default(),
+        manifest.target.kind(),
+    );
+
+    let mut roblox_target = match &mut manifest.target {
+        Target::Roblox { build_files, .. } => Some(build_files),
+        Target::RobloxServer { build_files, .. } => Some(build_files),
+        _ => None,
+    };
+
+    if manifest.includes.insert(MANIFEST_FILE_NAME.to_string()) {
+        warnings.push(PublishWarning::ManifestNotIncluded);
+    }
+
+    if manifest.includes.remove(".git") {
+        warnings.push(PublishWarning::GitIncluded);
+    }
+
+    if !manifest.includes.iter().any(|f| {
+        matches!(
+            f.to_lowercase().as_str(),
+            "readme" | "readme.md" | "readme.txt"
+        )
+    }) {
+        warnings.push(PublishWarning::NoReadme);
+    }
+
+    if !manifest.includes.iter().any(|f| f == "docs") {
+        warnings.push(PublishWarning::NoDocs);
+    }
+
+    if manifest.includes.remove("default.project.json") {
+        warnings.push(PublishWarning::SyncConfigIncluded);
+    }
+
+    for ignored_path in IGNORED_FILES.iter().chain(IGNORED_DIRS.iter()) {
+        if manifest.includes.remove(*ignored_path) {
+            warnings.push(PublishWarning::IgnoredPathIncluded(
+                ignored_path.to_string(),
+            ));
+        }
+    }
+
+    for (name, path) in [("lib path", lib_path), ("bin path", bin_path)]
+        .into_iter()
+        .chain(
+            named_bins
+                .into_values()
+                .map(|path| ("bin path", Some(path))),
+        )
+    {
+        let Some(export_path) = path else { continue };
+
+        let export_path = export_path.to_path(project.package_dir());
+        if !export_path.exists() {
+            return Err(errors::PrepareError::ExportNotFound(name));
+        }
+
+        if !export_path.is_file() {
+            return Err(errors::PrepareError::ExportNotFile(name));
+        }
+
+        let contents = std::fs::read_to_string(&export_path)
+            .map_err(|e| errors::PrepareError::ExportRead(name, e))?;
+
+        if let Err(errs) = full_moon::parse(&contents) {
+            return Err(errors::PrepareError::InvalidExport(
+                name,
+                errs.into_iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ));
+        }
+
+        let first_part = export_path
+            .strip_prefix(project.package_dir())
+            .ok()
+            .and_then(|path| path.components().next())
+            .and_then(|part| match part {
+                Component::Normal(part) => Some(part.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .ok_or(errors::PrepareError::ExportOutsideProject(name))?;
+
+        let included = export_path
+            .strip_prefix(project.package_dir())
+            .ok()
+            .and_then(|path| RelativePathBuf::from_path(path).ok())
+            .is_some_and(|path| {
+                FileSelector::new(&manifest.includes, &BTreeSet::new())
+                    .is_ok_and(|selector| selector.is_selected(&path))
+            });
+
+        if !included && manifest.includes.insert(first_part.clone()) {
+            warnings.push(PublishWarning::ExportNotIncluded {
+                export: name,
+                added: first_part.clone(),
+            });
+        }
+
+        if roblox_target
+            .as_mut()
+            .is_some_and(|build_files| build_files.insert(first_part.clone()))
+        {
+            warnings.push(PublishWarning::ExportNotInBuildFiles {
+                export: name,
+                added: first_part,
+            });
+        }
+    }
+
+    manifest.target.validate_exports(project.package_dir())?;
+
+    let mut files = FileSelector::new(&manifest.includes, &manifest.excludes)?
+        .select(project.package_dir())?
+        .into_iter()
+        // it's added last, with the changes made here
+        .filter(|file| !file.as_str().eq_ignore_ascii_case(MANIFEST_FILE_NAME))
+        .collect::<BTreeSet<_>>();
+
+    // assets are published even if they aren't included
+    for pattern in &manifest.exports.assets {
+        let assets = FileSelector::new(&BTreeSet::from([pattern.clone()]), &manifest.excludes)?
+            .select(project.package_dir())?;
+
+        if assets.is_empty() {
+            return Err(errors::PrepareError::UnmatchedAsset(pattern.clone()));
+        }
+
+        // Roblox packages only contain their build files once synced
+        if let Some(build_files) = manifest.target.build_files() {
+            if let Some(asset) = assets.iter().find(|asset| {
+                !build_files
+                    .iter()
+                    .any(|build_file| asset.starts_with(build_file))
+            }) {
+                return Err(errors::PrepareError::AssetNotInBuildFiles(
+                    asset.to_string(),
+                ));
+            }
+        }
+
+        files.extend(assets);
+    }
+
+    for (name, path) in [
+        ("lib path", manifest.target.lib_path()),
+        ("bin path", manifest.target.bin_path()),
+    ]
+    .into_iter()
+    .chain(
+        manifest
+            .target
+            .named_bins()
+            .into_iter()
+            .flat_map(|bins| bins.values().map(|path| ("bin path", Some(path)))),
+    ) {
+        if path.is_some_and(|path| !files.contains(path)) {
+            return Err(errors::PrepareError::ExportExcluded(name));
+        }
+    }
+
+    for pattern in &manifest.includes {
+        if pattern.eq_ignore_ascii_case(MANIFEST_FILE_NAME) {
+            continue;
+        }
+
+        let selector = FileSelector::new(&BTreeSet::from([pattern.clone()]), &BTreeSet::new())?;
+
+        if !files.iter().any(|file| selector.is_selected(file)) {
+            return Err(errors::PrepareError::UnmatchedInclude(pattern.clone()));
+        }
+    }
+
+    if let Some(build_files) = manifest.target.build_files() {
+        for build_file in build_files {
+            if build_file.eq_ignore_ascii_case(MANIFEST_FILE_NAME) {
+                warnings.push(PublishWarning::ManifestInBuildFiles);
+                continue;
+            }
+
+            if !project.package_dir().join(build_file).exists() {
+                return Err(errors::PrepareError::BuildFileNotFound(build_file.clone()));
+            }
+
+            if !files.iter().any(|file| file.starts_with(build_file)) {
+                return Err(errors::PrepareError::BuildFileNotIncluded(
+                    build_file.clone(),
+                ));
+            }
+        }
+    }
+
+    for specifier in manifest
+        .dependencies
+        .values_mut()
+        .chain(manifest.dev_dependencies.values_mut())
+        .chain(manifest.peer_dependencies.values_mut())
+    {
+        match specifier {
+            DependencySpecifiers::Pesde(specifier) => {
+                let index_name = specifier.index.as_deref().unwrap_or(DEFAULT_INDEX_NAME);
+                specifier.index = Some(
+                    manifest
+                        .indices
+                        .get(index_name)
+                        .ok_or_else(|| errors::PrepareError::IndexNotFound(index_name.to_string()))?
+                        .to_string(),
+                );
+            }
+            #[cfg(feature = "wally-compat")]
+            DependencySpecifiers::Wally(specifier) => {
+                let index_name = specifier.index.as_deref().unwrap_or(DEFAULT_INDEX_NAME);
+                specifier.index = Some(
+                    manifest
+                        .wally_indices
+                        .get(index_name)
+                        .ok_or_else(|| {
+                            errors::PrepareError::WallyIndexNotFound(index_name.to_string())
+                        })?
+                        .to_string(),
+                );
+            }
+            DependencySpecifiers::Git(_) => {}
+            DependencySpecifiers::Path(spec) => {
+                return Err(errors::PrepareError::PathDependency(spec.path.clone()));
+            }
+            DependencySpecifiers::Workspace(spec) => {
+                let pkg_ref = WorkspacePackageSource
+                    .resolve(spec, project, target_kind)?
+                    .1
+                    .pop_last()
+                    .ok_or_else(|| {
+                        errors::PrepareError::NoWorkspaceVersions(spec.name.to_string())
+                    })?
+                    .1;
+
+                let manifest = pkg_ref
+                    .path
+                    .to_path(
+                        project
+                            .workspace_dir()
+                            .ok_or(errors::PrepareError::NotInWorkspace)?,
+                    )
+                    .join(MANIFEST_FILE_NAME);
+                let manifest = std::fs::read_to_string(&manifest)
+                    .map_err(errors::PrepareError::WorkspaceManifestRead)?;
+                let manifest = parse_manifest(&manifest)
+                    .map_err(errors::PrepareError::WorkspaceManifestParse)?;
+
+                *specifier = DependencySpecifiers::Pesde(PesdeDependencySpecifier {
+                    name: spec.name.clone(),
+                    version: match spec.version.clone() {
+                        VersionTypeOrReq::VersionType(VersionType::Wildcard) => VersionReq::STAR,
+                        VersionTypeOrReq::Req(r) => r,
+                        v => VersionReq::parse(&format!("{v}{}", manifest.version))?,
+                    },
+                    index: Some(
+                        manifest
+                            .indices
+                            .get(DEFAULT_INDEX_NAME)
+                            .ok_or_else(|| {
+                                errors::PrepareError::WorkspaceMissingDefaultIndex(
+                                    spec.name.to_string(),
+                                )
+                            })?
+                            .to_string(),
+                    ),
+                    target: Some(spec.target.unwrap_or(manifest.target.kind())),
+                    optional: spec.optional,
+                });
+            }
+        }
+    }
+
+    let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(
+        vec![],
+        flate2::Compression::best(),
+    ));
+
+    for file in &files {
+        archive.append_path_with_name(file.to_path(project.package_dir()), file.as_str())?;
+    }
+
+    let serialized = toml::to_string(&manifest)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(serialized.len() as u64);
+    header.set_mode(0o644);
+    header.set_cksum();
+    archive.append_data(&mut header, MANIFEST_FILE_NAME, serialized.as_bytes())?;
+
+    let archive = archive.into_inner()?.finish()?;
+
+    Ok(PreparedPackage {
+        manifest,
+        files,
+        warnings,
+        archive,
+    })
+}
+
+/// Checks that the dependencies of a prepared package can be installed and that the index accepts it
+pub fn check_package(
+    project: &Project,
+    source: &PesdePackageSource,
+    config: &IndexConfig,
+    package: &PreparedPackage,
+) -> Result<(), errors::PublishError> {
+    if package.archive.len() > config.max_archive_size {
+        return Err(errors::PublishError::ArchiveTooLarge {
+            size: package.archive.len(),
+            max: config.max_archive_size,
+        });
+    }
+
+    let manifest = &package.manifest;
+    let dependencies = manifest.all_dependencies()?;
+
+    let pesde_version = current_pesde_version();
+    if let Some(req) = &manifest.compatibility.pesde {
+        if !req.matches(&pesde_version) {
+            return Err(errors::PublishError::IncompatiblePesde(
+                pesde_version,
+                req.clone(),
+            ));
+        }
+    }
+
+    #[cfg(feature = "wally-compat")]
+    let mut has_wally = false;
+    let mut has_git = false;
+
+    for (alias, (specifier, ty)) in &dependencies {
+        let specifier = match specifier {
+            DependencySpecifiers::Pesde(specifier) => specifier,
+            #[cfg(feature = "wally-compat")]
+            DependencySpecifiers::Wally(_) => {
+                has_wally = true;
+                continue;
+            }
+            DependencySpecifiers::Git(_) => {
+                has_git = true;
+                continue;
+            }
+            _ => continue,
+        };
+
+        // dev dependencies aren't installed by consumers, and optional ones may be missing
+        if *ty == DependencyType::Dev || specifier.optional {
+            continue;
+        }
+
+        let dependency_source = match specifier.index.as_deref() {
+            Some(index) => PesdePackageSource::new(gix::Url::try_from(index)?),
+            None => source.clone(),
+        };
+        if dependency_source != *source {
+            PackageSource::refresh(&dependency_source, project)?;
+        }
+
+        let versions = dependency_source
+            .resolve(specifier, project, manifest.target.kind())
+            .map_err(|e| errors::PublishError::ResolveDependency(alias.clone(), Box::new(e)))?
+            .1;
+
+        let Some((version_id, _)) = versions.last_key_value() else {
+            return Err(errors::PublishError::NoDependencyVersion {
+                alias: alias.clone(),
+                name: specifier.name.to_string(),
+                version: specifier.version.clone(),
+                target: specifier.target.unwrap_or(manifest.target.kind()),
+                pesde_version,
+            });
+        };
+
+        if manifest.compatibility.pesde.is_none() {
+            let (scope, name) = specifier.name.as_str();
+            let requirement = dependency_source
+                .read_file([scope, name], project, None)
+                .ok()
+                .flatten()
+                .and_then(|file| toml::from_str::<IndexFile>(&file).ok())
+                .and_then(|mut entries| entries.remove(version_id))
+                .and_then(|entry| entry.pesde_version);
+
+            if let Some(requirement) = requirement {
+                log::warn!(
+                    "dependency {alias} requires pesde {requirement}, consider declaring a compatible range in compatibility.pesde"
+                );
+            }
+        }
+    }
+
+    if !config.git_allowed && has_git {
+        return Err(errors::PublishError::GitNotAllowed);
+    }
+
+    #[cfg(feature = "wally-compat")]
+    if !config.wally_allowed && has_wally {
+        return Err(errors::PublishError::WallyNotAllowed);
+    }
+
+    Ok(())
+}
+
+/// Publishes a prepared package to the index, checking that its dependencies can be installed and
+/// that the index accepts it first
+#[cfg(feature = "registry-client")]
+pub fn publish_package(
+    project: &Project,
+    package: &PreparedPackage,
+    reqwest: &reqwest::blocking::Client,
+    options: &PublishOptions,
+) -> Result<PublishOutcome, errors::PublishError> {
+    publish_packages(project, std::slice::from_ref(package), reqwest, options)
+}
+
+/// Publishes prepared packages, usually the targets of one version, to the index in a single
+/// request, checking that their dependencies can be installed and that the index accepts them
+/// first. The registry publishes either all of them or none
+#[cfg(feature = "registry-client")]
+pub fn publish_packages(
+    project: &Project,
+    packages: &[PreparedPackage],
+    reqwest: &reqwest::blocking::Client,
+    options: &PublishOptions,
+) -> Result<PublishOutcome, errors::PublishError> {
+    if options.dry_run {
+        return Ok(PublishOutcome::DryRun(
+            packages
+                .iter()
+                .map(|package| inspect_archive(&package.archive))
+                .collect::<Result<_, _>>()?,
+        ));
+    }
+
+    let manifest = &packages
+        .first()
+        .ok_or(errors::PublishError::NoPackages)?
+        .manifest;
+
+    let index_url = manifest
+        .indices
+        .get(&options.index)
+        .ok_or_else(|| errors::PublishError::IndexNotFound(options.index.clone()))?;
+    let source = PesdePackageSource::new(index_url.clone());
+    PackageSource::refresh(&source, project)?;
+    let config = source.config(project)?;
+
+    for package in packages {
+        check_package(project, &source, &config, package)?;
+    }
+
+    let archives = packages
+        .iter()
+        .map(|package| package.archive.clone())
+        .collect::<Vec<_>>();
+
+    match RegistryClient::new(project, reqwest, index_url, &config).publish(&archives) {
+        Ok(text) => Ok(PublishOutcome::Published(text)),
+        Err(RegistryError::Status(StatusCode::CONFLICT, _)) => {
+            Err(errors::PublishError::VersionExists)
+        }
+        Err(RegistryError::Status(StatusCode::FORBIDDEN, _)) => {
+            Err(errors::PublishError::Unauthorized)
+        }
+        Err(RegistryError::Status(StatusCode::BAD_REQUEST, text)) => {
+            Err(errors::PublishError::InvalidPackage(text))
+        }
+        Err(RegistryError::Status(code, text)) => Err(errors::PublishError::Registry(code, text)),
+        Err(e) => Err(errors::PublishError::Request(e)),
+    }
+}
+
+/// Errors that can occur when selecting the files to publish
+pub mod errors {
+    use crate::manifest::target::TargetKind;
+    use relative_path::RelativePathBuf;
+    use reqwest::StatusCode;
+    use semver::{Version, VersionReq};
+    use std::path::PathBuf;
+    use thiserror::Error;
+
+    /// Errors that can occur when creating a file selector
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum FileSelectorError {
+        /// A pattern is invalid
+        #[error("invalid pattern {0}")]
+        InvalidPattern(String, #[source] globset::Error),
+
+        /// An error occurred building the set of patterns
+        #[error("error building pattern set")]
+        Build(#[source] globset::Error),
+    }
+
+    /// Errors that can occur when selecting the files to publish
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum SelectError {
+        /// An error occurred walking the package directory
+        #[error("error walking package directory")]
+        Walk(#[from] ignore::Error),
+    }
+
+    /// Errors that can occur when inspecting a package's archive
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum InspectArchiveError {
+        /// An error occurred interacting with the filesystem or reading the archive
+        #[error("error reading archive")]
+        Io(#[from] std::io::Error),
+
+        /// An error occurred building the default excludes
+        #[error("error building default excludes")]
+        FileSelector(#[from] FileSelectorError),
+
+        /// The archive contains a path which the registry rejects
+        #[error("archive contains {0}, which the registry rejects")]
+        Excluded(RelativePathBuf),
+
+        /// The archive doesn't contain a manifest
+        #[error("archive doesn't contain a manifest")]
+        MissingManifest(#[source] std::io::Error),
+
+        /// The archive contains an invalid manifest
+        #[error("archive contains an invalid manifest")]
+        InvalidManifest(#[from] toml::de::Error),
+
+        /// The archive contains invalid exports
+        #[error("archive contains invalid exports")]
+        Exports(#[from] crate::manifest::target::errors::ExportError),
+    }
+
+    /// Errors that can occur when preparing a package for publishing
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum PrepareError {
+        /// Reading the manifest failed
+        #[error("error reading manifest")]
+        ManifestRead(#[from] crate::errors::ManifestReadError),
+
+        /// The package is private
+        #[error("package is private")]
+        Private,
+
+        /// A target kind is in the manifest more than once
+        #[error("target {0} is in the manifest more than once")]
+        DuplicateTarget(TargetKind),
+
+        /// The manifest has includes for a target the package isn't published for
+        #[error("target_includes has includes for {0}, which isn't a target of the package")]
+        UnknownTargetIncludes(TargetKind),
+
+        /// The target has no exports
+        #[error("no exports found in target")]
+        NoExports,
+
+        /// The Roblox target has no build files
+        #[error("no build files found in target")]
+        NoBuildFiles,
+
+        /// The Roblox package's lockfile is missing or outdated
+        #[error("outdated lockfile, please run the install command first")]
+        OutdatedLockfile,
+
+        /// The Roblox package depends on a non-Roblox package
+        #[error("roblox packages may not depend on non-roblox packages")]
+        NonRobloxDependency,
+
+        /// An export points to a non-existent file
+        #[error("{0} points to non-existent file")]
+        ExportNotFound(&'static str),
+
+        /// An export doesn't point to a file
+        #[error("{0} must point to a file")]
+        ExportNotFile(&'static str),
+
+        /// Reading an export failed
+        #[error("failed to read {0}")]
+        ExportRead(&'static str, #[source] std::io::Error),
+
+        /// An export isn't a valid Luau file
+        #[error("{0} is not a valid Luau file: {1}")]
+        InvalidExport(&'static str, String),
+
+        /// An export isn't within the project's directory
+        #[error("{0} must be within project directory")]
+        ExportOutsideProject(&'static str),
+
+        /// The exports are invalid
+        #[error("invalid exports")]
+        Exports(#[from] crate::manifest::target::errors::ExportError),
+
+        /// The include or exclude patterns are invalid
+        #[error("invalid include or exclude patterns")]
+        FileSelector(#[from] FileSelectorError),
+
+        /// Selecting the files to publish failed
+        #[error("failed to select files to publish")]
+        Select(#[from] SelectError),
+
+        /// An export is excluded from the package
+        #[error("{0} is excluded from the package")]
+        ExportExcluded(&'static str),
+
+        /// An include pattern doesn't match any files
+        #[error("included pattern {0} doesn't match any files")]
+        UnmatchedInclude(String),
+
+        /// An asset pattern doesn't match any files
+        #[error("asset pattern {0} doesn't match any files")]
+        UnmatchedAsset(String),
+
+        /// An asset of a Roblox package isn't within any of its build files
+        #[error("asset {0} is not within any build file")]
+        AssetNotInBuildFiles(String),
+
+        /// A build file doesn't exist
+        #[error("build file {0} does not exist")]
+        BuildFileNotFound(String),
+
+        /// A build file isn't included
+        #[error("build file {0} is not included, please include it")]
+        BuildFileNotIncluded(String),
+
+        /// A dependency's index isn't in the manifest
+        #[error("index {0} not found in indices field")]
+        IndexNotFound(String),
+
+        /// A dependency's Wally index isn't in the manifest
+        #[cfg(feature = "wally-compat")]
+        #[error("index {0} not found in wally_indices field")]
+        WallyIndexNotFound(String),
+
+        /// The package has a path dependency
+        #[error("path dependency {} can't be published, depend on a published version instead", .0.display())]
+        PathDependency(PathBuf),
+
+        /// Resolving a workspace dependency failed
+        #[error("failed to resolve workspace package")]
+        WorkspaceResolve(#[from] crate::source::workspace::errors::ResolveError),
+
+        /// A workspace dependency has no versions
+        #[error("no versions found for workspace package {0}")]
+        NoWorkspaceVersions(String),
+
+        /// The package has workspace dependencies but isn't in a workspace
+        #[error("failed to get workspace directory")]
+        NotInWorkspace,
+
+        /// Reading a workspace dependency's manifest failed
+        #[error("failed to read workspace package manifest")]
+        WorkspaceManifestRead(#[source] std::io::Error),
+
+        /// Parsing a workspace dependency's manifest failed
+        #[error("failed to parse workspace package manifest")]
+        WorkspaceManifestParse(#[source] toml::de::Error),
+
+        /// A workspace dependency's manifest has no default index
+        #[error("missing default index in workspace package {0}'s manifest")]
+        WorkspaceMissingDefaultIndex(String),
+
+        /// A workspace dependency's version requirement is invalid
+        #[error("failed to parse version requirement for workspace package")]
+        WorkspaceVersion(#[from] semver::Error),
+
+        /// Serializing the manifest failed
+        #[error("failed to serialize manifest")]
+        SerializeManifest(#[from] toml::ser::Error),
+
+        /// Writing the archive failed
+        #[error("failed to write archive")]
+        Io(#[from] std::io::Error),
+    }
+
+    /// Errors that can occur when publishing a package
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum PublishError {
+        /// The dry run's archive is invalid
+        #[error("invalid archive")]
+        Inspect(#[from] InspectArchiveError),
+
+        /// No packages were given
+        #[error("no packages to publish")]
+        NoPackages,
+
+        /// The index to publish to isn't in the manifest
+        #[error("index {0} not found in indices field")]
+        IndexNotFound(String),
+
+        /// Refreshing an index failed
+        #[error("failed to refresh source")]
+        Refresh(#[from] crate::source::git_index::errors::RefreshError),
+
+        /// Reading the index's config failed
+        #[error("failed to get source config")]
+        Config(#[from] crate::source::pesde::errors::ConfigError),
+
+        /// The archive is larger than the index allows
+        #[error("archive size exceeds maximum size of {max} bytes by {} bytes", size - max)]
+        ArchiveTooLarge {
+            /// The size of the archive
+            size: usize,
+            /// The maximum size allowed by the index
+            max: usize,
+        },
+
+        /// The dependencies of the package conflict
+        #[error("dependency conflict")]
+        DependencyConflict(#[from] crate::manifest::errors::AllDependenciesError),
+
+        /// The current pesde version is outside of the package's compatibility range
+        #[error("pesde {0} is outside of the package's compatibility range {1}, so it couldn't install the package")]
+        IncompatiblePesde(Version, VersionReq),
+
+        /// A dependency's index URL is invalid
+        #[error("invalid index url")]
+        InvalidIndexUrl(#[from] gix::url::parse::Error),
+
+        /// Resolving a dependency failed
+        #[error("failed to resolve dependency {0}")]
+        ResolveDependency(
+            String,
+            #[source] Box<crate::source::pesde::errors::ResolveError>,
+        ),
+
+        /// No version of a dependency can be installed
+        #[error("no version of {name} matching {version} is available for {target} with pesde {pesde_version}, so the package couldn't be installed (dependency {alias})")]
+        NoDependencyVersion {
+            /// The alias of the dependency
+            alias: String,
+            /// The name of the dependency
+            name: String,
+            /// The version requirement of the dependency
+            version: VersionReq,
+            /// The target of the dependency
+            target: TargetKind,
+            /// The current pesde version
+            pesde_version: Version,
+        },
+
+        /// The index doesn't allow Git dependencies
+        #[error("git dependencies are not allowed on this index")]
+        GitNotAllowed,
+
+        /// The index doesn't allow Wally dependencies
+        #[cfg(feature = "wally-compat")]
+        #[error("wally dependencies are not allowed on this index")]
+        WallyNotAllowed,
+
+        /// Sending the package to the registry failed
+        #[cfg(feature = "registry-client")]
+        #[error("failed to send request")]
+        Request(#[from] crate::registry_client::errors::RegistryError),
+
+        /// The version is already published
+        #[error("package version already exists")]
+        VersionExists,
+
+        /// The publisher isn't authorized to publish to the scope
+        #[error("unauthorized to publish under this scope")]
+        Unauthorized,
+
+        /// The registry rejected the package
+        #[error("invalid package: {0}")]
+        InvalidPackage(String),
+
+        /// The registry responded with an error
+        #[error("failed to publish package: {0} ({1})")]
+        Registry(StatusCode, String),
+    }
+}
@@ -0,0 +1,614 @@
+use crate::{
+    manifest::target::TargetKind,
+    names::PackageName,
+    source::{
+        git_index::GitBasedSource,
+        pesde::{IndexConfig, PesdePackageSource},
+        retry::{is_transient, RetryPolicy},
+        version_id::VersionId,
+    },
+    Project,
+};
+use reqwest::{
+    blocking::{Client, RequestBuilder, Response},
+    header::{ACCEPT, AUTHORIZATION, CONTENT_ENCODING},
+    Method, StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{collections::BTreeSet, io::Read};
+
+/// The order search results are returned in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchSort {
+    /// The most recently published packages first
+    #[default]
+    Recent,
+    /// The packages best matching the query first
+    Relevance,
+    /// The most downloaded packages first
+    Downloads,
+}
+
+impl SearchSort {
+    fn as_str(self) -> &'static str {
+        match self {
+            SearchSort::Recent => "recent",
+            SearchSort::Relevance => "relevance",
+            SearchSort::Downloads => "downloads",
+        }
+    }
+}
+
+/// A query for searching the packages of a registry
+#[derive(Debug, Clone, Default)]
+pub struct SearchQuery {
+    /// The text to search for
+    pub query: String,
+    /// Only return packages in this category
+    pub category: Option<String>,
+    /// Only return packages whose latest version supports this target
+    pub target: Option<TargetKind>,
+    /// Only return packages in this scope
+    pub scope: Option<String>,
+    /// Only return packages with this license
+    pub license: Option<String>,
+    /// How to order the packages, left to the registry if `None`
+    pub sort: Option<SearchSort>,
+}
+
+impl SearchQuery {
+    /// Creates a query searching for the text
+    pub fn new<S: Into<String>>(query: S) -> Self {
+        Self {
+            query: query.into(),
+            ..Default::default()
+        }
+    }
+
+    fn pairs(&self, offset: usize) -> Vec<(&'static str, String)> {
+        let mut pairs = vec![("query", self.query.clone())];
+
+        if let Some(category) = &self.category {
+            pairs.push(("category", category.clone()));
+        }
+        if let Some(target) = self.target {
+            pairs.push(("target", target.to_string()));
+        }
+        if let Some(scope) = &self.scope {
+            pairs.push(("scope", scope.clone()));
+        }
+        if let Some(license) = &self.license {
+            pairs.push(("license", license.clone()));
+        }
+        if let Some(sort) = self.sort {
+            pairs.push(("sort", sort.as_str().to_string()));
+        }
+
+        pairs.push(("offset", offset.to_string()));
+        pairs
+    }
+}
+
+/// A target of a package found by searching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchTarget {
+    /// The kind of the target
+    pub kind: TargetKind,
+}
+
+/// A package found by searching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    /// The name of the package
+    pub name: String,
+    /// The latest version of the package
+    pub version: String,
+    /// The description of the package
+    #[serde(default)]
+    pub description: String,
+    /// The targets of the latest version of the package
+    #[serde(default)]
+    pub targets: Vec<SearchTarget>,
+}
+
+/// The packages found by searching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResponse {
+    /// The packages found
+    pub data: Vec<SearchResult>,
+    /// How many packages match the query in total
+    pub count: usize,
+}
+
+/// A package in a scope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopePackage {
+    /// The name of the package
+    pub name: String,
+    /// The latest version of the package
+    pub version: String,
+    /// The description of the package
+    #[serde(default)]
+    pub description: String,
+}
+
+/// The owners and packages of a scope
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScopeInfo {
+    /// The IDs of the users owning the scope
+    pub owners: BTreeSet<u64>,
+    /// The packages in the scope
+    pub packages: Vec<ScopePackage>,
+}
+
+/// The user a token belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    /// The ID of the user
+    pub user_id: u64,
+}
+
+/// A client for the API of the registry of a pesde index. Requests which don't change anything
+/// are retried according to the retry policy
+#[derive(Clone)]
+pub struct RegistryClient {
+    client: Client,
+    api: String,
+    token: Option<String>,
+    retry_policy: RetryPolicy,
+}
+
+impl RegistryClient {
+    /// Creates a client for the registry of the index with the config, using the project's HTTP
+    /// client, token and retry policy for the index
+    pub fn new(
+        project: &Project,
+        reqwest: &Client,
+        index_url: &gix::Url,
+        config: &IndexConfig,
+    ) -> Self {
+        let token = project.auth_config().tokens().get(index_url).cloned();
+        if token.is_some() {
+            log::debug!("using token for {index_url}");
+        }
+
+        Self {
+            client: project
+                .auth_config()
+                .index_client(index_url)
+                .unwrap_or(reqwest)
+                .clone(),
+            api: config.api().to_string(),
+            token,
+            retry_policy: project.retry_policy(),
+        }
+    }
+
+    /// Refreshes the index and creates a client for its registry
+    pub fn from_index(
+        project: &Project,
+        reqwest: &Client,
+        index_url: &gix::Url,
+    ) -> Result<Self, errors::FromIndexError> {
+        let source = PesdePackageSource::new(index_url.clone());
+        GitBasedSource::refresh(&source, project).map_err(Box::new)?;
+        let config = source.config(project)?;
+
+        Ok(Self::new(project, reqwest, index_url, &config))
+    }
+
+    /// Sets the token to authenticate with, instead of the project's one for the index
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Sets how failed requests are retried
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// The URL of the registry's API
+    pub fn api(&self) -> &str {
+        &self.api
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(method, format!("{}/{path}", self.api));
+
+        match &self.token {
+            Some(token) => request.header(AUTHORIZATION, token),
+            None => request,
+        }
+    }
+
+    /// Sends the request, retrying it on timeouts, connection errors and server errors
+    fn send<F: Fn() -> RequestBuilder>(&self, request: F) -> Result<Response, reqwest::Error> {
+        let mut retry = 0;
+
+        loop {
+            let result = request().send();
+            let transient = match &result {
+                Ok(response) => {
+                    response.status().is_server_error()
+                        || response.status() == StatusCode::TOO_MANY_REQUESTS
+                }
+                Err(e) => is_transient(e),
+            };
+
+            if !transient || retry + 1 >= self.retry_policy.attempts {
+                return result;
+            }
+
+            let delay = self.retry_policy.delay(retry);
+            log::warn!("registry request failed, retrying in {delay:?}");
+
+            std::thread::sleep(delay);
+            retry += 1;
+        }
+    }
+
+    fn get<T: DeserializeOwned>(&self, path: &str) -> Result<Option<T>, errors::RegistryError> {
+        let response = self.send(|| self.request(Method::GET, path))?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let text = check_status(response)?.text()?;
+        Ok(Some(serde_json::from_str(&text)?))
+    }
+
+    /// Gets the user the token belongs to, which is `None` if the registry predates the endpoint
+    pub fn user(&self) -> Result<Option<User>, errors::RegistryError> {
+        self.get("v0/user")
+    }
+
+    /// Searches the packages of the registry, returning a page of results starting at `offset`
+    pub fn search(
+        &self,
+        query: &SearchQuery,
+        offset: usize,
+    ) -> Result<SearchResponse, errors::RegistryError> {
+        let pairs = query.pairs(offset);
+        let response = self.send(|| self.request(Method::GET, "v0/search").query(&pairs))?;
+        let text = check_status(response)?.text()?;
+
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    /// Searches the packages of the registry, fetching more pages until `limit` packages are
+    /// found, or just the first page without a limit
+    pub fn search_all(
+        &self,
+        query: &SearchQuery,
+        limit: Option<usize>,
+    ) -> Result<SearchResponse, errors::RegistryError> {
+        let mut response = SearchResponse {
+            data: vec![],
+            count: 0,
+        };
+
+        loop {
+            let page = self.search(query, response.data.len())?;
+
+            let empty = page.data.is_empty();
+            response.count = page.count;
+            response.data.extend(page.data);
+
+            let Some(limit) = limit else {
+                break;
+            };
+            if empty || response.data.len() >= limit || response.data.len() >= response.count {
+                response.data.truncate(limit);
+                break;
+            }
+        }
+
+        Ok(response)
+    }
+
+    /// Gets the owners and packages of the scope, which is `None` if it doesn't exist
+    pub fn scope(&self, scope: &str) -> Result<Option<ScopeInfo>, errors::RegistryError> {
+        self.get(&format!("v0/scopes/{scope}"))
+    }
+
+    /// Gets the readme of the version, which is `None` if it has none
+    pub fn readme(
+        &self,
+        name: &PackageName,
+        version_id: &VersionId,
+    ) -> Result<Option<String>, errors::RegistryError> {
+        // the name is a single path segment, so its slash has to be escaped
+        let path = format!(
+            "v0/packages/{}/{}/{}",
+            name.to_string().replace('/', "%2F"),
+            version_id.version(),
+            version_id.target()
+        );
+
+        let response = self.send(|| {
+            self.request(Method::GET, &path)
+                .header(ACCEPT, "text/plain")
+        })?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let response = check_status(response)?;
+        let gzipped = response
+            .headers()
+            .get(CONTENT_ENCODING)
+            .is_some_and(|encoding| encoding == "gzip");
+        let bytes = response.bytes()?;
+
+        let mut readme = String::new();
+        if gzipped {
+            flate2::read::GzDecoder::new(bytes.as_ref()).read_to_string(&mut readme)?;
+        } else {
+            readme = String::from_utf8_lossy(&bytes).into_owned();
+        }
+
+        Ok(Some(readme))
+    }
+
+    /// Publishes the archives of the targets of a version in a single request, returning the
+    /// registry's message. Publishing isn't retried, as the registry may have accepted it
+    pub fn publish(&self, archives: &[Vec<u8>]) -> Result<String, errors::RegistryError> {
+        let form = archives.iter().fold(
+            reqwest::blocking::multipart::Form::new(),
+            |form, archive| {
+                form.part(
+                    "tarball",
+                    reqwest::blocking::multipart::Part::bytes(archive.clone())
+                        .file_name("package.tar.gz"),
+                )
+            },
+        );
+
+        let response = self
+            .request(Method::POST, "v0/packages")
+            .multipart(form)
+            .send()?;
+
+        Ok(check_status(response)?.text()?)
+    }
+}
+
+/// Turns an unsuccessful response into an error with its body
+fn check_status(response: Response) -> Result<Response, errors::RegistryError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    Err(errors::RegistryError::Status(status, response.text()?))
+}
+
+/// Errors that can occur when interacting with a registry
+pub mod errors {
+    use reqwest::StatusCode;
+    use thiserror::Error;
+
+    /// Errors that can occur when creating a client from an index
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum FromIndexError {
+        /// Refreshing the index failed
+        #[error("error refreshing index")]
+        Refresh(#[from] Box<crate::source::git_index::errors::RefreshError>),
+
+        /// Reading the index's config failed
+        #[error("error reading index config")]
+        Config(#[from] crate::source::pesde::errors::ConfigError),
+    }
+
+    /// Errors that can occur when sending a request to a registry
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum RegistryError {
+        /// Sending the request or reading the response failed
+        #[error("error sending request")]
+        Request(#[from] reqwest::Error),
+
+        /// The registry responded with an error
+        #[error("registry responded with {0}: {1}")]
+        Status(StatusCode, String),
+
+        /// The response couldn't be parsed
+        #[error("error parsing response")]
+        Json(#[from] serde_json::Error),
+
+        /// The response couldn't be decompressed
+        #[error("error decompressing response")]
+        Io(#[from] std::io::Error),
+    }
+
+    impl RegistryError {
+        /// The status the registry responded with, if it responded with an error
+        pub fn status(&self) -> Option<StatusCode> {
+            match self {
+                RegistryError::Status(status, _) => Some(*status),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    /// Serves the responses to the requests in order, recording the request lines and headers
+    fn serve(responses: Vec<String>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    request.push_str(&line.to_lowercase());
+                    line.clear();
+                }
+                recorded.lock().unwrap().push(request);
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    }
+
+    fn client(api: String) -> RegistryClient {
+        RegistryClient {
+            client: Client::new(),
+            api,
+            token: None,
+            retry_policy: RetryPolicy {
+                attempts: 3,
+                backoff: Duration::ZERO,
+                max_backoff: Duration::ZERO,
+            },
+        }
+    }
+
+    #[test]
+    fn search_pairs() {
+        assert_eq!(
+            SearchQuery::new("foo").pairs(0),
+            vec![("query", "foo".to_string()), ("offset", "0".to_string())]
+        );
+
+        let query = SearchQuery {
+            query: "foo".to_string(),
+            category: Some("ui".to_string()),
+            target: Some(TargetKind::Roblox),
+            scope: Some("acme".to_string()),
+            license: Some("MIT".to_string()),
+            sort: Some(SearchSort::Downloads),
+        };
+        assert_eq!(
+            query.pairs(20),
+            vec![
+                ("query", "foo".to_string()),
+                ("category", "ui".to_string()),
+                ("target", "roblox".to_string()),
+                ("scope", "acme".to_string()),
+                ("license", "MIT".to_string()),
+                ("sort", "downloads".to_string()),
+                ("offset", "20".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let (url, requests) = serve(vec![
+            response("503 Service Unavailable", ""),
+            response("429 Too Many Requests", ""),
+            response("200 OK", r#"{"user_id":1}"#),
+        ]);
+
+        let user = client(url).with_token(Some("token".to_string())).user();
+        assert_eq!(user.unwrap().unwrap().user_id, 1);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|request| request.starts_with("get /v0/user ")
+                && request.contains("authorization: token\r\n")));
+    }
+
+    #[test]
+    fn gives_up_after_attempts() {
+        let (url, requests) = serve(vec![response("500 Internal Server Error", "oops"); 3]);
+
+        let e = client(url).user().unwrap_err();
+        assert_eq!(e.status(), Some(StatusCode::INTERNAL_SERVER_ERROR));
+        assert!(matches!(e, errors::RegistryError::Status(_, ref body) if body == "oops"));
+        assert_eq!(requests.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (url, requests) = serve(vec![
+            response("400 Bad Request", "bad query"),
+            response("404 Not Found", ""),
+        ]);
+        let client = client(url);
+
+        let e = client.search(&SearchQuery::new("foo"), 0).unwrap_err();
+        assert_eq!(e.status(), Some(StatusCode::BAD_REQUEST));
+
+        // a missing resource isn't an error
+        assert!(client.scope("acme").unwrap().is_none());
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn connection_errors_are_transient() {
+        // nothing listens on the port once the listener is dropped
+        let url = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("http://{}", listener.local_addr().unwrap())
+        };
+
+        let e = Client::new().get(&url).send().unwrap_err();
+        assert!(is_transient(&e));
+
+        let e = client(url).user().unwrap_err();
+        assert!(matches!(e, errors::RegistryError::Request(ref e) if e.is_connect()));
+        assert_eq!(e.status(), None);
+    }
+
+    #[test]
+    fn search_all_fetches_pages_up_to_limit() {
+        let page = |names: &[&str]| {
+            let data = names
+                .iter()
+                .map(|name| format!(r#"{{"name":"{name}","version":"1.0.0"}}"#))
+                .collect::<Vec<_>>()
+                .join(",");
+            response("200 OK", &format!(r#"{{"data":[{data}],"count":5}}"#))
+        };
+        let (url, requests) = serve(vec![page(&["a/a", "a/b"]), page(&["a/c", "a/d"])]);
+
+        let response = client(url)
+            .search_all(&SearchQuery::new("a"), Some(3))
+            .unwrap();
+        assert_eq!(
+            response
+                .data
+                .iter()
+                .map(|result| result.name.as_str())
+                .collect::<Vec<_>>(),
+            ["a/a", "a/b", "a/c"]
+        );
+        assert_eq!(response.count, 5);
+
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].starts_with("get /v0/search?query=a&offset=0 "));
+        assert!(requests[1].starts_with("get /v0/search?query=a&offset=2 "));
+    }
+}
@@ -1,9 +1,14 @@
 use crate::{
-    lockfile::{insert_node, DependencyGraph, DependencyGraphNode},
-    manifest::DependencyType,
+    events::Event,
+    lockfile::{
+        direct_dependency_subgraph, insert_node, record_introducers, DependencyGraph,
+        DependencyGraphNode, Provenance,
+    },
+    manifest::{target::TargetKind, DependencyType},
     names::PackageNames,
     source::{
-        pesde::PesdePackageSource,
+        pesde::{specifier::PesdeDependencySpecifier, PesdePackageSource},
+        refs::PackageRefs,
         specifiers::DependencySpecifiers,
         traits::{PackageRef, PackageSource},
         version_id::VersionId,
@@ -11,7 +16,211 @@ use crate::{
     },
     Project, DEFAULT_INDEX_NAME,
 };
-use std::collections::{HashMap, HashSet, VecDeque};
+use semver::{Comparator, Op, Version, VersionReq};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    path::PathBuf,
+};
+
+/// Resolves a Wally package from the pesde index mirroring Wally packages, if the mirror has the
+/// version it resolved to on Wally, keeping only the versions both of them have
+#[cfg(feature = "wally-compat")]
+fn resolve_wally_mirror(
+    project: &Project,
+    mirror: &PesdePackageSource,
+    specifier: &crate::source::wally::specifier::WallyDependencySpecifier,
+    wally_resolved: &BTreeMap<VersionId, PackageRefs>,
+    target: TargetKind,
+) -> Result<Option<crate::source::ResolveResult<PackageRefs>>, Box<errors::DependencyGraphError>> {
+    let Some((latest, _)) = wally_resolved.last_key_value() else {
+        return Ok(None);
+    };
+
+    // Wally names may have characters pesde names can't, in which case they can't be mirrored
+    let (scope, name) = specifier.name.as_str();
+    let Ok(name) = format!("{scope}/{name}").parse() else {
+        return Ok(None);
+    };
+
+    let mirror_specifier = PesdeDependencySpecifier {
+        name,
+        version: specifier.version.clone(),
+        index: None,
+        target: None,
+        optional: specifier.optional,
+    };
+
+    let (name, resolved) = match mirror.resolve(&mirror_specifier, project, target) {
+        Ok((name, resolved)) => (name, resolved),
+        Err(crate::source::pesde::errors::ResolveError::NotFound(_)) => return Ok(None),
+        Err(e) => {
+            return Err(Box::new(
+                Box::new(crate::source::errors::ResolveError::Pesde(e)).into(),
+            ))
+        }
+    };
+
+    if !resolved
+        .keys()
+        .any(|version_id| version_id.version() == latest.version())
+    {
+        return Ok(None);
+    }
+
+    let resolved = resolved
+        .into_iter()
+        .filter(|(version_id, _)| {
+            wally_resolved
+                .keys()
+                .any(|wally_version_id| wally_version_id.version() == version_id.version())
+        })
+        .map(|(version_id, pkg_ref)| (version_id, PackageRefs::Pesde(Box::new(pkg_ref))))
+        .collect();
+
+    Ok(Some((name, resolved)))
+}
+
+fn exact_version_req(version: &Version) -> VersionReq {
+    VersionReq {
+        comparators: vec![Comparator {
+            op: Op::Exact,
+            major: version.major,
+            minor: Some(version.minor),
+            patch: Some(version.patch),
+            pre: version.pre.clone(),
+        }],
+    }
+}
+
+/// A peer dependency of a package in the graph which the project doesn't provide a compatible
+/// version of
+#[derive(Debug, Clone)]
+pub struct UnmetPeerDependency {
+    /// The aliases leading from a direct dependency of the project to the package with the peer
+    /// dependency
+    pub path: Vec<String>,
+    /// The alias of the peer dependency
+    pub alias: String,
+    /// The specifier of the peer dependency
+    pub specifier: DependencySpecifiers,
+    /// The versions of the package the project provides, none of which satisfy the specifier
+    pub provided: Vec<VersionId>,
+}
+
+impl Display for UnmetPeerDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} requires peer dependency {} ({})",
+            self.path.join(">"),
+            self.alias,
+            self.specifier
+        )?;
+
+        if self.provided.is_empty() {
+            write!(f, ", which the project doesn't provide")
+        } else {
+            write!(
+                f,
+                ", but the project provides {}",
+                self.provided
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    }
+}
+
+/// The shortest path of aliases from a direct dependency to the package
+fn path_to(graph: &DependencyGraph, name: &PackageNames, version_id: &VersionId) -> Vec<String> {
+    let mut queue = graph
+        .iter()
+        .flat_map(|(name, versions)| versions.iter().map(move |(v, node)| (name, v, node)))
+        .filter_map(|(name, version_id, node)| {
+            node.direct
+                .as_ref()
+                .map(|(alias, _)| (name, version_id, vec![alias.clone()]))
+        })
+        .collect::<VecDeque<_>>();
+    let mut visited = HashSet::new();
+
+    while let Some((dep_name, dep_version_id, path)) = queue.pop_front() {
+        if dep_name == name && dep_version_id == version_id {
+            return path;
+        }
+
+        if !visited.insert((dep_name, dep_version_id)) {
+            continue;
+        }
+
+        let Some(node) = graph.get(dep_name).and_then(|v| v.get(dep_version_id)) else {
+            continue;
+        };
+
+        for (dep_name, (dep_version_id, alias)) in &node.dependencies {
+            queue.push_back((
+                dep_name,
+                dep_version_id,
+                path.iter().cloned().chain([alias.clone()]).collect(),
+            ));
+        }
+    }
+
+    vec![format!("{name}@{version_id}")]
+}
+
+/// Finds the peer dependencies of packages in the graph which the project doesn't provide a
+/// compatible version of, either because it doesn't depend on the package at all or because the
+/// versions it depends on don't satisfy the peer dependency's specifier
+pub fn unmet_peer_dependencies(graph: &DependencyGraph) -> Vec<UnmetPeerDependency> {
+    let mut unmet = vec![];
+
+    for (name, versions) in graph {
+        for (version_id, node) in versions {
+            for (alias, (specifier, ty)) in node.pkg_ref.dependencies() {
+                if *ty != DependencyType::Peer {
+                    continue;
+                }
+
+                // unresolved optional peer dependencies aren't in the graph
+                let Some((peer_name, (peer_version_id, _))) = node
+                    .dependencies
+                    .iter()
+                    .find(|(_, (_, dep_alias))| dep_alias == alias)
+                else {
+                    continue;
+                };
+
+                // peer dependencies provided by the project are resolved as standard ones
+                if graph
+                    .get(peer_name)
+                    .and_then(|v| v.get(peer_version_id))
+                    .is_none_or(|peer| peer.ty != DependencyType::Peer)
+                {
+                    continue;
+                }
+
+                unmet.push(UnmetPeerDependency {
+                    path: path_to(graph, name, version_id),
+                    alias: alias.clone(),
+                    specifier: specifier.clone(),
+                    provided: graph
+                        .get(peer_name)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, peer)| peer.ty != DependencyType::Peer)
+                        .map(|(v, _)| v.clone())
+                        .collect(),
+                });
+            }
+        }
+    }
+
+    unmet
+}
 
 impl Project {
     /// Create a dependency graph from the project's manifest
@@ -20,12 +229,158 @@ impl Project {
         previous_graph: Option<&DependencyGraph>,
         refreshed_sources: &mut HashSet<PackageSources>,
     ) -> Result<DependencyGraph, Box<errors::DependencyGraphError>> {
+        self.dependency_graph_with(previous_graph, refreshed_sources, &DependencyGraph::new())
+    }
+
+    /// Create a dependency graph which only resolves the given packages again, keeping the versions
+    /// of every other package in the previous graph. If `recursive` is set, the dependencies of the
+    /// given packages are resolved again too
+    pub fn update_dependency_graph(
+        &self,
+        previous_graph: &DependencyGraph,
+        packages: &HashSet<PackageNames>,
+        recursive: bool,
+        refreshed_sources: &mut HashSet<PackageSources>,
+    ) -> Result<DependencyGraph, Box<errors::DependencyGraphError>> {
+        let mut updated = packages.clone();
+
+        if recursive {
+            let mut queue = packages.iter().cloned().collect::<Vec<_>>();
+
+            while let Some(name) = queue.pop() {
+                for node in previous_graph
+                    .get(&name)
+                    .into_iter()
+                    .flat_map(|v| v.values())
+                {
+                    for dep_name in node.dependencies.keys() {
+                        if updated.insert(dep_name.clone()) {
+                            queue.push(dep_name.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        let kept = previous_graph
+            .iter()
+            .filter(|(name, _)| !updated.contains(*name))
+            .map(|(name, versions)| (name.clone(), versions.clone()))
+            .collect::<DependencyGraph>();
+
+        // reused direct dependencies bring their whole subgraph along, so the ones depending on
+        // an updated package must be resolved again, which will prefer the kept versions
+        let stale_aliases = previous_graph
+            .values()
+            .flat_map(|versions| versions.values())
+            .filter_map(|node| node.direct.as_ref().map(|(alias, _)| alias))
+            .filter(|alias| {
+                direct_dependency_subgraph(previous_graph, alias)
+                    .is_some_and(|subgraph| subgraph.keys().any(|name| updated.contains(name)))
+            })
+            .cloned()
+            .collect::<HashSet<_>>();
+
+        let previous_graph = previous_graph
+            .iter()
+            .map(|(name, versions)| {
+                (
+                    name.clone(),
+                    versions
+                        .iter()
+                        .filter(|(_, node)| {
+                            !node
+                                .direct
+                                .as_ref()
+                                .is_some_and(|(alias, _)| stale_aliases.contains(alias))
+                        })
+                        .map(|(version_id, node)| (version_id.clone(), node.clone()))
+                        .collect::<BTreeMap<_, _>>(),
+                )
+            })
+            .filter(|(_, versions)| !versions.is_empty())
+            .collect::<DependencyGraph>();
+
+        self.dependency_graph_with(Some(&previous_graph), refreshed_sources, &kept)
+    }
+
+    /// Create the dependency graphs of the project and every member of its workspace as one,
+    /// making them share the versions of the packages they have in common. The graphs are keyed by
+    /// the directory of the package they belong to
+    pub fn workspace_dependency_graphs(
+        &self,
+        previous_graphs: &BTreeMap<PathBuf, DependencyGraph>,
+        refreshed_sources: &mut HashSet<PackageSources>,
+    ) -> Result<BTreeMap<PathBuf, DependencyGraph>, Box<errors::WorkspaceDependencyGraphError>>
+    {
+        let mut members = self
+            .workspace_members(self.package_dir())
+            .map_err(|e| Box::new(e.into()))?
+            .into_keys()
+            .collect::<Vec<_>>();
+        // the order of resolution decides which versions are shared, so it must be stable
+        members.sort();
+
+        let mut shared = DependencyGraph::new();
+        let mut graphs = BTreeMap::new();
+
+        for dir in std::iter::once(self.package_dir().to_path_buf()).chain(members) {
+            let project = if dir == self.package_dir() {
+                self.clone()
+            } else {
+                self.for_member(&dir)
+            };
+
+            let graph = project
+                .dependency_graph_with(previous_graphs.get(&dir), refreshed_sources, &shared)
+                .map_err(|e| {
+                    Box::new(errors::WorkspaceDependencyGraphError::DependencyGraph(
+                        dir.clone(),
+                        e,
+                    ))
+                })?;
+
+            for (name, versions) in &graph {
+                for (version_id, node) in versions {
+                    shared
+                        .entry(name.clone())
+                        .or_default()
+                        .entry(version_id.clone())
+                        .or_insert_with(|| DependencyGraphNode {
+                            direct: None,
+                            ..node.clone()
+                        });
+                }
+            }
+
+            graphs.insert(dir, graph);
+        }
+
+        Ok(graphs)
+    }
+
+    /// Create a dependency graph, preferring the versions of packages in the shared graph
+    fn dependency_graph_with(
+        &self,
+        previous_graph: Option<&DependencyGraph>,
+        refreshed_sources: &mut HashSet<PackageSources>,
+        shared: &DependencyGraph,
+    ) -> Result<DependencyGraph, Box<errors::DependencyGraphError>> {
+        self.emit(|| Event::ResolveStarted {
+            package_dir: self.package_dir().to_path_buf(),
+        });
+
         let manifest = self.deser_manifest().map_err(|e| Box::new(e.into()))?;
+        let pins = self.deser_pins().map_err(|e| Box::new(e.into()))?;
+        let disabled = manifest
+            .disabled_dependencies(self.features())
+            .map_err(|e| Box::new(e.into()))?;
 
         let mut all_specifiers = manifest
             .all_dependencies()
             .map_err(|e| Box::new(e.into()))?
             .into_iter()
+            .filter(|(alias, _)| !disabled.contains(alias))
             .map(|(alias, (spec, ty))| ((spec, ty), alias))
             .collect::<HashMap<_, _>>();
 
@@ -34,7 +389,7 @@ impl Project {
         if let Some(previous_graph) = previous_graph {
             for (name, versions) in previous_graph {
                 for (version, node) in versions {
-                    let Some((_, specifier)) = &node.direct else {
+                    let Some((old_alias, specifier)) = &node.direct else {
                         // this is not a direct dependency, will be added if it's still being used later
                         continue;
                     };
@@ -44,6 +399,44 @@ impl Project {
                         continue;
                     }
 
+                    if let Some((changed_name, _)) =
+                        direct_dependency_subgraph(previous_graph, old_alias)
+                            .into_iter()
+                            .flatten()
+                            .find(|(_, versions)| {
+                                versions.values().any(|node| match &node.pkg_ref {
+                                    PackageRefs::Path(pkg_ref) => {
+                                        crate::source::path::manifest_hash(self, &pkg_ref.path)
+                                            .ok()
+                                            .as_ref()
+                                            != Some(&pkg_ref.manifest_hash)
+                                    }
+                                    _ => false,
+                                })
+                            })
+                    {
+                        log::debug!(
+                            "dependency {name}@{version} from old dependency graph uses path package {changed_name}, whose manifest changed",
+                        );
+                        continue;
+                    }
+
+                    if let Some((pinned_name, _)) =
+                        direct_dependency_subgraph(previous_graph, old_alias)
+                            .into_iter()
+                            .flatten()
+                            .find(|(name, versions)| {
+                                pins.get(name).is_some_and(|pinned| {
+                                    versions.keys().any(|version| version.version() != pinned)
+                                })
+                            })
+                    {
+                        log::debug!(
+                            "dependency {name}@{version} from old dependency graph uses a different version of pinned {pinned_name}",
+                        );
+                        continue;
+                    }
+
                     let Some(alias) = all_specifiers.remove(&(specifier.clone(), node.ty)) else {
                         log::debug!(
                             "dependency {name}@{version} from old dependency graph is no longer in the manifest",
@@ -109,6 +502,25 @@ impl Project {
             }
         }
 
+        #[cfg(feature = "wally-compat")]
+        let wally_mirror = manifest
+            .wally_mirror
+            .as_deref()
+            .map(|index_name| {
+                manifest
+                    .indices
+                    .get(index_name)
+                    .cloned()
+                    .map(PesdePackageSource::new)
+                    .ok_or_else(|| {
+                        errors::DependencyGraphError::IndexNotFound(index_name.to_string())
+                    })
+            })
+            .transpose()?;
+
+        let resolved_at = chrono::Utc::now();
+        let mut index_commits = HashMap::<PackageSources, Option<String>>::new();
+
         let mut queue = all_specifiers
             .into_iter()
             .map(|((spec, ty), alias)| {
@@ -127,6 +539,10 @@ impl Project {
         while let Some((alias, specifier, ty, dependant, path, overridden, target)) =
             queue.pop_front()
         {
+            if self.cancelled() {
+                return Err(Box::new(errors::DependencyGraphError::Cancelled));
+            }
+
             let depth = path.len() - 1;
 
             log::debug!(
@@ -187,30 +603,105 @@ impl Project {
                 DependencySpecifiers::Workspace(_) => {
                     PackageSources::Workspace(crate::source::workspace::WorkspacePackageSource)
                 }
+                DependencySpecifiers::Path(_) => {
+                    PackageSources::Path(crate::source::path::PathPackageSource)
+                }
             };
 
             if refreshed_sources.insert(source.clone()) {
                 source.refresh(self).map_err(|e| Box::new(e.into()))?;
             }
 
-            let (name, resolved) = source
-                .resolve(&specifier, self, target)
-                .map_err(|e| Box::new(e.into()))?;
+            let pinned = match &specifier {
+                DependencySpecifiers::Pesde(specifier) => pins
+                    .get(&PackageNames::Pesde(specifier.name.clone()))
+                    .map(|version| {
+                        DependencySpecifiers::Pesde(PesdeDependencySpecifier {
+                            version: exact_version_req(version),
+                            ..specifier.clone()
+                        })
+                    }),
+                #[cfg(feature = "wally-compat")]
+                DependencySpecifiers::Wally(specifier) => pins
+                    .get(&PackageNames::Wally(specifier.name.clone()))
+                    .map(|version| {
+                        DependencySpecifiers::Wally(
+                            crate::source::wally::specifier::WallyDependencySpecifier {
+                                version: exact_version_req(version),
+                                ..specifier.clone()
+                            },
+                        )
+                    }),
+                _ => None,
+            };
+
+            if let Some(pinned) = &pinned {
+                log::debug!("{}{specifier} is pinned to {pinned}", "\t".repeat(depth));
+            }
+
+            let resolve_specifier = pinned.as_ref().unwrap_or(&specifier);
 
-            let Some(target_version_id) = graph
-                .get(&name)
-                .and_then(|versions| {
+            let (name, resolved) = match source.resolve(resolve_specifier, self, target) {
+                Ok(resolved) => resolved,
+                Err(e) if specifier.is_optional() && e.is_not_found() => {
+                    log::info!(
+                        "{}optional dependency {resolve_specifier} ({target}) not found, a stub will be linked",
+                        "\t".repeat(depth)
+                    );
+                    continue;
+                }
+                Err(e) => return Err(Box::new(Box::new(e).into())),
+            };
+
+            #[cfg(feature = "wally-compat")]
+            let (source, name, resolved) = match (&wally_mirror, resolve_specifier) {
+                (Some(mirror), DependencySpecifiers::Wally(wally_specifier)) => {
+                    let mirror_source = PackageSources::Pesde(mirror.clone());
+                    if refreshed_sources.insert(mirror_source.clone()) {
+                        mirror_source
+                            .refresh(self)
+                            .map_err(|e| Box::new(e.into()))?;
+                    }
+
+                    match resolve_wally_mirror(self, mirror, wally_specifier, &resolved, target)? {
+                        Some((mirror_name, mirror_resolved)) => {
+                            log::debug!(
+                                "{}resolving {specifier} from the wally mirror as {mirror_name}",
+                                "\t".repeat(depth)
+                            );
+                            (mirror_source, mirror_name, mirror_resolved)
+                        }
+                        None => (source, name, resolved),
+                    }
+                }
+                _ => (source, name, resolved),
+            };
+
+            let compatible_in = |graph: &DependencyGraph| {
+                graph.get(&name).and_then(|versions| {
                     versions
                         .keys()
                         // only consider versions that are compatible with the specifier
                         .filter(|ver| resolved.contains_key(ver))
                         .max()
+                        .cloned()
                 })
-                .or_else(|| resolved.last_key_value().map(|(ver, _)| ver))
-                .cloned()
+            };
+
+            let Some(target_version_id) = compatible_in(&graph)
+                .or_else(|| compatible_in(shared))
+                .or_else(|| resolved.last_key_value().map(|(ver, _)| ver.clone()))
             else {
+                if specifier.is_optional() {
+                    log::info!(
+                        "{}no version of optional dependency {resolve_specifier} ({target}) found, a stub will be linked",
+                        "\t".repeat(depth)
+                    );
+                    continue;
+                }
+
                 return Err(Box::new(errors::DependencyGraphError::NoMatchingVersion(
-                    format!("{specifier} ({target})"),
+                    format!("{resolve_specifier} ({target})"),
                 )));
             };
 
@@ -262,6 +753,48 @@ impl Project {
                 continue;
             }
 
+            if let PackageRefs::Pesde(pkg_ref) = pkg_ref {
+                for capability in &pkg_ref.capabilities {
+                    if capability.is_available(manifest.target.kind(), manifest.engine.as_ref()) {
+                        continue;
+                    }
+
+                    let (required_target, required_version) = capability.requirement();
+
+                    if let Some(engine) = manifest
+                        .engine
+                        .as_ref()
+                        .filter(|_| required_target == manifest.target.kind())
+                    {
+                        log::warn!(
+                            "{name}@{target_version_id} requires {capability}, which needs {required_target} {required_version} or newer, but the project uses {engine}"
+                        );
+                    } else {
+                        log::warn!(
+                            "{name}@{target_version_id} requires {capability}, which is only available on {required_target}"
+                        );
+                    }
+                }
+
+                if let Some(engine) = manifest
+                    .engine
+                    .as_ref()
+                    .filter(|_| manifest.target.kind() == TargetKind::Lune)
+                {
+                    if !pkg_ref.tested_lune.is_empty() && !pkg_ref.tested_lune.contains(engine) {
+                        log::warn!(
+                            "{name}@{target_version_id} hasn't been tested with Lune {engine}, only with {}",
+                            pkg_ref
+                                .tested_lune
+                                .iter()
+                                .map(|version| version.to_string())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        );
+                    }
+                }
+            }
+
             let node = DependencyGraphNode {
                 direct: if depth == 0 {
                     Some((alias.clone(), specifier.clone()))
@@ -271,6 +804,14 @@ impl Project {
                 pkg_ref: pkg_ref.clone(),
                 dependencies: Default::default(),
                 ty,
+                introduced_by: Default::default(),
+                provenance: Some(Provenance {
+                    index_commit: index_commits
+                        .entry(source.clone())
+                        .or_insert_with(|| source.index_commit(self))
+                        .clone(),
+                    resolved_at,
+                }),
             };
             insert_node(
                 &mut graph,
@@ -279,6 +820,10 @@ impl Project {
                 node.clone(),
                 depth == 0,
             );
+            self.emit(|| Event::PackageResolved {
+                name: name.clone(),
+                version_id: target_version_id.clone(),
+            });
 
             log::debug!(
                 "{}resolved {}@{} from new dependency graph",
@@ -329,20 +874,24 @@ impl Project {
             }
         }
 
-        for (name, versions) in &graph {
-            for (version_id, node) in versions {
-                if node.ty == DependencyType::Peer {
-                    log::warn!("peer dependency {name}@{version_id} was not resolved");
-                }
-            }
+        for unmet in unmet_peer_dependencies(&graph) {
+            log::warn!("{unmet}");
         }
 
+        record_introducers(&mut graph);
+
+        self.emit(|| Event::ResolveFinished {
+            package_dir: self.package_dir().to_path_buf(),
+            packages: graph.values().map(BTreeMap::len).sum(),
+        });
+
         Ok(graph)
     }
 }
 
 /// Errors that can occur when resolving dependencies
 pub mod errors {
+    use std::path::PathBuf;
     use thiserror::Error;
 
     /// Errors that can occur when creating a dependency graph
@@ -353,10 +902,18 @@ pub mod errors {
         #[error("failed to deserialize manifest")]
         ManifestRead(#[from] crate::errors::ManifestReadError),
 
+        /// An error occurred while reading the pins file
+        #[error("failed to read pins file")]
+        PinsRead(#[from] crate::errors::PinsReadError),
+
         /// An error occurred while reading all dependencies from the manifest
         #[error("error getting all project dependencies")]
         AllDependencies(#[from] crate::manifest::errors::AllDependenciesError),
 
+        /// An error occurred while getting the dependencies of the enabled features
+        #[error("error getting dependencies of enabled features")]
+        Features(#[from] crate::manifest::errors::FeaturesError),
+
         /// An index was not found in the manifest
         #[error("index named `{0}` not found in manifest")]
         IndexNotFound(String),
@@ -372,10 +929,138 @@ pub mod errors {
 
         /// An error occurred while resolving a package
         #[error("error resolving package")]
-        Resolve(#[from] crate::source::errors::ResolveError),
+        Resolve(#[from] Box<crate::source::errors::ResolveError>),
 
         /// No matching version was found for a specifier
         #[error("no matching version found for {0}")]
         NoMatchingVersion(String),
+
+        /// The operation was cancelled
+        #[error("resolving was cancelled")]
+        Cancelled,
+    }
+
+    /// Errors that can occur when creating the dependency graphs of a workspace
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum WorkspaceDependencyGraphError {
+        /// An error occurred while getting the workspace members
+        #[error("failed to get workspace members")]
+        WorkspaceMembers(#[from] crate::errors::WorkspaceMembersError),
+
+        /// An error occurred while creating the dependency graph of a package
+        #[error("failed to create dependency graph of {0}")]
+        DependencyGraph(PathBuf, #[source] Box<DependencyGraphError>),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AuthConfig;
+
+    /// Creates a workspace with a member depending on a package, returning the workspace project
+    fn workspace(dir: &std::path::Path) -> Project {
+        std::fs::write(
+            dir.join(crate::MANIFEST_FILE_NAME),
+            r#"name = "acme/root"
+version = "1.0.0"
+workspace_members = ["member"]
+target = { environment = "luau" }
+"#,
+        )
+        .unwrap();
+
+        std::fs::create_dir(dir.join("member")).unwrap();
+        std::fs::write(
+            dir.join("member").join(crate::MANIFEST_FILE_NAME),
+            r#"name = "acme/member"
+version = "1.0.0"
+target = { environment = "luau" }
+
+[dependencies]
+bar = { name = "acme/bar", version = "^1.0.0" }
+"#,
+        )
+        .unwrap();
+
+        Project::new(
+            dir,
+            None::<PathBuf>,
+            dir.join("data"),
+            dir.join("cas"),
+            AuthConfig::default(),
+        )
+    }
+
+    #[test]
+    fn cancelling_stops_member_resolution() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = workspace(dir.path());
+        project.cancellation().cancel();
+
+        let error = project
+            .workspace_dependency_graphs(&BTreeMap::new(), &mut HashSet::new())
+            .unwrap_err();
+
+        match *error {
+            errors::WorkspaceDependencyGraphError::DependencyGraph(member, e) => {
+                assert_eq!(member, dir.path().join("member"));
+                assert!(matches!(*e, errors::DependencyGraphError::Cancelled));
+            }
+            e => panic!("unexpected error {e:?}"),
+        }
+    }
+
+    #[test]
+    fn members_use_source_replacements() {
+        let dir = tempfile::tempdir().unwrap();
+        let index = gix::Url::try_from("https://github.com/acme/index").unwrap();
+        let mirror = gix::Url::try_from("https://mirror.example.com/acme/index").unwrap();
+        let project =
+            workspace(dir.path()).with_source_replacements([(index.clone(), mirror.clone())]);
+
+        let member = project.for_member(dir.path().join("member"));
+        assert_eq!(member.source_replacements().get(&index), Some(&mirror));
+    }
+
+    #[test]
+    fn members_inherit_workspace_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let started = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let project = workspace(dir.path())
+            .with_event_handler(Some(crate::events::EventHandler::new({
+                let started = started.clone();
+                move |event| {
+                    if let Event::ResolveStarted { package_dir } = event {
+                        started.lock().unwrap().push(package_dir.clone());
+                    }
+                }
+            })))
+            .with_lenient_index(true)
+            .with_trust_policy(crate::source::pesde::TrustPolicy::Enforce)
+            .with_network_limiter(crate::source::network::NetworkLimiter::new(
+                Some(1024),
+                None,
+            ));
+
+        let member = project.for_member(dir.path().join("member"));
+        assert!(member.lenient_index());
+        assert_eq!(
+            member.trust_policy(),
+            crate::source::pesde::TrustPolicy::Enforce
+        );
+        assert!(member.network_limiter().is_limited());
+
+        // cancelled so the member's dependency isn't fetched, which happens after it starts
+        project.cancellation().cancel();
+        project
+            .workspace_dependency_graphs(&BTreeMap::new(), &mut HashSet::new())
+            .unwrap_err();
+
+        assert_eq!(
+            *started.lock().unwrap(),
+            vec![dir.path().to_path_buf(), dir.path().join("member")]
+        );
     }
 }
@@ -1,21 +1,41 @@
-use crate::Project;
+use crate::{
+    lockfile::{DependencyGraph, Graph},
+    manifest::Manifest,
+    names::PackageNames,
+    source::{refs::PackageRefs, traits::PackageRef},
+    Project,
+};
+use serde::{Deserialize, Serialize};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     ffi::OsStr,
     fmt::{Display, Formatter},
     io::{BufRead, BufReader},
     path::Path,
     process::{Command, Stdio},
     thread::spawn,
+    time::{Duration, Instant},
 };
 
 /// Script names used by pesde
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ScriptName {
     /// Generates a config for syncing tools for Roblox. For example, for Rojo it should create a `default.project.json` file
     RobloxSyncConfigGenerator,
     /// Prints a sourcemap for a Wally package, used for finding the library export file
     #[cfg(feature = "wally-compat")]
     SourcemapGenerator,
+    /// Lifecycle hook run when installing, before the dependency graph is built
+    PreInstall,
+    /// Lifecycle hook run when installing, after the dependencies have been linked and the
+    /// `post_link` commands have run
+    PostInstall,
+    /// Lifecycle hook run when publishing, before the package is packed. Also run for dry runs,
+    /// so it can build the files which are published
+    PrePublish,
+    /// Lifecycle hook run after the package has been published
+    PostPublish,
 }
 
 impl Display for ScriptName {
@@ -24,17 +44,110 @@ impl Display for ScriptName {
             ScriptName::RobloxSyncConfigGenerator => write!(f, "roblox_sync_config_generator"),
             #[cfg(feature = "wally-compat")]
             ScriptName::SourcemapGenerator => write!(f, "sourcemap_generator"),
+            ScriptName::PreInstall => write!(f, "pre_install"),
+            ScriptName::PostInstall => write!(f, "post_install"),
+            ScriptName::PrePublish => write!(f, "pre_publish"),
+            ScriptName::PostPublish => write!(f, "post_publish"),
+        }
+    }
+}
+
+/// Which packages the project's scripts, such as the Roblox sync config generator, may be run
+/// for. Packages are identified by their name, or their repository's URL for Git packages
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptPolicy {
+    default: bool,
+    decisions: BTreeMap<String, bool>,
+}
+
+impl Default for ScriptPolicy {
+    fn default() -> Self {
+        ScriptPolicy::new(true)
+    }
+}
+
+impl ScriptPolicy {
+    /// Create a new `ScriptPolicy`, allowing scripts for packages without a decision if `default`
+    /// is true
+    pub fn new(default: bool) -> Self {
+        ScriptPolicy {
+            default,
+            decisions: BTreeMap::new(),
         }
     }
+
+    /// Set whether scripts may be run for the package
+    pub fn with_decision(mut self, package: String, allowed: bool) -> Self {
+        self.decisions.insert(package, allowed);
+        self
+    }
+
+    /// Whether there is a decision for the package
+    pub fn is_decided(&self, package: &str) -> bool {
+        self.decisions.contains_key(package)
+    }
+
+    /// Whether scripts may be run for the package
+    pub fn allows(&self, package: &str) -> bool {
+        self.decisions.get(package).copied().unwrap_or(self.default)
+    }
+}
+
+/// How a package is identified in a [`ScriptPolicy`]
+pub fn script_subject(name: &PackageNames, pkg_ref: &PackageRefs) -> String {
+    match pkg_ref {
+        PackageRefs::Git(pkg_ref) => pkg_ref.repo.to_bstring().to_string(),
+        _ => name.to_string(),
+    }
+}
+
+/// The scripts of the manifest installing the graph runs, by the package they are run for
+pub fn graph_scripts(
+    manifest: &Manifest,
+    graph: &DependencyGraph,
+) -> BTreeMap<String, BTreeSet<ScriptName>> {
+    let mut scripts = BTreeMap::<String, BTreeSet<ScriptName>>::new();
+
+    for (name, versions) in graph {
+        for node in versions.values() {
+            let script = if node.pkg_ref.like_wally() {
+                #[cfg(feature = "wally-compat")]
+                {
+                    ScriptName::SourcemapGenerator
+                }
+                #[cfg(not(feature = "wally-compat"))]
+                continue;
+            } else if node.pkg_ref.target_kind().has_build_files() {
+                ScriptName::RobloxSyncConfigGenerator
+            } else {
+                continue;
+            };
+
+            if manifest.scripts.contains_key(&script.to_string()) {
+                scripts
+                    .entry(script_subject(name, &node.pkg_ref))
+                    .or_default()
+                    .insert(script);
+            }
+        }
+    }
+
+    scripts
 }
 
 pub(crate) fn execute_script<A: IntoIterator<Item = S>, S: AsRef<OsStr>>(
     script_name: ScriptName,
     script_path: &Path,
+    package: &str,
     args: A,
     project: &Project,
     return_stdout: bool,
 ) -> Result<Option<String>, std::io::Error> {
+    if !project.script_policy().allows(package) {
+        log::warn!("not running the {script_name} script for {package}, as it isn't allowed");
+        return Ok(None);
+    }
+
     match Command::new("lune")
         .arg("run")
         .arg(script_path.as_os_str())
@@ -100,3 +213,183 @@ pub(crate) fn execute_script<A: IntoIterator<Item = S>, S: AsRef<OsStr>>(
         Err(e) => Err(e),
     }
 }
+
+fn graph_entries<N>(graph: &Graph<N>) -> BTreeSet<String> {
+    graph
+        .iter()
+        .flat_map(|(name, versions)| {
+            versions
+                .keys()
+                .map(move |version_id| format!("{name}@{}", version_id.escaped()))
+        })
+        .collect()
+}
+
+fn join_entries<'a, I: IntoIterator<Item = &'a String>>(entries: I) -> String {
+    entries
+        .into_iter()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+impl Project {
+    /// Runs the manifest's script for a lifecycle hook with Lune, if it has one. Nothing is run if
+    /// hooks are disabled for the project. The script is stopped if it runs for longer than its
+    /// timeout in the manifest's `hook_timeouts`
+    pub fn run_lifecycle_hook(&self, hook: ScriptName) -> Result<(), errors::LifecycleHookError> {
+        if !self.hooks() {
+            return Ok(());
+        }
+
+        let manifest = self.deser_manifest()?;
+        let Some(script_path) = manifest.scripts.get(&hook.to_string()) else {
+            return Ok(());
+        };
+        let timeout = manifest
+            .hook_timeouts
+            .get(&hook)
+            .map(|secs| Duration::from_secs(*secs));
+
+        log::debug!("running {hook} hook");
+
+        let mut child = Command::new("lune")
+            .arg("run")
+            .arg(script_path.to_path(self.package_dir()).as_os_str())
+            .current_dir(self.package_dir())
+            .env("PESDE_ROOT", self.package_dir())
+            .spawn()
+            .map_err(|e| errors::LifecycleHookError::Spawn(hook, e))?;
+
+        let status = match timeout {
+            Some(timeout) => {
+                let start = Instant::now();
+
+                loop {
+                    if let Some(status) = child
+                        .try_wait()
+                        .map_err(|e| errors::LifecycleHookError::Wait(hook, e))?
+                    {
+                        break status;
+                    }
+
+                    if start.elapsed() >= timeout {
+                        // the hook is abandoned either way, so failing to stop it isn't reported
+                        let _ = child.kill();
+                        let _ = child.wait();
+
+                        return Err(errors::LifecycleHookError::TimedOut(hook, timeout));
+                    }
+
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            }
+            None => child
+                .wait()
+                .map_err(|e| errors::LifecycleHookError::Wait(hook, e))?,
+        };
+
+        if !status.success() {
+            return Err(errors::LifecycleHookError::Failed(hook, status));
+        }
+
+        Ok(())
+    }
+
+    /// Runs the project's `post_link` commands. They receive the packages which are linked, and those
+    /// added or removed compared to the previous graph, in the `PESDE_LINKED`, `PESDE_ADDED` and
+    /// `PESDE_REMOVED` environment variables as newline separated `name@version+target` entries
+    pub fn run_post_link_hooks<N, M>(
+        &self,
+        previous_graph: Option<&Graph<N>>,
+        graph: &Graph<M>,
+    ) -> Result<(), errors::PostLinkError> {
+        let manifest = self.deser_manifest()?;
+        if manifest.post_link.is_empty() {
+            return Ok(());
+        }
+
+        let linked = graph_entries(graph);
+        let previous = previous_graph.map(graph_entries).unwrap_or_default();
+
+        for command in &manifest.post_link {
+            log::debug!("running post link command `{command}`");
+
+            #[cfg(windows)]
+            let mut process = {
+                let mut process = Command::new("cmd");
+                process.arg("/C").arg(command);
+                process
+            };
+            #[cfg(not(windows))]
+            let mut process = {
+                let mut process = Command::new("sh");
+                process.arg("-c").arg(command);
+                process
+            };
+
+            let status = process
+                .current_dir(self.package_dir())
+                .env("PESDE_ROOT", self.package_dir())
+                .env("PESDE_LINKED", join_entries(&linked))
+                .env("PESDE_ADDED", join_entries(linked.difference(&previous)))
+                .env("PESDE_REMOVED", join_entries(previous.difference(&linked)))
+                .status()
+                .map_err(|e| errors::PostLinkError::Spawn(command.clone(), e))?;
+
+            if !status.success() {
+                return Err(errors::PostLinkError::Failed(command.clone(), status));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Errors that can occur when running scripts
+pub mod errors {
+    use crate::scripts::ScriptName;
+    use thiserror::Error;
+
+    /// Errors that can occur when running a lifecycle hook
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum LifecycleHookError {
+        /// An error occurred while reading the manifest
+        #[error("error reading manifest")]
+        ManifestRead(#[from] crate::errors::ManifestReadError),
+
+        /// The hook's script could not be started
+        #[error("error starting `{0}` hook")]
+        Spawn(ScriptName, #[source] std::io::Error),
+
+        /// An error occurred while waiting for the hook's script to exit
+        #[error("error waiting for `{0}` hook")]
+        Wait(ScriptName, #[source] std::io::Error),
+
+        /// The hook's script ran for longer than its timeout
+        #[error("`{0}` hook timed out after {1:?}")]
+        TimedOut(ScriptName, std::time::Duration),
+
+        /// The hook's script exited unsuccessfully
+        #[error("`{0}` hook failed with {1}")]
+        Failed(ScriptName, std::process::ExitStatus),
+    }
+
+    /// Errors that can occur when running post link commands
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum PostLinkError {
+        /// An error occurred while reading the manifest
+        #[error("error reading manifest")]
+        ManifestRead(#[from] crate::errors::ManifestReadError),
+
+        /// A command could not be started
+        #[error("error starting post link command `{0}`")]
+        Spawn(String, #[source] std::io::Error),
+
+        /// A command exited unsuccessfully
+        #[error("post link command `{0}` failed with {1}")]
+        Failed(String, std::process::ExitStatus),
+    }
+}
@@ -59,10 +59,42 @@ pub(crate) fn store_in_cas<P: AsRef<Path>>(
     Ok((hash, cas_path))
 }
 
-pub(crate) fn store_reader_in_cas<P: AsRef<Path>>(
+/// A file written to the CAS's temporary directory, which is deleted unless it is committed
+pub(crate) struct StagedFile {
+    hash: String,
+    path: tempfile::TempPath,
+}
+
+impl StagedFile {
+    /// Moves the file into the CAS, returning its hash
+    pub(crate) fn commit<P: AsRef<Path>>(self, cas_dir: P) -> std::io::Result<String> {
+        let (prefix, rest) = self.hash.split_at(2);
+
+        let folder = cas_dir.as_ref().join(prefix);
+        std::fs::create_dir_all(&folder)?;
+
+        let cas_path = folder.join(rest);
+        match self.path.persist_noclobber(&cas_path) {
+            Ok(()) => {
+                // prevent the CAS from being corrupted due to accidental modifications
+                let mut permissions = std::fs::metadata(&cas_path)?.permissions();
+                permissions.set_readonly(true);
+                std::fs::set_permissions(&cas_path, permissions)?;
+            }
+            Err(e) if e.error.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(e.error),
+        };
+
+        Ok(self.hash)
+    }
+}
+
+/// Writes the contents to the CAS's temporary directory, so they can be committed once they are
+/// known to be wanted
+pub(crate) fn stage_reader_in_cas<P: AsRef<Path>>(
     cas_dir: P,
     contents: &mut dyn Read,
-) -> std::io::Result<String> {
+) -> std::io::Result<StagedFile> {
     let tmp_dir = cas_dir.as_ref().join(".tmp");
     std::fs::create_dir_all(&tmp_dir)?;
     let mut hasher = Sha256::new();
@@ -80,25 +112,19 @@ pub(crate) fn store_reader_in_cas<P: AsRef<Path>>(
         file_writer.write_all(bytes)?;
     }
 
-    let hash = format!("{:x}", hasher.finalize());
-    let (prefix, rest) = hash.split_at(2);
-
-    let folder = cas_dir.as_ref().join(prefix);
-    std::fs::create_dir_all(&folder)?;
-
-    let cas_path = folder.join(rest);
-    match file_writer.into_inner()?.persist_noclobber(cas_path) {
-        Ok(f) => {
-            // prevent the CAS from being corrupted due to accidental modifications
-            let mut permissions = f.metadata()?.permissions();
-            permissions.set_readonly(true);
-            f.set_permissions(permissions)?;
-        }
-        Err(e) if e.error.kind() == std::io::ErrorKind::AlreadyExists => {}
-        Err(e) => return Err(e.error),
-    };
+    Ok(StagedFile {
+        hash: format!("{:x}", hasher.finalize()),
+        // closes the file, so packages with many files don't exhaust file descriptors
+        path: file_writer.into_inner()?.into_temp_path(),
+    })
+}
 
-    Ok(hash)
+#[cfg(feature = "wally-compat")]
+pub(crate) fn store_reader_in_cas<P: AsRef<Path>>(
+    cas_dir: P,
+    contents: &mut dyn Read,
+) -> std::io::Result<String> {
+    stage_reader_in_cas(&cas_dir, contents)?.commit(&cas_dir)
 }
 
 fn copy_dir_all(
@@ -191,6 +217,24 @@ impl PackageFS {
         Ok(())
     }
 
+    /// Returns the paths of the CAS files this file system consists of
+    pub fn cas_files<P: AsRef<Path>>(&self, cas_path: P) -> Vec<PathBuf> {
+        let PackageFS::CAS(entries) = self else {
+            return vec![];
+        };
+
+        entries
+            .values()
+            .filter_map(|entry| match entry {
+                FSEntry::File(hash) => {
+                    let (prefix, rest) = hash.split_at(2);
+                    Some(cas_path.as_ref().join(prefix).join(rest))
+                }
+                FSEntry::Directory => None,
+            })
+            .collect()
+    }
+
     /// Returns the contents of the file with the given hash
     pub fn read_file<P: AsRef<Path>, H: AsRef<str>>(
         &self,
@@ -206,3 +250,60 @@ impl PackageFS {
         std::fs::read_to_string(cas_file_path).ok()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cas_files(cas_dir: &Path) -> Vec<PathBuf> {
+        let mut files = vec![];
+        let mut dirs = vec![cas_dir.to_path_buf()];
+
+        while let Some(dir) = dirs.pop() {
+            for entry in std::fs::read_dir(dir).unwrap() {
+                let path = entry.unwrap().path();
+                if path.is_dir() {
+                    dirs.push(path);
+                } else {
+                    files.push(path);
+                }
+            }
+        }
+
+        files
+    }
+
+    #[test]
+    fn uncommitted_files_are_removed() {
+        let cas_dir = tempfile::tempdir().unwrap();
+
+        let staged = stage_reader_in_cas(cas_dir.path(), &mut "contents".as_bytes()).unwrap();
+        assert_eq!(cas_files(cas_dir.path()).len(), 1);
+
+        drop(staged);
+        assert!(cas_files(cas_dir.path()).is_empty());
+    }
+
+    #[test]
+    fn committed_files_are_stored_by_hash() {
+        let cas_dir = tempfile::tempdir().unwrap();
+
+        let hash = stage_reader_in_cas(cas_dir.path(), &mut "contents".as_bytes())
+            .unwrap()
+            .commit(cas_dir.path())
+            .unwrap();
+        assert_eq!(hash, crate::util::hash("contents".as_bytes()));
+
+        let (prefix, rest) = hash.split_at(2);
+        let path = cas_dir.path().join(prefix).join(rest);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+        assert!(std::fs::metadata(&path).unwrap().permissions().readonly());
+
+        // committing the same contents again keeps the stored file
+        stage_reader_in_cas(cas_dir.path(), &mut "contents".as_bytes())
+            .unwrap()
+            .commit(cas_dir.path())
+            .unwrap();
+        assert_eq!(cas_files(cas_dir.path()), [path]);
+    }
+}
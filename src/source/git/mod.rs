@@ -4,7 +4,9 @@ use gix::{bstr::BStr, traverse::tree::Recorder, Url};
 use relative_path::RelativePathBuf;
 
 use crate::{
+    events::Event,
     manifest::{
+        migration::{parse_legacy_manifest, parse_manifest, LEGACY_MANIFEST_FILE_NAME},
         target::{Target, TargetKind},
         Manifest,
     },
@@ -12,7 +14,7 @@ use crate::{
     source::{
         fs::{store_in_cas, FSEntry, PackageFS},
         git::{pkg_ref::GitPackageRef, specifier::GitDependencySpecifier},
-        git_index::GitBasedSource,
+        git_index::{open_repo, GitBasedSource},
         specifiers::DependencySpecifiers,
         PackageSource, ResolveResult, VersionId, IGNORED_DIRS, IGNORED_FILES,
     },
@@ -25,6 +27,15 @@ pub mod pkg_ref;
 /// The Git dependency specifier
 pub mod specifier;
 
+/// Parses a package's manifest, which is the legacy YAML one if `legacy` is true
+fn deser_manifest(contents: &str, legacy: bool) -> Result<Manifest, toml::de::Error> {
+    if legacy {
+        parse_legacy_manifest(contents).map(|(manifest, _)| manifest)
+    } else {
+        parse_manifest(contents)
+    }
+}
+
 /// The Git package source
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
 pub struct GitPackageSource {
@@ -53,6 +64,15 @@ impl GitPackageSource {
     fn as_bytes(&self) -> Vec<u8> {
         self.repo_url.to_bstring().to_vec()
     }
+
+    /// The path to the file in which the file system of the given package is cached
+    pub(crate) fn cached_index_file(&self, pkg_ref: &GitPackageRef, project: &Project) -> PathBuf {
+        project
+            .cas_dir
+            .join("git_index")
+            .join(hash(self.as_bytes()))
+            .join(&pkg_ref.tree_id)
+    }
 }
 
 impl PackageSource for GitPackageSource {
@@ -72,7 +92,7 @@ impl PackageSource for GitPackageSource {
         project: &Project,
         _project_target: TargetKind,
     ) -> Result<ResolveResult<Self::Ref>, Self::ResolveError> {
-        let repo = gix::open(self.path(project))
+        let repo = open_repo(&self.path(project))
             .map_err(|e| errors::ResolveError::OpenRepo(Box::new(self.repo_url.clone()), e))?;
         let rev = repo
             .rev_parse_single(BStr::new(&specifier.rev))
@@ -80,7 +100,7 @@ impl PackageSource for GitPackageSource {
                 errors::ResolveError::ParseRev(
                     specifier.rev.clone(),
                     Box::new(self.repo_url.clone()),
-                    e,
+                    Box::new(e),
                 )
             })?;
 
@@ -126,21 +146,26 @@ impl PackageSource for GitPackageSource {
             root_tree.clone()
         };
 
-        let manifest = match self
-            .read_file([MANIFEST_FILE_NAME], project, Some(tree.clone()))
-            .map_err(|e| errors::ResolveError::ReadManifest(Box::new(self.repo_url.clone()), e))?
-        {
-            Some(m) => match toml::from_str::<Manifest>(&m) {
-                Ok(m) => Some(m),
-                Err(e) => {
-                    return Err(errors::ResolveError::DeserManifest(
-                        Box::new(self.repo_url.clone()),
-                        e,
-                    ))
-                }
-            },
-            None => None,
-        };
+        let mut manifest = None;
+        // packages published before the manifest was TOML are still resolvable
+        for (file_name, legacy) in [
+            (MANIFEST_FILE_NAME, false),
+            (LEGACY_MANIFEST_FILE_NAME, true),
+        ] {
+            let Some(m) = self
+                .read_file([file_name], project, Some(tree.clone()))
+                .map_err(|e| {
+                    errors::ResolveError::ReadManifest(Box::new(self.repo_url.clone()), e)
+                })?
+            else {
+                continue;
+            };
+
+            manifest = Some(deser_manifest(&m, legacy).map_err(|e| {
+                errors::ResolveError::DeserManifest(Box::new(self.repo_url.clone()), e)
+            })?);
+            break;
+        }
 
         let (name, version_id, dependencies) = match manifest {
             Some(manifest) => {
@@ -245,8 +270,15 @@ impl PackageSource for GitPackageSource {
                                     repo: self.repo_url.clone(),
                                     rev: rev.to_string(),
                                     path: Some(path),
+                                    optional: specifier.optional,
                                 })
                             }
+                            DependencySpecifiers::Path(_) => {
+                                return Err(errors::ResolveError::PathDependency(
+                                    alias,
+                                    Box::new(self.repo_url.clone()),
+                                ));
+                            }
                         }
 
                         Ok((alias, (spec, ty)))
@@ -331,11 +363,7 @@ impl PackageSource for GitPackageSource {
         project: &Project,
         _reqwest: &reqwest::blocking::Client,
     ) -> Result<(PackageFS, Target), Self::DownloadError> {
-        let index_file = project
-            .cas_dir
-            .join("git_index")
-            .join(hash(self.as_bytes()))
-            .join(&pkg_ref.tree_id);
+        let index_file = self.cached_index_file(pkg_ref, project);
 
         match std::fs::read_to_string(&index_file) {
             Ok(s) => {
@@ -351,24 +379,23 @@ impl PackageSource for GitPackageSource {
                 })?;
 
                 let manifest = match &fs {
-                    PackageFS::CAS(entries) => {
-                        match entries.get(&RelativePathBuf::from(MANIFEST_FILE_NAME)) {
-                            Some(FSEntry::File(hash)) => match fs
+                    PackageFS::CAS(entries) => [
+                        (MANIFEST_FILE_NAME, false),
+                        (LEGACY_MANIFEST_FILE_NAME, true),
+                    ]
+                    .into_iter()
+                    .find_map(|(file_name, legacy)| {
+                        match entries.get(&RelativePathBuf::from(file_name)) {
+                            Some(FSEntry::File(hash)) => fs
                                 .read_file(hash, project.cas_dir())
-                                .map(|m| toml::de::from_str::<Manifest>(&m))
-                            {
-                                Some(Ok(m)) => Some(m),
-                                Some(Err(e)) => {
-                                    return Err(errors::DownloadError::DeserializeFile(
-                                        Box::new(self.repo_url.clone()),
-                                        e,
-                                    ))
-                                }
-                                None => None,
-                            },
+                                .map(|m| deser_manifest(&m, legacy)),
                             _ => None,
                         }
-                    }
+                    })
+                    .transpose()
+                    .map_err(|e| {
+                        errors::DownloadError::DeserializeFile(Box::new(self.repo_url.clone()), e)
+                    })?,
                     _ => unreachable!("the package fs should be CAS"),
                 };
 
@@ -376,10 +403,14 @@ impl PackageSource for GitPackageSource {
                     Some(manifest) => manifest.target,
                     #[cfg(feature = "wally-compat")]
                     None if !pkg_ref.new_structure => {
-                        let tempdir = tempfile::tempdir()?;
+                        let tempdir = crate::util::tempdirs::tempdir()?;
                         fs.write_to(tempdir.path(), project.cas_dir(), false)?;
 
-                        crate::source::wally::compat_util::get_target(project, &tempdir)?
+                        crate::source::wally::compat_util::get_target(
+                            project,
+                            &pkg_ref.repo.to_bstring().to_string(),
+                            &tempdir,
+                        )?
                     }
                     None => {
                         return Err(errors::DownloadError::NoManifest(Box::new(
@@ -394,7 +425,7 @@ impl PackageSource for GitPackageSource {
             Err(e) => return Err(errors::DownloadError::Io(e)),
         }
 
-        let repo = gix::open(self.path(project))
+        let repo = open_repo(&self.path(project))
             .map_err(|e| errors::DownloadError::OpenRepo(Box::new(self.repo_url.clone()), e))?;
         let rev = repo
             .rev_parse_single(BStr::new(&pkg_ref.tree_id))
@@ -402,7 +433,7 @@ impl PackageSource for GitPackageSource {
                 errors::DownloadError::ParseRev(
                     pkg_ref.tree_id.clone(),
                     Box::new(self.repo_url.clone()),
-                    e,
+                    Box::new(e),
                 )
             })?;
         let tree = rev
@@ -422,6 +453,12 @@ impl PackageSource for GitPackageSource {
 
         let mut entries = BTreeMap::new();
         let mut manifest = None;
+        let skipped = |path| {
+            project.emit(|| Event::FileSkipped {
+                package: format!("{}#{}", pkg_ref.repo, pkg_ref.tree_id),
+                path,
+            })
+        };
 
         for entry in recorder.records {
             let path = RelativePathBuf::from(entry.filepath.to_string());
@@ -435,6 +472,7 @@ impl PackageSource for GitPackageSource {
                     .next()
                     .is_some_and(|ct| IGNORED_DIRS.contains(&ct.as_str()))
                 {
+                    skipped(path);
                     continue;
                 }
 
@@ -444,6 +482,7 @@ impl PackageSource for GitPackageSource {
             }
 
             if IGNORED_FILES.contains(&path.as_str()) {
+                skipped(path);
                 continue;
             }
 
@@ -451,15 +490,17 @@ impl PackageSource for GitPackageSource {
             let hash = store_in_cas(project.cas_dir(), &data)?.0;
 
             if path == MANIFEST_FILE_NAME {
-                manifest = Some(data);
+                manifest = Some((data, false));
+            } else if path == LEGACY_MANIFEST_FILE_NAME && manifest.is_none() {
+                manifest = Some((data, true));
             }
 
             entries.insert(path, FSEntry::File(hash));
         }
 
         let manifest = match manifest {
-            Some(data) => match String::from_utf8(data.to_vec()) {
-                Ok(s) => match toml::from_str::<Manifest>(&s) {
+            Some((data, legacy)) => match String::from_utf8(data.to_vec()) {
+                Ok(s) => match deser_manifest(&s, legacy) {
                     Ok(m) => Some(m),
                     Err(e) => {
                         return Err(errors::DownloadError::DeserializeFile(
@@ -479,10 +520,14 @@ impl PackageSource for GitPackageSource {
             Some(manifest) => manifest.target,
             #[cfg(feature = "wally-compat")]
             None if !pkg_ref.new_structure => {
-                let tempdir = tempfile::tempdir()?;
+                let tempdir = crate::util::tempdirs::tempdir()?;
                 fs.write_to(tempdir.path(), project.cas_dir(), false)?;
 
-                crate::source::wally::compat_util::get_target(project, &tempdir)?
+                crate::source::wally::compat_util::get_target(
+                    project,
+                    &pkg_ref.repo.to_bstring().to_string(),
+                    &tempdir,
+                )?
             }
             None => {
                 return Err(errors::DownloadError::NoManifest(Box::new(
@@ -519,14 +564,14 @@ pub mod errors {
     pub enum ResolveError {
         /// An error occurred opening the Git repository
         #[error("error opening Git repository for url {0}")]
-        OpenRepo(Box<gix::Url>, #[source] gix::open::Error),
+        OpenRepo(Box<gix::Url>, #[source] Box<gix::open::Error>),
 
         /// An error occurred parsing rev
         #[error("error parsing rev {0} for repository {1}")]
         ParseRev(
             String,
             Box<gix::Url>,
-            #[source] gix::revision::spec::parse::single::Error,
+            #[source] Box<gix::revision::spec::parse::single::Error>,
         ),
 
         /// An error occurred parsing rev to object
@@ -601,6 +646,10 @@ pub mod errors {
         /// No path for a workspace member was found in the lockfile
         #[error("no path found for workspace member {0} {1} in lockfile for repository {2}")]
         NoPathForWorkspaceMember(String, TargetKind, Box<gix::Url>),
+
+        /// The manifest has a path dependency, which can't be resolved from a repository
+        #[error("path dependency {0} isn't supported in repository {1}")]
+        PathDependency(String, Box<gix::Url>),
     }
 
     /// Errors that can occur when downloading a package from a Git package source
@@ -626,14 +675,14 @@ pub mod errors {
 
         /// An error occurred opening the Git repository
         #[error("error opening Git repository for url {0}")]
-        OpenRepo(Box<gix::Url>, #[source] gix::open::Error),
+        OpenRepo(Box<gix::Url>, #[source] Box<gix::open::Error>),
 
         /// An error occurred parsing rev
         #[error("error parsing rev {0} for repository {1}")]
         ParseRev(
             String,
             Box<gix::Url>,
-            #[source] gix::revision::spec::parse::single::Error,
+            #[source] Box<gix::revision::spec::parse::single::Error>,
         ),
 
         /// An error occurred while traversing the tree
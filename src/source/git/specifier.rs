@@ -18,6 +18,9 @@ pub struct GitDependencySpecifier {
     /// The path of the package in the repository
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub path: Option<RelativePathBuf>,
+    /// Whether the dependency may be missing, in which case a stub is linked in its place
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
 }
 impl DependencySpecifier for GitDependencySpecifier {}
 
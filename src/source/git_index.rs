@@ -1,7 +1,51 @@
 use gix::remote::Direction;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock, PoisonError},
+};
 
 use crate::{util::authenticate_conn, Project};
 
+/// The size of the object cache of each repository handle, so trees and blobs read repeatedly
+/// during resolution and downloads are only decompressed once
+const OBJECT_CACHE_SIZE: usize = 4 * 1024 * 1024;
+
+/// Repositories opened by this process, shared between threads so their configuration and pack
+/// indices are only loaded once
+fn repos() -> &'static Mutex<HashMap<PathBuf, gix::ThreadSafeRepository>> {
+    static REPOS: OnceLock<Mutex<HashMap<PathBuf, gix::ThreadSafeRepository>>> = OnceLock::new();
+    REPOS.get_or_init(Default::default)
+}
+
+/// Opens the repository at the given path, reusing the shared handle if it was opened before.
+/// Each returned handle has its own object cache
+pub(crate) fn open_repo(path: &Path) -> Result<gix::Repository, Box<gix::open::Error>> {
+    let mut repos = repos().lock().unwrap_or_else(PoisonError::into_inner);
+
+    let mut repo = match repos.get(path) {
+        Some(repo) => repo.to_thread_local(),
+        None => {
+            let repo = gix::open(path).map_err(Box::new)?;
+            repos.insert(path.to_path_buf(), repo.clone().into_sync());
+            repo
+        }
+    };
+    drop(repos);
+
+    repo.object_cache_size_if_unset(OBJECT_CACHE_SIZE);
+    Ok(repo)
+}
+
+/// Forgets the shared handle of the repository at the given path, for example after it was
+/// fetched, so later calls see its new state
+pub(crate) fn forget_repo(path: &Path) {
+    repos()
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(path);
+}
+
 /// A trait for sources that are based on Git repositories
 pub trait GitBasedSource {
     /// The path to the index
@@ -10,8 +54,8 @@ pub trait GitBasedSource {
     /// The URL of the repository
     fn repo_url(&self) -> &gix::Url;
 
-    /// Gets the tree of the repository
-    fn tree<'a>(&'a self, repo: &'a gix::Repository) -> Result<gix::Tree<'a>, errors::TreeError> {
+    /// Gets the name of the local reference the repository's tree is read from
+    fn local_ref_name(&self, repo: &gix::Repository) -> Result<String, errors::TreeError> {
         // this is a bare repo, so this is the actual path
         let path = repo.path().to_path_buf();
 
@@ -29,13 +73,34 @@ pub trait GitBasedSource {
         };
 
         let spec_ref = refspec.to_ref();
-        let local_ref = match spec_ref.local() {
-            Some(local) => local
+        match spec_ref.local() {
+            Some(local) => Ok(local
                 .to_string()
-                .replace('*', repo.branch_names().first().unwrap_or(&"main")),
-            None => return Err(errors::TreeError::NoLocalRefSpec(path)),
+                .replace('*', repo.branch_names().first().unwrap_or(&"main"))),
+            None => Err(errors::TreeError::NoLocalRefSpec(path)),
+        }
+    }
+
+    /// Gets the ID of the commit the repository's tree is read from
+    fn commit_id(&self, repo: &gix::Repository) -> Result<String, errors::TreeError> {
+        let local_ref = self.local_ref_name(repo)?;
+
+        let reference = match repo.find_reference(&local_ref) {
+            Ok(reference) => reference,
+            Err(e) => return Err(errors::TreeError::NoReference(local_ref.to_string(), e)),
         };
 
+        let reference_name = reference.name().as_bstr().to_string();
+        match reference.into_fully_peeled_id() {
+            Ok(id) => Ok(id.to_string()),
+            Err(e) => Err(errors::TreeError::CannotPeel(reference_name, e)),
+        }
+    }
+
+    /// Gets the tree of the repository
+    fn tree<'a>(&'a self, repo: &'a gix::Repository) -> Result<gix::Tree<'a>, errors::TreeError> {
+        let local_ref = self.local_ref_name(repo)?;
+
         let reference = match repo.find_reference(&local_ref) {
             Ok(reference) => reference,
             Err(e) => return Err(errors::TreeError::NoReference(local_ref.to_string(), e)),
@@ -68,9 +133,9 @@ pub trait GitBasedSource {
     ) -> Result<Option<String>, errors::ReadFile> {
         let path = self.path(project);
 
-        let repo = match gix::open(&path) {
+        let repo = match open_repo(&path) {
             Ok(repo) => repo,
-            Err(e) => return Err(errors::ReadFile::Open(path, Box::new(e))),
+            Err(e) => return Err(errors::ReadFile::Open(path, e)),
         };
 
         let tree = match tree.map_or_else(|| self.tree(&repo), Ok) {
@@ -104,15 +169,91 @@ pub trait GitBasedSource {
         Ok(Some(string))
     }
 
+    /// Finds the path of an entry in the repository whose components equal the given ones when
+    /// ignoring ASCII case, returning its components as they are in the repository
+    fn find_ignoring_case(
+        &self,
+        file_path: &[&str],
+        project: &Project,
+    ) -> Result<Option<Vec<String>>, errors::ReadFile> {
+        let path = self.path(project);
+
+        let repo = match open_repo(&path) {
+            Ok(repo) => repo,
+            Err(e) => return Err(errors::ReadFile::Open(path, e)),
+        };
+
+        let mut tree = match self.tree(&repo) {
+            Ok(tree) => tree,
+            Err(e) => return Err(errors::ReadFile::Tree(path, Box::new(e))),
+        };
+
+        let mut found = vec![];
+
+        for (i, part) in file_path.iter().enumerate() {
+            let Some((name, id)) = tree.iter().filter_map(Result::ok).find_map(|entry| {
+                let name = entry.filename().to_string();
+                name.eq_ignore_ascii_case(part)
+                    .then(|| (name, entry.object_id()))
+            }) else {
+                return Ok(None);
+            };
+
+            found.push(name);
+
+            if i + 1 < file_path.len() {
+                let object = repo
+                    .find_object(id)
+                    .map_err(|e| errors::ReadFile::Lookup(found.join("/"), e))?;
+
+                tree = match object.try_into_tree() {
+                    Ok(tree) => tree,
+                    Err(_) => return Ok(None),
+                };
+            }
+        }
+
+        Ok(Some(found))
+    }
+
     /// Refreshes the repository
     fn refresh(&self, project: &Project) -> Result<(), errors::RefreshError> {
         let path = self.path(project);
+
+        if project.offline() || project.frozen() {
+            if path.exists() {
+                log::debug!(
+                    "offline or frozen, using existing clone of {}",
+                    self.repo_url()
+                );
+                return Ok(());
+            }
+
+            return Err(if project.offline() {
+                errors::RefreshError::Offline(self.repo_url().to_string())
+            } else {
+                errors::RefreshError::Frozen(self.repo_url().to_string())
+            });
+        }
+
+        // mirrors are only fetched from, the repository keeps being identified by its own URL
+        let fetch_url = match project.source_replacement(self.repo_url()) {
+            Some(url) => {
+                log::debug!("fetching {} from {url}", self.repo_url());
+                url
+            }
+            None => self.repo_url(),
+        };
+
+        let _permit = project.network_limiter().connect(fetch_url.host());
+        project.network_limiter().record_git_fetch();
+
         if path.exists() {
-            let repo = match gix::open(&path) {
+            let repo = match open_repo(&path) {
                 Ok(repo) => repo,
-                Err(e) => return Err(errors::RefreshError::Open(path, Box::new(e))),
+                Err(e) => return Err(errors::RefreshError::Open(path, e)),
             };
-            let remote = match repo.find_default_remote(Direction::Fetch) {
+            let mut remote = match repo.find_default_remote(Direction::Fetch) {
                 Some(Ok(remote)) => remote,
                 Some(Err(e)) => {
                     return Err(errors::RefreshError::GetDefaultRemote(path, Box::new(e)))
@@ -122,21 +263,39 @@ pub trait GitBasedSource {
                 }
             };
 
-            let mut connection = remote.connect(Direction::Fetch).map_err(|e| {
-                errors::RefreshError::Connect(self.repo_url().to_string(), Box::new(e))
-            })?;
+            // the repository may have been cloned from before the replacement changed, so fetch
+            // into the same references from the current URL
+            if remote.url(Direction::Fetch) != Some(fetch_url) {
+                let refspecs = remote
+                    .refspecs(Direction::Fetch)
+                    .iter()
+                    .map(|spec| spec.to_ref().to_bstring())
+                    .collect::<Vec<_>>();
+
+                remote = repo
+                    .remote_at(fetch_url.clone())
+                    .map_err(|e| errors::RefreshError::Remote(fetch_url.to_string(), Box::new(e)))?
+                    .with_refspecs(refspecs, Direction::Fetch)
+                    .map_err(|e| {
+                        errors::RefreshError::RefSpec(fetch_url.to_string(), Box::new(e))
+                    })?;
+            }
+
+            let mut connection = remote
+                .connect(Direction::Fetch)
+                .map_err(|e| errors::RefreshError::Connect(fetch_url.to_string(), Box::new(e)))?;
 
             authenticate_conn(&mut connection, &project.auth_config);
 
             connection
                 .prepare_fetch(gix::progress::Discard, Default::default())
                 .map_err(|e| {
-                    errors::RefreshError::PrepareFetch(self.repo_url().to_string(), Box::new(e))
+                    errors::RefreshError::PrepareFetch(fetch_url.to_string(), Box::new(e))
                 })?
                 .receive(gix::progress::Discard, &false.into())
-                .map_err(|e| {
-                    errors::RefreshError::Read(self.repo_url().to_string(), Box::new(e))
-                })?;
+                .map_err(|e| errors::RefreshError::Read(fetch_url.to_string(), Box::new(e)))?;
+
+            forget_repo(&path);
 
             return Ok(());
         }
@@ -145,14 +304,14 @@ pub trait GitBasedSource {
 
         let auth_config = project.auth_config.clone();
 
-        gix::prepare_clone_bare(self.repo_url().clone(), &path)
-            .map_err(|e| errors::RefreshError::Clone(self.repo_url().to_string(), Box::new(e)))?
+        gix::prepare_clone_bare(fetch_url.clone(), &path)
+            .map_err(|e| errors::RefreshError::Clone(fetch_url.to_string(), Box::new(e)))?
             .configure_connection(move |c| {
                 authenticate_conn(c, &auth_config);
                 Ok(())
             })
             .fetch_only(gix::progress::Discard, &false.into())
-            .map_err(|e| errors::RefreshError::Fetch(self.repo_url().to_string(), Box::new(e)))?;
+            .map_err(|e| errors::RefreshError::Fetch(fetch_url.to_string(), Box::new(e)))?;
 
         Ok(())
     }
@@ -184,6 +343,14 @@ pub mod errors {
         #[error("error getting default remote from repository at {0}")]
         GetDefaultRemote(PathBuf, #[source] Box<gix::remote::find::existing::Error>),
 
+        /// Error creating a remote for the replacement of the repository
+        #[error("error creating remote for {0}")]
+        Remote(String, #[source] Box<gix::remote::init::Error>),
+
+        /// Error parsing the refspecs of the repository's default remote
+        #[error("error parsing refspecs for {0}")]
+        RefSpec(String, #[source] Box<gix::refspec::parse::Error>),
+
         /// Error connecting to remote repository
         #[error("error connecting to remote repository at {0}")]
         Connect(String, #[source] Box<gix::remote::connect::Error>),
@@ -203,6 +370,14 @@ pub mod errors {
         /// Error fetching repository
         #[error("error fetching repository from {0}")]
         Fetch(String, #[source] Box<gix::clone::fetch::Error>),
+
+        /// The repository has not been cloned yet, and the project is offline
+        #[error("repository {0} has not been cloned yet, and offline mode is enabled")]
+        Offline(String),
+
+        /// The repository has not been cloned yet, and the project is frozen
+        #[error("repository {0} has not been cloned yet, and the project is frozen")]
+        Frozen(String),
     }
 
     /// Errors that can occur when reading a git-based package source's tree
@@ -267,3 +442,32 @@ pub mod errors {
         Utf8(String, #[source] std::string::FromUtf8Error),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_repos_between_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_path_buf();
+        gix::init_bare(&path).unwrap();
+
+        let git_dir = open_repo(&path).unwrap().git_dir().to_path_buf();
+
+        // other threads reuse the handle opened by this one
+        let shared = std::thread::spawn({
+            let path = path.clone();
+            move || {
+                let cached = repos().lock().unwrap().contains_key(&path);
+                (cached, open_repo(&path).unwrap().git_dir().to_path_buf())
+            }
+        })
+        .join()
+        .unwrap();
+        assert_eq!(shared, (true, git_dir));
+
+        forget_repo(&path);
+        assert!(!repos().lock().unwrap().contains_key(&path));
+    }
+}
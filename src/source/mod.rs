@@ -1,4 +1,8 @@
-use std::{collections::BTreeMap, fmt::Debug};
+use std::{
+    collections::BTreeMap,
+    fmt::{Debug, Display, Formatter},
+    path::PathBuf,
+};
 
 use crate::{
     manifest::target::{Target, TargetKind},
@@ -16,10 +20,18 @@ pub mod fs;
 pub mod git;
 /// Git index-based package source utilities
 pub mod git_index;
+/// Limiting and counting network usage
+pub mod network;
+/// The path package source
+pub mod path;
 /// The pesde package source
 pub mod pesde;
+/// Streaming package downloads and reporting their progress
+pub mod progress;
 /// Package references
 pub mod refs;
+/// Retrying failed downloads
+pub mod retry;
 /// Dependency specifiers
 pub mod specifiers;
 /// Traits for sources and packages
@@ -53,6 +65,81 @@ pub enum PackageSources {
     Git(git::GitPackageSource),
     /// A workspace package source
     Workspace(workspace::WorkspacePackageSource),
+    /// A path package source
+    Path(path::PathPackageSource),
+}
+
+impl PackageSources {
+    /// The path to the file in which this source caches the file system of the given package, if it does
+    pub fn cached_index_file(&self, pkg_ref: &PackageRefs, project: &Project) -> Option<PathBuf> {
+        match (self, pkg_ref) {
+            (PackageSources::Pesde(source), PackageRefs::Pesde(pkg_ref)) => {
+                Some(source.cached_index_file(pkg_ref, project))
+            }
+            #[cfg(feature = "wally-compat")]
+            (PackageSources::Wally(source), PackageRefs::Wally(pkg_ref)) => {
+                Some(source.cached_index_file(pkg_ref, project))
+            }
+            (PackageSources::Git(source), PackageRefs::Git(pkg_ref)) => {
+                Some(source.cached_index_file(pkg_ref, project))
+            }
+            _ => None,
+        }
+    }
+
+    /// The path to this source's local copy of its repository, if it has one
+    pub fn local_path(&self, project: &Project) -> Option<PathBuf> {
+        match self {
+            PackageSources::Pesde(source) => Some(git_index::GitBasedSource::path(source, project)),
+            #[cfg(feature = "wally-compat")]
+            PackageSources::Wally(source) => Some(git_index::GitBasedSource::path(source, project)),
+            PackageSources::Git(source) => Some(git_index::GitBasedSource::path(source, project)),
+            PackageSources::Workspace(_) | PackageSources::Path(_) => None,
+        }
+    }
+
+    /// The commit this source's index is at, if it is an index
+    pub fn index_commit(&self, project: &Project) -> Option<String> {
+        fn commit_of<S: git_index::GitBasedSource>(
+            source: &S,
+            project: &Project,
+        ) -> Option<String> {
+            let repo = git_index::open_repo(&source.path(project)).ok()?;
+            source.commit_id(&repo).ok()
+        }
+
+        match self {
+            PackageSources::Pesde(source) => commit_of(source, project),
+            #[cfg(feature = "wally-compat")]
+            PackageSources::Wally(source) => commit_of(source, project),
+            PackageSources::Git(_) | PackageSources::Workspace(_) | PackageSources::Path(_) => None,
+        }
+    }
+}
+
+impl Display for PackageSources {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackageSources::Pesde(source) => write!(
+                f,
+                "pesde index {}",
+                git_index::GitBasedSource::repo_url(source)
+            ),
+            #[cfg(feature = "wally-compat")]
+            PackageSources::Wally(source) => write!(
+                f,
+                "wally index {}",
+                git_index::GitBasedSource::repo_url(source)
+            ),
+            PackageSources::Git(source) => write!(
+                f,
+                "git repository {}",
+                git_index::GitBasedSource::repo_url(source)
+            ),
+            PackageSources::Workspace(_) => write!(f, "workspace"),
+            PackageSources::Path(_) => write!(f, "local path"),
+        }
+    }
 }
 
 impl PackageSource for PackageSources {
@@ -69,6 +156,7 @@ impl PackageSource for PackageSources {
             PackageSources::Wally(source) => source.refresh(project).map_err(Into::into),
             PackageSources::Git(source) => source.refresh(project).map_err(Into::into),
             PackageSources::Workspace(source) => source.refresh(project).map_err(Into::into),
+            PackageSources::Path(source) => source.refresh(project).map_err(Into::into),
         }
     }
 
@@ -86,7 +174,9 @@ impl PackageSource for PackageSources {
                         name,
                         results
                             .into_iter()
-                            .map(|(version, pkg_ref)| (version, PackageRefs::Pesde(pkg_ref)))
+                            .map(|(version, pkg_ref)| {
+                                (version, PackageRefs::Pesde(Box::new(pkg_ref)))
+                            })
                             .collect(),
                     )
                 })
@@ -136,6 +226,19 @@ impl PackageSource for PackageSources {
                     .map_err(Into::into)
             }
 
+            (PackageSources::Path(source), DependencySpecifiers::Path(specifier)) => source
+                .resolve(specifier, project, package_target)
+                .map(|(name, results)| {
+                    (
+                        name,
+                        results
+                            .into_iter()
+                            .map(|(version, pkg_ref)| (version, PackageRefs::Path(pkg_ref)))
+                            .collect(),
+                    )
+                })
+                .map_err(Into::into),
+
             _ => Err(errors::ResolveError::Mismatch),
         }
     }
@@ -164,6 +267,10 @@ impl PackageSource for PackageSources {
                 .download(pkg_ref, project, reqwest)
                 .map_err(Into::into),
 
+            (PackageSources::Path(source), PackageRefs::Path(pkg_ref)) => source
+                .download(pkg_ref, project, reqwest)
+                .map_err(Into::into),
+
             _ => Err(errors::DownloadError::Mismatch),
         }
     }
@@ -184,6 +291,10 @@ pub mod errors {
         /// A workspace package source failed to refresh
         #[error("error refreshing workspace package source")]
         Workspace(#[from] crate::source::workspace::errors::RefreshError),
+
+        /// A path package source failed to refresh
+        #[error("error refreshing path package source")]
+        Path(#[from] crate::source::path::errors::RefreshError),
     }
 
     /// Errors that can occur when resolving a package
@@ -210,6 +321,10 @@ pub mod errors {
         /// A workspace package source failed to resolve
         #[error("error resolving workspace package")]
         Workspace(#[from] crate::source::workspace::errors::ResolveError),
+
+        /// A path package source failed to resolve
+        #[error("error resolving path package")]
+        Path(#[from] crate::source::path::errors::ResolveError),
     }
 
     /// Errors that can occur when downloading a package
@@ -236,5 +351,64 @@ pub mod errors {
         /// A workspace package source failed to download
         #[error("error downloading workspace package")]
         Workspace(#[from] crate::source::workspace::errors::DownloadError),
+
+        /// A path package source failed to download
+        #[error("error downloading path package")]
+        Path(#[from] crate::source::path::errors::DownloadError),
+    }
+
+    impl ResolveError {
+        /// Whether the package isn't in its index at all
+        pub fn is_not_found(&self) -> bool {
+            match self {
+                ResolveError::Pesde(crate::source::pesde::errors::ResolveError::NotFound(_)) => {
+                    true
+                }
+                #[cfg(feature = "wally-compat")]
+                ResolveError::Wally(crate::source::wally::errors::ResolveError::NotFound(_)) => {
+                    true
+                }
+                _ => false,
+            }
+        }
+    }
+
+    impl DownloadError {
+        /// The package which could not be downloaded because it requires network access in offline mode, if that is the cause
+        pub fn offline_package(&self) -> Option<&str> {
+            match self {
+                DownloadError::Pesde(crate::source::pesde::errors::DownloadError::Offline(
+                    package,
+                )) => Some(package),
+                #[cfg(feature = "wally-compat")]
+                DownloadError::Wally(crate::source::wally::errors::DownloadError::Offline(
+                    package,
+                )) => Some(package),
+                _ => None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_missing_packages_are_not_found() {
+        assert!(
+            errors::ResolveError::from(pesde::errors::ResolveError::NotFound(
+                "acme/package".to_string()
+            ))
+            .is_not_found()
+        );
+        assert!(
+            !errors::ResolveError::from(pesde::errors::ResolveError::CaseMismatch(
+                "acme/package".to_string(),
+                "Acme/Package".to_string()
+            ))
+            .is_not_found()
+        );
+        assert!(!errors::ResolveError::Mismatch.is_not_found());
     }
 }
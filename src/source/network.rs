@@ -0,0 +1,228 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    num::NonZeroUsize,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// Counters of the network usage of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NetworkMetrics {
+    /// The number of bytes of packages downloaded
+    pub downloaded: u64,
+    /// The number of packages downloaded
+    pub downloads: u64,
+    /// The number of Git repositories fetched or cloned
+    pub git_fetches: u64,
+    /// How long downloads were slowed down for in total to stay within the download rate
+    pub throttled: Duration,
+    /// How long downloads and fetches waited in total for a connection to their host
+    pub connection_wait: Duration,
+}
+
+#[derive(Default)]
+struct Inner {
+    max_download_rate: Option<u64>,
+    max_host_connections: Option<NonZeroUsize>,
+    /// When the bytes downloaded so far are allowed to have been received by, at the download rate
+    next_free: Mutex<Option<Instant>>,
+    connections: Mutex<HashMap<String, usize>>,
+    connection_released: Condvar,
+
+    downloaded: AtomicU64,
+    downloads: AtomicU64,
+    git_fetches: AtomicU64,
+    throttled_nanos: AtomicU64,
+    connection_wait_nanos: AtomicU64,
+}
+
+/// Limits the bandwidth and connections used by package downloads and Git fetches, counting
+/// their usage. Clones share their limits and counters.
+/// The download rate only applies to package archives, as Git fetches can't be slowed down
+#[derive(Clone, Default)]
+pub struct NetworkLimiter(Arc<Inner>);
+
+impl NetworkLimiter {
+    /// Create a new `NetworkLimiter`, with the download rate in bytes per second and the number
+    /// of connections allowed to a host at once
+    pub fn new(max_download_rate: Option<u64>, max_host_connections: Option<NonZeroUsize>) -> Self {
+        NetworkLimiter(Arc::new(Inner {
+            max_download_rate: max_download_rate.filter(|rate| *rate > 0),
+            max_host_connections,
+            ..Default::default()
+        }))
+    }
+
+    /// Whether any limits are set
+    pub fn is_limited(&self) -> bool {
+        self.0.max_download_rate.is_some() || self.0.max_host_connections.is_some()
+    }
+
+    /// The network usage counted so far
+    pub fn metrics(&self) -> NetworkMetrics {
+        NetworkMetrics {
+            downloaded: self.0.downloaded.load(Ordering::Relaxed),
+            downloads: self.0.downloads.load(Ordering::Relaxed),
+            git_fetches: self.0.git_fetches.load(Ordering::Relaxed),
+            throttled: Duration::from_nanos(self.0.throttled_nanos.load(Ordering::Relaxed)),
+            connection_wait: Duration::from_nanos(
+                self.0.connection_wait_nanos.load(Ordering::Relaxed),
+            ),
+        }
+    }
+
+    /// Counts bytes received by a download, sleeping for as long as needed to stay within the
+    /// download rate
+    pub(crate) fn throttle(&self, bytes: u64) {
+        self.0.downloaded.fetch_add(bytes, Ordering::Relaxed);
+
+        let Some(rate) = self.0.max_download_rate else {
+            return;
+        };
+
+        let until = {
+            let mut next_free = self.0.next_free.lock().unwrap();
+            let now = Instant::now();
+            let start = next_free.filter(|next| *next > now).unwrap_or(now);
+            let until = start + Duration::from_secs_f64(bytes as f64 / rate as f64);
+            *next_free = Some(until);
+
+            until
+        };
+
+        let wait = until.saturating_duration_since(Instant::now());
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+            self.0
+                .throttled_nanos
+                .fetch_add(wait.as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+
+    /// Waits until a connection to the host is allowed, holding it until the permit is dropped
+    pub(crate) fn connect(&self, host: Option<&str>) -> HostPermit {
+        let (Some(max), Some(host)) = (self.0.max_host_connections, host) else {
+            return HostPermit(None);
+        };
+
+        let start = Instant::now();
+        let mut connections = self.0.connections.lock().unwrap();
+        while connections
+            .get(host)
+            .is_some_and(|count| *count >= max.get())
+        {
+            connections = self.0.connection_released.wait(connections).unwrap();
+        }
+        *connections.entry(host.to_string()).or_default() += 1;
+
+        self.0
+            .connection_wait_nanos
+            .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+
+        HostPermit(Some((self.clone(), host.to_string())))
+    }
+
+    /// Waits until a connection to the host of the URL is allowed, see [NetworkLimiter::connect]
+    pub(crate) fn connect_url(&self, url: &str) -> HostPermit {
+        self.connect(
+            url::Url::parse(url)
+                .ok()
+                .as_ref()
+                .and_then(|url| url.host_str()),
+        )
+    }
+
+    /// Counts a package download
+    pub(crate) fn record_download(&self) {
+        self.0.downloads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a Git fetch or clone
+    pub(crate) fn record_git_fetch(&self) {
+        self.0.git_fetches.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Debug for NetworkLimiter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NetworkLimiter")
+            .field("max_download_rate", &self.0.max_download_rate)
+            .field("max_host_connections", &self.0.max_host_connections)
+            .finish()
+    }
+}
+
+/// A connection to a host, allowing another one once dropped
+pub(crate) struct HostPermit(Option<(NetworkLimiter, String)>);
+
+impl Drop for HostPermit {
+    fn drop(&mut self) {
+        let Some((limiter, host)) = self.0.take() else {
+            return;
+        };
+
+        let mut connections = limiter.0.connections.lock().unwrap();
+        if let Some(count) = connections.get_mut(&host) {
+            *count -= 1;
+        }
+        limiter.0.connection_released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_connections_per_host() {
+        let limiter = NetworkLimiter::new(None, NonZeroUsize::new(1));
+
+        let permit = limiter.connect(Some("a.example.com"));
+        // other hosts aren't affected by the connection to this one
+        drop(limiter.connect(Some("b.example.com")));
+
+        let waiting = {
+            let limiter = limiter.clone();
+            std::thread::spawn(move || drop(limiter.connect(Some("a.example.com"))))
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!waiting.is_finished());
+
+        drop(permit);
+        waiting.join().unwrap();
+        assert!(limiter.metrics().connection_wait >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn connect_url_uses_host() {
+        let limiter = NetworkLimiter::new(None, NonZeroUsize::new(1));
+
+        let permit = limiter.connect_url("https://a.example.com/package");
+        assert_eq!(
+            limiter.0.connections.lock().unwrap().get("a.example.com"),
+            Some(&1)
+        );
+
+        drop(permit);
+        assert_eq!(
+            limiter.0.connections.lock().unwrap().get("a.example.com"),
+            Some(&0)
+        );
+    }
+
+    #[test]
+    fn unlimited_limiter_never_waits() {
+        let limiter = NetworkLimiter::default();
+        assert!(!limiter.is_limited());
+
+        let _permits = (0..16)
+            .map(|_| limiter.connect(Some("a.example.com")))
+            .collect::<Vec<_>>();
+        assert_eq!(limiter.metrics().connection_wait, Duration::ZERO);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::{
+    manifest::{
+        migration::parse_manifest,
+        target::{Target, TargetKind},
+    },
+    names::PackageNames,
+    source::{
+        fs::PackageFS, path::pkg_ref::PathPackageRef, specifiers::DependencySpecifiers,
+        traits::PackageSource, version_id::VersionId, ResolveResult,
+    },
+    util::hash,
+    Project, DEFAULT_INDEX_NAME, MANIFEST_FILE_NAME,
+};
+use reqwest::blocking::Client;
+use std::{collections::BTreeMap, path::Path};
+
+/// The path package reference
+pub mod pkg_ref;
+/// The path dependency specifier
+pub mod specifier;
+
+/// The hash of the manifest of the package at the given path, relative to the project
+pub(crate) fn manifest_hash(project: &Project, path: &Path) -> std::io::Result<String> {
+    std::fs::read(project.package_dir().join(path).join(MANIFEST_FILE_NAME)).map(hash)
+}
+
+/// The path package source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PathPackageSource;
+
+impl PackageSource for PathPackageSource {
+    type Specifier = specifier::PathDependencySpecifier;
+    type Ref = PathPackageRef;
+    type RefreshError = errors::RefreshError;
+    type ResolveError = errors::ResolveError;
+    type DownloadError = errors::DownloadError;
+
+    fn refresh(&self, _project: &Project) -> Result<(), Self::RefreshError> {
+        // no-op
+        Ok(())
+    }
+
+    fn resolve(
+        &self,
+        specifier: &Self::Specifier,
+        project: &Project,
+        _package_target: TargetKind,
+    ) -> Result<ResolveResult<Self::Ref>, Self::ResolveError> {
+        let path = project.package_dir().join(&specifier.path);
+
+        let manifest = std::fs::read_to_string(path.join(MANIFEST_FILE_NAME))
+            .map_err(|e| errors::ResolveError::ReadManifest(path.clone(), e))?;
+        let manifest_hash = hash(&manifest);
+        let manifest = parse_manifest(&manifest)
+            .map_err(|e| errors::ResolveError::DeserManifest(path.clone(), e))?;
+
+        Ok((
+            PackageNames::Pesde(manifest.name.clone()),
+            BTreeMap::from([(
+                VersionId::new(manifest.version.clone(), manifest.target.kind()),
+                PathPackageRef {
+                    path: specifier.path.clone(),
+                    manifest_hash,
+                    dependencies: manifest
+                        .all_dependencies()?
+                        .into_iter()
+                        .map(|(alias, (mut spec, ty))| {
+                            match &mut spec {
+                                DependencySpecifiers::Pesde(spec) => {
+                                    let index_name =
+                                        spec.index.as_deref().unwrap_or(DEFAULT_INDEX_NAME);
+
+                                    spec.index = Some(
+                                        manifest
+                                            .indices
+                                            .get(index_name)
+                                            .ok_or(errors::ResolveError::IndexNotFound(
+                                                index_name.to_string(),
+                                                manifest.name.to_string(),
+                                            ))?
+                                            .to_string(),
+                                    )
+                                }
+                                #[cfg(feature = "wally-compat")]
+                                DependencySpecifiers::Wally(spec) => {
+                                    let index_name =
+                                        spec.index.as_deref().unwrap_or(DEFAULT_INDEX_NAME);
+
+                                    spec.index = Some(
+                                        manifest
+                                            .wally_indices
+                                            .get(index_name)
+                                            .ok_or(errors::ResolveError::IndexNotFound(
+                                                index_name.to_string(),
+                                                manifest.name.to_string(),
+                                            ))?
+                                            .to_string(),
+                                    )
+                                }
+                                DependencySpecifiers::Git(_) => {}
+                                DependencySpecifiers::Workspace(_) => {
+                                    return Err(errors::ResolveError::WorkspaceDependency(
+                                        manifest.name.to_string(),
+                                        alias,
+                                    ));
+                                }
+                                // paths in the manifest are relative to the package, not the project
+                                DependencySpecifiers::Path(spec) => {
+                                    spec.path = specifier.path.join(&spec.path);
+                                }
+                            }
+
+                            Ok((alias, (spec, ty)))
+                        })
+                        .collect::<Result<_, errors::ResolveError>>()?,
+                    target: manifest.target,
+                },
+            )]),
+        ))
+    }
+
+    fn download(
+        &self,
+        pkg_ref: &Self::Ref,
+        project: &Project,
+        _reqwest: &Client,
+    ) -> Result<(PackageFS, Target), Self::DownloadError> {
+        let path = project.package_dir().join(&pkg_ref.path);
+
+        if !path.is_dir() {
+            return Err(errors::DownloadError::NotFound(path));
+        }
+
+        Ok((
+            PackageFS::Copy(path, pkg_ref.target.kind()),
+            pkg_ref.target.clone(),
+        ))
+    }
+}
+
+/// Errors that can occur when using a path package source
+pub mod errors {
+    use std::path::PathBuf;
+    use thiserror::Error;
+
+    /// Errors that can occur when refreshing the path package source
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum RefreshError {}
+
+    /// Errors that can occur when resolving a path package
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum ResolveError {
+        /// An error occurred reading the manifest of the package
+        #[error("failed to read manifest of package at {0}")]
+        ReadManifest(PathBuf, #[source] std::io::Error),
+
+        /// An error occurred deserializing the manifest of the package
+        #[error("failed to deserialize manifest of package at {0}")]
+        DeserManifest(PathBuf, #[source] toml::de::Error),
+
+        /// An error occurred getting all dependencies
+        #[error("failed to get all dependencies")]
+        AllDependencies(#[from] crate::manifest::errors::AllDependenciesError),
+
+        /// An index of the package was not found
+        #[error("index {0} not found in package {1}")]
+        IndexNotFound(String, String),
+
+        /// The package has a workspace dependency, which can't be resolved outside its workspace
+        #[error(
+            "package {0} has workspace dependency {1}, which isn't supported for path dependencies"
+        )]
+        WorkspaceDependency(String, String),
+    }
+
+    /// Errors that can occur when downloading a path package
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum DownloadError {
+        /// The package's directory no longer exists
+        #[error("package directory {0} not found")]
+        NotFound(PathBuf),
+    }
+}
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{
+    manifest::{
+        target::{Target, TargetKind},
+        DependencyType,
+    },
+    source::{path::PathPackageSource, DependencySpecifiers, PackageRef, PackageSources},
+};
+
+/// A path package reference
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+pub struct PathPackageRef {
+    /// The path of the package, relative to the project if it was specified as a relative path
+    pub path: PathBuf,
+    /// The hash of the package's manifest when it was resolved, used to detect changes to it
+    pub manifest_hash: String,
+    /// The dependencies of the package
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dependencies: BTreeMap<String, (DependencySpecifiers, DependencyType)>,
+    /// The target of the package
+    pub target: Target,
+}
+impl PackageRef for PathPackageRef {
+    fn dependencies(&self) -> &BTreeMap<String, (DependencySpecifiers, DependencyType)> {
+        &self.dependencies
+    }
+
+    fn use_new_structure(&self) -> bool {
+        true
+    }
+
+    fn target_kind(&self) -> TargetKind {
+        self.target.kind()
+    }
+
+    fn source(&self) -> PackageSources {
+        PackageSources::Path(PathPackageSource)
+    }
+}
@@ -0,0 +1,20 @@
+use crate::source::DependencySpecifier;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Display, path::PathBuf};
+
+/// The specifier for a path dependency
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct PathDependencySpecifier {
+    /// The path to the package, relative to the directory of the manifest it is specified in
+    pub path: PathBuf,
+    /// Whether the dependency may be missing, in which case a stub is linked in its place
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+impl DependencySpecifier for PathDependencySpecifier {}
+
+impl Display for PathDependencySpecifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "path:{}", self.path.display())
+    }
+}
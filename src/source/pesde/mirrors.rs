@@ -0,0 +1,402 @@
+use crate::source::{
+    network::{HostPermit, NetworkLimiter},
+    retry::{is_transient, ResumingReader, RetryPolicy},
+};
+use reqwest::{
+    blocking::RequestBuilder,
+    header::{ACCEPT_RANGES, CONTENT_LENGTH, RANGE},
+    Method, StatusCode,
+};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Archives at least this large are fetched in segments from multiple mirrors, if enabled
+const SEGMENTED_THRESHOLD: u64 = 1024 * 1024;
+/// The maximum number of mirrors a single archive is fetched from at once
+const MAX_SEGMENTS: usize = 4;
+
+/// The latencies of download mirrors, measured the first time each is used. `None` if the
+/// mirror was unreachable
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MirrorLatencies(Arc<Mutex<HashMap<String, Option<Duration>>>>);
+
+/// A URL to download a package from
+#[derive(Debug)]
+pub(crate) struct Mirror {
+    url: String,
+    /// The origin of the URL, which latencies are measured per
+    label: String,
+    latency: Option<Duration>,
+}
+
+impl Mirror {
+    pub(crate) fn new(url: String) -> Self {
+        let label = url::Url::parse(&url)
+            .map(|url| url.origin().ascii_serialization())
+            .unwrap_or_else(|_| url.clone());
+
+        Mirror {
+            url,
+            label,
+            latency: None,
+        }
+    }
+}
+
+/// The body of a package archive fetched from one or more mirrors
+pub(crate) struct Fetched<'a> {
+    pub(crate) body: Box<dyn Read + Send + 'a>,
+    pub(crate) total: Option<u64>,
+    /// The mirrors the archive was fetched from
+    pub(crate) mirror: String,
+    /// The connection to the mirror the body is read from, if it is still being received
+    pub(crate) _permit: Option<HostPermit>,
+}
+
+impl MirrorLatencies {
+    /// Sorts the URLs by the latency of their mirrors, measuring it for mirrors not used before
+    pub(crate) fn rank<F: Fn(Method, &str) -> RequestBuilder + Sync>(
+        &self,
+        urls: Vec<String>,
+        limiter: &NetworkLimiter,
+        request: &F,
+    ) -> Vec<Mirror> {
+        let mut mirrors = urls.into_iter().map(Mirror::new).collect::<Vec<_>>();
+
+        // held while measuring, so concurrent downloads wait instead of measuring again
+        let mut latencies = self.0.lock().unwrap();
+
+        let unmeasured = mirrors
+            .iter()
+            .filter(|mirror| !latencies.contains_key(&mirror.label))
+            .map(|mirror| (mirror.label.as_str(), mirror.url.as_str()))
+            .collect::<HashMap<_, _>>();
+
+        let measured = std::thread::scope(|s| {
+            unmeasured
+                .into_iter()
+                .map(|(label, url)| {
+                    s.spawn(move || {
+                        let _permit = limiter.connect_url(url);
+                        let start = Instant::now();
+                        // only the time until a response matters, not its status
+                        let latency = request(Method::HEAD, url)
+                            .send()
+                            .ok()
+                            .map(|_| start.elapsed());
+
+                        (label.to_string(), latency)
+                    })
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect::<Vec<_>>()
+        });
+
+        for (label, latency) in measured {
+            match latency {
+                Some(latency) => log::debug!("mirror {label} has a latency of {latency:?}"),
+                None => log::debug!("mirror {label} is unreachable"),
+            }
+
+            latencies.insert(label, latency);
+        }
+
+        for mirror in &mut mirrors {
+            mirror.latency = latencies.get(&mirror.label).copied().flatten();
+        }
+
+        // unreachable mirrors are still tried last, in case they were only briefly down
+        mirrors.sort_by_key(|mirror| mirror.latency.unwrap_or(Duration::MAX));
+
+        mirrors
+    }
+}
+
+fn fetch_segment<F: Fn(Method, &str) -> RequestBuilder>(
+    mirror: &Mirror,
+    start: u64,
+    end: u64,
+    policy: RetryPolicy,
+    limiter: &NetworkLimiter,
+    request: &F,
+) -> std::io::Result<File> {
+    let _permit = limiter.connect_url(&mirror.url);
+    let mut response = policy
+        .send(|| request(Method::GET, &mirror.url).header(RANGE, format!("bytes={start}-{end}")))
+        .map_err(std::io::Error::other)?;
+
+    if response.status() != StatusCode::PARTIAL_CONTENT {
+        return Err(std::io::Error::other(format!(
+            "mirror {} doesn't support ranged requests",
+            mirror.label
+        )));
+    }
+
+    let mut file = tempfile::tempfile()?;
+    let written = response.copy_to(&mut file).map_err(std::io::Error::other)?;
+
+    if written != end - start + 1 {
+        return Err(std::io::Error::other(format!(
+            "mirror {} sent {written} bytes for a segment of {} bytes",
+            mirror.label,
+            end - start + 1
+        )));
+    }
+
+    file.seek(SeekFrom::Start(0))?;
+
+    Ok(file)
+}
+
+fn fetch_segmented<F: Fn(Method, &str) -> RequestBuilder + Sync>(
+    mirrors: &[&Mirror],
+    total: u64,
+    policy: RetryPolicy,
+    limiter: &NetworkLimiter,
+    request: &F,
+) -> std::io::Result<Box<dyn Read + Send>> {
+    let segment_size = total.div_ceil(mirrors.len() as u64);
+
+    let segments = std::thread::scope(|s| {
+        mirrors
+            .iter()
+            .enumerate()
+            .map(|(i, mirror)| {
+                let start = i as u64 * segment_size;
+                let end = (start + segment_size).min(total) - 1;
+
+                s.spawn(move || fetch_segment(mirror, start, end, policy, limiter, request))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect::<std::io::Result<Vec<_>>>()
+    })?;
+
+    Ok(segments
+        .into_iter()
+        .fold(Box::new(std::io::empty()), |body, segment| {
+            Box::new(body.chain(segment))
+        }))
+}
+
+/// Fetches an archive from the first mirror which responds successfully, trying all of them
+/// again after a backoff if none does. If `segmented` is set, large archives are fetched in
+/// segments from multiple mirrors in parallel
+pub(crate) fn fetch<'a, F: Fn(Method, &str) -> RequestBuilder + Sync>(
+    mirrors: &'a [Mirror],
+    segmented: bool,
+    policy: RetryPolicy,
+    limiter: &NetworkLimiter,
+    request: &'a F,
+) -> Result<Fetched<'a>, reqwest::Error> {
+    let mut last_error = None;
+
+    for attempt in 0..policy.attempts.max(1) {
+        if attempt > 0 {
+            let delay = policy.delay(attempt - 1);
+            log::warn!("all mirrors failed, retrying in {delay:?}");
+            std::thread::sleep(delay);
+        }
+
+        match fetch_once(mirrors, segmented, policy, limiter, request) {
+            Ok(fetched) => return Ok(fetched),
+            Err(e) if is_transient(&e) => last_error = Some(e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(last_error.expect("at least one attempt is always made"))
+}
+
+fn fetch_once<'a, F: Fn(Method, &str) -> RequestBuilder + Sync>(
+    mirrors: &'a [Mirror],
+    segmented: bool,
+    policy: RetryPolicy,
+    limiter: &NetworkLimiter,
+    request: &'a F,
+) -> Result<Fetched<'a>, reqwest::Error> {
+    let mut last_error = None;
+
+    for (i, mirror) in mirrors.iter().enumerate() {
+        let reachable = mirrors[i..]
+            .iter()
+            .filter(|mirror| mirror.latency.is_some())
+            .take(MAX_SEGMENTS)
+            .collect::<Vec<_>>();
+
+        if segmented && reachable.len() > 1 {
+            // the size and range support are known from a HEAD request, so no body is wasted
+            let head = {
+                let _permit = limiter.connect_url(&mirror.url);
+                request(Method::HEAD, &mirror.url)
+                    .send()
+                    .and_then(|response| response.error_for_status())
+            };
+
+            match head {
+                Ok(response) => {
+                    let headers = response.headers();
+                    let total = headers
+                        .get(CONTENT_LENGTH)
+                        .and_then(|value| value.to_str().ok())
+                        .and_then(|value| value.parse::<u64>().ok());
+                    let accepts_ranges = headers
+                        .get(ACCEPT_RANGES)
+                        .is_some_and(|value| value == "bytes");
+
+                    if let Some(total) =
+                        total.filter(|total| accepts_ranges && *total >= SEGMENTED_THRESHOLD)
+                    {
+                        match fetch_segmented(&reachable, total, policy, limiter, request) {
+                            Ok(body) => {
+                                return Ok(Fetched {
+                                    body,
+                                    total: Some(total),
+                                    mirror: reachable
+                                        .iter()
+                                        .map(|mirror| mirror.label.as_str())
+                                        .collect::<Vec<_>>()
+                                        .join(", "),
+                                    _permit: None,
+                                })
+                            }
+                            Err(e) => log::debug!(
+                                "failed to fetch archive in segments, falling back: {e}"
+                            ),
+                        }
+                    }
+                }
+                Err(e) => {
+                    log::warn!("failed to download from mirror {}: {e}", mirror.label);
+                    last_error = Some(e);
+                    continue;
+                }
+            }
+        }
+
+        let permit = limiter.connect_url(&mirror.url);
+        let response = match request(Method::GET, &mirror.url)
+            .send()
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(response) => response,
+            Err(e) => {
+                log::warn!("failed to download from mirror {}: {e}", mirror.label);
+                last_error = Some(e);
+                continue;
+            }
+        };
+
+        let total = response.content_length();
+
+        return Ok(Fetched {
+            body: Box::new(ResumingReader::new(
+                response,
+                move || request(Method::GET, &mirror.url),
+                policy,
+            )),
+            total,
+            mirror: mirror.label.clone(),
+            _permit: Some(permit),
+        });
+    }
+
+    Err(last_error.expect("at least one mirror is always given"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    /// Serves the response to every request on a local port, returning the URL to request
+    fn serve(response: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/archive", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else {
+                    continue;
+                };
+
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    line.clear();
+                }
+
+                let _ = stream.write_all(response.as_bytes());
+            }
+        });
+
+        url
+    }
+
+    fn unreachable() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}/archive", listener.local_addr().unwrap())
+    }
+
+    fn fetch_body(urls: Vec<String>) -> Result<(String, String), reqwest::Error> {
+        let client = reqwest::blocking::Client::new();
+        let request = |method: Method, url: &str| client.request(method, url);
+        let mirrors = urls.into_iter().map(Mirror::new).collect::<Vec<_>>();
+
+        let mut fetched = fetch(
+            &mirrors,
+            false,
+            RetryPolicy::NONE,
+            &NetworkLimiter::default(),
+            &request,
+        )?;
+        let mut body = String::new();
+        fetched.body.read_to_string(&mut body).unwrap();
+
+        Ok((body, fetched.mirror))
+    }
+
+    #[test]
+    fn falls_back_to_reachable_mirror() {
+        let mirror = serve("HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\narchive");
+        let label = Mirror::new(mirror.clone()).label;
+
+        let (body, used) = fetch_body(vec![unreachable(), mirror]).unwrap();
+        assert_eq!(body, "archive");
+        assert_eq!(used, label);
+    }
+
+    #[test]
+    fn falls_back_on_error_status() {
+        let failing = serve("HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n");
+        let mirror = serve("HTTP/1.1 200 OK\r\nContent-Length: 7\r\n\r\narchive");
+
+        let (body, _) = fetch_body(vec![failing, mirror]).unwrap();
+        assert_eq!(body, "archive");
+    }
+
+    #[test]
+    fn fails_when_no_mirror_responds() {
+        let missing = serve("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n");
+
+        let error = fetch_body(vec![unreachable(), missing]).unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+    }
+
+    #[test]
+    fn labels_mirrors_by_origin() {
+        let mirror = Mirror::new("https://cdn.example.com:8443/v0/packages/a%2Fb".to_string());
+        assert_eq!(mirror.label, "https://cdn.example.com:8443");
+    }
+}
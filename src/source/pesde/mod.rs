@@ -10,24 +10,30 @@ use relative_path::RelativePathBuf;
 use reqwest::header::{ACCEPT, AUTHORIZATION};
 use serde::{Deserialize, Serialize};
 
+use mirrors::Mirror;
 use pkg_ref::PesdePackageRef;
 use specifier::PesdeDependencySpecifier;
 
 use crate::{
+    events::Event,
     manifest::{
+        capabilities::Capability,
+        compatibility::current_pesde_version,
         target::{Target, TargetKind},
         DependencyType,
     },
     names::{PackageName, PackageNames},
     source::{
-        fs::{store_reader_in_cas, FSEntry, PackageFS},
-        git_index::GitBasedSource,
+        fs::{stage_reader_in_cas, FSEntry, PackageFS},
+        git_index::{open_repo, GitBasedSource},
+        progress::DownloadReader,
         DependencySpecifiers, PackageSource, ResolveResult, VersionId, IGNORED_DIRS, IGNORED_FILES,
     },
     util::hash,
     Project,
 };
 
+pub(crate) mod mirrors;
 /// The pesde package reference
 pub mod pkg_ref;
 /// The pesde dependency specifier
@@ -69,6 +75,20 @@ impl PesdePackageSource {
         self.repo_url.to_bstring().to_vec()
     }
 
+    /// The path to the file in which the file system of the given package is cached
+    pub(crate) fn cached_index_file(
+        &self,
+        pkg_ref: &PesdePackageRef,
+        project: &Project,
+    ) -> PathBuf {
+        project
+            .cas_dir
+            .join("index")
+            .join(pkg_ref.name.escaped())
+            .join(pkg_ref.version.to_string())
+            .join(pkg_ref.target.to_string())
+    }
+
     /// Reads the config file
     pub fn config(&self, project: &Project) -> Result<IndexConfig, errors::ConfigError> {
         let file = self
@@ -87,6 +107,25 @@ impl PesdePackageSource {
         toml::from_str(&string).map_err(Into::into)
     }
 
+    /// Reads the index file of the package, returning `None` if it isn't in the index
+    pub fn index_file(
+        &self,
+        name: &PackageName,
+        project: &Project,
+    ) -> Result<Option<IndexFile>, errors::IndexFileError> {
+        let (scope, name_part) = name.as_str();
+        let Some(string) = self
+            .read_file([scope, name_part], project, None)
+            .map_err(|e| errors::IndexFileError::Read(name.to_string(), Box::new(e)))?
+        else {
+            return Ok(None);
+        };
+
+        parse_index_file(&name.to_string(), &string, project.lenient_index())
+            .map(|(file, _)| Some(file))
+            .map_err(|e| errors::IndexFileError::Parse(name.to_string(), e))
+    }
+
     /// Reads all packages from the index
     pub fn all_packages(
         &self,
@@ -94,9 +133,9 @@ impl PesdePackageSource {
     ) -> Result<BTreeMap<PackageName, IndexFile>, errors::AllPackagesError> {
         let path = self.path(project);
 
-        let repo = match gix::open(&path) {
+        let repo = match open_repo(&path) {
             Ok(repo) => repo,
-            Err(e) => return Err(errors::AllPackagesError::Open(path, Box::new(e))),
+            Err(e) => return Err(errors::AllPackagesError::Open(path, e)),
         };
 
         let tree = match self.tree(&repo) {
@@ -149,8 +188,12 @@ impl PesdePackageSource {
                 let string = String::from_utf8(blob.data.clone())
                     .map_err(|e| errors::AllPackagesError::Utf8(package_name.to_string(), e))?;
 
-                let file: IndexFile = match toml::from_str(&string) {
-                    Ok(file) => file,
+                let file = match parse_index_file(
+                    &format!("{package_scope}/{package_name}"),
+                    &string,
+                    project.lenient_index(),
+                ) {
+                    Ok((file, _)) => file,
                     Err(e) => {
                         return Err(errors::AllPackagesError::Deserialize(
                             package_name,
@@ -199,7 +242,22 @@ impl PackageSource for PesdePackageSource {
         let (scope, name) = specifier.name.as_str();
         let string = match self.read_file([scope, name], project, None) {
             Ok(Some(s)) => s,
-            Ok(None) => return Err(Self::ResolveError::NotFound(specifier.name.to_string())),
+            Ok(None) => {
+                // names used to be case-sensitive, so the index may have a differently cased entry
+                return Err(
+                    match self
+                        .find_ignoring_case(&[scope, name], project)
+                        .ok()
+                        .flatten()
+                    {
+                        Some(found) => Self::ResolveError::CaseMismatch(
+                            specifier.name.to_string(),
+                            found.join("/"),
+                        ),
+                        None => Self::ResolveError::NotFound(specifier.name.to_string()),
+                    },
+                );
+            }
             Err(e) => {
                 return Err(Self::ResolveError::Read(
                     specifier.name.to_string(),
@@ -208,35 +266,68 @@ impl PackageSource for PesdePackageSource {
             }
         };
 
-        let entries: IndexFile = toml::from_str(&string)
-            .map_err(|e| Self::ResolveError::Parse(specifier.name.to_string(), e))?;
+        let (entries, skipped) = parse_index_file(
+            &specifier.name.to_string(),
+            &string,
+            project.lenient_index(),
+        )
+        .map_err(|e| Self::ResolveError::Parse(specifier.name.to_string(), e))?;
 
         log::debug!("{} has {} possible entries", specifier.name, entries.len());
 
-        Ok((
-            PackageNames::Pesde(specifier.name.clone()),
-            entries
-                .into_iter()
-                .filter(|(VersionId(version, target), _)| {
-                    specifier.version.matches(version)
-                        && specifier.target.unwrap_or(package_target) == *target
-                })
-                .map(|(id, entry)| {
-                    let version = id.version().clone();
-
-                    (
-                        id,
-                        PesdePackageRef {
-                            name: specifier.name.clone(),
-                            version,
-                            index_url: self.repo_url.clone(),
-                            dependencies: entry.dependencies,
-                            target: entry.target,
-                        },
-                    )
-                })
-                .collect(),
-        ))
+        let pesde_version = current_pesde_version();
+
+        let versions = entries
+            .into_iter()
+            .filter(|(VersionId(version, target), entry)| {
+                !entry.yanked
+                    && specifier.version.matches(version)
+                    && specifier.target.unwrap_or(package_target) == *target
+            })
+            .filter(|(version_id, entry)| {
+                let compatible = entry
+                    .pesde_version
+                    .as_ref()
+                    .is_none_or(|req| req.matches(&pesde_version));
+
+                if !compatible {
+                    log::warn!(
+                        "skipping {}@{version_id} as it's incompatible with pesde {pesde_version}",
+                        specifier.name
+                    );
+                }
+
+                compatible
+            })
+            .map(|(id, entry)| {
+                let version = id.version().clone();
+
+                (
+                    id,
+                    PesdePackageRef {
+                        name: specifier.name.clone(),
+                        version,
+                        index_url: self.repo_url.clone(),
+                        dependencies: entry.dependencies,
+                        target: entry.target,
+                        capabilities: entry.capabilities,
+                        tested_lune: entry.tested_lune,
+                        checksum: entry.checksum,
+                        signature: entry.signature,
+                    },
+                )
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        // the skipped versions could have been the matching ones
+        if versions.is_empty() && skipped > 0 {
+            return Err(Self::ResolveError::NoValidVersions(
+                specifier.name.to_string(),
+                skipped,
+            ));
+        }
+
+        Ok((PackageNames::Pesde(specifier.name.clone()), versions))
     }
 
     fn download(
@@ -246,12 +337,7 @@ impl PackageSource for PesdePackageSource {
         reqwest: &reqwest::blocking::Client,
     ) -> Result<(PackageFS, Target), Self::DownloadError> {
         let config = self.config(project).map_err(Box::new)?;
-        let index_file = project
-            .cas_dir
-            .join("index")
-            .join(pkg_ref.name.escaped())
-            .join(pkg_ref.version.to_string())
-            .join(pkg_ref.target.to_string());
+        let index_file = self.cached_index_file(pkg_ref, project);
 
         match std::fs::read_to_string(&index_file) {
             Ok(s) => {
@@ -267,51 +353,139 @@ impl PackageSource for PesdePackageSource {
             Err(e) => return Err(errors::DownloadError::ReadIndex(e)),
         }
 
-        let url = config
-            .download()
-            .replace("{PACKAGE}", &pkg_ref.name.to_string().replace("/", "%2F"))
-            .replace("{PACKAGE_VERSION}", &pkg_ref.version.to_string())
-            .replace("{PACKAGE_TARGET}", &pkg_ref.target.to_string());
+        let urls = config
+            .download_urls()
+            .into_iter()
+            .map(|url| {
+                url.replace("{PACKAGE}", &pkg_ref.name.to_string().replace("/", "%2F"))
+                    .replace("{PACKAGE_VERSION}", &pkg_ref.version.to_string())
+                    .replace("{PACKAGE_TARGET}", &pkg_ref.target.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        if project.offline() {
+            return Err(errors::DownloadError::Offline(format!(
+                "{}@{}",
+                pkg_ref.name, pkg_ref.version
+            )));
+        }
 
-        let mut request = reqwest.get(&url).header(ACCEPT, "application/octet-stream");
+        let reqwest = project
+            .auth_config
+            .index_client(&self.repo_url)
+            .unwrap_or(reqwest);
+        let token = project.auth_config.tokens().get(&self.repo_url);
 
-        if let Some(token) = project.auth_config.tokens().get(&self.repo_url) {
+        if token.is_some() {
             log::debug!("using token for {}", self.repo_url);
-            request = request.header(AUTHORIZATION, token);
         }
 
-        let response = request.send()?.error_for_status()?;
-        let bytes = response.bytes()?;
+        // mirrors may be run by third parties, so the token is only sent to the index's own hosts
+        let token_origins = std::iter::once(config.api.clone())
+            .chain(url::Url::parse(&urls[0]).ok())
+            .map(|url| url.origin())
+            .collect::<Vec<_>>();
+
+        let request = |method: reqwest::Method, url: &str| {
+            let request = reqwest
+                .request(method, url)
+                .header(ACCEPT, "application/octet-stream");
+
+            match token {
+                Some(token)
+                    if url::Url::parse(url)
+                        .is_ok_and(|url| token_origins.contains(&url.origin())) =>
+                {
+                    request.header(AUTHORIZATION, token)
+                }
+                _ => request,
+            }
+        };
+
+        let has_mirrors = urls.len() > 1;
+        let mirrors = if has_mirrors {
+            project
+                .mirror_latencies()
+                .rank(urls, project.network_limiter(), &request)
+        } else {
+            urls.into_iter().map(Mirror::new).collect()
+        };
 
-        let mut decoder = flate2::read::GzDecoder::new(bytes.as_ref());
-        let mut archive = tar::Archive::new(&mut decoder);
+        let fetched = mirrors::fetch(
+            &mirrors,
+            project.segmented_downloads(),
+            project.retry_policy(),
+            project.network_limiter(),
+            &request,
+        )?;
+
+        if has_mirrors {
+            log::debug!(
+                "downloading {}@{} {} from {}",
+                pkg_ref.name,
+                pkg_ref.version,
+                pkg_ref.target,
+                fetched.mirror
+            );
+        }
+
+        // the archive is extracted as it is received, so it never has to be fully in memory
+        let mut reader = DownloadReader::new(
+            fetched.body,
+            format!("{}@{} {}", pkg_ref.name, pkg_ref.version, pkg_ref.target),
+            has_mirrors.then_some(fetched.mirror),
+            fetched.total,
+            project.download_progress().cloned(),
+            project.network_limiter().clone(),
+        );
 
         let mut entries = BTreeMap::new();
+        let mut staged = vec![];
+        let skipped = |path| {
+            project.emit(|| Event::FileSkipped {
+                package: format!("{}@{} {}", pkg_ref.name, pkg_ref.version, pkg_ref.target),
+                path,
+            })
+        };
 
-        for entry in archive.entries()? {
-            let mut entry = entry?;
-            let path = RelativePathBuf::from_path(entry.path()?).unwrap();
+        {
+            let mut decoder = flate2::read::GzDecoder::new(&mut reader);
+            let mut archive = tar::Archive::new(&mut decoder);
+
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                let path = RelativePathBuf::from_path(entry.path()?).unwrap();
+
+                if entry.header().entry_type().is_dir() {
+                    if path
+                        .components()
+                        .next()
+                        .is_some_and(|ct| IGNORED_DIRS.contains(&ct.as_str()))
+                    {
+                        skipped(path);
+                        continue;
+                    }
+
+                    entries.insert(path, FSEntry::Directory);
 
-            if entry.header().entry_type().is_dir() {
-                if path
-                    .components()
-                    .next()
-                    .is_some_and(|ct| IGNORED_DIRS.contains(&ct.as_str()))
-                {
                     continue;
                 }
 
-                entries.insert(path, FSEntry::Directory);
+                if IGNORED_FILES.contains(&path.as_str()) {
+                    skipped(path);
+                    continue;
+                }
 
-                continue;
+                staged.push((path, stage_reader_in_cas(project.cas_dir(), &mut entry)?));
             }
+        }
 
-            if IGNORED_FILES.contains(&path.as_str()) {
-                continue;
-            }
+        // files are only moved into the CAS once the archive is verified, so a tampered archive
+        // leaves nothing behind
+        verify_archive(pkg_ref, &config, &reader.finish()?, project.trust_policy())?;
 
-            let hash = store_reader_in_cas(project.cas_dir(), &mut entry)?;
-            entries.insert(path, FSEntry::File(hash));
+        for (path, file) in staged {
+            entries.insert(path, FSEntry::File(file.commit(project.cas_dir())?));
         }
 
         let fs = PackageFS::CAS(entries);
@@ -331,14 +505,20 @@ fn default_archive_size() -> usize {
     4 * 1024 * 1024
 }
 
-/// The configuration for the pesde index
+/// The configuration for the pesde index. Unknown fields are ignored, so indices can adopt new
+/// fields without breaking clients which don't know them yet. Clients released before this
+/// refuse configs with unknown fields, so indices shouldn't set `mirrors` or `signing_key` until
+/// those clients are no longer supported
 #[derive(Deserialize, Debug, Clone)]
-#[serde(deny_unknown_fields)]
 pub struct IndexConfig {
     /// The URL of the API
     pub api: url::Url,
     /// The URL to download packages from
     pub download: Option<String>,
+    /// Additional URLs to download packages from, in the same format as `download`. The fastest
+    /// reachable one is preferred
+    #[serde(default)]
+    pub mirrors: Vec<String>,
     /// Whether Git is allowed as a source for publishing packages
     #[serde(default)]
     pub git_allowed: bool,
@@ -354,6 +534,13 @@ pub struct IndexConfig {
     /// The maximum size of an archive in bytes
     #[serde(default = "default_archive_size")]
     pub max_archive_size: usize,
+    /// The hex encoded Ed25519 public key the registry signs packages with. There is one key
+    /// per registry, publishers don't sign their packages
+    #[serde(default)]
+    pub signing_key: Option<String>,
+    /// The Git repository of the security advisory database for the index's packages
+    #[serde(default, deserialize_with = "crate::util::deserialize_gix_url_opt")]
+    pub advisories: Option<gix::Url>,
 }
 
 impl IndexConfig {
@@ -369,6 +556,17 @@ impl IndexConfig {
             .unwrap_or("{API_URL}/v0/packages/{PACKAGE}/{PACKAGE_VERSION}/{PACKAGE_TARGET}")
             .replace("{API_URL}", self.api())
     }
+
+    /// The URLs to download packages from, the main one followed by the mirrors
+    pub fn download_urls(&self) -> Vec<String> {
+        std::iter::once(self.download())
+            .chain(
+                self.mirrors
+                    .iter()
+                    .map(|mirror| mirror.replace("{API_URL}", self.api())),
+            )
+            .collect()
+    }
 }
 
 /// An entry in a package's documentation
@@ -445,19 +643,187 @@ pub struct IndexFileEntry {
     /// The repository of this package
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub repository: Option<url::Url>,
+    /// Whether the registry verified that the publisher can push to the repository
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub repository_verified: bool,
+    /// The keywords of this package
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub keywords: BTreeSet<String>,
+    /// The categories of this package
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub categories: BTreeSet<String>,
 
     /// The documentation for this package
     #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
     pub docs: BTreeSet<DocEntry>,
 
+    /// The runtime features this package requires
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub capabilities: BTreeSet<Capability>,
+
+    /// The SHA-256 checksum of this package's archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// The registry's signature of this package's checksum, see [signed_message]. This is made
+    /// with the single key of the registry, not by the package's publisher, so it vouches for
+    /// what the registry received rather than for who published it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+
+    /// The versions of pesde which can install this package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pesde_version: Option<semver::VersionReq>,
+    /// The versions of Lune this package has been tested with
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tested_lune: BTreeSet<semver::Version>,
+
+    /// Whether this version has been yanked, meaning it won't be resolved anymore unless it's already in a lockfile
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub yanked: bool,
+
     /// The dependencies of this package
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub dependencies: BTreeMap<String, (DependencySpecifiers, DependencyType)>,
 }
 
+/// How signatures and missing checksums of downloaded packages are treated. Packages whose
+/// archive doesn't match their checksum are refused under every policy
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrustPolicy {
+    /// Don't verify the signatures of downloaded packages
+    Ignore,
+    /// Warn about packages which fail verification
+    #[default]
+    Warn,
+    /// Refuse packages which fail or can't be verified
+    Enforce,
+}
+
+/// The message the registry signs when a package is published
+pub fn signed_message(name: &PackageName, version_id: &VersionId, checksum: &str) -> String {
+    format!("{name}@{} {checksum}", version_id.escaped())
+}
+
+fn verify_archive(
+    pkg_ref: &PesdePackageRef,
+    config: &IndexConfig,
+    archive_hash: &str,
+    policy: TrustPolicy,
+) -> Result<(), errors::DownloadError> {
+    let package = format!("{}@{} {}", pkg_ref.name, pkg_ref.version, pkg_ref.target);
+
+    // a mismatching checksum means the archive was corrupted or tampered with, which no policy
+    // accepts
+    if pkg_ref
+        .checksum
+        .as_ref()
+        .is_some_and(|checksum| archive_hash != checksum)
+    {
+        return Err(errors::DownloadError::ChecksumMismatch(package));
+    }
+
+    if policy == TrustPolicy::Ignore {
+        return Ok(());
+    }
+
+    let fail = |error: errors::DownloadError| {
+        if policy == TrustPolicy::Enforce {
+            return Err(error);
+        }
+
+        log::warn!("{error}");
+        Ok(())
+    };
+    // packages published before checksums were introduced only fail when enforcing
+    let unverified = |error: errors::DownloadError| {
+        if policy == TrustPolicy::Enforce {
+            return Err(error);
+        }
+
+        log::debug!("{error}");
+        Ok(())
+    };
+
+    let Some(checksum) = &pkg_ref.checksum else {
+        return unverified(errors::DownloadError::MissingChecksum(package));
+    };
+
+    let Some(signing_key) = &config.signing_key else {
+        return Ok(());
+    };
+
+    let Some(signature) = &pkg_ref.signature else {
+        return unverified(errors::DownloadError::MissingSignature(package));
+    };
+
+    let valid = match (hex::decode(signing_key), hex::decode(signature)) {
+        (Ok(signing_key), Ok(signature)) => {
+            let message = signed_message(
+                &pkg_ref.name,
+                &VersionId::new(pkg_ref.version.clone(), pkg_ref.target.kind()),
+                checksum,
+            );
+
+            ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, signing_key)
+                .verify(message.as_bytes(), &signature)
+                .is_ok()
+        }
+        _ => false,
+    };
+
+    if !valid {
+        return fail(errors::DownloadError::InvalidSignature(package));
+    }
+
+    Ok(())
+}
+
 /// The index file for a package
 pub type IndexFile = BTreeMap<VersionId, IndexFileEntry>;
 
+/// Parses the index file of a package. If `lenient` is set, versions which can't be parsed are
+/// skipped instead of failing the whole file, returning how many were skipped
+pub(crate) fn parse_index_file(
+    name: &str,
+    contents: &str,
+    lenient: bool,
+) -> Result<(IndexFile, usize), toml::de::Error> {
+    if !lenient {
+        return toml::from_str(contents).map(|file| (file, 0));
+    }
+
+    let mut file = IndexFile::new();
+    let mut skipped = 0;
+
+    for (version_id, entry) in toml::from_str::<toml::Table>(contents)? {
+        let parsed = version_id
+            .parse::<VersionId>()
+            .map_err(|e| e.to_string())
+            .and_then(|version_id| {
+                entry
+                    .try_into::<IndexFileEntry>()
+                    .map(|entry| (version_id, entry))
+                    .map_err(|e| e.to_string())
+            });
+
+        match parsed {
+            Ok((version_id, entry)) => {
+                file.insert(version_id, entry);
+            }
+            Err(e) => {
+                skipped += 1;
+                log::warn!(
+                    "skipping malformed version {version_id} of {name} in index: {}",
+                    e.trim().replace('\n', " ")
+                );
+            }
+        }
+    }
+
+    Ok((file, skipped))
+}
+
 /// Errors that can occur when interacting with the pesde package source
 pub mod errors {
     use std::path::PathBuf;
@@ -478,6 +844,10 @@ pub mod errors {
         #[error("package {0} not found")]
         NotFound(String),
 
+        /// Package not found in index, but one whose name only differs in case was
+        #[error("package {0} not found, did you mean {1}? package names are case-insensitive, so it should be republished in lowercase")]
+        CaseMismatch(String, String),
+
         /// Error reading file for package
         #[error("error reading file for {0}")]
         Read(String, #[source] Box<ReadFile>),
@@ -486,6 +856,10 @@ pub mod errors {
         #[error("error parsing file for {0}")]
         Parse(String, #[source] toml::de::Error),
 
+        /// Every version of the package which could have matched is malformed
+        #[error("no valid versions of {0} found, {1} malformed version(s) were skipped")]
+        NoValidVersions(String, usize),
+
         /// Error parsing file for package as utf8
         #[error("error parsing file for {0} to utf8")]
         Utf8(String, #[source] std::string::FromUtf8Error),
@@ -508,6 +882,19 @@ pub mod errors {
         Missing(Box<gix::Url>),
     }
 
+    /// Errors that can occur when reading the index file of a package
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum IndexFileError {
+        /// Error reading file for package
+        #[error("error reading file for {0}")]
+        Read(String, #[source] Box<ReadFile>),
+
+        /// Error parsing file for package
+        #[error("error parsing file for {0}")]
+        Parse(String, #[source] toml::de::Error),
+    }
+
     /// Errors that can occur when reading all packages from a pesde package source
     #[derive(Debug, Error)]
     #[non_exhaustive]
@@ -568,5 +955,140 @@ pub mod errors {
         /// Error writing index file
         #[error("error reading index file")]
         ReadIndex(#[source] std::io::Error),
+
+        /// The package is not in the CAS, and the project is offline
+        #[error("package {0} is not cached, and offline mode is enabled")]
+        Offline(String),
+
+        /// The package has no checksum to verify its archive with
+        #[error("package {0} has no checksum to verify its archive with")]
+        MissingChecksum(String),
+
+        /// The checksum of the package's archive doesn't match the expected one
+        #[error("checksum of package {0} doesn't match the one in the index")]
+        ChecksumMismatch(String),
+
+        /// The package has no signature, but its index signs packages
+        #[error("package {0} has no signature, but its index signs packages")]
+        MissingSignature(String),
+
+        /// The signature of the package is invalid
+        #[error("signature of package {0} is invalid")]
+        InvalidSignature(String),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::KeyPair;
+
+    fn pkg_ref(checksum: Option<&str>, signature: Option<String>) -> PesdePackageRef {
+        PesdePackageRef {
+            name: "acme/package".parse().unwrap(),
+            version: "1.0.0".parse().unwrap(),
+            index_url: "https://github.com/acme/index".try_into().unwrap(),
+            dependencies: Default::default(),
+            target: Target::Lune {
+                lib: None,
+                bin: None,
+                bins: Default::default(),
+            },
+            capabilities: Default::default(),
+            tested_lune: Default::default(),
+            checksum: checksum.map(str::to_string),
+            signature,
+        }
+    }
+
+    fn config(signing_key: Option<String>) -> IndexConfig {
+        let mut config = toml::from_str::<IndexConfig>("api = \"https://api.acme.dev\"").unwrap();
+        config.signing_key = signing_key;
+        config
+    }
+
+    fn key_pair() -> ring::signature::Ed25519KeyPair {
+        let pkcs8 =
+            ring::signature::Ed25519KeyPair::generate_pkcs8(&ring::rand::SystemRandom::new())
+                .unwrap();
+        ring::signature::Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+    }
+
+    fn sign(key_pair: &ring::signature::Ed25519KeyPair, pkg_ref: &PesdePackageRef) -> String {
+        let message = signed_message(
+            &pkg_ref.name,
+            &VersionId::new(pkg_ref.version.clone(), pkg_ref.target.kind()),
+            pkg_ref.checksum.as_deref().unwrap(),
+        );
+        hex::encode(key_pair.sign(message.as_bytes()))
+    }
+
+    #[test]
+    fn index_config_ignores_unknown_fields() {
+        let config = toml::from_str::<IndexConfig>(
+            "api = \"https://api.acme.dev/\"\nmirrors = [\"https://cdn.acme.dev/{PACKAGE}\"]\nfuture = true",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.download_urls(),
+            [
+                "https://api.acme.dev/v0/packages/{PACKAGE}/{PACKAGE_VERSION}/{PACKAGE_TARGET}",
+                "https://cdn.acme.dev/{PACKAGE}"
+            ]
+        );
+    }
+
+    #[test]
+    fn checksum_mismatch_is_refused_under_every_policy() {
+        let pkg_ref = pkg_ref(Some("expected"), None);
+
+        for policy in [TrustPolicy::Ignore, TrustPolicy::Warn, TrustPolicy::Enforce] {
+            assert!(matches!(
+                verify_archive(&pkg_ref, &config(None), "actual", policy),
+                Err(errors::DownloadError::ChecksumMismatch(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn missing_checksum_is_only_refused_when_enforcing() {
+        let pkg_ref = pkg_ref(None, None);
+
+        assert!(verify_archive(&pkg_ref, &config(None), "actual", TrustPolicy::Warn).is_ok());
+        assert!(matches!(
+            verify_archive(&pkg_ref, &config(None), "actual", TrustPolicy::Enforce),
+            Err(errors::DownloadError::MissingChecksum(_))
+        ));
+    }
+
+    #[test]
+    fn verifies_registry_signature() {
+        let registry = key_pair();
+        let signing_key = Some(hex::encode(registry.public_key().as_ref()));
+
+        let mut signed = pkg_ref(Some("checksum"), None);
+        signed.signature = Some(sign(&registry, &signed));
+        assert!(verify_archive(
+            &signed,
+            &config(signing_key.clone()),
+            "checksum",
+            TrustPolicy::Enforce
+        )
+        .is_ok());
+
+        let other = pkg_ref(Some("checksum"), Some(sign(&key_pair(), &signed)));
+        assert!(matches!(
+            verify_archive(
+                &other,
+                &config(signing_key.clone()),
+                "checksum",
+                TrustPolicy::Enforce
+            ),
+            Err(errors::DownloadError::InvalidSignature(_))
+        ));
+        assert!(
+            verify_archive(&other, &config(signing_key), "checksum", TrustPolicy::Warn).is_ok()
+        );
     }
 }
@@ -1,10 +1,11 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     manifest::{
+        capabilities::Capability,
         target::{Target, TargetKind},
         DependencyType,
     },
@@ -30,6 +31,18 @@ pub struct PesdePackageRef {
     pub dependencies: BTreeMap<String, (DependencySpecifiers, DependencyType)>,
     /// The target of the package
     pub target: Target,
+    /// The runtime features the package requires
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub capabilities: BTreeSet<Capability>,
+    /// The versions of Lune the package has been tested with
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tested_lune: BTreeSet<Version>,
+    /// The SHA-256 checksum of the package's archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+    /// The registry's signature of the package's checksum
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
 }
 impl PackageRef for PesdePackageRef {
     fn dependencies(&self) -> &BTreeMap<String, (DependencySpecifiers, DependencyType)> {
@@ -0,0 +1,107 @@
+use crate::source::network::NetworkLimiter;
+use sha2::{Digest, Sha256};
+use std::{
+    fmt::{Debug, Formatter},
+    io::Read,
+    sync::Arc,
+};
+
+/// An update on the download of a package
+#[derive(Debug, Clone, Copy)]
+pub struct DownloadProgressUpdate<'a> {
+    /// The package being downloaded
+    pub package: &'a str,
+    /// The mirrors the package is downloaded from, if its index lists any
+    pub mirror: Option<&'a str>,
+    /// The number of bytes received since the last update
+    pub received: u64,
+    /// The number of bytes of the package received so far
+    pub downloaded: u64,
+    /// The size of the package in bytes, if known
+    pub total: Option<u64>,
+}
+
+/// A callback reporting the progress of package downloads. It may be called from multiple threads
+#[derive(Clone)]
+pub struct DownloadProgress(Arc<dyn Fn(DownloadProgressUpdate) + Send + Sync>);
+
+impl DownloadProgress {
+    /// Create a new `DownloadProgress` from a callback
+    pub fn new<F: Fn(DownloadProgressUpdate) + Send + Sync + 'static>(callback: F) -> Self {
+        DownloadProgress(Arc::new(callback))
+    }
+
+    pub(crate) fn report(&self, update: DownloadProgressUpdate) {
+        (self.0)(update)
+    }
+}
+
+impl Debug for DownloadProgress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DownloadProgress")
+    }
+}
+
+/// Wraps the body of a download, hashing it, reporting progress and throttling it as it is read
+pub(crate) struct DownloadReader<R: Read> {
+    inner: R,
+    hasher: Sha256,
+    package: String,
+    mirror: Option<String>,
+    downloaded: u64,
+    total: Option<u64>,
+    progress: Option<DownloadProgress>,
+    limiter: NetworkLimiter,
+}
+
+impl<R: Read> DownloadReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        package: String,
+        mirror: Option<String>,
+        total: Option<u64>,
+        progress: Option<DownloadProgress>,
+        limiter: NetworkLimiter,
+    ) -> Self {
+        limiter.record_download();
+
+        DownloadReader {
+            inner,
+            hasher: Sha256::new(),
+            package,
+            mirror,
+            downloaded: 0,
+            total,
+            progress,
+            limiter,
+        }
+    }
+
+    /// Reads the rest of the body, returning the hash of all of it
+    pub(crate) fn finish(mut self) -> std::io::Result<String> {
+        std::io::copy(&mut self, &mut std::io::sink())?;
+
+        Ok(format!("{:x}", self.hasher.finalize()))
+    }
+}
+
+impl<R: Read> Read for DownloadReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        self.downloaded += read as u64;
+        self.limiter.throttle(read as u64);
+
+        if let Some(progress) = self.progress.as_ref().filter(|_| read > 0) {
+            progress.report(DownloadProgressUpdate {
+                package: &self.package,
+                mirror: self.mirror.as_deref(),
+                received: read as u64,
+                downloaded: self.downloaded,
+                total: self.total,
+            });
+        }
+
+        Ok(read)
+    }
+}
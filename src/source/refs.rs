@@ -10,7 +10,7 @@ use std::collections::BTreeMap;
 #[serde(rename_all = "snake_case", tag = "ref_ty")]
 pub enum PackageRefs {
     /// A pesde package reference
-    Pesde(pesde::pkg_ref::PesdePackageRef),
+    Pesde(Box<pesde::pkg_ref::PesdePackageRef>),
     /// A Wally package reference
     #[cfg(feature = "wally-compat")]
     Wally(crate::source::wally::pkg_ref::WallyPackageRef),
@@ -18,6 +18,8 @@ pub enum PackageRefs {
     Git(crate::source::git::pkg_ref::GitPackageRef),
     /// A workspace package reference
     Workspace(crate::source::workspace::pkg_ref::WorkspacePackageRef),
+    /// A path package reference
+    Path(crate::source::path::pkg_ref::PathPackageRef),
 }
 
 impl PackageRefs {
@@ -40,6 +42,7 @@ impl PackageRef for PackageRefs {
             PackageRefs::Wally(pkg_ref) => pkg_ref.dependencies(),
             PackageRefs::Git(pkg_ref) => pkg_ref.dependencies(),
             PackageRefs::Workspace(pkg_ref) => pkg_ref.dependencies(),
+            PackageRefs::Path(pkg_ref) => pkg_ref.dependencies(),
         }
     }
 
@@ -50,6 +53,7 @@ impl PackageRef for PackageRefs {
             PackageRefs::Wally(pkg_ref) => pkg_ref.use_new_structure(),
             PackageRefs::Git(pkg_ref) => pkg_ref.use_new_structure(),
             PackageRefs::Workspace(pkg_ref) => pkg_ref.use_new_structure(),
+            PackageRefs::Path(pkg_ref) => pkg_ref.use_new_structure(),
         }
     }
 
@@ -60,6 +64,7 @@ impl PackageRef for PackageRefs {
             PackageRefs::Wally(pkg_ref) => pkg_ref.target_kind(),
             PackageRefs::Git(pkg_ref) => pkg_ref.target_kind(),
             PackageRefs::Workspace(pkg_ref) => pkg_ref.target_kind(),
+            PackageRefs::Path(pkg_ref) => pkg_ref.target_kind(),
         }
     }
 
@@ -70,6 +75,7 @@ impl PackageRef for PackageRefs {
             PackageRefs::Wally(pkg_ref) => pkg_ref.source(),
             PackageRefs::Git(pkg_ref) => pkg_ref.source(),
             PackageRefs::Workspace(pkg_ref) => pkg_ref.source(),
+            PackageRefs::Path(pkg_ref) => pkg_ref.source(),
         }
     }
 }
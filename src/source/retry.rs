@@ -0,0 +1,308 @@
+use reqwest::{
+    blocking::{RequestBuilder, Response},
+    header::{HeaderValue, ACCEPT_RANGES, CONTENT_RANGE, ETAG, IF_RANGE, RANGE},
+    StatusCode,
+};
+use std::{io::Read, time::Duration};
+
+/// How failed requests to download packages are retried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts, including the first one
+    pub attempts: u32,
+    /// How long to wait before the first retry, doubled for every following one
+    pub backoff: Duration,
+    /// The longest to wait between two attempts
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 3,
+            backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+/// Whether the request might succeed if it is sent again
+pub(crate) fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_timeout()
+        || e.is_connect()
+        || e.status().is_some_and(|status| {
+            status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+        })
+}
+
+impl RetryPolicy {
+    /// A policy which never retries
+    pub const NONE: RetryPolicy = RetryPolicy {
+        attempts: 1,
+        backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+    };
+
+    pub(crate) fn delay(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff)
+    }
+
+    /// Sends the request, retrying it on timeouts, connection errors and server errors
+    pub(crate) fn send<F: Fn() -> RequestBuilder>(
+        &self,
+        request: F,
+    ) -> Result<Response, reqwest::Error> {
+        let mut retry = 0;
+
+        loop {
+            match request()
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                Err(e) if retry + 1 < self.attempts && is_transient(&e) => {
+                    let delay = self.delay(retry);
+                    log::warn!("request failed, retrying in {delay:?}: {e}");
+
+                    std::thread::sleep(delay);
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Reads the body of a response, resuming it with a ranged request if the connection breaks
+/// midway. Resuming requires the response to have an ETag, so the rest of a changed object is
+/// never appended to what was read of the old one. Interrupted downloads are only resumed
+/// within the same run
+pub(crate) struct ResumingReader<F> {
+    response: Response,
+    request: F,
+    policy: RetryPolicy,
+    etag: Option<HeaderValue>,
+    position: u64,
+    retries: u32,
+}
+
+impl<F: Fn() -> RequestBuilder> ResumingReader<F> {
+    pub(crate) fn new(response: Response, request: F, policy: RetryPolicy) -> Self {
+        let accepts_ranges = response
+            .headers()
+            .get(ACCEPT_RANGES)
+            .is_some_and(|value| value == "bytes");
+        // weak ETags can't be used to resume, as the bytes may differ
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .filter(|etag| accepts_ranges && !etag.as_bytes().starts_with(b"W/"))
+            .cloned();
+
+        ResumingReader {
+            response,
+            request,
+            policy,
+            etag,
+            position: 0,
+            retries: 0,
+        }
+    }
+}
+
+/// Whether the response continues the body of the object from `position`
+fn continues_at(response: &Response, position: u64) -> bool {
+    response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|range| range.to_str().ok())
+        .and_then(|range| range.strip_prefix("bytes "))
+        .and_then(|range| range.split_once('-'))
+        .and_then(|(start, _)| start.parse::<u64>().ok())
+        == Some(position)
+}
+
+impl<F: Fn() -> RequestBuilder> Read for ResumingReader<F> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut error = match self.response.read(buf) {
+            Ok(read) => {
+                self.position += read as u64;
+                return Ok(read);
+            }
+            Err(e) => e,
+        };
+
+        let Some(etag) = self.etag.clone() else {
+            return Err(error);
+        };
+
+        while self.retries + 1 < self.policy.attempts {
+            let delay = self.policy.delay(self.retries);
+            self.retries += 1;
+
+            log::warn!(
+                "download interrupted after {} bytes, resuming in {delay:?}: {error}",
+                self.position
+            );
+            std::thread::sleep(delay);
+
+            // if the object changed, the server sends all of the new one instead of the range
+            match (self.request)()
+                .header(RANGE, format!("bytes={}-", self.position))
+                .header(IF_RANGE, etag.clone())
+                .send()
+                .and_then(|response| response.error_for_status())
+            {
+                Ok(response)
+                    if response.status() == StatusCode::PARTIAL_CONTENT
+                        && continues_at(&response, self.position) =>
+                {
+                    self.response = response;
+                    return self.read(buf);
+                }
+                // the server sent the whole body again, so what was already read is skipped if
+                // it is still the same object
+                Ok(mut response)
+                    if response.status() == StatusCode::OK
+                        && response.headers().get(ETAG) == Some(&etag) =>
+                {
+                    match std::io::copy(
+                        &mut (&mut response).take(self.position),
+                        &mut std::io::sink(),
+                    ) {
+                        Ok(skipped) if skipped == self.position => {
+                            self.response = response;
+                            return self.read(buf);
+                        }
+                        Ok(_) => {
+                            error = std::io::Error::other(
+                                "the download ended before where it was interrupted",
+                            )
+                        }
+                        Err(e) => error = e,
+                    }
+                }
+                Ok(_) => {
+                    return Err(std::io::Error::other(
+                        "the download changed while resuming it",
+                    ))
+                }
+                Err(e) => error = std::io::Error::other(e),
+            }
+        }
+
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    /// Serves the responses to the requests in order, recording the headers of the requests
+    fn serve(responses: Vec<&'static str>) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/archive", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+
+        let recorded = requests.clone();
+        std::thread::spawn(move || {
+            for (stream, response) in listener.incoming().zip(responses) {
+                let mut stream = stream.unwrap();
+
+                let mut reader = BufReader::new(&stream);
+                let mut request = String::new();
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|read| read > 2) {
+                    request.push_str(&line.to_lowercase());
+                    line.clear();
+                }
+                recorded.lock().unwrap().push(request);
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (url, requests)
+    }
+
+    const POLICY: RetryPolicy = RetryPolicy {
+        attempts: 3,
+        backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+    };
+
+    /// The first response breaks off after 5 of its 10 bytes
+    const INTERRUPTED: &str = "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nAccept-Ranges: bytes\r\nETag: \"v1\"\r\n\r\n01234";
+
+    fn read(responses: Vec<&'static str>) -> (std::io::Result<String>, Vec<String>) {
+        let (url, requests) = serve(responses);
+        let client = reqwest::blocking::Client::new();
+
+        let response = client.get(&url).send().unwrap();
+        let mut reader = ResumingReader::new(response, || client.get(&url), POLICY);
+        let mut body = String::new();
+        let result = reader.read_to_string(&mut body).map(|_| body);
+
+        let requests = requests.lock().unwrap().clone();
+        (result, requests)
+    }
+
+    #[test]
+    fn resumes_with_if_range() {
+        let (body, requests) = read(vec![
+            INTERRUPTED,
+            "HTTP/1.1 206 Partial Content\r\nContent-Length: 5\r\nContent-Range: bytes 5-9/10\r\nETag: \"v1\"\r\n\r\n56789",
+        ]);
+
+        assert_eq!(body.unwrap(), "0123456789");
+        assert!(requests[1].contains("range: bytes=5-"));
+        assert!(requests[1].contains("if-range: \"v1\""));
+    }
+
+    #[test]
+    fn rejects_range_starting_elsewhere() {
+        let (body, _) = read(vec![
+            INTERRUPTED,
+            "HTTP/1.1 206 Partial Content\r\nContent-Length: 10\r\nContent-Range: bytes 0-9/10\r\nETag: \"v1\"\r\n\r\n0123456789",
+        ]);
+
+        assert!(body.is_err());
+    }
+
+    #[test]
+    fn restarts_unchanged_object() {
+        let (body, _) = read(vec![
+            INTERRUPTED,
+            "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: \"v1\"\r\n\r\n0123456789",
+        ]);
+
+        assert_eq!(body.unwrap(), "0123456789");
+    }
+
+    #[test]
+    fn refuses_changed_object() {
+        let (body, _) = read(vec![
+            INTERRUPTED,
+            "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nETag: \"v2\"\r\n\r\nabcdefghij",
+        ]);
+
+        assert!(body.is_err());
+    }
+
+    #[test]
+    fn doesnt_resume_without_etag() {
+        let (body, requests) = read(vec![
+            "HTTP/1.1 200 OK\r\nContent-Length: 10\r\nAccept-Ranges: bytes\r\n\r\n01234",
+        ]);
+
+        assert!(body.is_err());
+        assert_eq!(requests.len(), 1);
+    }
+}
@@ -15,9 +15,48 @@ pub enum DependencySpecifiers {
     Git(crate::source::git::specifier::GitDependencySpecifier),
     /// A workspace dependency specifier
     Workspace(crate::source::workspace::specifier::WorkspaceDependencySpecifier),
+    /// A path dependency specifier
+    Path(crate::source::path::specifier::PathDependencySpecifier),
 }
 impl DependencySpecifier for DependencySpecifiers {}
 
+impl DependencySpecifiers {
+    /// Whether the dependency may be missing, in which case a stub is linked in its place
+    pub fn is_optional(&self) -> bool {
+        match self {
+            DependencySpecifiers::Pesde(specifier) => specifier.optional,
+            #[cfg(feature = "wally-compat")]
+            DependencySpecifiers::Wally(specifier) => specifier.optional,
+            DependencySpecifiers::Git(specifier) => specifier.optional,
+            DependencySpecifiers::Workspace(specifier) => specifier.optional,
+            DependencySpecifiers::Path(specifier) => specifier.optional,
+        }
+    }
+
+    /// A copy of the specifier which accepts every version, used to find what would resolve if
+    /// the version requirement wasn't there. `None` for specifiers without version requirements
+    pub fn unconstrained(&self) -> Option<Self> {
+        match self {
+            DependencySpecifiers::Pesde(specifier) => Some(DependencySpecifiers::Pesde(
+                pesde::specifier::PesdeDependencySpecifier {
+                    version: semver::VersionReq::STAR,
+                    ..specifier.clone()
+                },
+            )),
+            #[cfg(feature = "wally-compat")]
+            DependencySpecifiers::Wally(specifier) => Some(DependencySpecifiers::Wally(
+                crate::source::wally::specifier::WallyDependencySpecifier {
+                    version: semver::VersionReq::STAR,
+                    ..specifier.clone()
+                },
+            )),
+            DependencySpecifiers::Git(_)
+            | DependencySpecifiers::Workspace(_)
+            | DependencySpecifiers::Path(_) => None,
+        }
+    }
+}
+
 impl Display for DependencySpecifiers {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -26,6 +65,7 @@ impl Display for DependencySpecifiers {
             DependencySpecifiers::Wally(specifier) => write!(f, "{specifier}"),
             DependencySpecifiers::Git(specifier) => write!(f, "{specifier}"),
             DependencySpecifiers::Workspace(specifier) => write!(f, "{specifier}"),
+            DependencySpecifiers::Path(specifier) => write!(f, "{specifier}"),
         }
     }
 }
@@ -2,12 +2,13 @@ use std::path::Path;
 
 use relative_path::RelativePathBuf;
 use serde::Deserialize;
-use tempfile::TempDir;
 
 use crate::{
-    manifest::target::Target,
+    linking::sourcemap::project_lib_path,
+    manifest::target::{Target, TargetKind},
     scripts::{execute_script, ScriptName},
-    source::wally::manifest::{Realm, WallyManifest},
+    source::wally::manifest::WallyManifest,
+    util::tempdirs::TempDir,
     Project, LINK_LIB_NO_FILE_FOUND,
 };
 
@@ -20,6 +21,7 @@ struct SourcemapNode {
 
 pub(crate) fn find_lib_path(
     project: &Project,
+    package: &str,
     package_dir: &Path,
 ) -> Result<Option<RelativePathBuf>, errors::FindLibPathError> {
     let manifest = project.deser_manifest()?;
@@ -28,13 +30,16 @@ pub(crate) fn find_lib_path(
         .scripts
         .get(&ScriptName::SourcemapGenerator.to_string())
     else {
-        log::warn!("no sourcemap generator script found in manifest");
-        return Ok(None);
+        log::debug!(
+            "no sourcemap generator script found in manifest, using the package's project file"
+        );
+        return Ok(project_lib_path(package_dir)?);
     };
 
     let result = execute_script(
         ScriptName::SourcemapGenerator,
         &script_path.to_path(&project.package_dir),
+        package,
         [package_dir],
         project,
         true,
@@ -55,9 +60,10 @@ pub(crate) const WALLY_MANIFEST_FILE_NAME: &str = "wally.toml";
 
 pub(crate) fn get_target(
     project: &Project,
+    package: &str,
     tempdir: &TempDir,
 ) -> Result<Target, errors::FindLibPathError> {
-    let lib = find_lib_path(project, tempdir.path())?
+    let lib = find_lib_path(project, package, tempdir.path())?
         .or_else(|| Some(RelativePathBuf::from(LINK_LIB_NO_FILE_FOUND)));
     let build_files = Default::default();
 
@@ -65,10 +71,9 @@ pub(crate) fn get_target(
     let manifest = std::fs::read_to_string(&manifest)?;
     let manifest: WallyManifest = toml::from_str(&manifest)?;
 
-    Ok(if matches!(manifest.package.realm, Realm::Shared) {
-        Target::Roblox { lib, build_files }
-    } else {
-        Target::RobloxServer { lib, build_files }
+    Ok(match manifest.package.realm.target_kind() {
+        TargetKind::RobloxServer => Target::RobloxServer { lib, build_files },
+        _ => Target::Roblox { lib, build_files },
     })
 }
 
@@ -91,6 +96,10 @@ pub mod errors {
         #[error("error deserializing sourcemap result")]
         Serde(#[from] serde_json::Error),
 
+        /// An error occurred while building the sourcemap of the package's project file
+        #[error("error building sourcemap of project file")]
+        Sourcemap(#[from] crate::linking::sourcemap::errors::SourcemapError),
+
         /// An error occurred while deserializing the wally manifest
         #[error("error deserializing wally manifest")]
         WallyManifest(#[from] toml::de::Error),
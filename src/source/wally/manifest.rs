@@ -6,17 +6,12 @@ use serde::{Deserialize, Deserializer};
 use crate::{
     manifest::{errors, DependencyType},
     names::wally::WallyPackageName,
-    source::{specifiers::DependencySpecifiers, wally::specifier::WallyDependencySpecifier},
+    source::{
+        specifiers::DependencySpecifiers,
+        wally::{pkg_ref::Realm, specifier::WallyDependencySpecifier},
+    },
 };
 
-#[derive(Deserialize, Clone, Debug)]
-#[serde(rename_all = "lowercase")]
-pub enum Realm {
-    #[serde(alias = "dev")]
-    Shared,
-    Server,
-}
-
 #[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub struct WallyPackage {
@@ -43,6 +38,7 @@ pub fn deserialize_specifiers<'de, D: Deserializer<'de>>(
                     name: name.parse().map_err(serde::de::Error::custom)?,
                     version: VersionReq::parse(version).map_err(serde::de::Error::custom)?,
                     index: None,
+                    optional: false,
                 },
             ))
         })
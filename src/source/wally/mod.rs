@@ -1,5 +1,6 @@
 use std::{
     collections::{BTreeMap, VecDeque},
+    io::{Seek, SeekFrom},
     path::PathBuf,
 };
 
@@ -7,20 +8,22 @@ use gix::Url;
 use relative_path::RelativePathBuf;
 use reqwest::header::AUTHORIZATION;
 use serde::Deserialize;
-use tempfile::tempdir;
 
 use crate::{
+    events::Event,
     manifest::target::{Target, TargetKind},
     names::PackageNames,
     source::{
         fs::{store_reader_in_cas, FSEntry, PackageFS},
         git_index::GitBasedSource,
+        progress::DownloadReader,
+        retry::ResumingReader,
         traits::PackageSource,
         version_id::VersionId,
         wally::{compat_util::get_target, manifest::WallyManifest, pkg_ref::WallyPackageRef},
         IGNORED_DIRS, IGNORED_FILES,
     },
-    util::hash,
+    util::{hash, tempdirs::tempdir},
     Project,
 };
 
@@ -60,6 +63,19 @@ impl WallyPackageSource {
         self.repo_url.to_bstring().to_vec()
     }
 
+    /// The path to the file in which the file system of the given package is cached
+    pub(crate) fn cached_index_file(
+        &self,
+        pkg_ref: &WallyPackageRef,
+        project: &Project,
+    ) -> PathBuf {
+        project
+            .cas_dir
+            .join("wally_index")
+            .join(pkg_ref.name.escaped())
+            .join(pkg_ref.version.to_string())
+    }
+
     /// Reads the config file
     pub fn config(&self, project: &Project) -> Result<WallyIndexConfig, errors::ConfigError> {
         let file = self
@@ -123,10 +139,14 @@ impl PackageSource for WallyPackageSource {
                 .filter(|manifest| specifier.version.matches(&manifest.package.version))
                 .map(|manifest| {
                     Ok((
-                        VersionId(manifest.package.version.clone(), TargetKind::Roblox),
+                        VersionId(
+                            manifest.package.version.clone(),
+                            manifest.package.realm.target_kind(),
+                        ),
                         WallyPackageRef {
                             name: specifier.name.clone(),
                             index_url: self.repo_url.clone(),
+                            realm: manifest.package.realm,
                             dependencies: manifest.all_dependencies().map_err(|e| {
                                 Self::ResolveError::AllDependencies(specifier.to_string(), e)
                             })?,
@@ -145,11 +165,7 @@ impl PackageSource for WallyPackageSource {
         reqwest: &reqwest::blocking::Client,
     ) -> Result<(PackageFS, Target), Self::DownloadError> {
         let config = self.config(project).map_err(Box::new)?;
-        let index_file = project
-            .cas_dir
-            .join("wally_index")
-            .join(pkg_ref.name.escaped())
-            .join(pkg_ref.version.to_string());
+        let index_file = self.cached_index_file(pkg_ref, project);
 
         let tempdir = match std::fs::read_to_string(&index_file) {
             Ok(s) => {
@@ -164,7 +180,10 @@ impl PackageSource for WallyPackageSource {
 
                 fs.write_to(&tempdir, project.cas_dir(), false)?;
 
-                return Ok((fs, get_target(project, &tempdir)?));
+                return Ok((
+                    fs,
+                    get_target(project, &pkg_ref.name.to_string(), &tempdir)?,
+                ));
             }
             Err(e) if e.kind() == std::io::ErrorKind::NotFound => tempdir()?,
             Err(e) => return Err(errors::DownloadError::ReadIndex(e)),
@@ -178,25 +197,65 @@ impl PackageSource for WallyPackageSource {
             pkg_ref.version
         );
 
-        let mut request = reqwest.get(&url).header(
-            "Wally-Version",
-            std::env::var("PESDE_WALLY_VERSION")
-                .as_deref()
-                .unwrap_or("0.3.2"),
-        );
+        if project.offline() {
+            return Err(errors::DownloadError::Offline(format!(
+                "{}@{}",
+                pkg_ref.name, pkg_ref.version
+            )));
+        }
+
+        let reqwest = project
+            .auth_config
+            .index_client(&self.repo_url)
+            .unwrap_or(reqwest);
+
+        let token = project.auth_config.tokens().get(&self.repo_url);
 
-        if let Some(token) = project.auth_config.tokens().get(&self.repo_url) {
+        if token.is_some() {
             log::debug!("using token for {}", self.repo_url);
-            request = request.header(AUTHORIZATION, token);
         }
 
-        let response = request.send()?.error_for_status()?;
-        let bytes = response.bytes()?;
+        let request = || {
+            let request = reqwest.get(&url).header(
+                "Wally-Version",
+                std::env::var("PESDE_WALLY_VERSION")
+                    .as_deref()
+                    .unwrap_or("0.3.2"),
+            );
+
+            match token {
+                Some(token) => request.header(AUTHORIZATION, token),
+                None => request,
+            }
+        };
 
-        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+        let _permit = project.network_limiter().connect_url(&url);
+        let response = project.retry_policy().send(request)?;
+        let total = response.content_length();
+
+        // zip archives can't be read as a stream, so the archive is spooled to disk instead of memory
+        let mut reader = DownloadReader::new(
+            ResumingReader::new(response, request, project.retry_policy()),
+            format!("{}@{}", pkg_ref.name, pkg_ref.version),
+            None,
+            total,
+            project.download_progress().cloned(),
+            project.network_limiter().clone(),
+        );
+        let mut archive_file = tempfile::tempfile()?;
+        std::io::copy(&mut reader, &mut archive_file)?;
+        archive_file.seek(SeekFrom::Start(0))?;
+
+        let mut archive = zip::ZipArchive::new(archive_file)?;
         archive.extract(tempdir.path())?;
 
         let mut entries = BTreeMap::new();
+        let skipped = |path| {
+            project.emit(|| Event::FileSkipped {
+                package: format!("{}@{}", pkg_ref.name, pkg_ref.version),
+                path,
+            })
+        };
 
         let mut dir_entries = std::fs::read_dir(tempdir.path())?.collect::<VecDeque<_>>();
         while let Some(entry) = dir_entries.pop_front() {
@@ -206,6 +265,7 @@ impl PackageSource for WallyPackageSource {
 
             if entry.file_type()?.is_dir() {
                 if IGNORED_DIRS.contains(&path.as_str()) {
+                    skipped(path);
                     continue;
                 }
 
@@ -216,6 +276,7 @@ impl PackageSource for WallyPackageSource {
             }
 
             if IGNORED_FILES.contains(&path.as_str()) {
+                skipped(path);
                 continue;
             }
 
@@ -233,7 +294,10 @@ impl PackageSource for WallyPackageSource {
         std::fs::write(&index_file, toml::to_string(&fs)?)
             .map_err(errors::DownloadError::WriteIndex)?;
 
-        Ok((fs, get_target(project, &tempdir)?))
+        Ok((
+            fs,
+            get_target(project, &pkg_ref.name.to_string(), &tempdir)?,
+        ))
     }
 }
 
@@ -341,5 +405,9 @@ pub mod errors {
         /// Error writing index file
         #[error("error writing index file")]
         WriteIndex(#[source] std::io::Error),
+
+        /// The package is not in the CAS, and the project is offline
+        #[error("package {0} is not cached, and offline mode is enabled")]
+        Offline(String),
     }
 }
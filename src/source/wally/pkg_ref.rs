@@ -9,6 +9,33 @@ use crate::{
     source::{wally::WallyPackageSource, DependencySpecifiers, PackageRef, PackageSources},
 };
 
+/// The realm of a Wally package, deciding where it is placed in Roblox
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Realm {
+    /// The package is used by both the client and the server
+    #[default]
+    Shared,
+    /// The package is only used by the server
+    Server,
+    /// The package is only used during development, and is placed like shared packages
+    Dev,
+}
+
+impl Realm {
+    /// The kind of target packages in this realm are installed as
+    pub fn target_kind(&self) -> TargetKind {
+        match self {
+            Realm::Server => TargetKind::RobloxServer,
+            Realm::Shared | Realm::Dev => TargetKind::Roblox,
+        }
+    }
+
+    fn is_shared(&self) -> bool {
+        *self == Realm::Shared
+    }
+}
+
 /// A Wally package reference
 #[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
 pub struct WallyPackageRef {
@@ -23,6 +50,9 @@ pub struct WallyPackageRef {
         deserialize_with = "crate::util::deserialize_gix_url"
     )]
     pub index_url: gix::Url,
+    /// The realm of the package
+    #[serde(default, skip_serializing_if = "Realm::is_shared")]
+    pub realm: Realm,
     /// The dependencies of the package
     #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
     pub dependencies: BTreeMap<String, (DependencySpecifiers, DependencyType)>,
@@ -37,7 +67,7 @@ impl PackageRef for WallyPackageRef {
     }
 
     fn target_kind(&self) -> TargetKind {
-        TargetKind::Roblox
+        self.realm.target_kind()
     }
 
     fn source(&self) -> PackageSources {
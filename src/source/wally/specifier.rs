@@ -16,6 +16,9 @@ pub struct WallyDependencySpecifier {
     /// The index to use for the package
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub index: Option<String>,
+    /// Whether the dependency may be missing, in which case a stub is linked in its place
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
 }
 impl DependencySpecifier for WallyDependencySpecifier {}
 
@@ -14,6 +14,9 @@ pub struct WorkspaceDependencySpecifier {
     pub version: VersionTypeOrReq,
     /// The target of the workspace package
     pub target: Option<TargetKind>,
+    /// Whether the dependency may be missing, in which case a stub is linked in its place
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
 }
 impl DependencySpecifier for WorkspaceDependencySpecifier {}
 
@@ -4,6 +4,9 @@ use serde::{ser::SerializeMap, Deserialize, Deserializer, Serializer};
 use sha2::{Digest, Sha256};
 use std::collections::BTreeMap;
 
+/// Temporary directories which are cleaned up when interrupted
+pub mod tempdirs;
+
 pub fn authenticate_conn(
     conn: &mut gix::remote::Connection<
         '_,
@@ -13,6 +16,8 @@ pub fn authenticate_conn(
     auth_config: &AuthConfig,
 ) {
     if let Some(iden) = auth_config.git_credentials().cloned() {
+        // the error type of the credentials helper is dictated by gix
+        #[allow(clippy::result_large_err)]
         conn.set_credentials(move |action| match action {
             gix::credentials::helper::Action::Get(ctx) => {
                 Ok(Some(gix::credentials::protocol::Outcome {
@@ -37,6 +42,14 @@ pub fn deserialize_gix_url<'de, D: Deserializer<'de>>(
     gix::Url::from_bytes(BStr::new(&s)).map_err(serde::de::Error::custom)
 }
 
+pub fn deserialize_gix_url_opt<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<gix::Url>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| gix::Url::from_bytes(BStr::new(&s)).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
 pub fn serialize_gix_url_map<S: Serializer>(
     url: &BTreeMap<String, gix::Url>,
     serializer: S,
@@ -0,0 +1,128 @@
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime},
+};
+
+/// The prefix of the temporary directories created by pesde
+const PREFIX: &str = ".pesde-";
+
+/// The directories which currently exist, removed by [`cleanup`]
+static LIVE: Mutex<BTreeSet<PathBuf>> = Mutex::new(BTreeSet::new());
+/// The directory temporary directories are created in, if not the system's temporary directory
+static ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// A temporary directory which is removed when dropped, or by [`cleanup`] if the process is
+/// interrupted before then
+#[derive(Debug)]
+pub struct TempDir(tempfile::TempDir);
+
+impl TempDir {
+    fn register(dir: tempfile::TempDir) -> Self {
+        LIVE.lock().unwrap().insert(dir.path().to_path_buf());
+        TempDir(dir)
+    }
+
+    /// The path of the directory
+    pub fn path(&self) -> &Path {
+        self.0.path()
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        self.path()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        LIVE.lock().unwrap().remove(self.0.path());
+    }
+}
+
+/// Sets the directory temporary directories are created in, creating it. Without one, they are
+/// created in the system's temporary directory
+pub fn set_root<P: AsRef<Path>>(root: P) -> std::io::Result<()> {
+    std::fs::create_dir_all(root.as_ref())?;
+    *ROOT.lock().unwrap() = Some(root.as_ref().to_path_buf());
+
+    Ok(())
+}
+
+/// Creates a temporary directory in the root set by [`set_root`]
+pub fn tempdir() -> std::io::Result<TempDir> {
+    match ROOT.lock().unwrap().as_deref() {
+        Some(root) => tempdir_in(root),
+        None => tempdir_in(std::env::temp_dir()),
+    }
+}
+
+/// Creates a temporary directory in the directory
+pub fn tempdir_in<P: AsRef<Path>>(dir: P) -> std::io::Result<TempDir> {
+    tempfile::Builder::new()
+        .prefix(PREFIX)
+        .tempdir_in(dir)
+        .map(TempDir::register)
+}
+
+/// Removes every temporary directory which still exists. Used before exiting when interrupted,
+/// as destructors don't run then
+pub fn cleanup() {
+    let dirs = std::mem::take(&mut *LIVE.lock().unwrap());
+
+    for dir in dirs {
+        if let Err(e) = std::fs::remove_dir_all(&dir) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!(
+                    "failed to remove temporary directory {}: {e}",
+                    dir.display()
+                );
+            }
+        }
+    }
+}
+
+/// Removes the entries of the directory which weren't modified within `max_age`, left behind by
+/// processes which were killed. Returns how many were removed
+pub fn sweep<P: AsRef<Path>>(dir: P, max_age: Duration) -> std::io::Result<usize> {
+    let entries = match std::fs::read_dir(dir.as_ref()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+
+        let stale = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+        if !stale {
+            continue;
+        }
+
+        let result = if metadata.is_dir() {
+            std::fs::remove_dir_all(entry.path())
+        } else {
+            std::fs::remove_file(entry.path())
+        };
+
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!(
+                "failed to remove stale temporary entry {}: {e}",
+                entry.path().display()
+            ),
+        }
+    }
+
+    Ok(removed)
+}
@@ -0,0 +1,262 @@
+use crate::{
+    manifest::Manifest,
+    source::{
+        specifiers::DependencySpecifiers, workspace::specifier::WorkspaceDependencySpecifier,
+    },
+    Project,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    path::{Component, Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// A cache of the members of workspaces, shared between clones of a project. Entries are
+/// invalidated when the manifest of the workspace or of a member changes, or when a directory
+/// members are searched in does
+#[derive(Debug, Default, Clone)]
+pub struct WorkspaceMembersCache(Arc<Mutex<HashMap<PathBuf, CachedMembers>>>);
+
+#[derive(Debug)]
+struct CachedMembers {
+    /// The modification times of the files and directories the members were found from
+    mtimes: Vec<(PathBuf, Option<SystemTime>)>,
+    members: HashMap<PathBuf, Manifest>,
+}
+
+pub(crate) fn mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// The deepest directory of a glob pattern which doesn't contain any glob syntax
+pub(crate) fn glob_base(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|component| match component {
+            Component::Normal(name) => !name.to_string_lossy().contains(['*', '?', '[']),
+            _ => true,
+        })
+        .collect()
+}
+
+impl WorkspaceMembersCache {
+    /// Removes all cached members, forcing them to be found again
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+
+    pub(crate) fn get(&self, dir: &Path) -> Option<HashMap<PathBuf, Manifest>> {
+        let mut cache = self.0.lock().unwrap();
+
+        match cache.get(dir) {
+            Some(cached)
+                if cached
+                    .mtimes
+                    .iter()
+                    .all(|(path, modified)| mtime(path) == *modified) =>
+            {
+                Some(cached.members.clone())
+            }
+            Some(_) => {
+                log::debug!("workspace members of {} changed", dir.display());
+                cache.remove(dir);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub(crate) fn insert(
+        &self,
+        dir: PathBuf,
+        mtimes: Vec<(PathBuf, Option<SystemTime>)>,
+        members: HashMap<PathBuf, Manifest>,
+    ) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(dir, CachedMembers { mtimes, members });
+    }
+}
+
+/// The members of a workspace and the dependencies between them
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceGraph {
+    /// The root directory of the workspace
+    pub root: PathBuf,
+    /// The members of the workspace, keyed by their directory
+    pub members: BTreeMap<PathBuf, Manifest>,
+    /// The members each member depends on through `workspace` specifiers
+    pub dependencies: BTreeMap<PathBuf, BTreeSet<PathBuf>>,
+}
+
+impl WorkspaceGraph {
+    /// The members which directly depend on the given member
+    pub fn dependents(&self, member: &Path) -> BTreeSet<&Path> {
+        self.dependencies
+            .iter()
+            .filter(|(_, dependencies)| dependencies.contains(member))
+            .map(|(path, _)| path.as_path())
+            .collect()
+    }
+
+    /// The members ordered so that every member comes after the members it depends on
+    pub fn sorted(&self) -> Vec<&Path> {
+        fn visit<'a>(
+            graph: &'a WorkspaceGraph,
+            member: &'a Path,
+            visited: &mut BTreeSet<&'a Path>,
+            sorted: &mut Vec<&'a Path>,
+        ) {
+            // also guards against cycles
+            if !visited.insert(member) {
+                return;
+            }
+
+            for dependency in graph.dependencies.get(member).into_iter().flatten() {
+                visit(graph, dependency, visited, sorted);
+            }
+
+            sorted.push(member);
+        }
+
+        let mut visited = BTreeSet::new();
+        let mut sorted = vec![];
+
+        for member in self.members.keys() {
+            visit(self, member, &mut visited, &mut sorted);
+        }
+
+        sorted
+    }
+
+    /// The member the given path belongs to, if any. Relative paths are resolved against the workspace root
+    pub fn member_of(&self, path: &Path) -> Option<&Path> {
+        let path = self.root.join(path);
+
+        self.members
+            .keys()
+            .filter(|member| path.starts_with(member))
+            // members may be nested, the deepest one owns the path
+            .max_by_key(|member| member.components().count())
+            .map(PathBuf::as_path)
+    }
+
+    /// The members affected by changes to the given paths, that is the members containing
+    /// the paths and every member which (transitively) depends on them
+    pub fn affected_by<I: IntoIterator<Item = P>, P: AsRef<Path>>(
+        &self,
+        changed_paths: I,
+    ) -> BTreeSet<PathBuf> {
+        let mut affected = BTreeSet::new();
+        let mut queue = changed_paths
+            .into_iter()
+            .filter_map(|path| self.member_of(path.as_ref()))
+            .map(Path::to_path_buf)
+            .collect::<VecDeque<_>>();
+
+        while let Some(member) = queue.pop_front() {
+            if !affected.insert(member.clone()) {
+                continue;
+            }
+
+            queue.extend(
+                self.dependents(&member)
+                    .into_iter()
+                    .map(Path::to_path_buf)
+                    .filter(|dependent| !affected.contains(dependent)),
+            );
+        }
+
+        affected
+    }
+}
+
+fn find_member<'a>(
+    members: &'a BTreeMap<PathBuf, Manifest>,
+    specifier: &WorkspaceDependencySpecifier,
+    dependant: &Manifest,
+) -> Option<&'a Path> {
+    let target = specifier.target.unwrap_or(dependant.target.kind());
+
+    members
+        .iter()
+        .find(|(_, manifest)| manifest.name == specifier.name && manifest.target.kind() == target)
+        .map(|(path, _)| path.as_path())
+}
+
+impl Project {
+    /// Get the graph of the workspace this project belongs to
+    pub fn workspace_graph(&self) -> Result<WorkspaceGraph, errors::WorkspaceGraphError> {
+        let root = self
+            .workspace_dir()
+            .unwrap_or(self.package_dir())
+            .to_path_buf();
+        let members = self
+            .workspace_members(&root)?
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+
+        let dependencies = members
+            .iter()
+            .map(|(path, manifest)| {
+                let dependencies = manifest
+                    .all_dependencies()
+                    .map_err(|e| errors::WorkspaceGraphError::AllDependencies(path.clone(), e))?
+                    .into_values()
+                    .filter_map(|(spec, _)| match spec {
+                        DependencySpecifiers::Workspace(spec) => Some(spec),
+                        _ => None,
+                    })
+                    .map(|spec| {
+                        find_member(&members, &spec, manifest)
+                            .map(Path::to_path_buf)
+                            .ok_or_else(|| {
+                                errors::WorkspaceGraphError::MemberNotFound(
+                                    path.clone(),
+                                    spec.to_string(),
+                                )
+                            })
+                    })
+                    .collect::<Result<BTreeSet<_>, _>>()?;
+
+                Ok((path.clone(), dependencies))
+            })
+            .collect::<Result<_, errors::WorkspaceGraphError>>()?;
+
+        Ok(WorkspaceGraph {
+            root,
+            members,
+            dependencies,
+        })
+    }
+}
+
+/// Errors that can occur when building the workspace graph
+pub mod errors {
+    use std::path::PathBuf;
+    use thiserror::Error;
+
+    /// Errors that can occur when building the workspace graph
+    #[derive(Debug, Error)]
+    #[non_exhaustive]
+    pub enum WorkspaceGraphError {
+        /// An error occurred finding the workspace members
+        #[error("error finding workspace members")]
+        WorkspaceMembers(#[from] crate::errors::WorkspaceMembersError),
+
+        /// An error occurred getting the dependencies of a member
+        #[error("error getting dependencies of member at {0}")]
+        AllDependencies(
+            PathBuf,
+            #[source] crate::manifest::errors::AllDependenciesError,
+        ),
+
+        /// A workspace dependency of a member does not match any member
+        #[error("member at {0} depends on {1}, which is not a member of the workspace")]
+        MemberNotFound(PathBuf, String),
+    }
+}